@@ -0,0 +1,11 @@
+#![no_main]
+
+use ankistats::verse_parser::try_count_verses_in_reference;
+use libfuzzer_sys::fuzz_target;
+
+// Runs inside the `count_verses` SQLite function, where a panic aborts the whole query
+fuzz_target!(|reference: &str| {
+    if let Ok(count) = try_count_verses_in_reference(reference) {
+        assert!(count >= 1, "'{reference}' counted {count} verses");
+    }
+});
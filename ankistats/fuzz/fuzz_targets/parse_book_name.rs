@@ -0,0 +1,11 @@
+#![no_main]
+
+use ankistats::book_name_parser::try_parse_book_name;
+use libfuzzer_sys::fuzz_target;
+
+// Runs inside the `parse_book_name` SQLite function, where a panic aborts the whole query
+fuzz_target!(|reference: &str| {
+    if let Ok(book) = try_parse_book_name(reference) {
+        assert!(!book.is_empty(), "'{reference}' parsed to an empty book name");
+    }
+});
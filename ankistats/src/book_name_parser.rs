@@ -1,14 +1,7 @@
-/// Normalizes a book name to use the standard display name
-///
-/// Currently handles:
-/// - "Psalm" (from references) → "Psalms" (display name)
-fn normalize_book_name(book_name: &str) -> String {
-    if book_name.eq_ignore_ascii_case("Psalm") {
-        "Psalms".to_string()
-    } else {
-        book_name.to_string()
-    }
-}
+use crate::bible::normalize_book_name;
+use crate::models::ParseWarningKind;
+use crate::parse_warnings::record_fallback;
+use crate::reference::strip_formatting_chars;
 
 /// Parses a Bible reference and extracts the book name
 ///
@@ -20,19 +13,7 @@ fn normalize_book_name(book_name: &str) -> String {
 /// Returns an error if the reference cannot be parsed.
 pub fn try_parse_book_name(reference: &str) -> Result<String, String> {
     // Strip any Unicode formatting characters (like zero-width spaces and directional marks)
-    let reference = reference
-        .chars()
-        .filter(|c| {
-            !c.is_control()
-                && *c != '\u{200B}' // Zero Width Space
-                && *c != '\u{FEFF}' // Zero Width No-Break Space (BOM)
-                && *c != '\u{202A}' // Left-to-Right Embedding
-                && *c != '\u{202B}' // Right-to-Left Embedding
-                && *c != '\u{202C}' // Pop Directional Formatting
-                && *c != '\u{202D}' // Left-to-Right Override
-                && *c != '\u{202E}' // Right-to-Left Override
-        })
-        .collect::<String>();
+    let reference = strip_formatting_chars(reference);
 
     // Find the last space to extract the book name
     match reference.rfind(' ') {
@@ -41,7 +22,7 @@ pub fn try_parse_book_name(reference: &str) -> Result<String, String> {
             if book_name.is_empty() {
                 Err(format!("No book name found in reference '{}'", reference))
             } else {
-                Ok(normalize_book_name(book_name))
+                Ok(normalize_book_name(book_name).to_string())
             }
         }
         None => Err(format!(
@@ -58,14 +39,14 @@ pub fn try_parse_book_name(reference: &str) -> Result<String, String> {
 /// - Numbered books: "2 Timothy 3:16" → "2 Timothy"
 /// - Single-chapter books: "Jude 24" → "Jude"
 ///
-/// For unparsable references, returns None.
+/// For unparsable references, logs a warning (once per reference) and returns None.
 /// This is a wrapper around `try_parse_book_name` for use in contexts where
 /// errors should be handled gracefully (e.g., SQLite functions).
 pub fn parse_book_name(reference: &str) -> Option<String> {
     match try_parse_book_name(reference) {
         Ok(book_name) => Some(book_name),
         Err(err) => {
-            eprintln!("Warning: {}", err);
+            record_fallback(ParseWarningKind::BookName, reference, &err);
             None
         }
     }
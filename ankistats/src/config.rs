@@ -0,0 +1,97 @@
+#[cfg(feature = "sqlite")]
+use statsutils::pool::ConnectionPool;
+use std::env;
+
+/// Deck holding the Bible verse cards by default, with "::" separating the deck name parts
+pub const DEFAULT_DECK_NAME: &str = "Bible::Verses";
+
+/// Note type of the Bible verse cards by default
+pub const DEFAULT_NOTE_TYPE: &str = "Bible Verse";
+
+/// Names of the deck and note type the statistics are read from
+///
+/// Both are matched case-insensitively. Every note of the note type in the deck is expected
+/// to have the reference as its sort field and two cards (ordinals 0 and 1).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnkiStatsConfig {
+    /// Deck name with "::" separating the parts, e.g. "Bible::Verses"
+    pub deck_name: String,
+    pub note_type: String,
+}
+
+impl Default for AnkiStatsConfig {
+    fn default() -> Self {
+        Self {
+            deck_name: DEFAULT_DECK_NAME.to_string(),
+            note_type: DEFAULT_NOTE_TYPE.to_string(),
+        }
+    }
+}
+
+impl AnkiStatsConfig {
+    /// Reads the names from ANKI_DECK_NAME and ANKI_NOTE_TYPE, using the defaults for unset
+    /// variables
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            deck_name: env::var("ANKI_DECK_NAME").unwrap_or(default.deck_name),
+            note_type: env::var("ANKI_NOTE_TYPE").unwrap_or(default.note_type),
+        }
+    }
+}
+
+/// An Anki database (or .colpkg/.apkg export) and the deck and note type to read from it
+///
+/// Every statistics function takes one, so that each caller (e.g. each user of the backend)
+/// can read its own deck. A plain path reads the default deck and note type with a
+/// connection opened for the query.
+#[derive(Debug, Clone, Copy)]
+pub struct AnkiCollection<'a> {
+    pub path: &'a str,
+    /// Deck and note type names, the defaults if `None`
+    pub config: Option<&'a AnkiStatsConfig>,
+    /// Connections to the database kept between queries, if any; not used for exports
+    #[cfg(feature = "sqlite")]
+    pub pool: Option<&'a ConnectionPool>,
+}
+
+impl<'a> AnkiCollection<'a> {
+    pub fn new(path: &'a str) -> Self {
+        Self {
+            path,
+            config: None,
+            #[cfg(feature = "sqlite")]
+            pool: None,
+        }
+    }
+
+    /// Reads the database through the pool's connections, which must be to the same path
+    #[cfg(feature = "sqlite")]
+    pub fn with_pool(mut self, pool: &'a ConnectionPool) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    /// Reads the given deck and note type instead of the defaults
+    pub fn with_config(mut self, config: &'a AnkiStatsConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// The deck and note type names to read
+    pub fn config(&self) -> AnkiStatsConfig {
+        self.config.cloned().unwrap_or_default()
+    }
+}
+
+impl<'a> From<&'a str> for AnkiCollection<'a> {
+    fn from(path: &'a str) -> Self {
+        Self::new(path)
+    }
+}
+
+impl<'a> From<&'a String> for AnkiCollection<'a> {
+    fn from(path: &'a String) -> Self {
+        Self::new(path)
+    }
+}
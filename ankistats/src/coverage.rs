@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+
+use crate::bible::{chapter_count, chapter_verse_count};
+use crate::models::{BookCoverage, ChapterCoverage, PassageStatus, VerseStatus};
+use crate::reference::{Reference, parse_reference};
+
+/// Builds the verse-level coverage of a book from every passage's reference and status
+///
+/// Passages from other books are ignored, as are references `parse_reference` cannot handle
+/// (see the parse warnings for those).
+pub fn book_coverage(book: &'static str, passages: &[(String, PassageStatus)]) -> BookCoverage {
+    let references = parse_passages(passages).filter(|(reference, _)| reference.book == book);
+    build_coverage(book, references)
+}
+
+/// Builds the verse-level coverage of every book that has at least one parsable passage
+pub fn books_coverage(passages: &[(String, PassageStatus)]) -> HashMap<&'static str, BookCoverage> {
+    let mut by_book: HashMap<&'static str, Vec<(Reference, PassageStatus)>> = HashMap::new();
+    for (reference, status) in parse_passages(passages) {
+        by_book
+            .entry(reference.book)
+            .or_default()
+            .push((reference, status));
+    }
+
+    by_book
+        .into_iter()
+        .map(|(book, references)| (book, build_coverage(book, references)))
+        .collect()
+}
+
+/// Parses each passage's reference, skipping the ones that cannot be parsed
+fn parse_passages(
+    passages: &[(String, PassageStatus)],
+) -> impl Iterator<Item = (Reference, PassageStatus)> + '_ {
+    passages.iter().filter_map(|(reference, status)| {
+        parse_reference(reference)
+            .ok()
+            .map(|reference| (reference, *status))
+    })
+}
+
+/// Marks every verse of the given references with the most learned status covering it
+///
+/// Verses past the end of their chapter, or in a chapter past the book's last, are left out
+/// (the parse warnings list references to chapters the book does not have).
+fn build_coverage(
+    book: &'static str,
+    references: impl IntoIterator<Item = (Reference, PassageStatus)>,
+) -> BookCoverage {
+    let mut chapters: Vec<Vec<VerseStatus>> =
+        vec![Vec::new(); chapter_count(book).unwrap_or(0) as usize];
+
+    for (reference, status) in references {
+        for (chapter, verse) in reference.verses() {
+            if chapter_verse_count(book, chapter).is_none_or(|total| verse > total) {
+                continue;
+            }
+            let (chapter, verse) = (chapter as usize - 1, verse as usize - 1);
+            let verses = &mut chapters[chapter];
+            if verses.len() <= verse {
+                verses.resize(verse + 1, VerseStatus::Uncovered);
+            }
+            verses[verse] = verses[verse].max(VerseStatus::from(status));
+        }
+    }
+
+    BookCoverage {
+        book: book.to_string(),
+        chapters: chapters
+            .into_iter()
+            .enumerate()
+            .map(|(index, verses)| {
+                let chapter = index as i64 + 1;
+                ChapterCoverage {
+                    chapter,
+                    total_verses: chapter_verse_count(book, chapter).unwrap_or(0),
+                    verses,
+                }
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_book_coverage() {
+        let passages = vec![
+            ("Jude 24-25".to_string(), PassageStatus::Young),
+            ("Jude 1:25".to_string(), PassageStatus::Mature),
+            ("Jude 20".to_string(), PassageStatus::Suspended),
+            ("John 3:16".to_string(), PassageStatus::Mature),
+            ("not a reference".to_string(), PassageStatus::Mature),
+        ];
+
+        let coverage = book_coverage("Jude", &passages);
+
+        assert_eq!(coverage.chapters.len(), 1);
+        let verses = &coverage.chapters[0].verses;
+        assert_eq!(verses.len(), 25);
+        assert_eq!(verses[0], VerseStatus::Uncovered);
+        assert_eq!(verses[19], VerseStatus::Suspended);
+        assert_eq!(verses[23], VerseStatus::Young);
+        assert_eq!(verses[24], VerseStatus::Mature);
+    }
+
+    #[test]
+    fn test_book_coverage_lists_every_chapter() {
+        let passages = vec![("Romans 8:1".to_string(), PassageStatus::Unseen)];
+
+        let coverage = book_coverage("Romans", &passages);
+
+        assert_eq!(coverage.chapters.len(), 16);
+        assert!(coverage.chapters[0].verses.is_empty());
+        assert_eq!(coverage.chapters[7].verses, vec![VerseStatus::Unseen]);
+        assert_eq!(coverage.chapters[7].total_verses, 39);
+    }
+
+    #[test]
+    fn test_book_coverage_leaves_out_verses_past_the_end() {
+        let passages = vec![
+            ("Romans 17:1".to_string(), PassageStatus::Mature),
+            ("Romans 16:26-30".to_string(), PassageStatus::Young),
+        ];
+
+        let coverage = book_coverage("Romans", &passages);
+
+        assert_eq!(coverage.chapters.len(), 16);
+        assert_eq!(coverage.chapters[15].verses.len(), 27);
+        assert_eq!(coverage.verse_count(VerseStatus::Young), 2);
+        assert_eq!(coverage.verse_count(VerseStatus::Mature), 0);
+    }
+
+    #[test]
+    fn test_books_coverage_counts_overlapping_verses_once() {
+        let passages = vec![
+            ("Romans 8:28-30".to_string(), PassageStatus::Young),
+            ("Romans 8:28".to_string(), PassageStatus::Mature),
+            ("Romans 8:29-31".to_string(), PassageStatus::Unseen),
+            ("Jude 24-25".to_string(), PassageStatus::Suspended),
+        ];
+
+        let coverage = books_coverage(&passages);
+
+        assert_eq!(coverage.len(), 2);
+        let romans = &coverage["Romans"];
+        assert_eq!(romans.verse_count(VerseStatus::Mature), 1);
+        assert_eq!(romans.verse_count(VerseStatus::Young), 2);
+        assert_eq!(romans.verse_count(VerseStatus::Unseen), 1);
+        assert_eq!(coverage["Jude"].verse_count(VerseStatus::Suspended), 2);
+    }
+}
@@ -1,10 +1,25 @@
 use anyhow::{Context, Result};
-use rusqlite::{Connection, OpenFlags};
-use statsutils::{DatePeriod, get_today_start_ms, register_date_functions};
+use rusqlite::{CachedStatement, Connection, OpenFlags, OptionalExtension};
+use statsutils::pool::PooledConnection;
+use statsutils::{
+    DatePeriod, check_schema, get_today_start_ms, register_date_functions, timestamp_ms_to_date,
+};
+use std::cell::Cell;
 use std::collections::HashMap;
 
 use crate::book_name_parser;
-use crate::models::{BookStats, DayStats, WeekStats};
+use crate::config::{AnkiCollection, AnkiStatsConfig};
+use crate::models::{
+    BookStats, ChapterStats, DayStats, DeckSettings, DueDay, PassageDetails, PassageStatus,
+    Retention, WeekStats,
+};
+use crate::package::{self, ExtractedCollection};
+use crate::parse_warnings;
+use crate::reference::parse_reference;
+use crate::relapse::ProgressEvent;
+use crate::retention::PassageAnswers;
+use crate::sessions::Review;
+use crate::settings;
 use crate::verse_parser;
 
 // Anki queue type constants
@@ -15,18 +30,53 @@ const QUEUE_TYPE_MANUALLY_BURIED: i64 = -3;
 const QUEUE_TYPE_SIBLING_BURIED: i64 = -2;
 const QUEUE_TYPE_SUSPENDED: i64 = -1;
 const QUEUE_TYPE_NEW: i64 = 0;
-#[allow(dead_code)]
 const QUEUE_TYPE_LRN: i64 = 1;
-#[allow(dead_code)]
 const QUEUE_TYPE_REV: i64 = 2;
-#[allow(dead_code)]
 const QUEUE_TYPE_DAY_LEARN_RELEARN: i64 = 3;
 #[allow(dead_code)]
 const QUEUE_TYPE_PREVIEW: i64 = 4;
 
+// Anki review log constants
+// See https://github.com/ankitects/anki/blob/76d3237139b3e73b98f5a5b4dfeeeea2f0554644/pylib/anki/consts.py
+const REVLOG_TYPE_REVIEW: i64 = 1;
+const EASE_AGAIN: i64 = 1;
+
+/// Maximum number of matured or lost references listed per day or week
+pub const MAX_PROGRESS_REFERENCES: usize = 10;
+
+/// Matured and lost passage references of a single day or week
+type ProgressReferences = (Vec<String>, Vec<String>);
+
+/// Tables and columns the queries rely on, checked when opening a database
+const EXPECTED_SCHEMA: &[(&str, &[&str])] = &[
+    ("cards", &["id", "nid", "did", "ord", "queue", "ivl", "due"]),
+    ("notes", &["id", "mid", "sfld"]),
+    (
+        "revlog",
+        &["id", "cid", "ease", "time", "type", "ivl", "lastIvl"],
+    ),
+    ("col", &["crt"]),
+];
+
 /// Unicode unit separator character (used in Anki deck names)
 const UNIT_SEPARATOR: char = '\x1F';
 
+/// SQL expression classifying a passage from its two cards (`c0` and `c1`) as
+/// 'suspended', 'unseen', 'mature' or 'young'
+fn passage_status_sql() -> String {
+    format!(
+        r#"CASE
+                    WHEN c0.queue={QUEUE_TYPE_SUSPENDED} OR c1.queue={QUEUE_TYPE_SUSPENDED}
+                        THEN 'suspended'
+                    WHEN c0.queue={QUEUE_TYPE_NEW} AND c1.queue={QUEUE_TYPE_NEW}
+                        THEN 'unseen'
+                    WHEN c0.ivl >= 21 AND c1.ivl >= 21
+                        THEN 'mature'
+                    ELSE 'young'
+                    END"#
+    )
+}
+
 /// Opens a connection to an Anki database in read-only mode
 pub fn open_database(path: &str) -> Result<Connection> {
     let conn = Connection::open_with_flags(
@@ -35,8 +85,19 @@ pub fn open_database(path: &str) -> Result<Connection> {
     )
     .context("Failed to open Anki database in read-only mode")?;
 
+    // Fail early with a list of what's missing rather than with an SQL error mid-request
+    check_schema(&conn, "Anki", EXPECTED_SCHEMA)?;
+
+    register_functions(&conn)?;
+
+    Ok(conn)
+}
+
+/// Registers the custom SQLite functions used by the queries: the statsutils date/week
+/// functions, `count_verses`, `parse_book_name` and `parse_chapter`
+pub fn register_functions(conn: &Connection) -> Result<()> {
     // Register date functions from statsutils
-    register_date_functions(&conn)?;
+    register_date_functions(conn)?;
 
     // Register custom SQLite function for counting verses in a reference
     conn.create_scalar_function(
@@ -46,6 +107,10 @@ pub fn open_database(path: &str) -> Result<Connection> {
             | rusqlite::functions::FunctionFlags::SQLITE_DETERMINISTIC,
         |ctx| {
             let reference = ctx.get::<String>(0)?;
+            if parse_warnings::strict_references() {
+                return verse_parser::try_count_verses_in_reference(&reference)
+                    .map_err(|e| unparsable_reference_error(&reference, e));
+            }
             Ok(verse_parser::count_verses_in_reference(&reference))
         },
     )
@@ -59,51 +124,218 @@ pub fn open_database(path: &str) -> Result<Connection> {
             | rusqlite::functions::FunctionFlags::SQLITE_DETERMINISTIC,
         |ctx| {
             let reference = ctx.get::<String>(0)?;
+            if parse_warnings::strict_references() {
+                return book_name_parser::try_parse_book_name(&reference)
+                    .map(Some)
+                    .map_err(|e| unparsable_reference_error(&reference, e));
+            }
             Ok(book_name_parser::parse_book_name(&reference))
         },
     )
     .context("Failed to register parse_book_name SQLite function")?;
 
-    Ok(conn)
+    // Register custom SQLite function for parsing chapter numbers from references. References
+    // `parse_reference` cannot handle (e.g. ranges spanning chapters) have no chapter rather
+    // than failing in strict mode, like in the verse-level coverage.
+    conn.create_scalar_function(
+        "parse_chapter",
+        1, // number of arguments
+        rusqlite::functions::FunctionFlags::SQLITE_UTF8
+            | rusqlite::functions::FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| {
+            let reference = ctx.get::<String>(0)?;
+            Ok(parse_reference(&reference)
+                .ok()
+                .map(|reference| reference.chapter))
+        },
+    )
+    .context("Failed to register parse_chapter SQLite function")?;
+
+    Ok(())
 }
 
-/// Looks up the deck ID for "Bible<unit-separator>Verses"
-pub fn get_deck_id(conn: &Connection) -> Result<i64> {
-    let deck_name = format!("Bible{}Verses", UNIT_SEPARATOR);
+/// Converts a parser error into an error that aborts the SQLite query (strict mode)
+fn unparsable_reference_error(reference: &str, error: String) -> rusqlite::Error {
+    rusqlite::Error::UserFunctionError(
+        format!("Unparsable Bible reference '{}': {}", reference, error).into(),
+    )
+}
 
-    let deck_id: i64 = conn
-        .query_row(
+/// Looks up the ID of a deck, e.g. "Bible::Verses"
+///
+/// Current collections store the deck name parts separated by the unit separator rather than
+/// "::". Collections from before Anki 2.1.28 have no `decks` table; their decks are stored as
+/// a JSON object in `col.decks`, with "::" separating the deck name parts.
+pub fn get_deck_id(conn: &Connection, deck_name: &str) -> Result<i64> {
+    let (query, stored_name) = if has_table(conn, "decks")? {
+        (
             "SELECT id FROM decks WHERE LOWER(name) = LOWER(?1)",
-            [&deck_name],
-            |row| row.get(0),
+            deck_name.replace("::", &UNIT_SEPARATOR.to_string()),
         )
+    } else {
+        (
+            r#"
+            SELECT CAST(deck.key AS INTEGER)
+            FROM col, json_each(col.decks) AS deck
+            WHERE LOWER(json_extract(deck.value, '$.name')) = LOWER(?1)
+            "#,
+            deck_name.to_string(),
+        )
+    };
+
+    let deck_id: i64 = conn
+        .query_row(query, [&stored_name], |row| row.get(0))
         .context(format!("Failed to find deck '{}'", deck_name))?;
 
     Ok(deck_id)
 }
 
-/// Looks up the model ID for the "Bible Verse" note type
-pub fn get_model_id(conn: &Connection) -> Result<i64> {
-    let model_name = "Bible Verse";
+/// Looks up the ID of a note type (model), e.g. "Bible Verse"
+///
+/// Collections from before Anki 2.1.28 have no `notetypes` table; their note types are
+/// stored as a JSON object in `col.models`.
+pub fn get_model_id(conn: &Connection, model_name: &str) -> Result<i64> {
+    let query = if has_table(conn, "notetypes")? {
+        "SELECT id FROM notetypes WHERE LOWER(name) = LOWER(?1)"
+    } else {
+        r#"
+        SELECT CAST(model.key AS INTEGER)
+        FROM col, json_each(col.models) AS model
+        WHERE LOWER(json_extract(model.value, '$.name')) = LOWER(?1)
+        "#
+    };
 
     let model_id: i64 = conn
+        .query_row(query, [model_name], |row| row.get(0))
+        .context(format!("Failed to find note type '{}'", model_name))?;
+
+    Ok(model_id)
+}
+
+/// Checks whether the database has a table with the given name
+fn has_table(conn: &Connection, table: &str) -> Result<bool> {
+    let exists = conn
         .query_row(
-            "SELECT id FROM notetypes WHERE LOWER(name) = LOWER(?1)",
-            [model_name],
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1)",
+            [table],
             |row| row.get(0),
         )
-        .context(format!("Failed to find note type '{}'", model_name))?;
+        .context("Failed to read database schema")?;
 
-    Ok(model_id)
+    Ok(exists)
+}
+
+/// An open Anki database for the duration of a single request
+///
+/// The deck and note type are the collection's (the defaults for a plain path) unless set
+/// with `with_config`.
+/// Their IDs are looked up on first use and then cached, and statements
+/// prepared through `prepare` are kept in the connection's statement cache, so the query
+/// helpers below can each ask for them without repeating the lookups. With a pool, the
+/// connection (and its statement cache) is reused by later requests.
+pub struct AnkiContext {
+    conn: PooledConnection,
+    config: AnkiStatsConfig,
+    deck_id: Cell<Option<i64>>,
+    model_id: Cell<Option<i64>>,
+    /// Temporary copy of the collection when opened from an export; declared after `conn`
+    /// so that the connection is closed before the file is deleted
+    package: Option<ExtractedCollection>,
+}
+
+impl AnkiContext {
+    /// Opens the database read-only with the custom SQLite functions registered
+    ///
+    /// The path can also be a .colpkg/.apkg export, whose collection is then extracted to a
+    /// temporary directory for as long as the context lives.
+    pub fn open<'a>(collection: impl Into<AnkiCollection<'a>>) -> Result<Self> {
+        let collection = collection.into();
+        let path = collection.path;
+        if !package::is_package(path) {
+            let conn = match collection.pool {
+                Some(pool) => pool.get(open_database)?,
+                None => PooledConnection::unpooled(open_database(path)?),
+            };
+            return Ok(Self::pooled(conn).with_config(collection.config()));
+        }
+
+        let extracted = package::extract_collection(path)?;
+        let extracted_path = extracted
+            .path()
+            .to_str()
+            .context("Invalid temporary path")?;
+        let mut ctx = Self::new(open_database(extracted_path)?).with_config(collection.config());
+        ctx.package = Some(extracted);
+        Ok(ctx)
+    }
+
+    /// Wraps an already opened connection (which must have the custom functions registered)
+    pub fn new(conn: Connection) -> Self {
+        Self::pooled(PooledConnection::unpooled(conn))
+    }
+
+    fn pooled(conn: PooledConnection) -> Self {
+        Self {
+            conn,
+            config: AnkiStatsConfig::default(),
+            deck_id: Cell::new(None),
+            model_id: Cell::new(None),
+            package: None,
+        }
+    }
+
+    /// Reads the given deck and note type instead of the defaults
+    pub fn with_config(mut self, config: AnkiStatsConfig) -> Self {
+        self.config = config;
+        self.deck_id.set(None);
+        self.model_id.set(None);
+        self
+    }
+
+    /// The underlying connection
+    pub fn conn(&self) -> &Connection {
+        &self.conn
+    }
+
+    /// ID of the configured deck ("Bible::Verses" by default)
+    pub fn deck_id(&self) -> Result<i64> {
+        cached_id(&self.deck_id, || {
+            get_deck_id(&self.conn, &self.config.deck_name)
+        })
+    }
+
+    /// ID of the configured note type ("Bible Verse" by default)
+    pub fn model_id(&self) -> Result<i64> {
+        cached_id(&self.model_id, || {
+            get_model_id(&self.conn, &self.config.note_type)
+        })
+    }
+
+    /// Prepares a statement, reusing it if the same SQL was prepared before
+    pub fn prepare(&self, sql: &str) -> Result<CachedStatement<'_>> {
+        self.conn
+            .prepare_cached(sql)
+            .context("Failed to prepare Anki query")
+    }
+}
+
+/// Returns the cached ID, or looks it up and caches it
+fn cached_id(cache: &Cell<Option<i64>>, lookup: impl FnOnce() -> Result<i64>) -> Result<i64> {
+    if let Some(id) = cache.get() {
+        return Ok(id);
+    }
+    let id = lookup()?;
+    cache.set(Some(id));
+    Ok(id)
 }
 
 /// Gets statistics for all Bible books in a single query using GROUP BY
-/// Returns a HashMap with book names as keys and BookStats as values
-pub fn get_all_books_stats(
-    conn: &Connection,
-    deck_id: i64,
-    model_id: i64,
-) -> Result<HashMap<String, BookStats>> {
+/// Returns a HashMap with book names as keys and BookStats as values, each with the chapters
+/// that have passages from `get_chapter_stats()`
+pub fn get_all_books_stats(ctx: &AnkiContext) -> Result<HashMap<String, BookStats>> {
+    let mut chapters = get_chapter_stats(ctx)?;
+
+    let (deck_id, model_id) = (ctx.deck_id()?, ctx.model_id()?);
     let query = format!(
         r#"
         SELECT 
@@ -120,15 +352,7 @@ pub fn get_all_books_stats(
             SELECT
                 parse_book_name(sfld) AS book,
                 count_verses(sfld) AS verses_count,
-                CASE
-                    WHEN c0.queue={QUEUE_TYPE_SUSPENDED} OR c1.queue={QUEUE_TYPE_SUSPENDED}
-                        THEN 'suspended'
-                    WHEN c0.queue={QUEUE_TYPE_NEW} AND c1.queue={QUEUE_TYPE_NEW}
-                        THEN 'unseen'
-                    WHEN c0.ivl >= 21 AND c1.ivl >= 21
-                        THEN 'mature'
-                    ELSE 'young'
-                    END as type
+                {status} AS type
             FROM notes
             JOIN cards AS c0 ON c0.nid = notes.id AND c0.ord = 0 AND c0.did = ?2
             JOIN cards AS c1 ON c1.nid = notes.id AND c1.ord = 1 AND c1.did = ?2
@@ -136,10 +360,11 @@ pub fn get_all_books_stats(
         )
         WHERE book IS NOT NULL
         GROUP BY book
-        "#
+        "#,
+        status = passage_status_sql()
     );
 
-    let mut stmt = conn.prepare(&query)?;
+    let mut stmt = ctx.prepare(&query)?;
 
     let books_iter = stmt.query_map(rusqlite::params![model_id, deck_id], |row| {
         let book_name: String = row.get(0)?;
@@ -155,24 +380,184 @@ pub fn get_all_books_stats(
                 young_verses: row.get(6).unwrap_or(0),
                 unseen_verses: row.get(7).unwrap_or(0),
                 suspended_verses: row.get(8).unwrap_or(0),
+                // Filled in from the verse-level coverage by get_bible_stats
+                unique_mature_verses: 0,
+                unique_young_verses: 0,
+                unique_unseen_verses: 0,
+                unique_suspended_verses: 0,
+                chapters: Vec::new(),
             },
         ))
     })?;
 
     let mut books_map = HashMap::new();
     for book_result in books_iter {
-        let (book_name, stats) = book_result?;
+        let (book_name, mut stats) = book_result?;
+        stats.chapters = chapters.remove(&book_name).unwrap_or_default();
         books_map.insert(book_name, stats);
     }
 
     Ok(books_map)
 }
 
+/// Gets statistics for every chapter with passages in a single query using GROUP BY
+/// Returns a HashMap with book names as keys and the book's chapters in order as values
+pub fn get_chapter_stats(ctx: &AnkiContext) -> Result<HashMap<String, Vec<ChapterStats>>> {
+    let (deck_id, model_id) = (ctx.deck_id()?, ctx.model_id()?);
+    let query = format!(
+        r#"
+        SELECT
+            book,
+            chapter,
+            SUM(CASE WHEN type='mature' THEN 1 ELSE 0 END) AS mature_passages,
+            SUM(CASE WHEN type='young' THEN 1 ELSE 0 END) AS young_passages,
+            SUM(CASE WHEN type='unseen' THEN 1 ELSE 0 END) AS unseen_passages,
+            SUM(CASE WHEN type='suspended' THEN 1 ELSE 0 END) AS suspended_passages,
+            SUM(CASE WHEN type='mature' THEN verses_count ELSE 0 END) AS mature_verses,
+            SUM(CASE WHEN type='young' THEN verses_count ELSE 0 END) AS young_verses,
+            SUM(CASE WHEN type='unseen' THEN verses_count ELSE 0 END) AS unseen_verses,
+            SUM(CASE WHEN type='suspended' THEN verses_count ELSE 0 END) AS suspended_verses
+        FROM (
+            SELECT
+                parse_book_name(sfld) AS book,
+                parse_chapter(sfld) AS chapter,
+                count_verses(sfld) AS verses_count,
+                {status} AS type
+            FROM notes
+            JOIN cards AS c0 ON c0.nid = notes.id AND c0.ord = 0 AND c0.did = ?2
+            JOIN cards AS c1 ON c1.nid = notes.id AND c1.ord = 1 AND c1.did = ?2
+            WHERE notes.mid = ?1
+        )
+        WHERE book IS NOT NULL AND chapter IS NOT NULL
+        GROUP BY book, chapter
+        ORDER BY book, chapter
+        "#,
+        status = passage_status_sql()
+    );
+
+    let mut stmt = ctx.prepare(&query)?;
+
+    let chapters_iter = stmt.query_map(rusqlite::params![model_id, deck_id], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            ChapterStats {
+                chapter: row.get(1)?,
+                mature_passages: row.get(2).unwrap_or(0),
+                young_passages: row.get(3).unwrap_or(0),
+                unseen_passages: row.get(4).unwrap_or(0),
+                suspended_passages: row.get(5).unwrap_or(0),
+                mature_verses: row.get(6).unwrap_or(0),
+                young_verses: row.get(7).unwrap_or(0),
+                unseen_verses: row.get(8).unwrap_or(0),
+                suspended_verses: row.get(9).unwrap_or(0),
+                // Filled in from the verse-level coverage by get_bible_stats
+                ..ChapterStats::default()
+            },
+        ))
+    })?;
+
+    let mut chapters_map: HashMap<String, Vec<ChapterStats>> = HashMap::new();
+    for chapter_result in chapters_iter {
+        let (book_name, stats) = chapter_result?;
+        chapters_map.entry(book_name).or_default().push(stats);
+    }
+
+    Ok(chapters_map)
+}
+
+/// Parses a status from `passage_status_sql()`
+fn passage_status(status: &str) -> PassageStatus {
+    match status {
+        "mature" => PassageStatus::Mature,
+        "young" => PassageStatus::Young,
+        "unseen" => PassageStatus::Unseen,
+        _ => PassageStatus::Suspended,
+    }
+}
+
+/// Gets the reference and status of every passage in the deck
+pub fn get_passage_statuses(ctx: &AnkiContext) -> Result<Vec<(String, PassageStatus)>> {
+    let (deck_id, model_id) = (ctx.deck_id()?, ctx.model_id()?);
+    let query = format!(
+        r#"
+        SELECT notes.sfld, {status}
+        FROM notes
+        JOIN cards AS c0 ON c0.nid = notes.id AND c0.ord = 0 AND c0.did = ?2
+        JOIN cards AS c1 ON c1.nid = notes.id AND c1.ord = 1 AND c1.did = ?2
+        WHERE notes.mid = ?1
+        "#,
+        status = passage_status_sql()
+    );
+
+    let mut stmt = ctx.prepare(&query)?;
+    let passages = stmt
+        .query_map(rusqlite::params![model_id, deck_id], |row| {
+            Ok((row.get(0)?, passage_status(&row.get::<_, String>(1)?)))
+        })?
+        .collect::<Result<Vec<(String, PassageStatus)>, _>>()?;
+
+    Ok(passages)
+}
+
+/// Gets every passage in the deck, in the order they were added, with the dates of its first
+/// review and first maturity from the review log
+///
+/// A passage matured on the first day both of its cards had reached 21 days, so `MAX` of the
+/// two cards' first mature reviews (which is `NULL` if either card never matured).
+pub fn get_passage_details(ctx: &AnkiContext) -> Result<Vec<PassageDetails>> {
+    let (deck_id, model_id) = (ctx.deck_id()?, ctx.model_id()?);
+    let query = format!(
+        r#"
+        SELECT
+            notes.sfld,
+            {status},
+            (SELECT MIN(r.id) FROM revlog r WHERE r.cid IN (c0.id, c1.id)),
+            MAX(
+                (SELECT MIN(r.id) FROM revlog r WHERE r.cid = c0.id AND r.ivl >= 21),
+                (SELECT MIN(r.id) FROM revlog r WHERE r.cid = c1.id AND r.ivl >= 21)
+            )
+        FROM notes
+        JOIN cards AS c0 ON c0.nid = notes.id AND c0.ord = 0 AND c0.did = ?2
+        JOIN cards AS c1 ON c1.nid = notes.id AND c1.ord = 1 AND c1.did = ?2
+        WHERE notes.mid = ?1
+        ORDER BY notes.id
+        "#,
+        status = passage_status_sql()
+    );
+
+    let mut stmt = ctx.prepare(&query)?;
+    let rows = stmt.query_map(rusqlite::params![model_id, deck_id], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, Option<i64>>(2)?,
+            row.get::<_, Option<i64>>(3)?,
+        ))
+    })?;
+
+    let mut passages = Vec::new();
+    for row in rows {
+        let (reference, status, first_review_ms, matured_ms) = row?;
+        passages.push(PassageDetails {
+            reference,
+            status: passage_status(&status),
+            first_review_date: first_review_ms
+                .map(statsutils::timestamp_ms_to_date_string)
+                .transpose()?,
+            matured_date: matured_ms
+                .map(statsutils::timestamp_ms_to_date_string)
+                .transpose()?,
+        });
+    }
+
+    Ok(passages)
+}
+
 /// Gets the total study time for today in minutes
-pub fn get_today_study_minutes(conn: &Connection) -> Result<f64> {
+pub fn get_today_study_minutes(ctx: &AnkiContext) -> Result<f64> {
     let today_start_ms = get_today_start_ms()?;
 
-    let deck_id = get_deck_id(conn)?;
+    let deck_id = ctx.deck_id()?;
 
     let query = r#"
         SELECT COALESCE(SUM(r.time), 0) as total_ms
@@ -181,35 +566,41 @@ pub fn get_today_study_minutes(conn: &Connection) -> Result<f64> {
         WHERE c.did = ?1 AND r.id >= ?2
     "#;
 
-    let total_ms: i64 = conn.query_row(query, [deck_id, today_start_ms], |row| row.get(0))?;
+    let total_ms: i64 = ctx
+        .prepare(query)?
+        .query_row([deck_id, today_start_ms], |row| row.get(0))?;
 
     // Convert milliseconds to minutes
     Ok(total_ms as f64 / 60000.0)
 }
 
 /// Gets study time and learning progress for each of the last 30 days
-pub fn get_last_30_days_stats(conn: &Connection) -> Result<Vec<DayStats>> {
-    let deck_id = get_deck_id(conn)?;
-    let model_id = get_model_id(conn)?;
+pub fn get_last_30_days_stats(ctx: &AnkiContext) -> Result<Vec<DayStats>> {
+    get_daily_stats(ctx, DatePeriod::last_30_days()?)
+}
 
-    // Get the period data for the last 30 days
-    let period = DatePeriod::last_30_days()?;
+/// Gets study time and learning progress for each day in the period
+pub fn get_daily_stats(ctx: &AnkiContext, period: DatePeriod) -> Result<Vec<DayStats>> {
+    let (deck_id, model_id) = (ctx.deck_id()?, ctx.model_id()?);
 
     // Query 1: Study time grouped by date
     let time_query = r#"
-        SELECT date_str_from_ms(r.id) as date, SUM(r.time) as total_ms
+        SELECT date_str_from_ms(r.id) as date, SUM(r.time) as total_ms, COUNT(*) as reviews
         FROM revlog r
         JOIN cards c ON c.id = r.cid
         WHERE c.did = ?1 AND r.id >= ?2 AND r.id < ?3
         GROUP BY date_str_from_ms(r.id)
     "#;
 
-    let mut time_stmt = conn.prepare(time_query)?;
+    let mut time_stmt = ctx.prepare(time_query)?;
     let time_results = time_stmt
         .query_map([deck_id, period.start_ms, period.end_ms], |row| {
-            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+            Ok((
+                row.get::<_, String>(0)?,
+                (row.get::<_, i64>(1)?, row.get::<_, i64>(2)?),
+            ))
         })?
-        .collect::<Result<HashMap<String, i64>, _>>()?;
+        .collect::<Result<HashMap<String, (i64, i64)>, _>>()?;
 
     // Query 2: Progress (maturation and loss) grouped by date
     let progress_query = format!(
@@ -228,7 +619,7 @@ pub fn get_last_30_days_stats(conn: &Connection) -> Result<Vec<DayStats>> {
         "#
     );
 
-    let mut progress_stmt = conn.prepare(&progress_query)?;
+    let mut progress_stmt = ctx.prepare(&progress_query)?;
     let progress_results = progress_stmt
         .query_map([deck_id, model_id, period.start_ms, period.end_ms], |row| {
             Ok((
@@ -238,20 +629,47 @@ pub fn get_last_30_days_stats(conn: &Connection) -> Result<Vec<DayStats>> {
         })?
         .collect::<Result<HashMap<String, (i64, i64)>, _>>()?;
 
+    // Query 3: Which passages matured or were lost, grouped by date
+    let mut references = get_progress_references(ctx, &period, "date_str_from_ms")?;
+
+    // Query 4: Answers to cards in review, grouped by date
+    let retention = get_retention_counts(ctx, &period, "date_str_from_ms")?;
+
+    // The period may list only some of the days it spans (see `DatePeriod::for_dates`), so
+    // the running total also takes the progress of the days in between
+    let mut net_progress: Vec<(String, i64)> = progress_results
+        .iter()
+        .map(|(date, (matured, lost))| (date.clone(), matured - lost))
+        .collect();
+    net_progress.sort();
+    let mut net_progress = net_progress.into_iter().peekable();
     let mut cumulative_passages = 0i64;
 
     let results = period.build_results_2(
         time_results,
         progress_results,
-        |date, total_ms, (matured_passages, lost_passages)| {
-            cumulative_passages += matured_passages - lost_passages;
+        |date, (total_ms, reviews), (matured_passages, lost_passages)| {
+            while let Some((_, net)) = net_progress.next_if(|(day, _)| *day <= date) {
+                cumulative_passages += net;
+            }
+
+            let (matured_references, lost_references) =
+                references.remove(&date).unwrap_or_default();
+            let retention = retention.get(&date).and_then(|counts| counts.retention);
 
             DayStats {
-                date,
-                minutes: total_ms as f64 / 60_000.0,
+                time: statsmodels::DayStats {
+                    date,
+                    minutes: total_ms as f64 / 60_000.0,
+                },
                 matured_passages,
                 lost_passages,
                 cumulative_passages,
+                reviews,
+                avg_seconds_per_review: average_seconds(total_ms, reviews),
+                retention,
+                matured_references,
+                lost_references,
             }
         },
     );
@@ -260,28 +678,32 @@ pub fn get_last_30_days_stats(conn: &Connection) -> Result<Vec<DayStats>> {
 }
 
 /// Gets study time and learning progress for each of the last 12 weeks
-pub fn get_last_12_weeks_stats(conn: &Connection) -> Result<Vec<WeekStats>> {
-    let deck_id = get_deck_id(conn)?;
-    let model_id = get_model_id(conn)?;
+pub fn get_last_12_weeks_stats(ctx: &AnkiContext) -> Result<Vec<WeekStats>> {
+    get_weekly_stats(ctx, DatePeriod::last_12_weeks()?)
+}
 
-    // Get the period data for the last 12 weeks
-    let period = DatePeriod::last_12_weeks()?;
+/// Gets study time and learning progress for each week in the period
+pub fn get_weekly_stats(ctx: &AnkiContext, period: DatePeriod) -> Result<Vec<WeekStats>> {
+    let (deck_id, model_id) = (ctx.deck_id()?, ctx.model_id()?);
 
     // Query 1: Study time grouped by week
     let time_query = r#"
-        SELECT week_str_from_ms(r.id) as week, SUM(r.time) as total_ms
+        SELECT week_str_from_ms(r.id) as week, SUM(r.time) as total_ms, COUNT(*) as reviews
         FROM revlog r
         JOIN cards c ON c.id = r.cid
         WHERE c.did = ?1 AND r.id >= ?2 AND r.id < ?3
         GROUP BY week_str_from_ms(r.id)
     "#;
 
-    let mut time_stmt = conn.prepare(time_query)?;
+    let mut time_stmt = ctx.prepare(time_query)?;
     let time_results = time_stmt
         .query_map([deck_id, period.start_ms, period.end_ms], |row| {
-            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+            Ok((
+                row.get::<_, String>(0)?,
+                (row.get::<_, i64>(1)?, row.get::<_, i64>(2)?),
+            ))
         })?
-        .collect::<Result<HashMap<String, i64>, _>>()?;
+        .collect::<Result<HashMap<String, (i64, i64)>, _>>()?;
 
     // Query 2: Progress (maturation and loss) grouped by week
     let progress_query = format!(
@@ -300,7 +722,7 @@ pub fn get_last_12_weeks_stats(conn: &Connection) -> Result<Vec<WeekStats>> {
         "#
     );
 
-    let mut progress_stmt = conn.prepare(&progress_query)?;
+    let mut progress_stmt = ctx.prepare(&progress_query)?;
     let progress_results = progress_stmt
         .query_map([deck_id, model_id, period.start_ms, period.end_ms], |row| {
             Ok((
@@ -310,20 +732,37 @@ pub fn get_last_12_weeks_stats(conn: &Connection) -> Result<Vec<WeekStats>> {
         })?
         .collect::<Result<HashMap<String, (i64, i64)>, _>>()?;
 
+    // Query 3: Which passages matured or were lost, grouped by week
+    let mut references = get_progress_references(ctx, &period, "week_str_from_ms")?;
+
+    // Query 4: Answers to cards in review, grouped by week
+    let retention = get_retention_counts(ctx, &period, "week_str_from_ms")?;
+
     let mut cumulative_passages = 0;
 
     let results = period.build_results_2(
         time_results,
         progress_results,
-        |date, total_ms, (matured_passages, lost_passages)| {
+        |date, (total_ms, reviews), (matured_passages, lost_passages)| {
             cumulative_passages += matured_passages - lost_passages;
 
+            let (matured_references, lost_references) =
+                references.remove(&date).unwrap_or_default();
+            let retention = retention.get(&date).and_then(|counts| counts.retention);
+
             WeekStats {
-                week_start: date,
-                minutes: total_ms as f64 / 60_000.0,
+                time: statsmodels::WeekStats {
+                    week_start: date,
+                    minutes: total_ms as f64 / 60_000.0,
+                },
                 matured_passages,
                 lost_passages,
                 cumulative_passages,
+                reviews,
+                avg_seconds_per_review: average_seconds(total_ms, reviews),
+                retention,
+                matured_references,
+                lost_references,
             }
         },
     );
@@ -331,8 +770,314 @@ pub fn get_last_12_weeks_stats(conn: &Connection) -> Result<Vec<WeekStats>> {
     Ok(results)
 }
 
+/// Average seconds per review, or `None` without reviews
+fn average_seconds(total_ms: i64, reviews: i64) -> Option<f64> {
+    (reviews > 0).then(|| total_ms as f64 / 1000.0 / reviews as f64)
+}
+
+/// Lists the references of passages that matured or were lost, grouped by the given SQL
+/// date function (`date_str_from_ms` or `week_str_from_ms`)
+///
+/// Each list is in review order and capped at `MAX_PROGRESS_REFERENCES`.
+fn get_progress_references(
+    ctx: &AnkiContext,
+    period: &DatePeriod,
+    date_function: &str,
+) -> Result<HashMap<String, ProgressReferences>> {
+    let (deck_id, model_id) = (ctx.deck_id()?, ctx.model_id()?);
+    let query = format!(
+        r#"
+        SELECT {date_function}(r.id), n.sfld, r.ivl >= 21 AS matured
+        FROM revlog r
+        JOIN cards c ON c.id = r.cid
+        JOIN notes n ON n.id = c.nid
+        WHERE c.did = ?1 AND n.mid = ?2 AND c.ord = 0
+            AND c.queue != {QUEUE_TYPE_SUSPENDED}
+            AND r.id >= ?3 AND r.id < ?4
+            AND ((r.lastIvl < 21 AND r.ivl >= 21) OR (r.lastIvl >= 21 AND r.ivl < 21))
+        ORDER BY r.id
+        "#
+    );
+
+    let mut stmt = ctx.prepare(&query)?;
+    let rows = stmt.query_map([deck_id, model_id, period.start_ms, period.end_ms], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, bool>(2)?,
+        ))
+    })?;
+
+    let mut references: HashMap<String, ProgressReferences> = HashMap::new();
+    for row in rows {
+        let (date, reference, matured) = row?;
+        let (matured_references, lost_references) = references.entry(date).or_default();
+        let list = if matured {
+            matured_references
+        } else {
+            lost_references
+        };
+        if list.len() < MAX_PROGRESS_REFERENCES && !list.contains(&reference) {
+            list.push(reference);
+        }
+    }
+
+    Ok(references)
+}
+
+/// Counts the passed and failed answers to cards in review, grouped by the given SQL date
+/// function (`date_str_from_ms` or `week_str_from_ms`)
+///
+/// Both cards of a passage count, but answers in the learning and relearning steps do not.
+/// Like the other passage queries, only notes of the configured note type count.
+pub fn get_retention_counts(
+    ctx: &AnkiContext,
+    period: &DatePeriod,
+    date_function: &str,
+) -> Result<HashMap<String, Retention>> {
+    let (deck_id, model_id) = (ctx.deck_id()?, ctx.model_id()?);
+    let query = format!(
+        r#"
+        SELECT
+            {date_function}(r.id),
+            COUNT(CASE WHEN r.ease != {EASE_AGAIN} THEN 1 END) as passed,
+            COUNT(CASE WHEN r.ease = {EASE_AGAIN} THEN 1 END) as failed
+        FROM revlog r
+        JOIN cards c ON c.id = r.cid
+        JOIN notes n ON n.id = c.nid
+        WHERE c.did = ?1 AND n.mid = ?2 AND r.type = {REVLOG_TYPE_REVIEW}
+            AND r.id >= ?3 AND r.id < ?4
+        GROUP BY {date_function}(r.id)
+        "#
+    );
+
+    let mut stmt = ctx.prepare(&query)?;
+    let counts = stmt
+        .query_map([deck_id, model_id, period.start_ms, period.end_ms], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                Retention::new(row.get(1)?, row.get(2)?),
+            ))
+        })?
+        .collect::<Result<HashMap<String, Retention>, _>>()?;
+
+    Ok(counts)
+}
+
+/// Counts the passed and failed answers to the cards in review of each passage in the period
+pub fn get_passage_answers(ctx: &AnkiContext, period: &DatePeriod) -> Result<Vec<PassageAnswers>> {
+    let (deck_id, model_id) = (ctx.deck_id()?, ctx.model_id()?);
+    let query = format!(
+        r#"
+        SELECT
+            n.sfld,
+            COUNT(CASE WHEN r.ease != {EASE_AGAIN} THEN 1 END) as passed,
+            COUNT(CASE WHEN r.ease = {EASE_AGAIN} THEN 1 END) as failed
+        FROM revlog r
+        JOIN cards c ON c.id = r.cid
+        JOIN notes n ON n.id = c.nid
+        WHERE c.did = ?1 AND n.mid = ?2 AND r.type = {REVLOG_TYPE_REVIEW}
+            AND r.id >= ?3 AND r.id < ?4
+        GROUP BY n.sfld
+        "#
+    );
+
+    let mut stmt = ctx.prepare(&query)?;
+    let answers = stmt
+        .query_map([deck_id, model_id, period.start_ms, period.end_ms], |row| {
+            Ok(PassageAnswers {
+                reference: row.get(0)?,
+                passed_reviews: row.get(1)?,
+                failed_reviews: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<PassageAnswers>, _>>()?;
+
+    Ok(answers)
+}
+
+/// Gets every review that moved a passage across the 21-day maturity threshold, sorted by
+/// card and time
+pub fn get_progress_events(ctx: &AnkiContext) -> Result<Vec<ProgressEvent>> {
+    let (deck_id, model_id) = (ctx.deck_id()?, ctx.model_id()?);
+    let query = format!(
+        r#"
+        SELECT n.sfld, c.id, r.id, r.ivl >= 21 AS matured
+        FROM revlog r
+        JOIN cards c ON c.id = r.cid
+        JOIN notes n ON n.id = c.nid
+        WHERE c.did = ?1 AND n.mid = ?2 AND c.ord = 0
+            AND c.queue != {QUEUE_TYPE_SUSPENDED}
+            AND ((r.lastIvl < 21 AND r.ivl >= 21) OR (r.lastIvl >= 21 AND r.ivl < 21))
+        ORDER BY c.id, r.id
+        "#
+    );
+
+    let mut stmt = ctx.prepare(&query)?;
+    let events = stmt
+        .query_map([deck_id, model_id], |row| {
+            Ok(ProgressEvent {
+                reference: row.get(0)?,
+                card_id: row.get(1)?,
+                timestamp_ms: row.get(2)?,
+                matured: row.get(3)?,
+            })
+        })?
+        .collect::<Result<Vec<ProgressEvent>, _>>()?;
+
+    Ok(events)
+}
+
+/// Counts the reviews falling due on each of the next `days` days, starting with today
+/// (which includes overdue reviews)
+///
+/// Review and day-learning cards store their due date as a day number relative to the
+/// collection's creation; learning cards store a timestamp in seconds. Both are turned into
+/// days with the 4 AM rollover, the same way as the dates the counts are labeled with.
+pub fn get_due_reviews_by_day(ctx: &AnkiContext, days: i64) -> Result<Vec<DueDay>> {
+    get_due_reviews_by_day_at(ctx, days, chrono::Utc::now().timestamp_millis())
+}
+
+/// Same as `get_due_reviews_by_day()`, as of `now_ms`
+fn get_due_reviews_by_day_at(ctx: &AnkiContext, days: i64, now_ms: i64) -> Result<Vec<DueDay>> {
+    let deck_id = ctx.deck_id()?;
+    let collection_created_sec: i64 = ctx
+        .conn
+        .query_row("SELECT crt FROM col", [], |row| row.get(0))
+        .context("Failed to read collection creation time")?;
+    let today = timestamp_ms_to_date(now_ms)?;
+    // Anki numbers the days from the one the collection was created on
+    let today_number = (today - timestamp_ms_to_date(collection_created_sec * 1000)?).num_days();
+
+    let query = format!(
+        r#"
+        SELECT queue, due, COUNT(*)
+        FROM cards
+        WHERE did = ?1
+            AND queue IN ({QUEUE_TYPE_REV}, {QUEUE_TYPE_DAY_LEARN_RELEARN}, {QUEUE_TYPE_LRN})
+        GROUP BY queue, due
+        "#
+    );
+
+    let mut due_reviews = vec![0; days.max(0) as usize];
+    let mut stmt = ctx.prepare(&query)?;
+    let rows = stmt.query_map([deck_id], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, i64>(1)?,
+            row.get::<_, i64>(2)?,
+        ))
+    })?;
+    for row in rows {
+        let (queue, due, count) = row?;
+        let day = if queue == QUEUE_TYPE_LRN {
+            (timestamp_ms_to_date(due * 1000)? - today).num_days()
+        } else {
+            due - today_number
+        };
+        // Overdue reviews count today
+        if let Some(due_reviews) = due_reviews.get_mut(day.max(0) as usize) {
+            *due_reviews += count;
+        }
+    }
+
+    Ok(due_reviews
+        .into_iter()
+        .zip(today.iter_days())
+        .map(|(due_reviews, date)| DueDay {
+            date: date.format("%Y-%m-%d").to_string(),
+            due_reviews,
+        })
+        .collect())
+}
+
+/// Gets the average time per review (in seconds) since the given timestamp, or `None` if
+/// there were no reviews
+pub fn get_average_review_seconds(ctx: &AnkiContext, since_ms: i64) -> Result<Option<f64>> {
+    let deck_id = ctx.deck_id()?;
+    let query = r#"
+        SELECT SUM(r.time) / 1000.0 / COUNT(*)
+        FROM revlog r
+        JOIN cards c ON c.id = r.cid
+        WHERE c.did = ?1 AND r.id >= ?2
+    "#;
+
+    let average_seconds = ctx
+        .prepare(query)?
+        .query_row([deck_id, since_ms], |row| row.get(0))?;
+
+    Ok(average_seconds)
+}
+
+/// Gets the reviews of the Bible deck answered within the period, oldest first
+pub fn get_reviews(ctx: &AnkiContext, period: &DatePeriod) -> Result<Vec<Review>> {
+    let deck_id = ctx.deck_id()?;
+    let query = r#"
+        SELECT r.id, r.time
+        FROM revlog r
+        JOIN cards c ON c.id = r.cid
+        WHERE c.did = ?1 AND r.id >= ?2 AND r.id < ?3
+        ORDER BY r.id
+    "#;
+
+    let mut stmt = ctx.prepare(query)?;
+    let reviews = stmt
+        .query_map([deck_id, period.start_ms, period.end_ms], |row| {
+            Ok(Review {
+                timestamp_ms: row.get(0)?,
+                duration_ms: row.get(1)?,
+            })
+        })?
+        .collect::<Result<Vec<Review>, _>>()?;
+
+    Ok(reviews)
+}
+
+/// Gets the first and last review timestamps (milliseconds) for the Bible deck
+pub fn get_review_range(ctx: &AnkiContext) -> Result<(Option<i64>, Option<i64>)> {
+    let deck_id = ctx.deck_id()?;
+
+    let query = r#"
+        SELECT MIN(r.id), MAX(r.id)
+        FROM revlog r
+        JOIN cards c ON c.id = r.cid
+        WHERE c.did = ?1
+    "#;
+
+    let range = ctx
+        .prepare(query)?
+        .query_row([deck_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+    Ok(range)
+}
+
+/// Gets the dates (YYYY-MM-DD) with any study time on Bible passages over the whole review
+/// history, oldest first
+pub fn get_study_dates(ctx: &AnkiContext) -> Result<Vec<String>> {
+    let (deck_id, model_id) = (ctx.deck_id()?, ctx.model_id()?);
+
+    let query = r#"
+        SELECT date_str_from_ms(r.id) as date
+        FROM revlog r
+        JOIN cards c ON c.id = r.cid
+        JOIN notes n ON n.id = c.nid
+        WHERE c.did = ?1 AND n.mid = ?2
+        GROUP BY date_str_from_ms(r.id)
+        HAVING SUM(r.time) > 0
+        ORDER BY date
+    "#;
+
+    let mut stmt = ctx.prepare(query)?;
+    let dates = stmt
+        .query_map([deck_id, model_id], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<String>, _>>()?;
+
+    Ok(dates)
+}
+
 /// Gets all distinct Bible references from the database, sorted alphabetically
-pub fn get_all_references(conn: &Connection, deck_id: i64, model_id: i64) -> Result<Vec<String>> {
+pub fn get_all_references(ctx: &AnkiContext) -> Result<Vec<String>> {
+    let (deck_id, model_id) = (ctx.deck_id()?, ctx.model_id()?);
     let query = r#"
         SELECT DISTINCT n.sfld
         FROM notes n
@@ -341,10 +1086,469 @@ pub fn get_all_references(conn: &Connection, deck_id: i64, model_id: i64) -> Res
         ORDER BY n.sfld
     "#;
 
-    let mut stmt = conn.prepare(query)?;
+    let mut stmt = ctx.prepare(query)?;
     let references = stmt
         .query_map([deck_id, model_id], |row| row.get::<_, String>(0))?
         .collect::<Result<Vec<String>, _>>()?;
 
     Ok(references)
 }
+
+/// Gets each Bible reference with the number of cards using it
+pub fn get_reference_card_counts(ctx: &AnkiContext) -> Result<Vec<(String, i64)>> {
+    let (deck_id, model_id) = (ctx.deck_id()?, ctx.model_id()?);
+    let query = r#"
+        SELECT n.sfld, COUNT(c.id)
+        FROM notes n
+        JOIN cards c ON c.nid = n.id
+        WHERE c.did = ?1 AND n.mid = ?2
+        GROUP BY n.sfld
+        ORDER BY n.sfld
+    "#;
+
+    let mut stmt = ctx.prepare(query)?;
+    let references = stmt
+        .query_map([deck_id, model_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<(String, i64)>, _>>()?;
+
+    Ok(references)
+}
+
+/// Gets the scheduling settings of the Bible deck's options group
+///
+/// Since Anki 2.1.28 options groups live in the `deck_config` table as protobuf blobs, with
+/// FSRS switched on collection-wide through the "fsrs" config key. Older collections keep them
+/// as JSON in `col.dconf` and have no FSRS.
+pub fn get_deck_settings(ctx: &AnkiContext) -> Result<DeckSettings> {
+    let conn = ctx.conn();
+    let deck_id = ctx.deck_id()?;
+
+    if !has_table(conn, "deck_config")? {
+        return get_legacy_deck_settings(conn, deck_id);
+    }
+
+    let kind: Vec<u8> = conn
+        .query_row("SELECT kind FROM decks WHERE id = ?1", [deck_id], |row| {
+            row.get(0)
+        })
+        .context("Failed to read deck")?;
+    let config_id = settings::deck_config_id(&kind)?;
+
+    let (name, config): (String, Vec<u8>) = conn
+        .query_row(
+            "SELECT name, config FROM deck_config WHERE id = ?1",
+            [config_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .context(format!("Failed to find options group {}", config_id))?;
+
+    let fsrs_enabled = has_table(conn, "config")?
+        && conn
+            .query_row("SELECT val FROM config WHERE KEY = 'fsrs'", [], |row| {
+                row.get::<_, Vec<u8>>(0)
+            })
+            .optional()
+            .context("Failed to read FSRS setting")?
+            .is_some_and(|val| val == b"true");
+
+    settings::parse_deck_config(name, &config, fsrs_enabled)
+}
+
+/// Gets the deck settings from the JSON options groups of collections before Anki 2.1.28
+fn get_legacy_deck_settings(conn: &Connection, deck_id: i64) -> Result<DeckSettings> {
+    let query = r#"
+        SELECT
+            json_extract(conf.value, '$.name'),
+            json_extract(conf.value, '$.new.perDay'),
+            json_extract(conf.value, '$.rev.perDay'),
+            json_extract(conf.value, '$.rev.maxIvl')
+        FROM col, json_each(col.decks) AS deck, json_each(col.dconf) AS conf
+        WHERE CAST(deck.key AS INTEGER) = ?1
+            AND CAST(conf.key AS INTEGER) = json_extract(deck.value, '$.conf')
+    "#;
+
+    conn.query_row(query, [deck_id], |row| {
+        Ok(DeckSettings {
+            config_name: row.get(0)?,
+            new_per_day: row.get(1)?,
+            reviews_per_day: row.get(2)?,
+            maximum_review_interval_days: row.get(3)?,
+            fsrs_enabled: false,
+            desired_retention: None,
+        })
+    })
+    .context("Failed to read deck options group")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ids_from_current_schema() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE decks (id INTEGER, name TEXT);
+             CREATE TABLE notetypes (id INTEGER, name TEXT);
+             INSERT INTO notetypes VALUES (3, 'Basic'), (4, 'Bible Verse');",
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO decks VALUES (1, 'Default'), (2, ?1)",
+            [format!("bible{}verses", UNIT_SEPARATOR)],
+        )
+        .unwrap();
+
+        assert_eq!(get_deck_id(&conn, "Bible::Verses").unwrap(), 2);
+        assert_eq!(get_model_id(&conn, "Bible Verse").unwrap(), 4);
+        assert_eq!(get_deck_id(&conn, "Default").unwrap(), 1);
+        assert_eq!(get_model_id(&conn, "basic").unwrap(), 3);
+    }
+
+    #[test]
+    fn test_ids_from_legacy_col_json() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r#"CREATE TABLE col (id INTEGER, crt INTEGER, decks TEXT, models TEXT);
+               INSERT INTO col VALUES (
+                   1,
+                   0,
+                   '{"1": {"name": "Default"}, "1500000000000": {"name": "Bible::Verses"}}',
+                   '{"1400000000000": {"name": "Basic"}, "1400000000001": {"name": "Bible Verse"}}'
+               );"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            get_deck_id(&conn, "Bible::Verses").unwrap(),
+            1_500_000_000_000
+        );
+        assert_eq!(
+            get_model_id(&conn, "Bible Verse").unwrap(),
+            1_400_000_000_001
+        );
+    }
+
+    #[test]
+    fn test_configured_deck_and_note_type() {
+        let conn = Connection::open_in_memory().unwrap();
+        register_functions(&conn).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE decks (id INTEGER, name TEXT);
+             CREATE TABLE notetypes (id INTEGER, name TEXT);
+             CREATE TABLE notes (id INTEGER, mid INTEGER, sfld TEXT);
+             CREATE TABLE cards (id INTEGER, nid INTEGER, did INTEGER, ord INTEGER, queue INTEGER, ivl INTEGER);
+             INSERT INTO notetypes VALUES (4, 'Bible Verse'), (5, 'Scripture');
+             INSERT INTO notes VALUES (10, 4, 'John 3:16'), (11, 5, 'Romans 8:28');
+             INSERT INTO cards VALUES
+                 (100, 10, 2, 0, 2, 30), (101, 10, 2, 1, 2, 25),
+                 (110, 11, 3, 0, 2, 30), (111, 11, 3, 1, 2, 25);",
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO decks VALUES (2, ?1), (3, ?2)",
+            [
+                format!("Bible{}Verses", UNIT_SEPARATOR),
+                format!("Memory{}New Testament", UNIT_SEPARATOR),
+            ],
+        )
+        .unwrap();
+
+        let ctx = AnkiContext::new(conn).with_config(AnkiStatsConfig {
+            deck_name: "memory::new testament".to_string(),
+            note_type: "Scripture".to_string(),
+        });
+        assert_eq!(ctx.deck_id().unwrap(), 3);
+        let passages = get_passage_statuses(&ctx).unwrap();
+        assert_eq!(
+            passages,
+            vec![("Romans 8:28".to_string(), PassageStatus::Mature)]
+        );
+    }
+
+    #[test]
+    fn test_passage_details_from_revlog() {
+        let conn = Connection::open_in_memory().unwrap();
+        register_functions(&conn).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE decks (id INTEGER, name TEXT);
+             CREATE TABLE notetypes (id INTEGER, name TEXT);
+             CREATE TABLE notes (id INTEGER, mid INTEGER, sfld TEXT);
+             CREATE TABLE cards (id INTEGER, nid INTEGER, did INTEGER, ord INTEGER, queue INTEGER, ivl INTEGER);
+             CREATE TABLE revlog (id INTEGER, cid INTEGER, ivl INTEGER);
+             INSERT INTO notetypes VALUES (4, 'Bible Verse');
+             INSERT INTO notes VALUES (10, 4, 'John 3:16'), (11, 4, 'Jude 1:24-25');
+             INSERT INTO cards VALUES
+                 (100, 10, 2, 0, 2, 30), (101, 10, 2, 1, 2, 25),
+                 (110, 11, 2, 0, 0, 0), (111, 11, 2, 1, 0, 0);
+             -- 2024-03-10, 2024-03-11 and 2024-04-20 at noon UTC
+             INSERT INTO revlog VALUES
+                 (1710072000000, 100, 1), (1710158400000, 101, 1),
+                 (1713614400000, 100, 22), (1713614400001, 101, 8),
+                 (1713700800000, 101, 21), (1714478400000, 100, 40);",
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO decks VALUES (2, ?1)",
+            [format!("Bible{}Verses", UNIT_SEPARATOR)],
+        )
+        .unwrap();
+
+        let passages = get_passage_details(&AnkiContext::new(conn)).unwrap();
+        assert_eq!(passages.len(), 2);
+        assert_eq!(passages[0].reference, "John 3:16");
+        assert_eq!(passages[0].status, PassageStatus::Mature);
+        assert_eq!(
+            passages[0].first_review_date,
+            Some(statsutils::timestamp_ms_to_date_string(1710072000000).unwrap())
+        );
+        // Card 101 only reached 21 days a day after card 100
+        assert_eq!(
+            passages[0].matured_date,
+            Some(statsutils::timestamp_ms_to_date_string(1713700800000).unwrap())
+        );
+        assert_eq!(passages[1].status, PassageStatus::Unseen);
+        assert_eq!(passages[1].first_review_date, None);
+        assert_eq!(passages[1].matured_date, None);
+    }
+
+    #[test]
+    fn test_retention_counts() {
+        let conn = Connection::open_in_memory().unwrap();
+        register_functions(&conn).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE decks (id INTEGER, name TEXT);
+             CREATE TABLE notetypes (id INTEGER, name TEXT);
+             CREATE TABLE notes (id INTEGER, mid INTEGER, sfld TEXT);
+             CREATE TABLE cards (id INTEGER, nid INTEGER, did INTEGER, ord INTEGER, queue INTEGER, ivl INTEGER);
+             CREATE TABLE revlog (id INTEGER, cid INTEGER, ease INTEGER, type INTEGER);
+             INSERT INTO notetypes VALUES (4, 'Bible Verse'), (5, 'Basic');
+             INSERT INTO notes VALUES
+                 (10, 4, 'John 3:16'), (11, 4, 'Romans 8:28'), (12, 5, 'Capital of France');
+             INSERT INTO cards VALUES
+                 (100, 10, 2, 0, 2, 30), (101, 10, 2, 1, 2, 25), (110, 11, 2, 0, 2, 5),
+                 (120, 12, 2, 0, 2, 10);
+             -- 2024-03-10 and 2024-03-11 at noon UTC; the learning step (type 0), the
+             -- relearning step (type 2) after the failed review and the answer to a note of
+             -- another type do not count
+             INSERT INTO revlog VALUES
+                 (1710072000000, 100, 3, 1), (1710072000001, 101, 1, 1),
+                 (1710072000002, 101, 3, 2), (1710072000003, 110, 1, 0),
+                 (1710158400000, 110, 4, 1), (1710158400001, 120, 1, 1);",
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO decks VALUES (2, ?1)",
+            [format!("Bible{}Verses", UNIT_SEPARATOR)],
+        )
+        .unwrap();
+        let ctx = AnkiContext::new(conn);
+        let dates = ["2024-03-10", "2024-03-11"].map(String::from);
+        let period = DatePeriod::for_dates(&dates).unwrap();
+
+        let counts = get_retention_counts(&ctx, &period, "date_str_from_ms").unwrap();
+        assert_eq!(counts["2024-03-10"].passed_reviews, 1);
+        assert_eq!(counts["2024-03-10"].failed_reviews, 1);
+        assert_eq!(counts["2024-03-10"].retention, Some(0.5));
+        assert_eq!(counts["2024-03-11"].retention, Some(1.0));
+
+        let mut answers = get_passage_answers(&ctx, &period).unwrap();
+        answers.sort_by(|a, b| a.reference.cmp(&b.reference));
+        assert_eq!(answers.len(), 2);
+        assert_eq!(answers[0].reference, "John 3:16");
+        assert_eq!(answers[0].failed_reviews, 1);
+        assert_eq!(answers[1].passed_reviews, 1);
+    }
+
+    #[test]
+    fn test_study_dates() {
+        let conn = Connection::open_in_memory().unwrap();
+        register_functions(&conn).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE decks (id INTEGER, name TEXT);
+             CREATE TABLE notetypes (id INTEGER, name TEXT);
+             CREATE TABLE notes (id INTEGER, mid INTEGER, sfld TEXT);
+             CREATE TABLE cards (id INTEGER, nid INTEGER, did INTEGER, ord INTEGER, queue INTEGER, ivl INTEGER);
+             CREATE TABLE revlog (id INTEGER, cid INTEGER, time INTEGER);
+             INSERT INTO notetypes VALUES (4, 'Bible Verse'), (5, 'Basic');
+             INSERT INTO notes VALUES (10, 4, 'John 3:16'), (12, 5, 'Capital of France');
+             INSERT INTO cards VALUES (100, 10, 2, 0, 2, 30), (120, 12, 2, 0, 2, 10);
+             -- 2024-03-11 at noon UTC twice, 2024-03-10, then 2024-03-12 with no time spent
+             -- and 2024-03-13 only on a note of another type
+             INSERT INTO revlog VALUES
+                 (1710158400000, 100, 8000), (1710158400001, 100, 6000),
+                 (1710072000000, 100, 9000), (1710244800000, 100, 0),
+                 (1710331200000, 120, 7000);",
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO decks VALUES (2, ?1)",
+            [format!("Bible{}Verses", UNIT_SEPARATOR)],
+        )
+        .unwrap();
+
+        let dates = get_study_dates(&AnkiContext::new(conn)).unwrap();
+        let expected = [1710072000000, 1710158400000]
+            .map(|ms| statsutils::timestamp_ms_to_date_string(ms).unwrap());
+        assert_eq!(dates, expected);
+    }
+
+    #[test]
+    fn test_due_reviews_by_day() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE decks (id INTEGER, name TEXT);
+             CREATE TABLE col (crt INTEGER);
+             CREATE TABLE cards (id INTEGER, did INTEGER, queue INTEGER, due INTEGER);",
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO decks VALUES (2, ?1)",
+            [format!("Bible{}Verses", UNIT_SEPARATOR)],
+        )
+        .unwrap();
+        let local_ms = |datetime: &str| {
+            let datetime = chrono::NaiveDateTime::parse_from_str(datetime, "%Y-%m-%d %H:%M");
+            statsutils::local_datetime_to_ms(datetime.unwrap()).unwrap()
+        };
+        // Created at the rollover 100 days before 2025-03-20, across the change to daylight
+        // saving time, so 2025-03-20 is day 100
+        conn.execute(
+            "INSERT INTO col VALUES (?1)",
+            [local_ms("2024-12-10 04:00") / 1000],
+        )
+        .unwrap();
+        // Overdue and due today, due tomorrow, after the forecast, in learning later today
+        // and tomorrow, new, suspended, and in another deck
+        conn.execute_batch(&format!(
+            "INSERT INTO cards VALUES
+                 (1, 2, {rev}, 97), (2, 2, {rev}, 100), (3, 2, {day_learn}, 101),
+                 (4, 2, {rev}, 103), (5, 2, {lrn}, {later_today}), (6, 2, {lrn}, {tomorrow}),
+                 (7, 2, {new}, 0), (8, 2, {suspended}, 100), (9, 3, {rev}, 100);",
+            rev = QUEUE_TYPE_REV,
+            day_learn = QUEUE_TYPE_DAY_LEARN_RELEARN,
+            lrn = QUEUE_TYPE_LRN,
+            new = QUEUE_TYPE_NEW,
+            suspended = QUEUE_TYPE_SUSPENDED,
+            later_today = local_ms("2025-03-20 13:00") / 1000,
+            tomorrow = local_ms("2025-03-21 13:00") / 1000,
+        ))
+        .unwrap();
+        let ctx = AnkiContext::new(conn);
+        let counts = |due: Vec<DueDay>| due.iter().map(|d| d.due_reviews).collect::<Vec<_>>();
+
+        let due = get_due_reviews_by_day_at(&ctx, 3, local_ms("2025-03-20 12:00")).unwrap();
+        assert_eq!(due[0].date, "2025-03-20");
+        assert_eq!(due[2].date, "2025-03-22");
+        assert_eq!(counts(due), vec![3, 2, 0]);
+        assert!(
+            get_due_reviews_by_day_at(&ctx, 0, local_ms("2025-03-20 12:00"))
+                .unwrap()
+                .is_empty()
+        );
+
+        // Before the rollover it is still the 19th (day 99), and the learning card due at
+        // 13:00 counts on the 20th like the review due that day
+        let due = get_due_reviews_by_day_at(&ctx, 3, local_ms("2025-03-20 03:30")).unwrap();
+        assert_eq!(due[0].date, "2025-03-19");
+        assert_eq!(counts(due), vec![1, 2, 2]);
+    }
+
+    #[test]
+    fn test_chapter_stats() {
+        let conn = Connection::open_in_memory().unwrap();
+        register_functions(&conn).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE decks (id INTEGER, name TEXT);
+             CREATE TABLE notetypes (id INTEGER, name TEXT);
+             CREATE TABLE notes (id INTEGER, mid INTEGER, sfld TEXT);
+             CREATE TABLE cards (id INTEGER, nid INTEGER, did INTEGER, ord INTEGER, queue INTEGER, ivl INTEGER);
+             INSERT INTO notetypes VALUES (4, 'Bible Verse');
+             INSERT INTO notes VALUES
+                 (10, 4, 'Romans 12:1-2'), (11, 4, 'Romans 8:28'), (12, 4, 'Romans 8:38-39'),
+                 (13, 4, 'Romans 8:39-9:1');
+             INSERT INTO cards VALUES
+                 (100, 10, 2, 0, 0, 0), (101, 10, 2, 1, 0, 0),
+                 (110, 11, 2, 0, 2, 30), (111, 11, 2, 1, 2, 25),
+                 (120, 12, 2, 0, 2, 30), (121, 12, 2, 1, 2, 3),
+                 (130, 13, 2, 0, 2, 30), (131, 13, 2, 1, 2, 30);",
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO decks VALUES (2, ?1)",
+            [format!("Bible{}Verses", UNIT_SEPARATOR)],
+        )
+        .unwrap();
+
+        let ctx = AnkiContext::new(conn);
+        let chapters = get_chapter_stats(&ctx).unwrap();
+        let romans = &chapters["Romans"];
+        assert_eq!(romans.len(), 2);
+        assert_eq!(romans[0].chapter, 8);
+        assert_eq!(romans[0].mature_passages, 1);
+        assert_eq!(romans[0].young_passages, 1);
+        assert_eq!(romans[0].young_verses, 2);
+        assert_eq!(romans[1].chapter, 12);
+        assert_eq!(romans[1].unseen_verses, 2);
+
+        // The book still counts the passage spanning chapters
+        let books = get_all_books_stats(&ctx).unwrap();
+        let romans = books["Romans"].clone().with_unique_verses(None);
+        assert_eq!(romans.mature_passages, 2);
+        assert_eq!(romans.chapters.len(), 16);
+        assert_eq!(romans.chapters[0].total_passages(), 0);
+        assert_eq!(romans.chapters[7].total_passages(), 2);
+        assert_eq!(romans.chapters[11].chapter, 12);
+        assert_eq!(romans.chapters[11].unseen_passages, 1);
+    }
+
+    #[test]
+    fn test_deck_settings_from_current_schema() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE decks (id INTEGER, name TEXT, kind BLOB);
+             CREATE TABLE deck_config (id INTEGER, name TEXT, config BLOB);
+             CREATE TABLE config (KEY TEXT, val BLOB);
+             INSERT INTO deck_config VALUES (1, 'Default', x'4814');
+             INSERT INTO deck_config VALUES (7, 'Bible', x'4805500f80013cad02cdcc4c3f');
+             INSERT INTO config VALUES ('fsrs', CAST('true' AS BLOB));",
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO decks VALUES (2, ?1, x'0a020807')",
+            [format!("Bible{}Verses", UNIT_SEPARATOR)],
+        )
+        .unwrap();
+
+        let settings = get_deck_settings(&AnkiContext::new(conn)).unwrap();
+        assert_eq!(settings.config_name, "Bible");
+        assert_eq!(settings.new_per_day, 5);
+        assert_eq!(settings.reviews_per_day, 15);
+        assert_eq!(settings.maximum_review_interval_days, 60);
+        assert!(settings.fsrs_enabled);
+        assert_eq!(settings.desired_retention, Some(0.8));
+    }
+
+    #[test]
+    fn test_deck_settings_from_legacy_col_json() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r#"CREATE TABLE col (id INTEGER, crt INTEGER, decks TEXT, dconf TEXT);
+               INSERT INTO col VALUES (
+                   1,
+                   0,
+                   '{"1500000000000": {"name": "Bible::Verses", "conf": 2}}',
+                   '{"1": {"name": "Default", "new": {"perDay": 20}, "rev": {"perDay": 200, "maxIvl": 36500}},
+                     "2": {"name": "Bible", "new": {"perDay": 3}, "rev": {"perDay": 100, "maxIvl": 3650}}}'
+               );"#,
+        )
+        .unwrap();
+
+        let settings = get_deck_settings(&AnkiContext::new(conn)).unwrap();
+        assert_eq!(settings.config_name, "Bible");
+        assert_eq!(settings.new_per_day, 3);
+        assert_eq!(settings.reviews_per_day, 100);
+        assert_eq!(settings.maximum_review_interval_days, 3650);
+        assert!(!settings.fsrs_enabled);
+    }
+}
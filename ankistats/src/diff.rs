@@ -0,0 +1,103 @@
+use crate::models::{BibleStats, BookDelta, BookStats, CollectionDiff};
+
+/// Compares the book statistics of two collections (e.g. monthly backups)
+///
+/// Every book of the Bible is listed, with positive numbers for growth from `old` to `new`.
+pub fn diff_stats(old: &BibleStats, new: &BibleStats) -> CollectionDiff {
+    let mut books = Vec::new();
+    let mut total = BookDelta {
+        book: "Total".to_string(),
+        ..Default::default()
+    };
+
+    for (old_book, new_book) in all_books(old).zip(all_books(new)) {
+        let delta = book_delta(old_book, new_book);
+        total.mature_passages += delta.mature_passages;
+        total.young_passages += delta.young_passages;
+        total.unseen_passages += delta.unseen_passages;
+        total.mature_verses += delta.mature_verses;
+        total.young_verses += delta.young_verses;
+        total.unseen_verses += delta.unseen_verses;
+        books.push(delta);
+    }
+
+    CollectionDiff { books, total }
+}
+
+/// All books in canonical order (both reports list every book, so they line up)
+fn all_books(stats: &BibleStats) -> impl Iterator<Item = &BookStats> {
+    stats
+        .old_testament
+        .book_stats
+        .iter()
+        .chain(&stats.new_testament.book_stats)
+}
+
+fn book_delta(old: &BookStats, new: &BookStats) -> BookDelta {
+    BookDelta {
+        book: new.book.clone(),
+        mature_passages: new.mature_passages - old.mature_passages,
+        young_passages: new.young_passages - old.young_passages,
+        unseen_passages: new.unseen_passages - old.unseen_passages,
+        mature_verses: new.mature_verses - old.mature_verses,
+        young_verses: new.young_verses - old.young_verses,
+        unseen_verses: new.unseen_verses - old.unseen_verses,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn book(name: &str, mature: i64, young: i64, unseen: i64) -> BookStats {
+        BookStats {
+            book: name.to_string(),
+            mature_passages: mature,
+            young_passages: young,
+            unseen_passages: unseen,
+            suspended_passages: 0,
+            mature_verses: mature * 2,
+            young_verses: young * 2,
+            unseen_verses: unseen * 2,
+            suspended_verses: 0,
+            unique_mature_verses: 0,
+            unique_young_verses: 0,
+            unique_unseen_verses: 0,
+            unique_suspended_verses: 0,
+            chapters: Vec::new(),
+        }
+    }
+
+    fn stats(genesis: BookStats, john: BookStats) -> BibleStats {
+        let mut stats = BibleStats::new();
+        stats.old_testament.add_book(genesis);
+        stats.new_testament.add_book(john);
+        stats
+    }
+
+    #[test]
+    fn test_diff_stats() {
+        let old = stats(book("Genesis", 1, 2, 3), book("John", 4, 0, 0));
+        let new = stats(book("Genesis", 2, 1, 5), book("John", 4, 0, 0));
+
+        let diff = diff_stats(&old, &new);
+
+        assert_eq!(diff.books.len(), 2);
+        assert_eq!(
+            diff.books[0],
+            BookDelta {
+                book: "Genesis".to_string(),
+                mature_passages: 1,
+                young_passages: -1,
+                unseen_passages: 2,
+                mature_verses: 2,
+                young_verses: -2,
+                unseen_verses: 4,
+            }
+        );
+        assert!(diff.books[0].is_changed());
+        assert!(!diff.books[1].is_changed());
+        assert_eq!(diff.total.mature_passages, 1);
+        assert_eq!(diff.total.book, "Total");
+    }
+}
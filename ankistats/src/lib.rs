@@ -1,27 +1,63 @@
-pub mod bible;
 pub mod book_name_parser;
+pub mod config;
+pub mod coverage;
+#[cfg(feature = "sqlite")]
 pub mod db;
+pub mod diff;
 pub mod models;
+#[cfg(feature = "sqlite")]
+pub mod package;
+pub mod parse_warnings;
+pub mod reference;
+pub mod relapse;
+pub mod retention;
+pub mod sessions;
+pub mod settings;
+pub mod spans;
+pub mod streaks;
 pub mod verse_parser;
 
+/// Canonical Bible books, chapters and verse counts
+pub use bibledata as bible;
+
+#[cfg(feature = "sqlite")]
 use anyhow::Result;
+#[cfg(feature = "sqlite")]
+use chrono::NaiveDate;
+#[cfg(feature = "sqlite")]
+use statsutils::{DataRange, DatePeriod};
 
+#[cfg(feature = "sqlite")]
 use crate::bible::{NEW_TESTAMENT, OLD_TESTAMENT};
-use crate::models::{BibleStats, DayStats, WeekStats};
+#[cfg(feature = "sqlite")]
+use crate::config::AnkiCollection;
+#[cfg(feature = "sqlite")]
+use crate::models::{
+    BibleStats, BookCoverage, CollectionDiff, DayRetention, DayStats, DeckSettings, ParseWarning,
+    PassageDetails, RelapseStats, RetentionStats, ReviewForecast, SpanBucketStats, StudySessions,
+    StudyStreaks, WeekRetention, WeekStats,
+};
 
 /// Retrieves statistics for all Bible books from an Anki database
-pub fn get_bible_stats(db_path: &str) -> Result<BibleStats> {
-    let conn = db::open_database(db_path)?;
-    let deck_id = db::get_deck_id(&conn)?;
-    let model_id = db::get_model_id(&conn)?;
+#[cfg(feature = "sqlite")]
+pub fn get_bible_stats<'a>(collection: impl Into<AnkiCollection<'a>>) -> Result<BibleStats> {
+    let ctx = db::AnkiContext::open(collection)?;
+
+    // In strict mode, report every unparsable reference up front rather than failing on the
+    // first one the stats query hits
+    check_strict_references(&ctx)?;
 
     // Get all book stats in a single query
-    let books_map = db::get_all_books_stats(&conn, deck_id, model_id)?;
+    let books_map = db::get_all_books_stats(&ctx)?;
+
+    // Expand every passage into verses so overlapping cards are only counted once
+    let passages = db::get_passage_statuses(&ctx)?;
+    let coverage = coverage::books_coverage(&passages);
 
     let mut stats = BibleStats::new();
 
     // Get Old Testament stats - lookup from HashMap or create zero-filled stats
-    for &book in OLD_TESTAMENT {
+    for book in OLD_TESTAMENT.iter().map(|book| book.name) {
         let book_stats = books_map
             .get(book)
             .cloned()
@@ -35,12 +71,18 @@ pub fn get_bible_stats(db_path: &str) -> Result<BibleStats> {
                 young_verses: 0,
                 unseen_verses: 0,
                 suspended_verses: 0,
-            });
+                unique_mature_verses: 0,
+                unique_young_verses: 0,
+                unique_unseen_verses: 0,
+                unique_suspended_verses: 0,
+                chapters: Vec::new(),
+            })
+            .with_unique_verses(coverage.get(book));
         stats.old_testament.add_book(book_stats);
     }
 
     // Get New Testament stats - lookup from HashMap or create zero-filled stats
-    for &book in NEW_TESTAMENT {
+    for book in NEW_TESTAMENT.iter().map(|book| book.name) {
         let book_stats = books_map
             .get(book)
             .cloned()
@@ -54,35 +96,306 @@ pub fn get_bible_stats(db_path: &str) -> Result<BibleStats> {
                 young_verses: 0,
                 unseen_verses: 0,
                 suspended_verses: 0,
-            });
+                unique_mature_verses: 0,
+                unique_young_verses: 0,
+                unique_unseen_verses: 0,
+                unique_suspended_verses: 0,
+                chapters: Vec::new(),
+            })
+            .with_unique_verses(coverage.get(book));
         stats.new_testament.add_book(book_stats);
     }
 
     Ok(stats)
 }
 
+/// Compares the book statistics of two collection files (or .colpkg/.apkg exports), e.g. to
+/// quantify progress between monthly backups
+#[cfg(feature = "sqlite")]
+pub fn diff_collections<'a>(
+    old: impl Into<AnkiCollection<'a>>,
+    new: impl Into<AnkiCollection<'a>>,
+) -> Result<CollectionDiff> {
+    let old = get_bible_stats(old)?;
+    let new = get_bible_stats(new)?;
+    Ok(diff::diff_stats(&old, &new))
+}
+
+/// Counts passages by length (1 verse, 2–3, 4–6, 7+) and status
+#[cfg(feature = "sqlite")]
+pub fn get_span_distribution<'a>(
+    collection: impl Into<AnkiCollection<'a>>,
+) -> Result<Vec<SpanBucketStats>> {
+    let ctx = db::AnkiContext::open(collection)?;
+    check_strict_references(&ctx)?;
+
+    let passages = db::get_passage_statuses(&ctx)?;
+    Ok(spans::span_distribution(&passages))
+}
+
+/// Gets the fraction of matured passages that dropped below 21 days again within 90 days,
+/// per book
+#[cfg(feature = "sqlite")]
+pub fn get_relapse_stats<'a>(collection: impl Into<AnkiCollection<'a>>) -> Result<RelapseStats> {
+    let ctx = db::AnkiContext::open(collection)?;
+
+    let events = db::get_progress_events(&ctx)?;
+    Ok(relapse::relapse_stats(
+        &events,
+        chrono::Utc::now().timestamp_millis(),
+    ))
+}
+
+/// Gets the retention of the cards in review for each of the last `days` days and `weeks`
+/// weeks, and per book over those days
+#[cfg(feature = "sqlite")]
+pub fn get_retention_stats<'a>(
+    collection: impl Into<AnkiCollection<'a>>,
+    days: i32,
+    weeks: i32,
+) -> Result<RetentionStats> {
+    let ctx = db::AnkiContext::open(collection)?;
+
+    let day_period = DatePeriod::last_n_days(days)?;
+    let (books, total) = retention::book_retention(&db::get_passage_answers(&ctx, &day_period)?);
+    let daily = db::get_retention_counts(&ctx, &day_period, "date_str_from_ms")?;
+    let daily = day_period.build_results(daily, |date, retention| DayRetention { date, retention });
+
+    let week_period = DatePeriod::last_n_weeks(weeks)?;
+    let weekly = db::get_retention_counts(&ctx, &week_period, "week_str_from_ms")?;
+    let weekly = week_period.build_results(weekly, |week_start, retention| WeekRetention {
+        week_start,
+        retention,
+    });
+
+    Ok(RetentionStats {
+        daily,
+        weekly,
+        books,
+        total,
+    })
+}
+
+/// Groups the reviews of the last `days` days into study sessions, split by pauses longer
+/// than `gap_minutes`
+#[cfg(feature = "sqlite")]
+pub fn get_study_sessions<'a>(
+    collection: impl Into<AnkiCollection<'a>>,
+    days: i32,
+    gap_minutes: i64,
+) -> Result<StudySessions> {
+    let ctx = db::AnkiContext::open(collection)?;
+
+    let period = DatePeriod::last_n_days(days)?;
+    let reviews = db::get_reviews(&ctx, &period)?;
+    Ok(StudySessions {
+        gap_minutes,
+        days: sessions::daily_sessions(&reviews, gap_minutes, &period.dates)?,
+    })
+}
+
+/// Computes the current and longest study streaks over the whole review history, counting
+/// the days with any study time in the Bible deck
+#[cfg(feature = "sqlite")]
+pub fn get_streaks<'a>(collection: impl Into<AnkiCollection<'a>>) -> Result<StudyStreaks> {
+    let ctx = db::AnkiContext::open(collection)?;
+
+    let dates = db::get_study_dates(&ctx)?
+        .iter()
+        .map(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d"))
+        .collect::<Result<Vec<NaiveDate>, _>>()?;
+    let today = NaiveDate::parse_from_str(&statsutils::get_today_date()?, "%Y-%m-%d")?;
+    Ok(streaks::study_streaks(&dates, today))
+}
+
+/// In strict mode, fails with a list of every unparsable reference in the deck
+#[cfg(feature = "sqlite")]
+fn check_strict_references(ctx: &db::AnkiContext) -> Result<()> {
+    if !parse_warnings::strict_references() {
+        return Ok(());
+    }
+
+    let references = db::get_reference_card_counts(ctx)?;
+    let warnings = parse_warnings::find_parse_warnings(&references);
+    if !warnings.is_empty() {
+        anyhow::bail!(parse_warnings::describe_parse_warnings(&warnings));
+    }
+    Ok(())
+}
+
+/// Estimates the Anki review workload for each of the next `days` days from the due reviews
+/// and the average time per review over the last 30 days
+#[cfg(feature = "sqlite")]
+pub fn get_review_forecast<'a>(
+    collection: impl Into<AnkiCollection<'a>>,
+    days: i64,
+) -> Result<ReviewForecast> {
+    let ctx = db::AnkiContext::open(collection)?;
+
+    let daily = db::get_due_reviews_by_day(&ctx, days)?;
+    let since_ms = DatePeriod::last_30_days()?.start_ms;
+    let average_seconds = db::get_average_review_seconds(&ctx, since_ms)?;
+
+    Ok(ReviewForecast::new(daily, average_seconds.unwrap_or(0.0)))
+}
+
+/// Gets the number of reviews falling due on each of the next `days` days, starting with
+/// today (which includes overdue reviews)
+#[cfg(feature = "sqlite")]
+pub fn get_due_reviews_by_day<'a>(
+    collection: impl Into<AnkiCollection<'a>>,
+    days: i64,
+) -> Result<Vec<i64>> {
+    let ctx = db::AnkiContext::open(collection)?;
+    Ok(db::get_due_reviews_by_day(&ctx, days)?
+        .into_iter()
+        .map(|day| day.due_reviews)
+        .collect())
+}
+
 /// Gets the total study time for today in minutes
-pub fn get_today_study_time(db_path: &str) -> Result<f64> {
-    let conn = db::open_database(db_path)?;
-    db::get_today_study_minutes(&conn)
+#[cfg(feature = "sqlite")]
+pub fn get_today_study_time<'a>(collection: impl Into<AnkiCollection<'a>>) -> Result<f64> {
+    let ctx = db::AnkiContext::open(collection)?;
+    db::get_today_study_minutes(&ctx)
 }
 
 /// Gets study time and learning progress for each of the last 30 days
-pub fn get_last_30_days_stats(db_path: &str) -> Result<Vec<DayStats>> {
-    let conn = db::open_database(db_path)?;
-    db::get_last_30_days_stats(&conn)
+#[cfg(feature = "sqlite")]
+pub fn get_last_30_days_stats<'a>(
+    collection: impl Into<AnkiCollection<'a>>,
+) -> Result<Vec<DayStats>> {
+    let ctx = db::AnkiContext::open(collection)?;
+    db::get_last_30_days_stats(&ctx)
 }
 
 /// Gets study time and learning progress for each of the last 12 weeks
-pub fn get_last_12_weeks_stats(db_path: &str) -> Result<Vec<WeekStats>> {
-    let conn = db::open_database(db_path)?;
-    db::get_last_12_weeks_stats(&conn)
+#[cfg(feature = "sqlite")]
+pub fn get_last_12_weeks_stats<'a>(
+    collection: impl Into<AnkiCollection<'a>>,
+) -> Result<Vec<WeekStats>> {
+    let ctx = db::AnkiContext::open(collection)?;
+    db::get_last_12_weeks_stats(&ctx)
+}
+
+/// Gets study time and learning progress for each of the last `days` days
+#[cfg(feature = "sqlite")]
+pub fn get_last_n_days_stats<'a>(
+    collection: impl Into<AnkiCollection<'a>>,
+    days: i32,
+) -> Result<Vec<DayStats>> {
+    let ctx = db::AnkiContext::open(collection)?;
+    db::get_daily_stats(&ctx, DatePeriod::last_n_days(days)?)
+}
+
+/// Gets study time and learning progress for each day of the period
+#[cfg(feature = "sqlite")]
+pub fn get_days_stats<'a>(
+    collection: impl Into<AnkiCollection<'a>>,
+    period: DatePeriod,
+) -> Result<Vec<DayStats>> {
+    let ctx = db::AnkiContext::open(collection)?;
+    db::get_daily_stats(&ctx, period)
+}
+
+/// Gets study time and learning progress for each of the last `weeks` weeks
+#[cfg(feature = "sqlite")]
+pub fn get_last_n_weeks_stats<'a>(
+    collection: impl Into<AnkiCollection<'a>>,
+    weeks: i32,
+) -> Result<Vec<WeekStats>> {
+    let ctx = db::AnkiContext::open(collection)?;
+    db::get_weekly_stats(&ctx, DatePeriod::last_n_weeks(weeks)?)
 }
 
 /// Gets all Bible references from the database, sorted alphabetically
-pub fn get_bible_references(db_path: &str) -> Result<Vec<String>> {
-    let conn = db::open_database(db_path)?;
-    let deck_id = db::get_deck_id(&conn)?;
-    let model_id = db::get_model_id(&conn)?;
-    db::get_all_references(&conn, deck_id, model_id)
+#[cfg(feature = "sqlite")]
+pub fn get_bible_references<'a>(collection: impl Into<AnkiCollection<'a>>) -> Result<Vec<String>> {
+    let ctx = db::AnkiContext::open(collection)?;
+    db::get_all_references(&ctx)
+}
+
+/// Finds the references in the database that the verse or book name parser cannot handle,
+/// with the number of cards using each
+#[cfg(feature = "sqlite")]
+pub fn get_parse_warnings<'a>(
+    collection: impl Into<AnkiCollection<'a>>,
+) -> Result<Vec<ParseWarning>> {
+    let ctx = db::AnkiContext::open(collection)?;
+    let references = db::get_reference_card_counts(&ctx)?;
+    Ok(parse_warnings::find_parse_warnings(&references))
+}
+
+/// Gets the verse-level coverage of a book, or `None` if the book name is unknown
+#[cfg(feature = "sqlite")]
+pub fn get_book_coverage<'a>(
+    collection: impl Into<AnkiCollection<'a>>,
+    book: &str,
+) -> Result<Option<BookCoverage>> {
+    let Some(book) = bible::find_book(book) else {
+        return Ok(None);
+    };
+
+    let ctx = db::AnkiContext::open(collection)?;
+    let passages = db::get_passage_statuses(&ctx)?;
+
+    Ok(Some(coverage::book_coverage(book, &passages)))
+}
+
+/// Gets every passage with its first review and maturity dates, optionally only those of one
+/// book, or `None` if the book name is unknown
+#[cfg(feature = "sqlite")]
+pub fn get_passages<'a>(
+    collection: impl Into<AnkiCollection<'a>>,
+    book: Option<&str>,
+) -> Result<Option<Vec<PassageDetails>>> {
+    let book = match book.map(bible::find_book) {
+        Some(None) => return Ok(None),
+        Some(Some(book)) => Some(book),
+        None => None,
+    };
+
+    let ctx = db::AnkiContext::open(collection)?;
+    let passages = db::get_passage_details(&ctx)?;
+
+    Ok(Some(match book {
+        Some(book) => passages
+            .into_iter()
+            .filter(|passage| {
+                book_name_parser::try_parse_book_name(&passage.reference)
+                    .ok()
+                    .as_deref()
+                    == Some(book)
+            })
+            .collect(),
+        None => passages,
+    }))
+}
+
+/// Gets the verse-level coverage of every book with at least one parsable passage
+#[cfg(feature = "sqlite")]
+pub fn get_books_coverage<'a>(
+    collection: impl Into<AnkiCollection<'a>>,
+) -> Result<std::collections::HashMap<&'static str, BookCoverage>> {
+    let ctx = db::AnkiContext::open(collection)?;
+    let passages = db::get_passage_statuses(&ctx)?;
+
+    Ok(coverage::books_coverage(&passages))
+}
+
+/// Gets the scheduling settings of the Bible deck's options group
+#[cfg(feature = "sqlite")]
+pub fn get_deck_settings<'a>(collection: impl Into<AnkiCollection<'a>>) -> Result<DeckSettings> {
+    let ctx = db::AnkiContext::open(collection)?;
+    db::get_deck_settings(&ctx)
+}
+
+/// Gets the earliest and latest review timestamps for the Bible deck, plus the
+/// database file's last-modified time
+#[cfg(feature = "sqlite")]
+pub fn get_data_range<'a>(collection: impl Into<AnkiCollection<'a>>) -> Result<DataRange> {
+    let collection = collection.into();
+    let ctx = db::AnkiContext::open(collection)?;
+    let (earliest_ms, latest_ms) = db::get_review_range(&ctx)?;
+    DataRange::new(earliest_ms, latest_ms, collection.path)
 }
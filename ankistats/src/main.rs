@@ -1,17 +1,39 @@
-use ankistats::models::{BookStats, BookStatsDisplay};
+use ankistats::config::{AnkiCollection, AnkiStatsConfig};
+use ankistats::models::{
+    AggregateStats, BookDelta, BookStats, BookStatsDisplay, ChapterStatsDisplay, DailyStats,
+    WeeklyStats,
+};
 use ankistats::{
-    get_bible_references, get_bible_stats, get_last_12_weeks_stats, get_last_30_days_stats,
-    get_today_study_time,
+    diff_collections, get_bible_references, get_bible_stats, get_last_12_weeks_stats,
+    get_last_30_days_stats, get_streaks, get_today_study_time,
 };
 use clap::{Parser, Subcommand};
+use statsutils::i18n::tr;
+use statsutils::{PartialPeriod, get_current_week_start, get_today_date};
 use std::process;
-use tabled::{Table, settings::Style};
+use tabled::{
+    Table,
+    settings::{Format, Style, object::Rows},
+};
 
 #[derive(Parser)]
 #[command(name = "anki-bible-stats")]
 #[command(about = "Analyze Anki flashcard databases for Bible verse memorization progress", long_about = None)]
 #[command(version)]
 struct Cli {
+    /// Fail instead of falling back when a Bible reference cannot be parsed
+    #[arg(long, global = true)]
+    strict: bool,
+
+    /// Deck holding the verse cards, with "::" separating the parts (default: ANKI_DECK_NAME
+    /// or "Bible::Verses")
+    #[arg(long, global = true, value_name = "NAME")]
+    deck: Option<String>,
+
+    /// Note type of the verse cards (default: ANKI_NOTE_TYPE or "Bible Verse")
+    #[arg(long, global = true, value_name = "NAME")]
+    note_type: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -24,6 +46,15 @@ enum Commands {
         #[arg(value_name = "DATABASE_PATH")]
         db_path: String,
     },
+    /// Show statistics for each chapter of a Bible book
+    Chapters {
+        /// Path to the Anki database file
+        #[arg(value_name = "DATABASE_PATH")]
+        db_path: String,
+        /// Book name, e.g. "Romans" or "psalms"
+        #[arg(value_name = "BOOK")]
+        book: String,
+    },
     /// Show study time for today
     Today {
         /// Path to the Anki database file
@@ -35,12 +66,33 @@ enum Commands {
         /// Path to the Anki database file
         #[arg(value_name = "DATABASE_PATH")]
         db_path: String,
+        /// List the references of the passages that matured or were lost
+        #[arg(short, long)]
+        verbose: bool,
+        /// Whether the summary counts today, which is not over yet (include or exclude)
+        #[arg(long, default_value_t = PartialPeriod::Exclude)]
+        partial: PartialPeriod,
     },
     /// Show study time for each of the last 12 weeks
     Weekly {
         /// Path to the Anki database file
         #[arg(value_name = "DATABASE_PATH")]
         db_path: String,
+        /// List the references of the passages that matured or were lost
+        #[arg(short, long)]
+        verbose: bool,
+        /// Whether the summary counts this week, which is not over yet (include or exclude)
+        #[arg(long, default_value_t = PartialPeriod::Exclude)]
+        partial: PartialPeriod,
+    },
+    /// Show the current and longest study streaks over the whole review history
+    Streak {
+        /// Path to the Anki database file
+        #[arg(value_name = "DATABASE_PATH")]
+        db_path: String,
+        /// Number of most recent streaks to list
+        #[arg(short, long, default_value_t = 10)]
+        limit: usize,
     },
     /// List all Bible references in the database
     Refs {
@@ -48,87 +100,124 @@ enum Commands {
         #[arg(value_name = "DATABASE_PATH")]
         db_path: String,
     },
+    /// Show the per-book progress between two collection files (e.g. monthly .colpkg backups)
+    Diff {
+        /// Path to the older Anki database or export file
+        #[arg(value_name = "OLD_PATH")]
+        old_path: String,
+        /// Path to the newer Anki database or export file
+        #[arg(value_name = "NEW_PATH")]
+        new_path: String,
+    },
 }
 
 fn main() {
     let cli = Cli::parse();
 
+    // Print parser warnings (e.g. unparsable references) to stderr
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .without_time()
+        .init();
+
+    ankistats::parse_warnings::set_strict_references(cli.strict);
+
+    let mut config = AnkiStatsConfig::from_env();
+    if let Some(deck) = cli.deck {
+        config.deck_name = deck;
+    }
+    if let Some(note_type) = cli.note_type {
+        config.note_type = note_type;
+    }
+
+    match statsutils::i18n::locale_from_env() {
+        Ok(locale) => statsutils::i18n::set_locale(locale),
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            process::exit(1);
+        }
+    }
+
+    let collection = |path| AnkiCollection::new(path).with_config(&config);
     match cli.command {
         Commands::Books { db_path } => {
-            run_books_command(&db_path);
+            run_books_command(collection(&db_path));
+        }
+        Commands::Chapters { db_path, book } => {
+            run_chapters_command(collection(&db_path), &book);
         }
         Commands::Today { db_path } => {
-            run_today_command(&db_path);
+            run_today_command(collection(&db_path));
+        }
+        Commands::Daily {
+            db_path,
+            verbose,
+            partial,
+        } => {
+            run_daily_command(collection(&db_path), verbose, partial);
         }
-        Commands::Daily { db_path } => {
-            run_daily_command(&db_path);
+        Commands::Weekly {
+            db_path,
+            verbose,
+            partial,
+        } => {
+            run_weekly_command(collection(&db_path), verbose, partial);
         }
-        Commands::Weekly { db_path } => {
-            run_weekly_command(&db_path);
+        Commands::Streak { db_path, limit } => {
+            run_streak_command(collection(&db_path), limit);
         }
         Commands::Refs { db_path } => {
-            run_refs_command(&db_path);
+            run_refs_command(collection(&db_path));
+        }
+        Commands::Diff { old_path, new_path } => {
+            run_diff_command(collection(&old_path), collection(&new_path));
         }
     }
 }
 
-fn run_books_command(db_path: &str) {
-    match get_bible_stats(db_path) {
+fn run_books_command(collection: AnkiCollection) {
+    match get_bible_stats(collection) {
         Ok(stats) => {
-            println!("\n=== OLD TESTAMENT ===\n");
+            println!("\n=== {} ===\n", tr("OLD TESTAMENT"));
             print_book_stats(&stats.old_testament.book_stats);
-            println!(
-                "\nOT Passages: Mature={}, Young={}, Unseen={}, Suspended={}, Total={}",
-                stats.old_testament.mature_passages,
-                stats.old_testament.young_passages,
-                stats.old_testament.unseen_passages,
-                stats.old_testament.suspended_passages,
-                stats.old_testament.total_passages()
-            );
-            println!(
-                "OT Verses:   Mature={}, Young={}, Unseen={}, Suspended={}, Total={}",
-                stats.old_testament.mature_verses,
-                stats.old_testament.young_verses,
-                stats.old_testament.unseen_verses,
-                stats.old_testament.suspended_verses,
-                stats.old_testament.total_verses()
-            );
+            println!();
+            print_testament_counts(tr("OT"), &stats.old_testament);
 
-            println!("\n\n=== NEW TESTAMENT ===\n");
+            println!("\n\n=== {} ===\n", tr("NEW TESTAMENT"));
             print_book_stats(&stats.new_testament.book_stats);
-            println!(
-                "\nNT Passages: Mature={}, Young={}, Unseen={}, Suspended={}, Total={}",
-                stats.new_testament.mature_passages,
-                stats.new_testament.young_passages,
-                stats.new_testament.unseen_passages,
-                stats.new_testament.suspended_passages,
-                stats.new_testament.total_passages()
-            );
-            println!(
-                "NT Verses:   Mature={}, Young={}, Unseen={}, Suspended={}, Total={}",
-                stats.new_testament.mature_verses,
-                stats.new_testament.young_verses,
-                stats.new_testament.unseen_verses,
-                stats.new_testament.suspended_verses,
-                stats.new_testament.total_verses()
-            );
+            println!();
+            print_testament_counts(tr("NT"), &stats.new_testament);
 
-            println!("\n\n=== GRAND TOTAL ===");
-            println!(
-                "Passages: Mature={}, Young={}, Unseen={}, Suspended={}, Total={}",
-                stats.total_mature_passages(),
-                stats.total_young_passages(),
-                stats.total_unseen_passages(),
-                stats.total_suspended_passages(),
-                stats.total_passages()
+            println!("\n\n=== {} ===", tr("GRAND TOTAL"));
+            print_counts(
+                tr("Passages"),
+                [
+                    stats.total_mature_passages(),
+                    stats.total_young_passages(),
+                    stats.total_unseen_passages(),
+                    stats.total_suspended_passages(),
+                    stats.total_passages(),
+                ],
             );
-            println!(
-                "Verses:   Mature={}, Young={}, Unseen={}, Suspended={}, Total={}",
-                stats.total_mature_verses(),
-                stats.total_young_verses(),
-                stats.total_unseen_verses(),
-                stats.total_suspended_verses(),
-                stats.total_verses()
+            print_counts(
+                tr("Verses"),
+                [
+                    stats.total_mature_verses(),
+                    stats.total_young_verses(),
+                    stats.total_unseen_verses(),
+                    stats.total_suspended_verses(),
+                    stats.total_verses(),
+                ],
+            );
+            print_counts(
+                tr("Unique"),
+                [
+                    stats.total_unique_mature_verses(),
+                    stats.total_unique_young_verses(),
+                    stats.total_unique_unseen_verses(),
+                    stats.total_unique_suspended_verses(),
+                    stats.total_unique_verses(),
+                ],
             );
         }
         Err(e) => {
@@ -138,21 +227,121 @@ fn run_books_command(db_path: &str) {
     }
 }
 
+/// Prints the passage, verse and unique verse counts of a testament
+fn print_testament_counts(prefix: &str, testament: &AggregateStats) {
+    print_counts(
+        &format!("{} {}", prefix, tr("Passages")),
+        [
+            testament.mature_passages,
+            testament.young_passages,
+            testament.unseen_passages,
+            testament.suspended_passages,
+            testament.total_passages(),
+        ],
+    );
+    print_counts(
+        &format!("{} {}", prefix, tr("Verses")),
+        [
+            testament.mature_verses,
+            testament.young_verses,
+            testament.unseen_verses,
+            testament.suspended_verses,
+            testament.total_verses(),
+        ],
+    );
+    print_counts(
+        &format!("{} {}", prefix, tr("Unique")),
+        [
+            testament.unique_mature_verses,
+            testament.unique_young_verses,
+            testament.unique_unseen_verses,
+            testament.unique_suspended_verses,
+            testament.total_unique_verses(),
+        ],
+    );
+}
+
+/// Prints one line of counts by card state, e.g. "Passages: Mature=1, Young=2, ..."
+fn print_counts(label: &str, [mature, young, unseen, suspended, total]: [i64; 5]) {
+    println!(
+        "{:<13}{}={}, {}={}, {}={}, {}={}, {}={}",
+        format!("{}:", label),
+        tr("Mature"),
+        mature,
+        tr("Young"),
+        young,
+        tr("Unseen"),
+        unseen,
+        tr("Suspended"),
+        suspended,
+        tr("Total"),
+        total
+    );
+}
+
 fn print_book_stats(book_stats: &[BookStats]) {
     let display_stats: Vec<BookStatsDisplay> = book_stats.iter().map(|s| s.into()).collect();
-    let table = Table::new(display_stats).with(Style::rounded()).to_string();
+    let table = Table::new(display_stats)
+        .with(Style::rounded())
+        .modify(
+            Rows::first(),
+            Format::content(|header| tr(header).to_string()),
+        )
+        .to_string();
     println!("{}", table);
-    println!("\n(Format: Passages / Verses)");
+    println!("\n{}", tr("(Format: Passages / Verses)"));
+}
+
+fn run_chapters_command(collection: AnkiCollection, book: &str) {
+    let Some(book) = ankistats::bible::find_book(book) else {
+        eprintln!("Error: {} '{}'", tr("Unknown book"), book);
+        process::exit(1);
+    };
+
+    match get_bible_stats(collection) {
+        Ok(stats) => {
+            let Some(book_stats) = stats
+                .old_testament
+                .book_stats
+                .iter()
+                .chain(&stats.new_testament.book_stats)
+                .find(|stats| stats.book == book)
+            else {
+                eprintln!("Error: {} {}", tr("No statistics for"), book);
+                process::exit(1);
+            };
+
+            println!("\n=== {} ===\n", book.to_uppercase());
+            let display_stats: Vec<ChapterStatsDisplay> =
+                book_stats.chapters.iter().map(|s| s.into()).collect();
+            let table = Table::new(display_stats)
+                .with(Style::rounded())
+                .modify(
+                    Rows::first(),
+                    Format::content(|header| tr(header).to_string()),
+                )
+                .to_string();
+            println!("{}", table);
+            println!("\n{}", tr("(Format: Passages / Verses)"));
+        }
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            process::exit(1);
+        }
+    }
 }
 
-fn run_today_command(db_path: &str) {
-    match get_today_study_time(db_path) {
+fn run_today_command(collection: AnkiCollection) {
+    match get_today_study_time(collection) {
         Ok(minutes) => {
-            println!("\n=== TODAY'S STUDY TIME ===\n");
+            println!("\n=== {} ===\n", tr("TODAY'S STUDY TIME"));
             println!(
-                "Total: {:.2} minutes ({:.1} hours)",
+                "{}: {:.2} {} ({:.1} {})",
+                tr("Total"),
                 minutes,
-                minutes / 60.0
+                tr("minutes"),
+                minutes / 60.0,
+                tr("hours")
             );
         }
         Err(e) => {
@@ -162,59 +351,80 @@ fn run_today_command(db_path: &str) {
     }
 }
 
-fn run_daily_command(db_path: &str) {
-    match get_last_30_days_stats(db_path) {
-        Ok(daily_stats) => {
-            println!("\n=== DAILY STATS - LAST 30 DAYS ===\n");
+fn run_daily_command(collection: AnkiCollection, verbose: bool, partial: PartialPeriod) {
+    match get_last_30_days_stats(collection).and_then(|stats| Ok((stats, get_today_date()?))) {
+        Ok((daily_stats, today)) => {
+            println!("\n=== {} ===\n", tr("DAILY STATS - LAST 30 DAYS"));
 
-            let total_minutes: f64 = daily_stats.iter().map(|d| d.minutes).sum();
-            let avg_minutes = total_minutes / daily_stats.len() as f64;
-            let total_matured: i64 = daily_stats.iter().map(|d| d.matured_passages).sum();
-            let total_lost: i64 = daily_stats.iter().map(|d| d.lost_passages).sum();
+            let daily_stats = DailyStats::new(daily_stats).with_partial_period(partial, &today);
+            let summary = &daily_stats.summary;
 
             // Print each day
-            for day in &daily_stats {
-                let hours = day.minutes / 60.0;
+            for day in &daily_stats.days {
+                let hours = day.time.minutes / 60.0;
                 let progress_str = if day.matured_passages > 0 || day.lost_passages > 0 {
-                    format!(
-                        " | Matured: {}, Lost: {}, Cumulative: {}",
-                        day.matured_passages, day.lost_passages, day.cumulative_passages
+                    format_progress(
+                        day.matured_passages,
+                        day.lost_passages,
+                        day.cumulative_passages,
                     )
                 } else if day.cumulative_passages != 0 {
-                    format!(" | Cumulative: {}", day.cumulative_passages)
+                    format!(" | {}: {}", tr("Cumulative"), day.cumulative_passages)
                 } else {
                     String::new()
                 };
 
-                if day.minutes > 0.0 || day.matured_passages > 0 || day.lost_passages > 0 {
+                if day.time.minutes > 0.0 || day.matured_passages > 0 || day.lost_passages > 0 {
                     println!(
-                        "{}: {:.2} min ({:.1} hrs){}",
-                        day.date, day.minutes, hours, progress_str
+                        "{}: {:.2} {} ({:.1} {}){}{}",
+                        day.time.date,
+                        day.time.minutes,
+                        tr("min"),
+                        hours,
+                        tr("hrs"),
+                        format_review_pace(day.avg_seconds_per_review),
+                        progress_str
                     );
                 } else {
-                    println!("{}: --- (no activity)", day.date);
+                    println!("{}: --- ({})", day.time.date, tr("no activity"));
+                }
+
+                if verbose {
+                    print_progress_references(&day.matured_references, &day.lost_references);
                 }
             }
 
-            println!("\n--- SUMMARY ---");
-            println!(
-                "Study Time: {:.2} minutes ({:.1} hours)",
-                total_minutes,
-                total_minutes / 60.0
+            println!("\n--- {} ---", tr("SUMMARY"));
+            print_minutes(tr("Study Time"), summary.total_minutes, summary.total_hours);
+            print_minutes(
+                tr("Average per day"),
+                summary.average_minutes_per_day,
+                summary.average_hours_per_day,
             );
+
+            if summary.total_reviews > 0 {
+                println!(
+                    "{}: {:.1} {} ({} {})",
+                    tr("Average per review"),
+                    summary.total_minutes * 60.0 / summary.total_reviews as f64,
+                    tr("seconds"),
+                    summary.total_reviews,
+                    tr("reviews")
+                );
+            }
+
             println!(
-                "Average per day: {:.2} minutes ({:.1} hours)",
-                avg_minutes,
-                avg_minutes / 60.0
+                "{}: {} {} {}",
+                tr("Days studied"),
+                summary.days_studied,
+                tr("out of"),
+                summary.total_days
             );
 
-            let days_studied = daily_stats.iter().filter(|d| d.minutes > 0.0).count();
-            println!("Days studied: {} out of 30", days_studied);
-
-            println!("\nProgress:");
-            println!("  Matured: {} passages", total_matured);
-            println!("  Lost: {} passages", total_lost);
-            println!("  Net: {} passages", total_matured - total_lost);
+            println!("\n{}:", tr("Progress"));
+            print_passages(tr("Matured"), summary.total_matured_passages);
+            print_passages(tr("Lost"), summary.total_lost_passages);
+            print_passages(tr("Net"), summary.net_progress);
         }
         Err(e) => {
             eprintln!("Error: {:#}", e);
@@ -223,59 +433,128 @@ fn run_daily_command(db_path: &str) {
     }
 }
 
-fn run_weekly_command(db_path: &str) {
-    match get_last_12_weeks_stats(db_path) {
-        Ok(weekly_stats) => {
-            println!("\n=== WEEKLY STATS - LAST 12 WEEKS ===\n");
+fn run_weekly_command(collection: AnkiCollection, verbose: bool, partial: PartialPeriod) {
+    match get_last_12_weeks_stats(collection)
+        .and_then(|stats| Ok((stats, get_current_week_start()?)))
+    {
+        Ok((weekly_stats, week_start)) => {
+            println!("\n=== {} ===\n", tr("WEEKLY STATS - LAST 12 WEEKS"));
 
-            let total_minutes: f64 = weekly_stats.iter().map(|w| w.minutes).sum();
-            let avg_minutes = total_minutes / weekly_stats.len() as f64;
-            let total_matured: i64 = weekly_stats.iter().map(|w| w.matured_passages).sum();
-            let total_lost: i64 = weekly_stats.iter().map(|w| w.lost_passages).sum();
+            let weekly_stats =
+                WeeklyStats::new(weekly_stats).with_partial_period(partial, &week_start);
+            let summary = &weekly_stats.summary;
 
             // Print each week
-            for week in &weekly_stats {
-                let hours = week.minutes / 60.0;
+            for week in &weekly_stats.weeks {
+                let hours = week.time.minutes / 60.0;
                 let progress_str = if week.matured_passages > 0 || week.lost_passages > 0 {
-                    format!(
-                        " | Matured: {}, Lost: {}, Cumulative: {}",
-                        week.matured_passages, week.lost_passages, week.cumulative_passages
+                    format_progress(
+                        week.matured_passages,
+                        week.lost_passages,
+                        week.cumulative_passages,
                     )
                 } else if week.cumulative_passages != 0 {
-                    format!(" | Cumulative: {}", week.cumulative_passages)
+                    format!(" | {}: {}", tr("Cumulative"), week.cumulative_passages)
                 } else {
                     String::new()
                 };
 
-                if week.minutes > 0.0 || week.matured_passages > 0 || week.lost_passages > 0 {
+                if week.time.minutes > 0.0 || week.matured_passages > 0 || week.lost_passages > 0 {
                     println!(
-                        "Week of {}: {:.2} min ({:.1} hrs){}",
-                        week.week_start, week.minutes, hours, progress_str
+                        "{} {}: {:.2} {} ({:.1} {}){}{}",
+                        tr("Week of"),
+                        week.time.week_start,
+                        week.time.minutes,
+                        tr("min"),
+                        hours,
+                        tr("hrs"),
+                        format_review_pace(week.avg_seconds_per_review),
+                        progress_str
                     );
                 } else {
-                    println!("Week of {}: --- (no activity)", week.week_start);
+                    println!(
+                        "{} {}: --- ({})",
+                        tr("Week of"),
+                        week.time.week_start,
+                        tr("no activity")
+                    );
+                }
+
+                if verbose {
+                    print_progress_references(&week.matured_references, &week.lost_references);
                 }
             }
 
-            println!("\n--- SUMMARY ---");
-            println!(
-                "Study Time: {:.2} minutes ({:.1} hours)",
-                total_minutes,
-                total_minutes / 60.0
+            println!("\n--- {} ---", tr("SUMMARY"));
+            print_minutes(tr("Study Time"), summary.total_minutes, summary.total_hours);
+            print_minutes(
+                tr("Average per week"),
+                summary.average_minutes_per_week,
+                summary.average_hours_per_week,
             );
+
+            if summary.total_reviews > 0 {
+                println!(
+                    "{}: {:.1} {} ({} {})",
+                    tr("Average per review"),
+                    summary.total_minutes * 60.0 / summary.total_reviews as f64,
+                    tr("seconds"),
+                    summary.total_reviews,
+                    tr("reviews")
+                );
+            }
+
             println!(
-                "Average per week: {:.2} minutes ({:.1} hours)",
-                avg_minutes,
-                avg_minutes / 60.0
+                "{}: {} {} {}",
+                tr("Weeks studied"),
+                summary.weeks_studied,
+                tr("out of"),
+                summary.total_weeks
             );
 
-            let weeks_studied = weekly_stats.iter().filter(|w| w.minutes > 0.0).count();
-            println!("Weeks studied: {} out of 12", weeks_studied);
+            println!("\n{}:", tr("Progress"));
+            print_passages(tr("Matured"), summary.total_matured_passages);
+            print_passages(tr("Lost"), summary.total_lost_passages);
+            print_passages(tr("Net"), summary.net_progress);
+        }
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn run_streak_command(collection: AnkiCollection, limit: usize) {
+    match get_streaks(collection) {
+        Ok(streaks) => {
+            println!("\n=== {} ===\n", tr("STUDY STREAKS"));
+            println!(
+                "{}: {} {}",
+                tr("Current streak"),
+                streaks.current_streak_days,
+                tr("days")
+            );
+            println!(
+                "{}: {} {}",
+                tr("Longest streak"),
+                streaks.longest_streak_days,
+                tr("days")
+            );
+            println!("{}: {}", tr("Days studied"), streaks.days_studied);
 
-            println!("\nProgress:");
-            println!("  Matured: {} passages", total_matured);
-            println!("  Lost: {} passages", total_lost);
-            println!("  Net: {} passages", total_matured - total_lost);
+            if !streaks.history.is_empty() {
+                println!("\n--- {} ---", tr("RECENT STREAKS"));
+                for streak in streaks.history.iter().rev().take(limit) {
+                    println!(
+                        "{} {} {}: {} {}",
+                        streak.start,
+                        tr("to"),
+                        streak.end,
+                        streak.days,
+                        tr("days")
+                    );
+                }
+            }
         }
         Err(e) => {
             eprintln!("Error: {:#}", e);
@@ -284,8 +563,8 @@ fn run_weekly_command(db_path: &str) {
     }
 }
 
-fn run_refs_command(db_path: &str) {
-    match get_bible_references(db_path) {
+fn run_refs_command(collection: AnkiCollection) {
+    match get_bible_references(collection) {
         Ok(references) => {
             for reference in references {
                 println!("{}", reference);
@@ -297,3 +576,99 @@ fn run_refs_command(db_path: &str) {
         }
     }
 }
+
+fn run_diff_command(old: AnkiCollection, new: AnkiCollection) {
+    match diff_collections(old, new) {
+        Ok(diff) => {
+            println!(
+                "\n=== {} {} {} {} ===\n",
+                tr("CHANGES FROM"),
+                old.path,
+                tr("TO"),
+                new.path
+            );
+
+            let changed: Vec<&BookDelta> = diff.books.iter().filter(|b| b.is_changed()).collect();
+            if changed.is_empty() {
+                println!("{}", tr("No changes"));
+                return;
+            }
+            for book in changed {
+                print_book_delta(book);
+            }
+            println!();
+            print_book_delta(&diff.total);
+        }
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Prints one line of passage and verse changes, e.g. "Genesis  Mature +2 (+5 verses), ..."
+fn print_book_delta(delta: &BookDelta) {
+    println!(
+        "{:<16} {} {:+} ({:+} {}), {} {:+} ({:+} {}), {} {:+} ({:+} {})",
+        delta.book,
+        tr("Mature"),
+        delta.mature_passages,
+        delta.mature_verses,
+        tr("verses"),
+        tr("Young"),
+        delta.young_passages,
+        delta.young_verses,
+        tr("verses"),
+        tr("Unseen"),
+        delta.unseen_passages,
+        delta.unseen_verses,
+        tr("verses")
+    );
+}
+
+/// Prints a labeled time in minutes and hours, e.g. "Study Time: 12.50 minutes (0.2 hours)"
+fn print_minutes(label: &str, minutes: f64, hours: f64) {
+    println!(
+        "{}: {:.2} {} ({:.1} {})",
+        label,
+        minutes,
+        tr("minutes"),
+        hours,
+        tr("hours")
+    );
+}
+
+/// Prints a labeled number of passages of the progress summary
+fn print_passages(label: &str, passages: i64) {
+    println!("  {}: {} {}", label, passages, tr("passages"));
+}
+
+/// Formats the matured, lost and cumulative passages of a day or week
+fn format_progress(matured: i64, lost: i64, cumulative: i64) -> String {
+    format!(
+        " | {}: {}, {}: {}, {}: {}",
+        tr("Matured"),
+        matured,
+        tr("Lost"),
+        lost,
+        tr("Cumulative"),
+        cumulative
+    )
+}
+
+/// Formats the average time per review, or nothing without reviews
+fn format_review_pace(avg_seconds_per_review: Option<f64>) -> String {
+    avg_seconds_per_review
+        .map(|seconds| format!(", {:.1} {}", seconds, tr("s/review")))
+        .unwrap_or_default()
+}
+
+/// Prints the references behind the matured/lost counts of a day or week
+fn print_progress_references(matured: &[String], lost: &[String]) {
+    if !matured.is_empty() {
+        println!("    + {}", matured.join(", "));
+    }
+    if !lost.is_empty() {
+        println!("    - {}", lost.join(", "));
+    }
+}
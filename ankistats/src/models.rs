@@ -1,9 +1,15 @@
 use serde::Serialize;
+use statsutils::PartialPeriod;
 use tabled::Tabled;
-use utoipa::ToSchema;
+
+pub use statsmodels::ErrorResponse;
+
+use crate::bible::chapter_count;
+use crate::reference::Reference;
 
 /// Statistics for a single Bible book
-#[derive(Debug, Clone, Serialize, ToSchema)]
+#[derive(Debug, Clone, Default, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct BookStats {
     pub book: String,
     pub mature_passages: i64,
@@ -14,6 +20,91 @@ pub struct BookStats {
     pub young_verses: i64,
     pub unseen_verses: i64,
     pub suspended_verses: i64,
+    /// Verses covered by at least one card, each counted once under the most learned status
+    /// of the cards covering it (overlapping cards don't inflate these)
+    pub unique_mature_verses: i64,
+    pub unique_young_verses: i64,
+    pub unique_unseen_verses: i64,
+    pub unique_suspended_verses: i64,
+    /// Every chapter of the book in order, filled in by `with_unique_verses()`
+    pub chapters: Vec<ChapterStats>,
+}
+
+/// Statistics for a single chapter of a Bible book
+///
+/// Passages are counted in the chapter their reference names; `parse_reference` only accepts
+/// references within one chapter.
+#[derive(Debug, Clone, Default, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ChapterStats {
+    pub chapter: i64,
+    pub mature_passages: i64,
+    pub young_passages: i64,
+    pub unseen_passages: i64,
+    pub suspended_passages: i64,
+    pub mature_verses: i64,
+    pub young_verses: i64,
+    pub unseen_verses: i64,
+    pub suspended_verses: i64,
+    pub unique_mature_verses: i64,
+    pub unique_young_verses: i64,
+    pub unique_unseen_verses: i64,
+    pub unique_suspended_verses: i64,
+}
+
+impl ChapterStats {
+    pub fn total_passages(&self) -> i64 {
+        self.mature_passages + self.young_passages + self.unseen_passages + self.suspended_passages
+    }
+
+    /// Fills in the unique verse counts from the chapter's verse-level coverage
+    fn with_unique_verses(mut self, coverage: Option<&ChapterCoverage>) -> Self {
+        if let Some(coverage) = coverage {
+            self.unique_mature_verses = coverage.verse_count(VerseStatus::Mature);
+            self.unique_young_verses = coverage.verse_count(VerseStatus::Young);
+            self.unique_unseen_verses = coverage.verse_count(VerseStatus::Unseen);
+            self.unique_suspended_verses = coverage.verse_count(VerseStatus::Suspended);
+        }
+        self
+    }
+}
+
+/// Display wrapper for ChapterStats that formats passages and verses as "P / V"
+#[derive(Debug, Clone, Tabled)]
+pub struct ChapterStatsDisplay {
+    #[tabled(rename = "Chapter")]
+    pub chapter: i64,
+
+    #[tabled(rename = "Mature")]
+    pub mature: String,
+
+    #[tabled(rename = "Young")]
+    pub young: String,
+
+    #[tabled(rename = "Unseen")]
+    pub unseen: String,
+
+    #[tabled(rename = "Suspended")]
+    pub suspended: String,
+
+    #[tabled(rename = "Unique Verses")]
+    pub unique_verses: i64,
+}
+
+impl From<&ChapterStats> for ChapterStatsDisplay {
+    fn from(stats: &ChapterStats) -> Self {
+        Self {
+            chapter: stats.chapter,
+            mature: format!("{} / {}", stats.mature_passages, stats.mature_verses),
+            young: format!("{} / {}", stats.young_passages, stats.young_verses),
+            unseen: format!("{} / {}", stats.unseen_passages, stats.unseen_verses),
+            suspended: format!("{} / {}", stats.suspended_passages, stats.suspended_verses),
+            unique_verses: stats.unique_mature_verses
+                + stats.unique_young_verses
+                + stats.unique_unseen_verses
+                + stats.unique_suspended_verses,
+        }
+    }
 }
 
 /// Display wrapper for BookStats that formats passages and verses as "P / V"
@@ -33,6 +124,9 @@ pub struct BookStatsDisplay {
 
     #[tabled(rename = "Suspended")]
     pub suspended: String,
+
+    #[tabled(rename = "Unique Verses")]
+    pub unique_verses: i64,
 }
 
 impl From<&BookStats> for BookStatsDisplay {
@@ -43,6 +137,7 @@ impl From<&BookStats> for BookStatsDisplay {
             young: format!("{} / {}", stats.young_passages, stats.young_verses),
             unseen: format!("{} / {}", stats.unseen_passages, stats.unseen_verses),
             suspended: format!("{} / {}", stats.suspended_passages, stats.suspended_verses),
+            unique_verses: stats.total_unique_verses(),
         }
     }
 }
@@ -55,10 +150,57 @@ impl BookStats {
     pub fn total_verses(&self) -> i64 {
         self.mature_verses + self.young_verses + self.unseen_verses + self.suspended_verses
     }
+
+    pub fn total_unique_verses(&self) -> i64 {
+        self.unique_mature_verses
+            + self.unique_young_verses
+            + self.unique_unseen_verses
+            + self.unique_suspended_verses
+    }
+
+    /// Fills in the unique verse counts from the book's verse-level coverage, and lists every
+    /// chapter of the book, including the ones without any passage
+    ///
+    /// Chapters past the book's last one are left out (they are listed as parse warnings);
+    /// for a book without a known chapter count, every chapter up to the last with passages
+    /// is listed.
+    pub fn with_unique_verses(mut self, coverage: Option<&BookCoverage>) -> Self {
+        if let Some(coverage) = coverage {
+            self.unique_mature_verses = coverage.verse_count(VerseStatus::Mature);
+            self.unique_young_verses = coverage.verse_count(VerseStatus::Young);
+            self.unique_unseen_verses = coverage.verse_count(VerseStatus::Unseen);
+            self.unique_suspended_verses = coverage.verse_count(VerseStatus::Suspended);
+        }
+
+        let last_chapter = chapter_count(&self.book).unwrap_or_else(|| {
+            self.chapters
+                .iter()
+                .map(|chapter| chapter.chapter)
+                .max()
+                .unwrap_or(0)
+        });
+        let mut counted = std::mem::take(&mut self.chapters).into_iter().peekable();
+        self.chapters = (1..=last_chapter)
+            .map(|chapter| {
+                let stats =
+                    counted
+                        .next_if(|stats| stats.chapter == chapter)
+                        .unwrap_or(ChapterStats {
+                            chapter,
+                            ..ChapterStats::default()
+                        });
+                let chapter_coverage =
+                    coverage.and_then(|coverage| coverage.chapters.get(chapter as usize - 1));
+                stats.with_unique_verses(chapter_coverage)
+            })
+            .collect();
+        self
+    }
 }
 
 /// Aggregated statistics for a collection of books
-#[derive(Debug, Clone, Serialize, ToSchema)]
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct AggregateStats {
     pub label: String,
     pub mature_passages: i64,
@@ -69,6 +211,10 @@ pub struct AggregateStats {
     pub young_verses: i64,
     pub unseen_verses: i64,
     pub suspended_verses: i64,
+    pub unique_mature_verses: i64,
+    pub unique_young_verses: i64,
+    pub unique_unseen_verses: i64,
+    pub unique_suspended_verses: i64,
     pub book_stats: Vec<BookStats>,
 }
 
@@ -84,6 +230,10 @@ impl AggregateStats {
             young_verses: 0,
             unseen_verses: 0,
             suspended_verses: 0,
+            unique_mature_verses: 0,
+            unique_young_verses: 0,
+            unique_unseen_verses: 0,
+            unique_suspended_verses: 0,
             book_stats: Vec::new(),
         }
     }
@@ -97,6 +247,10 @@ impl AggregateStats {
         self.young_verses += stats.young_verses;
         self.unseen_verses += stats.unseen_verses;
         self.suspended_verses += stats.suspended_verses;
+        self.unique_mature_verses += stats.unique_mature_verses;
+        self.unique_young_verses += stats.unique_young_verses;
+        self.unique_unseen_verses += stats.unique_unseen_verses;
+        self.unique_suspended_verses += stats.unique_suspended_verses;
         self.book_stats.push(stats);
     }
 
@@ -107,10 +261,18 @@ impl AggregateStats {
     pub fn total_verses(&self) -> i64 {
         self.mature_verses + self.young_verses + self.unseen_verses + self.suspended_verses
     }
+
+    pub fn total_unique_verses(&self) -> i64 {
+        self.unique_mature_verses
+            + self.unique_young_verses
+            + self.unique_unseen_verses
+            + self.unique_suspended_verses
+    }
 }
 
 /// Complete Bible statistics report
-#[derive(Debug, Serialize, ToSchema)]
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct BibleStats {
     pub old_testament: AggregateStats,
     pub new_testament: AggregateStats,
@@ -163,6 +325,26 @@ impl BibleStats {
     pub fn total_verses(&self) -> i64 {
         self.old_testament.total_verses() + self.new_testament.total_verses()
     }
+
+    pub fn total_unique_mature_verses(&self) -> i64 {
+        self.old_testament.unique_mature_verses + self.new_testament.unique_mature_verses
+    }
+
+    pub fn total_unique_young_verses(&self) -> i64 {
+        self.old_testament.unique_young_verses + self.new_testament.unique_young_verses
+    }
+
+    pub fn total_unique_unseen_verses(&self) -> i64 {
+        self.old_testament.unique_unseen_verses + self.new_testament.unique_unseen_verses
+    }
+
+    pub fn total_unique_suspended_verses(&self) -> i64 {
+        self.old_testament.unique_suspended_verses + self.new_testament.unique_suspended_verses
+    }
+
+    pub fn total_unique_verses(&self) -> i64 {
+        self.old_testament.total_unique_verses() + self.new_testament.total_unique_verses()
+    }
 }
 
 impl Default for BibleStats {
@@ -171,23 +353,71 @@ impl Default for BibleStats {
     }
 }
 
+/// Change in a book's (or the whole Bible's) passages and verses between two collections
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BookDelta {
+    pub book: String,
+    pub mature_passages: i64,
+    pub young_passages: i64,
+    pub unseen_passages: i64,
+    pub mature_verses: i64,
+    pub young_verses: i64,
+    pub unseen_verses: i64,
+}
+
+impl BookDelta {
+    /// Whether anything changed
+    pub fn is_changed(&self) -> bool {
+        *self
+            != BookDelta {
+                book: self.book.clone(),
+                ..Default::default()
+            }
+    }
+}
+
+/// Per-book changes between an older and a newer collection, in canonical book order
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CollectionDiff {
+    pub books: Vec<BookDelta>,
+    /// Sum over all books
+    pub total: BookDelta,
+}
+
 /// Study time and progress statistics for a single day
-#[derive(Debug, Clone, Serialize, ToSchema)]
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct DayStats {
-    pub date: String,
-    pub minutes: f64,
+    /// Date and study time, shared with the other sources' daily series
+    #[serde(flatten)]
+    pub time: statsmodels::DayStats,
     pub matured_passages: i64,
     pub lost_passages: i64,
     pub cumulative_passages: i64,
+    /// Number of reviews
+    pub reviews: i64,
+    /// Average time per review in seconds (`None` without reviews)
+    pub avg_seconds_per_review: Option<f64>,
+    /// Fraction of answers to cards in review that were not "Again" (`None` without such
+    /// answers), see `RetentionStats`
+    pub retention: Option<f64>,
+    /// References of the passages that matured (at most `db::MAX_PROGRESS_REFERENCES`)
+    pub matured_references: Vec<String>,
+    /// References of the passages that were lost (at most `db::MAX_PROGRESS_REFERENCES`)
+    pub lost_references: Vec<String>,
 }
 
 /// Health check response
-#[derive(Debug, Clone, Serialize, ToSchema)]
+#[deprecated(note = "Health checks are answered by the backend, which has its own response")]
+#[derive(Debug, Clone, Serialize)]
 pub struct HealthCheck {
     pub status: String,
     pub service: String,
 }
 
+#[allow(deprecated)]
 impl HealthCheck {
     pub fn new() -> Self {
         Self {
@@ -197,6 +427,7 @@ impl HealthCheck {
     }
 }
 
+#[allow(deprecated)]
 impl Default for HealthCheck {
     fn default() -> Self {
         Self::new()
@@ -204,7 +435,8 @@ impl Default for HealthCheck {
 }
 
 /// Today's study time response
-#[derive(Debug, Clone, Serialize, ToSchema)]
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct TodayStats {
     pub minutes: f64,
     pub hours: f64,
@@ -220,7 +452,8 @@ impl TodayStats {
 }
 
 /// Summary statistics for daily study time and progress
-#[derive(Debug, Clone, Serialize, ToSchema)]
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct DailySummary {
     pub total_minutes: f64,
     pub total_hours: f64,
@@ -228,16 +461,19 @@ pub struct DailySummary {
     pub average_hours_per_day: f64,
     pub days_studied: usize,
     pub total_days: usize,
+    pub total_reviews: i64,
     pub total_matured_passages: i64,
     pub total_lost_passages: i64,
     pub net_progress: i64,
+    /// Whether the current, still running day is counted (`include`) or left out (`exclude`)
+    pub partial_period: PartialPeriod,
 }
 
 impl DailySummary {
     pub fn from_daily_stats(daily: &[DayStats]) -> Self {
-        let total_minutes: f64 = daily.iter().map(|d| d.minutes).sum();
+        let total_minutes: f64 = daily.iter().map(|d| d.time.minutes).sum();
         let avg_minutes = total_minutes / daily.len() as f64;
-        let days_studied = daily.iter().filter(|d| d.minutes > 0.0).count();
+        let days_studied = daily.iter().filter(|d| d.time.minutes > 0.0).count();
         let total_matured: i64 = daily.iter().map(|d| d.matured_passages).sum();
         let total_lost: i64 = daily.iter().map(|d| d.lost_passages).sum();
 
@@ -248,15 +484,18 @@ impl DailySummary {
             average_hours_per_day: avg_minutes / 60.0,
             days_studied,
             total_days: daily.len(),
+            total_reviews: daily.iter().map(|d| d.reviews).sum(),
             total_matured_passages: total_matured,
             total_lost_passages: total_lost,
             net_progress: total_matured - total_lost,
+            partial_period: PartialPeriod::Include,
         }
     }
 }
 
 /// Daily study time response with summary
-#[derive(Debug, Clone, Serialize, ToSchema)]
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct DailyStats {
     pub days: Vec<DayStats>,
     pub summary: DailySummary,
@@ -267,20 +506,44 @@ impl DailyStats {
         let summary = DailySummary::from_daily_stats(&days);
         Self { days, summary }
     }
+
+    /// Recomputes the summary, counting or leaving out the current day (`today`)
+    pub fn with_partial_period(mut self, partial_period: PartialPeriod, today: &str) -> Self {
+        let days = partial_period.complete(&self.days, |day| day.time.date == today);
+        self.summary = DailySummary {
+            partial_period,
+            ..DailySummary::from_daily_stats(days)
+        };
+        self
+    }
 }
 
 /// Study time and progress statistics for a single week
-#[derive(Debug, Clone, Serialize, ToSchema)]
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct WeekStats {
-    pub week_start: String,
-    pub minutes: f64,
+    /// Week start and study time, shared with the other sources' weekly series
+    #[serde(flatten)]
+    pub time: statsmodels::WeekStats,
     pub matured_passages: i64,
     pub lost_passages: i64,
     pub cumulative_passages: i64,
+    /// Number of reviews
+    pub reviews: i64,
+    /// Average time per review in seconds (`None` without reviews)
+    pub avg_seconds_per_review: Option<f64>,
+    /// Fraction of answers to cards in review that were not "Again" (`None` without such
+    /// answers), see `RetentionStats`
+    pub retention: Option<f64>,
+    /// References of the passages that matured (at most `db::MAX_PROGRESS_REFERENCES`)
+    pub matured_references: Vec<String>,
+    /// References of the passages that were lost (at most `db::MAX_PROGRESS_REFERENCES`)
+    pub lost_references: Vec<String>,
 }
 
 /// Summary statistics for weekly study time and progress
-#[derive(Debug, Clone, Serialize, ToSchema)]
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct WeeklySummary {
     pub total_minutes: f64,
     pub total_hours: f64,
@@ -288,16 +551,19 @@ pub struct WeeklySummary {
     pub average_hours_per_week: f64,
     pub weeks_studied: usize,
     pub total_weeks: usize,
+    pub total_reviews: i64,
     pub total_matured_passages: i64,
     pub total_lost_passages: i64,
     pub net_progress: i64,
+    /// Whether the current, still running week is counted (`include`) or left out (`exclude`)
+    pub partial_period: PartialPeriod,
 }
 
 impl WeeklySummary {
     pub fn from_weekly_stats(weekly: &[WeekStats]) -> Self {
-        let total_minutes: f64 = weekly.iter().map(|w| w.minutes).sum();
+        let total_minutes: f64 = weekly.iter().map(|w| w.time.minutes).sum();
         let avg_minutes = total_minutes / weekly.len() as f64;
-        let weeks_studied = weekly.iter().filter(|w| w.minutes > 0.0).count();
+        let weeks_studied = weekly.iter().filter(|w| w.time.minutes > 0.0).count();
         let total_matured: i64 = weekly.iter().map(|w| w.matured_passages).sum();
         let total_lost: i64 = weekly.iter().map(|w| w.lost_passages).sum();
 
@@ -308,15 +574,18 @@ impl WeeklySummary {
             average_hours_per_week: avg_minutes / 60.0,
             weeks_studied,
             total_weeks: weekly.len(),
+            total_reviews: weekly.iter().map(|w| w.reviews).sum(),
             total_matured_passages: total_matured,
             total_lost_passages: total_lost,
             net_progress: total_matured - total_lost,
+            partial_period: PartialPeriod::Include,
         }
     }
 }
 
 /// Weekly study time response with summary
-#[derive(Debug, Clone, Serialize, ToSchema)]
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct WeeklyStats {
     pub weeks: Vec<WeekStats>,
     pub summary: WeeklySummary,
@@ -327,16 +596,511 @@ impl WeeklyStats {
         let summary = WeeklySummary::from_weekly_stats(&weeks);
         Self { weeks, summary }
     }
+
+    /// Recomputes the summary, counting or leaving out the current week (`week_start`)
+    pub fn with_partial_period(mut self, partial_period: PartialPeriod, week_start: &str) -> Self {
+        let weeks = partial_period.complete(&self.weeks, |week| week.time.week_start == week_start);
+        self.summary = WeeklySummary {
+            partial_period,
+            ..WeeklySummary::from_weekly_stats(weeks)
+        };
+        self
+    }
 }
 
-/// Error response
-#[derive(Debug, Clone, Serialize, ToSchema)]
-pub struct ErrorResponse {
+/// Parser that fell back to a default for an unparsable reference
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ParseWarningKind {
+    /// The verse count could not be determined
+    VerseCount,
+    /// The book name could not be determined
+    BookName,
+    /// The chapter is past the book's last chapter
+    Chapter,
+}
+
+impl ParseWarningKind {
+    /// What happens to a card whose reference fails this parser
+    pub fn fallback(&self) -> &'static str {
+        match self {
+            ParseWarningKind::VerseCount => "counted as 1 verse",
+            ParseWarningKind::BookName => "left out of book statistics",
+            ParseWarningKind::Chapter => "left out of chapter statistics",
+        }
+    }
+}
+
+/// A card reference that one of the parsers cannot handle
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ParseWarning {
+    /// Reference as written in the card's sort field
+    pub reference: String,
+    pub kind: ParseWarningKind,
+    /// Parser error message
     pub error: String,
+    /// What the stats do with the card instead (e.g. "counted as 1 verse")
+    pub fallback: String,
+    /// Number of cards with this reference
+    pub cards: i64,
+}
+
+impl ParseWarning {
+    pub fn new(reference: &str, kind: ParseWarningKind, error: String, cards: i64) -> Self {
+        Self {
+            reference: reference.to_string(),
+            kind,
+            error,
+            fallback: kind.fallback().to_string(),
+            cards,
+        }
+    }
+}
+
+/// A reference converted to its canonical "Book C:V–V" form
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct NormalizedReference {
+    /// Reference as given
+    pub input: String,
+    /// Canonical form, e.g. "Romans 5:1–8"
+    pub reference: String,
+    /// Book name as used in the book statistics
+    pub book: String,
+    pub chapter: i64,
+    /// Number of verses in the reference
+    pub verses: i64,
 }
 
-impl ErrorResponse {
-    pub fn new(error: String) -> Self {
-        Self { error }
+impl NormalizedReference {
+    pub fn new(input: &str, reference: &Reference) -> Self {
+        Self {
+            input: input.to_string(),
+            reference: reference.to_string(),
+            book: reference.book.to_string(),
+            chapter: reference.chapter,
+            verses: reference.verse_count(),
+        }
+    }
+}
+
+/// Learning state of a passage card (both of its cards together)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum PassageStatus {
+    Mature,
+    Young,
+    Unseen,
+    Suspended,
+}
+
+/// A passage with its learning history from the review log
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PassageDetails {
+    pub reference: String,
+    pub status: PassageStatus,
+    /// Date of the first review of either card in YYYY-MM-DD format (`null` if never reviewed)
+    pub first_review_date: Option<String>,
+    /// Date in YYYY-MM-DD format on which both cards had first reached an interval of 21 days
+    /// (`null` if the passage never matured); stays set if the passage relapsed since
+    pub matured_date: Option<String>,
+}
+
+/// Coverage state of a single verse, ordered from least to most learned
+///
+/// A verse covered by several passages takes the most learned state among them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum VerseStatus {
+    /// No card covers the verse
+    Uncovered,
+    Suspended,
+    Unseen,
+    Young,
+    Mature,
+}
+
+impl From<PassageStatus> for VerseStatus {
+    fn from(status: PassageStatus) -> Self {
+        match status {
+            PassageStatus::Mature => VerseStatus::Mature,
+            PassageStatus::Young => VerseStatus::Young,
+            PassageStatus::Unseen => VerseStatus::Unseen,
+            PassageStatus::Suspended => VerseStatus::Suspended,
+        }
+    }
+}
+
+/// Verse-level coverage of one chapter
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ChapterCoverage {
+    pub chapter: i64,
+    /// Number of verses in the chapter (KJV versification), for drawing the verses past the
+    /// highest covered one; 0 for a chapter the book does not have
+    pub total_verses: i64,
+    /// Status of each verse, starting at verse 1 and ending at the highest covered verse
+    /// (empty when no card covers the chapter)
+    pub verses: Vec<VerseStatus>,
+}
+
+/// Verse-level coverage of one book, for "colored Bible page" visualizations
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BookCoverage {
+    pub book: String,
+    /// Every chapter of the book in order
+    pub chapters: Vec<ChapterCoverage>,
+}
+
+impl ChapterCoverage {
+    /// Number of verses with the given status
+    pub fn verse_count(&self, status: VerseStatus) -> i64 {
+        self.verses.iter().filter(|&&verse| verse == status).count() as i64
+    }
+}
+
+impl BookCoverage {
+    /// Number of verses with the given status
+    pub fn verse_count(&self, status: VerseStatus) -> i64 {
+        self.chapters
+            .iter()
+            .map(|chapter| chapter.verse_count(status))
+            .sum()
+    }
+}
+
+/// Number of passages of a given length range, by status
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SpanBucketStats {
+    /// Human-readable length range, e.g. "2–3 verses"
+    pub label: String,
+    pub min_verses: i64,
+    /// Upper bound of the range (`None` for the open-ended last bucket)
+    pub max_verses: Option<i64>,
+    pub mature_passages: i64,
+    pub young_passages: i64,
+    pub unseen_passages: i64,
+    pub suspended_passages: i64,
+}
+
+impl SpanBucketStats {
+    pub fn total_passages(&self) -> i64 {
+        self.mature_passages + self.young_passages + self.unseen_passages + self.suspended_passages
+    }
+}
+
+/// How many matured passages of a book dropped back below 21 days soon after
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BookRelapseStats {
+    pub book: String,
+    /// Number of times a passage matured (at least the relapse window ago)
+    pub matured_passages: i64,
+    /// How many of those dropped below 21 days within the relapse window
+    pub relapsed_passages: i64,
+    /// relapsed_passages / matured_passages (0 when nothing matured)
+    pub relapse_rate: f64,
+}
+
+impl BookRelapseStats {
+    pub fn new(book: String, matured_passages: i64, relapsed_passages: i64) -> Self {
+        let relapse_rate = if matured_passages > 0 {
+            relapsed_passages as f64 / matured_passages as f64
+        } else {
+            0.0
+        };
+        Self {
+            book,
+            matured_passages,
+            relapsed_passages,
+            relapse_rate,
+        }
+    }
+}
+
+/// Relapse rates after maturity, per book and overall
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RelapseStats {
+    /// Days after maturing in which a drop below 21 days counts as a relapse
+    pub window_days: i64,
+    /// Books with at least one counted maturation, in canonical order
+    pub books: Vec<BookRelapseStats>,
+    /// Totals over all books (book "All")
+    pub total: BookRelapseStats,
+}
+
+/// Recall of the cards in review over some days, from their answers
+///
+/// Only answers to cards in review count: "Again" fails the review, any other answer passes
+/// it. Answers in the learning and relearning steps are left out.
+#[derive(Debug, Clone, Default, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Retention {
+    pub passed_reviews: i64,
+    pub failed_reviews: i64,
+    /// passed_reviews / (passed_reviews + failed_reviews), `None` without answers
+    pub retention: Option<f64>,
+}
+
+impl Retention {
+    pub fn new(passed_reviews: i64, failed_reviews: i64) -> Self {
+        let answers = passed_reviews + failed_reviews;
+        Self {
+            passed_reviews,
+            failed_reviews,
+            retention: (answers > 0).then(|| passed_reviews as f64 / answers as f64),
+        }
+    }
+}
+
+/// Retention of the cards in review on a single day
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DayRetention {
+    /// Date in YYYY-MM-DD format
+    pub date: String,
+    #[serde(flatten)]
+    pub retention: Retention,
+}
+
+/// Retention of the cards in review in a single week
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct WeekRetention {
+    /// Sunday starting the week, in YYYY-MM-DD format
+    pub week_start: String,
+    #[serde(flatten)]
+    pub retention: Retention,
+}
+
+/// Retention of the cards in review of one book
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BookRetention {
+    pub book: String,
+    #[serde(flatten)]
+    pub retention: Retention,
+}
+
+/// Retention of the cards in review per day, week and book
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RetentionStats {
+    /// Days oldest first, including days without answers
+    pub daily: Vec<DayRetention>,
+    /// Weeks oldest first, including weeks without answers
+    pub weekly: Vec<WeekRetention>,
+    /// Books answered on the days of `daily`, in canonical order
+    pub books: Vec<BookRetention>,
+    /// Totals over the days of `daily` (book "All")
+    pub total: BookRetention,
+}
+
+/// Reviews falling due on one day
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DueDay {
+    /// Date in YYYY-MM-DD format
+    pub date: String,
+    /// Reviews due on the day; today's include overdue ones
+    pub due_reviews: i64,
+}
+
+/// Expected Anki workload for the coming days, from due counts and recent review speed
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ReviewForecast {
+    /// Number of days covered, starting today
+    pub days: i64,
+    /// Reviews due within those days, including overdue ones
+    pub due_reviews: i64,
+    /// Average time per review over the last 30 days (0 without recent reviews)
+    pub average_seconds_per_review: f64,
+    /// due_reviews × average_seconds_per_review, in minutes
+    pub projected_minutes: f64,
+    /// Reviews due on each of the days, starting today
+    pub daily: Vec<DueDay>,
+}
+
+impl ReviewForecast {
+    pub fn new(daily: Vec<DueDay>, average_seconds_per_review: f64) -> Self {
+        let due_reviews = daily.iter().map(|day| day.due_reviews).sum::<i64>();
+        Self {
+            days: daily.len() as i64,
+            due_reviews,
+            average_seconds_per_review,
+            projected_minutes: due_reviews as f64 * average_seconds_per_review / 60.0,
+            daily,
+        }
+    }
+}
+
+/// Scheduling settings of the Bible deck's options group
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DeckSettings {
+    /// Name of the options group ("Default" unless the deck has its own)
+    pub config_name: String,
+    /// New cards introduced per day
+    pub new_per_day: i64,
+    /// Maximum reviews per day
+    pub reviews_per_day: i64,
+    /// Longest interval a card can be scheduled for
+    pub maximum_review_interval_days: i64,
+    /// Whether the FSRS scheduler is enabled (always `false` before Anki 23.10)
+    pub fsrs_enabled: bool,
+    /// Retention FSRS schedules for (0-1), `null` while FSRS is disabled
+    pub desired_retention: Option<f64>,
+}
+
+/// A run of Anki reviews without a long pause between them
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct StudySession {
+    /// When the first review was started (RFC 3339, local offset)
+    pub start: String,
+    /// When the last review was answered (RFC 3339, local offset)
+    pub end: String,
+    /// Time from start to end, including the pauses between reviews
+    pub minutes: f64,
+    /// Time spent on the reviews themselves, as recorded by Anki
+    pub review_minutes: f64,
+    pub reviews: i64,
+}
+
+/// Anki study sessions started on one day
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DaySessions {
+    /// Date in YYYY-MM-DD format
+    pub date: String,
+    pub session_count: i64,
+    /// Sum of the sessions' lengths in minutes
+    pub total_minutes: f64,
+    /// Length of the longest session in minutes (0 without sessions)
+    pub longest_minutes: f64,
+    /// Sessions in the order they started
+    pub sessions: Vec<StudySession>,
+}
+
+/// Anki study sessions per day
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct StudySessions {
+    /// Longest pause between reviews within one session, in minutes
+    pub gap_minutes: i64,
+    /// Days oldest first, including days without sessions
+    pub days: Vec<DaySessions>,
+}
+
+/// A run of consecutive days with Anki study
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct StudyStreak {
+    /// First day in YYYY-MM-DD format
+    pub start: String,
+    /// Last day in YYYY-MM-DD format
+    pub end: String,
+    pub days: u32,
+}
+
+/// Streaks of consecutive days with study time in the Bible deck, over its whole history
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct StudyStreaks {
+    /// Consecutive days studied, ending with today (or yesterday while today is still open)
+    pub current_streak_days: u32,
+    /// Longest run of consecutive days studied
+    pub longest_streak_days: u32,
+    /// Days with any study time
+    pub days_studied: u32,
+    /// Every streak, oldest first
+    pub history: Vec<StudyStreak>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_unique_verses_leaves_out_chapters_past_the_last() {
+        let jude = BookStats {
+            book: "Jude".to_string(),
+            chapters: vec![
+                ChapterStats {
+                    chapter: 1,
+                    mature_passages: 1,
+                    ..ChapterStats::default()
+                },
+                ChapterStats {
+                    chapter: 3,
+                    mature_passages: 1,
+                    ..ChapterStats::default()
+                },
+            ],
+            ..BookStats::default()
+        }
+        .with_unique_verses(None);
+
+        assert_eq!(jude.chapters.len(), 1);
+        assert_eq!(jude.chapters[0].mature_passages, 1);
+
+        let unknown = BookStats {
+            book: "Unknown".to_string(),
+            chapters: vec![ChapterStats {
+                chapter: 2,
+                ..ChapterStats::default()
+            }],
+            ..BookStats::default()
+        }
+        .with_unique_verses(None);
+        assert_eq!(unknown.chapters.len(), 2);
+    }
+
+    #[test]
+    fn test_daily_stats_with_partial_period() {
+        let day = |date: &str, minutes: f64, reviews: i64| DayStats {
+            time: statsmodels::DayStats {
+                date: date.to_string(),
+                minutes,
+            },
+            matured_passages: 0,
+            lost_passages: 0,
+            cumulative_passages: 0,
+            reviews,
+            avg_seconds_per_review: None,
+            retention: None,
+            matured_references: Vec::new(),
+            lost_references: Vec::new(),
+        };
+        let stats = DailyStats::new(vec![
+            day("2025-01-06", 20.0, 40),
+            day("2025-01-07", 10.0, 20),
+            day("2025-01-08", 3.0, 6),
+        ]);
+        assert_eq!(stats.summary.partial_period, PartialPeriod::Include);
+        assert_eq!(stats.summary.total_reviews, 66);
+
+        let stats = stats.with_partial_period(PartialPeriod::Exclude, "2025-01-08");
+        assert_eq!(stats.days.len(), 3);
+        assert_eq!(stats.summary.partial_period, PartialPeriod::Exclude);
+        assert_eq!(stats.summary.total_days, 2);
+        assert_eq!(stats.summary.total_reviews, 60);
+        assert_eq!(stats.summary.average_minutes_per_day, 15.0);
+
+        // The shared date and minutes stay at the top level of each day
+        let json = serde_json::to_value(&stats.days[0]).unwrap();
+        assert_eq!(json["date"], "2025-01-06");
+        assert_eq!(json["minutes"], 20.0);
+        assert_eq!(json["reviews"], 40);
+        assert!(json.get("time").is_none());
     }
 }
@@ -0,0 +1,132 @@
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// Collection files inside an export, most recent format first
+///
+/// Current exports contain a zstd-compressed `collection.anki21b` next to a placeholder
+/// `collection.anki2` that only tells old Anki versions to upgrade, so the order matters.
+const COLLECTION_ENTRIES: [&str; 3] = [
+    "collection.anki21b",
+    "collection.anki21",
+    "collection.anki2",
+];
+
+/// A collection extracted from a .colpkg/.apkg export into a temporary directory, which is
+/// deleted when this is dropped
+pub struct ExtractedCollection {
+    _dir: TempDir,
+    path: PathBuf,
+}
+
+impl ExtractedCollection {
+    /// Path of the extracted SQLite collection
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Returns whether the path is an Anki export (.colpkg or .apkg) rather than a collection
+pub fn is_package(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            extension.eq_ignore_ascii_case("colpkg") || extension.eq_ignore_ascii_case("apkg")
+        })
+}
+
+/// Extracts the collection from a .colpkg/.apkg export (a zip archive) to a temporary
+/// directory, decompressing it if it is stored in the newer zstd format
+pub fn extract_collection(package_path: &str) -> Result<ExtractedCollection> {
+    let file = File::open(package_path)
+        .context(format!("Failed to open Anki export '{}'", package_path))?;
+    let mut archive = zip::ZipArchive::new(file)
+        .context(format!("Failed to read Anki export '{}'", package_path))?;
+
+    let entry_name = COLLECTION_ENTRIES
+        .into_iter()
+        .find(|name| archive.index_for_name(name).is_some())
+        .context(format!(
+            "No collection found in Anki export '{}'",
+            package_path
+        ))?;
+
+    let dir = tempfile::tempdir().context("Failed to create temporary directory")?;
+    let path = dir.path().join("collection.anki2");
+    let mut output = File::create(&path).context("Failed to create extracted collection")?;
+
+    let mut entry = archive
+        .by_name(entry_name)
+        .context(format!("Failed to read '{}' from Anki export", entry_name))?;
+    if entry_name.ends_with('b') {
+        zstd::stream::copy_decode(&mut entry, &mut output)
+            .context(format!("Failed to decompress '{}'", entry_name))?;
+    } else {
+        io::copy(&mut entry, &mut output).context(format!("Failed to extract '{}'", entry_name))?;
+    }
+
+    Ok(ExtractedCollection { _dir: dir, path })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    fn write_package(dir: &Path, entries: &[(&str, &[u8])]) -> String {
+        let path = dir.join("export.colpkg");
+        let mut writer = zip::ZipWriter::new(File::create(&path).unwrap());
+        for (name, contents) in entries {
+            writer
+                .start_file(*name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(contents).unwrap();
+        }
+        writer.finish().unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_is_package() {
+        assert!(is_package("/backups/2025-01-01.colpkg"));
+        assert!(is_package("Bible.APKG"));
+        assert!(!is_package("/data/collection.anki2"));
+    }
+
+    #[test]
+    fn test_extract_prefers_compressed_collection() {
+        let dir = tempfile::tempdir().unwrap();
+        let compressed = zstd::encode_all(&b"current collection"[..], 0).unwrap();
+        let package = write_package(
+            dir.path(),
+            &[
+                ("collection.anki2", b"please upgrade"),
+                ("collection.anki21b", &compressed),
+            ],
+        );
+
+        let extracted = extract_collection(&package).unwrap();
+
+        assert_eq!(
+            std::fs::read(extracted.path()).unwrap(),
+            b"current collection"
+        );
+    }
+
+    #[test]
+    fn test_extract_legacy_collection() {
+        let dir = tempfile::tempdir().unwrap();
+        let package = write_package(dir.path(), &[("collection.anki2", b"legacy collection")]);
+
+        let extracted = extract_collection(&package).unwrap();
+
+        assert_eq!(
+            std::fs::read(extracted.path()).unwrap(),
+            b"legacy collection"
+        );
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LazyLock, Mutex};
+
+use crate::book_name_parser::try_parse_book_name;
+use crate::models::{ParseWarning, ParseWarningKind};
+use crate::reference::check_chapter;
+use crate::verse_parser::try_count_verses_in_reference;
+
+/// Number of times each unparsable reference fell back to a default in this process
+static FALLBACK_COUNTS: LazyLock<Mutex<HashMap<(ParseWarningKind, String), u64>>> =
+    LazyLock::new(Default::default);
+
+/// Whether unparsable references fail stats queries instead of falling back to a default
+static STRICT_REFERENCES: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables strict reference parsing for this process
+///
+/// In strict mode, `get_bible_stats` fails with an error listing every unparsable reference
+/// instead of counting it as 1 verse or dropping the card from the book totals.
+pub fn set_strict_references(strict: bool) {
+    STRICT_REFERENCES.store(strict, Ordering::Relaxed);
+}
+
+/// Returns whether strict reference parsing is enabled
+pub fn strict_references() -> bool {
+    STRICT_REFERENCES.load(Ordering::Relaxed)
+}
+
+/// Records that a parser fell back to a default for the given reference
+///
+/// Each reference is logged once as a warning; repeated failures (e.g. from every stats query
+/// that touches the card) are only logged at debug level with the running count.
+pub(crate) fn record_fallback(kind: ParseWarningKind, reference: &str, error: &str) {
+    let count = {
+        let mut counts = FALLBACK_COUNTS.lock().unwrap();
+        let count = counts.entry((kind, reference.to_string())).or_insert(0);
+        *count += 1;
+        *count
+    };
+
+    if count == 1 {
+        tracing::warn!(
+            reference,
+            ?kind,
+            error,
+            "Unparsable Bible reference, {}",
+            kind.fallback()
+        );
+    } else {
+        tracing::debug!(
+            reference,
+            ?kind,
+            count,
+            "Unparsable Bible reference seen again"
+        );
+    }
+}
+
+/// Checks references with their card counts and returns those that the parsers cannot handle,
+/// sorted by reference
+pub fn find_parse_warnings(references: &[(String, i64)]) -> Vec<ParseWarning> {
+    let mut warnings = Vec::new();
+
+    for (reference, cards) in references {
+        if let Err(error) = try_count_verses_in_reference(reference) {
+            warnings.push(ParseWarning::new(
+                reference,
+                ParseWarningKind::VerseCount,
+                error,
+                *cards,
+            ));
+        }
+        if let Err(error) = try_parse_book_name(reference) {
+            warnings.push(ParseWarning::new(
+                reference,
+                ParseWarningKind::BookName,
+                error,
+                *cards,
+            ));
+        }
+        if let Err(error) = check_chapter(reference) {
+            warnings.push(ParseWarning::new(
+                reference,
+                ParseWarningKind::Chapter,
+                error,
+                *cards,
+            ));
+        }
+    }
+
+    warnings.sort_by(|a, b| a.reference.cmp(&b.reference));
+    warnings
+}
+
+/// Builds the error message for strict mode, listing each offending reference once
+pub fn describe_parse_warnings(warnings: &[ParseWarning]) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut last_reference: Option<&str> = None;
+
+    for warning in warnings {
+        if last_reference == Some(warning.reference.as_str()) {
+            continue;
+        }
+        last_reference = Some(&warning.reference);
+        lines.push(format!(
+            "'{}' ({} card(s)): {}",
+            warning.reference, warning.cards, warning.error
+        ));
+    }
+
+    format!(
+        "Found {} unparsable Bible reference(s); fix them in Anki or disable strict mode:\n  {}",
+        lines.len(),
+        lines.join("\n  ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_parse_warnings() {
+        let references = vec![
+            ("John 3:16".to_string(), 2),
+            ("Jhn 3:16x-".to_string(), 1),
+            ("Psalm 23".to_string(), 3),
+        ];
+
+        let warnings = find_parse_warnings(&references);
+
+        assert!(warnings.iter().all(|w| w.reference != "John 3:16"));
+        assert!(
+            warnings
+                .iter()
+                .any(|w| w.reference == "Jhn 3:16x-" && w.kind == ParseWarningKind::VerseCount)
+        );
+        assert!(warnings.iter().all(|w| w.cards > 0));
+    }
+
+    #[test]
+    fn test_find_parse_warnings_chapter_past_the_last() {
+        let references = vec![
+            ("Romans 17:1".to_string(), 1),
+            ("Romans 16:27".to_string(), 1),
+        ];
+
+        let warnings = find_parse_warnings(&references);
+
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].reference, "Romans 17:1");
+        assert_eq!(warnings[0].kind, ParseWarningKind::Chapter);
+        assert_eq!(warnings[0].fallback, "left out of chapter statistics");
+    }
+
+    #[test]
+    fn test_describe_parse_warnings() {
+        let references = vec![("Jhn 3:16x-".to_string(), 2), ("John 3:16".to_string(), 1)];
+
+        let message = describe_parse_warnings(&find_parse_warnings(&references));
+
+        assert!(message.starts_with("Found 1 unparsable Bible reference(s)"));
+        assert!(message.contains("'Jhn 3:16x-' (2 card(s))"));
+        assert!(!message.contains("'John 3:16'"));
+    }
+}
@@ -0,0 +1,275 @@
+use std::fmt;
+
+use crate::bible::{chapter_count, find_book, is_single_chapter_book};
+
+/// Dash characters accepted between the start and end of a verse range
+pub(crate) const RANGE_DASHES: [char; 3] = ['-', '\u{2013}', '\u{2014}'];
+
+/// A verse number with an optional part letter (e.g. "4a")
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VersePoint {
+    pub verse: i64,
+    pub part: Option<char>,
+}
+
+/// A parsed Bible reference covering a verse range within one chapter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    /// Book name as listed in `bible::OLD_TESTAMENT`/`bible::NEW_TESTAMENT`
+    pub book: &'static str,
+    pub chapter: i64,
+    pub start: VersePoint,
+    pub end: VersePoint,
+}
+
+impl Reference {
+    /// Number of verses in the range (verse parts count as whole verses)
+    pub fn verse_count(&self) -> i64 {
+        self.end.verse - self.start.verse + 1
+    }
+
+    /// Expands the range into individual (chapter, verse) coordinates
+    pub fn verses(&self) -> impl Iterator<Item = (i64, i64)> + '_ {
+        (self.start.verse..=self.end.verse).map(|verse| (self.chapter, verse))
+    }
+}
+
+/// Formats the canonical form: "Book C:V" or "Book C:V–V" (with an en dash)
+impl fmt::Display for Reference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}:{}", self.book, self.chapter, self.start)?;
+        if self.end != self.start {
+            write!(f, "\u{2013}{}", self.end)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for VersePoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.verse)?;
+        if let Some(part) = self.part {
+            write!(f, "{}", part)?;
+        }
+        Ok(())
+    }
+}
+
+/// Removes Unicode formatting characters (like zero-width spaces and directional marks)
+/// that sneak into references copied from Bible apps
+pub(crate) fn strip_formatting_chars(reference: &str) -> String {
+    reference
+        .chars()
+        .filter(|c| {
+            !c.is_control()
+                && *c != '\u{200B}' // Zero Width Space
+                && *c != '\u{FEFF}' // Zero Width No-Break Space (BOM)
+                && *c != '\u{202A}' // Left-to-Right Embedding
+                && *c != '\u{202B}' // Right-to-Left Embedding
+                && *c != '\u{202C}' // Pop Directional Formatting
+                && *c != '\u{202D}' // Left-to-Right Override
+                && *c != '\u{202E}' // Right-to-Left Override
+        })
+        .collect()
+}
+
+/// Checks that the chapter of a reference is within its book
+///
+/// References `parse_reference` cannot handle pass, since the verse and book name parsers
+/// report those.
+pub fn check_chapter(reference: &str) -> Result<(), String> {
+    let Ok(parsed) = parse_reference(reference) else {
+        return Ok(());
+    };
+    match chapter_count(parsed.book) {
+        Some(chapters) if parsed.chapter > chapters => Err(format!(
+            "{} has {} chapter(s), not {} (reference '{}')",
+            parsed.book, chapters, parsed.chapter, reference
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Parses a Bible reference into its book, chapter and verse range
+///
+/// Accepts the same forms as the verse and book name parsers, plus:
+/// - Any letter case and extra whitespace: "1  samuel 17 : 47" → 1 Samuel 17:47
+/// - En and em dashes in ranges: "John 3:16–18"
+/// - Chapter numbers for single-chapter books: "Jude 1:24"
+///
+/// Returns an error for unknown books and for ranges that span chapters.
+pub fn parse_reference(reference: &str) -> Result<Reference, String> {
+    let stripped = strip_formatting_chars(reference);
+    let tokens: Vec<&str> = stripped.split_whitespace().collect();
+
+    // The book is the first token plus every following token that doesn't start with a digit,
+    // so numbered books ("1 Samuel") and multi-word books ("Song of Solomon") both work
+    let book_len = 1 + tokens
+        .iter()
+        .skip(1)
+        .take_while(|token| !token.starts_with(|c: char| c.is_ascii_digit()))
+        .count();
+    if tokens.len() <= book_len {
+        return Err(format!(
+            "No chapter or verse found in reference '{}'",
+            stripped
+        ));
+    }
+
+    let book_name = tokens[..book_len].join(" ");
+    let book = find_book(&book_name)
+        .ok_or_else(|| format!("Unknown book '{}' in reference '{}'", book_name, stripped))?;
+
+    let location = tokens[book_len..].concat();
+    let (chapter, verses) = match location.split_once(':') {
+        Some((chapter, verses)) => {
+            let chapter = chapter
+                .parse::<i64>()
+                .ok()
+                .filter(|&chapter| chapter > 0)
+                .ok_or_else(|| {
+                    format!(
+                        "Could not parse chapter '{}' in reference '{}'",
+                        chapter, stripped
+                    )
+                })?;
+            (chapter, verses)
+        }
+        None if is_single_chapter_book(book) => (1, location.as_str()),
+        None => {
+            return Err(format!(
+                "No colon found in reference '{}' (not a single-chapter book)",
+                stripped
+            ));
+        }
+    };
+
+    if verses.contains(':') {
+        return Err(format!(
+            "Ranges spanning chapters are not supported in reference '{}'",
+            stripped
+        ));
+    }
+
+    let (start, end) = match verses.split_once(RANGE_DASHES) {
+        Some((start, end)) => (parse_verse_point(start), parse_verse_point(end)),
+        None => (parse_verse_point(verses), parse_verse_point(verses)),
+    };
+
+    match (start, end) {
+        (Some(start), Some(end)) if end.verse >= start.verse => Ok(Reference {
+            book,
+            chapter,
+            start,
+            end,
+        }),
+        _ => Err(format!(
+            "Could not parse verses '{}' in reference '{}'",
+            verses, stripped
+        )),
+    }
+}
+
+/// Converts a reference into its canonical "Book C:V–V" form
+///
+/// Unicode formatting characters are stripped, the book name is spelled as in the book
+/// statistics (e.g. "psalm" → "Psalms"), and ranges use an en dash. Normalizing an already
+/// canonical reference returns it unchanged.
+pub fn normalize_reference(reference: &str) -> Result<String, String> {
+    parse_reference(reference).map(|reference| reference.to_string())
+}
+
+/// Parses a verse number with an optional single part letter (e.g. "4a")
+fn parse_verse_point(s: &str) -> Option<VersePoint> {
+    let digits_len = s.chars().take_while(|c| c.is_ascii_digit()).count();
+    let verse = s[..digits_len].parse::<i64>().ok().filter(|&v| v > 0)?;
+
+    let mut rest = s[digits_len..].chars();
+    let part = match (rest.next(), rest.next()) {
+        (None, _) => None,
+        (Some(c), None) if c.is_ascii_alphabetic() => Some(c.to_ascii_lowercase()),
+        _ => return None,
+    };
+
+    Some(VersePoint { verse, part })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_reference() {
+        assert_eq!(
+            normalize_reference("Genesis 1:1"),
+            Ok("Genesis 1:1".to_string())
+        );
+        assert_eq!(
+            normalize_reference("1  samuel 17 : 47"),
+            Ok("1 Samuel 17:47".to_string())
+        );
+        assert_eq!(
+            normalize_reference("Romans 5: 1 - 8 "),
+            Ok("Romans 5:1\u{2013}8".to_string())
+        );
+        assert_eq!(
+            normalize_reference("Psalm \u{202d}51\u{202c}:\u{202d}3"),
+            Ok("Psalms 51:3".to_string())
+        );
+        assert_eq!(
+            normalize_reference("Colossians 1:9A-12"),
+            Ok("Colossians 1:9a\u{2013}12".to_string())
+        );
+        assert_eq!(
+            normalize_reference("John 3:16-16"),
+            Ok("John 3:16".to_string())
+        );
+        assert_eq!(
+            normalize_reference("Song of Solomon 2:4"),
+            Ok("Song of Solomon 2:4".to_string())
+        );
+    }
+
+    #[test]
+    fn test_normalize_single_chapter_books() {
+        assert_eq!(
+            normalize_reference("Jude 24-25"),
+            Ok("Jude 1:24\u{2013}25".to_string())
+        );
+        assert_eq!(
+            normalize_reference("3 John 14"),
+            Ok("3 John 1:14".to_string())
+        );
+    }
+
+    #[test]
+    fn test_normalize_is_idempotent() {
+        for reference in ["Romans 5:1-8", "Jude 24", "Proverbs 12:4a", "Psalm 23:1—3"] {
+            let normalized = normalize_reference(reference).unwrap();
+            assert_eq!(normalize_reference(&normalized), Ok(normalized));
+        }
+    }
+
+    #[test]
+    fn test_verses() {
+        let reference = parse_reference("Colossians 1:9a-12").unwrap();
+        assert_eq!(
+            reference.verses().collect::<Vec<_>>(),
+            vec![(1, 9), (1, 10), (1, 11), (1, 12)]
+        );
+        assert_eq!(reference.verse_count(), 4);
+
+        let reference = parse_reference("Jude 24").unwrap();
+        assert_eq!(reference.verses().collect::<Vec<_>>(), vec![(1, 24)]);
+    }
+
+    #[test]
+    fn test_normalize_invalid() {
+        assert!(normalize_reference("Genesis 1").is_err());
+        assert!(normalize_reference("Genesis").is_err());
+        assert!(normalize_reference("Hezekiah 1:1").is_err());
+        assert!(normalize_reference("Genesis 1:5-1").is_err());
+        assert!(normalize_reference("John 3:16-4:2").is_err());
+        assert!(normalize_reference("Genesis 1:abc").is_err());
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+
+use crate::bible::all_books;
+use crate::book_name_parser::parse_book_name;
+use crate::models::{BookRelapseStats, RelapseStats};
+
+/// Number of days after maturing in which a drop below 21 days counts as a relapse
+pub const RELAPSE_WINDOW_DAYS: i64 = 90;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// A review that moved a passage across the 21-day maturity threshold
+#[derive(Debug, Clone)]
+pub struct ProgressEvent {
+    pub reference: String,
+    pub card_id: i64,
+    /// Review timestamp in milliseconds
+    pub timestamp_ms: i64,
+    /// `true` if the passage matured, `false` if it dropped below 21 days
+    pub matured: bool,
+}
+
+/// Computes per-book relapse rates from maturity threshold crossings
+///
+/// Only maturations at least `RELAPSE_WINDOW_DAYS` old are counted, so every counted passage
+/// has had the full window to relapse. Events must be sorted by card and timestamp.
+pub fn relapse_stats(events: &[ProgressEvent], now_ms: i64) -> RelapseStats {
+    let window_ms = RELAPSE_WINDOW_DAYS * DAY_MS;
+    let mut counts: HashMap<String, (i64, i64)> = HashMap::new();
+
+    for (index, event) in events.iter().enumerate() {
+        if !event.matured || event.timestamp_ms > now_ms - window_ms {
+            continue;
+        }
+        let Some(book) = parse_book_name(&event.reference) else {
+            continue;
+        };
+
+        let relapsed = events[index + 1..]
+            .iter()
+            .take_while(|later| later.card_id == event.card_id)
+            .take_while(|later| later.timestamp_ms <= event.timestamp_ms + window_ms)
+            .any(|later| !later.matured);
+
+        let (matured, relapses) = counts.entry(book).or_default();
+        *matured += 1;
+        if relapsed {
+            *relapses += 1;
+        }
+    }
+
+    let books: Vec<BookRelapseStats> = all_books()
+        .filter_map(|book| {
+            let &(matured, relapsed) = counts.get(book)?;
+            Some(BookRelapseStats::new(book.to_string(), matured, relapsed))
+        })
+        .collect();
+
+    let matured = books.iter().map(|b| b.matured_passages).sum();
+    let relapsed = books.iter().map(|b| b.relapsed_passages).sum();
+    RelapseStats {
+        window_days: RELAPSE_WINDOW_DAYS,
+        total: BookRelapseStats::new("All".to_string(), matured, relapsed),
+        books,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(reference: &str, card_id: i64, day: i64, matured: bool) -> ProgressEvent {
+        ProgressEvent {
+            reference: reference.to_string(),
+            card_id,
+            timestamp_ms: day * DAY_MS,
+            matured,
+        }
+    }
+
+    #[test]
+    fn test_relapse_stats() {
+        let events = vec![
+            // Relapsed after 30 days, then matured again too recently to count
+            event("John 3:16", 1, 0, true),
+            event("John 3:16", 1, 30, false),
+            event("John 3:16", 1, 250, true),
+            // Dropped below 21 days only after the window
+            event("John 1:1", 2, 10, true),
+            event("John 1:1", 2, 150, false),
+            // Durable
+            event("Romans 8:28", 3, 20, true),
+            // Unparsable reference is skipped
+            event("Nonsense", 4, 0, true),
+        ];
+
+        let stats = relapse_stats(&events, 300 * DAY_MS);
+
+        assert_eq!(stats.books.len(), 2);
+        assert_eq!(stats.books[0].book, "John");
+        assert_eq!(stats.books[0].matured_passages, 2);
+        assert_eq!(stats.books[0].relapsed_passages, 1);
+        assert_eq!(stats.books[0].relapse_rate, 0.5);
+        assert_eq!(stats.books[1].relapse_rate, 0.0);
+        assert_eq!(stats.total.matured_passages, 3);
+        assert_eq!(stats.total.relapsed_passages, 1);
+    }
+}
@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+
+use crate::bible::all_books;
+use crate::book_name_parser::parse_book_name;
+use crate::models::{BookRetention, Retention};
+
+/// Passed and failed answers to the cards in review of one passage
+#[derive(Debug, Clone)]
+pub struct PassageAnswers {
+    pub reference: String,
+    pub passed_reviews: i64,
+    pub failed_reviews: i64,
+}
+
+/// Sums the answers of each passage into per-book retention, in canonical book order, and
+/// the total over all books (book "All")
+///
+/// Passages whose book cannot be parsed only count towards the total.
+pub fn book_retention(answers: &[PassageAnswers]) -> (Vec<BookRetention>, BookRetention) {
+    let mut counts: HashMap<String, (i64, i64)> = HashMap::new();
+    let (mut passed, mut failed) = (0, 0);
+    for passage in answers {
+        passed += passage.passed_reviews;
+        failed += passage.failed_reviews;
+        let Some(book) = parse_book_name(&passage.reference) else {
+            continue;
+        };
+        let (book_passed, book_failed) = counts.entry(book).or_default();
+        *book_passed += passage.passed_reviews;
+        *book_failed += passage.failed_reviews;
+    }
+
+    let books = all_books()
+        .filter_map(|book| {
+            let &(passed, failed) = counts.get(book)?;
+            Some(BookRetention {
+                book: book.to_string(),
+                retention: Retention::new(passed, failed),
+            })
+        })
+        .collect();
+
+    let total = BookRetention {
+        book: "All".to_string(),
+        retention: Retention::new(passed, failed),
+    };
+    (books, total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn answers(reference: &str, passed_reviews: i64, failed_reviews: i64) -> PassageAnswers {
+        PassageAnswers {
+            reference: reference.to_string(),
+            passed_reviews,
+            failed_reviews,
+        }
+    }
+
+    #[test]
+    fn test_book_retention() {
+        let answers = vec![
+            answers("Romans 8:28", 3, 1),
+            answers("John 3:16", 4, 0),
+            answers("John 1:1-5", 2, 2),
+            answers("Nonsense", 0, 2),
+        ];
+
+        let (books, total) = book_retention(&answers);
+
+        assert_eq!(books.len(), 2);
+        assert_eq!(books[0].book, "John");
+        assert_eq!(books[0].retention.passed_reviews, 6);
+        assert_eq!(books[0].retention.retention, Some(0.75));
+        assert_eq!(books[1].book, "Romans");
+        assert_eq!(total.retention.passed_reviews, 9);
+        assert_eq!(total.retention.failed_reviews, 5);
+        assert_eq!(Retention::new(0, 0).retention, None);
+    }
+}
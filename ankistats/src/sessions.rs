@@ -0,0 +1,129 @@
+use anyhow::Result;
+use statsutils::{timestamp_ms_to_date_string, timestamp_ms_to_rfc3339};
+
+use crate::models::{DaySessions, StudySession};
+
+/// Default longest pause (in minutes) between reviews within one study session
+pub const DEFAULT_SESSION_GAP_MINUTES: i64 = 10;
+
+/// A single review from the `revlog` table
+#[derive(Debug, Clone, Copy)]
+pub struct Review {
+    /// When the review was answered, in milliseconds (the revlog ID)
+    pub timestamp_ms: i64,
+    /// Time spent on the review in milliseconds
+    pub duration_ms: i64,
+}
+
+impl Review {
+    fn start_ms(&self) -> i64 {
+        self.timestamp_ms - self.duration_ms
+    }
+}
+
+/// Groups reviews sorted by time into study sessions, listed per date of `dates`
+///
+/// A pause of more than `gap_minutes` between answering one review and starting the next
+/// starts a new session. Sessions are counted on the day they started, even when they run
+/// past the 4 AM rollover.
+pub fn daily_sessions(
+    reviews: &[Review],
+    gap_minutes: i64,
+    dates: &[String],
+) -> Result<Vec<DaySessions>> {
+    let gap_ms = gap_minutes * 60_000;
+    let mut sessions: Vec<&[Review]> = Vec::new();
+    let mut first = 0;
+    for index in 1..=reviews.len() {
+        let ends_session = reviews
+            .get(index)
+            .is_none_or(|review| review.start_ms() - reviews[index - 1].timestamp_ms > gap_ms);
+        if ends_session && first < index {
+            sessions.push(&reviews[first..index]);
+            first = index;
+        }
+    }
+
+    let mut days: Vec<DaySessions> = dates
+        .iter()
+        .map(|date| DaySessions {
+            date: date.clone(),
+            session_count: 0,
+            total_minutes: 0.0,
+            longest_minutes: 0.0,
+            sessions: Vec::new(),
+        })
+        .collect();
+
+    for session in sessions {
+        let (first, last) = (session[0], session[session.len() - 1]);
+        let start_ms = first.start_ms();
+        let date = timestamp_ms_to_date_string(start_ms)?;
+        let Some(day) = days.iter_mut().find(|day| day.date == date) else {
+            continue;
+        };
+
+        let minutes = round1((last.timestamp_ms - start_ms) as f64 / 60_000.0);
+        let review_ms: i64 = session.iter().map(|review| review.duration_ms).sum();
+        day.session_count += 1;
+        day.total_minutes = round1(day.total_minutes + minutes);
+        day.longest_minutes = day.longest_minutes.max(minutes);
+        day.sessions.push(StudySession {
+            start: timestamp_ms_to_rfc3339(start_ms)?,
+            end: timestamp_ms_to_rfc3339(last.timestamp_ms)?,
+            minutes,
+            review_minutes: round1(review_ms as f64 / 60_000.0),
+            reviews: session.len() as i64,
+        });
+    }
+
+    Ok(days)
+}
+
+fn round1(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2025-01-08 10:00 in America/Chicago
+    const WEDNESDAY_MORNING_MS: i64 = 1_736_352_000_000;
+
+    fn review(minutes_after: i64, duration_seconds: i64) -> Review {
+        Review {
+            timestamp_ms: WEDNESDAY_MORNING_MS + minutes_after * 60_000,
+            duration_ms: duration_seconds * 1000,
+        }
+    }
+
+    #[test]
+    fn test_daily_sessions() {
+        let reviews = [
+            review(1, 30),
+            review(2, 30),
+            review(5, 60),
+            // 15 minutes later, less the 30 seconds the review took
+            review(20, 30),
+            review(21, 60),
+        ];
+        let dates = vec!["2025-01-07".to_string(), "2025-01-08".to_string()];
+
+        let days = daily_sessions(&reviews, 10, &dates).unwrap();
+        assert_eq!(days[0].session_count, 0);
+        let day = &days[1];
+        assert_eq!(day.session_count, 2);
+        assert_eq!(day.sessions[0].reviews, 3);
+        assert_eq!(day.sessions[0].minutes, 4.5);
+        assert_eq!(day.sessions[0].review_minutes, 2.0);
+        assert_eq!(day.sessions[1].minutes, 1.5);
+        assert_eq!(day.longest_minutes, 4.5);
+        assert_eq!(day.total_minutes, 6.0);
+        assert!(day.sessions[0].start.starts_with("2025-01-08T10:00:30"));
+
+        let days = daily_sessions(&reviews, 15, &dates).unwrap();
+        assert_eq!(days[1].session_count, 1);
+        assert_eq!(days[1].sessions[0].reviews, 5);
+    }
+}
@@ -0,0 +1,191 @@
+use anyhow::{Result, bail};
+
+use crate::models::DeckSettings;
+
+// Field numbers of the protobuf messages Anki 2.1.28+ stores in `decks.kind` and
+// `deck_config.config`
+// See https://github.com/ankitects/anki/blob/main/proto/anki/decks.proto and
+// https://github.com/ankitects/anki/blob/main/proto/anki/deck_config.proto
+const DECK_KIND_NORMAL: u32 = 1;
+const NORMAL_DECK_CONFIG_ID: u32 = 1;
+const CONFIG_NEW_PER_DAY: u32 = 9;
+const CONFIG_REVIEWS_PER_DAY: u32 = 10;
+const CONFIG_MAXIMUM_REVIEW_INTERVAL: u32 = 16;
+const CONFIG_DESIRED_RETENTION: u32 = 37;
+
+/// Value of a single protobuf field
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FieldValue<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(&'a [u8]),
+    Fixed32(u32),
+}
+
+/// Reads a base 128 varint, advancing `pos` past it
+fn read_varint(bytes: &[u8], pos: &mut usize) -> Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let Some(&byte) = bytes.get(*pos) else {
+            bail!("Truncated protobuf varint");
+        };
+        *pos += 1;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    bail!("Protobuf varint is too long")
+}
+
+/// Takes the next `len` bytes, advancing `pos` past them
+fn read_bytes<'a>(bytes: &'a [u8], pos: &mut usize, len: usize) -> Result<&'a [u8]> {
+    let Some(slice) = pos.checked_add(len).and_then(|end| bytes.get(*pos..end)) else {
+        bail!("Truncated protobuf field");
+    };
+    *pos += len;
+    Ok(slice)
+}
+
+/// Splits a protobuf message into its top-level fields, in the order they appear
+fn decode_fields(bytes: &[u8]) -> Result<Vec<(u32, FieldValue<'_>)>> {
+    let mut fields = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let key = read_varint(bytes, &mut pos)?;
+        let number = (key >> 3) as u32;
+        let value = match key & 0x7 {
+            0 => FieldValue::Varint(read_varint(bytes, &mut pos)?),
+            1 => {
+                let raw = read_bytes(bytes, &mut pos, 8)?;
+                FieldValue::Fixed64(u64::from_le_bytes(raw.try_into()?))
+            }
+            2 => {
+                let len = read_varint(bytes, &mut pos)? as usize;
+                FieldValue::Bytes(read_bytes(bytes, &mut pos, len)?)
+            }
+            5 => {
+                let raw = read_bytes(bytes, &mut pos, 4)?;
+                FieldValue::Fixed32(u32::from_le_bytes(raw.try_into()?))
+            }
+            wire_type => bail!("Unsupported protobuf wire type {}", wire_type),
+        };
+        fields.push((number, value));
+    }
+    Ok(fields)
+}
+
+/// Last value of a field, as protobuf lets later occurrences override earlier ones
+fn last_field<'a>(fields: &[(u32, FieldValue<'a>)], number: u32) -> Option<FieldValue<'a>> {
+    fields
+        .iter()
+        .rev()
+        .find(|(n, _)| *n == number)
+        .map(|(_, value)| *value)
+}
+
+/// Reads an unsigned integer field, which is 0 when absent
+fn uint_field(fields: &[(u32, FieldValue)], number: u32) -> i64 {
+    match last_field(fields, number) {
+        Some(FieldValue::Varint(value)) => value as i64,
+        _ => 0,
+    }
+}
+
+/// Gets the options group ID from a deck's `kind` blob
+pub fn deck_config_id(kind: &[u8]) -> Result<i64> {
+    let Some(FieldValue::Bytes(normal)) = last_field(&decode_fields(kind)?, DECK_KIND_NORMAL)
+    else {
+        bail!("Deck is a filtered deck without an options group");
+    };
+    Ok(uint_field(&decode_fields(normal)?, NORMAL_DECK_CONFIG_ID))
+}
+
+/// Reads the scheduling settings from an options group's `config` blob
+///
+/// Desired retention is only reported while FSRS is enabled, as the SM-2 scheduler ignores it.
+pub fn parse_deck_config(name: String, config: &[u8], fsrs_enabled: bool) -> Result<DeckSettings> {
+    let fields = decode_fields(config)?;
+    let desired_retention = match last_field(&fields, CONFIG_DESIRED_RETENTION) {
+        Some(FieldValue::Fixed32(bits)) if fsrs_enabled => {
+            Some((f64::from(f32::from_bits(bits)) * 1000.0).round() / 1000.0)
+        }
+        _ => None,
+    };
+
+    Ok(DeckSettings {
+        config_name: name,
+        new_per_day: uint_field(&fields, CONFIG_NEW_PER_DAY),
+        reviews_per_day: uint_field(&fields, CONFIG_REVIEWS_PER_DAY),
+        maximum_review_interval_days: uint_field(&fields, CONFIG_MAXIMUM_REVIEW_INTERVAL),
+        fsrs_enabled,
+        desired_retention,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn varint(mut value: u64) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                bytes.push(byte);
+                return bytes;
+            }
+            bytes.push(byte | 0x80);
+        }
+    }
+
+    fn uint(number: u32, value: u64) -> Vec<u8> {
+        [varint(u64::from(number) << 3), varint(value)].concat()
+    }
+
+    fn message(number: u32, payload: &[u8]) -> Vec<u8> {
+        [
+            varint(u64::from(number) << 3 | 2),
+            varint(payload.len() as u64),
+            payload.to_vec(),
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn test_deck_config_id() {
+        let normal = [uint(1, 1_700_000_000_000), uint(2, 5)].concat();
+        assert_eq!(
+            deck_config_id(&message(1, &normal)).unwrap(),
+            1_700_000_000_000
+        );
+        assert!(deck_config_id(&message(2, &uint(1, 3))).is_err());
+        assert!(deck_config_id(&[0x08]).is_err());
+    }
+
+    #[test]
+    fn test_parse_deck_config() {
+        let config = [
+            // Learning steps (packed floats) and an ease to skip over
+            message(1, &[0, 0, 0x80, 0x3f]),
+            uint(9, 10),
+            uint(10, 200),
+            vec![0x5d, 0, 0, 0x20, 0x40],
+            uint(16, 36500),
+            varint(37 << 3 | 5),
+            0.9f32.to_le_bytes().to_vec(),
+            uint(9, 5),
+        ]
+        .concat();
+
+        let settings = parse_deck_config("Bible".to_string(), &config, true).unwrap();
+        assert_eq!(settings.new_per_day, 5);
+        assert_eq!(settings.reviews_per_day, 200);
+        assert_eq!(settings.maximum_review_interval_days, 36500);
+        assert_eq!(settings.desired_retention, Some(0.9));
+
+        let settings = parse_deck_config("Bible".to_string(), &config, false).unwrap();
+        assert_eq!(settings.desired_retention, None);
+    }
+}
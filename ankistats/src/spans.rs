@@ -0,0 +1,76 @@
+use crate::models::{PassageStatus, SpanBucketStats};
+use crate::verse_parser::count_verses_in_reference;
+
+/// Passage length buckets as (label, fewest verses, most verses)
+const SPAN_BUCKETS: [(&str, i64, Option<i64>); 4] = [
+    ("1 verse", 1, Some(1)),
+    ("2\u{2013}3 verses", 2, Some(3)),
+    ("4\u{2013}6 verses", 4, Some(6)),
+    ("7+ verses", 7, None),
+];
+
+/// Counts passages per length bucket and status
+///
+/// Lengths come from the same verse counting as the book statistics, so unparsable references
+/// count as a single verse.
+pub fn span_distribution(passages: &[(String, PassageStatus)]) -> Vec<SpanBucketStats> {
+    let mut buckets: Vec<SpanBucketStats> = SPAN_BUCKETS
+        .iter()
+        .map(|&(label, min_verses, max_verses)| SpanBucketStats {
+            label: label.to_string(),
+            min_verses,
+            max_verses,
+            mature_passages: 0,
+            young_passages: 0,
+            unseen_passages: 0,
+            suspended_passages: 0,
+        })
+        .collect();
+
+    for (reference, status) in passages {
+        let verses = count_verses_in_reference(reference);
+        let Some(bucket) = buckets
+            .iter_mut()
+            .find(|bucket| bucket.max_verses.is_none_or(|max| verses <= max))
+        else {
+            continue;
+        };
+
+        match status {
+            PassageStatus::Mature => bucket.mature_passages += 1,
+            PassageStatus::Young => bucket.young_passages += 1,
+            PassageStatus::Unseen => bucket.unseen_passages += 1,
+            PassageStatus::Suspended => bucket.suspended_passages += 1,
+        }
+    }
+
+    buckets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_span_distribution() {
+        let passages = vec![
+            ("John 3:16".to_string(), PassageStatus::Mature),
+            ("Romans 8:28-29".to_string(), PassageStatus::Mature),
+            ("Romans 8:31-33".to_string(), PassageStatus::Young),
+            ("Psalm 23:1-6".to_string(), PassageStatus::Suspended),
+            ("1 Corinthians 13:4-13".to_string(), PassageStatus::Young),
+            ("Jude 24-25".to_string(), PassageStatus::Unseen),
+        ];
+
+        let buckets = span_distribution(&passages);
+
+        assert_eq!(buckets.len(), 4);
+        assert_eq!(buckets[0].mature_passages, 1);
+        assert_eq!(buckets[1].mature_passages, 1);
+        assert_eq!(buckets[1].young_passages, 1);
+        assert_eq!(buckets[1].unseen_passages, 1);
+        assert_eq!(buckets[2].suspended_passages, 1);
+        assert_eq!(buckets[3].young_passages, 1);
+        assert_eq!(buckets[3].total_passages(), 1);
+    }
+}
@@ -0,0 +1,77 @@
+use chrono::NaiveDate;
+
+use crate::models::{StudyStreak, StudyStreaks};
+
+/// Groups the days with study time into streaks of consecutive days
+///
+/// `dates` must be sorted and free of duplicates. A streak that has not been continued
+/// today yet still counts as the current streak until the day is over.
+pub fn study_streaks(dates: &[NaiveDate], today: NaiveDate) -> StudyStreaks {
+    let mut runs: Vec<(NaiveDate, NaiveDate)> = Vec::new();
+    for &date in dates {
+        match runs.last_mut() {
+            Some((_, end)) if end.succ_opt() == Some(date) => *end = date,
+            _ => runs.push((date, date)),
+        }
+    }
+
+    let history: Vec<StudyStreak> = runs
+        .into_iter()
+        .map(|(start, end)| StudyStreak {
+            start: start.format("%Y-%m-%d").to_string(),
+            end: end.format("%Y-%m-%d").to_string(),
+            days: ((end - start).num_days() + 1) as u32,
+        })
+        .collect();
+
+    let yesterday = today.pred_opt().unwrap_or(today);
+    let current_streak_days = match dates.last() {
+        Some(&last) if last == today || last == yesterday => {
+            history.last().map_or(0, |streak| streak.days)
+        }
+        _ => 0,
+    };
+
+    StudyStreaks {
+        current_streak_days,
+        longest_streak_days: history.iter().map(|streak| streak.days).max().unwrap_or(0),
+        days_studied: dates.len() as u32,
+        history,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 1, day).unwrap()
+    }
+
+    #[test]
+    fn test_study_streaks() {
+        let dates = [1, 2, 3, 5, 6, 9].map(date);
+
+        let streaks = study_streaks(&dates, date(9));
+        assert_eq!(streaks.current_streak_days, 1);
+        assert_eq!(streaks.longest_streak_days, 3);
+        assert_eq!(streaks.days_studied, 6);
+        assert_eq!(streaks.history.len(), 3);
+        assert_eq!(streaks.history[0].start, "2025-01-01");
+        assert_eq!(streaks.history[0].end, "2025-01-03");
+        assert_eq!(streaks.history[1].days, 2);
+
+        // Not studied yet today: yesterday's streak still counts
+        assert_eq!(study_streaks(&dates, date(10)).current_streak_days, 1);
+        assert_eq!(study_streaks(&dates, date(11)).current_streak_days, 0);
+        assert_eq!(study_streaks(&dates[..5], date(7)).current_streak_days, 2);
+    }
+
+    #[test]
+    fn test_study_streaks_without_study() {
+        let streaks = study_streaks(&[], date(1));
+        assert_eq!(streaks.current_streak_days, 0);
+        assert_eq!(streaks.longest_streak_days, 0);
+        assert!(streaks.history.is_empty());
+    }
+}
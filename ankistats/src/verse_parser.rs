@@ -1,36 +1,20 @@
-/// Checks if a book name is a single-chapter book in the Bible
-fn is_single_chapter_book(book_name: &str) -> bool {
-    const SINGLE_CHAPTER_BOOKS: &[&str] = &["Obadiah", "Philemon", "2 John", "3 John", "Jude"];
-
-    SINGLE_CHAPTER_BOOKS
-        .iter()
-        .any(|&book| book_name.eq_ignore_ascii_case(book))
-}
+use crate::bible::is_single_chapter_book;
+use crate::models::ParseWarningKind;
+use crate::parse_warnings::record_fallback;
+use crate::reference::{RANGE_DASHES, strip_formatting_chars};
 
 /// Parses a Bible verse reference and counts the number of verses it contains
 ///
 /// Supports:
 /// - Single verses: "Genesis 1:1" → 1
-/// - Simple ranges: "Genesis 1:1-5" → 5
+/// - Simple ranges: "Genesis 1:1-5" → 5 (or "Genesis 1:1–5" with an en dash)
 /// - Verse parts (letters are stripped): "Proverbs 12:4a" → 1, "Colossians 1:9a-12" → 4
 /// - Single-chapter books: "Jude 24-25" → 2 (no colon needed)
 ///
 /// Returns an error if the reference cannot be parsed.
 pub fn try_count_verses_in_reference(reference: &str) -> Result<i64, String> {
     // Strip any Unicode formatting characters (like zero-width spaces and directional marks)
-    let reference = reference
-        .chars()
-        .filter(|c| {
-            !c.is_control()
-                && *c != '\u{200B}' // Zero Width Space
-                && *c != '\u{FEFF}' // Zero Width No-Break Space (BOM)
-                && *c != '\u{202A}' // Left-to-Right Embedding
-                && *c != '\u{202B}' // Right-to-Left Embedding
-                && *c != '\u{202C}' // Pop Directional Formatting
-                && *c != '\u{202D}' // Left-to-Right Override
-                && *c != '\u{202E}' // Right-to-Left Override
-        })
-        .collect::<String>();
+    let reference = strip_formatting_chars(reference);
 
     // Find the last colon to extract the verse portion
     let verse_part = match reference.rfind(':') {
@@ -60,16 +44,17 @@ pub fn try_count_verses_in_reference(reference: &str) -> Result<i64, String> {
     // Strip any whitespace and remaining Unicode formatting characters
     let verse_part = verse_part.trim();
 
-    // Check if it's a range (contains a hyphen)
-    if let Some(hyphen_pos) = verse_part.find('-') {
-        let start_str = verse_part[..hyphen_pos].trim();
-        let end_str = verse_part[hyphen_pos + 1..].trim();
+    // Check if it's a range (contains a hyphen, en dash or em dash)
+    if let Some((start_str, end_str)) = verse_part.split_once(RANGE_DASHES) {
+        let start_str = start_str.trim();
+        let end_str = end_str.trim();
 
         let start = parse_verse_number(start_str);
         let end = parse_verse_number(end_str);
 
+        // Huge verse numbers would overflow the count (and panic inside a SQLite callback)
         match (start, end) {
-            (Some(s), Some(e)) if e >= s => Ok(e - s + 1),
+            (Some(s), Some(e)) if e >= s && e - s < i64::MAX => Ok(e - s + 1),
             _ => Err(format!(
                 "Could not parse range '{}' in reference '{}'",
                 verse_part, reference
@@ -94,14 +79,15 @@ pub fn try_count_verses_in_reference(reference: &str) -> Result<i64, String> {
 /// - Simple ranges: "Genesis 1:1-5" → 5
 /// - Verse parts (letters are stripped): "Proverbs 12:4a" → 1, "Colossians 1:9a-12" → 4
 ///
-/// For unparsable references, logs a warning and returns 1 (treating as a single verse).
+/// For unparsable references, logs a warning (once per reference) and returns 1 (treating
+/// as a single verse).
 /// This is a wrapper around `try_count_verses_in_reference` for use in contexts where
 /// errors should be handled gracefully (e.g., SQLite functions).
 pub fn count_verses_in_reference(reference: &str) -> i64 {
     match try_count_verses_in_reference(reference) {
         Ok(count) => count,
         Err(err) => {
-            eprintln!("Warning: {}, treating as 1 verse", err);
+            record_fallback(ParseWarningKind::VerseCount, reference, &err);
             1
         }
     }
@@ -141,6 +127,10 @@ mod tests {
         assert_eq!(try_count_verses_in_reference("Genesis 1:1-5"), Ok(5));
         assert_eq!(try_count_verses_in_reference("Romans 5:1-8"), Ok(8));
         assert_eq!(try_count_verses_in_reference("John 3:16-17"), Ok(2));
+
+        // Canonical references use an en dash
+        assert_eq!(try_count_verses_in_reference("Genesis 1:1\u{2013}5"), Ok(5));
+        assert_eq!(try_count_verses_in_reference("Jude 1:24\u{2014}25"), Ok(2));
     }
 
     #[test]
@@ -207,6 +197,7 @@ mod tests {
         assert!(try_count_verses_in_reference("Genesis 1").is_err());
         assert!(try_count_verses_in_reference("Genesis 1:abc").is_err());
         assert!(try_count_verses_in_reference("Genesis 1:5-1").is_err());
+        assert!(try_count_verses_in_reference("John 3:0-9223372036854775807").is_err());
     }
 
     #[test]
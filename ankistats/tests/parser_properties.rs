@@ -0,0 +1,153 @@
+//! Property tests for the reference parsers
+//!
+//! These parsers run inside SQLite callbacks (`count_verses`, `parse_book_name`,
+//! `parse_chapter`), where a panic aborts the whole query, so they must return an error for
+//! any input rather than panic. The fuzz targets in `ankistats/fuzz` explore the same
+//! functions with coverage guidance.
+
+use ankistats::bible::{BOOKS, Book};
+use ankistats::book_name_parser::try_parse_book_name;
+use ankistats::reference::parse_reference;
+use ankistats::verse_parser::try_count_verses_in_reference;
+use proptest::prelude::*;
+
+/// Invisible characters Bible apps leave in copied references
+const FORMATTING_CHARS: &[char] = &[
+    '\u{200B}', '\u{FEFF}', '\u{202A}', '\u{202B}', '\u{202C}', '\u{202D}', '\u{202E}',
+];
+
+/// Text mixing digits, reference punctuation, right-to-left scripts, combining marks,
+/// directional overrides and emoji
+fn unicode_heavy() -> impl Strategy<Value = String> {
+    proptest::collection::vec(
+        prop_oneof![
+            "[0-9 :,;.\\-\u{2013}\u{2014}]",
+            "[a-zA-Z]",
+            "[\u{0590}-\u{05FF}\u{0600}-\u{06FF}]",
+            "[\u{0300}-\u{036F}]",
+            "[\u{200B}-\u{200F}\u{202A}-\u{202E}\u{2066}-\u{2069}\u{FEFF}]",
+            "[\u{1F300}-\u{1F6FF}]",
+            any::<char>().prop_map(String::from),
+        ],
+        0..40,
+    )
+    .prop_map(|parts| parts.concat())
+}
+
+/// Chapter or verse number, including the edges of the integer types
+fn number() -> impl Strategy<Value = u64> {
+    prop_oneof![
+        0..200u64,
+        Just(i64::MAX as u64),
+        Just(i64::MAX as u64 + 1),
+        Just(u64::MAX),
+        any::<u64>(),
+    ]
+}
+
+/// A book with a chapter and verse range it has, e.g. (Genesis, 3, 15, 17)
+fn verse_range() -> impl Strategy<Value = (Book, u16, u16, u16)> {
+    proptest::sample::select(BOOKS)
+        .prop_flat_map(|book| (Just(book), 1..=book.chapters.len() as u16))
+        .prop_flat_map(|(book, chapter)| {
+            let verses = book.chapters[usize::from(chapter) - 1];
+            (Just(book), Just(chapter), 1..=verses)
+        })
+        .prop_flat_map(|(book, chapter, start)| {
+            let verses = book.chapters[usize::from(chapter) - 1];
+            (Just(book), Just(chapter), Just(start), start..=verses)
+        })
+}
+
+/// Inserts formatting characters before the characters at the given positions
+fn with_formatting_chars(reference: &str, insertions: &[(usize, usize)]) -> String {
+    let mut chars: Vec<char> = reference.chars().collect();
+    for &(position, char_index) in insertions {
+        let position = position % (chars.len() + 1);
+        chars.insert(
+            position,
+            FORMATTING_CHARS[char_index % FORMATTING_CHARS.len()],
+        );
+    }
+    chars.into_iter().collect()
+}
+
+proptest! {
+    #[test]
+    fn parsers_do_not_panic_on_any_string(reference in any::<String>()) {
+        let _ = try_count_verses_in_reference(&reference);
+        let _ = try_parse_book_name(&reference);
+        let _ = parse_reference(&reference);
+    }
+
+    #[test]
+    fn parsers_do_not_panic_on_unicode_heavy_text(reference in unicode_heavy()) {
+        let _ = try_count_verses_in_reference(&reference);
+        let _ = try_parse_book_name(&reference);
+        let _ = parse_reference(&reference);
+    }
+
+    #[test]
+    fn parsers_do_not_panic_after_a_book_name(
+        book in proptest::sample::select(BOOKS),
+        rest in unicode_heavy(),
+    ) {
+        let reference = format!("{} {}", book.name, rest);
+        let _ = try_count_verses_in_reference(&reference);
+        let _ = try_parse_book_name(&reference);
+        let _ = parse_reference(&reference);
+    }
+
+    #[test]
+    fn parsers_do_not_panic_on_large_numbers(
+        book in proptest::sample::select(BOOKS),
+        (chapter, start, end) in (number(), number(), number()),
+    ) {
+        for reference in [
+            format!("{} {}:{}-{}", book.name, chapter, start, end),
+            format!("{} {}-{}", book.name, start, end),
+            format!("{} 1:0-{}", book.name, end),
+        ] {
+            let _ = try_count_verses_in_reference(&reference);
+            let _ = try_parse_book_name(&reference);
+            let _ = parse_reference(&reference);
+        }
+    }
+
+    #[test]
+    fn verse_counts_are_positive(reference in unicode_heavy()) {
+        if let Ok(count) = try_count_verses_in_reference(&reference) {
+            prop_assert!(count >= 1, "'{}' counted {} verses", reference, count);
+        }
+    }
+
+    #[test]
+    fn valid_references_are_parsed((book, chapter, start, end) in verse_range()) {
+        let reference = if start == end {
+            format!("{} {}:{}", book.name, chapter, start)
+        } else {
+            format!("{} {}:{}-{}", book.name, chapter, start, end)
+        };
+
+        prop_assert_eq!(
+            try_count_verses_in_reference(&reference),
+            Ok(i64::from(end - start) + 1)
+        );
+        prop_assert_eq!(try_parse_book_name(&reference), Ok(book.name.to_string()));
+    }
+
+    #[test]
+    fn formatting_chars_are_ignored(
+        (book, chapter, start, end) in verse_range(),
+        insertions in proptest::collection::vec((any::<usize>(), any::<usize>()), 1..6),
+    ) {
+        let reference = format!("{} {}:{}-{}", book.name, chapter, start, end);
+        let copied = with_formatting_chars(&reference, &insertions);
+
+        prop_assert_eq!(
+            try_count_verses_in_reference(&copied),
+            try_count_verses_in_reference(&reference)
+        );
+        prop_assert_eq!(try_parse_book_name(&copied), try_parse_book_name(&reference));
+    }
+}
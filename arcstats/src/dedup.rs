@@ -0,0 +1,218 @@
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::models::ItemWithPlace;
+
+/// Fraction of the shorter item that must be covered by another item for the two to count
+/// as copies of the same time span; Arc's neighbouring items share a few seconds at their edges
+const MIN_OVERLAP_FRACTION: f64 = 0.5;
+
+/// Why an item was dropped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropReason {
+    /// Another item has the same ID (e.g. in two monthly files after a re-export)
+    DuplicateId,
+    /// Another item covers most of the same time range
+    Overlapping,
+}
+
+/// An item dropped by `dedupe_items`
+#[derive(Debug, Clone)]
+pub struct DroppedItem {
+    pub item_id: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub reason: DropReason,
+    /// ID of the item that was kept in its place
+    pub kept_item_id: String,
+}
+
+/// What `dedupe_items` dropped
+#[derive(Debug, Clone, Default)]
+pub struct DedupReport {
+    pub dropped: Vec<DroppedItem>,
+}
+
+impl DedupReport {
+    /// Returns whether nothing was dropped
+    pub fn is_empty(&self) -> bool {
+        self.dropped.is_empty()
+    }
+
+    /// Counts dropped items with the given reason
+    pub fn count(&self, reason: DropReason) -> usize {
+        self.dropped
+            .iter()
+            .filter(|dropped| dropped.reason == reason)
+            .count()
+    }
+
+    fn record(&mut self, dropped: &ItemWithPlace, kept: &ItemWithPlace, reason: DropReason) {
+        self.dropped.push(DroppedItem {
+            item_id: dropped.item.base.id.to_string(),
+            start: dropped.item.start_datetime(),
+            end: dropped.item.end_datetime(),
+            reason,
+            kept_item_id: kept.item.base.id.to_string(),
+        });
+    }
+}
+
+/// Removes duplicated and overlapping items, keeping the most recently saved copy
+///
+/// Re-exporting from Arc can leave the same item in two monthly files, or a re-segmented
+/// copy of a time span under new IDs, which would otherwise be counted twice. Deleted items
+/// are left alone since the stats skip them anyway. Returns the remaining items sorted by
+/// start date.
+pub fn dedupe_items(items: Vec<ItemWithPlace>) -> (Vec<ItemWithPlace>, DedupReport) {
+    let mut report = DedupReport::default();
+
+    // Same ID: keep the copy saved last
+    let mut by_id: HashMap<Rc<str>, ItemWithPlace> = HashMap::new();
+    let mut deleted = Vec::new();
+    for item in items {
+        if item.item.base.deleted {
+            deleted.push(item);
+            continue;
+        }
+        match by_id.remove(&item.item.base.id) {
+            Some(existing) => {
+                let (kept, dropped) = newest_first(existing, item);
+                report.record(&dropped, &kept, DropReason::DuplicateId);
+                by_id.insert(kept.item.base.id.clone(), kept);
+            }
+            None => {
+                by_id.insert(item.item.base.id.clone(), item);
+            }
+        }
+    }
+
+    let mut unique: Vec<ItemWithPlace> = by_id.into_values().collect();
+    unique.sort_by(|a, b| {
+        a.item
+            .base
+            .start_date
+            .total_cmp(&b.item.base.start_date)
+            .then_with(|| a.item.base.id.cmp(&b.item.base.id))
+    });
+
+    // Overlapping time ranges: keep the copy saved last
+    let mut kept_items: Vec<ItemWithPlace> = Vec::with_capacity(unique.len());
+    for item in unique {
+        match kept_items.pop() {
+            Some(previous) if overlaps(&previous, &item) => {
+                let (kept, dropped) = newest_first(previous, item);
+                report.record(&dropped, &kept, DropReason::Overlapping);
+                kept_items.push(kept);
+            }
+            Some(previous) => {
+                kept_items.push(previous);
+                kept_items.push(item);
+            }
+            None => kept_items.push(item),
+        }
+    }
+
+    kept_items.extend(deleted);
+    kept_items.sort_by(|a, b| a.item.base.start_date.total_cmp(&b.item.base.start_date));
+    (kept_items, report)
+}
+
+/// Orders two copies as (kept, dropped) by when they were last saved
+fn newest_first(a: ItemWithPlace, b: ItemWithPlace) -> (ItemWithPlace, ItemWithPlace) {
+    if b.item.base.last_saved > a.item.base.last_saved {
+        (b, a)
+    } else {
+        (a, b)
+    }
+}
+
+/// Returns whether two items cover mostly the same time range
+fn overlaps(a: &ItemWithPlace, b: &ItemWithPlace) -> bool {
+    let (a, b) = (&a.item.base, &b.item.base);
+    let overlap = a.end_date.min(b.end_date) - a.start_date.max(b.start_date);
+    let shorter = (a.end_date - a.start_date).min(b.end_date - b.start_date);
+    overlap > 0.0 && overlap >= shorter * MIN_OVERLAP_FRACTION
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{BaseItem, Item, ItemVariant, VisitDetails};
+
+    fn item(id: &str, start: f64, end: f64, last_saved: f64) -> ItemWithPlace {
+        ItemWithPlace {
+            item: Item {
+                base: BaseItem {
+                    id: id.into(),
+                    start_date: start,
+                    end_date: end,
+                    last_saved,
+                    source: "LocoKit".into(),
+                    source_version: None,
+                    is_visit: true,
+                    deleted: false,
+                    disabled: false,
+                    samples_changed: None,
+                    step_count: None,
+                    active_energy_burned: None,
+                    max_heart_rate: None,
+                    average_heart_rate: None,
+                    previous_item_id: None,
+                    next_item_id: None,
+                },
+                variant: ItemVariant::Visit(VisitDetails {
+                    item_id: id.into(),
+                    place_id: None,
+                    latitude: 0.0,
+                    longitude: 0.0,
+                    radius_mean: 0.0,
+                    radius_sd: 0.0,
+                    confirmed_place: false,
+                    uncertain_place: false,
+                    last_saved,
+                    street_address: None,
+                }),
+            },
+            place: None,
+        }
+    }
+
+    fn ids(items: &[ItemWithPlace]) -> Vec<&str> {
+        items.iter().map(|i| &*i.item.base.id).collect()
+    }
+
+    #[test]
+    fn test_duplicate_ids_keep_newest() {
+        let items = vec![
+            item("a", 0.0, 3600.0, 10.0),
+            item("b", 3600.0, 7200.0, 10.0),
+            item("a", 0.0, 3000.0, 20.0),
+        ];
+        let (items, report) = dedupe_items(items);
+
+        assert_eq!(ids(&items), ["a", "b"]);
+        assert_eq!(items[0].item.base.end_date, 3000.0);
+        assert_eq!(report.count(DropReason::DuplicateId), 1);
+        assert_eq!(report.count(DropReason::Overlapping), 0);
+    }
+
+    #[test]
+    fn test_overlapping_items() {
+        let items = vec![
+            item("old", 0.0, 3600.0, 10.0),
+            // Re-segmented copy of the same visit
+            item("new", 60.0, 3600.0, 20.0),
+            // Shares only its first 30 seconds with the visit before it
+            item("trip", 3570.0, 4800.0, 10.0),
+        ];
+        let (items, report) = dedupe_items(items);
+
+        assert_eq!(ids(&items), ["new", "trip"]);
+        assert_eq!(report.dropped.len(), 1);
+        assert_eq!(report.dropped[0].item_id, "old");
+        assert_eq!(report.dropped[0].kept_item_id, "new");
+        assert_eq!(report.dropped[0].reason, DropReason::Overlapping);
+    }
+}
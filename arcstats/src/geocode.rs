@@ -0,0 +1,358 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::models::{ItemWithPlace, Place};
+use crate::provider::PlaceProvider;
+
+/// Place names that say nothing about the place, compared case-insensitively
+const GENERIC_NAMES: [&str; 5] = [
+    "unknown place",
+    "unknown",
+    "unnamed place",
+    "dropped pin",
+    "place",
+];
+
+/// Farthest a GeoNames city can be from a place to be used as its locality
+const MAX_CITY_DISTANCE_KM: f64 = 50.0;
+
+/// Mean radius of the Earth, for great-circle distances
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// What a reverse geocoder knows about a coordinate
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct GeocodedPlace {
+    /// Name of the point of interest or building, if any
+    pub name: Option<String>,
+    /// City, town or village
+    pub locality: Option<String>,
+    /// ISO 3166-1 alpha-2 country code
+    pub country_code: Option<String>,
+}
+
+/// Looks up names for coordinates
+pub trait ReverseGeocoder: Send + Sync {
+    /// Returns what is known about the coordinate, or `None` if nothing is
+    fn reverse(&self, latitude: f64, longitude: f64) -> Result<Option<GeocodedPlace>>;
+}
+
+/// Returns whether a place has an empty or generic name that should be geocoded
+pub fn needs_name(place: &Place) -> bool {
+    let name = place.name.trim();
+    name.is_empty()
+        || GENERIC_NAMES
+            .iter()
+            .any(|generic| name.eq_ignore_ascii_case(generic))
+}
+
+/// Fills in the name and locality of a place from a geocoding result
+///
+/// Without a point-of-interest name the place is named after its locality ("Near Springfield"),
+/// which is still more useful than "Unknown Place" in the top places list.
+pub fn apply_geocoded(place: &Place, geocoded: &GeocodedPlace) -> Place {
+    let mut place = place.clone();
+    if let Some(name) = geocoded.name.clone().or_else(|| {
+        geocoded
+            .locality
+            .as_ref()
+            .map(|locality| format!("Near {}", locality))
+    }) {
+        place.name = name;
+    }
+    if place.locality.is_none() {
+        place.locality = geocoded.locality.clone();
+    }
+    if place.country_code.is_none() {
+        place.country_code = geocoded.country_code.clone();
+    }
+    place
+}
+
+/// Geocodes every distinct place that needs a name, replacing it in all items that share it
+///
+/// A failing lookup leaves the place as it is, so stats still work when the geocoder is
+/// unavailable.
+pub fn enrich_places(items: &mut [ItemWithPlace], geocoder: &dyn ReverseGeocoder) {
+    let mut enriched: HashMap<String, Rc<Place>> = HashMap::new();
+
+    for item in items.iter_mut() {
+        let Some(place) = &item.place else {
+            continue;
+        };
+        if !needs_name(place) {
+            continue;
+        }
+
+        let replacement = enriched.entry(place.id.clone()).or_insert_with(|| {
+            match geocoder.reverse(place.latitude, place.longitude) {
+                Ok(Some(geocoded)) => Rc::new(apply_geocoded(place, &geocoded)),
+                Ok(None) => Rc::clone(place),
+                Err(e) => {
+                    tracing::warn!(
+                        place = place.id.as_str(),
+                        "Reverse geocoding failed: {:#}",
+                        e
+                    );
+                    Rc::clone(place)
+                }
+            }
+        });
+        item.place = Some(Rc::clone(replacement));
+    }
+}
+
+/// Wraps a place provider, naming its unnamed places with a reverse geocoder
+pub struct GeocodedProvider {
+    inner: Box<dyn PlaceProvider>,
+    geocoder: Arc<dyn ReverseGeocoder>,
+}
+
+impl GeocodedProvider {
+    pub fn new(inner: Box<dyn PlaceProvider>, geocoder: Arc<dyn ReverseGeocoder>) -> Self {
+        Self { inner, geocoder }
+    }
+}
+
+impl PlaceProvider for GeocodedProvider {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn load_items_with_places(&self) -> Result<Vec<ItemWithPlace>> {
+        let mut items = self.inner.load_items_with_places()?;
+        enrich_places(&mut items, self.geocoder.as_ref());
+        Ok(items)
+    }
+
+    fn load_items_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ItemWithPlace>> {
+        let mut items = self.inner.load_items_between(start, end)?;
+        enrich_places(&mut items, self.geocoder.as_ref());
+        Ok(items)
+    }
+
+    fn modified_path(&self) -> PathBuf {
+        self.inner.modified_path()
+    }
+}
+
+/// A city from a GeoNames dump
+#[derive(Debug, Clone)]
+struct City {
+    name: String,
+    latitude: f64,
+    longitude: f64,
+    country_code: String,
+}
+
+/// Offline reverse geocoder using a GeoNames cities dump (e.g. `cities15000.txt`)
+///
+/// Only knows localities, so places are named after the nearest city within 50 km.
+#[derive(Debug, Clone)]
+pub struct OfflineGeocoder {
+    cities: Vec<City>,
+}
+
+impl OfflineGeocoder {
+    /// Loads a tab-separated GeoNames file from https://download.geonames.org/export/dump/
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .context(format!("Failed to read GeoNames file: {:?}", path))?;
+        Self::parse(&content).context(format!("Failed to parse GeoNames file: {:?}", path))
+    }
+
+    /// Parses GeoNames rows (name in column 2, latitude/longitude in 5/6, country in 9)
+    pub fn parse(content: &str) -> Result<Self> {
+        let cities = content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .enumerate()
+            .map(|(index, line)| {
+                let columns: Vec<&str> = line.split('\t').collect();
+                let column = |n: usize| {
+                    columns
+                        .get(n)
+                        .copied()
+                        .context(format!("Row {} has too few columns", index + 1))
+                };
+                Ok(City {
+                    name: column(1)?.to_string(),
+                    latitude: column(4)?
+                        .parse()
+                        .context(format!("Invalid latitude in row {}", index + 1))?,
+                    longitude: column(5)?
+                        .parse()
+                        .context(format!("Invalid longitude in row {}", index + 1))?,
+                    country_code: column(8)?.to_string(),
+                })
+            })
+            .collect::<Result<Vec<City>>>()?;
+
+        Ok(Self { cities })
+    }
+}
+
+impl ReverseGeocoder for OfflineGeocoder {
+    fn reverse(&self, latitude: f64, longitude: f64) -> Result<Option<GeocodedPlace>> {
+        let nearest = self
+            .cities
+            .iter()
+            .map(|city| {
+                let distance = distance_km(latitude, longitude, city.latitude, city.longitude);
+                (city, distance)
+            })
+            .filter(|(_, distance)| *distance <= MAX_CITY_DISTANCE_KM)
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+
+        Ok(nearest.map(|(city, _)| GeocodedPlace {
+            name: None,
+            locality: Some(city.name.clone()),
+            country_code: Some(city.country_code.clone()),
+        }))
+    }
+}
+
+/// Great-circle distance between two coordinates in kilometers (haversine formula)
+fn distance_km(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (lon2 - lon1).to_radians();
+
+    let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{BaseItem, Item, ItemVariant, VisitDetails};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const CITIES: &str = "4409896\tSpringfield\tSpringfield\t\t37.21533\t-93.29824\tP\tPPLA2\tUS\n\
+                          4393217\tKansas City\tKansas City\t\t39.09973\t-94.57857\tP\tPPL\tUS\n";
+
+    fn place(id: &str, name: &str) -> Rc<Place> {
+        Rc::new(Place {
+            id: id.to_string(),
+            name: name.to_string(),
+            latitude: 37.2,
+            longitude: -93.3,
+            radius_mean: 50.0,
+            radius_sd: 0.0,
+            visit_count: 1,
+            visit_days: 1,
+            last_saved: 0.0,
+            is_stale: false,
+            source: "LocoKit".into(),
+            rtree_id: 0,
+            seconds_from_gmt: None,
+            street_address: None,
+            locality: None,
+            country_code: None,
+            google_place_id: None,
+            google_primary_type: None,
+            last_visit_date: None,
+        })
+    }
+
+    fn visit(place: &Rc<Place>) -> ItemWithPlace {
+        ItemWithPlace {
+            item: Item {
+                base: BaseItem {
+                    id: "item".into(),
+                    start_date: 0.0,
+                    end_date: 60.0,
+                    last_saved: 0.0,
+                    source: "LocoKit".into(),
+                    source_version: None,
+                    is_visit: true,
+                    deleted: false,
+                    disabled: false,
+                    samples_changed: None,
+                    step_count: None,
+                    active_energy_burned: None,
+                    max_heart_rate: None,
+                    average_heart_rate: None,
+                    previous_item_id: None,
+                    next_item_id: None,
+                },
+                variant: ItemVariant::Visit(VisitDetails {
+                    item_id: "item".into(),
+                    place_id: Some(place.id.as_str().into()),
+                    latitude: place.latitude,
+                    longitude: place.longitude,
+                    radius_mean: 50.0,
+                    radius_sd: 0.0,
+                    confirmed_place: false,
+                    uncertain_place: false,
+                    last_saved: 0.0,
+                    street_address: None,
+                }),
+            },
+            place: Some(Rc::clone(place)),
+        }
+    }
+
+    /// Counts lookups so tests can check each place is geocoded once
+    struct CountingGeocoder(AtomicUsize);
+
+    impl ReverseGeocoder for CountingGeocoder {
+        fn reverse(&self, _latitude: f64, _longitude: f64) -> Result<Option<GeocodedPlace>> {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            Ok(Some(GeocodedPlace {
+                name: Some("Grace Church".to_string()),
+                locality: Some("Springfield".to_string()),
+                country_code: Some("US".to_string()),
+            }))
+        }
+    }
+
+    #[test]
+    fn test_needs_name() {
+        assert!(needs_name(&place("a", "")));
+        assert!(needs_name(&place("a", "  Unknown Place ")));
+        assert!(!needs_name(&place("a", "Home")));
+    }
+
+    #[test]
+    fn test_enrich_places() {
+        let unknown = place("a", "Unknown Place");
+        let home = place("b", "Home");
+        let mut items = vec![visit(&unknown), visit(&home), visit(&unknown)];
+
+        let geocoder = CountingGeocoder(AtomicUsize::new(0));
+        enrich_places(&mut items, &geocoder);
+
+        assert_eq!(geocoder.0.load(Ordering::Relaxed), 1);
+        let enriched = items[0].place.as_ref().unwrap();
+        assert_eq!(enriched.name, "Grace Church");
+        assert_eq!(enriched.locality.as_deref(), Some("Springfield"));
+        assert!(Rc::ptr_eq(enriched, items[2].place.as_ref().unwrap()));
+        assert_eq!(items[1].place.as_ref().unwrap().name, "Home");
+    }
+
+    #[test]
+    fn test_offline_geocoder() {
+        let geocoder = OfflineGeocoder::parse(CITIES).unwrap();
+
+        let geocoded = geocoder.reverse(37.19, -93.25).unwrap().unwrap();
+        assert_eq!(geocoded.locality.as_deref(), Some("Springfield"));
+        assert_eq!(geocoded.country_code.as_deref(), Some("US"));
+        assert_eq!(
+            apply_geocoded(&place("a", ""), &geocoded).name,
+            "Near Springfield"
+        );
+
+        // Nowhere near either city
+        assert!(geocoder.reverse(0.0, 0.0).unwrap().is_none());
+    }
+}
@@ -0,0 +1,129 @@
+//! String interning for deserializing Arc exports
+//!
+//! Multi-year exports repeat the same strings millions of times: every item carries its
+//! source ("LocoKit"), the ID of its place and the IDs of its neighbours, and visit details
+//! repeat the item ID. Interned fields share one `Rc<str>` allocation per distinct value
+//! within a file instead of allocating a `String` each time.
+
+use serde::de::{self, Deserializer, Visitor};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt;
+use std::rc::Rc;
+
+thread_local! {
+    static INTERNER: RefCell<Option<HashSet<Rc<str>>>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` with a fresh interner, so strings deserialized by it share allocations
+///
+/// The interner is dropped afterwards; only the strings still referenced by the
+/// deserialized values are kept alive. Without an active interner, interned fields fall back
+/// to one allocation per value.
+pub fn with_interner<T>(f: impl FnOnce() -> T) -> T {
+    let previous = INTERNER.with(|interner| interner.replace(Some(HashSet::new())));
+    let result = f();
+    INTERNER.with(|interner| interner.replace(previous));
+    result
+}
+
+/// Returns the shared copy of a string from the active interner
+pub fn intern(value: &str) -> Rc<str> {
+    INTERNER.with(|interner| match interner.borrow_mut().as_mut() {
+        Some(strings) => match strings.get(value) {
+            Some(shared) => Rc::clone(shared),
+            None => {
+                let shared: Rc<str> = Rc::from(value);
+                strings.insert(Rc::clone(&shared));
+                shared
+            }
+        },
+        None => Rc::from(value),
+    })
+}
+
+struct InternVisitor;
+
+impl<'de> Visitor<'de> for InternVisitor {
+    type Value = Rc<str>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        Ok(intern(value))
+    }
+}
+
+/// Deserializes an interned string (for `#[serde(deserialize_with = "...")]`)
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Rc<str>, D::Error> {
+    deserializer.deserialize_str(InternVisitor)
+}
+
+/// Deserializes an optional interned string; use together with `#[serde(default)]`
+pub fn deserialize_option<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Rc<str>>, D::Error> {
+    struct OptionVisitor;
+
+    impl<'de> Visitor<'de> for OptionVisitor {
+        type Value = Option<Rc<str>>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a string or null")
+        }
+
+        fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_some<D: Deserializer<'de>>(
+            self,
+            deserializer: D,
+        ) -> Result<Self::Value, D::Error> {
+            deserialize(deserializer).map(Some)
+        }
+    }
+
+    deserializer.deserialize_option(OptionVisitor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    struct Row {
+        #[serde(deserialize_with = "deserialize")]
+        source: Rc<str>,
+        #[serde(default, deserialize_with = "deserialize_option")]
+        parent: Option<Rc<str>>,
+    }
+
+    #[test]
+    fn test_interned_strings_are_shared() {
+        let json = r#"[{"source": "LocoKit", "parent": null},
+                       {"source": "LocoKit", "parent": "LocoKit"},
+                       {"source": "Arc"}]"#;
+        let rows: Vec<Row> = with_interner(|| serde_json::from_str(json)).unwrap();
+
+        assert!(Rc::ptr_eq(&rows[0].source, &rows[1].source));
+        // Values are shared across fields too
+        assert!(Rc::ptr_eq(
+            &rows[0].source,
+            rows[1].parent.as_ref().unwrap()
+        ));
+        assert_eq!(&*rows[2].source, "Arc");
+        assert!(rows[0].parent.is_none() && rows[2].parent.is_none());
+
+        // Without an interner every value gets its own allocation
+        let rows: Vec<Row> = serde_json::from_str(json).unwrap();
+        assert!(!Rc::ptr_eq(&rows[0].source, &rows[1].source));
+    }
+}
@@ -23,17 +23,33 @@
 //! }
 //! ```
 
+pub mod dedup;
+pub mod geocode;
+pub mod intern;
 pub mod loader;
 pub mod models;
+#[cfg(feature = "nominatim")]
+pub mod nominatim;
+pub mod overrides;
+pub mod provider;
 pub mod stats;
 
 // Re-export commonly used types and functions
 pub use loader::{
-    PlaceCache, load_all_items, load_all_items_with_places, load_all_places, load_items_for_month,
-    load_items_with_places, load_metadata, load_places_file,
+    PlaceCache, list_item_months, load_all_items, load_all_items_with_place_cache,
+    load_all_items_with_places, load_all_places, load_items_between, load_items_for_month,
+    load_items_with_place_cache_between, load_items_with_places, load_metadata, load_places_file,
 };
 pub use models::{
     BaseItem, ExportStats, Item, ItemWithPlace, Metadata, Place, TripDetails, VisitDetails,
-    apple_timestamp_to_datetime,
+    apple_timestamp_to_datetime, datetime_to_apple_timestamp,
+};
+pub use overrides::{PlaceOverride, PlaceOverrides};
+pub use provider::{ArcExport, PlaceProvider};
+pub use stats::{
+    DayCoverage, HomeRatioWeek, MonthNewPlaces, PlaceVisit, WeekStats, get_coverage_gaps,
+    get_coverage_gaps_from, get_data_range, get_data_range_from, get_home_ratio_last_n_weeks_from,
+    get_last_12_weeks_stats, get_last_n_weeks_stats, get_last_n_weeks_stats_from,
+    get_new_places_last_n_months_from, get_notable_visits_on_this_day_from,
+    get_visited_places_from,
 };
-pub use stats::{WeekStats, get_last_12_weeks_stats};
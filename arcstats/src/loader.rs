@@ -1,15 +1,23 @@
+use crate::intern;
 use crate::models::{Item, ItemWithPlace, Metadata, Place};
+use crate::overrides::PlaceOverrides;
 use anyhow::{Context, Result};
-use std::collections::HashMap;
+use chrono::{DateTime, Datelike, Months, Utc};
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
 /// Cache for places, shared via Rc
+///
+/// Place files are sharded by the first character of the place ID, and only the shards of
+/// requested places are read (each at most once).
 #[derive(Debug, Clone)]
 pub struct PlaceCache {
     places: HashMap<String, Rc<Place>>,
+    loaded_shards: HashSet<char>,
     export_path: PathBuf,
+    overrides: PlaceOverrides,
 }
 
 impl PlaceCache {
@@ -17,31 +25,53 @@ impl PlaceCache {
     pub fn new<P: AsRef<Path>>(export_path: P) -> Self {
         Self {
             places: HashMap::new(),
+            loaded_shards: HashSet::new(),
             export_path: export_path.as_ref().to_path_buf(),
+            overrides: PlaceOverrides::default(),
         }
     }
 
+    /// Create a new empty place cache that applies user overrides to the places it loads
+    pub fn with_overrides<P: AsRef<Path>>(export_path: P, overrides: PlaceOverrides) -> Self {
+        Self {
+            overrides,
+            ..Self::new(export_path)
+        }
+    }
+
+    /// Path of the export directory places are loaded from
+    pub fn export_path(&self) -> &Path {
+        &self.export_path
+    }
+
     /// Get a place by ID, loading it if necessary
+    ///
+    /// A place merged into another by the overrides resolves to the merge target.
     pub fn get_place(&mut self, place_id: &str) -> Result<Rc<Place>> {
+        let place_id = self.overrides.resolve_id(place_id)?.to_string();
+        let place_id = place_id.as_str();
+
         // Check if already cached
         if let Some(place) = self.places.get(place_id) {
             return Ok(Rc::clone(place));
         }
 
-        // Load the place file based on first character of ID
+        // Load the place file based on first character of ID, unless it was already read
         let first_char = place_id
             .chars()
             .next()
             .context("Place ID is empty")?
             .to_ascii_uppercase();
 
-        let places = load_places_file(&self.export_path, first_char)?;
+        if self.loaded_shards.insert(first_char) {
+            let places = load_places_file(&self.export_path, first_char)?;
 
-        // Cache all places from this file
-        for place in places {
-            let place_rc = Rc::new(place);
-            self.places
-                .insert(place_rc.id.clone(), Rc::clone(&place_rc));
+            // Cache all places from this file
+            for place in places {
+                let place_rc = Rc::new(self.overrides.apply(place));
+                self.places
+                    .insert(place_rc.id.clone(), Rc::clone(&place_rc));
+            }
         }
 
         // Now retrieve the requested place
@@ -56,6 +86,11 @@ impl PlaceCache {
         self.places.len()
     }
 
+    /// Get number of place files read so far
+    pub fn loaded_shard_count(&self) -> usize {
+        self.loaded_shards.len()
+    }
+
     /// Check if cache is empty
     pub fn is_empty(&self) -> bool {
         self.places.is_empty()
@@ -79,7 +114,8 @@ pub fn load_places_file<P: AsRef<Path>>(export_path: P, first_char: char) -> Res
     let content = fs::read_to_string(&places_path)
         .context(format!("Failed to read places file: {:?}", places_path))?;
 
-    serde_json::from_str(&content).context(format!("Failed to parse places file: {}", filename))
+    intern::with_interner(|| serde_json::from_str(&content))
+        .context(format!("Failed to parse places file: {}", filename))
 }
 
 /// Load all places from all files (0-9, A-F)
@@ -106,13 +142,13 @@ pub fn load_items_for_month<P: AsRef<Path>>(export_path: P, year_month: &str) ->
     let content = fs::read_to_string(&items_path)
         .context(format!("Failed to read items file: {:?}", items_path))?;
 
-    serde_json::from_str(&content).context(format!("Failed to parse items file: {}", filename))
+    intern::with_interner(|| serde_json::from_str(&content))
+        .context(format!("Failed to parse items file: {}", filename))
 }
 
-/// Load all items from all available month files
-pub fn load_all_items<P: AsRef<Path>>(export_path: P) -> Result<Vec<Item>> {
+/// List the months (e.g., "2025-08") that have an items file, in chronological order
+pub fn list_item_months<P: AsRef<Path>>(export_path: P) -> Result<Vec<String>> {
     let items_dir = export_path.as_ref().join("items");
-    let mut all_items = Vec::new();
 
     // Read the items directory
     let entries = fs::read_dir(&items_dir)
@@ -136,8 +172,15 @@ pub fn load_all_items<P: AsRef<Path>>(export_path: P) -> Result<Vec<Item>> {
     // Sort chronologically
     month_files.sort();
 
+    Ok(month_files)
+}
+
+/// Load all items from all available month files
+pub fn load_all_items<P: AsRef<Path>>(export_path: P) -> Result<Vec<Item>> {
+    let mut all_items = Vec::new();
+
     // Load each month
-    for year_month in month_files {
+    for year_month in list_item_months(&export_path)? {
         let mut items = load_items_for_month(&export_path, &year_month)?;
         all_items.append(&mut items);
     }
@@ -145,32 +188,75 @@ pub fn load_all_items<P: AsRef<Path>>(export_path: P) -> Result<Vec<Item>> {
     Ok(all_items)
 }
 
+/// Load the items overlapping the time range, reading only the month files that can hold them
+///
+/// Items are filed under the month they start in, so the month before `start` is read too
+/// for items running into the range.
+pub fn load_items_between<P: AsRef<Path>>(
+    export_path: P,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<Item>> {
+    let first_month = start
+        .date_naive()
+        .with_day(1)
+        .and_then(|date| date.checked_sub_months(Months::new(1)))
+        .context("Range start is out of range")?
+        .format("%Y-%m")
+        .to_string();
+    let last_month = end.format("%Y-%m").to_string();
+
+    let mut items = Vec::new();
+    for year_month in list_item_months(&export_path)? {
+        if year_month < first_month || year_month > last_month {
+            continue;
+        }
+        items.extend(
+            load_items_for_month(&export_path, &year_month)?
+                .into_iter()
+                .filter(|item| item.start_datetime() < end && item.end_datetime() > start),
+        );
+    }
+
+    Ok(items)
+}
+
+/// Load the items overlapping the time range, resolving places through the cache
+///
+/// Only the month files and place files the range needs are read.
+pub fn load_items_with_place_cache_between(
+    place_cache: &mut PlaceCache,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<ItemWithPlace>> {
+    let items = load_items_between(place_cache.export_path(), start, end)?;
+    resolve_places(items, place_cache)
+}
+
 /// Load items with their associated places resolved
 pub fn load_items_with_places<P: AsRef<Path>>(
     export_path: P,
     year_month: &str,
 ) -> Result<Vec<ItemWithPlace>> {
     let items = load_items_for_month(&export_path, year_month)?;
-    let mut place_cache = PlaceCache::new(&export_path);
-    let mut items_with_places = Vec::new();
-
-    for item in items {
-        let place = if let Some(place_id) = item.place_id() {
-            Some(place_cache.get_place(place_id)?)
-        } else {
-            None
-        };
-
-        items_with_places.push(ItemWithPlace { item, place });
-    }
-
-    Ok(items_with_places)
+    resolve_places(items, &mut PlaceCache::new(&export_path))
 }
 
 /// Load all items with their associated places resolved
 pub fn load_all_items_with_places<P: AsRef<Path>>(export_path: P) -> Result<Vec<ItemWithPlace>> {
-    let items = load_all_items(&export_path)?;
-    let mut place_cache = PlaceCache::new(&export_path);
+    load_all_items_with_place_cache(&mut PlaceCache::new(&export_path))
+}
+
+/// Load all items from the place cache's export, resolving places through the cache
+///
+/// Use a cache created with `PlaceCache::with_overrides` to apply user corrections.
+pub fn load_all_items_with_place_cache(place_cache: &mut PlaceCache) -> Result<Vec<ItemWithPlace>> {
+    let items = load_all_items(place_cache.export_path())?;
+    resolve_places(items, place_cache)
+}
+
+/// Pair items with their places, loading only the place files they reference
+fn resolve_places(items: Vec<Item>, place_cache: &mut PlaceCache) -> Result<Vec<ItemWithPlace>> {
     let mut items_with_places = Vec::new();
 
     for item in items {
@@ -248,6 +334,88 @@ mod tests {
         assert!(Rc::ptr_eq(&place, &place2));
     }
 
+    #[test]
+    fn test_place_cache_with_overrides() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("places")).unwrap();
+        let place = |id: &str, name: &str| {
+            format!(
+                r#"{{"id": "{}", "name": "{}", "latitude": 38.5, "longitude": -90.4,
+                    "radiusMean": 50, "radiusSD": 0, "visitCount": 1, "visitDays": 1,
+                    "lastSaved": 0, "isStale": false, "source": "LocoKit", "rtreeId": 0}}"#,
+                id, name
+            )
+        };
+        fs::write(
+            dir.path().join("places/A.json"),
+            format!(
+                "[{}, {}]",
+                place("A1", "Unknown Place"),
+                place("A2", "Parking Lot")
+            ),
+        )
+        .unwrap();
+
+        let overrides: PlaceOverrides = toml::from_str(
+            "[places.A1]\nname = \"Grace Church\"\ncategory = \"church\"\n\n\
+             [places.A2]\nmerge_into = \"A1\"\n",
+        )
+        .unwrap();
+        let mut cache = PlaceCache::with_overrides(dir.path(), overrides);
+
+        let church = cache.get_place("A1").unwrap();
+        assert_eq!(church.name, "Grace Church");
+        assert!(church.is_church());
+        assert!(Rc::ptr_eq(&church, &cache.get_place("A2").unwrap()));
+    }
+
+    #[test]
+    fn test_load_items_between_reads_only_needed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("items")).unwrap();
+        fs::create_dir(dir.path().join("places")).unwrap();
+        let visit = |id: &str, start: f64, end: f64, place_id: &str| {
+            format!(
+                r#"{{"base": {{"id": "{id}", "startDate": {start}, "endDate": {end},
+                    "lastSaved": 0, "source": "LocoKit", "isVisit": true, "deleted": false,
+                    "disabled": false}},
+                  "visit": {{"itemId": "{id}", "placeId": "{place_id}", "latitude": 0,
+                    "longitude": 0, "radiusMean": 0, "radiusSD": 0, "confirmedPlace": false,
+                    "uncertainPlace": false, "lastSaved": 0}}}}"#
+            )
+        };
+        let place = |id: &str| {
+            format!(
+                r#"[{{"id": "{}", "name": "Place {}", "latitude": 0, "longitude": 0,
+                    "radiusMean": 0, "radiusSD": 0, "visitCount": 1, "visitDays": 1,
+                    "lastSaved": 0, "isStale": false, "source": "LocoKit", "rtreeId": 0}}]"#,
+                id, id
+            )
+        };
+        let items = dir.path().join("items");
+        // Months outside the range are never read, so they may even be broken
+        fs::write(items.join("2025-06.json"), "not json").unwrap();
+        // 2025-07-31 12:00 UTC for two days, running into August
+        let july = visit("july", 775656000.0, 775828800.0, "2B");
+        fs::write(items.join("2025-07.json"), format!("[{}]", july)).unwrap();
+        let august = visit("august", 776520000.0, 776523600.0, "3C");
+        fs::write(items.join("2025-08.json"), format!("[{}]", august)).unwrap();
+        let september = visit("september", 778766400.0, 778770000.0, "4D");
+        fs::write(items.join("2025-09.json"), format!("[{}]", september)).unwrap();
+        fs::write(dir.path().join("places/2.json"), place("2B")).unwrap();
+        fs::write(dir.path().join("places/3.json"), place("3C")).unwrap();
+
+        let start = "2025-08-01T00:00:00Z".parse().unwrap();
+        let end = "2025-09-01T00:00:00Z".parse().unwrap();
+        let mut cache = PlaceCache::new(dir.path());
+        let items = load_items_with_place_cache_between(&mut cache, start, end).unwrap();
+
+        let ids: Vec<&str> = items.iter().map(|i| &*i.item.base.id).collect();
+        assert_eq!(ids, ["july", "august"]);
+        assert_eq!(items[1].place.as_ref().unwrap().name, "Place 3C");
+        assert_eq!(cache.loaded_shard_count(), 2);
+    }
+
     #[test]
     fn test_load_items_for_month() {
         let items = load_items_for_month(EXPORT_PATH, "2025-08").expect("Failed to load items");
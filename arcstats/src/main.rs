@@ -1,7 +1,9 @@
 use arcstats::{load_items_for_month, load_metadata, load_places_file};
 use clap::Parser;
+use statsutils::format::format_distance;
 use std::fs;
 use std::path::PathBuf;
+use std::process;
 
 #[derive(Parser, Debug)]
 #[command(name = "arcstats")]
@@ -13,6 +15,14 @@ struct Args {
 
 fn main() {
     let args = Args::parse();
+
+    match statsutils::format::display_format_from_env() {
+        Ok(format) => statsutils::format::set_display_format(format),
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            process::exit(1);
+        }
+    }
     let export_path = &args.export_path;
 
     println!("Loading Arc export from: {:?}\n", export_path);
@@ -84,13 +94,21 @@ fn main() {
             month_files.sort();
 
             let mut total_items = 0;
+            let mut total_distance = 0.0;
             let mut failed_months = Vec::new();
 
             for year_month in month_files {
                 match load_items_for_month(export_path, &year_month) {
                     Ok(items) => {
-                        println!("✓ Loaded {}.json: {} items", year_month, items.len());
+                        let distance: f64 = items.iter().filter_map(|i| i.trip_distance()).sum();
+                        println!(
+                            "✓ Loaded {}.json: {} items, {} traveled",
+                            year_month,
+                            items.len(),
+                            format_distance(distance)
+                        );
                         total_items += items.len();
+                        total_distance += distance;
                     }
                     Err(e) => {
                         println!("✗ Failed to load {}.json", year_month);
@@ -108,6 +126,10 @@ fn main() {
             }
 
             println!("\nTotal items loaded: {}", total_items);
+            println!(
+                "Total distance traveled: {}",
+                format_distance(total_distance)
+            );
         }
         Err(e) => {
             println!("✗ Failed to read items directory: {}", e);
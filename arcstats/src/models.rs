@@ -2,6 +2,8 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::rc::Rc;
 
+use crate::intern;
+
 /// Metadata about the Arc export
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -40,7 +42,8 @@ pub struct Place {
     pub visit_days: u32,
     pub last_saved: f64,
     pub is_stale: bool,
-    pub source: String,
+    #[serde(deserialize_with = "intern::deserialize")]
+    pub source: Rc<str>,
     pub rtree_id: u32,
     pub seconds_from_gmt: Option<i32>,
     pub street_address: Option<String>,
@@ -71,12 +74,15 @@ pub struct Item {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BaseItem {
-    pub id: String,
+    #[serde(deserialize_with = "intern::deserialize")]
+    pub id: Rc<str>,
     pub start_date: f64,
     pub end_date: f64,
     pub last_saved: f64,
-    pub source: String,
-    pub source_version: Option<String>,
+    #[serde(deserialize_with = "intern::deserialize")]
+    pub source: Rc<str>,
+    #[serde(default, deserialize_with = "intern::deserialize_option")]
+    pub source_version: Option<Rc<str>>,
     pub is_visit: bool,
     pub deleted: bool,
     pub disabled: bool,
@@ -85,16 +91,20 @@ pub struct BaseItem {
     pub active_energy_burned: Option<f64>,
     pub max_heart_rate: Option<f64>,
     pub average_heart_rate: Option<f64>,
-    pub previous_item_id: Option<String>,
-    pub next_item_id: Option<String>,
+    #[serde(default, deserialize_with = "intern::deserialize_option")]
+    pub previous_item_id: Option<Rc<str>>,
+    #[serde(default, deserialize_with = "intern::deserialize_option")]
+    pub next_item_id: Option<Rc<str>>,
 }
 
 /// Details specific to visit items
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct VisitDetails {
-    pub item_id: String,
-    pub place_id: Option<String>,
+    #[serde(deserialize_with = "intern::deserialize")]
+    pub item_id: Rc<str>,
+    #[serde(default, deserialize_with = "intern::deserialize_option")]
+    pub place_id: Option<Rc<str>>,
     pub latitude: f64,
     pub longitude: f64,
     pub radius_mean: f64,
@@ -110,7 +120,8 @@ pub struct VisitDetails {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TripDetails {
-    pub item_id: String,
+    #[serde(deserialize_with = "intern::deserialize")]
+    pub item_id: Rc<str>,
     pub distance: f64,
     pub speed: f64,
     pub classified_activity_type: Option<u32>,
@@ -137,6 +148,14 @@ impl Item {
         matches!(self.variant, ItemVariant::Trip(_))
     }
 
+    /// Distance traveled in meters, if this item is a trip
+    pub fn trip_distance(&self) -> Option<f64> {
+        match &self.variant {
+            ItemVariant::Trip(trip) => Some(trip.distance),
+            _ => None,
+        }
+    }
+
     /// Get the place_id if this is a visit
     pub fn place_id(&self) -> Option<&str> {
         match &self.variant {
@@ -167,6 +186,15 @@ impl Place {
         apple_timestamp_to_datetime(self.last_saved)
     }
 
+    /// Check if this place is a church, by its name or its category
+    pub fn is_church(&self) -> bool {
+        self.name.to_lowercase().contains("church")
+            || self
+                .google_primary_type
+                .as_deref()
+                .is_some_and(|category| category.eq_ignore_ascii_case("church"))
+    }
+
     /// Get the last visit date as DateTime if available
     pub fn last_visit_datetime(&self) -> Option<DateTime<Utc>> {
         self.last_visit_date.map(apple_timestamp_to_datetime)
@@ -184,6 +212,17 @@ pub fn apple_timestamp_to_datetime(timestamp: f64) -> DateTime<Utc> {
     apple_epoch + chrono::Duration::milliseconds((timestamp * 1000.0) as i64)
 }
 
+/// Convert a DateTime to an Apple NSTimeInterval (seconds since 2001-01-01 00:00:00 UTC)
+///
+/// Used by other location providers to fill in the Arc model's timestamps.
+pub fn datetime_to_apple_timestamp(datetime: DateTime<Utc>) -> f64 {
+    let apple_epoch = DateTime::parse_from_rfc3339("2001-01-01T00:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+
+    (datetime - apple_epoch).num_milliseconds() as f64 / 1000.0
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -197,18 +236,21 @@ mod tests {
 
         // This should be sometime in 2025
         assert_eq!(dt.year(), 2025);
+
+        // Round-trips to the millisecond
+        assert_eq!(datetime_to_apple_timestamp(dt), 782854313.455);
     }
 
     #[test]
     fn test_item_helpers() {
         let visit_item = Item {
             base: BaseItem {
-                id: "test-id".to_string(),
+                id: "test-id".into(),
                 start_date: 778085854.759,
                 end_date: 778099244.398,
                 last_saved: 780692329.0,
-                source: "LocoKit".to_string(),
-                source_version: Some("9.0.0".to_string()),
+                source: "LocoKit".into(),
+                source_version: Some("9.0.0".into()),
                 is_visit: true,
                 deleted: false,
                 disabled: false,
@@ -218,11 +260,11 @@ mod tests {
                 max_heart_rate: Some(121.0),
                 average_heart_rate: Some(88.56),
                 previous_item_id: None,
-                next_item_id: Some("next-id".to_string()),
+                next_item_id: Some("next-id".into()),
             },
             variant: ItemVariant::Visit(VisitDetails {
-                item_id: "test-id".to_string(),
-                place_id: Some("place-id".to_string()),
+                item_id: "test-id".into(),
+                place_id: Some("place-id".into()),
                 latitude: 38.5,
                 longitude: -90.4,
                 radius_mean: 50.0,
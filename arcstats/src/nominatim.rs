@@ -0,0 +1,216 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::geocode::{GeocodedPlace, ReverseGeocoder};
+
+/// Minimum time between requests, per the public Nominatim usage policy
+const MIN_REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Longest time a request may take, so that an unresponsive server fails the lookup
+/// instead of holding up the caller
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// User agent sent with every request; Nominatim rejects requests without one
+const USER_AGENT: &str = concat!("lifestats/", env!("CARGO_PKG_VERSION"));
+
+/// Address fields that name a locality, most specific first
+const LOCALITY_FIELDS: [&str; 5] = ["city", "town", "village", "hamlet", "suburb"];
+
+/// Reverse geocoder using a Nominatim server, caching results on disk
+///
+/// Results (including misses) are cached by coordinates rounded to about a meter, so each
+/// place is only looked up once even across restarts. Requests are made without holding the
+/// cache lock, so cached lookups from other threads are never held up by a slow server.
+pub struct NominatimGeocoder {
+    endpoint: String,
+    agent: ureq::Agent,
+    cache_path: Option<PathBuf>,
+    state: Mutex<NominatimState>,
+}
+
+struct NominatimState {
+    cache: HashMap<String, Option<GeocodedPlace>>,
+    /// When the latest request was (or is scheduled to be) made
+    last_request: Option<Instant>,
+}
+
+/// The parts of a Nominatim `/reverse?format=jsonv2` response that are used
+#[derive(Debug, Deserialize)]
+struct ReverseResponse {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    address: HashMap<String, String>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+impl NominatimGeocoder {
+    /// Creates a geocoder for the server at `endpoint` (e.g. https://nominatim.openstreetmap.org)
+    ///
+    /// # Arguments
+    /// * `endpoint` - Base URL of the Nominatim server
+    /// * `cache_path` - JSON file to keep results in between runs; results are only cached in
+    ///   memory without one
+    ///
+    /// # Errors
+    /// Returns an error if the cache file exists but cannot be read
+    pub fn new(endpoint: &str, cache_path: Option<PathBuf>) -> Result<Self> {
+        let cache = match &cache_path {
+            Some(path) if path.exists() => {
+                let content = fs::read_to_string(path)
+                    .context(format!("Failed to read geocoding cache: {:?}", path))?;
+                serde_json::from_str(&content)
+                    .context(format!("Failed to parse geocoding cache: {:?}", path))?
+            }
+            _ => HashMap::new(),
+        };
+
+        Ok(Self {
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            agent: ureq::Agent::config_builder()
+                .timeout_global(Some(REQUEST_TIMEOUT))
+                .build()
+                .into(),
+            cache_path,
+            state: Mutex::new(NominatimState {
+                cache,
+                last_request: None,
+            }),
+        })
+    }
+
+    fn request(&self, latitude: f64, longitude: f64) -> Result<Option<GeocodedPlace>> {
+        let url = format!("{}/reverse", self.endpoint);
+        let response: ReverseResponse = self
+            .agent
+            .get(&url)
+            .header("User-Agent", USER_AGENT)
+            .query("format", "jsonv2")
+            .query("lat", latitude.to_string())
+            .query("lon", longitude.to_string())
+            .call()
+            .context(format!("Nominatim request to {} failed", url))?
+            .body_mut()
+            .read_json()
+            .context("Failed to parse Nominatim response")?;
+
+        Ok(parse_response(response))
+    }
+}
+
+impl ReverseGeocoder for NominatimGeocoder {
+    fn reverse(&self, latitude: f64, longitude: f64) -> Result<Option<GeocodedPlace>> {
+        let key = format!("{:.5},{:.5}", latitude, longitude);
+        let start = {
+            let mut state = self.state.lock().unwrap();
+            if let Some(cached) = state.cache.get(&key) {
+                return Ok(cached.clone());
+            }
+
+            // Reserve the next request slot so that concurrent lookups stay rate limited
+            let now = Instant::now();
+            let start = state
+                .last_request
+                .map_or(now, |last| (last + MIN_REQUEST_INTERVAL).max(now));
+            state.last_request = Some(start);
+            start
+        };
+
+        thread::sleep(start.saturating_duration_since(Instant::now()));
+        let geocoded = self.request(latitude, longitude)?;
+
+        let mut state = self.state.lock().unwrap();
+        state.cache.insert(key, geocoded.clone());
+
+        if let Some(path) = &self.cache_path {
+            let content = serde_json::to_string(&state.cache)?;
+            fs::write(path, content)
+                .context(format!("Failed to write geocoding cache: {:?}", path))?;
+        }
+
+        Ok(geocoded)
+    }
+}
+
+/// Converts a Nominatim response; "Unable to geocode" errors become `None`
+fn parse_response(response: ReverseResponse) -> Option<GeocodedPlace> {
+    if response.error.is_some() {
+        return None;
+    }
+
+    Some(GeocodedPlace {
+        name: response.name.filter(|name| !name.is_empty()),
+        locality: LOCALITY_FIELDS
+            .iter()
+            .find_map(|field| response.address.get(*field).cloned()),
+        country_code: response
+            .address
+            .get("country_code")
+            .map(|code| code.to_ascii_uppercase()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_response() {
+        let response: ReverseResponse = serde_json::from_str(
+            r#"{"place_id": 1, "name": "Grace Church", "display_name": "Grace Church, ...",
+                "address": {"amenity": "Grace Church", "town": "Webster Groves",
+                            "state": "Missouri", "country_code": "us"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            parse_response(response),
+            Some(GeocodedPlace {
+                name: Some("Grace Church".to_string()),
+                locality: Some("Webster Groves".to_string()),
+                country_code: Some("US".to_string()),
+            })
+        );
+
+        let response: ReverseResponse =
+            serde_json::from_str(r#"{"error": "Unable to geocode"}"#).unwrap();
+        assert_eq!(parse_response(response), None);
+    }
+
+    #[test]
+    fn test_cached_results_skip_requests() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache_path = dir.path().join("geocode.json");
+        fs::write(
+            &cache_path,
+            r#"{"38.50000,-90.40000": {"name": null, "locality": "Kirkwood", "country_code": "US"},
+                "0.00000,0.00000": null}"#,
+        )
+        .unwrap();
+
+        // The endpoint is unreachable, so any request would fail
+        let geocoder = NominatimGeocoder::new("http://127.0.0.1:9", Some(cache_path)).unwrap();
+
+        let geocoded = geocoder.reverse(38.5, -90.4).unwrap().unwrap();
+        assert_eq!(geocoded.locality.as_deref(), Some("Kirkwood"));
+        assert_eq!(geocoder.reverse(0.0, 0.0).unwrap(), None);
+    }
+
+    #[test]
+    fn test_failed_request_is_not_cached() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache_path = dir.path().join("geocode.json");
+        let geocoder =
+            NominatimGeocoder::new("http://127.0.0.1:9", Some(cache_path.clone())).unwrap();
+
+        assert!(geocoder.reverse(38.5, -90.4).is_err());
+        assert!(!cache_path.exists());
+        assert!(geocoder.state.lock().unwrap().cache.is_empty());
+    }
+}
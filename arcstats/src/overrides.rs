@@ -0,0 +1,144 @@
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use crate::models::Place;
+
+/// User-maintained corrections for places, keyed by place ID
+///
+/// Fixes Arc's mislabels without editing the export, which Arc overwrites. In TOML:
+///
+/// ```toml
+/// [places.0A1B2C3D-...]
+/// name = "Grace Church"
+/// category = "church"
+///
+/// # Count visits to the parking lot as visits to the church
+/// [places.F9E8D7C6-...]
+/// merge_into = "0A1B2C3D-..."
+/// ```
+///
+/// The JSON form is the same structure: `{"places": {"<id>": {"name": "..."}}}`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PlaceOverrides {
+    #[serde(default)]
+    pub places: HashMap<String, PlaceOverride>,
+}
+
+/// Corrections for a single place
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PlaceOverride {
+    /// Replaces the place name
+    pub name: Option<String>,
+    /// Replaces the place category (`google_primary_type`), e.g. "church"
+    pub category: Option<String>,
+    /// ID of the place that visits to this place should count towards
+    pub merge_into: Option<String>,
+}
+
+impl PlaceOverrides {
+    /// Loads overrides from a `.json` file, or from TOML for any other extension
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .context(format!("Failed to read place overrides file: {:?}", path))?;
+
+        let is_json = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+        if is_json {
+            serde_json::from_str(&content)
+                .context(format!("Failed to parse place overrides file: {:?}", path))
+        } else {
+            toml::from_str(&content)
+                .context(format!("Failed to parse place overrides file: {:?}", path))
+        }
+    }
+
+    /// Returns whether there are no overrides
+    pub fn is_empty(&self) -> bool {
+        self.places.is_empty()
+    }
+
+    /// Follows `merge_into` links from a place ID to the place its visits count towards
+    ///
+    /// # Errors
+    /// Returns an error if the merge targets form a cycle
+    pub fn resolve_id<'a>(&'a self, place_id: &'a str) -> Result<&'a str> {
+        let mut current = place_id;
+        let mut seen = HashSet::new();
+
+        while let Some(target) = self
+            .places
+            .get(current)
+            .and_then(|place| place.merge_into.as_deref())
+        {
+            if !seen.insert(current) {
+                bail!("Place overrides merge {} into itself", place_id);
+            }
+            current = target;
+        }
+
+        Ok(current)
+    }
+
+    /// Applies the name and category overrides for a place
+    pub fn apply(&self, mut place: Place) -> Place {
+        if let Some(place_override) = self.places.get(&place.id) {
+            if let Some(name) = &place_override.name {
+                place.name = name.clone();
+            }
+            if let Some(category) = &place_override.category {
+                place.google_primary_type = Some(category.clone());
+            }
+        }
+        place
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OVERRIDES: &str = r#"
+        [places.A1]
+        name = "Grace Church"
+        category = "church"
+
+        [places.B2]
+        merge_into = "A1"
+
+        [places.C3]
+        merge_into = "B2"
+    "#;
+
+    #[test]
+    fn test_resolve_id() {
+        let overrides: PlaceOverrides = toml::from_str(OVERRIDES).unwrap();
+        assert_eq!(overrides.resolve_id("C3").unwrap(), "A1");
+        assert_eq!(overrides.resolve_id("B2").unwrap(), "A1");
+        assert_eq!(overrides.resolve_id("D4").unwrap(), "D4");
+
+        let cycle: PlaceOverrides = serde_json::from_str(
+            r#"{"places": {"A1": {"merge_into": "B2"}, "B2": {"merge_into": "A1"}}}"#,
+        )
+        .unwrap();
+        assert!(cycle.resolve_id("A1").is_err());
+    }
+
+    #[test]
+    fn test_load_rejects_unknown_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("places.toml");
+        fs::write(&path, "[places.A1]\nnmae = \"Typo\"\n").unwrap();
+        let error = PlaceOverrides::load(&path).unwrap_err();
+        assert!(format!("{:#}", error).contains("unknown field"));
+
+        fs::write(&path, OVERRIDES).unwrap();
+        assert_eq!(PlaceOverrides::load(&path).unwrap().places.len(), 3);
+    }
+}
@@ -0,0 +1,116 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::path::{Path, PathBuf};
+
+use crate::dedup::{DedupReport, dedupe_items};
+use crate::loader::{
+    PlaceCache, load_all_items_with_place_cache, load_items_with_place_cache_between,
+};
+use crate::models::ItemWithPlace;
+use crate::overrides::PlaceOverrides;
+
+/// A source of timeline visits and the places they happened at
+///
+/// Arc Timeline is iOS-only, so other location histories implement this by converting their
+/// data into the same `Item`/`Place` model; the place and church stats then work unchanged.
+pub trait PlaceProvider {
+    /// Human-readable name of the source, e.g. "Arc Timeline"
+    fn name(&self) -> &'static str;
+
+    /// Loads all timeline items, with the place of each visit resolved
+    fn load_items_with_places(&self) -> Result<Vec<ItemWithPlace>>;
+
+    /// Loads the timeline items overlapping the time range, with places resolved
+    ///
+    /// Filters all items by default; providers that can read less for a range override this.
+    fn load_items_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ItemWithPlace>> {
+        let mut items = self.load_items_with_places()?;
+        items.retain(|item| item.item.start_datetime() < end && item.item.end_datetime() > start);
+        Ok(items)
+    }
+
+    /// File whose last-modified time tells when the location history was last updated
+    fn modified_path(&self) -> PathBuf;
+}
+
+/// An Arc Timeline export directory containing places/, items/, and metadata.json
+#[derive(Debug, Clone)]
+pub struct ArcExport {
+    export_path: PathBuf,
+    overrides: PlaceOverrides,
+}
+
+impl ArcExport {
+    /// Creates a provider for the export at the given path
+    pub fn new<P: AsRef<Path>>(export_path: P) -> Self {
+        Self {
+            export_path: export_path.as_ref().to_path_buf(),
+            overrides: PlaceOverrides::default(),
+        }
+    }
+
+    /// Applies user corrections to the places as they are loaded
+    pub fn with_overrides(mut self, overrides: PlaceOverrides) -> Self {
+        self.overrides = overrides;
+        self
+    }
+
+    /// Loads all items with places, dropping duplicated and overlapping items
+    ///
+    /// Returns the report of what was dropped alongside the items, for the doctor command.
+    pub fn load_deduplicated(&self) -> Result<(Vec<ItemWithPlace>, DedupReport)> {
+        let mut place_cache = PlaceCache::with_overrides(&self.export_path, self.overrides.clone());
+        let items = load_all_items_with_place_cache(&mut place_cache)?;
+        Ok(dedupe_items(items))
+    }
+
+    /// Returns whether the path looks like an Arc Timeline export (has a metadata.json)
+    pub fn is_arc_export<P: AsRef<Path>>(path: P) -> bool {
+        path.as_ref().join("metadata.json").is_file()
+    }
+}
+
+impl PlaceProvider for ArcExport {
+    fn name(&self) -> &'static str {
+        "Arc Timeline"
+    }
+
+    /// All items, deduplicated so re-exported items are not counted twice
+    fn load_items_with_places(&self) -> Result<Vec<ItemWithPlace>> {
+        let (items, report) = self.load_deduplicated()?;
+        log_dropped(&report);
+        Ok(items)
+    }
+
+    /// Reads only the month files and place files the range needs
+    fn load_items_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ItemWithPlace>> {
+        let mut place_cache = PlaceCache::with_overrides(&self.export_path, self.overrides.clone());
+        let items = load_items_with_place_cache_between(&mut place_cache, start, end)?;
+        let (items, report) = dedupe_items(items);
+        log_dropped(&report);
+        Ok(items)
+    }
+
+    /// metadata.json, which Arc rewrites at the end of every export session
+    fn modified_path(&self) -> PathBuf {
+        self.export_path.join("metadata.json")
+    }
+}
+
+/// Logs how many items deduplication dropped; `faithstats doctor` lists them
+fn log_dropped(report: &DedupReport) {
+    if !report.is_empty() {
+        tracing::debug!(
+            dropped = report.dropped.len(),
+            "Dropped duplicate Arc timeline items"
+        );
+    }
+}
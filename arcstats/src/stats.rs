@@ -1,54 +1,114 @@
-use anyhow::Result;
-use chrono::{DateTime, Datelike, Duration, Timelike, Utc};
-use chrono_tz::America::Chicago;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use utoipa::ToSchema;
+use std::collections::{HashMap, HashSet};
 
-use crate::loader::load_all_items_with_places;
-use statsutils::DatePeriod;
+use crate::models::ItemWithPlace;
+use crate::provider::{ArcExport, PlaceProvider};
+use statsutils::{
+    DataRange, DatePeriod, date_start_ms, get_today_date, minutes_by_week, split_by_week,
+    timestamp_ms_to_date_string, timestamp_ms_to_rfc3339,
+};
 
-/// Weekly statistics for church attendance
-#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
-pub struct WeekStats {
-    /// Week start date in YYYY-MM-DD format (Sunday)
-    pub week_start: String,
-    /// Time spent at church in minutes
-    pub minutes: f64,
-}
+/// Weekly time spent at church
+pub use statsmodels::WeekStats;
 
 /// Statistics for a single place showing time spent
-#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct PlaceStats {
     /// Name of the place
     pub place_name: String,
     /// Total hours spent at this place
     pub hours: f64,
+    /// Category of the place (Google place type, e.g. "church"), if known
+    pub category: Option<String>,
+    /// City, town or village of the place, if known
+    pub locality: Option<String>,
+    /// Number of visits in the window
+    pub visit_count: u32,
+    /// Start of the first visit in the window (RFC 3339)
+    pub first_visit: String,
+    /// Start of the last visit in the window (RFC 3339)
+    pub last_visit: String,
+}
+
+/// Weekly share of visit time spent at home
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct HomeRatioWeek {
+    /// Week start date in YYYY-MM-DD format (Sunday)
+    pub week_start: String,
+    /// Minutes of visits at the home place
+    pub home_minutes: f64,
+    /// Minutes of visits at any place
+    pub visit_minutes: f64,
+    /// Home minutes as a percentage of visit minutes (0-100), or `None` without visits
+    pub home_percentage: Option<f64>,
 }
 
-/// Converts a UTC datetime to a week start date string (YYYY-MM-DD)
-/// Applies 4 AM rollover and finds the most recent Sunday in Chicago timezone
-fn get_week_start_for_datetime(dt: DateTime<Utc>) -> String {
-    const ROLLOVER_HOUR: i64 = 4;
+/// Places visited for the first time ever in a month
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct MonthNewPlaces {
+    /// Month in YYYY-MM format
+    pub month: String,
+    /// Number of places first visited in this month
+    pub new_places: u32,
+    /// Names of those places, in the order they were first visited
+    pub place_names: Vec<String>,
+}
 
-    // Convert to Chicago timezone
-    let dt_chicago = dt.with_timezone(&Chicago);
+/// Minutes at a place on one day from which a visit is notable even if not the first one
+pub const NOTABLE_VISIT_MINUTES: f64 = 60.0;
 
-    // Apply 4 AM rollover: if before 4 AM, consider it part of previous day
-    let adjusted_dt = if dt_chicago.hour() < ROLLOVER_HOUR as u32 {
-        dt_chicago - Duration::hours(24)
-    } else {
-        dt_chicago
-    };
+/// Time spent at a place on one day
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PlaceVisit {
+    /// Date in YYYY-MM-DD format
+    pub date: String,
+    /// Name of the place
+    pub place_name: String,
+    /// Category of the place (Google place type, e.g. "church"), if known
+    pub category: Option<String>,
+    /// City, town or village of the place, if known
+    pub locality: Option<String>,
+    /// Minutes of all visits to the place that day
+    pub minutes: f64,
+    /// Whether the place was visited for the first time ever that day
+    pub first_visit: bool,
+}
 
-    // Calculate days since last Sunday (0 if today is Sunday)
-    let days_since_sunday = adjusted_dt.weekday().num_days_from_sunday();
+/// Running totals for a place while summarizing visits
+struct PlaceTotals {
+    hours: f64,
+    category: Option<String>,
+    locality: Option<String>,
+    visit_count: u32,
+    first_visit: DateTime<Utc>,
+    last_visit: DateTime<Utc>,
+}
 
-    // Go back to the most recent Sunday
-    let week_start = adjusted_dt - Duration::days(days_since_sunday as i64);
+/// Recorded and unrecorded time of a single day
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DayCoverage {
+    /// Date in YYYY-MM-DD format
+    pub date: String,
+    /// Hours covered by timeline items
+    pub recorded_hours: f64,
+    /// Hours without any timeline item (phone off, Arc not recording)
+    pub gap_hours: f64,
+}
 
-    // Format as YYYY-MM-DD
-    week_start.format("%Y-%m-%d").to_string()
+/// Start and duration of an item in milliseconds
+fn item_span(item_with_place: &ItemWithPlace) -> (i64, i64) {
+    let item = &item_with_place.item;
+    (
+        item.start_datetime().timestamp_millis(),
+        (item.duration_seconds() * 1000.0).round() as i64,
+    )
 }
 
 /// Gets church attendance statistics for the last 12 weeks
@@ -62,48 +122,251 @@ fn get_week_start_for_datetime(dt: DateTime<Utc>) -> String {
 /// A vector of 12 WeekStats, one for each week, in chronological order.
 /// Weeks without church visits will have 0 minutes.
 pub fn get_last_12_weeks_stats(export_path: &str) -> Result<Vec<WeekStats>> {
-    // Get the period data for the last 12 weeks
-    let period = DatePeriod::last_12_weeks()?;
+    get_weekly_stats(&ArcExport::new(export_path), DatePeriod::last_12_weeks()?)
+}
 
-    // Load all items with their associated places
-    let items = load_all_items_with_places(export_path)?;
+/// Gets church attendance statistics for the last `weeks` weeks
+///
+/// # Arguments
+///
+/// * `export_path` - Path to the Arc Timeline export directory containing places/, items/, and metadata.json
+/// * `weeks` - Number of weeks to include, ending with this week
+pub fn get_last_n_weeks_stats(export_path: &str, weeks: i32) -> Result<Vec<WeekStats>> {
+    get_last_n_weeks_stats_from(&ArcExport::new(export_path), weeks)
+}
 
-    // Filter for visits at places containing "church" (case-insensitive)
-    // and calculate duration in minutes for each visit
-    let mut church_visits: Vec<(DateTime<Utc>, f64)> = Vec::new();
+/// Gets church attendance statistics for the last `weeks` weeks from any place provider
+pub fn get_last_n_weeks_stats_from(
+    provider: &dyn PlaceProvider,
+    weeks: i32,
+) -> Result<Vec<WeekStats>> {
+    get_weekly_stats(provider, DatePeriod::last_n_weeks(weeks)?)
+}
+
+/// Gets church attendance statistics for each week in the period
+///
+/// Visits running past the start of a week (Sunday 4 AM) are divided between the two weeks.
+fn get_weekly_stats(provider: &dyn PlaceProvider, period: DatePeriod) -> Result<Vec<WeekStats>> {
+    // Load the items of the period with their associated places
+    let (start, end) = period_range(&period)?;
+    let items = provider.load_items_between(start, end)?;
+
+    // Filter for visits at churches (name containing "church" or category "church")
+    let church_visits: Vec<(i64, i64)> = items
+        .iter()
+        .filter(|item_with_place| {
+            item_with_place.item.base.is_visit
+                && item_with_place
+                    .place
+                    .as_ref()
+                    .is_some_and(|place| place.is_church())
+        })
+        .map(item_span)
+        .collect();
+
+    // Sum the minutes of each week
+    let weekly_minutes = minutes_by_week(&church_visits)?;
+
+    // Build results for all weeks in the period, filling gaps with 0 minutes
+    let results = period.build_results(weekly_minutes, |date, minutes| WeekStats {
+        week_start: date,
+        minutes,
+    });
+
+    Ok(results)
+}
+
+/// Gets the weekly share of visit time spent at home for the last `weeks` weeks
+///
+/// # Arguments
+///
+/// * `provider` - Location history to read
+/// * `home_place` - ID or name of the home place
+/// * `weeks` - Number of weeks to include, ending with this week
+///
+/// # Returns
+///
+/// One HomeRatioWeek per week in chronological order. Trips are not counted, so the
+/// percentage compares time at home with time at other places. Visits running past the
+/// start of a week are divided between the two weeks.
+pub fn get_home_ratio_last_n_weeks_from(
+    provider: &dyn PlaceProvider,
+    home_place: &str,
+    weeks: i32,
+) -> Result<Vec<HomeRatioWeek>> {
+    let period = DatePeriod::last_n_weeks(weeks)?;
+    let (start, end) = period_range(&period)?;
+    let items = provider.load_items_between(start, end)?;
+
+    // (home minutes, visit minutes) per week
+    let mut weekly_minutes: HashMap<String, (f64, f64)> = HashMap::new();
 
     for item_with_place in items {
-        // Skip if not a visit
-        if !item_with_place.item.base.is_visit {
+        if !item_with_place.item.base.is_visit || item_with_place.item.base.deleted {
             continue;
         }
 
-        // Skip if no place or place name doesn't contain "church"
-        if let Some(place) = &item_with_place.place
-            && place.name.to_lowercase().contains("church") {
-                let start = item_with_place.item.start_datetime();
-                let duration_minutes = item_with_place.item.duration_seconds() / 60.0;
-                church_visits.push((start, duration_minutes));
+        let at_home = item_with_place
+            .place
+            .as_ref()
+            .is_some_and(|place| place.id == home_place || place.name == home_place);
+
+        let (start_ms, duration_ms) = item_span(&item_with_place);
+        for (week_start, ms) in split_by_week(start_ms, duration_ms)? {
+            let minutes = ms as f64 / 60_000.0;
+            let totals = weekly_minutes.entry(week_start).or_default();
+            if at_home {
+                totals.0 += minutes;
             }
+            totals.1 += minutes;
+        }
     }
 
-    // Group visits by week and sum minutes
-    let mut weekly_minutes: HashMap<String, f64> = HashMap::new();
+    let results =
+        period.build_results(weekly_minutes, |week_start, (home, visits)| HomeRatioWeek {
+            week_start,
+            home_minutes: home,
+            visit_minutes: visits,
+            home_percentage: (visits > 0.0).then(|| home / visits * 100.0),
+        });
 
-    for (visit_time, minutes) in church_visits {
-        let week_start = get_week_start_for_datetime(visit_time);
-        *weekly_minutes.entry(week_start).or_insert(0.0) += minutes;
+    Ok(results)
+}
+
+/// Gets how many places were visited for the first time ever in each of the last `months`
+/// months
+///
+/// The first visit to a place is the earliest visit item referencing it in the whole
+/// history, so all items are loaded even for a short window.
+///
+/// # Returns
+///
+/// One MonthNewPlaces per month, ending with the current month, in chronological order
+pub fn get_new_places_last_n_months_from(
+    provider: &dyn PlaceProvider,
+    months: u32,
+) -> Result<Vec<MonthNewPlaces>> {
+    let mut items = provider.load_items_with_places()?;
+    items.retain(|item| item.item.base.is_visit && !item.item.base.deleted);
+    items.sort_by(|a, b| a.item.base.start_date.total_cmp(&b.item.base.start_date));
+
+    // The first visit of each place, in chronological order
+    let mut seen = HashSet::new();
+    let mut first_visits: Vec<(String, String)> = Vec::new();
+    for item_with_place in &items {
+        let Some(place) = &item_with_place.place else {
+            continue;
+        };
+        if seen.insert(place.id.as_str()) {
+            let date = timestamp_ms_to_date_string(
+                item_with_place.item.start_datetime().timestamp_millis(),
+            )?;
+            first_visits.push((date[..7].to_string(), place.name.clone()));
+        }
     }
 
-    // Build results for all 12 weeks, filling gaps with 0 minutes
-    let results = period.build_results(weekly_minutes, |date, minutes| WeekStats {
-        week_start: date,
-        minutes,
-    });
+    let current_month = NaiveDate::parse_from_str(&get_today_date()?, "%Y-%m-%d")?
+        .with_day(1)
+        .context("Failed to get the start of the month")?;
+    let mut results = Vec::new();
+    for offset in (0..months).rev() {
+        let month = current_month
+            .checked_sub_months(Months::new(offset))
+            .context("Month is out of range")?
+            .format("%Y-%m")
+            .to_string();
+        let place_names: Vec<String> = first_visits
+            .iter()
+            .filter(|(first_month, _)| *first_month == month)
+            .map(|(_, name)| name.clone())
+            .collect();
+
+        results.push(MonthNewPlaces {
+            month,
+            new_places: place_names.len() as u32,
+            place_names,
+        });
+    }
 
     Ok(results)
 }
 
+/// Gets the notable places visited on a month and day in earlier years, e.g. for an
+/// "on this day" view
+///
+/// A visit is notable when the place was visited for the first time ever that day, or for
+/// at least `NOTABLE_VISIT_MINUTES`. The home place (matched by ID or name) is left out.
+///
+/// # Arguments
+///
+/// * `provider` - Location history to read
+/// * `home_place` - ID or name of the home place
+/// * `today` - Date in YYYY-MM-DD format whose month and day are looked up in earlier years
+///
+/// # Returns
+///
+/// One PlaceVisit per day and place, oldest first
+pub fn get_notable_visits_on_this_day_from(
+    provider: &dyn PlaceProvider,
+    home_place: &str,
+    today: &str,
+) -> Result<Vec<PlaceVisit>> {
+    let (Some(year), Some(month_day)) = (today.get(..4), today.get(4..)) else {
+        anyhow::bail!("Invalid date {}", today);
+    };
+
+    let mut items = provider.load_items_with_places()?;
+    items.retain(|item| item.item.base.is_visit && !item.item.base.deleted);
+    items.sort_by(|a, b| a.item.base.start_date.total_cmp(&b.item.base.start_date));
+
+    // Visits are collected in chronological order, keyed by date and place ID
+    let mut seen = HashSet::new();
+    let mut visits: Vec<(String, PlaceVisit)> = Vec::new();
+    for item_with_place in &items {
+        let Some(place) = &item_with_place.place else {
+            continue;
+        };
+        let first_visit = seen.insert(place.id.as_str());
+        let date =
+            timestamp_ms_to_date_string(item_with_place.item.start_datetime().timestamp_millis())?;
+        if !date.ends_with(month_day)
+            || date[..4] >= *year
+            || place.id == home_place
+            || place.name == home_place
+        {
+            continue;
+        }
+
+        let minutes = item_with_place.item.duration_seconds() / 60.0;
+        match visits
+            .iter_mut()
+            .find(|(id, visit)| *id == place.id && visit.date == date)
+        {
+            Some((_, visit)) => {
+                visit.minutes += minutes;
+                visit.first_visit |= first_visit;
+            }
+            None => visits.push((
+                place.id.clone(),
+                PlaceVisit {
+                    date,
+                    place_name: place.name.clone(),
+                    category: place.google_primary_type.clone(),
+                    locality: place.locality.clone(),
+                    minutes,
+                    first_visit,
+                },
+            )),
+        }
+    }
+
+    Ok(visits
+        .into_iter()
+        .map(|(_, visit)| visit)
+        .filter(|visit| visit.first_visit || visit.minutes >= NOTABLE_VISIT_MINUTES)
+        .collect())
+}
+
 /// Gets the top N places by total hours spent over the last 6 months
 ///
 /// # Arguments
@@ -116,17 +379,61 @@ pub fn get_last_12_weeks_stats(export_path: &str) -> Result<Vec<WeekStats>> {
 /// A vector of PlaceStats sorted by hours descending (most time first).
 /// Excludes the place named "Home".
 pub fn get_top_places_last_6_months(export_path: &str, limit: usize) -> Result<Vec<PlaceStats>> {
+    get_top_places_last_6_months_from(&ArcExport::new(export_path), limit)
+}
+
+/// Gets the top N places by total hours spent over the last 6 months from any place provider
+pub fn get_top_places_last_6_months_from(
+    provider: &dyn PlaceProvider,
+    limit: usize,
+) -> Result<Vec<PlaceStats>> {
     const DAYS_IN_6_MONTHS: i64 = 182;
 
     // Calculate the cutoff date (6 months ago)
     let now = Utc::now();
     let cutoff_date = now - Duration::days(DAYS_IN_6_MONTHS);
 
-    // Load all items with their associated places
-    let items = load_all_items_with_places(export_path)?;
+    // Load the items since the cutoff with their associated places
+    let items = provider.load_items_between(cutoff_date, now)?;
+
+    let mut place_stats = summarize_places(items, cutoff_date, "Home")?;
+
+    // Take top N
+    place_stats.truncate(limit);
+
+    Ok(place_stats)
+}
 
+/// Gets every visited place with the total hours spent there from any place provider
+///
+/// # Arguments
+///
+/// * `provider` - Source of timeline items and places
+/// * `home_place` - ID or name of the home place, which is left out
+///
+/// # Returns
+///
+/// A vector of PlaceStats sorted by hours descending, covering the whole location history.
+pub fn get_visited_places_from(
+    provider: &dyn PlaceProvider,
+    home_place: &str,
+) -> Result<Vec<PlaceStats>> {
+    summarize_places(
+        provider.load_items_with_places()?,
+        DateTime::<Utc>::MIN_UTC,
+        home_place,
+    )
+}
+
+/// Sums up the visits starting at or after the cutoff per place name, most hours first,
+/// leaving out the home place (by ID or name)
+fn summarize_places(
+    items: Vec<ItemWithPlace>,
+    cutoff_date: DateTime<Utc>,
+    home_place: &str,
+) -> Result<Vec<PlaceStats>> {
     // Collect visits with place names and durations
-    let mut place_durations: HashMap<String, f64> = HashMap::new();
+    let mut place_totals: HashMap<String, PlaceTotals> = HashMap::new();
 
     for item_with_place in items {
         // Skip if not a visit
@@ -139,8 +446,8 @@ pub fn get_top_places_last_6_months(export_path: &str, limit: usize) -> Result<V
             continue;
         };
 
-        // Skip if place name is "Home"
-        if place.name == "Home" {
+        // Skip the home place
+        if place.id == home_place || place.name == home_place {
             continue;
         }
 
@@ -153,15 +460,45 @@ pub fn get_top_places_last_6_months(export_path: &str, limit: usize) -> Result<V
         // Calculate duration in hours
         let duration_hours = item_with_place.item.duration_seconds() / 3600.0;
 
-        // Add to place total
-        *place_durations.entry(place.name.clone()).or_insert(0.0) += duration_hours;
+        // Add to place total; places sharing a name are combined, keeping the first known
+        // category and locality
+        let totals = place_totals
+            .entry(place.name.clone())
+            .or_insert_with(|| PlaceTotals {
+                hours: 0.0,
+                category: None,
+                locality: None,
+                visit_count: 0,
+                first_visit: visit_start,
+                last_visit: visit_start,
+            });
+        totals.hours += duration_hours;
+        totals.visit_count += 1;
+        totals.first_visit = totals.first_visit.min(visit_start);
+        totals.last_visit = totals.last_visit.max(visit_start);
+        if totals.category.is_none() {
+            totals.category = place.google_primary_type.clone();
+        }
+        if totals.locality.is_none() {
+            totals.locality = place.locality.clone();
+        }
     }
 
     // Convert to vec of PlaceStats and sort by hours descending
-    let mut place_stats: Vec<PlaceStats> = place_durations
+    let mut place_stats: Vec<PlaceStats> = place_totals
         .into_iter()
-        .map(|(place_name, hours)| PlaceStats { place_name, hours })
-        .collect();
+        .map(|(place_name, totals)| {
+            Ok(PlaceStats {
+                place_name,
+                hours: totals.hours,
+                category: totals.category,
+                locality: totals.locality,
+                visit_count: totals.visit_count,
+                first_visit: timestamp_ms_to_rfc3339(totals.first_visit.timestamp_millis())?,
+                last_visit: timestamp_ms_to_rfc3339(totals.last_visit.timestamp_millis())?,
+            })
+        })
+        .collect::<Result<_>>()?;
 
     place_stats.sort_by(|a, b| {
         b.hours
@@ -169,15 +506,404 @@ pub fn get_top_places_last_6_months(export_path: &str, limit: usize) -> Result<V
             .unwrap_or(std::cmp::Ordering::Equal)
     });
 
-    // Take top N
-    place_stats.truncate(limit);
-
     Ok(place_stats)
 }
 
+/// Gets the earliest and latest recorded timeline items, plus the export's last-modified time
+///
+/// # Arguments
+///
+/// * `export_path` - Path to the Arc Timeline export directory containing places/, items/, and metadata.json
+///
+/// # Returns
+///
+/// A DataRange spanning the non-deleted items in the export. The last-modified time is taken
+/// from metadata.json, which Arc rewrites at the end of every export session.
+pub fn get_data_range(export_path: &str) -> Result<DataRange> {
+    get_data_range_from(&ArcExport::new(export_path))
+}
+
+/// Gets the earliest and latest recorded timeline items from any place provider, plus the
+/// last-modified time of its `modified_path()`
+pub fn get_data_range_from(provider: &dyn PlaceProvider) -> Result<DataRange> {
+    let items = provider.load_items_with_places()?;
+
+    let earliest = items
+        .iter()
+        .filter(|item| !item.item.base.deleted)
+        .map(|item| item.item.start_datetime().timestamp_millis())
+        .min();
+    let latest = items
+        .iter()
+        .filter(|item| !item.item.base.deleted)
+        .map(|item| item.item.end_datetime().timestamp_millis())
+        .max();
+
+    DataRange::new(earliest, latest, provider.modified_path())
+}
+
+/// Gets how many hours of each day in the period have no recorded timeline items
+///
+/// # Arguments
+///
+/// * `export_path` - Path to the Arc Timeline export directory containing places/, items/, and metadata.json
+/// * `period` - Days to report on (e.g. `DatePeriod::last_30_days()`)
+///
+/// # Returns
+///
+/// One DayCoverage per day, in chronological order. Today only counts up to now, so a
+/// week with low church attendance can be told apart from one with missing data.
+pub fn get_coverage_gaps(export_path: &str, period: DatePeriod) -> Result<Vec<DayCoverage>> {
+    get_coverage_gaps_from(&ArcExport::new(export_path), period)
+}
+
+/// Gets how many hours of each day in the period have no recorded timeline items, from any
+/// place provider
+pub fn get_coverage_gaps_from(
+    provider: &dyn PlaceProvider,
+    period: DatePeriod,
+) -> Result<Vec<DayCoverage>> {
+    let (start, end) = period_range(&period)?;
+    let intervals = provider
+        .load_items_between(start, end)?
+        .iter()
+        .filter(|item| !item.item.base.deleted)
+        .map(|item| {
+            (
+                item.item.start_datetime().timestamp_millis(),
+                item.item.end_datetime().timestamp_millis(),
+            )
+        })
+        .collect();
+
+    coverage_by_day(intervals, &period, Utc::now().timestamp_millis())
+}
+
+/// Converts the millisecond boundaries of a period to datetimes
+fn period_range(period: &DatePeriod) -> Result<(DateTime<Utc>, DateTime<Utc>)> {
+    let start = DateTime::from_timestamp_millis(period.start_ms).context("Invalid period start")?;
+    let end = DateTime::from_timestamp_millis(period.end_ms).context("Invalid period end")?;
+    Ok((start, end))
+}
+
+/// Sums the time covered by the intervals (start/end in milliseconds) for each day
+fn coverage_by_day(
+    mut intervals: Vec<(i64, i64)>,
+    period: &DatePeriod,
+    now_ms: i64,
+) -> Result<Vec<DayCoverage>> {
+    const MS_PER_HOUR: f64 = 3_600_000.0;
+
+    // Merge overlapping intervals so time is not counted twice
+    intervals.sort_unstable();
+    let mut merged: Vec<(i64, i64)> = Vec::new();
+    for (start, end) in intervals {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+
+    let mut results = Vec::with_capacity(period.dates.len());
+    for (index, date) in period.dates.iter().enumerate() {
+        let day_start = date_start_ms(date)?;
+        let day_end = match period.dates.get(index + 1) {
+            Some(next) => date_start_ms(next)?,
+            None => period.end_ms,
+        };
+        let day_end = day_end.min(now_ms).max(day_start);
+
+        let recorded_ms: i64 = merged
+            .iter()
+            .map(|(start, end)| (end.min(&day_end) - start.max(&day_start)).max(0))
+            .sum();
+
+        results.push(DayCoverage {
+            date: date.clone(),
+            recorded_hours: recorded_ms as f64 / MS_PER_HOUR,
+            gap_hours: (day_end - day_start - recorded_ms) as f64 / MS_PER_HOUR,
+        });
+    }
+
+    Ok(results)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::{
+        BaseItem, Item, ItemVariant, ItemWithPlace, Place, VisitDetails,
+        datetime_to_apple_timestamp,
+    };
+    use std::rc::Rc;
+
+    const HOUR_MS: i64 = 3_600_000;
+
+    #[test]
+    fn test_coverage_by_day() {
+        let day1 = date_start_ms("2025-03-19").unwrap();
+        let day2 = date_start_ms("2025-03-20").unwrap();
+        let now = day2 + 12 * HOUR_MS;
+        let period = DatePeriod::last_n_days_at(2, now).unwrap();
+
+        let intervals = vec![
+            // Day 1 recorded except from 6 to 12 hours in, with an overlapping duplicate
+            (day1, day1 + 6 * HOUR_MS),
+            (day1 + 2 * HOUR_MS, day1 + 4 * HOUR_MS),
+            (day1 + 12 * HOUR_MS, day2 + HOUR_MS),
+            (day2 + 3 * HOUR_MS, day2 + 4 * HOUR_MS),
+        ];
+        let coverage = coverage_by_day(intervals, &period, now).unwrap();
+
+        assert_eq!(coverage.len(), 2);
+        assert_eq!(coverage[0].date, "2025-03-19");
+        assert_eq!(coverage[0].recorded_hours, 18.0);
+        assert_eq!(coverage[0].gap_hours, 6.0);
+        // Today only counts the 12 hours that have passed
+        assert_eq!(coverage[1].recorded_hours, 2.0);
+        assert_eq!(coverage[1].gap_hours, 10.0);
+    }
+
+    /// Place provider serving fixed items
+    struct FixedItems(Vec<ItemWithPlace>);
+
+    impl PlaceProvider for FixedItems {
+        fn name(&self) -> &'static str {
+            "Fixed"
+        }
+
+        fn load_items_with_places(&self) -> Result<Vec<ItemWithPlace>> {
+            Ok(self.0.clone())
+        }
+
+        fn modified_path(&self) -> std::path::PathBuf {
+            std::path::PathBuf::new()
+        }
+    }
+
+    fn place(id: &str, name: &str, category: Option<&str>) -> Rc<Place> {
+        Rc::new(Place {
+            id: id.to_string(),
+            name: name.to_string(),
+            latitude: 0.0,
+            longitude: 0.0,
+            radius_mean: 50.0,
+            radius_sd: 0.0,
+            visit_count: 1,
+            visit_days: 1,
+            last_saved: 0.0,
+            is_stale: false,
+            source: "LocoKit".into(),
+            rtree_id: 0,
+            seconds_from_gmt: None,
+            street_address: None,
+            locality: Some("Springfield".to_string()),
+            country_code: None,
+            google_place_id: None,
+            google_primary_type: category.map(str::to_string),
+            last_visit_date: None,
+        })
+    }
+
+    fn visit(place: &Rc<Place>, start: DateTime<Utc>, hours: i64) -> ItemWithPlace {
+        let start_date = datetime_to_apple_timestamp(start);
+        let end_date = datetime_to_apple_timestamp(start + Duration::hours(hours));
+        ItemWithPlace {
+            item: Item {
+                base: BaseItem {
+                    id: format!("{}-{}", place.id, start_date).into(),
+                    start_date,
+                    end_date,
+                    last_saved: 0.0,
+                    source: "LocoKit".into(),
+                    source_version: None,
+                    is_visit: true,
+                    deleted: false,
+                    disabled: false,
+                    samples_changed: None,
+                    step_count: None,
+                    active_energy_burned: None,
+                    max_heart_rate: None,
+                    average_heart_rate: None,
+                    previous_item_id: None,
+                    next_item_id: None,
+                },
+                variant: ItemVariant::Visit(VisitDetails {
+                    item_id: "item".into(),
+                    place_id: Some(place.id.as_str().into()),
+                    latitude: 0.0,
+                    longitude: 0.0,
+                    radius_mean: 50.0,
+                    radius_sd: 0.0,
+                    confirmed_place: false,
+                    uncertain_place: false,
+                    last_saved: 0.0,
+                    street_address: None,
+                }),
+            },
+            place: Some(Rc::clone(place)),
+        }
+    }
+
+    #[test]
+    fn test_top_places_metadata() {
+        let now = Utc::now();
+        let church = place("A", "Grace Church", Some("church"));
+        let cafe = place("B", "Cafe", None);
+        let home = place("C", "Home", None);
+        let provider = FixedItems(vec![
+            visit(&church, now - Duration::days(300), 2),
+            visit(&church, now - Duration::days(20), 2),
+            visit(&church, now - Duration::days(13), 2),
+            visit(&cafe, now - Duration::days(5), 1),
+            visit(&home, now - Duration::days(1), 12),
+        ]);
+
+        let stats = get_top_places_last_6_months_from(&provider, 10).unwrap();
+
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].place_name, "Grace Church");
+        assert_eq!(stats[0].hours, 4.0);
+        assert_eq!(stats[0].visit_count, 2);
+        assert_eq!(stats[0].category.as_deref(), Some("church"));
+        assert_eq!(stats[0].locality.as_deref(), Some("Springfield"));
+        assert!(stats[0].first_visit < stats[0].last_visit);
+        assert_eq!(stats[1].place_name, "Cafe");
+        assert_eq!(stats[1].first_visit, stats[1].last_visit);
+    }
+
+    #[test]
+    fn test_visited_places_leave_out_home_place() {
+        let now = Utc::now();
+        let home = place("H", "Parsonage", None);
+        let church = place("A", "Grace Church", Some("church"));
+        let provider = FixedItems(vec![
+            visit(&home, now - Duration::days(400), 12),
+            visit(&church, now - Duration::days(300), 2),
+        ]);
+
+        for home_place in ["H", "Parsonage"] {
+            let places = get_visited_places_from(&provider, home_place).unwrap();
+            assert_eq!(places.len(), 1);
+            assert_eq!(places[0].place_name, "Grace Church");
+        }
+
+        let places = get_visited_places_from(&provider, "Home").unwrap();
+        assert_eq!(places[0].place_name, "Parsonage");
+        assert_eq!(places.len(), 2);
+    }
+
+    #[test]
+    fn test_home_ratio() {
+        let now = Utc::now();
+        let home = place("H", "Home", None);
+        let church = place("A", "Grace Church", Some("church"));
+        let provider = FixedItems(vec![
+            visit(&home, now - Duration::hours(10), 3),
+            visit(&church, now - Duration::hours(6), 1),
+        ]);
+
+        for home_place in ["H", "Home"] {
+            let weeks = get_home_ratio_last_n_weeks_from(&provider, home_place, 4).unwrap();
+            assert_eq!(weeks.len(), 4);
+            let home_minutes: f64 = weeks.iter().map(|w| w.home_minutes).sum();
+            let visit_minutes: f64 = weeks.iter().map(|w| w.visit_minutes).sum();
+            assert_eq!(home_minutes, 180.0);
+            assert_eq!(visit_minutes, 240.0);
+            assert!(weeks[0].home_percentage.is_none());
+        }
+
+        let weeks = get_home_ratio_last_n_weeks_from(&provider, "Elsewhere", 4).unwrap();
+        assert!(weeks.iter().all(|w| w.home_minutes == 0.0));
+    }
+
+    #[test]
+    fn test_new_places() {
+        let now = Utc::now();
+        let old = place("A", "Old Favorite", None);
+        let cafe = place("B", "Cafe", None);
+        let park = place("C", "Park", None);
+        let provider = FixedItems(vec![
+            visit(&cafe, now - Duration::hours(2), 1),
+            visit(&old, now - Duration::days(400), 1),
+            visit(&old, now - Duration::hours(3), 1),
+            visit(&park, now - Duration::days(100), 1),
+            visit(&cafe, now - Duration::hours(1), 1),
+        ]);
+
+        let months = get_new_places_last_n_months_from(&provider, 12).unwrap();
+
+        assert_eq!(months.len(), 12);
+        assert_eq!(months[11].month, &get_today_date().unwrap()[..7]);
+        let names: Vec<&str> = months
+            .iter()
+            .flat_map(|m| m.place_names.iter().map(String::as_str))
+            .collect();
+        // The old favorite was first visited before the window
+        assert_eq!(names, ["Park", "Cafe"]);
+        assert_eq!(months.iter().map(|m| m.new_places).sum::<u32>(), 2);
+    }
+
+    #[test]
+    fn test_notable_visits_on_this_day() {
+        let at = |date: &str, hour: i64| {
+            DateTime::from_timestamp_millis(date_start_ms(date).unwrap() + hour * HOUR_MS).unwrap()
+        };
+        let home = place("H", "Home", None);
+        let church = place("A", "Grace Church", Some("church"));
+        let cafe = place("B", "Cafe", None);
+        let provider = FixedItems(vec![
+            visit(&church, at("2023-05-02", 4), 2),
+            visit(&home, at("2024-05-02", 2), 8),
+            // A short first visit is notable, a short revisit is not
+            visit(&cafe, at("2024-05-02", 4), 0),
+            visit(&church, at("2024-05-02", 6), 1),
+            visit(&church, at("2024-05-02", 9), 1),
+            visit(&cafe, at("2024-05-03", 4), 3),
+            visit(&cafe, at("2025-05-02", 4), 0),
+            visit(&church, at("2026-05-02", 4), 2),
+        ]);
+
+        let visits = get_notable_visits_on_this_day_from(&provider, "H", "2026-05-02").unwrap();
+
+        let summary: Vec<(&str, &str, f64, bool)> = visits
+            .iter()
+            .map(|v| {
+                (
+                    v.date.as_str(),
+                    v.place_name.as_str(),
+                    v.minutes,
+                    v.first_visit,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("2023-05-02", "Grace Church", 120.0, true),
+                ("2024-05-02", "Cafe", 0.0, true),
+                ("2024-05-02", "Grace Church", 120.0, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_weekly_stats_split_at_week_start() {
+        // 3 AM to 5 AM on Sunday 2025-01-05 in America/Chicago, across the week's 4 AM start
+        let church = place("A", "Grace Church", Some("church"));
+        let start = DateTime::from_timestamp_millis(date_start_ms("2025-01-05").unwrap() - HOUR_MS)
+            .unwrap();
+        let provider = FixedItems(vec![visit(&church, start, 2)]);
+        let period = DatePeriod {
+            dates: vec!["2024-12-29".to_string(), "2025-01-05".to_string()],
+            start_ms: date_start_ms("2024-12-29").unwrap(),
+            end_ms: date_start_ms("2025-01-12").unwrap(),
+        };
+
+        let weeks = get_weekly_stats(&provider, period).unwrap();
+        let minutes: Vec<f64> = weeks.iter().map(|week| week.minutes).collect();
+        assert_eq!(minutes, vec![60.0, 60.0]);
+    }
 
     #[test]
     fn test_week_stats_structure() {
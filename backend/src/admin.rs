@@ -0,0 +1,97 @@
+use anyhow::Result;
+use faithstats::models::StaleSource;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+use utoipa::ToSchema;
+
+use crate::staleness::StalenessState;
+use crate::users::UserProfile;
+
+/// Result of re-reading every user's data sources
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RefreshReport {
+    /// Per-user results, sorted by user id
+    pub users: Vec<UserRefresh>,
+    /// Total time taken in milliseconds
+    pub duration_ms: f64,
+}
+
+/// Result of re-reading one user's data sources
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct UserRefresh {
+    pub user_id: String,
+    /// Time taken to read each source
+    pub sources: Vec<SourceRefresh>,
+    /// Sources breaching their staleness threshold after the refresh
+    pub stale_sources: Vec<StaleSource>,
+}
+
+/// Time taken to read one data source
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SourceRefresh {
+    /// Source name (anki, reading, prayer, arc)
+    pub source: String,
+    /// Time taken to read the source in milliseconds
+    pub duration_ms: f64,
+    /// Latest recorded activity (RFC 3339), if any
+    pub latest: Option<String>,
+    /// Error message if the source could not be read
+    pub error: Option<String>,
+}
+
+/// Date range to exempt from a user's streaks, goals and consistency metrics
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct NewExemption {
+    /// Id of the user the days are exempt for
+    pub user: String,
+    /// First exempt date in YYYY-MM-DD format
+    pub start: String,
+    /// Last exempt date in YYYY-MM-DD format (inclusive)
+    pub end: String,
+    /// Why the days are exempt, e.g. "Vacation"
+    pub reason: Option<String>,
+}
+
+/// Re-reads every user's data sources now instead of waiting for the staleness monitor
+///
+/// Reading the arc source loads and indexes every place of the location history. The
+/// staleness state (and thereby `/health`) is updated right away, and newly stale sources
+/// are notified as usual. Stats endpoints read their sources on every request, so there is
+/// nothing else to recompute.
+pub async fn refresh_all(
+    users: Vec<UserProfile>,
+    staleness: &StalenessState,
+) -> Result<RefreshReport> {
+    let start = Instant::now();
+    let mut results = Vec::new();
+
+    for user in users {
+        let config = user.config.clone();
+        let timed = tokio::task::spawn_blocking(move || {
+            config.sources.faith_stats().build().data_ranges_timed()
+        })
+        .await?;
+
+        let ranges: Vec<_> = timed.iter().map(|(range, _)| range.clone()).collect();
+        let stale_sources = staleness.record(&user, &ranges).await;
+
+        results.push(UserRefresh {
+            user_id: user.id.clone(),
+            sources: timed
+                .into_iter()
+                .map(|(range, duration)| SourceRefresh {
+                    source: range.source,
+                    duration_ms: duration.as_secs_f64() * 1000.0,
+                    latest: range.range.latest,
+                    error: range.error,
+                })
+                .collect(),
+            stale_sources,
+        });
+    }
+
+    Ok(RefreshReport {
+        users: results,
+        duration_ms: start.elapsed().as_secs_f64() * 1000.0,
+    })
+}
@@ -0,0 +1,947 @@
+//! Snapshot tests of the API response shapes
+//!
+//! The router is served from synthetic sources written to a temporary directory by
+//! `statsfixtures` (an Anki collection, a KOReader database, a Proseuche database and an Arc
+//! Timeline export) with a year of activity ending yesterday. The data follows from a fixed
+//! seed and the dates, but the values still depend on the current date, so each JSON response
+//! is snapshotted as its shape: objects keep their keys, scalars are replaced by their type
+//! and the elements of an array are merged into one shape. A change to a model that adds,
+//! renames or removes a field, or changes its type, fails these tests.
+//!
+//! After an intended change, review and accept the new snapshots with `cargo insta review`.
+
+use axum::Router;
+use axum::body::{Body, to_bytes};
+use axum::http::{Request, StatusCode, header};
+use chrono::{Days, NaiveDate};
+use faithstats::models::StalenessThresholds;
+use faithstats::warehouse::Warehouse;
+use quick_xml::Reader;
+use quick_xml::events::Event;
+use serde_json::{Value, json};
+use statsfixtures::{FixtureConfig, FixturePaths, generate};
+use statsutils::get_today_date;
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::Path;
+use tempfile::TempDir;
+use tower::ServiceExt;
+
+use crate::notify::Notifier;
+use crate::push::PushService;
+use crate::users::Users;
+use crate::{AdminApiKey, AppState, router, staleness, warehouse};
+
+const API_KEY: &str = "test-api-key";
+const ADMIN_API_KEY: &str = "test-admin-key";
+const SHARE_TOKEN: &str = "test-share-token";
+
+/// Seed of the synthetic sources
+const FIXTURE_SEED: u64 = 3506;
+
+/// Number of days with synthetic activity
+const FIXTURE_DAYS: i64 = 365;
+
+/// The date a number of days before today, in YYYY-MM-DD format
+fn days_ago(days: u64) -> String {
+    let today = NaiveDate::parse_from_str(&get_today_date().unwrap(), "%Y-%m-%d").unwrap();
+    (today - Days::new(days)).format("%Y-%m-%d").to_string()
+}
+
+/// A year of synthetic activity on every day up to yesterday, so that the data does not
+/// depend on the time of day the tests run
+fn fixture_config() -> FixtureConfig {
+    let end = NaiveDate::parse_from_str(&days_ago(1), "%Y-%m-%d").unwrap();
+    FixtureConfig {
+        seed: FIXTURE_SEED,
+        activity: 1.0,
+        ..FixtureConfig::days_ending(end, FIXTURE_DAYS).unwrap()
+    }
+}
+
+/// Router serving one user with synthetic sources in the returned directory
+fn fixture_app() -> (TempDir, Router) {
+    let dir = TempDir::new().unwrap();
+    let sources = generate(dir.path(), &fixture_config()).unwrap();
+    let state = app_state(dir.path(), &sources, StalenessThresholds::default());
+    (dir, router(state).unwrap())
+}
+
+/// State of a server for one user reading the sources, with the user's stores in the
+/// directory
+fn app_state(dir: &Path, sources: &FixturePaths, thresholds: StalenessThresholds) -> AppState {
+    let source = |path: &Path| path.to_str().unwrap().to_string();
+    let path = |name: &str| source(&dir.join(name));
+
+    let users_config = format!(
+        r#"
+[[users]]
+id = "fixture"
+api_key = "{}"
+anki_database_path = "{}"
+koreader_database_path = "{}"
+proseuche_database_path = "{}"
+arcstats_export_path = "{}"
+stats_store_path = "{}"
+warehouse_path = "{}"
+
+[users.share]
+token = "{}"
+"#,
+        API_KEY,
+        source(&sources.anki),
+        source(&sources.koreader),
+        source(&sources.proseuche),
+        source(&sources.arc),
+        path("stats.sqlite3"),
+        path("warehouse.sqlite3"),
+        SHARE_TOKEN,
+    );
+    fs::write(dir.join("users.toml"), users_config).unwrap();
+
+    let users = Users::from_file(&path("users.toml")).unwrap();
+    users_state(users, thresholds)
+}
+
+/// State of a server for the users
+fn users_state(users: Users, thresholds: StalenessThresholds) -> AppState {
+    let push = PushService::from_env().unwrap();
+    let notifier = Notifier::from_env(push.clone()).unwrap();
+    AppState {
+        staleness: staleness::spawn_monitor(users.profiles(), thresholds, notifier.clone())
+            .unwrap(),
+        users,
+        admin_api_key: AdminApiKey(Some(ADMIN_API_KEY.to_string())),
+        push,
+        notifier,
+    }
+}
+
+/// Sends a request, with the user's API key unless it has another, returning the status,
+/// content type and body
+async fn send(
+    app: &Router,
+    request: axum::http::request::Builder,
+    body: Body,
+) -> (StatusCode, String, Vec<u8>) {
+    let mut request = request.body(body).unwrap();
+    if !request.headers().contains_key(header::AUTHORIZATION) {
+        let api_key = format!("Bearer {}", API_KEY).parse().unwrap();
+        request.headers_mut().insert(header::AUTHORIZATION, api_key);
+    }
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .map(|value| value.to_str().unwrap().to_string())
+        .unwrap_or_default();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, content_type, body.to_vec())
+}
+
+async fn get_json(app: &Router, uri: &str) -> Value {
+    let (status, _, body) = send(app, Request::get(uri), Body::empty()).await;
+    assert_eq!(
+        status,
+        StatusCode::OK,
+        "GET {} failed: {}",
+        uri,
+        String::from_utf8_lossy(&body)
+    );
+    serde_json::from_slice(&body).unwrap()
+}
+
+/// Shape of a JSON value: its keys and value types, without the values
+///
+/// The elements of an array are merged into a single shape and a `null` only shows where a
+/// value is never present, so that the shape does not depend on which days happen to have,
+/// say, no reviews.
+fn shape(value: &Value) -> Value {
+    match value {
+        Value::Null => json!("<null>"),
+        Value::Bool(_) => json!("<bool>"),
+        Value::Number(_) => json!("<number>"),
+        Value::String(_) => json!("<string>"),
+        Value::Array(items) => {
+            Value::Array(items.iter().map(shape).reduce(merge).into_iter().collect())
+        }
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, value)| (key.clone(), shape(value)))
+                .collect(),
+        ),
+    }
+}
+
+/// Merges two shapes: objects get the keys of both, arrays the merged shape of all their
+/// elements, and a `null` takes the shape of the other value
+fn merge(a: Value, b: Value) -> Value {
+    let null = json!("<null>");
+    match (a, b) {
+        (Value::Object(mut a), Value::Object(b)) => {
+            for (key, value) in b {
+                let merged = match a.remove(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => value,
+                };
+                a.insert(key, merged);
+            }
+            Value::Object(a)
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            Value::Array(a.into_iter().chain(b).reduce(merge).into_iter().collect())
+        }
+        (a, b) if a == null => b,
+        (a, b) if b == null => a,
+        (a, b) => {
+            assert_eq!(a, b, "Values of the same field have different types");
+            a
+        }
+    }
+}
+
+/// Snapshots the shapes of the JSON responses to GET requests, by snapshot name
+async fn assert_get_shapes(app: &Router, endpoints: &[(&str, &str)]) {
+    for (name, uri) in endpoints {
+        let body = get_json(app, uri).await;
+        insta::assert_json_snapshot!(*name, shape(&body));
+    }
+}
+
+/// GET endpoints of the Anki statistics, by snapshot name
+const ANKI_ENDPOINTS: &[(&str, &str)] = &[
+    ("anki_books", "/api/v1/anki/books"),
+    ("anki_parse_warnings", "/api/v1/anki/parse-warnings"),
+    (
+        "anki_normalize",
+        "/api/v1/anki/normalize?reference=john%203%3A16",
+    ),
+    ("anki_coverage", "/api/v1/anki/coverage/John"),
+    ("anki_passages", "/api/v1/anki/passages"),
+    ("anki_span_distribution", "/api/v1/anki/span-distribution"),
+    ("anki_relapse", "/api/v1/anki/relapse"),
+    ("anki_retention", "/api/v1/anki/retention"),
+    ("anki_forecast", "/api/v1/anki/forecast"),
+    ("anki_sessions", "/api/v1/anki/sessions"),
+    ("anki_settings", "/api/v1/anki/settings"),
+];
+
+/// GET endpoints of the combined faith statistics, by snapshot name
+const FAITH_ENDPOINTS: &[(&str, &str)] = &[
+    ("faith_today", "/api/v1/faith/today"),
+    ("faith_daily", "/api/v1/faith/daily"),
+    ("faith_weekly", "/api/v1/faith/weekly"),
+    ("faith_score", "/api/v1/faith/score"),
+    ("faith_consistency", "/api/v1/faith/consistency"),
+    ("faith_budget", "/api/v1/faith/budget"),
+    ("faith_on_this_day", "/api/v1/faith/on-this-day"),
+    (
+        "goals_simulate",
+        "/api/v1/goals/simulate?new_verses_per_day=2&reading_minutes_per_day=15",
+    ),
+    ("plan_next_week", "/api/v1/plan/next-week"),
+    ("search", "/api/v1/search?q=John"),
+    ("ha_state", "/api/v1/ha/state"),
+    ("household_weekly", "/api/v1/household/weekly"),
+    ("social_preview", "/api/v1/social/preview"),
+];
+
+/// GET endpoints of the KOReader and Arc Timeline statistics, by snapshot name
+const READING_AND_ARC_ENDPOINTS: &[(&str, &str)] = &[
+    ("reading_patterns", "/api/v1/reading/patterns"),
+    ("reading_daily_office", "/api/v1/reading/daily-office"),
+    ("reading_completed", "/api/v1/reading/completed"),
+    ("arc_top_places", "/api/v1/arc/top-places"),
+    ("arc_gaps", "/api/v1/arc/gaps"),
+    ("arc_home_ratio", "/api/v1/arc/home-ratio"),
+    ("arc_new_places", "/api/v1/arc/new-places?months=24"),
+];
+
+#[tokio::test]
+async fn test_anki_endpoints() {
+    let (_dir, app) = fixture_app();
+    assert_get_shapes(&app, ANKI_ENDPOINTS).await;
+}
+
+#[tokio::test]
+async fn test_faith_endpoints() {
+    let (_dir, app) = fixture_app();
+    assert_get_shapes(&app, FAITH_ENDPOINTS).await;
+
+    let dates: Vec<String> = [1, 2, 3, 5, 8, 15].map(days_ago).to_vec();
+    let (status, _, body) = send(
+        &app,
+        Request::post("/api/v1/faith/days").header(header::CONTENT_TYPE, "application/json"),
+        Body::from(json!({ "dates": dates }).to_string()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    insta::assert_json_snapshot!("faith_days", shape(&body));
+}
+
+#[tokio::test]
+async fn test_untracked_days_default_to_zero() {
+    let (_dir, app) = fixture_app();
+    // Reaches back before the fixtures' first day
+    let days = FIXTURE_DAYS + 30;
+
+    let body = get_json(&app, &format!("/api/v1/faith/daily?days={}", days)).await;
+    let first = &body["days"][0];
+    assert_eq!(first["reading_minutes"], 0.0);
+    assert_eq!(body["summary"]["days_tracked"], days);
+
+    let body = get_json(
+        &app,
+        &format!("/api/v1/faith/daily?days={}&untracked=null", days),
+    )
+    .await;
+    let first = &body["days"][0];
+    assert!(first["reading_minutes"].is_null());
+    assert!(body["summary"]["days_tracked"].as_i64().unwrap() < days);
+
+    let body = get_json(&app, "/api/v1/faith/weekly?untracked=null").await;
+    assert_eq!(
+        body["summary"]["weeks_tracked"],
+        body["summary"]["total_weeks"]
+    );
+}
+
+#[tokio::test]
+async fn test_partial_period_defaults_to_include() {
+    let (_dir, app) = fixture_app();
+    for endpoint in ["/api/v1/faith/daily", "/api/v1/faith/weekly"] {
+        let body = get_json(&app, endpoint).await;
+        assert_eq!(body["summary"]["partial_period"], "include", "{}", endpoint);
+
+        let body = get_json(&app, &format!("{}?partial=exclude", endpoint)).await;
+        assert_eq!(body["summary"]["partial_period"], "exclude", "{}", endpoint);
+    }
+}
+
+#[tokio::test]
+async fn test_reading_and_arc_endpoints() {
+    let (_dir, app) = fixture_app();
+    assert_get_shapes(&app, READING_AND_ARC_ENDPOINTS).await;
+}
+
+#[tokio::test]
+async fn test_list_query() {
+    let (_dir, app) = fixture_app();
+    let places = get_json(
+        &app,
+        "/api/v1/arc/top-places?limit=2&fields=place_name,hours",
+    )
+    .await;
+    let places = places.as_array().unwrap();
+    assert_eq!(places.len(), 2);
+    for place in places {
+        let mut keys: Vec<&String> = place.as_object().unwrap().keys().collect();
+        keys.sort();
+        assert_eq!(keys, ["hours", "place_name"]);
+    }
+
+    let (status, _, _) = send(
+        &app,
+        Request::get("/api/v1/arc/top-places?fields=name"),
+        Body::empty(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_server_endpoints() {
+    let dir = TempDir::new().unwrap();
+    let config = fixture_config();
+    let sources = generate(dir.path(), &config).unwrap();
+    let any_age = Some(0.0);
+    let thresholds = StalenessThresholds {
+        anki: any_age,
+        reading: any_age,
+        prayer: any_age,
+        arc: any_age,
+    };
+    let state = app_state(dir.path(), &sources, thresholds);
+    let user = state.users.profiles().remove(0);
+
+    // Settings with fewer new cards a month ago, so that the rollup records a change
+    let mut settings = ankistats::get_deck_settings(sources.anki.to_str().unwrap()).unwrap();
+    settings.new_per_day -= 1;
+    Warehouse::open(user.config.warehouse_path.as_deref().unwrap())
+        .unwrap()
+        .record_deck_settings(&days_ago(30), &settings)
+        .unwrap();
+
+    // Roll the year up, then again after the sources lost all but the last month, so that
+    // the warehouse has rollups and the sources' history regressed
+    warehouse::rollup(&user.config, FIXTURE_DAYS as i32).unwrap();
+    let last_month = FixtureConfig {
+        start: config.end - Days::new(29),
+        ..config
+    };
+    generate(dir.path(), &last_month).unwrap();
+    let (_, regressions) = warehouse::rollup(&user.config, FIXTURE_DAYS as i32).unwrap();
+    assert!(!regressions.is_empty());
+    state
+        .staleness
+        .record_history_regressions(&user, regressions);
+    let ranges = user.config.sources.faith_stats().build().data_ranges();
+    assert!(!state.staleness.record(&user, &ranges).await.is_empty());
+
+    let app = router(state).unwrap();
+    let (status, _, body) = send(
+        &app,
+        Request::post("/api/v1/admin/exemptions")
+            .header(header::AUTHORIZATION, format!("Bearer {}", ADMIN_API_KEY))
+            .header(header::CONTENT_TYPE, "application/json"),
+        Body::from(
+            json!({
+                "user": "fixture",
+                "start": days_ago(10),
+                "end": days_ago(8),
+                "reason": "Vacation",
+            })
+            .to_string(),
+        ),
+    )
+    .await;
+    assert_eq!(
+        status,
+        StatusCode::CREATED,
+        "{}",
+        String::from_utf8_lossy(&body)
+    );
+
+    assert_get_shapes(
+        &app,
+        &[
+            ("health", "/health"),
+            ("user_health", "/api/v1/health"),
+            ("sources", "/api/v1/sources"),
+            ("warehouse_rollups", "/api/v1/warehouse/rollups"),
+            ("warehouse_deck_settings", "/api/v1/warehouse/deck-settings"),
+            ("share_weekly", &format!("/share/{}/weekly", SHARE_TOKEN)),
+        ],
+    )
+    .await;
+
+    let (status, _, body) = send(
+        &app,
+        Request::get("/api/v1/admin/exemptions?user=fixture")
+            .header(header::AUTHORIZATION, format!("Bearer {}", ADMIN_API_KEY)),
+        Body::empty(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    insta::assert_json_snapshot!("admin_exemptions", shape(&body));
+}
+
+#[tokio::test]
+async fn test_admin_routes_need_admin_key() {
+    let (_dir, app) = fixture_app();
+    let admin_key = format!("Bearer {}", ADMIN_API_KEY);
+
+    for uri in [
+        "/api/v1/admin/exemptions?user=fixture",
+        "/api/admin/exemptions?user=fixture",
+    ] {
+        // The user's own key (sent by default) and unknown keys are rejected
+        let (status, _, _) = send(&app, Request::get(uri), Body::empty()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", uri);
+        let (status, _, _) = send(
+            &app,
+            Request::get(uri).header(header::AUTHORIZATION, "Bearer wrong-key"),
+            Body::empty(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", uri);
+
+        let (status, _, _) = send(
+            &app,
+            Request::get(uri).header(header::AUTHORIZATION, &admin_key),
+            Body::empty(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{}", uri);
+    }
+
+    // The admin key does not belong to any user
+    let (status, _, _) = send(
+        &app,
+        Request::get("/api/v1/anki/books").header(header::AUTHORIZATION, &admin_key),
+        Body::empty(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Without ADMIN_API_KEY the admin routes are closed
+    let dir = TempDir::new().unwrap();
+    let sources = generate(dir.path(), &fixture_config()).unwrap();
+    let mut state = app_state(dir.path(), &sources, StalenessThresholds::default());
+    state.admin_api_key = AdminApiKey(None);
+    let app = router(state).unwrap();
+    let (status, _, _) = send(
+        &app,
+        Request::get("/api/v1/admin/exemptions?user=fixture")
+            .header(header::AUTHORIZATION, &admin_key),
+        Body::empty(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_feed_token() {
+    let (_dir, app) = fixture_app();
+    let encoded_key: String = API_KEY.bytes().map(|b| format!("%{:02X}", b)).collect();
+
+    for (uri, expected) in [
+        (
+            format!("/feed/weekly.atom?token={}", API_KEY),
+            StatusCode::OK,
+        ),
+        (
+            format!("/feed/weekly.atom?token={}", encoded_key),
+            StatusCode::OK,
+        ),
+        (
+            format!("/feed/weekly.atom?lang=en&token={}", encoded_key),
+            StatusCode::OK,
+        ),
+        (
+            "/feed/weekly.atom?token=wrong-key".to_string(),
+            StatusCode::UNAUTHORIZED,
+        ),
+        ("/feed/weekly.atom".to_string(), StatusCode::UNAUTHORIZED),
+        // Only feeds accept the key in the query string
+        (
+            format!("/api/v1/anki/books?token={}", API_KEY),
+            StatusCode::UNAUTHORIZED,
+        ),
+    ] {
+        // Without a Bearer header, so that only the query string authenticates
+        let (status, _, _) = send(
+            &app,
+            Request::get(&uri).header(header::AUTHORIZATION, ""),
+            Body::empty(),
+        )
+        .await;
+        assert_eq!(status, expected, "GET {}", uri);
+    }
+}
+
+#[tokio::test]
+async fn test_unconfigured_source_is_unavailable() {
+    let dir = TempDir::new().unwrap();
+    let sources = generate(dir.path(), &fixture_config()).unwrap();
+    let users_path = dir.path().join("users.toml");
+    fs::write(
+        &users_path,
+        format!(
+            "[[users]]\nid = \"anki-only\"\napi_key = \"{}\"\nanki_database_path = \"{}\"\n",
+            API_KEY,
+            sources.anki.display()
+        ),
+    )
+    .unwrap();
+    let users = Users::from_file(users_path.to_str().unwrap()).unwrap();
+    let app = router(users_state(users, StalenessThresholds::default())).unwrap();
+
+    get_json(&app, "/api/v1/anki/books").await;
+    for uri in ["/api/v1/reading/completed", "/api/v1/faith/daily"] {
+        let (status, _, body) = send(&app, Request::get(uri), Body::empty()).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE, "{}", uri);
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert!(
+            body["error"].as_str().unwrap().ends_with("path configured"),
+            "{}",
+            body
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_anki_deck_is_per_user() {
+    let dir = TempDir::new().unwrap();
+    let sources = generate(dir.path(), &fixture_config()).unwrap();
+    let users_path = dir.path().join("users.toml");
+    fs::write(
+        &users_path,
+        format!(
+            "[[users]]\nid = \"default-deck\"\napi_key = \"{}\"\nanki_database_path = \"{}\"\n\n\
+             [[users]]\nid = \"other-deck\"\napi_key = \"other-api-key\"\n\
+             anki_database_path = \"{}\"\nanki_deck_name = \"Memory::Psalms\"\n",
+            API_KEY,
+            sources.anki.display(),
+            sources.anki.display()
+        ),
+    )
+    .unwrap();
+    let users = Users::from_file(users_path.to_str().unwrap()).unwrap();
+    let app = router(users_state(users, StalenessThresholds::default())).unwrap();
+
+    get_json(&app, "/api/v1/anki/books").await;
+    let (status, _, body) = send(
+        &app,
+        Request::get("/api/v1/anki/books").header(header::AUTHORIZATION, "Bearer other-api-key"),
+        Body::empty(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert!(
+        body["error"]
+            .as_str()
+            .unwrap()
+            .contains("Failed to find deck 'Memory::Psalms'"),
+        "{}",
+        body
+    );
+}
+
+#[tokio::test]
+async fn test_requests_reuse_connections() {
+    let dir = TempDir::new().unwrap();
+    let sources = generate(dir.path(), &fixture_config()).unwrap();
+    let state = app_state(dir.path(), &sources, StalenessThresholds::default());
+    let user = state.users.authenticate(API_KEY).unwrap().clone();
+    let app = router(state).unwrap();
+    let (anki, reading) = (
+        user.config.sources.anki().unwrap().pool(),
+        user.config.sources.reading().unwrap().pool(),
+    );
+
+    // Each request returns its connection to the pool, where the next one takes it
+    for _ in 0..2 {
+        get_json(&app, "/api/v1/anki/books").await;
+        get_json(&app, "/api/v1/reading/completed").await;
+        assert_eq!(anki.idle_count(), 1);
+        assert_eq!(reading.idle_count(), 1);
+    }
+
+    // The combined stats read the same pools
+    get_json(&app, "/api/v1/faith/today").await;
+    assert_eq!(anki.idle_count(), 1);
+    assert_eq!(reading.idle_count(), 1);
+    assert_eq!(user.config.sources.prayer().unwrap().pool().idle_count(), 1);
+}
+
+#[tokio::test]
+async fn test_place_overrides_are_per_user() {
+    let dir = TempDir::new().unwrap();
+    let sources = generate(dir.path(), &fixture_config()).unwrap();
+    let overrides_path = dir.path().join("place-overrides.toml");
+    fs::write(
+        &overrides_path,
+        "[places.A0000000-0000-0000-0000-000000000002]\nname = \"Grace Church\"\n",
+    )
+    .unwrap();
+    let users_path = dir.path().join("users.toml");
+    fs::write(
+        &users_path,
+        format!(
+            "[[users]]\nid = \"overrides\"\napi_key = \"{}\"\narcstats_export_path = \"{}\"\n\
+             place_overrides_path = \"{}\"\n\n\
+             [[users]]\nid = \"no-overrides\"\napi_key = \"other-api-key\"\n\
+             arcstats_export_path = \"{}\"\n",
+            API_KEY,
+            sources.arc.display(),
+            overrides_path.display(),
+            sources.arc.display()
+        ),
+    )
+    .unwrap();
+    let users = Users::from_file(users_path.to_str().unwrap()).unwrap();
+    let app = router(users_state(users, StalenessThresholds::default())).unwrap();
+
+    let place_names = |body: &Value| -> Vec<String> {
+        body.as_array()
+            .unwrap()
+            .iter()
+            .map(|place| place["place_name"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    let uri = "/api/v1/arc/top-places?fields=place_name";
+    let names = place_names(&get_json(&app, uri).await);
+    assert!(names.contains(&"Grace Church".to_string()), "{:?}", names);
+    assert!(!names.contains(&"Grace Lutheran Church".to_string()));
+
+    let (status, _, body) = send(
+        &app,
+        Request::get(uri).header(header::AUTHORIZATION, "Bearer other-api-key"),
+        Body::empty(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let names = place_names(&serde_json::from_slice(&body).unwrap());
+    assert!(
+        names.contains(&"Grace Lutheran Church".to_string()),
+        "{:?}",
+        names
+    );
+}
+
+#[tokio::test]
+async fn test_home_place_is_per_user() {
+    let dir = TempDir::new().unwrap();
+    let sources = generate(dir.path(), &fixture_config()).unwrap();
+    let user = |id: &str, api_key: &str, home_place: &str| {
+        format!(
+            "[[users]]\nid = \"{}\"\napi_key = \"{}\"\nanki_database_path = \"{}\"\n\
+             koreader_database_path = \"{}\"\narcstats_export_path = \"{}\"\n{}\n",
+            id,
+            api_key,
+            sources.anki.display(),
+            sources.koreader.display(),
+            sources.arc.display(),
+            home_place
+        )
+    };
+    let users_path = dir.path().join("users.toml");
+    fs::write(
+        &users_path,
+        user("office", API_KEY, "home_place = \"Office\"\n") + &user("home", "other-api-key", ""),
+    )
+    .unwrap();
+    let users = Users::from_file(users_path.to_str().unwrap()).unwrap();
+    let app = router(users_state(users, StalenessThresholds::default())).unwrap();
+
+    let get_as = |uri: &'static str, api_key: &'static str| {
+        let app = app.clone();
+        async move {
+            let (status, _, body) = send(
+                &app,
+                Request::get(uri).header(header::AUTHORIZATION, format!("Bearer {}", api_key)),
+                Body::empty(),
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK, "GET {}", uri);
+            serde_json::from_slice::<Value>(&body).unwrap()
+        }
+    };
+    let home_minutes = |weeks: Value| -> f64 {
+        weeks
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|week| week["home_minutes"].as_f64().unwrap())
+            .sum()
+    };
+    let place_labels = |results: Value| -> Vec<String> {
+        results["hits"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|hit| hit["kind"] == "place")
+            .map(|hit| hit["label"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    let office = home_minutes(get_as("/api/v1/arc/home-ratio", API_KEY).await);
+    let home = home_minutes(get_as("/api/v1/arc/home-ratio", "other-api-key").await);
+    assert!(office > 0.0 && home > 0.0);
+    assert_ne!(office, home);
+
+    // The home place is left out of the places found by the search
+    let office = place_labels(get_as("/api/v1/search?q=o", API_KEY).await);
+    let home = place_labels(get_as("/api/v1/search?q=o", "other-api-key").await);
+    assert!(office.contains(&"Home".to_string()), "{:?}", office);
+    assert!(!office.contains(&"Office".to_string()), "{:?}", office);
+    assert!(home.contains(&"Office".to_string()), "{:?}", home);
+    assert!(!home.contains(&"Home".to_string()), "{:?}", home);
+}
+
+#[tokio::test]
+async fn test_share_hides_errors() {
+    let (dir, app) = fixture_app();
+    fs::write(dir.path().join("collection.anki2"), "not a database").unwrap();
+
+    let (status, _, body) = send(
+        &app,
+        Request::get(format!("/share/{}/weekly", SHARE_TOKEN)),
+        Body::empty(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["error"], "Failed to compute the shared statistics");
+}
+
+#[tokio::test]
+async fn test_notes_and_tags() {
+    let (_dir, app) = fixture_app();
+    let date = days_ago(1);
+
+    let (status, _, body) = send(
+        &app,
+        Request::put(format!("/api/v1/notes/{}", date))
+            .header(header::CONTENT_TYPE, "application/json"),
+        Body::from(json!({"text": "Read Romans with the family"}).to_string()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", String::from_utf8_lossy(&body));
+    let (status, _, body) = send(
+        &app,
+        Request::put(format!("/api/v1/tags/retreat/{}", date)),
+        Body::empty(),
+    )
+    .await;
+    assert!(status.is_success(), "{}", String::from_utf8_lossy(&body));
+
+    let (status, _, body) = send(
+        &app,
+        Request::get("/api/v1/faith/daily?days=90&tag=Retreat"),
+        Body::empty(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["days"].as_array().unwrap().len(), 1);
+    for uri in [
+        "/api/v1/faith/daily?tag=retreat_day",
+        "/api/v1/faith/daily?days=0&tag=retreat",
+    ] {
+        let (status, _, _) = send(&app, Request::get(uri), Body::empty()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "GET {}", uri);
+    }
+
+    assert_get_shapes(
+        &app,
+        &[
+            ("note", &format!("/api/v1/notes/{}", date)),
+            ("tags", "/api/v1/tags"),
+        ],
+    )
+    .await;
+}
+
+/// Sends a GET request asking for an envelope, returning the status and body
+async fn get_enveloped(app: &Router, uri: &str) -> (StatusCode, Vec<u8>) {
+    let request = Request::get(uri).header(crate::envelope::ENVELOPE_HEADER, "true");
+    let (status, _, body) = send(app, request, Body::empty()).await;
+    (status, body)
+}
+
+#[tokio::test]
+async fn test_envelope() {
+    let (_dir, app) = fixture_app();
+    let (status, body) = get_enveloped(&app, "/api/v1/faith/daily").await;
+    assert_eq!(status, StatusCode::OK);
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    insta::assert_json_snapshot!("faith_daily_envelope", shape(&body));
+
+    // The envelope wraps the usual response, which is left as it is without the header
+    let plain = get_json(&app, "/api/v1/faith/daily").await;
+    assert!(plain.get("meta").is_none());
+    assert_eq!(shape(&body["data"]), shape(&plain));
+    // Daily stats don't read the Arc export
+    assert_eq!(
+        body["meta"]["sources_used"],
+        json!(["anki", "koreader", "proseuche"])
+    );
+    assert_eq!(body["meta"]["period"]["end"], json!(days_ago(0)));
+
+    let (_, body) = get_enveloped(&app, "/api/v1/faith/weekly").await;
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        body["meta"]["sources_used"],
+        json!(["anki", "koreader", "proseuche", "arc"])
+    );
+
+    // Errors are not wrapped
+    let (status, body) = get_enveloped(&app, "/api/v1/faith/daily?days=0").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert!(body.get("data").is_none());
+    assert!(body.get("error").is_some());
+}
+
+/// Checks that a zip archive lists its files in a manifest and that they are valid JSON
+fn assert_json_archive(body: &[u8]) {
+    let mut archive = zip::ZipArchive::new(Cursor::new(body)).unwrap();
+    assert!(archive.file_names().any(|name| name == "manifest.json"));
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index).unwrap();
+        let mut contents = String::new();
+        file.read_to_string(&mut contents).unwrap();
+        if let Err(e) = serde_json::from_str::<Value>(&contents) {
+            panic!("{} in the archive is not valid JSON: {}", file.name(), e);
+        }
+    }
+}
+
+/// Parses an XML document, returning the name of its root element
+fn xml_root(body: &[u8]) -> String {
+    let mut reader = Reader::from_reader(body);
+    let mut root = None;
+    let mut buffer = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buffer) {
+            Ok(Event::Start(element) | Event::Empty(element)) if root.is_none() => {
+                root = Some(String::from_utf8(element.name().as_ref().to_vec()).unwrap());
+            }
+            Ok(Event::Eof) => break,
+            Ok(_) => {}
+            Err(e) => panic!(
+                "Invalid XML at position {}: {}",
+                reader.buffer_position(),
+                e
+            ),
+        }
+        buffer.clear();
+    }
+    root.expect("XML document without a root element")
+}
+
+#[tokio::test]
+async fn test_non_json_endpoints() {
+    let (_dir, app) = fixture_app();
+    let mut endpoints = vec![
+        "/api/v1/export/all".to_string(),
+        "/api/v1/export/lineprotocol".to_string(),
+        format!("/feed/weekly.atom?token={}", API_KEY),
+    ];
+    if cfg!(feature = "charts") {
+        endpoints.extend(
+            [
+                "/api/v1/render/weekly.png",
+                "/api/v1/charts/daily.svg",
+                "/api/v1/charts/weekly.svg",
+                "/api/v1/charts/heatmap.svg",
+            ]
+            .map(String::from),
+        );
+    }
+
+    for uri in endpoints {
+        let (status, content_type, body) = send(&app, Request::get(&uri), Body::empty()).await;
+        assert_eq!(
+            status,
+            StatusCode::OK,
+            "GET {} failed: {}",
+            uri,
+            String::from_utf8_lossy(&body)
+        );
+        assert!(!body.is_empty(), "GET {} returned an empty body", uri);
+        match content_type.split(';').next().unwrap() {
+            "application/zip" => assert_json_archive(&body),
+            "application/atom+xml" => assert_eq!(xml_root(&body), "feed", "GET {}", uri),
+            "image/svg+xml" => assert_eq!(xml_root(&body), "svg", "GET {}", uri),
+            _ => {}
+        }
+        insta::assert_snapshot!(
+            uri.trim_start_matches("/api/v1")
+                .trim_start_matches('/')
+                .split('?')
+                .next()
+                .unwrap()
+                .replace(['/', '.'], "_"),
+            content_type
+        );
+    }
+}
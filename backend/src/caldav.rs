@@ -0,0 +1,228 @@
+use anyhow::{Context, Result, bail};
+use chrono::{Days, NaiveDate, Utc};
+use faithstats::goals::Goal;
+use faithstats::report::format_minutes;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::env;
+use std::time::Duration;
+
+use crate::users::UserProfile;
+
+/// Default interval between goal checks
+const DEFAULT_CHECK_INTERVAL_MINUTES: u64 = 60;
+
+/// Goals less than this many minutes behind pace do not get a catch-up task
+const MIN_MINUTES_BEHIND: f64 = 15.0;
+
+/// CalDAV calendar collection that receives catch-up tasks
+#[derive(Clone, Deserialize)]
+pub struct CalDavConfig {
+    /// URL of the calendar collection (e.g. "https://dav.example.com/calendars/me/tasks/")
+    pub calendar_url: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+impl CalDavConfig {
+    /// Reads the default user's calendar from CALDAV_CALENDAR_URL, CALDAV_USERNAME and
+    /// CALDAV_PASSWORD
+    pub fn from_env() -> Option<Self> {
+        Some(Self {
+            calendar_url: env::var("CALDAV_CALENDAR_URL").ok()?,
+            username: env::var("CALDAV_USERNAME").ok(),
+            password: env::var("CALDAV_PASSWORD").ok(),
+        })
+    }
+}
+
+/// Spawns a background task that periodically checks every user's weekly goals against an
+/// even pace through the week, and creates a VTODO on the user's CalDAV calendar for each
+/// goal that falls behind (at most one per goal and week)
+///
+/// # Errors
+/// Returns an error if GOAL_CHECK_INTERVAL_MINUTES is not a whole number of minutes of at
+/// least 1
+pub fn spawn_catch_up_tasks(users: Vec<UserProfile>) -> Result<()> {
+    let users: Vec<UserProfile> = users
+        .into_iter()
+        .filter(|user| user.caldav.is_some() && !user.goals.is_empty())
+        .collect();
+
+    if users.is_empty() {
+        return Ok(());
+    }
+
+    let interval_minutes: u64 = match env::var("GOAL_CHECK_INTERVAL_MINUTES") {
+        Ok(minutes) => minutes
+            .parse()
+            .context("Invalid GOAL_CHECK_INTERVAL_MINUTES")?,
+        Err(_) => DEFAULT_CHECK_INTERVAL_MINUTES,
+    };
+    if interval_minutes < 1 {
+        bail!("GOAL_CHECK_INTERVAL_MINUTES must be at least 1");
+    }
+
+    tokio::spawn(async move {
+        let client = reqwest::Client::new();
+        let mut created = HashSet::new();
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_minutes * 60));
+
+        loop {
+            interval.tick().await;
+
+            for user in &users {
+                if let Err(e) = check_user(&client, user, &mut created).await {
+                    eprintln!("Goal check for user '{}' failed: {:#}", user.id, e);
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Creates catch-up tasks for the user's goals that are behind pace this week
+///
+/// `created` holds the UIDs of tasks already created by this process, to avoid repeating
+/// requests for them.
+async fn check_user(
+    client: &reqwest::Client,
+    user: &UserProfile,
+    created: &mut HashSet<String>,
+) -> Result<()> {
+    let Some(caldav) = &user.caldav else {
+        return Ok(());
+    };
+
+    let config = user.config.clone();
+    let stats = tokio::task::spawn_blocking(move || config.faith_weekly_stats()).await??;
+    let Some(week) = stats.weeks.last() else {
+        return Ok(());
+    };
+    let elapsed_fraction = statsutils::week_elapsed_fraction(&week.week_start)?;
+
+    for goal in &user.goals {
+        let minutes_behind = goal.minutes_behind_pace(week, elapsed_fraction);
+        if minutes_behind < MIN_MINUTES_BEHIND {
+            continue;
+        }
+
+        let uid = task_uid(&user.id, goal, &week.week_start);
+        if created.contains(&uid) {
+            continue;
+        }
+
+        let summary = format!(
+            "Catch up: {} {} behind this week",
+            format_minutes(minutes_behind),
+            goal.metric.activity_label()
+        );
+        let todo = vtodo(&uid, &summary, &week.week_start)?;
+        create_task(client, caldav, &uid, todo).await?;
+        created.insert(uid);
+    }
+
+    Ok(())
+}
+
+/// Stable task UID per user, goal and week, so that a task is only ever created once
+///
+/// The UID is also the task's file name in the calendar URL, so the user id and goal name
+/// are reduced to lowercase letters, digits and dashes.
+fn task_uid(user_id: &str, goal: &Goal, week_start: &str) -> String {
+    format!(
+        "life-stats-{}-{}-{}",
+        slug(user_id),
+        slug(&goal.name),
+        week_start
+    )
+}
+
+/// Lowercases ASCII letters and digits and replaces every other character with a dash
+fn slug(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+/// Builds an iCalendar object with a single VTODO due on the last day of the week
+fn vtodo(uid: &str, summary: &str, week_start: &str) -> Result<String> {
+    let week_end = NaiveDate::parse_from_str(week_start, "%Y-%m-%d")
+        .context(format!("Failed to parse date '{}'", week_start))?
+        .checked_add_days(Days::new(6))
+        .context("Week end out of range")?;
+
+    Ok(format!(
+        "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//Life Stats//EN\r\nBEGIN:VTODO\r\nUID:{}\r\nDTSTAMP:{}\r\nSUMMARY:{}\r\nDUE;VALUE=DATE:{}\r\nSTATUS:NEEDS-ACTION\r\nEND:VTODO\r\nEND:VCALENDAR\r\n",
+        uid,
+        Utc::now().format("%Y%m%dT%H%M%SZ"),
+        escape_ical_text(summary),
+        week_end.format("%Y%m%d"),
+    ))
+}
+
+/// Escapes text for use in an iCalendar TEXT property value
+fn escape_ical_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Uploads the task to the calendar, leaving an existing task with the same UID untouched
+async fn create_task(
+    client: &reqwest::Client,
+    caldav: &CalDavConfig,
+    uid: &str,
+    todo: String,
+) -> Result<()> {
+    let url = format!("{}/{}.ics", caldav.calendar_url.trim_end_matches('/'), uid);
+
+    let mut request = client
+        .put(&url)
+        .header("Content-Type", "text/calendar; charset=utf-8")
+        .header("If-None-Match", "*")
+        .body(todo);
+    if let Some(username) = &caldav.username {
+        request = request.basic_auth(username, caldav.password.as_ref());
+    }
+
+    let response = request
+        .send()
+        .await
+        .context(format!("Failed to create CalDAV task at {}", url))?;
+
+    match response.status() {
+        // The task already exists, e.g. created before a restart
+        StatusCode::PRECONDITION_FAILED => Ok(()),
+        status if status.is_success() => Ok(()),
+        status => bail!("CalDAV server returned {} for {}", status, url),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_uid_is_safe_in_url_path() {
+        let goal = Goal {
+            name: "Bible reading".to_string(),
+            metric: faithstats::goals::GoalMetric::ReadingMinutes,
+            weekly_target_minutes: 150.0,
+        };
+
+        assert_eq!(
+            task_uid("../Admin?x=1", &goal, "2024-01-01"),
+            "life-stats----admin-x-1-bible-reading-2024-01-01"
+        );
+    }
+}
@@ -0,0 +1,490 @@
+use anyhow::{Context, Result, anyhow};
+use chrono::{Datelike, NaiveDate};
+use faithstats::models::{FaithDailyStats, FaithWeeklyStats};
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use serde::Deserialize;
+use statsutils::format::format_number;
+use std::env;
+use std::sync::OnceLock;
+use utoipa::ToSchema;
+
+/// Font used for chart text unless RENDER_FONT_PATH is set
+const DEFAULT_FONT_PATH: &str = "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf";
+
+/// Font family name charts are drawn with
+const FONT_FAMILY: &str = "sans-serif";
+
+/// Largest width/height accepted for rendered images
+pub const MAX_IMAGE_SIZE: u32 = 4000;
+
+/// Color scheme of rendered charts
+#[derive(Debug, Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Palette {
+    /// Black text and distinct gray levels, suited for e-ink displays
+    #[default]
+    Grayscale,
+    Color,
+}
+
+impl Palette {
+    /// Colors for the Anki, reading, church and prayer series
+    fn series_colors(&self) -> [RGBColor; 4] {
+        match self {
+            Palette::Grayscale => [
+                RGBColor(0, 0, 0),
+                RGBColor(80, 80, 80),
+                RGBColor(150, 150, 150),
+                RGBColor(205, 205, 205),
+            ],
+            Palette::Color => [
+                RGBColor(59, 130, 246),
+                RGBColor(16, 185, 129),
+                RGBColor(245, 158, 11),
+                RGBColor(139, 92, 246),
+            ],
+        }
+    }
+
+    /// Heatmap cell colors, from no activity to most activity
+    fn heatmap_colors(&self) -> [RGBColor; 5] {
+        match self {
+            Palette::Grayscale => [
+                RGBColor(235, 235, 235),
+                RGBColor(190, 190, 190),
+                RGBColor(140, 140, 140),
+                RGBColor(90, 90, 90),
+                RGBColor(30, 30, 30),
+            ],
+            Palette::Color => [
+                RGBColor(235, 237, 240),
+                RGBColor(155, 233, 168),
+                RGBColor(64, 196, 99),
+                RGBColor(48, 161, 78),
+                RGBColor(33, 110, 57),
+            ],
+        }
+    }
+}
+
+/// Data of a stacked bar chart: one bar per label, one segment per series
+pub struct StackedBarChart {
+    pub title: &'static str,
+    /// Label of each bar
+    pub labels: Vec<String>,
+    /// Name and per-bar values of each series, stacked bottom to top
+    pub series: Vec<(&'static str, Vec<f64>)>,
+    /// Bars in which the Bible deck's scheduling settings changed, marked with a line
+    pub markers: Vec<usize>,
+}
+
+impl StackedBarChart {
+    /// Weekly faith minutes per source, marking the weeks with a deck settings change
+    /// (dates in YYYY-MM-DD format)
+    pub fn weekly(stats: &FaithWeeklyStats, change_dates: &[String]) -> Self {
+        let weeks = &stats.weeks;
+        let mut markers: Vec<usize> = change_dates
+            .iter()
+            .filter_map(|date| weeks.iter().rposition(|w| w.week_start <= *date))
+            .collect();
+        markers.dedup();
+        Self {
+            title: "Faith time per week (minutes)",
+            labels: weeks.iter().map(|w| short_date(&w.week_start)).collect(),
+            series: vec![
+                ("Anki", weeks.iter().map(|w| w.anki_minutes).collect()),
+                ("Reading", weeks.iter().map(|w| w.reading_minutes).collect()),
+                (
+                    "Church",
+                    weeks.iter().map(|w| w.at_church_minutes).collect(),
+                ),
+                ("Prayer", weeks.iter().map(|w| w.prayer_minutes).collect()),
+            ],
+            markers,
+        }
+    }
+
+    /// Daily faith minutes per source, marking the days with a deck settings change (dates
+    /// in YYYY-MM-DD format)
+    pub fn daily(stats: &FaithDailyStats, change_dates: &[String]) -> Self {
+        let days = &stats.days;
+        Self {
+            title: "Faith time per day (minutes)",
+            labels: days.iter().map(|d| short_date(&d.date)).collect(),
+            series: vec![
+                // Days before a source's history are drawn as days without activity
+                (
+                    "Anki",
+                    days.iter().map(|d| d.anki_minutes.unwrap_or(0.0)).collect(),
+                ),
+                (
+                    "Reading",
+                    days.iter()
+                        .map(|d| d.reading_minutes.unwrap_or(0.0))
+                        .collect(),
+                ),
+                (
+                    "Prayer",
+                    days.iter()
+                        .map(|d| d.prayer_minutes.unwrap_or(0.0))
+                        .collect(),
+                ),
+            ],
+            markers: change_dates
+                .iter()
+                .filter_map(|date| days.iter().position(|d| d.date == *date))
+                .collect(),
+        }
+    }
+
+    fn total(&self, bar: usize) -> f64 {
+        self.series.iter().map(|(_, values)| values[bar]).sum()
+    }
+}
+
+/// Formats YYYY-MM-DD as MM-DD
+fn short_date(date: &str) -> String {
+    date.get(5..).unwrap_or(date).to_string()
+}
+
+/// Draws a stacked bar chart onto the drawing area
+pub fn draw_stacked_bars<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    chart_data: &StackedBarChart,
+    palette: Palette,
+) -> Result<()> {
+    ensure_font_registered()?;
+    root.fill(&WHITE).map_err(|e| anyhow!("{}", e))?;
+
+    let bars = chart_data.labels.len();
+    let max_minutes = (0..bars)
+        .map(|bar| chart_data.total(bar))
+        .fold(0.0, f64::max)
+        .max(1.0);
+
+    let (width, height) = root.dim_in_pixel();
+    let font_size = (height / 24).max(10);
+    // Skip labels that would overlap when there are many narrow bars
+    let label_every = (bars as u32 * font_size * 3).div_ceil(width.max(1)).max(1) as usize;
+
+    let mut chart = ChartBuilder::on(root)
+        .caption(
+            chart_data.title,
+            (FONT_FAMILY, font_size + 4).into_font().color(&BLACK),
+        )
+        .margin(font_size)
+        .x_label_area_size(font_size * 2)
+        .y_label_area_size(font_size * 3)
+        .build_cartesian_2d(
+            (0..bars.saturating_sub(1)).into_segmented(),
+            0.0..max_minutes * 1.1,
+        )
+        .map_err(|e| anyhow!("{}", e))?;
+
+    chart
+        .configure_mesh()
+        .disable_x_mesh()
+        .light_line_style(WHITE)
+        .bold_line_style(RGBColor(220, 220, 220))
+        .label_style((FONT_FAMILY, font_size).into_font().color(&BLACK))
+        .x_labels(bars)
+        .x_label_formatter(&|segment| match segment {
+            SegmentValue::CenterOf(i) if i % label_every == 0 => {
+                chart_data.labels.get(*i).cloned().unwrap_or_default()
+            }
+            _ => String::new(),
+        })
+        .y_label_formatter(&|minutes| format_number(*minutes, 0))
+        .draw()
+        .map_err(|e| anyhow!("{}", e))?;
+
+    let colors = palette.series_colors();
+    let bar_margin = (width / (bars as u32 * 10).max(1)).clamp(1, 4);
+
+    for (index, (label, values)) in chart_data.series.iter().enumerate() {
+        let color = colors[index % colors.len()];
+        let rectangles = values.iter().enumerate().map(|(i, value)| {
+            let below: f64 = chart_data.series[..index]
+                .iter()
+                .map(|(_, values)| values[i])
+                .sum();
+            let mut bar = Rectangle::new(
+                [
+                    (SegmentValue::Exact(i), below),
+                    (SegmentValue::Exact(i + 1), below + value),
+                ],
+                color.filled(),
+            );
+            bar.set_margin(0, 0, bar_margin, bar_margin);
+            bar
+        });
+
+        chart
+            .draw_series(rectangles)
+            .map_err(|e| anyhow!("{}", e))?
+            .label(*label)
+            .legend(move |(x, y)| Rectangle::new([(x, y - 5), (x + 10, y + 5)], color.filled()));
+    }
+
+    if !chart_data.markers.is_empty() {
+        let top = max_minutes * 1.1;
+        chart
+            .draw_series(chart_data.markers.iter().map(|&bar| {
+                PathElement::new(
+                    vec![
+                        (SegmentValue::Exact(bar), 0.0),
+                        (SegmentValue::Exact(bar), top),
+                    ],
+                    BLACK.stroke_width(2),
+                )
+            }))
+            .map_err(|e| anyhow!("{}", e))?
+            .label("Deck settings changed")
+            .legend(|(x, y)| {
+                PathElement::new(vec![(x + 5, y - 5), (x + 5, y + 5)], BLACK.stroke_width(2))
+            });
+    }
+
+    chart
+        .configure_series_labels()
+        .position(SeriesLabelPosition::UpperLeft)
+        .background_style(WHITE.mix(0.9))
+        .border_style(BLACK)
+        .label_font((FONT_FAMILY, font_size).into_font().color(&BLACK))
+        .draw()
+        .map_err(|e| anyhow!("{}", e))?;
+
+    root.present().map_err(|e| anyhow!("{}", e))?;
+    Ok(())
+}
+
+/// Renders a stacked bar chart as a PNG image
+///
+/// The grayscale palette produces an 8-bit grayscale PNG, the color palette an RGB PNG.
+pub fn render_png(
+    chart: &StackedBarChart,
+    width: u32,
+    height: u32,
+    palette: Palette,
+) -> Result<Vec<u8>> {
+    let mut rgb = vec![0u8; width as usize * height as usize * 3];
+    {
+        let root = BitMapBackend::with_buffer(&mut rgb, (width, height)).into_drawing_area();
+        draw_stacked_bars(&root, chart, palette)?;
+    }
+
+    let (color_type, pixels) = match palette {
+        Palette::Grayscale => (
+            png::ColorType::Grayscale,
+            rgb.chunks_exact(3)
+                .map(|p| ((p[0] as u32 * 299 + p[1] as u32 * 587 + p[2] as u32 * 114) / 1000) as u8)
+                .collect(),
+        ),
+        Palette::Color => (png::ColorType::Rgb, rgb),
+    };
+
+    let mut image = Vec::new();
+    let mut encoder = png::Encoder::new(&mut image, width, height);
+    encoder.set_color(color_type);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder
+        .write_header()
+        .context("Failed to write PNG header")?;
+    writer
+        .write_image_data(&pixels)
+        .context("Failed to encode PNG image")?;
+    writer.finish().context("Failed to finish PNG image")?;
+
+    Ok(image)
+}
+
+/// Renders a stacked bar chart as an SVG document
+pub fn render_svg(
+    chart: &StackedBarChart,
+    width: u32,
+    height: u32,
+    palette: Palette,
+) -> Result<String> {
+    let mut svg = String::new();
+    {
+        let root = SVGBackend::with_string(&mut svg, (width, height)).into_drawing_area();
+        draw_stacked_bars(&root, chart, palette)?;
+    }
+    Ok(svg)
+}
+
+/// Renders a calendar heatmap of total daily faith minutes as an SVG document
+///
+/// Days are laid out in columns of weeks (Sunday at the top), and each cell is shaded by
+/// its total minutes relative to the most active day. The cells are as large as fits within
+/// `width` and `height`, and the image is cropped to the grid.
+pub fn render_heatmap_svg(
+    stats: &FaithDailyStats,
+    width: u32,
+    height: u32,
+    palette: Palette,
+) -> Result<String> {
+    ensure_font_registered()?;
+
+    let first_date = match stats.days.first() {
+        Some(day) => NaiveDate::parse_from_str(&day.date, "%Y-%m-%d")
+            .context(format!("Invalid date {}", day.date))?,
+        None => NaiveDate::default(),
+    };
+    let offset = first_date.weekday().num_days_from_sunday() as usize;
+    let columns = (stats.days.len() + offset).div_ceil(7).max(1) as u32;
+
+    let label_width = 30;
+    let label_height = 16;
+    let cell = (width.saturating_sub(label_width) / columns)
+        .min(height.saturating_sub(label_height) / 7)
+        .max(4);
+    let gap = (cell / 6).max(1);
+    let width = label_width + columns * cell;
+    let height = label_height + 7 * cell;
+
+    let max_minutes = stats
+        .days
+        .iter()
+        .map(|d| d.total_minutes())
+        .fold(0.0, f64::max);
+    let colors = palette.heatmap_colors();
+    let label_style = (FONT_FAMILY, 10).into_font().color(&BLACK);
+
+    let mut svg = String::new();
+    {
+        let root = SVGBackend::with_string(&mut svg, (width, height)).into_drawing_area();
+        root.fill(&WHITE).map_err(|e| anyhow!("{}", e))?;
+
+        for (row, name) in [(1, "Mon"), (3, "Wed"), (5, "Fri")] {
+            root.draw(&Text::new(
+                name,
+                (0, (label_height + row * cell) as i32),
+                label_style.clone(),
+            ))
+            .map_err(|e| anyhow!("{}", e))?;
+        }
+
+        let mut last_month = None;
+        for (index, day) in stats.days.iter().enumerate() {
+            let position = index + offset;
+            let x = (label_width + (position / 7) as u32 * cell) as i32;
+            let y = (label_height + (position % 7) as u32 * cell) as i32;
+
+            // Label the column in which each month starts
+            let month = day.date.get(5..7);
+            if (position.is_multiple_of(7) || index == 0) && month != last_month {
+                last_month = month;
+                if let Ok(date) = NaiveDate::parse_from_str(&day.date, "%Y-%m-%d") {
+                    root.draw(&Text::new(
+                        date.format("%b").to_string(),
+                        (x, 0),
+                        label_style.clone(),
+                    ))
+                    .map_err(|e| anyhow!("{}", e))?;
+                }
+            }
+
+            let level = if max_minutes > 0.0 && day.total_minutes() > 0.0 {
+                ((day.total_minutes() / max_minutes * 4.0).ceil() as usize).clamp(1, 4)
+            } else {
+                0
+            };
+            root.draw(&Rectangle::new(
+                [(x, y), (x + (cell - gap) as i32, y + (cell - gap) as i32)],
+                colors[level].filled(),
+            ))
+            .map_err(|e| anyhow!("{}", e))?;
+        }
+
+        root.present().map_err(|e| anyhow!("{}", e))?;
+    }
+
+    Ok(svg)
+}
+
+/// Registers the chart font with plotters on first use
+///
+/// The font is read from RENDER_FONT_PATH, or DejaVu Sans at its usual location.
+fn ensure_font_registered() -> Result<()> {
+    static REGISTERED: OnceLock<Result<(), String>> = OnceLock::new();
+
+    REGISTERED
+        .get_or_init(|| {
+            let path = env::var("RENDER_FONT_PATH").unwrap_or(DEFAULT_FONT_PATH.to_string());
+            let bytes = std::fs::read(&path)
+                .map_err(|e| format!("Failed to read chart font {}: {}", path, e))?;
+            plotters::style::register_font(
+                FONT_FAMILY,
+                FontStyle::Normal,
+                Box::leak(bytes.into_boxed_slice()),
+            )
+            .map_err(|_| format!("Invalid chart font {}", path))
+        })
+        .clone()
+        .map_err(|e| anyhow!(e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Days;
+    use faithstats::models::FaithDayStats;
+
+    /// Days with activity from a Sunday on, one more minute each day
+    fn daily_stats(days: u64) -> FaithDailyStats {
+        let start = NaiveDate::from_ymd_opt(2025, 1, 5).unwrap();
+        FaithDailyStats::new(
+            (0..days)
+                .map(|day| FaithDayStats {
+                    date: (start + Days::new(day)).to_string(),
+                    reading_minutes: Some(day as f64),
+                    ..Default::default()
+                })
+                .collect(),
+        )
+    }
+
+    /// Width and height of an SVG document
+    fn svg_size(svg: &str) -> (u32, u32) {
+        let attribute = |name: &str| {
+            let start = svg.find(&format!("{}=\"", name)).unwrap() + name.len() + 2;
+            let end = start + svg[start..].find('"').unwrap();
+            svg[start..end].parse().unwrap()
+        };
+        (attribute("width"), attribute("height"))
+    }
+
+    #[test]
+    fn test_heatmap_fits_size() {
+        let stats = daily_stats(365);
+
+        // 53 weeks of 14 pixel cells fit the width, leaving most of the height unused
+        let svg = render_heatmap_svg(&stats, 800, 480, Palette::Grayscale).unwrap();
+        assert_eq!(svg_size(&svg), (30 + 53 * 14, 16 + 7 * 14));
+
+        // A lower height shrinks the cells
+        let svg = render_heatmap_svg(&stats, 800, 100, Palette::Color).unwrap();
+        assert_eq!(svg_size(&svg), (30 + 53 * 12, 100));
+
+        // Without days a single empty week is drawn
+        let svg = render_heatmap_svg(&daily_stats(0), 400, 300, Palette::Grayscale).unwrap();
+        assert_eq!(svg_size(&svg), (30 + 40, 16 + 7 * 40));
+    }
+
+    #[test]
+    fn test_daily_chart() {
+        let stats = daily_stats(7);
+        let chart = StackedBarChart::daily(&stats, &["2025-01-07".to_string()]);
+        assert_eq!(chart.labels.first().unwrap(), "01-05");
+        assert_eq!(chart.markers, vec![2]);
+        assert_eq!(chart.total(6), 6.0);
+
+        let svg = render_svg(&chart, 640, 320, Palette::Color).unwrap();
+        assert_eq!(svg_size(&svg), (640, 320));
+
+        let png = render_png(&chart, 320, 200, Palette::Grayscale).unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+    }
+}
@@ -0,0 +1,172 @@
+use axum::{
+    body::{Body, to_bytes},
+    extract::Request,
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use serde_json::Value;
+use statsutils::{PartialPeriod, now_rfc3339};
+use utoipa::ToSchema;
+
+use crate::api_route;
+
+/// Request header that asks for the response to be wrapped in an envelope (`true` or `1`)
+pub const ENVELOPE_HEADER: &str = "X-Envelope";
+
+/// Largest response body that is wrapped
+const MAX_ENVELOPE_BYTES: usize = 16 * 1024 * 1024;
+
+/// Date range a response covers, attached by handlers as a response extension
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ResponsePeriod {
+    /// First date in YYYY-MM-DD format
+    pub start: String,
+    /// Last date in YYYY-MM-DD format (inclusive)
+    pub end: String,
+}
+
+impl ResponsePeriod {
+    /// Period from the first to the last of the dates, or `None` without any
+    pub fn spanning<'a>(mut dates: impl Iterator<Item = &'a str>) -> Option<Self> {
+        let start = dates.next()?;
+        let end = dates.last().unwrap_or(start);
+        Some(Self {
+            start: start.to_string(),
+            end: end.to_string(),
+        })
+    }
+
+    /// Adds the period to a response, for the envelope to report
+    pub fn attach(period: Option<Self>, mut response: Response) -> Response {
+        if let Some(period) = period {
+            response.extensions_mut().insert(period);
+        }
+        response
+    }
+}
+
+/// Metadata about how a response was generated
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ResponseMeta {
+    /// When the response was generated (RFC 3339, local offset)
+    pub generated_at: String,
+    /// Data sources the endpoint reads: `anki`, `koreader`, `proseuche` and/or `arc`
+    pub sources_used: Vec<String>,
+    /// `hit` when served from a cache, `miss` when computed for the request. Stats are read
+    /// from the sources on every request, so this is currently always `miss`.
+    pub cache: String,
+    /// Dates the response covers, for endpoints over a date range
+    pub period: Option<ResponsePeriod>,
+    /// Whether the summary counts the current, still running day or week, for endpoints
+    /// with a summary
+    pub partial_period: Option<PartialPeriod>,
+}
+
+/// Response wrapped in an envelope, as returned when the `X-Envelope` header is set
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ResponseEnvelope {
+    /// The endpoint's usual response body
+    pub data: Value,
+    pub meta: ResponseMeta,
+}
+
+/// Data sources read by an API route, judged by its path
+fn sources_used(route: &str) -> Vec<String> {
+    const ALL: &[&str] = &["anki", "koreader", "proseuche", "arc"];
+    // Church attendance is only counted by week, so daily stats don't read the Arc export
+    const DAILY: &[&str] = &["anki", "koreader", "proseuche"];
+    let sources: &[&str] = match route.trim_end_matches('/') {
+        "/faith/today" | "/faith/daily" | "/faith/days" | "/faith/consistency" => DAILY,
+        "/faith/on-this-day" | "/search" => &["anki", "koreader", "arc"],
+        "/goals/simulate" => &["anki"],
+        route => match route.trim_start_matches('/').split('/').next() {
+            Some("anki") => &["anki"],
+            Some("reading") => &["koreader"],
+            Some("arc") => &["arc"],
+            Some("faith" | "plan" | "ha" | "household" | "social" | "export" | "sources") => ALL,
+            _ => &[],
+        },
+    };
+    sources.iter().map(|source| source.to_string()).collect()
+}
+
+/// Wraps successful JSON responses in an envelope with generation metadata when requested
+///
+/// Clients opt in per request with `X-Envelope: true`; responses to other requests, errors
+/// and non-JSON bodies (charts, archives, feeds) are left as they are.
+pub async fn envelope_middleware(req: Request, next: Next) -> Response {
+    let wanted = req
+        .headers()
+        .get(ENVELOPE_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.eq_ignore_ascii_case("true") || value == "1");
+    let route = api_route(req.uri().path()).map(str::to_string);
+
+    let response = next.run(req).await;
+    let Some(route) = route.filter(|_| wanted) else {
+        return response;
+    };
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    if !response.status().is_success() || !is_json {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, MAX_ENVELOPE_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(error) => return crate::AppError::from(error).into_response(),
+    };
+    let (Ok(data), Ok(generated_at)) = (serde_json::from_slice::<Value>(&bytes), now_rfc3339())
+    else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+
+    let envelope = ResponseEnvelope {
+        data,
+        meta: ResponseMeta {
+            generated_at,
+            sources_used: sources_used(&route),
+            cache: "miss".to_string(),
+            period: parts.extensions.remove::<ResponsePeriod>(),
+            partial_period: parts.extensions.remove::<PartialPeriod>(),
+        },
+    };
+    match serde_json::to_vec(&envelope) {
+        Ok(body) => {
+            parts.headers.remove(header::CONTENT_LENGTH);
+            Response::from_parts(parts, Body::from(body))
+        }
+        Err(error) => crate::AppError::from(error).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sources_used() {
+        assert_eq!(sources_used("/anki/books"), vec!["anki"]);
+        assert_eq!(sources_used("/reading/patterns"), vec!["koreader"]);
+        assert_eq!(
+            sources_used("/faith/daily"),
+            vec!["anki", "koreader", "proseuche"]
+        );
+        assert_eq!(
+            sources_used("/faith/weekly"),
+            vec!["anki", "koreader", "proseuche", "arc"]
+        );
+        assert_eq!(
+            sources_used("/faith/on-this-day"),
+            vec!["anki", "koreader", "arc"]
+        );
+        assert_eq!(sources_used("/goals/simulate"), vec!["anki"]);
+        assert!(sources_used("/notes/2025-01-01").is_empty());
+    }
+}
@@ -0,0 +1,51 @@
+use anyhow::Result;
+use faithstats::models::FaithWeeklyStats;
+use faithstats::report::weekly_summary_text;
+use statsutils::{date_start_ms, timestamp_ms_to_rfc3339};
+
+/// Milliseconds in one week
+const WEEK_MS: i64 = 7 * 24 * 60 * 60 * 1000;
+
+/// Builds an Atom feed with one entry per completed week
+///
+/// The current week is still in progress, so it is left out. Each entry contains the
+/// plain-text weekly summary and is dated at the end of its week.
+pub fn weekly_atom_feed(user_id: &str, stats: &FaithWeeklyStats) -> Result<String> {
+    let completed_weeks = &stats.weeks[..stats.weeks.len().saturating_sub(1)];
+
+    let mut entries = Vec::new();
+    let mut feed_updated = None;
+    for week in completed_weeks.iter().rev() {
+        let updated = timestamp_ms_to_rfc3339(date_start_ms(&week.week_start)? + WEEK_MS)?;
+        feed_updated.get_or_insert_with(|| updated.clone());
+
+        entries.push(format!(
+            "  <entry>\n    <id>urn:life-stats:{user}:week:{week}</id>\n    <title>Week of {week}</title>\n    <updated>{updated}</updated>\n    <content type=\"text\">{content}</content>\n  </entry>\n",
+            user = escape_xml(user_id),
+            week = escape_xml(&week.week_start),
+            updated = updated,
+            content = escape_xml(&weekly_summary_text(week)?),
+        ));
+    }
+
+    let feed_updated = match feed_updated {
+        Some(updated) => updated,
+        None => timestamp_ms_to_rfc3339(chrono::Utc::now().timestamp_millis())?,
+    };
+
+    Ok(format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<feed xmlns=\"http://www.w3.org/2005/Atom\">\n  <id>urn:life-stats:{user}:weekly</id>\n  <title>Weekly faith summary</title>\n  <author><name>Life Stats</name></author>\n  <updated>{updated}</updated>\n{entries}</feed>\n",
+        user = escape_xml(user_id),
+        updated = feed_updated,
+        entries = entries.concat(),
+    ))
+}
+
+/// Escapes text for use in XML element content and attribute values
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
@@ -0,0 +1,180 @@
+use anyhow::{Context, Result, bail};
+use faithstats::goals::{DailyGoal, GoalMetric};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::env;
+use std::time::Duration;
+
+use crate::users::UserProfile;
+
+/// Default interval between habit checks
+const DEFAULT_CHECK_INTERVAL_MINUTES: u64 = 15;
+
+/// Base URL of the Habitica API
+const HABITICA_API_URL: &str = "https://habitica.com/api/v3";
+
+/// Habitica account credentials (found under Settings > API)
+#[derive(Clone, Deserialize)]
+pub struct HabiticaConfig {
+    pub user_id: String,
+    pub api_token: String,
+}
+
+/// An external habit that is marked complete once its daily goal is met
+#[derive(Clone, Deserialize)]
+pub struct HabitConfig {
+    #[serde(flatten)]
+    pub goal: DailyGoal,
+    /// Habitica task (habit or daily) to score up
+    pub habitica_task_id: Option<String>,
+    /// URL that receives a JSON `HabitCompleted` payload
+    pub webhook_url: Option<String>,
+}
+
+impl HabitConfig {
+    /// Validates that the habit can be tracked per day and has somewhere to report to
+    pub fn validate(&self) -> Result<()> {
+        if self.goal.metric == GoalMetric::AtChurchMinutes {
+            bail!(
+                "Habit '{}' uses at_church_minutes, which is only tracked per week",
+                self.goal.name
+            );
+        }
+        if self.habitica_task_id.is_none() && self.webhook_url.is_none() {
+            bail!(
+                "Habit '{}' needs a habitica_task_id or webhook_url",
+                self.goal.name
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Payload posted to a habit's webhook when its daily goal is met
+#[derive(Serialize)]
+struct HabitCompleted<'a> {
+    event: &'static str,
+    user: &'a str,
+    habit: &'a str,
+    /// Day the goal was met in YYYY-MM-DD format
+    date: &'a str,
+    minutes: f64,
+}
+
+/// Spawns a background task that periodically checks every user's habits and marks them
+/// complete on Habitica and/or their webhook once the day's goal is met
+///
+/// Each habit is completed at most once per day while the server is running.
+///
+/// # Errors
+/// Returns an error if HABIT_CHECK_INTERVAL_MINUTES is not a whole number of minutes of at
+/// least 1
+pub fn spawn_habit_sync(users: Vec<UserProfile>) -> Result<()> {
+    let users: Vec<UserProfile> = users
+        .into_iter()
+        .filter(|user| !user.habits.is_empty())
+        .collect();
+
+    if users.is_empty() {
+        return Ok(());
+    }
+
+    let interval_minutes: u64 = match env::var("HABIT_CHECK_INTERVAL_MINUTES") {
+        Ok(minutes) => minutes
+            .parse()
+            .context("Invalid HABIT_CHECK_INTERVAL_MINUTES")?,
+        Err(_) => DEFAULT_CHECK_INTERVAL_MINUTES,
+    };
+    if interval_minutes < 1 {
+        bail!("HABIT_CHECK_INTERVAL_MINUTES must be at least 1");
+    }
+
+    tokio::spawn(async move {
+        let client = reqwest::Client::new();
+        let mut completed = HashSet::new();
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_minutes * 60));
+
+        loop {
+            interval.tick().await;
+
+            for user in &users {
+                if let Err(e) = check_user(&client, user, &mut completed).await {
+                    eprintln!("Habit sync for user '{}' failed: {:#}", user.id, e);
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Completes the user's habits whose goal is met today
+///
+/// `completed` holds (user id, habit name, date) of habits already completed.
+async fn check_user(
+    client: &reqwest::Client,
+    user: &UserProfile,
+    completed: &mut HashSet<(String, String, String)>,
+) -> Result<()> {
+    let config = user.config.clone();
+    let stats = tokio::task::spawn_blocking(move || config.faith_daily_stats(1)).await??;
+    let Some(today) = stats.days.last() else {
+        return Ok(());
+    };
+
+    for habit in &user.habits {
+        let key = (user.id.clone(), habit.goal.name.clone(), today.date.clone());
+        if completed.contains(&key) || !habit.goal.is_met(today) {
+            continue;
+        }
+
+        if let Some(task_id) = &habit.habitica_task_id {
+            let habitica = user
+                .habitica
+                .as_ref()
+                .context("Habitica credentials are not configured")?;
+            score_habitica_task(client, habitica, task_id).await?;
+        }
+        if let Some(url) = &habit.webhook_url {
+            let payload = HabitCompleted {
+                event: "habit_completed",
+                user: &user.id,
+                habit: &habit.goal.name,
+                date: &today.date,
+                minutes: habit.goal.metric.day_minutes(today).unwrap_or_default(),
+            };
+            client
+                .post(url)
+                .json(&payload)
+                .send()
+                .await
+                .context("Failed to send habit webhook")?
+                .error_for_status()
+                .context("Habit webhook returned an error status")?;
+        }
+
+        completed.insert(key);
+    }
+
+    Ok(())
+}
+
+/// Scores a Habitica task up, which checks off a daily or increments a habit
+async fn score_habitica_task(
+    client: &reqwest::Client,
+    habitica: &HabiticaConfig,
+    task_id: &str,
+) -> Result<()> {
+    client
+        .post(format!("{}/tasks/{}/score/up", HABITICA_API_URL, task_id))
+        .header("x-api-user", &habitica.user_id)
+        .header("x-api-key", &habitica.api_token)
+        .header("x-client", format!("{}-LifeStats", habitica.user_id))
+        .send()
+        .await
+        .context("Failed to score Habitica task")?
+        .error_for_status()
+        .context(format!("Habitica returned an error for task {}", task_id))?;
+
+    Ok(())
+}
@@ -0,0 +1,217 @@
+use anyhow::{Context, Result, bail};
+use faithstats::models::FaithDayStats;
+use statsutils::date_start_ms;
+use std::env;
+use std::fmt::Write;
+use std::time::Duration;
+
+use crate::users::UserProfile;
+
+/// Measurement name of the daily metrics
+const MEASUREMENT: &str = "faith_daily";
+
+/// Default interval between pushes
+const DEFAULT_PUSH_INTERVAL_MINUTES: u64 = 60;
+
+/// Number of days sent with each push; today and yesterday can still change after a sync,
+/// and rewriting a point with the same timestamp replaces it
+const PUSH_DAYS: i32 = 7;
+
+/// Timeout of a write request, so that an unresponsive server cannot stall later pushes
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Formats daily faith stats as InfluxDB line protocol, one line per day
+///
+/// Each line is a `faith_daily` point tagged with the user, timestamped at local midnight of
+/// the day in nanoseconds.
+pub fn daily_line_protocol(user_id: &str, days: &[FaithDayStats]) -> Result<String> {
+    let mut lines = String::new();
+    for day in days {
+        let timestamp_ns = date_start_ms(&day.date)? * 1_000_000;
+        write!(
+            lines,
+            "{MEASUREMENT},user={user} total_minutes={total},anki_reviews={reviews}i,anki_matured_passages={matured}i,anki_lost_passages={lost}i,anki_cumulative_passages={cumulative}i",
+            user = escape_tag(user_id),
+            total = day.total_minutes(),
+            reviews = day.anki_reviews,
+            matured = day.anki_matured_passages,
+            lost = day.anki_lost_passages,
+            cumulative = day.anki_cumulative_passages,
+        )?;
+        // Days before a source's history have no minutes field rather than 0
+        for (field, minutes) in [
+            ("anki_minutes", day.anki_minutes),
+            ("reading_minutes", day.reading_minutes),
+            ("prayer_minutes", day.prayer_minutes),
+        ] {
+            if let Some(minutes) = minutes {
+                write!(lines, ",{}={}", field, minutes)?;
+            }
+        }
+        if let Some(seconds) = day.anki_avg_seconds_per_review {
+            write!(lines, ",anki_avg_seconds_per_review={}", seconds)?;
+        }
+        writeln!(lines, " {}", timestamp_ns)?;
+    }
+    Ok(lines)
+}
+
+/// Escapes commas, spaces and equals signs in a tag value
+fn escape_tag(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(',', "\\,")
+        .replace(' ', "\\ ")
+        .replace('=', "\\=")
+}
+
+/// Write endpoint settings read from `INFLUX_*` environment variables
+struct InfluxConfig {
+    write_url: String,
+    token: Option<String>,
+    interval_minutes: u64,
+}
+
+impl InfluxConfig {
+    /// Reads the InfluxDB configuration; returns `None` when INFLUX_WRITE_URL is not set
+    fn from_env() -> Result<Option<Self>> {
+        let Ok(write_url) = env::var("INFLUX_WRITE_URL") else {
+            return Ok(None);
+        };
+
+        let interval_minutes = match env::var("INFLUX_PUSH_INTERVAL_MINUTES") {
+            Ok(minutes) => minutes
+                .parse()
+                .context("Invalid INFLUX_PUSH_INTERVAL_MINUTES")?,
+            Err(_) => DEFAULT_PUSH_INTERVAL_MINUTES,
+        };
+        if interval_minutes < 1 {
+            bail!("INFLUX_PUSH_INTERVAL_MINUTES must be at least 1");
+        }
+
+        Ok(Some(Self {
+            write_url,
+            token: env::var("INFLUX_TOKEN").ok(),
+            interval_minutes,
+        }))
+    }
+}
+
+/// Spawns a background task that periodically pushes every user's daily metrics for the
+/// last week to the InfluxDB write endpoint configured via INFLUX_WRITE_URL
+///
+/// The URL is used as is, so it must include the database or bucket, e.g.
+/// `http://influx:8086/api/v2/write?org=home&bucket=lifestats&precision=ns` or
+/// `http://influx:8086/write?db=lifestats`. INFLUX_TOKEN is sent as `Authorization: Token`.
+///
+/// Does nothing when INFLUX_WRITE_URL is not set.
+pub fn spawn_pusher(users: Vec<UserProfile>) -> Result<()> {
+    let Some(config) = InfluxConfig::from_env()? else {
+        return Ok(());
+    };
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .context("Failed to create HTTP client")?;
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(config.interval_minutes * 60));
+
+        loop {
+            interval.tick().await;
+
+            for user in &users {
+                if let Err(e) = push_user(&client, &config, user).await {
+                    eprintln!("InfluxDB push for user '{}' failed: {:#}", user.id, e);
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Pushes a single user's daily metrics for the last week
+async fn push_user(
+    client: &reqwest::Client,
+    config: &InfluxConfig,
+    user: &UserProfile,
+) -> Result<()> {
+    let app_config = user.config.clone();
+    let user_id = user.id.clone();
+    let body = tokio::task::spawn_blocking(move || {
+        let stats = app_config.faith_daily_stats(PUSH_DAYS)?;
+        daily_line_protocol(&user_id, &stats.days)
+    })
+    .await??;
+
+    let mut request = client
+        .post(&config.write_url)
+        .header(reqwest::header::CONTENT_TYPE, "text/plain; charset=utf-8")
+        .body(body);
+    if let Some(token) = &config.token {
+        request = request.header(reqwest::header::AUTHORIZATION, format!("Token {}", token));
+    }
+
+    let response = request.send().await.context(format!(
+        "Failed to send line protocol to {}",
+        config.write_url
+    ))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        bail!(
+            "InfluxDB write failed with status {}: {}",
+            status,
+            text.trim()
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_daily_line_protocol() {
+        let days = vec![
+            FaithDayStats {
+                date: "2024-03-01".to_string(),
+                anki_cumulative_passages: 12,
+                ..Default::default()
+            },
+            FaithDayStats {
+                date: "2024-03-02".to_string(),
+                anki_minutes: Some(10.5),
+                anki_matured_passages: 2,
+                anki_lost_passages: 1,
+                anki_cumulative_passages: 13,
+                anki_reviews: 42,
+                anki_avg_seconds_per_review: Some(15.0),
+                reading_minutes: Some(20.0),
+                prayer_minutes: Some(0.0),
+                ..Default::default()
+            },
+        ];
+
+        let lines = daily_line_protocol("Jane Doe,admin", &days).unwrap();
+
+        let first_ns = date_start_ms("2024-03-01").unwrap() * 1_000_000;
+        let second_ns = date_start_ms("2024-03-02").unwrap() * 1_000_000;
+        assert_eq!(
+            lines,
+            format!(
+                "faith_daily,user=Jane\\ Doe\\,admin total_minutes=0,anki_reviews=0i,anki_matured_passages=0i,anki_lost_passages=0i,anki_cumulative_passages=12i {}\n\
+                 faith_daily,user=Jane\\ Doe\\,admin total_minutes=30.5,anki_reviews=42i,anki_matured_passages=2i,anki_lost_passages=1i,anki_cumulative_passages=13i,anki_minutes=10.5,reading_minutes=20,prayer_minutes=0,anki_avg_seconds_per_review=15 {}\n",
+                first_ns, second_ns
+            )
+        );
+    }
+
+    #[test]
+    fn test_escape_tag() {
+        assert_eq!(escape_tag(r"a=b c,d\e"), r"a\=b\ c\,d\\e");
+    }
+}
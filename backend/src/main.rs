@@ -1,62 +1,307 @@
+mod admin;
+mod caldav;
+#[cfg(feature = "charts")]
+mod charts;
+mod envelope;
+mod feed;
+mod habits;
+mod influx;
+mod mastodon;
+#[cfg(feature = "mqtt")]
+mod mqtt;
+mod notify;
+mod pagination;
+mod push;
+mod reminders;
+#[cfg(feature = "charts")]
+mod render;
+mod schemas;
+mod sources;
+mod staleness;
+mod users;
+mod warehouse;
+mod webpush;
+
+#[cfg(test)]
+mod api_tests;
+
 use ankistats::{
-    get_bible_stats,
-    models::{AggregateStats, BibleStats, BookStats, ErrorResponse, HealthCheck},
+    bible::find_book,
+    get_bible_stats, get_book_coverage, get_deck_settings, get_parse_warnings, get_passages,
+    get_relapse_stats, get_retention_stats, get_review_forecast, get_span_distribution,
+    get_study_sessions,
+    models::{
+        AggregateStats, BibleStats, BookCoverage, BookRelapseStats, BookRetention, BookStats,
+        ChapterCoverage, ChapterStats, DayRetention, DaySessions, DeckSettings, DueDay,
+        NormalizedReference, ParseWarning, ParseWarningKind, PassageDetails, PassageStatus,
+        RelapseStats, Retention, RetentionStats, ReviewForecast, SpanBucketStats, StudySession,
+        StudySessions, VerseStatus, WeekRetention,
+    },
+    reference::parse_reference,
+    sessions::DEFAULT_SESSION_GAP_MINUTES,
 };
-use arcstats::stats::{PlaceStats, get_top_places_last_6_months};
+use arcstats::stats::{DayCoverage, HomeRatioWeek, MonthNewPlaces, PlaceStats, PlaceVisit};
 use axum::{
     Router,
-    extract::Request,
-    http::StatusCode,
+    extract::{FromRef, OriginalUri, Path, Request},
+    http::{HeaderValue, StatusCode, header},
     middleware::{self, Next},
     response::{IntoResponse, Json, Response},
-    routing::get,
+    routing::{delete, get, post, put},
 };
+use chrono::{Datelike, Days, NaiveDate};
 use faithstats::{
-    get_faith_daily_stats, get_faith_today_stats, get_faith_weekly_stats,
+    SourceNotConfiguredError,
+    budget::{BudgetAllocation, BudgetCategory, BudgetReport, BudgetWeek, TimeBudget},
+    combine_household_weekly_stats,
+    consistency::{
+        ActivityConsistency, CONSISTENCY_WINDOWS, ConsistencyWindow, DEFAULT_MIN_MINUTES,
+        FaithConsistency,
+    },
+    exemptions::{Exemption, is_exempt, validate_range, with_exempt_days, with_exempt_weeks},
+    export::write_export_archive,
+    goals::{GoalMetric, GoalProgress},
+    import::{with_imported_days, with_imported_weeks},
     models::{
         FaithDailyStats, FaithDailySummary, FaithDayStats, FaithTodayStats, FaithWeekStats,
-        FaithWeeklyStats, FaithWeeklySummary,
+        FaithWeeklyStats, FaithWeeklySummary, HomeAssistantState, HouseholdMemberWeeklyStats,
+        HouseholdWeeklyStats, SharedWeekStats, SharedWeeklyStats, SourceDataRange, StaleSource,
+    },
+    notes::{DayNote, NoteText, validate_note, with_notes},
+    on_this_day::{FinishedBook, MemorizedPassage, OnThisDay, VisitedPlace},
+    places::{get_coverage_gaps, get_home_ratio, get_new_places, get_top_places_last_6_months},
+    plan::{AVAILABILITY_DAYS, PlanDay, PlannedActivity, PlannedGoal, ReviewOutlook, WeekPlan},
+    score::{DayScore, FaithScore, ScoreComponent, WeekScore},
+    search::{DEFAULT_SEARCH_LIMIT, SearchHit, SearchKind, SearchResults},
+    simulate::{Commitment, DEFAULT_READING_VERSES_PER_MINUTE, GoalSimulation, TargetProjection},
+    store::Store,
+    tags::{DayTag, TagCount, filter_by_tag, normalize_tag, with_tags},
+    warehouse::{DeckSettingsChange, HistoryRegression, Rollup, Warehouse},
+};
+use readingstats::{
+    get_completed_books, get_daily_office_stats, get_reading_patterns,
+    models::{
+        CompletedBook, DailyOfficeDay, DailyOfficeStats, HourPattern, ReadingPatterns,
+        WeekdayPattern,
     },
 };
-use std::env;
+use serde::{Deserialize, Serialize};
+use statsmodels::ErrorResponse;
+use statsutils::format::{DisplayFormat, with_display_format};
+use statsutils::{DataRange, DatePeriod, PartialPeriod, get_current_week_start, get_today_date};
+use std::io::Cursor;
+use std::sync::Arc;
 use tower_http::cors::CorsLayer;
-use utoipa::OpenApi;
+use utoipa::{IntoParams, OpenApi, ToSchema};
+#[cfg(feature = "swagger-ui")]
 use utoipa_swagger_ui::SwaggerUi;
 
-/// Application configuration holding database paths
+use crate::admin::{NewExemption, RefreshReport, SourceRefresh, UserRefresh};
+use crate::envelope::{ResponseEnvelope, ResponseMeta, ResponsePeriod};
+use crate::mastodon::{SummaryPeriod, SummaryPost};
+use crate::notify::{ChannelDelivery, DeliveryStatus, EventKind, Notifier, TestNotification};
+use crate::pagination::ListQuery;
+use crate::push::{PushService, PushSubscription, SubscriptionKeys, Unsubscribe, VapidPublicKey};
+use crate::schemas::JsonSchemas;
+use crate::sources::{AnkiSource, ArcSource, PrayerSource, ReadingSource, Sources};
+use crate::staleness::StalenessState;
+use crate::users::{UserProfile, Users};
+
+/// Data source configuration of a single user
 #[derive(Clone)]
 struct AppConfig {
-    anki_db_path: String,
-    koreader_db_path: String,
-    arcstats_export_path: String,
-    proseuche_db_path: String,
+    sources: Sources,
+    /// Optional writeable store holding imported history and exempt date ranges
+    stats_store_path: Option<String>,
+    /// Optional SQLite warehouse of nightly daily rollups from every source
+    warehouse_path: Option<String>,
+    /// How numbers, distances and times are displayed to the user
+    display_format: DisplayFormat,
+}
+
+/// Number of days looked at when computing streaks
+const STREAK_WINDOW_DAYS: i32 = 365;
+
+impl AppConfig {
+    /// Faith statistics for the last `days` days, with imported history filled in and exempt
+    /// days marked
+    fn faith_daily_stats(&self, days: i32) -> anyhow::Result<FaithDailyStats> {
+        self.faith_days_stats(DatePeriod::last_n_days(days)?)
+    }
+
+    /// Faith statistics for the days of the period, with imported history filled in, exempt
+    /// days marked, and notes and tags added
+    fn faith_days_stats(&self, period: DatePeriod) -> anyhow::Result<FaithDailyStats> {
+        let stats = self.sources.faith_stats().period(period).build().daily()?;
+        let Some(store_path) = &self.stats_store_path else {
+            return Ok(stats);
+        };
+        let store = Store::open(store_path)?;
+        let stats = with_exempt_days(with_imported_days(stats, &store)?, &store.get_exemptions()?);
+        let (notes, tags) = match (stats.days.first(), stats.days.last()) {
+            (Some(first), Some(last)) => (
+                store.get_notes(&first.date, &last.date)?,
+                store.get_day_tags(&first.date, &last.date)?,
+            ),
+            _ => (Vec::new(), Vec::new()),
+        };
+        Ok(with_tags(with_notes(stats, &notes), &tags))
+    }
+
+    /// Faith statistics for the last 12 weeks, with imported history filled in and exempt
+    /// days counted
+    fn faith_weekly_stats(&self) -> anyhow::Result<FaithWeeklyStats> {
+        let stats = self.sources.faith_stats().build().weekly()?;
+        let Some(store_path) = &self.stats_store_path else {
+            return Ok(stats);
+        };
+        let store = Store::open(store_path)?;
+        with_exempt_weeks(
+            with_imported_weeks(stats, &store)?,
+            &store.get_exemptions()?,
+        )
+    }
+
+    /// Exempt date ranges from the stats store, or none without one
+    fn exemptions(&self) -> anyhow::Result<Vec<Exemption>> {
+        match &self.stats_store_path {
+            Some(store_path) => Store::open(store_path)?.get_exemptions(),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Dates (YYYY-MM-DD) on which the nightly rollup saw the Bible deck's scheduling settings
+    /// change, or none without a warehouse
+    #[cfg(feature = "charts")]
+    fn deck_settings_change_dates(&self) -> anyhow::Result<Vec<String>> {
+        let Some(warehouse_path) = &self.warehouse_path else {
+            return Ok(Vec::new());
+        };
+        Ok(Warehouse::open(warehouse_path)?
+            .get_deck_settings_changes()?
+            .into_iter()
+            .map(|change| change.date)
+            .collect())
+    }
+}
+
+/// Shared router state
+#[derive(Clone, FromRef)]
+struct AppState {
+    users: Users,
+    staleness: StalenessState,
+    admin_api_key: AdminApiKey,
+    push: PushService,
+    notifier: Notifier,
 }
 
+/// API key for the admin endpoints, from ADMIN_API_KEY; admin endpoints are disabled without one
+#[derive(Clone)]
+struct AdminApiKey(Option<String>);
+
 /// OpenAPI documentation structure
 #[derive(OpenApi)]
 #[openapi(
     paths(
         health_check,
+        get_user_health_endpoint,
         get_books_stats,
+        get_parse_warnings_endpoint,
+        normalize_reference_endpoint,
+        get_book_coverage_endpoint,
+        get_span_distribution_endpoint,
+        get_relapse_stats_endpoint,
+        get_retention_stats_endpoint,
+        get_review_forecast_endpoint,
+        get_deck_settings_endpoint,
+        get_study_sessions_endpoint,
+        get_passages_endpoint,
         get_faith_today_stats_endpoint,
         get_faith_daily_stats_endpoint,
+        get_faith_days_stats_endpoint,
         get_faith_weekly_stats_endpoint,
+        get_faith_score_endpoint,
+        get_faith_consistency_endpoint,
+        get_faith_budget_endpoint,
+        get_on_this_day_endpoint,
+        simulate_goals_endpoint,
+        get_next_week_plan_endpoint,
+        get_home_assistant_state_endpoint,
+        get_household_weekly_stats_endpoint,
+        get_shared_weekly_stats_endpoint,
+        get_weekly_feed_endpoint,
+        get_summary_preview_endpoint,
         get_top_places_stats_endpoint,
+        get_coverage_gaps_endpoint,
+        get_home_ratio_endpoint,
+        get_new_places_endpoint,
+        get_reading_patterns_endpoint,
+        get_daily_office_endpoint,
+        get_completed_books_endpoint,
+        get_warehouse_rollups_endpoint,
+        get_deck_settings_changes_endpoint,
+        get_sources_endpoint,
+        get_export_all_endpoint,
+        get_line_protocol_export_endpoint,
+        refresh_sources_endpoint,
+        get_vapid_public_key_endpoint,
+        subscribe_push_endpoint,
+        unsubscribe_push_endpoint,
+        test_notification_endpoint,
+        list_exemptions_endpoint,
+        add_exemption_endpoint,
+        delete_exemption_endpoint,
+        search_endpoint,
+        get_note_endpoint,
+        put_note_endpoint,
+        delete_note_endpoint,
+        list_tags_endpoint,
+        add_tag_endpoint,
+        remove_tag_endpoint,
     ),
     components(
-        schemas(HealthCheck, BibleStats, BookStats, AggregateStats, ErrorResponse,
+        schemas(HealthCheck, UserHealth, StaleSource, ResponseEnvelope, ResponseMeta, ResponsePeriod, PartialPeriod, UntrackedMinutes, HistoryRegression, BibleStats, BookStats, ChapterStats, AggregateStats, ErrorResponse,
+                ParseWarning, ParseWarningKind, NormalizedReference, BookCoverage,
+                ChapterCoverage, VerseStatus, SpanBucketStats, RelapseStats, BookRelapseStats, RetentionStats, Retention, DayRetention, WeekRetention, BookRetention, ReviewForecast, DueDay, StudySessions, DaySessions, StudySession, DeckSettings, DeckSettingsChange,
+                PassageDetails, PassageStatus,
                 FaithTodayStats, FaithDailyStats, FaithDailySummary, FaithDayStats,
-                FaithWeeklyStats, FaithWeeklySummary, FaithWeekStats, PlaceStats)
+                FaithWeeklyStats, FaithWeeklySummary, FaithWeekStats, PlaceStats, DayCoverage, HomeRatioWeek, MonthNewPlaces,
+                SourceDataRange, DataRange, StaleSource, HouseholdWeeklyStats,
+                HouseholdMemberWeeklyStats, GoalProgress, GoalMetric, SharedWeeklyStats,
+                SharedWeekStats, HomeAssistantState, ReadingPatterns, HourPattern,
+                WeekdayPattern, DailyOfficeStats, DailyOfficeDay, CompletedBook, RefreshReport,
+                UserRefresh, SourceRefresh, PushSubscription, SubscriptionKeys, Unsubscribe,
+                VapidPublicKey, TestNotification, EventKind, ChannelDelivery, DeliveryStatus,
+                SummaryPost, SummaryPeriod, Rollup, FaithScore, DayScore, WeekScore,
+                ScoreComponent, FaithConsistency, ConsistencyWindow, ActivityConsistency, GoalSimulation, TargetProjection, Exemption, NewExemption, BudgetReport,
+                BudgetWeek, BudgetCategory, TimeBudget, BudgetAllocation,
+                OnThisDay, MemorizedPassage, FinishedBook, VisitedPlace, PlaceVisit,
+                WeekPlan, PlanDay, PlannedActivity, PlannedGoal, DayNote, NoteText,
+                DayTag, TagCount, FaithDaysRequest, SearchResults, SearchHit, SearchKind)
     ),
     tags(
         (name = "health", description = "Health check endpoints"),
         (name = "anki", description = "Anki Bible memorization statistics endpoints"),
         (name = "faith", description = "Unified faith statistics endpoints combining multiple sources"),
-        (name = "arc", description = "Arc Timeline location tracking statistics endpoints")
+        (name = "home-assistant", description = "Flat state for Home Assistant REST sensors"),
+        (name = "household", description = "Combined statistics for all users of the server"),
+        (name = "share", description = "Public, token-based read-only share endpoints"),
+        (name = "feed", description = "Syndication feeds"),
+        (name = "social", description = "Summary posts for social media"),
+        (name = "arc", description = "Arc Timeline location tracking statistics endpoints"),
+        (name = "reading", description = "KOReader Bible reading statistics endpoints"),
+        (name = "goals", description = "What-if projections for daily commitments"),
+        (name = "search", description = "Search across passages, places and books"),
+        (name = "notes", description = "Per-day notes and tags with qualitative context"),
+        (name = "warehouse", description = "Long-term daily rollups of every source"),
+        (name = "sources", description = "Data source freshness endpoints"),
+        (name = "export", description = "Bulk export of all derived statistics"),
+        (name = "push", description = "Web Push subscriptions for goal reminders"),
+        (name = "admin", description = "Server maintenance endpoints, authenticated with ADMIN_API_KEY")
     ),
     info(
         title = "Life Stats API",
-        description = "REST API for personal life and faith statistics.",
+        description = "REST API for personal life and faith statistics.\n\nSend `X-Envelope: true` to receive JSON responses as `{\"data\": ..., \"meta\": ...}` (`ResponseEnvelope`), with when the response was generated, the sources it read and the period it covers.",
         license(
             name = "AGPL-3.0-or-later",
             url = "https://www.gnu.org/licenses/agpl-3.0.en.html"
@@ -88,80 +333,152 @@ async fn main() {
     // Load environment variables from .env file if present
     dotenvy::dotenv().ok();
 
-    // Get configuration from environment variables
-    let anki_db_path = env::var("ANKI_DATABASE_PATH").unwrap_or_else(|_| {
-        eprintln!("Error: ANKI_DATABASE_PATH environment variable is required");
+    // Log warnings (e.g. unparsable references) to stderr
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .init();
+
+    // Fail Anki stats requests on unparsable Bible references instead of falling back
+    let strict = std::env::var("STRICT_REFERENCES").is_ok_and(|v| v == "1" || v == "true");
+    ankistats::parse_warnings::set_strict_references(strict);
+
+    // Name unnamed places with a reverse geocoder (GEONAMES_CITIES_PATH or NOMINATIM_URL)
+    match faithstats::places::build_geocoder(
+        std::env::var("GEONAMES_CITIES_PATH").ok().as_deref(),
+        std::env::var("NOMINATIM_URL").ok().as_deref(),
+        std::env::var("GEOCODE_CACHE_PATH").ok().as_deref(),
+    ) {
+        Ok(geocoder) => faithstats::places::set_geocoder(geocoder),
+        Err(e) => eprintln!("Warning: reverse geocoding disabled: {:#}", e),
+    }
+
+    // Language of labels, summaries and reports
+    match statsutils::i18n::locale_from_env() {
+        Ok(locale) => statsutils::i18n::set_locale(locale),
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
+    }
+
+    // Number, unit and time formatting, following the locale unless overridden
+    match statsutils::format::display_format_from_env() {
+        Ok(format) => statsutils::format::set_display_format(format),
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
+    }
+
+    // Let users override the summary and report templates from TEMPLATES_DIR
+    faithstats::templates::set_template_dir(std::env::var("TEMPLATES_DIR").ok().map(Into::into));
+    faithstats::templates::check_templates().unwrap_or_else(|e| {
+        eprintln!("Error: {:#}", e);
         std::process::exit(1);
     });
 
-    let koreader_db_path = env::var("KOREADER_DATABASE_PATH").unwrap_or_else(|_| {
-        eprintln!("Error: KOREADER_DATABASE_PATH environment variable is required");
+    // Get user profiles from the users config file or environment variables
+    let users = Users::from_env().unwrap_or_else(|e| {
+        eprintln!("Error: {:#}", e);
         std::process::exit(1);
     });
 
-    let arcstats_export_path = env::var("ARCSTATS_EXPORT_PATH").unwrap_or_else(|_| {
-        eprintln!("Error: ARCSTATS_EXPORT_PATH environment variable is required");
+    let push = PushService::from_env().unwrap_or_else(|e| {
+        eprintln!("Error: {:#}", e);
+        std::process::exit(1);
+    });
+    let notifier = Notifier::from_env(push.clone()).unwrap_or_else(|e| {
+        eprintln!("Error: {:#}", e);
         std::process::exit(1);
     });
 
-    let proseuche_db_path = env::var("PROSEUCHE_DATABASE_PATH").unwrap_or_else(|_| {
-        eprintln!("Error: PROSEUCHE_DATABASE_PATH environment variable is required");
+    let staleness = staleness::spawn_monitor(
+        users.profiles(),
+        staleness::thresholds_from_env(),
+        notifier.clone(),
+    )
+    .unwrap_or_else(|e| {
+        eprintln!("Error: {:#}", e);
         std::process::exit(1);
     });
 
-    let api_key = env::var("API_KEY").unwrap_or_else(|_| {
-        eprintln!("Error: API_KEY environment variable is required");
+    caldav::spawn_catch_up_tasks(users.profiles()).unwrap_or_else(|e| {
+        eprintln!("Error: {:#}", e);
         std::process::exit(1);
     });
 
-    // Validate that the database paths exist
-    if !std::path::Path::new(&anki_db_path).exists() {
-        eprintln!("Error: Anki database file not found at: {}", anki_db_path);
+    habits::spawn_habit_sync(users.profiles()).unwrap_or_else(|e| {
+        eprintln!("Error: {:#}", e);
         std::process::exit(1);
-    }
+    });
 
-    if !std::path::Path::new(&koreader_db_path).exists() {
-        eprintln!(
-            "Error: KOReader database file not found at: {}",
-            koreader_db_path
-        );
+    reminders::spawn_goal_checks(users.profiles(), notifier.clone()).unwrap_or_else(|e| {
+        eprintln!("Error: {:#}", e);
         std::process::exit(1);
-    }
+    });
 
-    if !std::path::Path::new(&proseuche_db_path).exists() {
-        eprintln!(
-            "Error: Proseuche database file not found at: {}",
-            proseuche_db_path
-        );
+    mastodon::spawn_publisher(users.profiles()).unwrap_or_else(|e| {
+        eprintln!("Error: {:#}", e);
         std::process::exit(1);
-    }
+    });
+
+    influx::spawn_pusher(users.profiles()).unwrap_or_else(|e| {
+        eprintln!("Error: {:#}", e);
+        std::process::exit(1);
+    });
+
+    warehouse::spawn_nightly_rollups(users.profiles(), staleness.clone()).unwrap_or_else(|e| {
+        eprintln!("Error: {:#}", e);
+        std::process::exit(1);
+    });
+
+    #[cfg(feature = "mqtt")]
+    mqtt::spawn_publisher(users.profiles()).unwrap_or_else(|e| {
+        eprintln!("Error: {:#}", e);
+        std::process::exit(1);
+    });
 
-    let config = AppConfig {
-        anki_db_path: anki_db_path.clone(),
-        koreader_db_path: koreader_db_path.clone(),
-        arcstats_export_path: arcstats_export_path.clone(),
-        proseuche_db_path: proseuche_db_path.clone(),
+    let state = AppState {
+        users: users.clone(),
+        staleness,
+        admin_api_key: AdminApiKey(std::env::var("ADMIN_API_KEY").ok()),
+        push,
+        notifier,
     };
 
     println!("Starting life stats API server...");
-    println!("Anki Database: {}", anki_db_path);
-    println!("KOReader Database: {}", koreader_db_path);
-    println!("Proseuche Database: {}", proseuche_db_path);
+    for user in users.profiles() {
+        println!("User: {}", user.id);
+        let sources = &user.config.sources;
+        let path = |path: anyhow::Result<&str>| path.unwrap_or("not configured").to_string();
+        println!(
+            "  Anki Database: {}",
+            path(sources.anki().map(AnkiSource::path))
+        );
+        println!(
+            "  KOReader Database: {}",
+            path(sources.reading().map(ReadingSource::path))
+        );
+        println!(
+            "  Proseuche Database: {}",
+            path(sources.prayer().map(PrayerSource::path))
+        );
+        println!(
+            "  Arc Timeline Export: {}",
+            path(sources.arc().map(ArcSource::path))
+        );
+        for source in sources.missing() {
+            eprintln!(
+                "Warning: no {} configured for user '{}'; endpoints reading it return 503",
+                source, user.id
+            );
+        }
+    }
 
-    // Build the router with routes
-    let app = Router::new()
-        .merge(SwaggerUi::new("/swagger-ui").url("/openapi.json", ApiDoc::openapi()))
-        .route("/health", get(health_check))
-        .route("/api/anki/books", get(get_books_stats))
-        .route("/api/faith/today", get(get_faith_today_stats_endpoint))
-        .route("/api/faith/daily", get(get_faith_daily_stats_endpoint))
-        .route("/api/faith/weekly", get(get_faith_weekly_stats_endpoint))
-        .route("/api/arc/top-places", get(get_top_places_stats_endpoint))
-        .layer(middleware::from_fn(move |req, next| {
-            auth_middleware(req, next, api_key.clone())
-        }))
-        .layer(CorsLayer::permissive())
-        .with_state(config);
+    let app = router(state).unwrap_or_else(|e| {
+        eprintln!("Error: {:#}", e);
+        std::process::exit(1);
+    });
 
     // Start the server
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3000")
@@ -170,24 +487,232 @@ async fn main() {
 
     println!("Server listening on http://0.0.0.0:3000");
     println!("API Documentation:");
+    #[cfg(feature = "swagger-ui")]
     println!("  - Swagger UI: http://localhost:3000/swagger-ui/");
     println!("  - OpenAPI spec: http://localhost:3000/openapi.json");
+    println!("  - JSON Schemas: http://localhost:3000/api/v1/schemas");
 
     axum::serve(listener, app)
         .await
         .expect("Server failed to start");
 }
 
-/// Authentication middleware that validates the API key
-async fn auth_middleware(
+/// Builds the router serving the API, the OpenAPI document and the public pages
+fn router(state: AppState) -> anyhow::Result<Router> {
+    #[allow(unused_mut)]
+    let mut openapi = ApiDoc::openapi();
+    #[allow(unused_mut)]
+    let mut api = Router::new();
+
+    #[cfg(feature = "charts")]
+    {
+        openapi.merge(render::RenderApiDoc::openapi());
+        api = api.merge(render::routes());
+    }
+
+    let json_schemas = Arc::new(JsonSchemas::from_openapi(&openapi)?);
+    let model_schemas = Arc::clone(&json_schemas);
+
+    // API routes, relative to the /api/v1 prefix
+    let api = api
+        .route(
+            "/schemas",
+            get(move || async move { Json(json_schemas.bundle.clone()) }),
+        )
+        .route(
+            "/schemas/{name}",
+            get(move |Path(name): Path<String>| async move {
+                let name = name.strip_suffix(".json").unwrap_or(&name);
+                match model_schemas.models.get(name) {
+                    Some(schema) => Json(schema.clone()).into_response(),
+                    None => StatusCode::NOT_FOUND.into_response(),
+                }
+            }),
+        )
+        .route("/anki/books", get(get_books_stats))
+        .route("/anki/parse-warnings", get(get_parse_warnings_endpoint))
+        .route("/anki/normalize", get(normalize_reference_endpoint))
+        .route("/anki/coverage/{book}", get(get_book_coverage_endpoint))
+        .route(
+            "/anki/span-distribution",
+            get(get_span_distribution_endpoint),
+        )
+        .route("/anki/relapse", get(get_relapse_stats_endpoint))
+        .route("/anki/retention", get(get_retention_stats_endpoint))
+        .route("/anki/forecast", get(get_review_forecast_endpoint))
+        .route("/anki/settings", get(get_deck_settings_endpoint))
+        .route("/anki/sessions", get(get_study_sessions_endpoint))
+        .route("/anki/passages", get(get_passages_endpoint))
+        .route("/faith/today", get(get_faith_today_stats_endpoint))
+        .route("/faith/daily", get(get_faith_daily_stats_endpoint))
+        .route("/faith/days", post(get_faith_days_stats_endpoint))
+        .route("/faith/weekly", get(get_faith_weekly_stats_endpoint))
+        .route("/faith/score", get(get_faith_score_endpoint))
+        .route("/faith/consistency", get(get_faith_consistency_endpoint))
+        .route("/faith/budget", get(get_faith_budget_endpoint))
+        .route("/faith/on-this-day", get(get_on_this_day_endpoint))
+        .route("/goals/simulate", get(simulate_goals_endpoint))
+        .route("/plan/next-week", get(get_next_week_plan_endpoint))
+        .route("/search", get(search_endpoint))
+        .route(
+            "/notes/{date}",
+            get(get_note_endpoint)
+                .put(put_note_endpoint)
+                .delete(delete_note_endpoint),
+        )
+        .route("/tags", get(list_tags_endpoint))
+        .route(
+            "/tags/{tag}/{date}",
+            put(add_tag_endpoint).delete(remove_tag_endpoint),
+        )
+        .route("/ha/state", get(get_home_assistant_state_endpoint))
+        .route(
+            "/household/weekly",
+            get(get_household_weekly_stats_endpoint),
+        )
+        .route("/arc/top-places", get(get_top_places_stats_endpoint))
+        .route("/arc/gaps", get(get_coverage_gaps_endpoint))
+        .route("/arc/home-ratio", get(get_home_ratio_endpoint))
+        .route("/arc/new-places", get(get_new_places_endpoint))
+        .route("/reading/patterns", get(get_reading_patterns_endpoint))
+        .route("/reading/daily-office", get(get_daily_office_endpoint))
+        .route("/reading/completed", get(get_completed_books_endpoint))
+        .route("/social/preview", get(get_summary_preview_endpoint))
+        .route("/warehouse/rollups", get(get_warehouse_rollups_endpoint))
+        .route(
+            "/warehouse/deck-settings",
+            get(get_deck_settings_changes_endpoint),
+        )
+        .route("/sources", get(get_sources_endpoint))
+        .route("/health", get(get_user_health_endpoint))
+        .route("/export/all", get(get_export_all_endpoint))
+        .route(
+            "/export/lineprotocol",
+            get(get_line_protocol_export_endpoint),
+        )
+        .route("/push/vapid-public-key", get(get_vapid_public_key_endpoint))
+        .route(
+            "/push/subscriptions",
+            post(subscribe_push_endpoint).delete(unsubscribe_push_endpoint),
+        )
+        .route("/admin/refresh", post(refresh_sources_endpoint))
+        .route(
+            "/admin/notifications/test",
+            post(test_notification_endpoint),
+        )
+        .route(
+            "/admin/exemptions",
+            get(list_exemptions_endpoint).post(add_exemption_endpoint),
+        )
+        .route(
+            "/admin/exemptions/{user}/{id}",
+            delete(delete_exemption_endpoint),
+        );
+
+    #[cfg(feature = "swagger-ui")]
+    let routes = Router::new().merge(SwaggerUi::new("/swagger-ui").url("/openapi.json", openapi));
+    #[cfg(not(feature = "swagger-ui"))]
+    let routes = Router::new().route("/openapi.json", get(move || async move { Json(openapi) }));
+
+    // Build the router with routes. The unversioned /api paths are kept for existing clients
+    // and answer the same as /api/v1, but are marked as deprecated.
+    let app = routes
+        .route("/health", get(health_check))
+        .route(
+            "/share/{token}/weekly",
+            get(get_shared_weekly_stats_endpoint),
+        )
+        .route("/feed/weekly.atom", get(get_weekly_feed_endpoint))
+        .nest("/api/v1", api.clone())
+        .nest(
+            "/api",
+            api.layer(middleware::from_fn(deprecated_api_middleware)),
+        )
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth_middleware,
+        ))
+        .layer(middleware::from_fn(envelope::envelope_middleware))
+        .layer(middleware::from_fn(timezone_middleware))
+        .layer(CorsLayer::permissive())
+        .with_state(state);
+
+    Ok(app)
+}
+
+/// When the unversioned `/api` paths were deprecated in favor of `/api/v1` (RFC 9745 format)
+const API_DEPRECATION: &str = "@1792108800";
+
+/// When the unversioned `/api` paths may be removed (RFC 8594 HTTP date)
+const API_SUNSET: &str = "Fri, 16 Apr 2027 00:00:00 GMT";
+
+/// Marks responses to the unversioned `/api` paths as deprecated
+///
+/// Adds `Deprecation` and `Sunset` headers, and a `Link` to the same endpoint under
+/// `/api/v1` so clients can find its replacement.
+async fn deprecated_api_middleware(
+    OriginalUri(uri): OriginalUri,
     req: Request,
     next: Next,
-    expected_api_key: String,
+) -> Response {
+    let mut response = next.run(req).await;
+
+    let successor =
+        api_route(uri.path()).map(|rest| format!("</api/v1{}>; rel=\"successor-version\"", rest));
+    let headers = response.headers_mut();
+    headers.insert("Deprecation", HeaderValue::from_static(API_DEPRECATION));
+    headers.insert("Sunset", HeaderValue::from_static(API_SUNSET));
+    if let Some(value) = successor.and_then(|link| HeaderValue::from_str(&link).ok()) {
+        headers.insert(header::LINK, value);
+    }
+
+    response
+}
+
+/// Names the timezone that dates, day boundaries and RFC 3339 timestamps in responses use
+///
+/// Days start at 4 AM in this timezone, so clients can place early-morning activity on the
+/// same day the server does.
+async fn timezone_middleware(req: Request, next: Next) -> Response {
+    let mut response = next.run(req).await;
+    response
+        .headers_mut()
+        .insert("X-Timezone", HeaderValue::from_static(statsutils::TIMEZONE));
+    response
+}
+
+/// Returns the route of an API path relative to its `/api/v1` or unversioned `/api` prefix
+fn api_route(path: &str) -> Option<&str> {
+    path.strip_prefix("/api/v1")
+        .or_else(|| path.strip_prefix("/api"))
+        .filter(|route| route.starts_with('/'))
+}
+
+/// API key passed in the query string of a feed URL, percent-decoded
+#[derive(Debug, Deserialize)]
+struct FeedTokenQuery {
+    token: Option<String>,
+}
+
+/// Authentication middleware that validates the API key and resolves the user it belongs to
+///
+/// The user's UserProfile and AppConfig are made available to handlers as request extensions.
+async fn auth_middleware(
+    axum::extract::State(users): axum::extract::State<Users>,
+    axum::extract::State(admin_api_key): axum::extract::State<AdminApiKey>,
+    mut req: Request,
+    next: Next,
 ) -> Result<Response, StatusCode> {
     let path = req.uri().path();
 
     // Skip auth for public endpoints
-    if path == "/health" || path == "/openapi.json" || path.starts_with("/swagger-ui") {
+    if path == "/health"
+        || path == "/openapi.json"
+        || api_route(path)
+            .is_some_and(|route| route == "/schemas" || route.starts_with("/schemas/"))
+        || path.starts_with("/swagger-ui")
+        || path.starts_with("/share/")
+    {
         return Ok(next.run(req).await);
     }
 
@@ -197,32 +722,127 @@ async fn auth_middleware(
         .and_then(|h| h.to_str().ok())
         .unwrap_or("");
 
-    if let Some(token) = auth_header.strip_prefix("Bearer ")
-        && token == expected_api_key
+    // Admin endpoints only accept the admin key, which does not belong to any user
+    if api_route(path).is_some_and(|route| route.starts_with("/admin/")) {
+        return match (auth_header.strip_prefix("Bearer "), &admin_api_key.0) {
+            (Some(token), Some(admin_key)) if token == admin_key => Ok(next.run(req).await),
+            _ => Err(StatusCode::UNAUTHORIZED),
+        };
+    }
+
+    // Feed readers usually cannot send headers, so feeds also accept `?token=<API key>`
+    let query_token = if path.starts_with("/feed/") {
+        axum::extract::Query::<FeedTokenQuery>::try_from_uri(req.uri())
+            .ok()
+            .and_then(|query| query.0.token)
+    } else {
+        None
+    };
+
+    if let Some(token) = auth_header
+        .strip_prefix("Bearer ")
+        .or(query_token.as_deref())
+        && let Some(user) = users.authenticate(token)
     {
+        let user = user.clone();
+        req.extensions_mut().insert(user.config.clone());
+        req.extensions_mut().insert(user);
         return Ok(next.run(req).await);
     }
 
     Err(StatusCode::UNAUTHORIZED)
 }
 
+/// Health check response
+#[derive(Debug, Clone, Serialize, ToSchema)]
+struct HealthCheck {
+    /// "ok", or "degraded" when any user's source breaches its staleness threshold
+    status: String,
+}
+
+/// Health of the authenticated user's sources
+#[derive(Debug, Clone, Serialize, ToSchema)]
+struct UserHealth {
+    /// "ok", or "degraded" when any of the user's sources breaches its staleness threshold
+    status: String,
+    service: String,
+    /// The user's sources whose data is older than their configured staleness threshold
+    stale_sources: Vec<StaleSource>,
+    /// The user's sources whose history starts later than the history preserved in the
+    /// warehouse, e.g. after the app pruned old data; a warning that does not affect the status
+    history_regressions: Vec<HistoryRegression>,
+    /// IANA timezone of dates, day boundaries (4 AM) and timestamps in responses
+    timezone: String,
+}
+
 /// Health check endpoint
+///
+/// Public, so it only reports whether any source is stale; which sources are stale or have
+/// a regressed history is reported to their user by `/api/v1/health`.
 #[utoipa::path(
     get,
     path = "/health",
     responses(
-        (status = 200, description = "Service is healthy", body = HealthCheck)
+        (status = 200, description = "Service is running; status is degraded if any source is stale", body = HealthCheck)
+    ),
+    tag = "health"
+)]
+async fn health_check(
+    axum::extract::State(staleness): axum::extract::State<StalenessState>,
+) -> impl IntoResponse {
+    Json(HealthCheck {
+        status: health_status(staleness.stale_sources().is_empty()).to_string(),
+    })
+}
+
+/// Get the health of the authenticated user's sources
+#[utoipa::path(
+    get,
+    path = "/api/v1/health",
+    responses(
+        (status = 200, description = "Health of the user's sources; status is degraded if any of them is stale", body = UserHealth),
+        (status = 401, description = "Unauthorized - invalid or missing API key")
+    ),
+    security(
+        ("bearer_auth" = [])
     ),
     tag = "health"
 )]
-async fn health_check() -> impl IntoResponse {
-    Json(HealthCheck::new())
+async fn get_user_health_endpoint(
+    axum::Extension(user): axum::Extension<UserProfile>,
+    axum::extract::State(staleness): axum::extract::State<StalenessState>,
+) -> Json<UserHealth> {
+    let stale_sources: Vec<StaleSource> = staleness
+        .stale_sources()
+        .into_iter()
+        .filter(|(user_id, _)| *user_id == user.id)
+        .map(|(_, source)| source)
+        .collect();
+    let history_regressions: Vec<HistoryRegression> = staleness
+        .history_regressions()
+        .into_iter()
+        .filter(|(user_id, _)| *user_id == user.id)
+        .map(|(_, regression)| regression)
+        .collect();
+
+    Json(UserHealth {
+        status: health_status(stale_sources.is_empty()).to_string(),
+        service: "life-stats".to_string(),
+        stale_sources,
+        history_regressions,
+        timezone: statsutils::TIMEZONE.to_string(),
+    })
+}
+
+/// Health status: "ok" unless a source is stale
+fn health_status(no_stale_sources: bool) -> &'static str {
+    if no_stale_sources { "ok" } else { "degraded" }
 }
 
 /// Get Bible book statistics
 #[utoipa::path(
     get,
-    path = "/api/anki/books",
+    path = "/api/v1/anki/books",
     responses(
         (status = 200, description = "Bible book statistics retrieved successfully", body = BibleStats),
         (status = 401, description = "Unauthorized - invalid or missing API key"),
@@ -234,119 +854,2175 @@ async fn health_check() -> impl IntoResponse {
     tag = "anki"
 )]
 async fn get_books_stats(
-    axum::extract::State(config): axum::extract::State<AppConfig>,
+    axum::Extension(config): axum::Extension<AppConfig>,
 ) -> Result<Json<BibleStats>, AppError> {
-    let stats = get_bible_stats(&config.anki_db_path)?;
+    let stats = get_bible_stats(config.sources.anki_collection()?)?;
     Ok(Json(stats))
 }
 
-/// Get today's unified faith statistics
+/// List card references that the verse or book name parser cannot handle
+///
+/// These cards are counted as a single verse or left out of book statistics, so fixing
+/// their sort field makes the coverage numbers accurate.
 #[utoipa::path(
     get,
-    path = "/api/faith/today",
+    path = "/api/v1/anki/parse-warnings",
+    params(ListQuery),
     responses(
-        (status = 200, description = "Today's unified faith statistics retrieved successfully", body = FaithTodayStats),
+        (status = 200, description = "Unparsable references retrieved successfully", body = Vec<ParseWarning>),
+        (status = 400, description = "Invalid limit or unknown field", body = ErrorResponse),
         (status = 401, description = "Unauthorized - invalid or missing API key"),
         (status = 500, description = "Internal server error", body = ErrorResponse)
     ),
     security(
         ("bearer_auth" = [])
     ),
-    tag = "faith"
+    tag = "anki"
 )]
-async fn get_faith_today_stats_endpoint(
-    axum::extract::State(config): axum::extract::State<AppConfig>,
-) -> Result<Json<FaithTodayStats>, AppError> {
-    let stats = get_faith_today_stats(
-        &config.anki_db_path,
-        &config.koreader_db_path,
-        &config.proseuche_db_path,
-    )?;
-    Ok(Json(stats))
+async fn get_parse_warnings_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+    axum::extract::Query(list): axum::extract::Query<ListQuery>,
+) -> Result<Response, AppError> {
+    list.respond(get_parse_warnings(config.sources.anki_collection()?)?)
+}
+
+/// Query parameters for reference normalization
+#[derive(Debug, Deserialize, IntoParams)]
+struct NormalizeQuery {
+    /// Bible reference in any accepted form, e.g. "romans 5: 1 - 8"
+    reference: String,
 }
 
-/// Get unified faith statistics for last 30 days
+/// Convert a Bible reference to its canonical "Book C:V–V" form
+///
+/// Strips Unicode formatting characters, fixes the book name's spelling and uses an en dash
+/// for ranges, so card-creation tools can keep new cards consistent.
 #[utoipa::path(
     get,
-    path = "/api/faith/daily",
+    path = "/api/v1/anki/normalize",
+    params(NormalizeQuery),
     responses(
-        (status = 200, description = "Unified faith statistics for last 30 days retrieved successfully", body = FaithDailyStats),
+        (status = 200, description = "Reference normalized successfully", body = NormalizedReference),
+        (status = 400, description = "Reference cannot be parsed", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "anki"
+)]
+async fn normalize_reference_endpoint(
+    axum::extract::Query(query): axum::extract::Query<NormalizeQuery>,
+) -> Response {
+    match parse_reference(&query.reference) {
+        Ok(reference) => {
+            Json(NormalizedReference::new(&query.reference, &reference)).into_response()
+        }
+        Err(error) => (StatusCode::BAD_REQUEST, Json(ErrorResponse::new(error))).into_response(),
+    }
+}
+
+/// Get the verse-level coverage of a Bible book
+///
+/// Every verse covered by a card gets the most learned status among the cards covering it,
+/// which is what a "colored Bible page" visualization needs.
+#[utoipa::path(
+    get,
+    path = "/api/v1/anki/coverage/{book}",
+    params(
+        ("book" = String, Path, description = "Book name, e.g. \"2 Timothy\" (case-insensitive)")
+    ),
+    responses(
+        (status = 200, description = "Book coverage retrieved successfully", body = BookCoverage),
         (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 404, description = "Unknown book"),
         (status = 500, description = "Internal server error", body = ErrorResponse)
     ),
     security(
         ("bearer_auth" = [])
     ),
-    tag = "faith"
+    tag = "anki"
 )]
-async fn get_faith_daily_stats_endpoint(
-    axum::extract::State(config): axum::extract::State<AppConfig>,
-) -> Result<Json<FaithDailyStats>, AppError> {
-    let stats = get_faith_daily_stats(
-        &config.anki_db_path,
-        &config.koreader_db_path,
-        &config.proseuche_db_path,
-    )?;
-    Ok(Json(stats))
+async fn get_book_coverage_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+    axum::extract::Path(book): axum::extract::Path<String>,
+) -> Result<Response, AppError> {
+    match get_book_coverage(config.sources.anki_collection()?, &book)? {
+        Some(coverage) => Ok(Json(coverage).into_response()),
+        None => Ok(StatusCode::NOT_FOUND.into_response()),
+    }
 }
 
-/// Get unified faith statistics for last 12 weeks
+/// Query parameters for the passage list
+#[derive(Debug, Deserialize, IntoParams)]
+struct PassagesQuery {
+    /// Only passages of this book, e.g. "2 Timothy" (case-insensitive)
+    book: Option<String>,
+}
+
+/// Get every passage with the dates it was first reviewed and first matured
+///
+/// Passages are listed in the order they were added. The dates come from the review log, so
+/// they allow anniversaries like "memorized two years ago today".
 #[utoipa::path(
     get,
-    path = "/api/faith/weekly",
+    path = "/api/v1/anki/passages",
+    params(PassagesQuery, ListQuery),
     responses(
-        (status = 200, description = "Unified faith statistics for last 12 weeks retrieved successfully", body = FaithWeeklyStats),
+        (status = 200, description = "Passages retrieved successfully", body = Vec<PassageDetails>),
+        (status = 400, description = "Unknown book, invalid limit or unknown field", body = ErrorResponse),
         (status = 401, description = "Unauthorized - invalid or missing API key"),
         (status = 500, description = "Internal server error", body = ErrorResponse)
     ),
     security(
         ("bearer_auth" = [])
     ),
-    tag = "faith"
+    tag = "anki"
 )]
-async fn get_faith_weekly_stats_endpoint(
-    axum::extract::State(config): axum::extract::State<AppConfig>,
-) -> Result<Json<FaithWeeklyStats>, AppError> {
-    let stats = get_faith_weekly_stats(
-        &config.anki_db_path,
-        &config.koreader_db_path,
-        &config.arcstats_export_path,
-        &config.proseuche_db_path,
-    )?;
-    Ok(Json(stats))
+async fn get_passages_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+    axum::extract::Query(query): axum::extract::Query<PassagesQuery>,
+    axum::extract::Query(list): axum::extract::Query<ListQuery>,
+) -> Result<Response, AppError> {
+    match get_passages(config.sources.anki_collection()?, query.book.as_deref())? {
+        Some(passages) => list.respond(passages),
+        None => Ok((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(format!(
+                "Unknown book: {}",
+                query.book.unwrap_or_default()
+            ))),
+        )
+            .into_response()),
+    }
 }
 
-/// Get top 10 places by time spent over last 6 months
+/// Get the number of passages of each length (1 verse, 2–3, 4–6, 7+) by status
+///
+/// Shows whether long passages lapse or get suspended disproportionately.
 #[utoipa::path(
     get,
-    path = "/api/arc/top-places",
+    path = "/api/v1/anki/span-distribution",
     responses(
-        (status = 200, description = "Top 10 places by hours spent over last 6 months retrieved successfully", body = Vec<PlaceStats>),
+        (status = 200, description = "Span length distribution retrieved successfully", body = Vec<SpanBucketStats>),
         (status = 401, description = "Unauthorized - invalid or missing API key"),
         (status = 500, description = "Internal server error", body = ErrorResponse)
     ),
     security(
         ("bearer_auth" = [])
     ),
-    tag = "arc"
+    tag = "anki"
 )]
-async fn get_top_places_stats_endpoint(
-    axum::extract::State(config): axum::extract::State<AppConfig>,
-) -> Result<Json<Vec<PlaceStats>>, AppError> {
-    let stats = get_top_places_last_6_months(&config.arcstats_export_path, 10)?;
-    Ok(Json(stats))
+async fn get_span_distribution_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+) -> Result<Json<Vec<SpanBucketStats>>, AppError> {
+    Ok(Json(get_span_distribution(
+        config.sources.anki_collection()?,
+    )?))
 }
 
-/// Custom error type for API errors
-struct AppError(anyhow::Error);
+/// Get the relapse rate after maturity per book
+///
+/// The fraction of passages that dropped below 21 days again within 90 days of maturing,
+/// which separates durable memorization from cramming.
+#[utoipa::path(
+    get,
+    path = "/api/v1/anki/relapse",
+    responses(
+        (status = 200, description = "Relapse rates retrieved successfully", body = RelapseStats),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "anki"
+)]
+async fn get_relapse_stats_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+) -> Result<Json<RelapseStats>, AppError> {
+    Ok(Json(get_relapse_stats(config.sources.anki_collection()?)?))
+}
 
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse::new(format!("{:#}", self.0))),
+/// Query parameters for Anki retention
+#[derive(Debug, Deserialize, IntoParams)]
+struct RetentionQuery {
+    /// Number of days to include, ending with today (default 30); the per-book retention
+    /// covers these days
+    days: Option<i32>,
+    /// Number of weeks to include, ending with this week (default 12)
+    weeks: Option<i32>,
+}
+
+/// Get the retention of Anki reviews per day, week and book
+///
+/// The fraction of answers to cards in review that were not "Again", which shows whether
+/// recall is improving where study time alone does not.
+#[utoipa::path(
+    get,
+    path = "/api/v1/anki/retention",
+    params(RetentionQuery),
+    responses(
+        (status = 200, description = "Retention retrieved successfully", body = RetentionStats),
+        (status = 400, description = "Invalid number of days or weeks", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "anki"
+)]
+async fn get_retention_stats_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+    axum::extract::Query(query): axum::extract::Query<RetentionQuery>,
+) -> Result<Response, AppError> {
+    let days = query.days.unwrap_or(30);
+    if let Some(response) = invalid_days_response(days) {
+        return Ok(response);
+    }
+    let weeks = query.weeks.unwrap_or(12);
+    if !(1..=520).contains(&weeks) {
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                "weeks must be between 1 and 520".to_string(),
+            )),
         )
-            .into_response()
+            .into_response());
+    }
+
+    let retention = get_retention_stats(config.sources.anki_collection()?, days, weeks)?;
+    let period = ResponsePeriod::spanning(retention.daily.iter().map(|day| day.date.as_str()));
+    Ok(ResponsePeriod::attach(
+        period,
+        Json(retention).into_response(),
+    ))
+}
+
+/// Query parameters for the Anki review forecast
+#[derive(Debug, Deserialize, IntoParams)]
+struct ForecastQuery {
+    /// Number of days to include, starting with today (default 14)
+    days: Option<i32>,
+}
+
+/// Get the Anki reviews due on each of the coming days
+///
+/// Counts the Bible deck's cards falling due per day from their scheduled due dates, with
+/// overdue cards on today, and projects the minutes they take at the recent time per review.
+#[utoipa::path(
+    get,
+    path = "/api/v1/anki/forecast",
+    params(ForecastQuery),
+    responses(
+        (status = 200, description = "Review forecast computed successfully", body = ReviewForecast),
+        (status = 400, description = "Invalid number of days", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "anki"
+)]
+async fn get_review_forecast_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+    axum::extract::Query(query): axum::extract::Query<ForecastQuery>,
+) -> Result<Response, AppError> {
+    let days = query.days.unwrap_or(14);
+    if let Some(response) = invalid_days_response(days) {
+        return Ok(response);
+    }
+
+    let forecast = get_review_forecast(config.sources.anki_collection()?, days.into())?;
+    let period = ResponsePeriod::spanning(forecast.daily.iter().map(|day| day.date.as_str()));
+    Ok(ResponsePeriod::attach(
+        period,
+        Json(forecast).into_response(),
+    ))
+}
+
+/// Query parameters for Anki study sessions
+#[derive(Debug, Deserialize, IntoParams)]
+struct SessionsQuery {
+    /// Number of days to include, ending with today (default 30)
+    days: Option<i32>,
+    /// Longest pause between reviews within one session, in minutes (default 10)
+    gap_minutes: Option<i64>,
+}
+
+/// Get Anki study sessions per day
+///
+/// Reviews of the Bible deck are grouped into sessions wherever the pause between two
+/// reviews exceeds `gap_minutes`, giving each day's session count, lengths and times.
+#[utoipa::path(
+    get,
+    path = "/api/v1/anki/sessions",
+    params(SessionsQuery),
+    responses(
+        (status = 200, description = "Study sessions retrieved successfully", body = StudySessions),
+        (status = 400, description = "Invalid number of days or gap", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "anki"
+)]
+async fn get_study_sessions_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+    axum::extract::Query(query): axum::extract::Query<SessionsQuery>,
+) -> Result<Response, AppError> {
+    let days = query.days.unwrap_or(30);
+    if let Some(response) = invalid_days_response(days) {
+        return Ok(response);
+    }
+    let gap_minutes = query.gap_minutes.unwrap_or(DEFAULT_SESSION_GAP_MINUTES);
+    if !(1..=24 * 60).contains(&gap_minutes) {
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                "gap_minutes must be between 1 and 1440".to_string(),
+            )),
+        )
+            .into_response());
+    }
+
+    let sessions = get_study_sessions(config.sources.anki_collection()?, days, gap_minutes)?;
+    let period = ResponsePeriod::spanning(sessions.days.iter().map(|day| day.date.as_str()));
+    Ok(ResponsePeriod::attach(
+        period,
+        Json(sessions).into_response(),
+    ))
+}
+
+/// Get the scheduling settings of the Bible deck
+///
+/// New cards and reviews per day, maximum interval and FSRS state from the deck's options
+/// group: the assumptions behind the projections and forecasts, and a way to notice when a
+/// sync reset them.
+#[utoipa::path(
+    get,
+    path = "/api/v1/anki/settings",
+    responses(
+        (status = 200, description = "Deck settings retrieved successfully", body = DeckSettings),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "anki"
+)]
+async fn get_deck_settings_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+) -> Result<Json<DeckSettings>, AppError> {
+    Ok(Json(get_deck_settings(config.sources.anki_collection()?)?))
+}
+
+/// Get today's unified faith statistics
+#[utoipa::path(
+    get,
+    path = "/api/v1/faith/today",
+    responses(
+        (status = 200, description = "Today's unified faith statistics retrieved successfully", body = FaithTodayStats),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "faith"
+)]
+async fn get_faith_today_stats_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+) -> Result<Json<FaithTodayStats>, AppError> {
+    let stats = config.sources.faith_stats().build().today()?;
+    Ok(Json(stats))
+}
+
+/// Query parameters for the daily stats: the period and filtering by tag
+#[derive(Debug, Deserialize, IntoParams)]
+struct FaithDailyQuery {
+    /// Number of days to include, ending with today (default 30)
+    days: Option<i32>,
+    /// Only include days carrying this tag
+    tag: Option<String>,
+    /// Only include days not carrying this tag
+    exclude_tag: Option<String>,
+}
+
+/// Query parameter choosing how summaries treat the current day or week
+#[derive(Debug, Deserialize, IntoParams)]
+struct PartialPeriodQuery {
+    /// `include` (default) counts the current, still running day or week like a complete one;
+    /// `exclude` leaves it out of the summary
+    #[serde(default = "include_partial_period")]
+    partial: PartialPeriod,
+}
+
+/// v1 summaries have always counted the current period, so that stays the default
+fn include_partial_period() -> PartialPeriod {
+    PartialPeriod::Include
+}
+
+/// How minutes from before a source's first recorded activity are reported
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+enum UntrackedMinutes {
+    /// As 0 minutes, counted in the averages like days without activity
+    #[default]
+    Zero,
+    /// As `null`, left out of the averages
+    Null,
+}
+
+/// Query parameter choosing how days before a source's history are reported
+#[derive(Debug, Deserialize, IntoParams)]
+struct UntrackedQuery {
+    /// `zero` (default) reports their minutes as 0, as v1 always has; `null` reports a day's
+    /// minutes as null and leaves days and weeks before the source's history out of its
+    /// averages (see the summary's `*_tracked` counts)
+    #[serde(default)]
+    untracked: UntrackedMinutes,
+}
+
+/// Get unified faith statistics for the last 30 days, or the given number of days
+///
+/// With `tag` or `exclude_tag`, only the matching days are listed and the summary covers
+/// just them, e.g. to compare the average reading on travel and non-travel days. With
+/// `partial=exclude`, today is listed but left out of the summary.
+#[utoipa::path(
+    get,
+    path = "/api/v1/faith/daily",
+    params(FaithDailyQuery, PartialPeriodQuery, UntrackedQuery),
+    responses(
+        (status = 200, description = "Unified faith statistics for last 30 days retrieved successfully", body = FaithDailyStats),
+        (status = 400, description = "Invalid number of days or tag", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "faith"
+)]
+async fn get_faith_daily_stats_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+    axum::extract::Query(query): axum::extract::Query<FaithDailyQuery>,
+    axum::extract::Query(partial): axum::extract::Query<PartialPeriodQuery>,
+    axum::extract::Query(untracked): axum::extract::Query<UntrackedQuery>,
+) -> Result<Response, AppError> {
+    let days = query.days.unwrap_or(30);
+    if let Some(response) = invalid_days_response(days) {
+        return Ok(response);
+    }
+    let mut filters = Vec::new();
+    for (tag, tagged) in [(&query.tag, true), (&query.exclude_tag, false)] {
+        let Some(tag) = tag else {
+            continue;
+        };
+        match normalize_tag(tag) {
+            Ok(tag) => filters.push((tag, tagged)),
+            Err(error) => return Ok(invalid_tag_response(&error)),
+        }
+    }
+
+    let mut stats = config.faith_daily_stats(days)?;
+    if untracked.untracked == UntrackedMinutes::Zero {
+        stats = stats.with_untracked_as_zero();
+    }
+    let period = ResponsePeriod::spanning(stats.days.iter().map(|day| day.date.as_str()));
+    for (tag, tagged) in filters {
+        stats = filter_by_tag(stats, &tag, tagged);
+    }
+    let stats = stats.with_partial_period(partial.partial, &get_today_date()?);
+    let mut response = ResponsePeriod::attach(period, Json(stats).into_response());
+    response.extensions_mut().insert(partial.partial);
+    Ok(response)
+}
+
+/// Most dates accepted by one request for specific days
+const MAX_REQUESTED_DAYS: usize = 1000;
+
+/// Dates to get faith statistics for
+#[derive(Debug, Deserialize, ToSchema)]
+struct FaithDaysRequest {
+    /// Dates in YYYY-MM-DD format, in any order; duplicates are returned once
+    dates: Vec<String>,
+}
+
+/// Get unified faith statistics for specific dates
+///
+/// Returns the days in date order with a summary over just them, e.g. all Sundays of the
+/// year without fetching every day in between. With `partial=exclude`, today is left out of
+/// the summary when it is the last of the dates.
+#[utoipa::path(
+    post,
+    path = "/api/v1/faith/days",
+    params(PartialPeriodQuery, UntrackedQuery),
+    request_body = FaithDaysRequest,
+    responses(
+        (status = 200, description = "Unified faith statistics for the dates retrieved successfully", body = FaithDailyStats),
+        (status = 400, description = "No dates, too many dates or an invalid date", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "faith"
+)]
+async fn get_faith_days_stats_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+    axum::extract::Query(partial): axum::extract::Query<PartialPeriodQuery>,
+    axum::extract::Query(untracked): axum::extract::Query<UntrackedQuery>,
+    Json(request): Json<FaithDaysRequest>,
+) -> Result<Response, AppError> {
+    if request.dates.is_empty() || request.dates.len() > MAX_REQUESTED_DAYS {
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(format!(
+                "dates must list between 1 and {} dates",
+                MAX_REQUESTED_DAYS
+            ))),
+        )
+            .into_response());
+    }
+    let period = match DatePeriod::for_dates(&request.dates) {
+        Ok(period) => period,
+        Err(error) => {
+            return Ok((
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::new(format!("{:#}", error))),
+            )
+                .into_response());
+        }
+    };
+
+    let mut stats = config.faith_days_stats(period)?;
+    if untracked.untracked == UntrackedMinutes::Zero {
+        stats = stats.with_untracked_as_zero();
+    }
+    let stats = stats.with_partial_period(partial.partial, &get_today_date()?);
+    let period = ResponsePeriod::spanning(stats.days.iter().map(|day| day.date.as_str()));
+    let mut response = ResponsePeriod::attach(period, Json(stats).into_response());
+    response.extensions_mut().insert(partial.partial);
+    Ok(response)
+}
+
+/// Get unified faith statistics for last 12 weeks
+///
+/// With `partial=exclude`, the current week is listed but left out of the summary.
+#[utoipa::path(
+    get,
+    path = "/api/v1/faith/weekly",
+    params(PartialPeriodQuery, UntrackedQuery),
+    responses(
+        (status = 200, description = "Unified faith statistics for last 12 weeks retrieved successfully", body = FaithWeeklyStats),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "faith"
+)]
+async fn get_faith_weekly_stats_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+    axum::extract::Query(partial): axum::extract::Query<PartialPeriodQuery>,
+    axum::extract::Query(untracked): axum::extract::Query<UntrackedQuery>,
+) -> Result<Response, AppError> {
+    let mut stats = config.faith_weekly_stats()?;
+    if untracked.untracked == UntrackedMinutes::Null {
+        stats = stats.with_history(config.sources.faith_stats().build().history_starts()?);
+    }
+    let stats = stats.with_partial_period(partial.partial, &get_current_week_start()?);
+    let period = match (stats.weeks.first(), stats.weeks.last()) {
+        (Some(first), Some(last)) => Some(ResponsePeriod {
+            start: first.week_start.clone(),
+            end: (NaiveDate::parse_from_str(&last.week_start, "%Y-%m-%d")? + Days::new(6))
+                .format("%Y-%m-%d")
+                .to_string(),
+        }),
+        _ => None,
+    };
+    let mut response = ResponsePeriod::attach(period, Json(stats).into_response());
+    response.extensions_mut().insert(partial.partial);
+    Ok(response)
+}
+
+/// Get the daily and weekly faith score for the last 30 days and 12 weeks
+///
+/// The score (0-100) is the weighted average of each activity's minutes as a fraction of its
+/// cap, so a single number tracks overall consistency. Weights and caps are configured per
+/// user (`score` in the users config, or FAITH_SCORE_CAPS/FAITH_SCORE_WEIGHTS). Church
+/// attendance is only tracked per week and only counts towards the weekly score.
+#[utoipa::path(
+    get,
+    path = "/api/v1/faith/score",
+    responses(
+        (status = 200, description = "Faith score retrieved successfully", body = FaithScore),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "faith"
+)]
+async fn get_faith_score_endpoint(
+    axum::Extension(user): axum::Extension<UserProfile>,
+) -> Result<Json<FaithScore>, AppError> {
+    let daily = user.config.faith_daily_stats(30)?;
+    let weekly = user.config.faith_weekly_stats()?;
+    Ok(Json(FaithScore::new(
+        &daily.days,
+        &weekly.weeks,
+        user.score.clone(),
+    )))
+}
+
+/// Query parameters for habit consistency
+#[derive(Debug, Deserialize, IntoParams)]
+struct ConsistencyQuery {
+    /// Minutes a day needs for an activity to count as practiced (default 5)
+    min_minutes: Option<f64>,
+}
+
+/// Get how consistently each activity was practiced over the last 30, 90 and 365 days
+///
+/// For each daily activity: the share of days with at least `min_minutes`, the longest run of
+/// days below it, and the coefficient of variation of the daily minutes (lower is steadier),
+/// for a habits-focused view rather than totals.
+#[utoipa::path(
+    get,
+    path = "/api/v1/faith/consistency",
+    params(ConsistencyQuery),
+    responses(
+        (status = 200, description = "Habit consistency retrieved successfully", body = FaithConsistency),
+        (status = 400, description = "Invalid minimum minutes", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "faith"
+)]
+async fn get_faith_consistency_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+    axum::extract::Query(query): axum::extract::Query<ConsistencyQuery>,
+) -> Result<Response, AppError> {
+    let min_minutes = query.min_minutes.unwrap_or(DEFAULT_MIN_MINUTES);
+    if !(min_minutes > 0.0 && min_minutes.is_finite()) {
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                "min_minutes must be positive".to_string(),
+            )),
+        )
+            .into_response());
+    }
+
+    let longest_window = CONSISTENCY_WINDOWS.into_iter().max().unwrap_or_default();
+    let stats = config.faith_daily_stats(longest_window)?;
+    Ok(Json(FaithConsistency::new(&stats.days, min_minutes)).into_response())
+}
+
+/// Get the last 12 weeks' faith time against the user's weekly time budget
+///
+/// Each activity is expressed as a percentage of the budget (`budget` in the users config,
+/// or FAITH_BUDGET_MINUTES, default 7 hours) and, where minutes are allocated to it, as
+/// minutes over or under its allocation. Exempt days shrink the week's budget by a seventh.
+#[utoipa::path(
+    get,
+    path = "/api/v1/faith/budget",
+    responses(
+        (status = 200, description = "Budget report retrieved successfully", body = BudgetReport),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "faith"
+)]
+async fn get_faith_budget_endpoint(
+    axum::Extension(user): axum::Extension<UserProfile>,
+) -> Result<Json<BudgetReport>, AppError> {
+    let weekly = user.config.faith_weekly_stats()?;
+    Ok(Json(BudgetReport::new(&weekly.weeks, user.budget.clone())))
+}
+
+/// Query parameters for the "on this day" view
+#[derive(Debug, Deserialize, IntoParams)]
+struct OnThisDayQuery {
+    /// Day to look up in YYYY-MM-DD format (default: today)
+    date: Option<String>,
+}
+
+/// Get what happened on today's date in earlier years
+///
+/// Passages that first matured (from the Anki review log), books finished in KOReader, and
+/// places visited for the first time or for at least an hour (from the location history),
+/// each with how many years ago it was.
+#[utoipa::path(
+    get,
+    path = "/api/v1/faith/on-this-day",
+    params(OnThisDayQuery),
+    responses(
+        (status = 200, description = "Anniversaries retrieved successfully", body = OnThisDay),
+        (status = 400, description = "Invalid date", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "faith"
+)]
+async fn get_on_this_day_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+    axum::extract::Query(query): axum::extract::Query<OnThisDayQuery>,
+) -> Result<Response, AppError> {
+    let date = match query
+        .date
+        .map(|date| NaiveDate::parse_from_str(&date, "%Y-%m-%d"))
+    {
+        Some(Ok(date)) => date.to_string(),
+        None => statsutils::get_today_date()?,
+        Some(Err(_)) => return Ok(invalid_date_response("date")),
+    };
+
+    let on_this_day = tokio::task::spawn_blocking(move || {
+        config.sources.faith_stats().build().on_this_day(&date)
+    })
+    .await??;
+    Ok(Json(on_this_day).into_response())
+}
+
+/// Query parameters for search
+#[derive(Debug, Deserialize, IntoParams)]
+struct SearchQuery {
+    /// Text to look for in passage references, place names and book titles (case-insensitive)
+    q: String,
+    /// Maximum number of results of each kind (1-50, default 10)
+    limit: Option<usize>,
+}
+
+/// Search memorized passages, places and books
+///
+/// Matches passage references (also in their canonical form, so "romans 5: 1" finds
+/// "Romans 5:1"), place names from the location history (home excluded) and KOReader book
+/// titles in one list, best matches first, for a quick-jump box.
+#[utoipa::path(
+    get,
+    path = "/api/v1/search",
+    params(SearchQuery),
+    responses(
+        (status = 200, description = "Search results retrieved successfully", body = SearchResults),
+        (status = 400, description = "Empty query or invalid limit", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "search"
+)]
+async fn search_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+    axum::extract::Query(query): axum::extract::Query<SearchQuery>,
+) -> Result<Response, AppError> {
+    if query.q.trim().is_empty() {
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new("q must not be empty".to_string())),
+        )
+            .into_response());
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    if !(1..=50).contains(&limit) {
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                "limit must be between 1 and 50".to_string(),
+            )),
+        )
+            .into_response());
+    }
+
+    let results = tokio::task::spawn_blocking(move || {
+        config.sources.faith_stats().build().search(&query.q, limit)
+    })
+    .await??;
+    Ok(Json(results).into_response())
+}
+
+/// Query parameters for the goal simulator
+#[derive(Debug, Deserialize, IntoParams)]
+struct SimulateGoalsQuery {
+    /// New verses added to Anki per day (default 0)
+    new_verses_per_day: Option<f64>,
+    /// Minutes of Bible reading per day (default 0)
+    reading_minutes_per_day: Option<f64>,
+    /// Reading speed in verses per minute (default 10)
+    reading_verses_per_minute: Option<f64>,
+    /// Comma-separated book names to project besides the New Testament, e.g. "Isaiah,Romans"
+    books: Option<String>,
+}
+
+/// Project completion dates for a hypothetical daily commitment
+///
+/// Given new verses and reading minutes per day, projects when each selected book and the
+/// whole New Testament would be memorized (from today's mature verses, discounted by the
+/// current relapse rate) and read through. Each target is projected as if the whole
+/// commitment went to it.
+#[utoipa::path(
+    get,
+    path = "/api/v1/goals/simulate",
+    params(SimulateGoalsQuery),
+    responses(
+        (status = 200, description = "Projections computed successfully", body = GoalSimulation),
+        (status = 400, description = "Unknown book or negative commitment", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "goals"
+)]
+async fn simulate_goals_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+    axum::extract::Query(query): axum::extract::Query<SimulateGoalsQuery>,
+) -> Result<Response, AppError> {
+    let bad_request = |message: String| {
+        Ok((StatusCode::BAD_REQUEST, Json(ErrorResponse::new(message))).into_response())
+    };
+
+    let commitment = Commitment {
+        new_verses_per_day: query.new_verses_per_day.unwrap_or(0.0),
+        reading_minutes_per_day: query.reading_minutes_per_day.unwrap_or(0.0),
+        reading_verses_per_minute: query
+            .reading_verses_per_minute
+            .unwrap_or(DEFAULT_READING_VERSES_PER_MINUTE),
+    };
+    if [
+        commitment.new_verses_per_day,
+        commitment.reading_minutes_per_day,
+        commitment.reading_verses_per_minute,
+    ]
+    .iter()
+    .any(|value| !(*value >= 0.0 && value.is_finite()))
+    {
+        return bad_request("Daily commitments and reading speed must not be negative".to_string());
+    }
+
+    let mut books = Vec::new();
+    for name in query.books.iter().flat_map(|books| books.split(',')) {
+        let name = name.trim();
+        if name.is_empty() {
+            continue;
+        }
+        match find_book(name) {
+            Some(book) => books.push(book),
+            None => return bad_request(format!("Unknown book '{}'", name)),
+        }
+    }
+
+    Ok(Json(
+        config
+            .sources
+            .faith_stats()
+            .build()
+            .simulate_goals(&books, commitment)?,
+    )
+    .into_response())
+}
+
+/// Get a suggested day-by-day plan for next week
+///
+/// The user's weekly goals, plus however far this week is behind an even pace, are spread
+/// over the days in proportion to the average faith time on each weekday over the last 12
+/// weeks. Anki time is raised to cover the reviews falling due each day, church goals go to
+/// Sunday, and exempt days get nothing.
+#[utoipa::path(
+    get,
+    path = "/api/v1/plan/next-week",
+    responses(
+        (status = 200, description = "Plan for next week retrieved successfully", body = WeekPlan),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "goals"
+)]
+async fn get_next_week_plan_endpoint(
+    axum::Extension(user): axum::Extension<UserProfile>,
+) -> Result<Json<WeekPlan>, AppError> {
+    let config = &user.config;
+    let today = NaiveDate::parse_from_str(&statsutils::get_today_date()?, "%Y-%m-%d")?;
+    let days_until_next_week = 7 - today.weekday().num_days_from_sunday() as i64;
+    let week_start = today + chrono::Days::new(days_until_next_week as u64);
+
+    let exemptions = config.exemptions()?;
+    let exempt_days = (0..7)
+        .filter_map(|offset| week_start.checked_add_days(chrono::Days::new(offset)))
+        .filter(|date| is_exempt(&exemptions, &date.format("%Y-%m-%d").to_string()))
+        .count() as i64;
+
+    let weekly = config.faith_weekly_stats()?;
+    let current_week = weekly.weeks.last();
+    let elapsed_fraction = match current_week {
+        Some(week) => statsutils::week_elapsed_fraction(&week.week_start)?,
+        None => 0.0,
+    };
+    let goals = user
+        .goals
+        .iter()
+        .map(|goal| PlannedGoal::new(goal, exempt_days, current_week, elapsed_fraction))
+        .collect();
+
+    let forecast =
+        get_review_forecast(config.sources.anki_collection()?, days_until_next_week + 7)?;
+    let reviews = ReviewOutlook::for_week(week_start, &forecast);
+
+    let history = config.faith_daily_stats(AVAILABILITY_DAYS)?;
+    Ok(Json(WeekPlan::new(
+        week_start,
+        goals,
+        &history.days,
+        &exemptions,
+        &reviews,
+    )))
+}
+
+/// Get a flat snapshot of today's minutes, streaks and this week's minutes
+///
+/// All keys are top-level and stable, for use with Home Assistant REST sensors.
+#[utoipa::path(
+    get,
+    path = "/api/v1/ha/state",
+    responses(
+        (status = 200, description = "Flat faith statistics state retrieved successfully", body = HomeAssistantState),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "home-assistant"
+)]
+async fn get_home_assistant_state_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+) -> Result<Json<HomeAssistantState>, AppError> {
+    let daily = config.faith_daily_stats(STREAK_WINDOW_DAYS)?;
+    let weekly = config.faith_weekly_stats()?;
+    Ok(Json(HomeAssistantState::new(&daily, &weekly)))
+}
+
+/// Get weekly faith statistics of every household member, their combined totals,
+/// and progress towards shared household goals
+#[utoipa::path(
+    get,
+    path = "/api/v1/household/weekly",
+    responses(
+        (status = 200, description = "Household faith statistics for last 12 weeks retrieved successfully", body = HouseholdWeeklyStats),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "household"
+)]
+async fn get_household_weekly_stats_endpoint(
+    axum::extract::State(users): axum::extract::State<Users>,
+) -> Result<Json<HouseholdWeeklyStats>, AppError> {
+    let members = users
+        .profiles()
+        .into_iter()
+        .map(|user| Ok((user.id, user.config.faith_weekly_stats()?)))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    Ok(Json(combine_household_weekly_stats(
+        members,
+        users.household_goals(),
+    )))
+}
+
+/// Atom feed with the summary of each completed week
+///
+/// Besides the Bearer header, the API key can be passed as `?token=` (URL-encoded) for feed readers.
+#[utoipa::path(
+    get,
+    path = "/feed/weekly.atom",
+    params(
+        ("token" = Option<String>, Query, description = "API key, for clients that cannot send an Authorization header")
+    ),
+    responses(
+        (status = 200, description = "Atom feed generated successfully", content_type = "application/atom+xml", body = String),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "feed"
+)]
+async fn get_weekly_feed_endpoint(
+    axum::Extension(user): axum::Extension<UserProfile>,
+) -> Result<Response, AppError> {
+    let stats = user.config.faith_weekly_stats()?;
+    let feed = with_display_format(user.config.display_format, || {
+        feed::weekly_atom_feed(&user.id, &stats)
+    })?;
+    Ok(([(header::CONTENT_TYPE, "application/atom+xml")], feed).into_response())
+}
+
+/// Query parameters for the summary post preview
+#[derive(Debug, Deserialize, IntoParams)]
+struct SummaryPreviewQuery {
+    /// Period to summarize (default: the period configured for Mastodon, or weekly)
+    period: Option<SummaryPeriod>,
+}
+
+/// Preview the summary post for today or the current week
+///
+/// Composed with the user's Mastodon template when its period matches, so templates can be
+/// checked before posting. Nothing is published.
+#[utoipa::path(
+    get,
+    path = "/api/v1/social/preview",
+    params(SummaryPreviewQuery),
+    responses(
+        (status = 200, description = "Summary post composed successfully", body = SummaryPost),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "social"
+)]
+async fn get_summary_preview_endpoint(
+    axum::Extension(user): axum::Extension<UserProfile>,
+    axum::extract::Query(query): axum::extract::Query<SummaryPreviewQuery>,
+) -> Result<Json<SummaryPost>, AppError> {
+    let period = query
+        .period
+        .or(user.mastodon.as_ref().map(|mastodon| mastodon.period))
+        .unwrap_or_default();
+    Ok(Json(mastodon::compose(&user, period)?))
+}
+
+/// Get the publicly shared subset of a user's weekly faith statistics
+///
+/// Public endpoint: the share token in the URL is the only credential. Only the metrics the
+/// user opted to share are returned, without passage counts, verse texts or place names.
+/// Errors are logged rather than returned, since they name the user's files.
+#[utoipa::path(
+    get,
+    path = "/share/{token}/weekly",
+    params(
+        ("token" = String, Path, description = "Public share token")
+    ),
+    responses(
+        (status = 200, description = "Shared weekly statistics retrieved successfully", body = SharedWeeklyStats),
+        (status = 404, description = "Unknown share token"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    tag = "share"
+)]
+async fn get_shared_weekly_stats_endpoint(
+    axum::extract::State(users): axum::extract::State<Users>,
+    axum::extract::Path(token): axum::extract::Path<String>,
+) -> Result<Response, AppError> {
+    let Some(user) = users.find_by_share_token(&token) else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+    let Some(share) = &user.share else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+
+    match user.config.faith_weekly_stats() {
+        Ok(stats) => Ok(Json(SharedWeeklyStats::new(&stats, &share.metrics)).into_response()),
+        Err(e) => {
+            eprintln!("Shared stats of user '{}' failed: {:#}", user.id, e);
+            Ok((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new(
+                    "Failed to compute the shared statistics".to_string(),
+                )),
+            )
+                .into_response())
+        }
+    }
+}
+
+/// Get top places by time spent over last 6 months
+///
+/// Returns the top 10 places unless `limit` is given.
+#[utoipa::path(
+    get,
+    path = "/api/v1/arc/top-places",
+    params(ListQuery),
+    responses(
+        (status = 200, description = "Top places by hours spent over last 6 months retrieved successfully", body = Vec<PlaceStats>),
+        (status = 400, description = "Invalid limit or unknown field", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "arc"
+)]
+async fn get_top_places_stats_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+    axum::extract::Query(list): axum::extract::Query<ListQuery>,
+) -> Result<Response, AppError> {
+    let stats = tokio::task::spawn_blocking(move || {
+        get_top_places_last_6_months(config.sources.location_history()?, usize::MAX)
+    })
+    .await??;
+    list.respond_with_default_limit(stats, Some(10))
+}
+
+/// Query parameters for timeline coverage gaps
+#[derive(Debug, Deserialize, IntoParams)]
+struct CoverageGapsQuery {
+    /// Number of days to include, ending with today (default 30)
+    days: Option<i32>,
+}
+
+/// Get how many hours of each day have no recorded location history
+///
+/// Hours without any timeline item mean the phone was off or Arc was not recording, so a
+/// week with low church attendance can be told apart from one with missing data. Today only
+/// counts up to now.
+#[utoipa::path(
+    get,
+    path = "/api/v1/arc/gaps",
+    params(CoverageGapsQuery, ListQuery),
+    responses(
+        (status = 200, description = "Recorded and unrecorded hours per day retrieved successfully", body = Vec<DayCoverage>),
+        (status = 400, description = "Invalid number of days, limit or unknown field", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "arc"
+)]
+async fn get_coverage_gaps_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+    axum::extract::Query(query): axum::extract::Query<CoverageGapsQuery>,
+    axum::extract::Query(list): axum::extract::Query<ListQuery>,
+) -> Result<Response, AppError> {
+    let days = query.days.unwrap_or(30);
+    if let Some(response) = invalid_days_response(days) {
+        return Ok(response);
+    }
+
+    let gaps = tokio::task::spawn_blocking(move || {
+        get_coverage_gaps(config.sources.location_history()?, days)
+    })
+    .await??;
+    list.respond(gaps)
+}
+
+/// Get the weekly share of visit time spent at home for the last 12 weeks
+///
+/// Minutes of visits at the user's home place (`home_place` in the users config, or
+/// `HOME_PLACE` for the default user, by place ID or name; default "Home")
+/// divided by the minutes of all visits, to correlate with reading and prayer consistency.
+#[utoipa::path(
+    get,
+    path = "/api/v1/arc/home-ratio",
+    responses(
+        (status = 200, description = "Weekly at-home percentages retrieved successfully", body = Vec<HomeRatioWeek>),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "arc"
+)]
+async fn get_home_ratio_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+) -> Result<Json<Vec<HomeRatioWeek>>, AppError> {
+    let weeks =
+        tokio::task::spawn_blocking(move || get_home_ratio(config.sources.location_history()?, 12))
+            .await??;
+    Ok(Json(weeks))
+}
+
+/// Query parameters for newly discovered places
+#[derive(Debug, Deserialize, IntoParams)]
+struct NewPlacesQuery {
+    /// Number of months to include, ending with this month (default 12)
+    months: Option<u32>,
+}
+
+/// Get how many places were visited for the first time ever in each month
+///
+/// A place counts in the month of the earliest visit referencing it in the whole location
+/// history, so revisits of places discovered before the window are not counted.
+#[utoipa::path(
+    get,
+    path = "/api/v1/arc/new-places",
+    params(NewPlacesQuery, ListQuery),
+    responses(
+        (status = 200, description = "New places per month retrieved successfully", body = Vec<MonthNewPlaces>),
+        (status = 400, description = "Invalid number of months, limit or unknown field", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "arc"
+)]
+async fn get_new_places_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+    axum::extract::Query(query): axum::extract::Query<NewPlacesQuery>,
+    axum::extract::Query(list): axum::extract::Query<ListQuery>,
+) -> Result<Response, AppError> {
+    let months = query.months.unwrap_or(12);
+    if !(1..=120).contains(&months) {
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                "months must be between 1 and 120".to_string(),
+            )),
+        )
+            .into_response());
+    }
+
+    let new_places = tokio::task::spawn_blocking(move || {
+        get_new_places(config.sources.location_history()?, months)
+    })
+    .await??;
+    list.respond(new_places)
+}
+
+/// Query parameters for reading patterns
+#[derive(Debug, Deserialize, IntoParams)]
+struct PatternsQuery {
+    /// Number of days to include, ending with today (default 90)
+    days: Option<i32>,
+}
+
+/// Get when Bible reading happens, by hour of day and weekday
+///
+/// Reading minutes and sessions of the KOReader Bible and Treasury of Daily Prayer books,
+/// bucketed by the local hour and weekday they started in.
+#[utoipa::path(
+    get,
+    path = "/api/v1/reading/patterns",
+    params(PatternsQuery),
+    responses(
+        (status = 200, description = "Reading patterns retrieved successfully", body = ReadingPatterns),
+        (status = 400, description = "Invalid number of days", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "reading"
+)]
+async fn get_reading_patterns_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+    axum::extract::Query(query): axum::extract::Query<PatternsQuery>,
+) -> Result<Response, AppError> {
+    let days = query.days.unwrap_or(90);
+    if let Some(response) = invalid_days_response(days) {
+        return Ok(response);
+    }
+
+    let patterns = get_reading_patterns(config.sources.reading()?.pool(), days)?;
+    Ok(Json(patterns).into_response())
+}
+
+/// Query parameters for Daily Office adherence
+#[derive(Debug, Deserialize, IntoParams)]
+struct DailyOfficeQuery {
+    /// Number of days to include, ending with today (default 365)
+    days: Option<i32>,
+}
+
+/// Get on which days the Treasury of Daily Prayer was opened, with streaks
+///
+/// A day counts as soon as there is any KOReader session in the Treasury of Daily Prayer,
+/// independent of the Bible reading minutes. Streaks cannot be longer than the window.
+#[utoipa::path(
+    get,
+    path = "/api/v1/reading/daily-office",
+    params(DailyOfficeQuery),
+    responses(
+        (status = 200, description = "Daily Office adherence retrieved successfully", body = DailyOfficeStats),
+        (status = 400, description = "Invalid number of days", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "reading"
+)]
+async fn get_daily_office_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+    axum::extract::Query(query): axum::extract::Query<DailyOfficeQuery>,
+) -> Result<Response, AppError> {
+    let days = query.days.unwrap_or(STREAK_WINDOW_DAYS);
+    if let Some(response) = invalid_days_response(days) {
+        return Ok(response);
+    }
+
+    let stats = get_daily_office_stats(config.sources.reading()?.pool(), days)?;
+    Ok(Json(stats).into_response())
+}
+
+/// Query parameters for completed books
+#[derive(Debug, Deserialize, IntoParams)]
+struct CompletedBooksQuery {
+    /// Only include books finished in this year (default: all years)
+    year: Option<i32>,
+}
+
+/// Get the timeline of books read to the end
+///
+/// A book counts as finished when one of its last pages is viewed in KOReader; a book that is
+/// read again from the start appears once per reading.
+#[utoipa::path(
+    get,
+    path = "/api/v1/reading/completed",
+    params(CompletedBooksQuery, ListQuery),
+    responses(
+        (status = 200, description = "Completed books retrieved successfully, oldest first", body = Vec<CompletedBook>),
+        (status = 400, description = "Invalid limit or unknown field", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "reading"
+)]
+async fn get_completed_books_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+    axum::extract::Query(query): axum::extract::Query<CompletedBooksQuery>,
+    axum::extract::Query(list): axum::extract::Query<ListQuery>,
+) -> Result<Response, AppError> {
+    let books = get_completed_books(config.sources.reading()?.pool(), query.year)?;
+    list.respond(books)
+}
+
+/// Bad request response for a `days` query parameter outside of 1 to 3650
+fn invalid_days_response(days: i32) -> Option<Response> {
+    (!(1..=3650).contains(&days)).then(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                "days must be between 1 and 3650".to_string(),
+            )),
+        )
+            .into_response()
+    })
+}
+
+/// Query parameters for warehouse rollups
+#[derive(Debug, Deserialize, IntoParams)]
+struct WarehouseRollupsQuery {
+    /// First date in YYYY-MM-DD format (default: a year before `end`)
+    start: Option<String>,
+    /// Last date in YYYY-MM-DD format (default: today)
+    end: Option<String>,
+    /// Only rollups of this source: "anki", "koreader", "proseuche" or "arc"
+    source: Option<String>,
+    /// Only rollups of this metric, e.g. "minutes"
+    metric: Option<String>,
+}
+
+/// Get daily rollups from the warehouse
+///
+/// The warehouse is filled nightly from every source and keeps history the source apps have
+/// since pruned, so multi-year ranges are fast to query. Arc church attendance is only
+/// tracked per week and recorded on the week's first day as `church_minutes_week`.
+#[utoipa::path(
+    get,
+    path = "/api/v1/warehouse/rollups",
+    params(WarehouseRollupsQuery, ListQuery),
+    responses(
+        (status = 200, description = "Rollups retrieved successfully", body = Vec<Rollup>),
+        (status = 400, description = "Invalid date, limit or unknown field", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 404, description = "No warehouse configured for the user"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "warehouse"
+)]
+async fn get_warehouse_rollups_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+    axum::extract::Query(query): axum::extract::Query<WarehouseRollupsQuery>,
+    axum::extract::Query(list): axum::extract::Query<ListQuery>,
+) -> Result<Response, AppError> {
+    let Some(warehouse_path) = &config.warehouse_path else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+
+    let parse_date = |date: &str| NaiveDate::parse_from_str(date, "%Y-%m-%d");
+    let end = match query.end.as_deref().map(parse_date) {
+        Some(Ok(end)) => end,
+        None => parse_date(&statsutils::get_today_date()?)?,
+        Some(Err(_)) => return Ok(invalid_date_response("end")),
+    };
+    let start = match query.start.as_deref().map(parse_date) {
+        Some(Ok(start)) => start,
+        None => end - chrono::Days::new(365),
+        Some(Err(_)) => return Ok(invalid_date_response("start")),
+    };
+
+    let rollups = Warehouse::open(warehouse_path)?.get_rollups(
+        &start.to_string(),
+        &end.to_string(),
+        query.source.as_deref(),
+        query.metric.as_deref(),
+    )?;
+    list.respond(rollups)
+}
+
+/// Get the changes of the Bible deck's scheduling settings
+///
+/// The nightly rollup records the deck's settings whenever they differ from the last ones,
+/// so a forgotten limit change or a sync that reset them can be dated afterwards. The charts
+/// mark these dates as well.
+#[utoipa::path(
+    get,
+    path = "/api/v1/warehouse/deck-settings",
+    responses(
+        (status = 200, description = "Deck settings changes retrieved successfully", body = Vec<DeckSettingsChange>),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 404, description = "No warehouse configured for the user"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "warehouse"
+)]
+async fn get_deck_settings_changes_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+) -> Result<Response, AppError> {
+    let Some(warehouse_path) = &config.warehouse_path else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+
+    let changes = Warehouse::open(warehouse_path)?.get_deck_settings_changes()?;
+    Ok(Json(changes).into_response())
+}
+
+/// 400 response for a date parameter that is not in YYYY-MM-DD format
+fn invalid_date_response(parameter: &str) -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse::new(format!(
+            "{} must be a date in YYYY-MM-DD format",
+            parameter
+        ))),
+    )
+        .into_response()
+}
+
+/// Get recorded activity range and data freshness for each source
+#[utoipa::path(
+    get,
+    path = "/api/v1/sources",
+    responses(
+        (status = 200, description = "Data range and freshness of every source retrieved successfully", body = Vec<SourceDataRange>),
+        (status = 401, description = "Unauthorized - invalid or missing API key")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "sources"
+)]
+async fn get_sources_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+) -> Json<Vec<SourceDataRange>> {
+    Json(config.sources.faith_stats().build().data_ranges())
+}
+
+/// Download a zip archive containing JSON dumps of all derived statistics
+#[utoipa::path(
+    get,
+    path = "/api/v1/export/all",
+    responses(
+        (status = 200, description = "Export archive generated successfully", content_type = "application/zip", body = Vec<u8>),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "export"
+)]
+async fn get_export_all_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+) -> Result<Response, AppError> {
+    let mut archive = Cursor::new(Vec::new());
+    write_export_archive(&mut archive, &config.sources.faith_stats().build())?;
+
+    let filename = format!("lifestats-export-{}.zip", statsutils::get_today_date()?);
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        archive.into_inner(),
+    )
+        .into_response())
+}
+
+/// Query parameters for the line protocol export
+#[derive(Debug, Deserialize, IntoParams)]
+struct LineProtocolQuery {
+    /// Number of days to include, ending with today (default 30)
+    days: Option<i32>,
+}
+
+/// Export daily faith metrics in InfluxDB line protocol
+///
+/// One `faith_daily` point per day, tagged with the user and timestamped at local midnight
+/// in nanoseconds, so the response can be posted to an InfluxDB write endpoint as is.
+#[utoipa::path(
+    get,
+    path = "/api/v1/export/lineprotocol",
+    params(LineProtocolQuery),
+    responses(
+        (status = 200, description = "Line protocol generated successfully", content_type = "text/plain", body = String),
+        (status = 400, description = "Invalid number of days", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "export"
+)]
+async fn get_line_protocol_export_endpoint(
+    axum::Extension(user): axum::Extension<UserProfile>,
+    axum::extract::Query(query): axum::extract::Query<LineProtocolQuery>,
+) -> Result<Response, AppError> {
+    let days = query.days.unwrap_or(30);
+    if let Some(response) = invalid_days_response(days) {
+        return Ok(response);
+    }
+
+    let stats = user.config.faith_daily_stats(days)?;
+    let lines = influx::daily_line_protocol(&user.id, &stats.days)?;
+    Ok(([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], lines).into_response())
+}
+
+/// Get the public key to subscribe to push notifications with
+#[utoipa::path(
+    get,
+    path = "/api/v1/push/vapid-public-key",
+    responses(
+        (status = 200, description = "Public key retrieved successfully", body = VapidPublicKey),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 404, description = "Push notifications are not configured")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "push"
+)]
+async fn get_vapid_public_key_endpoint(
+    axum::extract::State(push): axum::extract::State<PushService>,
+) -> Response {
+    match push.public_key() {
+        Some(public_key) => Json(VapidPublicKey {
+            public_key: public_key.to_string(),
+        })
+        .into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// Subscribe a browser to the user's goal reminders
+///
+/// The body is the browser's `PushSubscription` as JSON. Subscribing the same endpoint again
+/// replaces its keys.
+#[utoipa::path(
+    post,
+    path = "/api/v1/push/subscriptions",
+    request_body = PushSubscription,
+    responses(
+        (status = 204, description = "Subscription stored"),
+        (status = 400, description = "Invalid subscription keys, an endpoint that is not a public HTTPS URL, or too many subscriptions", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 404, description = "Push notifications are not configured"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "push"
+)]
+async fn subscribe_push_endpoint(
+    axum::Extension(user): axum::Extension<UserProfile>,
+    axum::extract::State(push): axum::extract::State<PushService>,
+    Json(subscription): Json<PushSubscription>,
+) -> Result<Response, AppError> {
+    if push.public_key().is_none() {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    }
+    if let Err(error) = push.validate(&user.id, &subscription) {
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(format!("{:#}", error))),
+        )
+            .into_response());
+    }
+    push.subscribe(&user.id, subscription)?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+/// Unsubscribe a browser from the user's goal reminders
+#[utoipa::path(
+    delete,
+    path = "/api/v1/push/subscriptions",
+    request_body = Unsubscribe,
+    responses(
+        (status = 204, description = "Subscription removed"),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 404, description = "No such subscription"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "push"
+)]
+async fn unsubscribe_push_endpoint(
+    axum::Extension(user): axum::Extension<UserProfile>,
+    axum::extract::State(push): axum::extract::State<PushService>,
+    Json(unsubscribe): Json<Unsubscribe>,
+) -> Result<StatusCode, AppError> {
+    if push.unsubscribe(&user.id, &unsubscribe.endpoint)? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Ok(StatusCode::NOT_FOUND)
+    }
+}
+
+/// Re-read every user's data sources now and report how long each source took
+///
+/// Meant to be called right after syncing new data: updates the staleness state behind
+/// `/health` and `/api/v1/health` (notifying newly stale sources) without waiting for the next
+/// scheduled check.
+/// Requires ADMIN_API_KEY as the Bearer token; user API keys are rejected.
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/refresh",
+    responses(
+        (status = 200, description = "Sources re-read successfully", body = RefreshReport),
+        (status = 401, description = "Unauthorized - invalid or missing admin API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "admin"
+)]
+async fn refresh_sources_endpoint(
+    axum::extract::State(users): axum::extract::State<Users>,
+    axum::extract::State(staleness): axum::extract::State<StalenessState>,
+) -> Result<Json<RefreshReport>, AppError> {
+    Ok(Json(
+        admin::refresh_all(users.profiles(), &staleness).await?,
+    ))
+}
+
+/// Send a test notification to check the notification channels
+///
+/// Without a body or `event`, a `test` notification is delivered to every channel (or only
+/// `channel`), ignoring event filters and quiet hours. With `event`, it is routed like a real
+/// event of that type. Requires ADMIN_API_KEY as the Bearer token.
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/notifications/test",
+    request_body(content = Option<TestNotification>, description = "Channel, event type and user to test (all optional)"),
+    responses(
+        (status = 200, description = "Outcome per channel", body = Vec<ChannelDelivery>),
+        (status = 401, description = "Unauthorized - invalid or missing admin API key"),
+        (status = 404, description = "Unknown channel or user", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "admin"
+)]
+async fn test_notification_endpoint(
+    axum::extract::State(users): axum::extract::State<Users>,
+    axum::extract::State(notifier): axum::extract::State<Notifier>,
+    request: Option<Json<TestNotification>>,
+) -> Response {
+    let request = request.map(|Json(request)| request).unwrap_or_default();
+    if let Some(user_id) = &request.user
+        && !users.profiles().iter().any(|user| &user.id == user_id)
+    {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new(format!("Unknown user: {}", user_id))),
+        )
+            .into_response();
+    }
+
+    let channel = request.channel.clone();
+    match notifier.test(request).await {
+        Some(deliveries) => Json(deliveries).into_response(),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new(format!(
+                "Unknown notification channel: {}",
+                channel.unwrap_or_default()
+            ))),
+        )
+            .into_response(),
+    }
+}
+
+/// Opens the stats store of a user for the exemption endpoints, or returns a 404 response
+/// for unknown users and users without a stats store
+fn exemption_store(users: &Users, user_id: &str) -> Result<Result<Store, Response>, AppError> {
+    let Some(user) = users.profiles().into_iter().find(|user| user.id == user_id) else {
+        return Ok(Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new(format!("Unknown user: {}", user_id))),
+        )
+            .into_response()));
+    };
+    let Some(store_path) = &user.config.stats_store_path else {
+        return Ok(Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new(format!(
+                "User '{}' has no stats store to keep exemptions in",
+                user_id
+            ))),
+        )
+            .into_response()));
+    };
+    Ok(Ok(Store::open(store_path)?))
+}
+
+/// Query parameters for listing exemptions
+#[derive(Debug, Deserialize, IntoParams)]
+struct ExemptionsQuery {
+    /// Id of the user
+    user: String,
+}
+
+/// List a user's exempt date ranges
+///
+/// Requires ADMIN_API_KEY as the Bearer token.
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/exemptions",
+    params(ExemptionsQuery),
+    responses(
+        (status = 200, description = "Exempt date ranges, ordered by start date", body = Vec<Exemption>),
+        (status = 401, description = "Unauthorized - invalid or missing admin API key"),
+        (status = 404, description = "Unknown user or user without a stats store", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "admin"
+)]
+async fn list_exemptions_endpoint(
+    axum::extract::State(users): axum::extract::State<Users>,
+    axum::extract::Query(query): axum::extract::Query<ExemptionsQuery>,
+) -> Result<Response, AppError> {
+    let store = match exemption_store(&users, &query.user)? {
+        Ok(store) => store,
+        Err(response) => return Ok(response),
+    };
+    Ok(Json(store.get_exemptions()?).into_response())
+}
+
+/// Exempt a date range (vacation, illness) from a user's streaks, goals and consistency
+///
+/// Exempt days neither break nor extend streaks, weekly goal targets shrink by a seventh for
+/// each exempt day, daily goal reminders are not sent, and consistency metrics leave the days
+/// out. Exemptions are kept in the user's stats store. Requires ADMIN_API_KEY as the Bearer
+/// token.
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/exemptions",
+    request_body = NewExemption,
+    responses(
+        (status = 201, description = "Exemption added", body = Exemption),
+        (status = 400, description = "Invalid date range", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing admin API key"),
+        (status = 404, description = "Unknown user or user without a stats store", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "admin"
+)]
+async fn add_exemption_endpoint(
+    axum::extract::State(users): axum::extract::State<Users>,
+    Json(exemption): Json<NewExemption>,
+) -> Result<Response, AppError> {
+    if let Err(error) = validate_range(&exemption.start, &exemption.end) {
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(format!("{:#}", error))),
+        )
+            .into_response());
+    }
+
+    let store = match exemption_store(&users, &exemption.user)? {
+        Ok(store) => store,
+        Err(response) => return Ok(response),
+    };
+    let added = store.add_exemption(
+        &exemption.start,
+        &exemption.end,
+        exemption.reason.as_deref(),
+    )?;
+    Ok((StatusCode::CREATED, Json(added)).into_response())
+}
+
+/// Remove an exempt date range of a user
+///
+/// Requires ADMIN_API_KEY as the Bearer token.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/exemptions/{user}/{id}",
+    params(
+        ("user" = String, Path, description = "Id of the user"),
+        ("id" = i64, Path, description = "Id of the exemption")
+    ),
+    responses(
+        (status = 204, description = "Exemption removed"),
+        (status = 401, description = "Unauthorized - invalid or missing admin API key"),
+        (status = 404, description = "Unknown user, user without a stats store, or no such exemption"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "admin"
+)]
+async fn delete_exemption_endpoint(
+    axum::extract::State(users): axum::extract::State<Users>,
+    axum::extract::Path((user_id, id)): axum::extract::Path<(String, i64)>,
+) -> Result<Response, AppError> {
+    let store = match exemption_store(&users, &user_id)? {
+        Ok(store) => store,
+        Err(response) => return Ok(response),
+    };
+    if store.delete_exemption(id)? {
+        Ok(StatusCode::NO_CONTENT.into_response())
+    } else {
+        Ok(StatusCode::NOT_FOUND.into_response())
+    }
+}
+
+/// Opens the stats store of the user for the note and tag endpoints, or returns a 404
+/// response without one
+fn user_store(config: &AppConfig) -> Result<Result<Store, Response>, AppError> {
+    let Some(store_path) = &config.stats_store_path else {
+        return Ok(Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new(
+                "No stats store configured to keep notes and tags in".to_string(),
+            )),
+        )
+            .into_response()));
+    };
+    Ok(Ok(Store::open(store_path)?))
+}
+
+/// Like `user_store`, also normalizing the date or returning a 400 response for an invalid one
+fn note_store(
+    config: &AppConfig,
+    date: &str,
+) -> Result<Result<(Store, String), Response>, AppError> {
+    let Ok(date) = NaiveDate::parse_from_str(date, "%Y-%m-%d") else {
+        return Ok(Err(invalid_date_response("date")));
+    };
+    Ok(user_store(config)?.map(|store| (store, date.to_string())))
+}
+
+/// Get the note of a day
+#[utoipa::path(
+    get,
+    path = "/api/v1/notes/{date}",
+    params(
+        ("date" = String, Path, description = "Date in YYYY-MM-DD format")
+    ),
+    responses(
+        (status = 200, description = "Note retrieved successfully", body = DayNote),
+        (status = 400, description = "Invalid date", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 404, description = "No note on the day, or no stats store configured"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "notes"
+)]
+async fn get_note_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+    Path(date): Path<String>,
+) -> Result<Response, AppError> {
+    let (store, date) = match note_store(&config, &date)? {
+        Ok(store) => store,
+        Err(response) => return Ok(response),
+    };
+    match store.get_note(&date)? {
+        Some(note) => Ok(Json(note).into_response()),
+        None => Ok(StatusCode::NOT_FOUND.into_response()),
+    }
+}
+
+/// Set the note of a day
+///
+/// Notes keep qualitative context ("sick", "retreat day", "started Romans") next to the
+/// numbers it explains: the daily stats include the first line of each day's note. Notes are
+/// kept in the user's stats store; setting a note replaces the day's earlier one.
+#[utoipa::path(
+    put,
+    path = "/api/v1/notes/{date}",
+    params(
+        ("date" = String, Path, description = "Date in YYYY-MM-DD format")
+    ),
+    request_body = NoteText,
+    responses(
+        (status = 200, description = "Note stored", body = DayNote),
+        (status = 400, description = "Invalid date, or an empty or too long note", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 404, description = "No stats store configured", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "notes"
+)]
+async fn put_note_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+    Path(date): Path<String>,
+    Json(note): Json<NoteText>,
+) -> Result<Response, AppError> {
+    let (store, date) = match note_store(&config, &date)? {
+        Ok(store) => store,
+        Err(response) => return Ok(response),
+    };
+    if let Err(error) = validate_note(&note.text) {
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(format!("{:#}", error))),
+        )
+            .into_response());
+    }
+    Ok(Json(store.put_note(&date, note.text.trim())?).into_response())
+}
+
+/// Remove the note of a day
+#[utoipa::path(
+    delete,
+    path = "/api/v1/notes/{date}",
+    params(
+        ("date" = String, Path, description = "Date in YYYY-MM-DD format")
+    ),
+    responses(
+        (status = 204, description = "Note removed"),
+        (status = 400, description = "Invalid date", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 404, description = "No note on the day, or no stats store configured"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "notes"
+)]
+async fn delete_note_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+    Path(date): Path<String>,
+) -> Result<Response, AppError> {
+    let (store, date) = match note_store(&config, &date)? {
+        Ok(store) => store,
+        Err(response) => return Ok(response),
+    };
+    if store.delete_note(&date)? {
+        Ok(StatusCode::NO_CONTENT.into_response())
+    } else {
+        Ok(StatusCode::NOT_FOUND.into_response())
+    }
+}
+
+/// List the tags attached to days, with the number of days carrying each
+#[utoipa::path(
+    get,
+    path = "/api/v1/tags",
+    responses(
+        (status = 200, description = "Tags retrieved successfully", body = Vec<TagCount>),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 404, description = "No stats store configured", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "notes"
+)]
+async fn list_tags_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+) -> Result<Response, AppError> {
+    let store = match user_store(&config)? {
+        Ok(store) => store,
+        Err(response) => return Ok(response),
+    };
+    Ok(Json(store.get_tag_counts()?).into_response())
+}
+
+/// 400 response for a tag rejected by `normalize_tag`
+fn invalid_tag_response(error: &anyhow::Error) -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse::new(format!("{:#}", error))),
+    )
+        .into_response()
+}
+
+/// Tag a day
+///
+/// Tags (travel, fast, feast, conference) are kept in the user's stats store. The daily stats
+/// list each day's tags and can be filtered with `?tag=` or `?exclude_tag=`, e.g. to compare
+/// the average reading on travel and non-travel days. Tags are lowercased.
+#[utoipa::path(
+    put,
+    path = "/api/v1/tags/{tag}/{date}",
+    params(
+        ("tag" = String, Path, description = "Tag of letters a-z, digits and '-'"),
+        ("date" = String, Path, description = "Date in YYYY-MM-DD format")
+    ),
+    responses(
+        (status = 201, description = "Tag added", body = DayTag),
+        (status = 200, description = "The day already carried the tag", body = DayTag),
+        (status = 400, description = "Invalid tag or date", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 404, description = "No stats store configured", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "notes"
+)]
+async fn add_tag_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+    Path((tag, date)): Path<(String, String)>,
+) -> Result<Response, AppError> {
+    let tag = match normalize_tag(&tag) {
+        Ok(tag) => tag,
+        Err(error) => return Ok(invalid_tag_response(&error)),
+    };
+    let (store, date) = match note_store(&config, &date)? {
+        Ok(store) => store,
+        Err(response) => return Ok(response),
+    };
+    let status = if store.add_tag(&date, &tag)? {
+        StatusCode::CREATED
+    } else {
+        StatusCode::OK
+    };
+    Ok((status, Json(DayTag { date, tag })).into_response())
+}
+
+/// Remove a tag from a day
+#[utoipa::path(
+    delete,
+    path = "/api/v1/tags/{tag}/{date}",
+    params(
+        ("tag" = String, Path, description = "Tag of letters a-z, digits and '-'"),
+        ("date" = String, Path, description = "Date in YYYY-MM-DD format")
+    ),
+    responses(
+        (status = 204, description = "Tag removed"),
+        (status = 400, description = "Invalid tag or date", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 404, description = "The day does not carry the tag, or no stats store configured"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "notes"
+)]
+async fn remove_tag_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+    Path((tag, date)): Path<(String, String)>,
+) -> Result<Response, AppError> {
+    let tag = match normalize_tag(&tag) {
+        Ok(tag) => tag,
+        Err(error) => return Ok(invalid_tag_response(&error)),
+    };
+    let (store, date) = match note_store(&config, &date)? {
+        Ok(store) => store,
+        Err(response) => return Ok(response),
+    };
+    if store.remove_tag(&date, &tag)? {
+        Ok(StatusCode::NO_CONTENT.into_response())
+    } else {
+        Ok(StatusCode::NOT_FOUND.into_response())
+    }
+}
+
+/// Custom error type for API errors
+///
+/// Reading a source the user has not configured is 503 Service Unavailable; anything else is
+/// 500 Internal Server Error.
+struct AppError(anyhow::Error);
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let status = if self
+            .0
+            .chain()
+            .any(|cause| cause.is::<SourceNotConfiguredError>())
+        {
+            StatusCode::SERVICE_UNAVAILABLE
+        } else {
+            StatusCode::INTERNAL_SERVER_ERROR
+        };
+        (status, Json(ErrorResponse::new(format!("{:#}", self.0)))).into_response()
     }
 }
 
@@ -0,0 +1,314 @@
+use anyhow::{Context, Result, bail};
+use faithstats::models::{FaithDayStats, FaithWeekStats};
+use faithstats::templates::{Value, day_context, render, render_str, week_context};
+use serde::{Deserialize, Serialize};
+use statsutils::format::with_display_format;
+use std::collections::HashSet;
+use std::env;
+use std::time::Duration;
+use utoipa::ToSchema;
+
+use crate::users::UserProfile;
+
+/// Default local hour from which summaries are posted
+const DEFAULT_POST_HOUR: u32 = 21;
+
+/// Weekday of the last day of the week (weeks start on Sunday)
+const LAST_WEEKDAY: u32 = 6;
+
+/// Interval between checks whether a summary is due
+const CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Period a summary post covers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SummaryPeriod {
+    /// Posted every day, covering that day
+    Daily,
+    /// Posted on the last day of the week, covering the week
+    #[default]
+    Weekly,
+}
+
+impl std::str::FromStr for SummaryPeriod {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "daily" => Ok(SummaryPeriod::Daily),
+            "weekly" => Ok(SummaryPeriod::Weekly),
+            _ => bail!("Unknown summary period '{}' (expected daily or weekly)", s),
+        }
+    }
+}
+
+/// Who can see the posts
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Visibility {
+    Public,
+    #[default]
+    Unlisted,
+    Private,
+    Direct,
+}
+
+impl std::str::FromStr for Visibility {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "public" => Ok(Visibility::Public),
+            "unlisted" => Ok(Visibility::Unlisted),
+            "private" => Ok(Visibility::Private),
+            "direct" => Ok(Visibility::Direct),
+            _ => bail!(
+                "Unknown visibility '{}' (expected public, unlisted, private or direct)",
+                s
+            ),
+        }
+    }
+}
+
+/// Mastodon account that receives a user's summary posts
+#[derive(Clone, Deserialize)]
+pub struct MastodonConfig {
+    /// Base URL of the instance (e.g. "https://mastodon.social")
+    pub instance_url: String,
+    /// Access token of an application with the `write:statuses` scope
+    pub access_token: String,
+    #[serde(default)]
+    pub period: SummaryPeriod,
+    /// Post text as a minijinja template with the context of `faithstats::templates::day_context`
+    /// or `week_context`; defaults to the `social_daily.txt`/`social_weekly.txt` template
+    pub template: Option<String>,
+    #[serde(default)]
+    pub visibility: Visibility,
+    /// Log the posts instead of publishing them
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+impl MastodonConfig {
+    /// Reads the default user's account from MASTODON_INSTANCE_URL, MASTODON_ACCESS_TOKEN,
+    /// MASTODON_PERIOD, MASTODON_TEMPLATE, MASTODON_VISIBILITY and MASTODON_DRY_RUN
+    pub fn from_env() -> Result<Option<Self>> {
+        let (Ok(instance_url), Ok(access_token)) = (
+            env::var("MASTODON_INSTANCE_URL"),
+            env::var("MASTODON_ACCESS_TOKEN"),
+        ) else {
+            return Ok(None);
+        };
+
+        Ok(Some(Self {
+            instance_url,
+            access_token,
+            period: match env::var("MASTODON_PERIOD") {
+                Ok(period) => period.parse().context("Invalid MASTODON_PERIOD")?,
+                Err(_) => SummaryPeriod::default(),
+            },
+            template: env::var("MASTODON_TEMPLATE").ok(),
+            visibility: match env::var("MASTODON_VISIBILITY") {
+                Ok(visibility) => visibility.parse().context("Invalid MASTODON_VISIBILITY")?,
+                Err(_) => Visibility::default(),
+            },
+            dry_run: env::var("MASTODON_DRY_RUN").is_ok_and(|v| v == "1" || v == "true"),
+        }))
+    }
+
+    /// Validates the template by rendering it for a day or week without activity
+    pub fn validate(&self) -> Result<()> {
+        let context = match self.period {
+            SummaryPeriod::Daily => day_context(&FaithDayStats::default()),
+            SummaryPeriod::Weekly => week_context(&FaithWeekStats::default()),
+        };
+        self.render(context)?;
+        Ok(())
+    }
+
+    /// Renders the post text with the configured template, or the template of the period
+    fn render(&self, context: Value) -> Result<String> {
+        match &self.template {
+            Some(template) => render_str(template, context),
+            None => render(template_name(self.period), context),
+        }
+    }
+}
+
+/// Name of the built-in template of a period
+fn template_name(period: SummaryPeriod) -> &'static str {
+    match period {
+        SummaryPeriod::Daily => "social_daily.txt",
+        SummaryPeriod::Weekly => "social_weekly.txt",
+    }
+}
+
+/// Composed summary post
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SummaryPost {
+    pub period: SummaryPeriod,
+    /// Post text
+    pub text: String,
+}
+
+/// Composes a user's summary post for today or the current week
+///
+/// Uses the user's Mastodon template when its period matches, and the period's template
+/// otherwise.
+pub fn compose(user: &UserProfile, period: SummaryPeriod) -> Result<SummaryPost> {
+    let context = match period {
+        SummaryPeriod::Daily => {
+            let stats = user.config.faith_daily_stats(1)?;
+            day_context(stats.days.last().context("No stats for today")?)
+        }
+        SummaryPeriod::Weekly => {
+            let stats = user.config.faith_weekly_stats()?;
+            week_context(stats.weeks.last().context("No stats for this week")?)
+        }
+    };
+    let text = with_display_format(user.config.display_format, || match &user.mastodon {
+        Some(mastodon) if mastodon.period == period => mastodon.render(context),
+        _ => render(template_name(period), context),
+    })?;
+
+    Ok(SummaryPost { period, text })
+}
+
+/// Spawns a background task that posts each user's summary to their Mastodon account
+///
+/// Daily summaries are posted every day and weekly ones on the last day of the week, from
+/// MASTODON_POST_HOUR (local time, default 21) on. Each summary is posted at most once while
+/// the server is running. Does nothing when no user has a Mastodon account configured.
+pub fn spawn_publisher(users: Vec<UserProfile>) -> Result<()> {
+    let users: Vec<UserProfile> = users
+        .into_iter()
+        .filter(|user| user.mastodon.is_some())
+        .collect();
+
+    if users.is_empty() {
+        return Ok(());
+    }
+
+    let post_hour = match env::var("MASTODON_POST_HOUR") {
+        Ok(hour) => hour.parse().context("Invalid MASTODON_POST_HOUR")?,
+        Err(_) => DEFAULT_POST_HOUR,
+    };
+    if post_hour > 23 {
+        bail!("MASTODON_POST_HOUR must be between 0 and 23");
+    }
+
+    tokio::spawn(async move {
+        let client = reqwest::Client::new();
+        let mut posted = HashSet::new();
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let now_ms = chrono::Utc::now().timestamp_millis();
+            let last_day_of_week = match statsutils::timestamp_ms_to_hour_and_weekday(now_ms) {
+                Ok((hour, weekday)) if hour >= post_hour => weekday == LAST_WEEKDAY,
+                Ok(_) => continue,
+                Err(e) => {
+                    eprintln!("Error: {:#}", e);
+                    continue;
+                }
+            };
+
+            for user in &users {
+                let due = match user.mastodon.as_ref().map(|mastodon| mastodon.period) {
+                    Some(SummaryPeriod::Daily) => true,
+                    Some(SummaryPeriod::Weekly) => last_day_of_week,
+                    None => false,
+                };
+                if !due {
+                    continue;
+                }
+                if let Err(e) = post_user_summary(&client, user, &mut posted).await {
+                    eprintln!("Mastodon post for user '{}' failed: {:#}", user.id, e);
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Posts the user's summary unless it was already posted today
+///
+/// `posted` holds (user id, date) of summaries already posted.
+async fn post_user_summary(
+    client: &reqwest::Client,
+    user: &UserProfile,
+    posted: &mut HashSet<(String, String)>,
+) -> Result<()> {
+    let Some(mastodon) = &user.mastodon else {
+        return Ok(());
+    };
+
+    let config = user.config.clone();
+    let stats = tokio::task::spawn_blocking(move || config.faith_daily_stats(1)).await??;
+    let Some(today) = stats.days.last() else {
+        return Ok(());
+    };
+
+    let key = (user.id.clone(), today.date.clone());
+    if posted.contains(&key) {
+        return Ok(());
+    }
+
+    let context = match mastodon.period {
+        SummaryPeriod::Daily => day_context(today),
+        SummaryPeriod::Weekly => {
+            let config = user.config.clone();
+            let stats = tokio::task::spawn_blocking(move || config.faith_weekly_stats()).await??;
+            let Some(week) = stats.weeks.last() else {
+                return Ok(());
+            };
+            week_context(week)
+        }
+    };
+    let text = with_display_format(user.config.display_format, || mastodon.render(context))?;
+
+    if mastodon.dry_run {
+        println!("Mastodon dry run for user '{}': {}", user.id, text);
+    } else {
+        post_status(
+            client,
+            mastodon,
+            &text,
+            &format!("{}-{}", user.id, today.date),
+        )
+        .await?;
+    }
+
+    posted.insert(key);
+    Ok(())
+}
+
+/// Publishes a status; the idempotency key keeps retries from posting it twice
+async fn post_status(
+    client: &reqwest::Client,
+    mastodon: &MastodonConfig,
+    text: &str,
+    idempotency_key: &str,
+) -> Result<()> {
+    client
+        .post(format!(
+            "{}/api/v1/statuses",
+            mastodon.instance_url.trim_end_matches('/')
+        ))
+        .bearer_auth(&mastodon.access_token)
+        .header("Idempotency-Key", format!("life-stats-{}", idempotency_key))
+        .json(&serde_json::json!({
+            "status": text,
+            "visibility": mastodon.visibility,
+        }))
+        .send()
+        .await
+        .context("Failed to post to Mastodon")?
+        .error_for_status()
+        .context("Mastodon returned an error status")?;
+
+    Ok(())
+}
@@ -0,0 +1,180 @@
+use anyhow::{Context, Result, bail};
+use faithstats::models::{FaithStreaks, FaithTodayStats};
+use rumqttc::{AsyncClient, MqttOptions, QoS};
+use std::env;
+use std::time::Duration;
+
+use crate::STREAK_WINDOW_DAYS;
+use crate::users::UserProfile;
+
+/// Default MQTT broker port
+const DEFAULT_PORT: u16 = 1883;
+
+/// Default prefix of all published topics
+const DEFAULT_TOPIC_PREFIX: &str = "lifestats";
+
+/// Default interval between publishes
+const DEFAULT_PUBLISH_INTERVAL_MINUTES: u64 = 5;
+
+/// Broker connection settings read from `MQTT_*` environment variables
+struct MqttConfig {
+    host: String,
+    port: u16,
+    credentials: Option<(String, String)>,
+    topic_prefix: String,
+    interval_minutes: u64,
+}
+
+impl MqttConfig {
+    /// Reads the MQTT configuration; returns `None` when MQTT_HOST is not set
+    fn from_env() -> Result<Option<Self>> {
+        let Ok(host) = env::var("MQTT_HOST") else {
+            return Ok(None);
+        };
+
+        let port = match env::var("MQTT_PORT") {
+            Ok(port) => port.parse().context("Invalid MQTT_PORT")?,
+            Err(_) => DEFAULT_PORT,
+        };
+        let credentials = match (env::var("MQTT_USERNAME"), env::var("MQTT_PASSWORD")) {
+            (Ok(username), Ok(password)) => Some((username, password)),
+            (Ok(username), Err(_)) => Some((username, String::new())),
+            _ => None,
+        };
+        let interval_minutes = match env::var("MQTT_PUBLISH_INTERVAL_MINUTES") {
+            Ok(minutes) => minutes
+                .parse()
+                .context("Invalid MQTT_PUBLISH_INTERVAL_MINUTES")?,
+            Err(_) => DEFAULT_PUBLISH_INTERVAL_MINUTES,
+        };
+        if interval_minutes < 1 {
+            bail!("MQTT_PUBLISH_INTERVAL_MINUTES must be at least 1");
+        }
+
+        Ok(Some(Self {
+            host,
+            port,
+            credentials,
+            topic_prefix: env::var("MQTT_TOPIC_PREFIX")
+                .unwrap_or_else(|_| DEFAULT_TOPIC_PREFIX.to_string()),
+            interval_minutes,
+        }))
+    }
+}
+
+/// Spawns a background task that periodically publishes every user's minutes for today
+/// and current streaks to the MQTT broker configured via MQTT_HOST
+///
+/// Values are published as retained messages so that subscribers such as Home Assistant
+/// see the latest state right after connecting:
+///
+/// - `{prefix}/{user}/today/{anki,reading,prayer,total}_minutes`
+/// - `{prefix}/{user}/streak/{any_activity,anki,reading,prayer}_days`
+///
+/// Does nothing when MQTT_HOST is not set.
+pub fn spawn_publisher(users: Vec<UserProfile>) -> Result<()> {
+    let Some(config) = MqttConfig::from_env()? else {
+        return Ok(());
+    };
+
+    let client = connect_with(&config, "life-stats");
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(config.interval_minutes * 60));
+
+        loop {
+            interval.tick().await;
+
+            for user in &users {
+                if let Err(e) = publish_user(&client, &config.topic_prefix, user).await {
+                    eprintln!("MQTT publish for user '{}' failed: {:#}", user.id, e);
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Connects to the broker configured via MQTT_HOST under the given client id
+///
+/// Returns the client and the topic prefix, or `None` when MQTT_HOST is not set.
+pub fn connect(client_id: &str) -> Result<Option<(AsyncClient, String)>> {
+    let Some(config) = MqttConfig::from_env()? else {
+        return Ok(None);
+    };
+    Ok(Some((
+        connect_with(&config, client_id),
+        config.topic_prefix,
+    )))
+}
+
+/// Creates a client and spawns the task driving its connection
+fn connect_with(config: &MqttConfig, client_id: &str) -> AsyncClient {
+    let mut options = MqttOptions::new(client_id, &config.host, config.port);
+    options.set_keep_alive(Duration::from_secs(30));
+    if let Some((username, password)) = &config.credentials {
+        options.set_credentials(username, password);
+    }
+
+    let (client, mut event_loop) = AsyncClient::new(options, 100);
+
+    // The event loop drives the connection (including reconnects) and must be polled
+    // continuously for published messages to be sent
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = event_loop.poll().await {
+                eprintln!("MQTT connection error: {}", e);
+                tokio::time::sleep(Duration::from_secs(10)).await;
+            }
+        }
+    });
+
+    client
+}
+
+/// Publishes a single user's minutes for today and current streaks
+async fn publish_user(client: &AsyncClient, prefix: &str, user: &UserProfile) -> Result<()> {
+    let config = user.config.clone();
+    let (today, streaks) = tokio::task::spawn_blocking(move || {
+        let stats = config.faith_daily_stats(STREAK_WINDOW_DAYS)?;
+        let today = match stats.days.last() {
+            Some(day) => FaithTodayStats::new(
+                day.anki_minutes.unwrap_or(0.0),
+                day.reading_minutes.unwrap_or(0.0),
+                day.prayer_minutes.unwrap_or(0.0),
+            ),
+            None => FaithTodayStats::new(0.0, 0.0, 0.0),
+        };
+        anyhow::Ok((today, FaithStreaks::from_days(&stats.days)))
+    })
+    .await??;
+
+    let values = [
+        ("today/anki_minutes", today.anki_minutes.to_string()),
+        ("today/reading_minutes", today.reading_minutes.to_string()),
+        ("today/prayer_minutes", today.prayer_minutes.to_string()),
+        ("today/total_minutes", today.total_minutes.to_string()),
+        (
+            "streak/any_activity_days",
+            streaks.any_activity_days.to_string(),
+        ),
+        ("streak/anki_days", streaks.anki_days.to_string()),
+        ("streak/reading_days", streaks.reading_days.to_string()),
+        ("streak/prayer_days", streaks.prayer_days.to_string()),
+    ];
+
+    for (topic, payload) in values {
+        client
+            .publish(
+                format!("{}/{}/{}", prefix, user.id, topic),
+                QoS::AtLeastOnce,
+                true,
+                payload,
+            )
+            .await
+            .context("Failed to queue MQTT message")?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,768 @@
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::env;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use utoipa::ToSchema;
+
+use crate::push::{PushMessage, PushService};
+
+/// Sendmail binary used by email channels that don't configure one
+const DEFAULT_SENDMAIL_PATH: &str = "/usr/sbin/sendmail";
+
+/// Type of event a notification is about, used to route it to channels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    /// A data source has not been updated within its staleness threshold
+    StaleData,
+    /// A daily goal was met
+    GoalMet,
+    /// Evening reminder of the daily goals not yet met
+    GoalReminder,
+    /// Sent by the test endpoint
+    Test,
+}
+
+impl EventKind {
+    /// Name of the event type as used in the config file and payloads
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::StaleData => "stale_data",
+            EventKind::GoalMet => "goal_met",
+            EventKind::GoalReminder => "goal_reminder",
+            EventKind::Test => "test",
+        }
+    }
+}
+
+/// Notification payload posted to JSON webhooks and published to MQTT
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    /// Machine-readable event type
+    pub event: EventKind,
+    /// User the notification is about; push channels only notify this user's devices
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    /// Short summary, used as the email subject and push notification title
+    pub title: String,
+    /// Human-readable message
+    pub message: String,
+}
+
+/// Local hours during which a channel receives no notifications
+///
+/// `start` is inclusive and `end` exclusive; a range like 22 to 7 wraps past midnight.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct QuietHours {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl QuietHours {
+    /// Whether the given local hour (0-23) falls within the quiet hours
+    pub fn contains(&self, hour: u32) -> bool {
+        if self.start <= self.end {
+            (self.start..self.end).contains(&hour)
+        } else {
+            hour >= self.start || hour < self.end
+        }
+    }
+}
+
+/// Body format of a webhook channel
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookFormat {
+    /// The `Notification` as JSON
+    #[default]
+    Json,
+    /// `{"content": ...}` as expected by Discord webhooks
+    Discord,
+    /// `{"text": ...}` as expected by Slack incoming webhooks
+    Slack,
+}
+
+/// Where a channel delivers notifications
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChannelTarget {
+    /// HTTP POST to a URL
+    Webhook {
+        url: String,
+        #[serde(default)]
+        format: WebhookFormat,
+    },
+    /// Plain text email handed to the local sendmail binary
+    Email {
+        to: String,
+        from: Option<String>,
+        sendmail_path: Option<String>,
+    },
+    /// Web Push to the devices the user subscribed (see VAPID_PUBLIC_KEY)
+    Push,
+    /// JSON message published to `{topic}/{event}` on the broker configured via MQTT_HOST
+    Mqtt {
+        #[cfg_attr(not(feature = "mqtt"), allow(dead_code))]
+        topic: Option<String>,
+    },
+}
+
+/// A notification channel of the notify config file
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChannelConfig {
+    /// Unique name, used in logs and by the test endpoint
+    pub name: String,
+    #[serde(flatten)]
+    pub target: ChannelTarget,
+    /// Event types delivered to the channel (default: all)
+    #[serde(default)]
+    pub events: Vec<EventKind>,
+    pub quiet_hours: Option<QuietHours>,
+}
+
+impl ChannelConfig {
+    /// Whether events of the given type are routed to the channel
+    fn routes(&self, event: EventKind) -> bool {
+        self.events.is_empty() || self.events.contains(&event)
+    }
+}
+
+/// Notify config file at NOTIFY_CONFIG_PATH
+#[derive(Deserialize)]
+struct NotifyFile {
+    #[serde(default)]
+    channels: Vec<ChannelConfig>,
+}
+
+/// Outcome of a notification for one channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    Sent,
+    /// The channel does not receive events of this type
+    NotRouted,
+    /// The channel is in its quiet hours
+    QuietHours,
+    Failed,
+}
+
+/// Result of delivering a notification to one channel
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ChannelDelivery {
+    pub channel: String,
+    pub status: DeliveryStatus,
+    /// Error message if delivery failed
+    pub error: Option<String>,
+}
+
+/// Request body of the notification test endpoint
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct TestNotification {
+    /// Only notify this channel (default: all channels)
+    pub channel: Option<String>,
+    /// Route the test like an event of this type, honoring event filters and quiet hours
+    /// (default: deliver to every channel)
+    pub event: Option<EventKind>,
+    /// User whose devices push channels notify (default: all subscribed users)
+    pub user: Option<String>,
+}
+
+/// Routes notifications to the configured channels
+///
+/// Channels are read from the TOML file at NOTIFY_CONFIG_PATH (see `notify.example.toml`).
+/// Without it, NOTIFY_WEBHOOK_URL receives stale data notifications as JSON and, when Web
+/// Push is configured, daily goal reminders are pushed.
+#[derive(Clone)]
+pub struct Notifier {
+    client: reqwest::Client,
+    channels: Arc<Vec<ChannelConfig>>,
+    push: PushService,
+    /// Client and topic prefix of the MQTT broker, if any channel publishes to it
+    #[cfg(feature = "mqtt")]
+    mqtt: Option<(rumqttc::AsyncClient, String)>,
+}
+
+impl Notifier {
+    /// Loads the channels from NOTIFY_CONFIG_PATH, or the defaults without it
+    pub fn from_env(push: PushService) -> Result<Self> {
+        let channels = match env::var("NOTIFY_CONFIG_PATH") {
+            Ok(path) => load_channels(&path)?,
+            Err(_) => default_channels(&push),
+        };
+        validate_channels(&channels, &push)?;
+
+        #[cfg(feature = "mqtt")]
+        let mqtt = if channels
+            .iter()
+            .any(|channel| matches!(channel.target, ChannelTarget::Mqtt { .. }))
+        {
+            crate::mqtt::connect("life-stats-notify")?
+        } else {
+            None
+        };
+
+        Ok(Self {
+            client: reqwest::Client::new(),
+            channels: Arc::new(channels),
+            push,
+            #[cfg(feature = "mqtt")]
+            mqtt,
+        })
+    }
+
+    /// Whether any channel receives events of the given type
+    pub fn routes(&self, event: EventKind) -> bool {
+        self.channels.iter().any(|channel| channel.routes(event))
+    }
+
+    /// Sends a notification to every channel routing its event type and not in quiet hours;
+    /// failures are logged
+    pub async fn send(&self, notification: &Notification) {
+        for delivery in self.dispatch(notification, None, true).await {
+            if let Some(error) = delivery.error {
+                eprintln!(
+                    "Notification to channel '{}' failed: {}",
+                    delivery.channel, error
+                );
+            }
+        }
+    }
+
+    /// Sends a test notification and reports the outcome per channel
+    ///
+    /// With an event type, the notification is routed like a real event of that type,
+    /// including quiet hours. Without one, a `test` event is sent to every channel. Returns
+    /// `None` if `channel` does not name a configured channel.
+    pub async fn test(&self, request: TestNotification) -> Option<Vec<ChannelDelivery>> {
+        let TestNotification {
+            channel,
+            event,
+            user,
+        } = request;
+        if let Some(name) = &channel
+            && !self.channels.iter().any(|channel| &channel.name == name)
+        {
+            return None;
+        }
+
+        let notification = Notification {
+            event: event.unwrap_or(EventKind::Test),
+            user,
+            title: "Test notification".to_string(),
+            message: match event {
+                Some(event) => format!("Test of the {} notification routing", event.as_str()),
+                None => "Notifications reach this channel".to_string(),
+            },
+        };
+        Some(
+            self.dispatch(&notification, channel.as_deref(), event.is_some())
+                .await,
+        )
+    }
+
+    /// Delivers a notification to all channels, or only the named one
+    ///
+    /// `routed` applies each channel's event filter and quiet hours.
+    async fn dispatch(
+        &self,
+        notification: &Notification,
+        only: Option<&str>,
+        routed: bool,
+    ) -> Vec<ChannelDelivery> {
+        let hour = if routed { local_hour() } else { None };
+        let mut deliveries = Vec::new();
+
+        for channel in self.channels.iter() {
+            if only.is_some_and(|name| name != channel.name) {
+                continue;
+            }
+
+            let status = if routed && !channel.routes(notification.event) {
+                Err(DeliveryStatus::NotRouted)
+            } else if let (Some(quiet_hours), Some(hour)) = (channel.quiet_hours, hour)
+                && quiet_hours.contains(hour)
+            {
+                Err(DeliveryStatus::QuietHours)
+            } else {
+                Ok(())
+            };
+
+            let (status, error) = match status {
+                Ok(()) => match self.deliver(&channel.target, notification).await {
+                    Ok(()) => (DeliveryStatus::Sent, None),
+                    Err(e) => (DeliveryStatus::Failed, Some(format!("{:#}", e))),
+                },
+                Err(status) => (status, None),
+            };
+            deliveries.push(ChannelDelivery {
+                channel: channel.name.clone(),
+                status,
+                error,
+            });
+        }
+
+        deliveries
+    }
+
+    /// Delivers a notification to a single channel target
+    async fn deliver(&self, target: &ChannelTarget, notification: &Notification) -> Result<()> {
+        match target {
+            ChannelTarget::Webhook { url, format } => {
+                let request = self.client.post(url);
+                let request = match format {
+                    WebhookFormat::Json => request.json(notification),
+                    WebhookFormat::Discord => request.json(&serde_json::json!({
+                        "content": format!("**{}**\n{}", notification.title, notification.message)
+                    })),
+                    WebhookFormat::Slack => request.json(&serde_json::json!({
+                        "text": format!("*{}*\n{}", notification.title, notification.message)
+                    })),
+                };
+                request
+                    .send()
+                    .await
+                    .context("Failed to send webhook notification")?
+                    .error_for_status()
+                    .context("Webhook returned an error status")?;
+                Ok(())
+            }
+            ChannelTarget::Email {
+                to,
+                from,
+                sendmail_path,
+            } => send_email(to, from.as_deref(), sendmail_path.as_deref(), notification).await,
+            ChannelTarget::Push => {
+                let message = PushMessage {
+                    title: notification.title.clone(),
+                    body: notification.message.clone(),
+                    tag: notification.event.as_str().to_string(),
+                };
+                let delivered = match &notification.user {
+                    Some(user_id) => self.push.send(user_id, &message).await?,
+                    None => self.push.send_to_all(&message).await?,
+                };
+                if delivered == 0 {
+                    bail!("No push subscription accepted the message");
+                }
+                Ok(())
+            }
+            #[cfg(feature = "mqtt")]
+            ChannelTarget::Mqtt { topic } => {
+                let (client, prefix) = self
+                    .mqtt
+                    .as_ref()
+                    .context("MQTT broker is not configured")?;
+                let topic = match topic {
+                    Some(topic) => topic.clone(),
+                    None => format!("{}/notifications", prefix),
+                };
+                client
+                    .publish(
+                        format!("{}/{}", topic, notification.event.as_str()),
+                        rumqttc::QoS::AtLeastOnce,
+                        false,
+                        serde_json::to_vec(notification)?,
+                    )
+                    .await
+                    .context("Failed to queue MQTT notification")
+            }
+            #[cfg(not(feature = "mqtt"))]
+            ChannelTarget::Mqtt { .. } => bail!("Built without the mqtt feature"),
+        }
+    }
+}
+
+/// Current local hour, or `None` (logged) if it cannot be determined
+fn local_hour() -> Option<u32> {
+    match statsutils::timestamp_ms_to_hour_and_weekday(chrono::Utc::now().timestamp_millis()) {
+        Ok((hour, _)) => Some(hour),
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            None
+        }
+    }
+}
+
+/// Pipes a plain text email to sendmail, which reads the recipients from the headers
+async fn send_email(
+    to: &str,
+    from: Option<&str>,
+    sendmail_path: Option<&str>,
+    notification: &Notification,
+) -> Result<()> {
+    let sendmail_path = sendmail_path.unwrap_or(DEFAULT_SENDMAIL_PATH);
+    let mut email = format!("To: {}\r\n", to);
+    if let Some(from) = from {
+        email.push_str(&format!("From: {}\r\n", from));
+    }
+    email.push_str(&format!(
+        "Subject: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{}\r\n",
+        notification.title, notification.message
+    ));
+
+    let mut child = tokio::process::Command::new(sendmail_path)
+        .args(["-t", "-i"])
+        .stdin(std::process::Stdio::piped())
+        .spawn()
+        .context(format!("Failed to run {}", sendmail_path))?;
+    let mut stdin = child
+        .stdin
+        .take()
+        .context("Failed to open sendmail input")?;
+    stdin
+        .write_all(email.as_bytes())
+        .await
+        .context(format!("Failed to write email to {}", sendmail_path))?;
+    drop(stdin);
+
+    let status = child.wait().await?;
+    if !status.success() {
+        bail!("{} exited with {}", sendmail_path, status);
+    }
+    Ok(())
+}
+
+fn load_channels(path: &str) -> Result<Vec<ChannelConfig>> {
+    let contents =
+        std::fs::read_to_string(path).context(format!("Failed to read notify config {}", path))?;
+    let file: NotifyFile =
+        toml::from_str(&contents).context(format!("Failed to parse notify config {}", path))?;
+    Ok(file.channels)
+}
+
+/// Channels used without a notify config file
+fn default_channels(push: &PushService) -> Vec<ChannelConfig> {
+    let mut channels = Vec::new();
+    if let Ok(url) = env::var("NOTIFY_WEBHOOK_URL") {
+        channels.push(ChannelConfig {
+            name: "webhook".to_string(),
+            target: ChannelTarget::Webhook {
+                url,
+                format: WebhookFormat::Json,
+            },
+            events: vec![EventKind::StaleData],
+            quiet_hours: None,
+        });
+    }
+    if push.public_key().is_some() {
+        channels.push(ChannelConfig {
+            name: "push".to_string(),
+            target: ChannelTarget::Push,
+            events: vec![EventKind::GoalReminder],
+            quiet_hours: None,
+        });
+    }
+    channels
+}
+
+/// Validates channel names, quiet hours and that push and MQTT channels can be delivered
+fn validate_channels(channels: &[ChannelConfig], push: &PushService) -> Result<()> {
+    let mut names = HashSet::new();
+    for channel in channels {
+        if !names.insert(channel.name.as_str()) {
+            bail!("Notification channel '{}' is defined twice", channel.name);
+        }
+        if let Some(quiet_hours) = channel.quiet_hours
+            && (quiet_hours.start > 23 || quiet_hours.end > 23)
+        {
+            bail!(
+                "Quiet hours of notification channel '{}' must be between 0 and 23",
+                channel.name
+            );
+        }
+        match &channel.target {
+            ChannelTarget::Push if push.public_key().is_none() => bail!(
+                "Notification channel '{}' uses push, but VAPID keys are not configured",
+                channel.name
+            ),
+            ChannelTarget::Mqtt { .. } if cfg!(not(feature = "mqtt")) => bail!(
+                "Notification channel '{}' uses MQTT, but the server was built without the mqtt feature",
+                channel.name
+            ),
+            ChannelTarget::Mqtt { .. } if env::var("MQTT_HOST").is_err() => bail!(
+                "Notification channel '{}' uses MQTT, but MQTT_HOST is not set",
+                channel.name
+            ),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::Json;
+    use axum::extract::State;
+    use axum::http::StatusCode;
+    use axum::routing::post;
+    use std::sync::Mutex;
+
+    fn channel(name: &str, target: ChannelTarget, events: Vec<EventKind>) -> ChannelConfig {
+        ChannelConfig {
+            name: name.to_string(),
+            target,
+            events,
+            quiet_hours: None,
+        }
+    }
+
+    fn webhook(url: &str, format: WebhookFormat) -> ChannelTarget {
+        ChannelTarget::Webhook {
+            url: url.to_string(),
+            format,
+        }
+    }
+
+    fn notifier(channels: Vec<ChannelConfig>) -> Notifier {
+        Notifier {
+            client: reqwest::Client::new(),
+            channels: Arc::new(channels),
+            push: PushService::from_env().unwrap(),
+            #[cfg(feature = "mqtt")]
+            mqtt: None,
+        }
+    }
+
+    fn notification(event: EventKind) -> Notification {
+        Notification {
+            event,
+            user: Some("alice".to_string()),
+            title: "Daily goal met".to_string(),
+            message: "20 min reading today".to_string(),
+        }
+    }
+
+    /// Starts a webhook server that records the bodies posted to `/hook` and fails requests
+    /// to `/fail`; returns its base URL
+    async fn webhook_server(received: Arc<Mutex<Vec<serde_json::Value>>>) -> String {
+        async fn hook(
+            State(received): State<Arc<Mutex<Vec<serde_json::Value>>>>,
+            Json(body): Json<serde_json::Value>,
+        ) -> StatusCode {
+            received.lock().unwrap().push(body);
+            StatusCode::NO_CONTENT
+        }
+
+        let app = axum::Router::new()
+            .route("/hook", post(hook))
+            .route(
+                "/fail",
+                post(|| async { StatusCode::INTERNAL_SERVER_ERROR }),
+            )
+            .with_state(received);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", address)
+    }
+
+    #[test]
+    fn test_quiet_hours_within_day() {
+        let quiet_hours = QuietHours { start: 9, end: 17 };
+        assert!(!quiet_hours.contains(8));
+        assert!(quiet_hours.contains(9));
+        assert!(quiet_hours.contains(16));
+        assert!(!quiet_hours.contains(17));
+    }
+
+    #[test]
+    fn test_quiet_hours_across_midnight() {
+        let quiet_hours = QuietHours { start: 22, end: 7 };
+        assert!(!quiet_hours.contains(21));
+        assert!(quiet_hours.contains(22));
+        assert!(quiet_hours.contains(23));
+        assert!(quiet_hours.contains(0));
+        assert!(quiet_hours.contains(6));
+        assert!(!quiet_hours.contains(7));
+        assert!(!quiet_hours.contains(12));
+    }
+
+    #[test]
+    fn test_quiet_hours_empty_range() {
+        let quiet_hours = QuietHours { start: 5, end: 5 };
+        assert!((0..24).all(|hour| !quiet_hours.contains(hour)));
+    }
+
+    #[test]
+    fn test_channel_routes_listed_events() {
+        let all = channel("all", ChannelTarget::Push, Vec::new());
+        let stale = channel("stale", ChannelTarget::Push, vec![EventKind::StaleData]);
+
+        assert!(all.routes(EventKind::GoalMet));
+        assert!(all.routes(EventKind::StaleData));
+        assert!(stale.routes(EventKind::StaleData));
+        assert!(!stale.routes(EventKind::GoalReminder));
+
+        let notifier = notifier(vec![stale]);
+        assert!(notifier.routes(EventKind::StaleData));
+        assert!(!notifier.routes(EventKind::GoalMet));
+    }
+
+    #[test]
+    fn test_load_channels_from_config() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(
+            file.path(),
+            r#"
+            [[channels]]
+            name = "discord"
+            type = "webhook"
+            url = "https://discord.example.com/api/webhooks/1"
+            format = "discord"
+            events = ["stale_data", "goal_met"]
+            quiet_hours = { start = 22, end = 7 }
+
+            [[channels]]
+            name = "mail"
+            type = "email"
+            to = "me@example.com"
+            "#,
+        )
+        .unwrap();
+
+        let channels = load_channels(file.path().to_str().unwrap()).unwrap();
+        assert_eq!(channels.len(), 2);
+        assert!(matches!(
+            channels[0].target,
+            ChannelTarget::Webhook {
+                format: WebhookFormat::Discord,
+                ..
+            }
+        ));
+        assert_eq!(
+            channels[0].events,
+            vec![EventKind::StaleData, EventKind::GoalMet]
+        );
+        assert!(channels[0].quiet_hours.unwrap().contains(23));
+        assert!(matches!(channels[1].target, ChannelTarget::Email { .. }));
+        assert!(channels[1].events.is_empty());
+    }
+
+    #[test]
+    fn test_validate_channels() {
+        let push = PushService::from_env().unwrap();
+        let hook = || webhook("https://example.com/hook", WebhookFormat::Json);
+
+        assert!(validate_channels(&[channel("a", hook(), Vec::new())], &push).is_ok());
+
+        let duplicate = [
+            channel("a", hook(), Vec::new()),
+            channel("a", hook(), Vec::new()),
+        ];
+        assert!(validate_channels(&duplicate, &push).is_err());
+
+        let mut late = channel("a", hook(), Vec::new());
+        late.quiet_hours = Some(QuietHours { start: 22, end: 24 });
+        assert!(validate_channels(&[late], &push).is_err());
+
+        // Push needs VAPID keys, which the tests don't configure
+        let push_channel = channel("push", ChannelTarget::Push, Vec::new());
+        assert!(validate_channels(&[push_channel], &push).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_reports_each_channel() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let base = webhook_server(received.clone()).await;
+        let hook = format!("{}/hook", base);
+        let hour = local_hour().unwrap();
+
+        let mut quiet = channel("quiet", webhook(&hook, WebhookFormat::Json), Vec::new());
+        // Two hours, in case the hour changes during the test
+        quiet.quiet_hours = Some(QuietHours {
+            start: hour,
+            end: (hour + 2) % 24,
+        });
+        let notifier = notifier(vec![
+            channel("json", webhook(&hook, WebhookFormat::Json), Vec::new()),
+            channel(
+                "discord",
+                webhook(&hook, WebhookFormat::Discord),
+                vec![EventKind::GoalMet],
+            ),
+            channel(
+                "stale",
+                webhook(&hook, WebhookFormat::Slack),
+                vec![EventKind::StaleData],
+            ),
+            quiet,
+            channel(
+                "broken",
+                webhook(&format!("{}/fail", base), WebhookFormat::Json),
+                Vec::new(),
+            ),
+        ]);
+
+        let deliveries = notifier
+            .dispatch(&notification(EventKind::GoalMet), None, true)
+            .await;
+        let statuses: Vec<(&str, DeliveryStatus)> = deliveries
+            .iter()
+            .map(|delivery| (delivery.channel.as_str(), delivery.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("json", DeliveryStatus::Sent),
+                ("discord", DeliveryStatus::Sent),
+                ("stale", DeliveryStatus::NotRouted),
+                ("quiet", DeliveryStatus::QuietHours),
+                ("broken", DeliveryStatus::Failed),
+            ]
+        );
+        assert!(deliveries[4].error.is_some());
+
+        let received = received.lock().unwrap().clone();
+        assert_eq!(
+            received,
+            vec![
+                serde_json::json!({
+                    "event": "goal_met",
+                    "user": "alice",
+                    "title": "Daily goal met",
+                    "message": "20 min reading today",
+                }),
+                serde_json::json!({ "content": "**Daily goal met**\n20 min reading today" }),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unrouted_dispatch_ignores_filters() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let hook = format!("{}/hook", webhook_server(received.clone()).await);
+        let mut stale = channel(
+            "stale",
+            webhook(&hook, WebhookFormat::Slack),
+            vec![EventKind::StaleData],
+        );
+        stale.quiet_hours = Some(QuietHours { start: 0, end: 23 });
+        let notifier = notifier(vec![
+            stale,
+            channel("other", webhook(&hook, WebhookFormat::Json), Vec::new()),
+        ]);
+
+        let deliveries = notifier
+            .dispatch(&notification(EventKind::Test), Some("stale"), false)
+            .await;
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].status, DeliveryStatus::Sent);
+        assert_eq!(
+            received.lock().unwrap()[0],
+            serde_json::json!({ "text": "*Daily goal met*\n20 min reading today" })
+        );
+
+        assert!(
+            notifier
+                .test(TestNotification {
+                    channel: Some("missing".to_string()),
+                    ..Default::default()
+                })
+                .await
+                .is_none()
+        );
+    }
+}
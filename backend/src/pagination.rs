@@ -0,0 +1,204 @@
+use axum::{
+    http::{HeaderValue, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use statsmodels::ErrorResponse;
+use utoipa::IntoParams;
+
+use crate::AppError;
+
+/// Response header with the number of items before pagination
+pub const TOTAL_COUNT_HEADER: &str = "X-Total-Count";
+
+/// Pagination and field selection shared by list endpoints
+///
+/// Combined with an endpoint's own query struct as a second `Query` extractor.
+#[derive(Debug, Default, Deserialize, IntoParams)]
+pub struct ListQuery {
+    /// Maximum number of items to return (default: all, unless noted by the endpoint)
+    pub limit: Option<usize>,
+    /// Number of items to skip (default 0)
+    pub offset: Option<usize>,
+    /// Comma-separated names of the fields to include in each item (default: all fields)
+    pub fields: Option<String>,
+}
+
+impl ListQuery {
+    /// Responds with a page of `items` as a JSON array, projected to the selected fields
+    ///
+    /// The total number of items is sent in the `X-Total-Count` header. A `limit` of 0 or an
+    /// unknown field name is answered with 400 Bad Request.
+    pub fn respond<T: Serialize>(&self, items: Vec<T>) -> Result<Response, AppError> {
+        self.respond_with_default_limit(items, None)
+    }
+
+    /// Like `respond`, but returns at most `default_limit` items when no `limit` is given
+    pub fn respond_with_default_limit<T: Serialize>(
+        &self,
+        items: Vec<T>,
+        default_limit: Option<usize>,
+    ) -> Result<Response, AppError> {
+        if self.limit == Some(0) {
+            return Ok(bad_request("limit must be at least 1".to_string()));
+        }
+
+        let total = items.len();
+        let page = items
+            .into_iter()
+            .skip(self.offset.unwrap_or(0))
+            .take(self.limit.or(default_limit).unwrap_or(usize::MAX));
+
+        let body = match self.selected_fields() {
+            Some(fields) => {
+                let mut projected = Vec::new();
+                for item in page {
+                    match project(serde_json::to_value(item)?, &fields) {
+                        Ok(item) => projected.push(item),
+                        Err(field) => {
+                            return Ok(bad_request(format!("Unknown field: {}", field)));
+                        }
+                    }
+                }
+                Value::Array(projected)
+            }
+            None => serde_json::to_value(page.collect::<Vec<T>>())?,
+        };
+
+        let mut response = Json(body).into_response();
+        response
+            .headers_mut()
+            .insert(TOTAL_COUNT_HEADER, HeaderValue::from(total));
+        Ok(response)
+    }
+
+    /// Returns the requested field names, or `None` to include all fields
+    fn selected_fields(&self) -> Option<Vec<&str>> {
+        let fields: Vec<&str> = self
+            .fields
+            .as_deref()?
+            .split(',')
+            .map(str::trim)
+            .filter(|field| !field.is_empty())
+            .collect();
+        (!fields.is_empty()).then_some(fields)
+    }
+}
+
+/// Keeps only the given top-level fields of a serialized item
+///
+/// Returns the first field the item does not have as the error.
+fn project<'a>(item: Value, fields: &[&'a str]) -> Result<Value, &'a str> {
+    let Value::Object(mut object) = item else {
+        return Ok(item);
+    };
+
+    let mut projected = serde_json::Map::new();
+    for field in fields {
+        let value = object.remove(*field).ok_or(*field)?;
+        projected.insert(field.to_string(), value);
+    }
+    Ok(Value::Object(projected))
+}
+
+/// Bad request response with an error message
+fn bad_request(error: String) -> Response {
+    (StatusCode::BAD_REQUEST, Json(ErrorResponse::new(error))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::to_bytes;
+    use serde_json::json;
+
+    #[derive(Serialize)]
+    struct Item {
+        name: &'static str,
+        hours: f64,
+    }
+
+    fn items() -> Vec<Item> {
+        ["a", "b", "c", "d"]
+            .into_iter()
+            .zip(1..)
+            .map(|(name, hours)| Item {
+                name,
+                hours: f64::from(hours),
+            })
+            .collect()
+    }
+
+    fn query(limit: Option<usize>, offset: Option<usize>, fields: Option<&str>) -> ListQuery {
+        ListQuery {
+            limit,
+            offset,
+            fields: fields.map(str::to_string),
+        }
+    }
+
+    async fn body(response: Result<Response, AppError>) -> (StatusCode, Option<usize>, Value) {
+        let Ok(response) = response else {
+            panic!("ListQuery failed to respond");
+        };
+        let status = response.status();
+        let total = response
+            .headers()
+            .get(TOTAL_COUNT_HEADER)
+            .map(|value| value.to_str().unwrap().parse().unwrap());
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, total, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_pagination() {
+        let response = ListQuery::default().respond(items());
+        let (status, total, body) = self::body(response).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(total, Some(4));
+        assert_eq!(body.as_array().unwrap().len(), 4);
+
+        let response = query(Some(2), Some(1), None).respond(items());
+        let (_, total, body) = self::body(response).await;
+        assert_eq!(total, Some(4));
+        assert_eq!(
+            body,
+            json!([{"name": "b", "hours": 2.0}, {"name": "c", "hours": 3.0}])
+        );
+
+        let response = query(None, Some(10), None).respond(items());
+        assert_eq!(self::body(response).await.2, json!([]));
+
+        let response = query(Some(0), None, None).respond(items());
+        assert_eq!(self::body(response).await.0, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_default_limit() {
+        let response = ListQuery::default().respond_with_default_limit(items(), Some(3));
+        let (_, total, body) = self::body(response).await;
+        assert_eq!(total, Some(4));
+        assert_eq!(body.as_array().unwrap().len(), 3);
+
+        let response = query(Some(4), None, None).respond_with_default_limit(items(), Some(3));
+        assert_eq!(self::body(response).await.2.as_array().unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_field_selection() {
+        let response = query(Some(1), None, Some(" hours , ,")).respond(items());
+        assert_eq!(self::body(response).await.2, json!([{"hours": 1.0}]));
+
+        let response = query(None, None, Some(",")).respond(items());
+        assert_eq!(
+            self::body(response).await.2[0],
+            json!({"name": "a", "hours": 1.0})
+        );
+
+        let response = query(None, None, Some("name,minutes")).respond(items());
+        let (status, _, body) = self::body(response).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "Unknown field: minutes");
+    }
+}
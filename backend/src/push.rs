@@ -0,0 +1,411 @@
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::net::{IpAddr, Ipv6Addr};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use utoipa::ToSchema;
+
+use crate::webpush::{self, VapidKey};
+
+/// How long push services keep an undelivered message, in seconds; a goal reminder is
+/// pointless after the day is over
+const MESSAGE_TTL_SECONDS: u32 = 4 * 60 * 60;
+
+/// Most subscriptions a user can have, e.g. one per browser and device
+const MAX_SUBSCRIPTIONS_PER_USER: usize = 10;
+
+/// Timeout of a request to a push service
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A browser push subscription, as returned by `PushSubscription.toJSON()`
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PushSubscription {
+    /// Push service URL the messages are posted to
+    pub endpoint: String,
+    pub keys: SubscriptionKeys,
+}
+
+/// Keys used to encrypt messages for a subscription (base64url-encoded)
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SubscriptionKeys {
+    /// The browser's P-256 public key
+    pub p256dh: String,
+    /// The browser's authentication secret
+    pub auth: String,
+}
+
+/// Identifies a subscription to remove
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct Unsubscribe {
+    pub endpoint: String,
+}
+
+/// Public key to subscribe with
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct VapidPublicKey {
+    /// Base64url-encoded key to pass as `applicationServerKey` to `PushManager.subscribe()`
+    pub public_key: String,
+}
+
+/// Notification shown by the dashboard's service worker
+#[derive(Debug, Clone, Serialize)]
+pub struct PushMessage {
+    pub title: String,
+    pub body: String,
+    /// Tag that lets a newer notification replace an older one
+    pub tag: String,
+}
+
+/// Sends Web Push messages and stores each user's subscriptions
+///
+/// Configured via VAPID_PUBLIC_KEY, VAPID_PRIVATE_KEY and VAPID_SUBJECT; push is disabled
+/// without them. Subscriptions are kept in the JSON file at PUSH_SUBSCRIPTIONS_PATH, or
+/// only in memory when it is not set.
+#[derive(Clone)]
+pub struct PushService {
+    vapid: Option<Arc<VapidKey>>,
+    subscriptions: Arc<RwLock<HashMap<String, Vec<PushSubscription>>>>,
+    path: Option<PathBuf>,
+    client: reqwest::Client,
+}
+
+impl PushService {
+    /// Reads the VAPID keys and loads stored subscriptions
+    pub fn from_env() -> Result<Self> {
+        let vapid = match (env::var("VAPID_PUBLIC_KEY"), env::var("VAPID_PRIVATE_KEY")) {
+            (Ok(public_key), Ok(private_key)) => {
+                let subject = env::var("VAPID_SUBJECT")
+                    .context("VAPID_SUBJECT is required when VAPID keys are set")?;
+                Some(Arc::new(VapidKey::new(
+                    &public_key,
+                    &private_key,
+                    &subject,
+                )?))
+            }
+            (Err(_), Err(_)) => None,
+            _ => bail!("VAPID_PUBLIC_KEY and VAPID_PRIVATE_KEY must be set together"),
+        };
+
+        let path = env::var("PUSH_SUBSCRIPTIONS_PATH").ok().map(PathBuf::from);
+        let subscriptions = match &path {
+            Some(path) if path.exists() => {
+                let content = std::fs::read_to_string(path)
+                    .context(format!("Failed to read push subscriptions: {:?}", path))?;
+                serde_json::from_str(&content)
+                    .context(format!("Failed to parse push subscriptions: {:?}", path))?
+            }
+            _ => HashMap::new(),
+        };
+
+        Ok(Self {
+            vapid,
+            subscriptions: Arc::new(RwLock::new(subscriptions)),
+            path,
+            client: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .redirect(reqwest::redirect::Policy::none())
+                .build()
+                .context("Failed to create HTTP client")?,
+        })
+    }
+
+    /// Public key for subscribing, or `None` when push is not configured
+    pub fn public_key(&self) -> Option<&str> {
+        self.vapid.as_deref().map(VapidKey::public_key)
+    }
+
+    /// Checks that a subscription can be added for a user
+    ///
+    /// # Errors
+    /// Returns an error if the subscription's keys are invalid, its endpoint is not a public
+    /// HTTPS URL, or the user already has the maximum number of other subscriptions
+    pub fn validate(&self, user_id: &str, subscription: &PushSubscription) -> Result<()> {
+        webpush::decode_subscription_keys(&subscription.keys.p256dh, &subscription.keys.auth)?;
+        validate_endpoint(&subscription.endpoint)?;
+        let subscriptions = self.subscriptions.read().unwrap();
+        check_subscription_limit(subscriptions.get(user_id), &subscription.endpoint)
+    }
+
+    /// Adds a subscription for a user, replacing any with the same endpoint
+    ///
+    /// # Errors
+    /// Returns an error if the subscription is invalid (see [`PushService::validate`]) or it
+    /// cannot be stored
+    pub fn subscribe(&self, user_id: &str, subscription: PushSubscription) -> Result<()> {
+        webpush::decode_subscription_keys(&subscription.keys.p256dh, &subscription.keys.auth)?;
+        validate_endpoint(&subscription.endpoint)?;
+        let mut subscriptions = self.subscriptions.write().unwrap();
+        check_subscription_limit(subscriptions.get(user_id), &subscription.endpoint)?;
+        let user_subscriptions = subscriptions.entry(user_id.to_string()).or_default();
+        user_subscriptions.retain(|existing| existing.endpoint != subscription.endpoint);
+        user_subscriptions.push(subscription);
+        self.save(&subscriptions)
+    }
+
+    /// Removes a user's subscription; returns whether it existed
+    pub fn unsubscribe(&self, user_id: &str, endpoint: &str) -> Result<bool> {
+        let mut subscriptions = self.subscriptions.write().unwrap();
+        let Some(user_subscriptions) = subscriptions.get_mut(user_id) else {
+            return Ok(false);
+        };
+        let count = user_subscriptions.len();
+        user_subscriptions.retain(|existing| existing.endpoint != endpoint);
+        let removed = user_subscriptions.len() < count;
+        if removed {
+            self.save(&subscriptions)?;
+        }
+        Ok(removed)
+    }
+
+    /// Sends a message to all of a user's subscriptions
+    ///
+    /// Subscriptions the push service reports as expired (404 or 410) are removed. Returns
+    /// the number of subscriptions the message was delivered to.
+    pub async fn send(&self, user_id: &str, message: &PushMessage) -> Result<usize> {
+        let Some(vapid) = &self.vapid else {
+            return Ok(0);
+        };
+        let targets = self
+            .subscriptions
+            .read()
+            .unwrap()
+            .get(user_id)
+            .cloned()
+            .unwrap_or_default();
+        let payload = serde_json::to_vec(message)?;
+
+        let mut delivered = 0;
+        for subscription in targets {
+            match self.deliver(vapid, &subscription, &payload).await {
+                Ok(200..=299) => delivered += 1,
+                Ok(404 | 410) => {
+                    if let Err(e) = self.unsubscribe(user_id, &subscription.endpoint) {
+                        eprintln!(
+                            "Failed to remove expired push subscription of user '{}': {:#}",
+                            user_id, e
+                        );
+                    }
+                }
+                Ok(status) => eprintln!(
+                    "Push service returned status {} for user '{}'",
+                    status, user_id
+                ),
+                Err(e) => eprintln!("Push to user '{}' failed: {:#}", user_id, e),
+            }
+        }
+
+        Ok(delivered)
+    }
+
+    /// Sends a message to the subscriptions of all users
+    ///
+    /// A failure for one user is logged and does not stop delivery to the others. Returns
+    /// the number of subscriptions the message was delivered to.
+    pub async fn send_to_all(&self, message: &PushMessage) -> Result<usize> {
+        let user_ids: Vec<String> = self.subscriptions.read().unwrap().keys().cloned().collect();
+        let mut delivered = 0;
+        for user_id in user_ids {
+            match self.send(&user_id, message).await {
+                Ok(count) => delivered += count,
+                Err(e) => eprintln!("Push to user '{}' failed: {:#}", user_id, e),
+            }
+        }
+        Ok(delivered)
+    }
+
+    /// Posts an encrypted message to one subscription; returns the push service's status
+    async fn deliver(
+        &self,
+        vapid: &VapidKey,
+        subscription: &PushSubscription,
+        payload: &[u8],
+    ) -> Result<u16> {
+        // The host may have been re-pointed at an internal address since subscribing
+        resolve_public_host(&subscription.endpoint).await?;
+        let body = webpush::encrypt(&subscription.keys.p256dh, &subscription.keys.auth, payload)?;
+        let response = self
+            .client
+            .post(&subscription.endpoint)
+            .header(
+                reqwest::header::AUTHORIZATION,
+                vapid.authorization(&subscription.endpoint)?,
+            )
+            .header(reqwest::header::CONTENT_ENCODING, "aes128gcm")
+            .header(reqwest::header::CONTENT_TYPE, "application/octet-stream")
+            .header("TTL", MESSAGE_TTL_SECONDS)
+            .body(body)
+            .send()
+            .await
+            .context("Failed to send push message")?;
+        Ok(response.status().as_u16())
+    }
+
+    /// Writes all subscriptions to the subscriptions file, if one is configured
+    fn save(&self, subscriptions: &HashMap<String, Vec<PushSubscription>>) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let content = serde_json::to_string_pretty(subscriptions)?;
+        std::fs::write(path, content)
+            .context(format!("Failed to write push subscriptions: {:?}", path))
+    }
+}
+
+/// Checks that a push endpoint is an HTTPS URL on a public host
+///
+/// Push services are always public HTTPS servers; anything else would let a user make the
+/// server send requests into its own network.
+fn validate_endpoint(endpoint: &str) -> Result<reqwest::Url> {
+    let url =
+        reqwest::Url::parse(endpoint).context(format!("Invalid push endpoint: {}", endpoint))?;
+    if url.scheme() != "https" {
+        bail!("Push endpoint must be an https:// URL");
+    }
+    let host = url.host_str().context("Push endpoint has no host")?;
+    match host.trim_start_matches('[').trim_end_matches(']').parse() {
+        Ok(ip) => check_public_ip(ip)?,
+        Err(_) => {
+            let domain = host.trim_end_matches('.').to_ascii_lowercase();
+            if domain == "localhost" || domain.ends_with(".localhost") {
+                bail!("Push endpoint must not be a local address");
+            }
+        }
+    }
+    Ok(url)
+}
+
+/// Resolves a push endpoint's host and checks that all its addresses are public
+async fn resolve_public_host(endpoint: &str) -> Result<()> {
+    let url = validate_endpoint(endpoint)?;
+    let host = url.host_str().context("Push endpoint has no host")?;
+    let port = url.port_or_known_default().unwrap_or(443);
+    let addresses = tokio::net::lookup_host((host, port))
+        .await
+        .context(format!("Failed to resolve push endpoint host: {}", host))?;
+    for address in addresses {
+        check_public_ip(address.ip())?;
+    }
+    Ok(())
+}
+
+/// Rejects loopback, private, link-local and other non-public addresses
+fn check_public_ip(ip: IpAddr) -> Result<()> {
+    let public = match ip {
+        IpAddr::V4(ip) => {
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                // Shared address space (RFC 6598)
+                || (ip.octets()[0] == 100 && (ip.octets()[1] & 0xc0) == 64))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => return check_public_ip(IpAddr::V4(ip)),
+            None => !(ip.is_loopback() || ip.is_unspecified() || is_local_ipv6(ip)),
+        },
+    };
+    if !public {
+        bail!(
+            "Push endpoint must not be a private or local address: {}",
+            ip
+        );
+    }
+    Ok(())
+}
+
+/// Whether an IPv6 address is unique local (fc00::/7) or link-local (fe80::/10)
+fn is_local_ipv6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    (first & 0xfe00) == 0xfc00 || (first & 0xffc0) == 0xfe80
+}
+
+/// Checks that a user may add a subscription with this endpoint
+fn check_subscription_limit(
+    existing: Option<&Vec<PushSubscription>>,
+    endpoint: &str,
+) -> Result<()> {
+    let others = existing
+        .map(|subscriptions| {
+            subscriptions
+                .iter()
+                .filter(|existing| existing.endpoint != endpoint)
+                .count()
+        })
+        .unwrap_or(0);
+    if others >= MAX_SUBSCRIPTIONS_PER_USER {
+        bail!(
+            "At most {} push subscriptions are allowed per user",
+            MAX_SUBSCRIPTIONS_PER_USER
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subscription(endpoint: &str) -> PushSubscription {
+        PushSubscription {
+            endpoint: endpoint.to_string(),
+            keys: SubscriptionKeys {
+                p256dh: String::new(),
+                auth: String::new(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_validate_endpoint_accepts_public_https() {
+        assert!(validate_endpoint("https://fcm.googleapis.com/fcm/send/abc").is_ok());
+        assert!(validate_endpoint("https://updates.push.services.mozilla.com/wpush/v2/x").is_ok());
+        assert!(validate_endpoint("https://93.184.216.34/push").is_ok());
+    }
+
+    #[test]
+    fn test_validate_endpoint_rejects_internal_targets() {
+        for endpoint in [
+            "http://fcm.googleapis.com/fcm/send/abc",
+            "file:///etc/passwd",
+            "https://localhost/push",
+            "https://api.localhost./push",
+            "https://127.0.0.1:8080/push",
+            "https://10.0.0.5/push",
+            "https://192.168.1.1/push",
+            "https://169.254.169.254/latest/meta-data",
+            "https://100.64.0.1/push",
+            "https://0.0.0.0/push",
+            "https://[::1]/push",
+            "https://[fd00::1]/push",
+            "https://[fe80::1]/push",
+            "https://[::ffff:127.0.0.1]/push",
+            "not a url",
+        ] {
+            assert!(
+                validate_endpoint(endpoint).is_err(),
+                "{} accepted",
+                endpoint
+            );
+        }
+    }
+
+    #[test]
+    fn test_subscription_limit_allows_replacing() {
+        let subscriptions: Vec<PushSubscription> = (0..MAX_SUBSCRIPTIONS_PER_USER)
+            .map(|i| subscription(&format!("https://push.example.net/{}", i)))
+            .collect();
+
+        assert!(check_subscription_limit(None, "https://push.example.net/new").is_ok());
+        assert!(
+            check_subscription_limit(Some(&subscriptions), "https://push.example.net/3").is_ok()
+        );
+        assert!(
+            check_subscription_limit(Some(&subscriptions), "https://push.example.net/new").is_err()
+        );
+    }
+}
@@ -0,0 +1,155 @@
+use anyhow::{Context, Result, bail};
+use faithstats::goals::DailyGoal;
+use std::collections::HashSet;
+use std::env;
+use std::time::Duration;
+
+use crate::notify::{EventKind, Notification, Notifier};
+use crate::users::UserProfile;
+
+/// Default local hour from which evening reminders are sent
+const DEFAULT_REMINDER_HOUR: u32 = 20;
+
+/// Interval between daily goal checks
+const CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Spawns a background task that checks every user's daily goals, notifying `goal_met` once a
+/// goal is met and sending a `goal_reminder` listing the goals not yet met in the evening
+///
+/// Each goal is notified as met at most once per day, and reminders are sent once per user
+/// and day from GOAL_REMINDER_HOUR (local time, default 20) on. Does nothing when no user has
+/// daily goals or no notification channel receives these events.
+pub fn spawn_goal_checks(users: Vec<UserProfile>, notifier: Notifier) -> Result<()> {
+    let users: Vec<UserProfile> = users
+        .into_iter()
+        .filter(|user| !user.reminder_goals().is_empty())
+        .collect();
+
+    if users.is_empty()
+        || !(notifier.routes(EventKind::GoalMet) || notifier.routes(EventKind::GoalReminder))
+    {
+        return Ok(());
+    }
+
+    let reminder_hour = match env::var("GOAL_REMINDER_HOUR") {
+        Ok(hour) => hour.parse().context("Invalid GOAL_REMINDER_HOUR")?,
+        Err(_) => DEFAULT_REMINDER_HOUR,
+    };
+    if reminder_hour > 23 {
+        bail!("GOAL_REMINDER_HOUR must be between 0 and 23");
+    }
+
+    tokio::spawn(async move {
+        let mut notified = NotifiedGoals::default();
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let now_ms = chrono::Utc::now().timestamp_millis();
+            let evening = match statsutils::timestamp_ms_to_hour_and_weekday(now_ms) {
+                Ok((hour, _)) => hour >= reminder_hour,
+                Err(e) => {
+                    eprintln!("Error: {:#}", e);
+                    continue;
+                }
+            };
+
+            for user in &users {
+                if let Err(e) = check_user(&notifier, user, evening, &mut notified).await {
+                    eprintln!("Daily goal check for user '{}' failed: {:#}", user.id, e);
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Notifications already sent while the server is running
+#[derive(Default)]
+struct NotifiedGoals {
+    /// (user id, goal name, date) of goals notified as met
+    met: HashSet<(String, String, String)>,
+    /// (user id, date) of reminders sent
+    reminded: HashSet<(String, String)>,
+}
+
+impl NotifiedGoals {
+    /// Forgets the notifications of days before today, which can no longer be repeated
+    fn prune(&mut self, today: &str) {
+        self.met.retain(|(_, _, date)| date.as_str() >= today);
+        self.reminded.retain(|(_, date)| date.as_str() >= today);
+    }
+}
+
+/// Notifies the user's goals newly met today and, in the evening, reminds them of the
+/// unmet ones
+async fn check_user(
+    notifier: &Notifier,
+    user: &UserProfile,
+    evening: bool,
+    notified: &mut NotifiedGoals,
+) -> Result<()> {
+    let config = user.config.clone();
+    let stats = tokio::task::spawn_blocking(move || config.faith_daily_stats(1)).await??;
+    let Some(today) = stats.days.last() else {
+        return Ok(());
+    };
+    notified.prune(&today.date);
+
+    let goals = user.reminder_goals();
+    for goal in goals.iter().filter(|goal| goal.is_met(today)) {
+        let key = (user.id.clone(), goal.name.clone(), today.date.clone());
+        if notified.met.insert(key) {
+            let notification = Notification {
+                event: EventKind::GoalMet,
+                user: Some(user.id.clone()),
+                title: "Daily goal met".to_string(),
+                message: format!(
+                    "{:.0} min {} today",
+                    goal.metric.day_minutes(today).unwrap_or_default(),
+                    goal.metric.activity_label()
+                ),
+            };
+            notifier.send(&notification).await;
+        }
+    }
+
+    // No reminders on exempt days (vacation, illness)
+    let key = (user.id.clone(), today.date.clone());
+    if !evening || today.exempt || notified.reminded.contains(&key) {
+        return Ok(());
+    }
+
+    let unmet: Vec<String> = goals
+        .iter()
+        .filter(|goal| !goal.is_met(today))
+        .filter_map(|goal| {
+            let minutes = goal.metric.day_minutes(today)?;
+            Some(remaining_text(goal, minutes))
+        })
+        .collect();
+
+    if !unmet.is_empty() {
+        let notification = Notification {
+            event: EventKind::GoalReminder,
+            user: Some(user.id.clone()),
+            title: "Daily goals".to_string(),
+            message: format!("Still to do today: {}", unmet.join(", ")),
+        };
+        notifier.send(&notification).await;
+    }
+
+    notified.reminded.insert(key);
+    Ok(())
+}
+
+/// Describes how much of a daily goal is left, e.g. "15 min reading"
+fn remaining_text(goal: &DailyGoal, minutes: f64) -> String {
+    format!(
+        "{:.0} min {}",
+        (goal.daily_target_minutes - minutes).max(1.0).ceil(),
+        goal.metric.activity_label()
+    )
+}
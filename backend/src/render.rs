@@ -0,0 +1,214 @@
+use axum::{
+    Router,
+    http::{StatusCode, header},
+    response::{IntoResponse, Json, Response},
+    routing::get,
+};
+use serde::Deserialize;
+use statsmodels::ErrorResponse;
+use statsutils::format::with_display_format;
+use utoipa::{IntoParams, OpenApi};
+
+use crate::charts::{
+    MAX_IMAGE_SIZE, Palette, StackedBarChart, render_heatmap_svg, render_png, render_svg,
+};
+use crate::{AppConfig, AppError, AppState};
+
+/// Number of days shown in the calendar heatmap
+const HEATMAP_DAYS: i32 = 365;
+
+/// OpenAPI documentation of the chart rendering endpoints
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        get_weekly_png_endpoint,
+        get_daily_svg_endpoint,
+        get_weekly_svg_endpoint,
+        get_heatmap_svg_endpoint,
+    ),
+    components(schemas(Palette)),
+    tags(
+        (name = "render", description = "Server-side rendered chart images")
+    )
+)]
+pub struct RenderApiDoc;
+
+/// Routes of the chart rendering endpoints, relative to the API prefix
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/render/weekly.png", get(get_weekly_png_endpoint))
+        .route("/charts/daily.svg", get(get_daily_svg_endpoint))
+        .route("/charts/weekly.svg", get(get_weekly_svg_endpoint))
+        .route("/charts/heatmap.svg", get(get_heatmap_svg_endpoint))
+}
+
+/// Query parameters for rendered chart images
+#[derive(Debug, Deserialize, IntoParams)]
+struct RenderQuery {
+    /// Image width in pixels (default 800)
+    width: Option<u32>,
+    /// Image height in pixels (default 480; the most the heatmap takes up)
+    height: Option<u32>,
+    /// Color scheme: "grayscale" (default) or "color"
+    palette: Option<Palette>,
+}
+
+impl RenderQuery {
+    /// Returns the requested width and height, or a 400 response if they are out of range
+    #[allow(clippy::result_large_err)]
+    fn size(&self) -> Result<(u32, u32), Response> {
+        let width = self.width.unwrap_or(800);
+        let height = self.height.unwrap_or(480);
+        if (100..=MAX_IMAGE_SIZE).contains(&width) && (100..=MAX_IMAGE_SIZE).contains(&height) {
+            return Ok((width, height));
+        }
+
+        Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(format!(
+                "width and height must be between 100 and {} pixels",
+                MAX_IMAGE_SIZE
+            ))),
+        )
+            .into_response())
+    }
+}
+
+/// Render the weekly faith chart for the last 12 weeks as a PNG image
+///
+/// Intended for devices that can only show static images, such as e-ink displays.
+#[utoipa::path(
+    get,
+    path = "/api/v1/render/weekly.png",
+    params(RenderQuery),
+    responses(
+        (status = 200, description = "Weekly chart rendered successfully", content_type = "image/png", body = Vec<u8>),
+        (status = 400, description = "Invalid image size", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "render"
+)]
+async fn get_weekly_png_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+    axum::extract::Query(query): axum::extract::Query<RenderQuery>,
+) -> Result<Response, AppError> {
+    let (width, height) = match query.size() {
+        Ok(size) => size,
+        Err(response) => return Ok(response),
+    };
+
+    let chart = StackedBarChart::weekly(
+        &config.faith_weekly_stats()?,
+        &config.deck_settings_change_dates()?,
+    );
+    let image = with_display_format(config.display_format, || {
+        render_png(&chart, width, height, query.palette.unwrap_or_default())
+    })?;
+    Ok(([(header::CONTENT_TYPE, "image/png")], image).into_response())
+}
+
+/// Render the daily faith chart for the last 30 days as an SVG image
+#[utoipa::path(
+    get,
+    path = "/api/v1/charts/daily.svg",
+    params(RenderQuery),
+    responses(
+        (status = 200, description = "Daily chart rendered successfully", content_type = "image/svg+xml", body = String),
+        (status = 400, description = "Invalid image size", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "render"
+)]
+async fn get_daily_svg_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+    axum::extract::Query(query): axum::extract::Query<RenderQuery>,
+) -> Result<Response, AppError> {
+    let (width, height) = match query.size() {
+        Ok(size) => size,
+        Err(response) => return Ok(response),
+    };
+
+    let chart = StackedBarChart::daily(
+        &config.faith_daily_stats(30)?,
+        &config.deck_settings_change_dates()?,
+    );
+    let svg = with_display_format(config.display_format, || {
+        render_svg(&chart, width, height, query.palette.unwrap_or_default())
+    })?;
+    Ok(([(header::CONTENT_TYPE, "image/svg+xml")], svg).into_response())
+}
+
+/// Render the weekly faith chart for the last 12 weeks as an SVG image
+#[utoipa::path(
+    get,
+    path = "/api/v1/charts/weekly.svg",
+    params(RenderQuery),
+    responses(
+        (status = 200, description = "Weekly chart rendered successfully", content_type = "image/svg+xml", body = String),
+        (status = 400, description = "Invalid image size", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "render"
+)]
+async fn get_weekly_svg_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+    axum::extract::Query(query): axum::extract::Query<RenderQuery>,
+) -> Result<Response, AppError> {
+    let (width, height) = match query.size() {
+        Ok(size) => size,
+        Err(response) => return Ok(response),
+    };
+
+    let chart = StackedBarChart::weekly(
+        &config.faith_weekly_stats()?,
+        &config.deck_settings_change_dates()?,
+    );
+    let svg = with_display_format(config.display_format, || {
+        render_svg(&chart, width, height, query.palette.unwrap_or_default())
+    })?;
+    Ok(([(header::CONTENT_TYPE, "image/svg+xml")], svg).into_response())
+}
+
+/// Render a calendar heatmap of daily faith time over the last year as an SVG image
+#[utoipa::path(
+    get,
+    path = "/api/v1/charts/heatmap.svg",
+    params(RenderQuery),
+    responses(
+        (status = 200, description = "Heatmap rendered successfully", content_type = "image/svg+xml", body = String),
+        (status = 400, description = "Invalid image size", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "render"
+)]
+async fn get_heatmap_svg_endpoint(
+    axum::Extension(config): axum::Extension<AppConfig>,
+    axum::extract::Query(query): axum::extract::Query<RenderQuery>,
+) -> Result<Response, AppError> {
+    let (width, height) = match query.size() {
+        Ok(size) => size,
+        Err(response) => return Ok(response),
+    };
+
+    let stats = config.faith_daily_stats(HEATMAP_DAYS)?;
+    let svg = with_display_format(config.display_format, || {
+        render_heatmap_svg(&stats, width, height, query.palette.unwrap_or_default())
+    })?;
+    Ok(([(header::CONTENT_TYPE, "image/svg+xml")], svg).into_response())
+}
@@ -0,0 +1,144 @@
+use anyhow::{Context, Result};
+use serde_json::{Map, Value, json};
+use std::collections::{BTreeMap, BTreeSet};
+use utoipa::openapi::OpenApi;
+
+/// JSON Schema dialect of the published schemas; OpenAPI 3.1 schema objects are valid
+/// JSON Schema 2020-12
+const DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Prefix of references between OpenAPI component schemas
+const COMPONENT_REF_PREFIX: &str = "#/components/schemas/";
+
+/// Prefix of references within a standalone JSON Schema document
+const DEFS_REF_PREFIX: &str = "#/$defs/";
+
+/// Standalone JSON Schema documents for every response model in the OpenAPI document
+#[derive(Debug, Clone)]
+pub struct JsonSchemas {
+    /// All models in a single document, under `$defs`
+    pub bundle: Value,
+    /// One self-contained document per model, keyed by model name
+    pub models: BTreeMap<String, Value>,
+}
+
+impl JsonSchemas {
+    /// Converts the component schemas of an OpenAPI document to JSON Schema
+    ///
+    /// References to other components are rewritten to `#/$defs/<name>`, and each per-model
+    /// document embeds the models it references (directly or indirectly) in its own `$defs`,
+    /// so code generators don't need the OpenAPI document to resolve them.
+    pub fn from_openapi(openapi: &OpenApi) -> Result<Self> {
+        let components = match &openapi.components {
+            Some(components) => serde_json::to_value(&components.schemas)
+                .context("Failed to serialize OpenAPI component schemas")?,
+            None => Value::Object(Map::new()),
+        };
+
+        let mut definitions: BTreeMap<String, Value> = match components {
+            Value::Object(schemas) => schemas.into_iter().collect(),
+            _ => BTreeMap::new(),
+        };
+        for schema in definitions.values_mut() {
+            rewrite_refs(schema);
+        }
+
+        let models = definitions
+            .iter()
+            .map(|(name, schema)| {
+                let mut document = json!({ "$schema": DIALECT, "title": name });
+                if let (Value::Object(document), Value::Object(schema)) = (&mut document, schema) {
+                    for (key, value) in schema {
+                        document.insert(key.clone(), value.clone());
+                    }
+                }
+
+                let defs: Map<String, Value> = referenced_models(name, &definitions)
+                    .into_iter()
+                    .filter_map(|referenced| {
+                        let schema = definitions.get(&referenced)?.clone();
+                        Some((referenced, schema))
+                    })
+                    .collect();
+                if !defs.is_empty() {
+                    document["$defs"] = Value::Object(defs);
+                }
+
+                (name.clone(), document)
+            })
+            .collect();
+
+        let bundle = json!({
+            "$schema": DIALECT,
+            "title": "Life Stats API response models",
+            "$defs": definitions,
+        });
+
+        Ok(Self { bundle, models })
+    }
+}
+
+/// Rewrites OpenAPI component references in a schema to `$defs` references
+fn rewrite_refs(value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object.iter_mut() {
+                match value {
+                    Value::String(reference) if key == "$ref" => {
+                        if let Some(name) = reference.strip_prefix(COMPONENT_REF_PREFIX) {
+                            *reference = format!("{}{}", DEFS_REF_PREFIX, name);
+                        }
+                    }
+                    _ => rewrite_refs(value),
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(rewrite_refs),
+        _ => {}
+    }
+}
+
+/// Collects the names of all models a model references, directly or indirectly
+///
+/// A recursive model references itself, so it is included in its own `$defs` as well.
+fn referenced_models(name: &str, definitions: &BTreeMap<String, Value>) -> BTreeSet<String> {
+    let mut found = BTreeSet::new();
+    let mut pending = vec![name.to_string()];
+
+    while let Some(current) = pending.pop() {
+        let Some(schema) = definitions.get(&current) else {
+            continue;
+        };
+        let mut references = Vec::new();
+        collect_refs(schema, &mut references);
+        for reference in references {
+            if found.insert(reference.clone()) {
+                pending.push(reference);
+            }
+        }
+    }
+
+    found
+}
+
+/// Collects the model names of all `$defs` references in a schema
+fn collect_refs(value: &Value, references: &mut Vec<String>) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object {
+                match value {
+                    Value::String(reference) if key == "$ref" => {
+                        if let Some(name) = reference.strip_prefix(DEFS_REF_PREFIX) {
+                            references.push(name.to_string());
+                        }
+                    }
+                    _ => collect_refs(value, references),
+                }
+            }
+        }
+        Value::Array(values) => values
+            .iter()
+            .for_each(|value| collect_refs(value, references)),
+        _ => {}
+    }
+}
@@ -0,0 +1,245 @@
+use ankistats::config::{AnkiCollection, AnkiStatsConfig};
+use anyhow::{Result, bail};
+use faithstats::places::LocationHistory;
+use faithstats::{FaithStats, FaithStatsBuilder, SourceNotConfiguredError};
+use statsutils::pool::ConnectionPool;
+use std::fmt;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+/// A kind of data source, naming it in errors
+pub trait SourceKind {
+    /// What the path points to, e.g. "Anki collection"
+    const DESCRIPTION: &'static str;
+}
+
+/// A data source that is an SQLite database, read through the source's connection pool
+pub trait SqliteKind: SourceKind {}
+
+/// Anki collection.anki2 database
+#[derive(Debug, Clone)]
+pub enum Anki {}
+
+/// KOReader statistics.sqlite3 database
+#[derive(Debug, Clone)]
+pub enum Reading {}
+
+/// Proseuche database.sqlite file
+#[derive(Debug, Clone)]
+pub enum Prayer {}
+
+/// Arc Timeline export directory
+#[derive(Debug, Clone)]
+pub enum ArcTimeline {}
+
+impl SourceKind for Anki {
+    const DESCRIPTION: &'static str = "Anki collection";
+}
+
+impl SourceKind for Reading {
+    const DESCRIPTION: &'static str = "KOReader database";
+}
+
+impl SourceKind for Prayer {
+    const DESCRIPTION: &'static str = "Proseuche database";
+}
+
+impl SourceKind for ArcTimeline {
+    const DESCRIPTION: &'static str = "Arc Timeline export";
+}
+
+impl SqliteKind for Anki {}
+
+impl SqliteKind for Reading {}
+
+impl SqliteKind for Prayer {}
+
+/// A user's database file or export directory, checked to exist when the server starts
+///
+/// A database is read through a pool of read-only connections shared by the clones of the
+/// source, so requests reuse the connections and their statement caches. The pool reopens
+/// its connections when the file is replaced, as the source apps do while syncing. Nothing
+/// is ever opened through the pool of an export directory.
+pub struct Source<K> {
+    pool: ConnectionPool,
+    kind: PhantomData<K>,
+}
+
+impl<K: SourceKind> Source<K> {
+    pub fn open(path: String) -> Result<Self> {
+        if !Path::new(&path).exists() {
+            bail!("{} not found at: {}", K::DESCRIPTION, path);
+        }
+        Ok(Self {
+            pool: ConnectionPool::new(path),
+            kind: PhantomData,
+        })
+    }
+
+    pub fn path(&self) -> &str {
+        self.pool.path()
+    }
+}
+
+impl<K: SqliteKind> Source<K> {
+    /// Connections to the database, for the queries of its stats crate
+    pub fn pool(&self) -> &ConnectionPool {
+        &self.pool
+    }
+}
+
+impl<K> Clone for Source<K> {
+    fn clone(&self) -> Self {
+        Self {
+            pool: self.pool.clone(),
+            kind: PhantomData,
+        }
+    }
+}
+
+impl<K> fmt::Debug for Source<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Source").field(&self.pool.path()).finish()
+    }
+}
+
+pub type AnkiSource = Source<Anki>;
+pub type ReadingSource = Source<Reading>;
+pub type PrayerSource = Source<Prayer>;
+pub type ArcSource = Source<ArcTimeline>;
+
+/// The data sources of a single user, each of which may be left out
+///
+/// Endpoints reading a source that is not configured fail with a `SourceNotConfiguredError`,
+/// which is returned as 503 Service Unavailable.
+#[derive(Debug, Clone, Default)]
+pub struct Sources {
+    anki: Option<AnkiSource>,
+    /// Deck and note type of the verse cards in the Anki collection
+    anki_config: AnkiStatsConfig,
+    reading: Option<ReadingSource>,
+    prayer: Option<PrayerSource>,
+    arc: Option<ArcSource>,
+    /// File correcting the places of the Arc export, read on every query
+    place_overrides_path: Option<PathBuf>,
+    /// ID or name of the user's home place, the place named "Home" if `None`
+    home_place: Option<String>,
+}
+
+impl Sources {
+    /// Opens the sources whose paths are given, failing if any of them does not exist
+    pub fn open(
+        anki: Option<String>,
+        reading: Option<String>,
+        prayer: Option<String>,
+        arc: Option<String>,
+    ) -> Result<Self> {
+        Ok(Self {
+            anki: anki.map(Source::open).transpose()?,
+            anki_config: AnkiStatsConfig::default(),
+            reading: reading.map(Source::open).transpose()?,
+            prayer: prayer.map(Source::open).transpose()?,
+            arc: arc.map(Source::open).transpose()?,
+            place_overrides_path: None,
+            home_place: None,
+        })
+    }
+
+    /// Reads the verse cards from the given deck and note type instead of the defaults
+    pub fn with_anki_config(mut self, config: AnkiStatsConfig) -> Self {
+        self.anki_config = config;
+        self
+    }
+
+    pub fn anki(&self) -> Result<&AnkiSource> {
+        configured(&self.anki)
+    }
+
+    /// The Anki collection with the user's deck and note type, for the `ankistats` queries
+    pub fn anki_collection(&self) -> Result<AnkiCollection<'_>> {
+        let anki = self.anki()?;
+        Ok(AnkiCollection::new(anki.path())
+            .with_config(&self.anki_config)
+            .with_pool(anki.pool()))
+    }
+
+    pub fn reading(&self) -> Result<&ReadingSource> {
+        configured(&self.reading)
+    }
+
+    pub fn prayer(&self) -> Result<&PrayerSource> {
+        configured(&self.prayer)
+    }
+
+    pub fn arc(&self) -> Result<&ArcSource> {
+        configured(&self.arc)
+    }
+
+    /// Applies a place overrides file (TOML or JSON) to the Arc export
+    pub fn with_place_overrides(mut self, path: Option<PathBuf>) -> Self {
+        self.place_overrides_path = path;
+        self
+    }
+
+    /// Uses the place with the given ID or name as the user's home
+    pub fn with_home_place(mut self, home_place: Option<String>) -> Self {
+        self.home_place = home_place;
+        self
+    }
+
+    /// The location history with the user's place overrides and home place, for the
+    /// `places` queries
+    pub fn location_history(&self) -> Result<LocationHistory<'_>> {
+        Ok(LocationHistory {
+            path: self.arc()?.path(),
+            overrides_path: self.place_overrides_path.as_deref(),
+            home_place: self.home_place.as_deref(),
+        })
+    }
+
+    /// Descriptions of the sources that are not configured
+    pub fn missing(&self) -> Vec<&'static str> {
+        [
+            (self.anki.is_none(), Anki::DESCRIPTION),
+            (self.reading.is_none(), Reading::DESCRIPTION),
+            (self.prayer.is_none(), Prayer::DESCRIPTION),
+            (self.arc.is_none(), ArcTimeline::DESCRIPTION),
+        ]
+        .into_iter()
+        .filter_map(|(missing, description)| missing.then_some(description))
+        .collect()
+    }
+
+    /// Faith stats query of the configured sources
+    pub fn faith_stats(&self) -> FaithStatsBuilder {
+        let mut builder = FaithStats::builder().anki_config(self.anki_config.clone());
+        if let Some(anki) = &self.anki {
+            builder = builder.anki_pool(anki.pool().clone());
+        }
+        if let Some(reading) = &self.reading {
+            builder = builder.reading_pool(reading.pool().clone());
+        }
+        if let Some(prayer) = &self.prayer {
+            builder = builder.prayer_pool(prayer.pool().clone());
+        }
+        if let Some(arc) = &self.arc {
+            builder = builder.arc(arc.path());
+        }
+        if let Some(path) = &self.place_overrides_path {
+            builder = builder.place_overrides(path);
+        }
+        if let Some(home_place) = &self.home_place {
+            builder = builder.home_place(home_place);
+        }
+        builder
+    }
+}
+
+fn configured<K: SourceKind>(source: &Option<Source<K>>) -> Result<&Source<K>> {
+    source.as_ref().ok_or_else(|| {
+        SourceNotConfiguredError {
+            source: K::DESCRIPTION.to_string(),
+        }
+        .into()
+    })
+}
@@ -0,0 +1,196 @@
+use anyhow::{Context, Result, bail};
+use faithstats::{
+    find_stale_sources,
+    models::{SourceDataRange, StaleSource, StalenessThresholds},
+    warehouse::HistoryRegression,
+};
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use crate::notify::{EventKind, Notification, Notifier};
+use crate::users::UserProfile;
+
+/// Default interval between staleness checks
+const DEFAULT_CHECK_INTERVAL_MINUTES: u64 = 60;
+
+/// Latest staleness check result per user id, shared with the health endpoint
+#[derive(Clone)]
+pub struct StalenessState {
+    stale_sources: Arc<RwLock<HashMap<String, Vec<StaleSource>>>>,
+    /// Sources with a shorter history than preserved in the warehouse, per user id, from the
+    /// latest warehouse rollup
+    history_regressions: Arc<RwLock<HashMap<String, Vec<HistoryRegression>>>>,
+    thresholds: StalenessThresholds,
+    notifier: Notifier,
+    multiple_users: bool,
+}
+
+impl StalenessState {
+    /// Sources that breached their threshold in the most recent check, with their user id,
+    /// sorted by user id
+    pub fn stale_sources(&self) -> Vec<(String, StaleSource)> {
+        let stale_sources = self.stale_sources.read().unwrap();
+        let mut user_ids: Vec<&String> = stale_sources.keys().collect();
+        user_ids.sort();
+
+        user_ids
+            .into_iter()
+            .flat_map(|user_id| {
+                stale_sources[user_id]
+                    .iter()
+                    .map(|source| (user_id.clone(), source.clone()))
+            })
+            .collect()
+    }
+
+    /// Sources whose history regressed in the latest warehouse rollup, with their user id,
+    /// sorted by user id
+    pub fn history_regressions(&self) -> Vec<(String, HistoryRegression)> {
+        let regressions = self.history_regressions.read().unwrap();
+        let mut user_ids: Vec<&String> = regressions.keys().collect();
+        user_ids.sort();
+
+        user_ids
+            .into_iter()
+            .flat_map(|user_id| {
+                regressions[user_id]
+                    .iter()
+                    .map(|regression| (user_id.clone(), regression.clone()))
+            })
+            .collect()
+    }
+
+    /// Records the sources whose history regressed in a user's warehouse rollup
+    pub fn record_history_regressions(
+        &self,
+        user: &UserProfile,
+        regressions: Vec<HistoryRegression>,
+    ) {
+        self.history_regressions
+            .write()
+            .unwrap()
+            .insert(user.id.clone(), regressions);
+    }
+
+    /// Records the result of checking a user's sources and notifies about newly stale ones
+    ///
+    /// When more than one user is configured, notifications are prefixed with the user id.
+    /// Returns the user's stale sources.
+    pub async fn record(&self, user: &UserProfile, ranges: &[SourceDataRange]) -> Vec<StaleSource> {
+        let stale = find_stale_sources(ranges, &self.thresholds);
+        let previous = self
+            .stale_sources
+            .write()
+            .unwrap()
+            .insert(user.id.clone(), stale.clone())
+            .unwrap_or_default();
+
+        for source in stale
+            .iter()
+            .filter(|s| !previous.iter().any(|p| p.source == s.source))
+        {
+            let message = if self.multiple_users {
+                format!("{}: {}", user.id, source.reason)
+            } else {
+                source.reason.clone()
+            };
+            let notification = Notification {
+                event: EventKind::StaleData,
+                user: Some(user.id.clone()),
+                title: format!("No new {} data", source.source),
+                message,
+            };
+            self.notifier.send(&notification).await;
+        }
+
+        stale
+    }
+}
+
+/// Reads staleness thresholds (in days) from `*_STALE_AFTER_DAYS` environment variables
+pub fn thresholds_from_env() -> StalenessThresholds {
+    let read = |name: &str| -> Option<f64> {
+        let value = env::var(name).ok()?;
+        match value.parse() {
+            Ok(days) => Some(days),
+            Err(_) => {
+                eprintln!("Error: {} must be a number of days, got '{}'", name, value);
+                std::process::exit(1);
+            }
+        }
+    };
+
+    StalenessThresholds {
+        anki: read("ANKI_STALE_AFTER_DAYS"),
+        reading: read("KOREADER_STALE_AFTER_DAYS"),
+        prayer: read("PROSEUCHE_STALE_AFTER_DAYS"),
+        arc: read("ARCSTATS_STALE_AFTER_DAYS"),
+    }
+}
+
+/// Spawns a background task that periodically checks every user's sources against their
+/// staleness threshold, records the result for the health endpoint, and sends a `stale_data`
+/// notification when a source newly becomes stale
+///
+/// # Errors
+/// Returns an error if STALENESS_CHECK_INTERVAL_MINUTES is not a whole number of minutes of
+/// at least 1
+pub fn spawn_monitor(
+    users: Vec<UserProfile>,
+    thresholds: StalenessThresholds,
+    notifier: Notifier,
+) -> Result<StalenessState> {
+    let state = StalenessState {
+        stale_sources: Arc::default(),
+        history_regressions: Arc::default(),
+        thresholds,
+        notifier,
+        multiple_users: users.len() > 1,
+    };
+
+    if state.thresholds.is_empty() {
+        return Ok(state);
+    }
+
+    let interval_minutes: u64 = match env::var("STALENESS_CHECK_INTERVAL_MINUTES") {
+        Ok(minutes) => minutes
+            .parse()
+            .context("Invalid STALENESS_CHECK_INTERVAL_MINUTES")?,
+        Err(_) => DEFAULT_CHECK_INTERVAL_MINUTES,
+    };
+    if interval_minutes < 1 {
+        bail!("STALENESS_CHECK_INTERVAL_MINUTES must be at least 1");
+    }
+
+    let task_state = state.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_minutes * 60));
+
+        loop {
+            interval.tick().await;
+
+            for user in &users {
+                check_user(user, &task_state).await;
+            }
+        }
+    });
+
+    Ok(state)
+}
+
+/// Checks a single user's sources and records the result
+async fn check_user(user: &UserProfile, state: &StalenessState) {
+    let config = user.config.clone();
+    let ranges =
+        tokio::task::spawn_blocking(move || config.sources.faith_stats().build().data_ranges())
+            .await;
+
+    match ranges {
+        Ok(ranges) => {
+            state.record(user, &ranges).await;
+        }
+        Err(e) => eprintln!("Staleness check for user '{}' failed: {}", user.id, e),
+    }
+}
@@ -0,0 +1,592 @@
+use ankistats::config::AnkiStatsConfig;
+use anyhow::{Context, Result, bail};
+use faithstats::budget::{BudgetAllocation, TimeBudget};
+use faithstats::goals::{DailyGoal, Goal, GoalMetric};
+use faithstats::score::{ScoreComponent, default_components, validate_components};
+use serde::Deserialize;
+use statsutils::format::{DisplayFormatConfig, display_format};
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::AppConfig;
+use crate::caldav::CalDavConfig;
+use crate::habits::{HabitConfig, HabiticaConfig};
+use crate::mastodon::MastodonConfig;
+use crate::sources::Sources;
+
+/// Id of the single user configured from environment variables
+const DEFAULT_USER_ID: &str = "default";
+
+/// A user with their own data sources
+#[derive(Clone)]
+pub struct UserProfile {
+    pub id: String,
+    pub config: AppConfig,
+    /// Public share settings, if the user opted in
+    pub share: Option<ShareConfig>,
+    /// Personal weekly goals
+    pub goals: Vec<Goal>,
+    /// CalDAV calendar that receives catch-up tasks for goals that fall behind
+    pub caldav: Option<CalDavConfig>,
+    /// External habits completed when their daily goal is met
+    pub habits: Vec<HabitConfig>,
+    /// Habitica account used for habits with a Habitica task
+    pub habitica: Option<HabiticaConfig>,
+    /// Daily goals that are notified when met and trigger an evening reminder while unmet
+    pub daily_goals: Vec<DailyGoal>,
+    /// Mastodon account that receives daily or weekly summary posts
+    pub mastodon: Option<MastodonConfig>,
+    /// Weighted activities of the faith score
+    pub score: Vec<ScoreComponent>,
+    /// Weekly time budget of the budget report
+    pub budget: TimeBudget,
+}
+
+impl UserProfile {
+    /// Daily goals covered by goal notifications: the daily goals and those of the habits
+    pub fn reminder_goals(&self) -> Vec<DailyGoal> {
+        self.daily_goals
+            .iter()
+            .chain(self.habits.iter().map(|habit| &habit.goal))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Opt-in public sharing of a subset of a user's weekly stats via `/share/{token}/weekly`
+#[derive(Clone, Deserialize)]
+pub struct ShareConfig {
+    /// Secret token that forms the public share URL
+    pub token: String,
+    /// Metrics exposed publicly
+    #[serde(default = "default_share_metrics")]
+    pub metrics: Vec<GoalMetric>,
+}
+
+fn default_share_metrics() -> Vec<GoalMetric> {
+    vec![GoalMetric::TotalMinutes]
+}
+
+/// Entry of the `[[users]]` array in the users config file
+#[derive(Deserialize)]
+struct UserEntry {
+    id: String,
+    api_key: String,
+    anki_database_path: Option<String>,
+    /// Deck of the verse cards (default: ANKI_DECK_NAME or "Bible::Verses")
+    anki_deck_name: Option<String>,
+    /// Note type of the verse cards (default: ANKI_NOTE_TYPE or "Bible Verse")
+    anki_note_type: Option<String>,
+    koreader_database_path: Option<String>,
+    arcstats_export_path: Option<String>,
+    /// File correcting the places of the user's Arc export (see `arcstats::overrides`)
+    place_overrides_path: Option<PathBuf>,
+    /// ID or name of the user's home place in the location history (default: "Home")
+    home_place: Option<String>,
+    proseuche_database_path: Option<String>,
+    stats_store_path: Option<String>,
+    warehouse_path: Option<String>,
+    share: Option<ShareConfig>,
+    #[serde(default)]
+    goals: Vec<Goal>,
+    caldav: Option<CalDavConfig>,
+    #[serde(default)]
+    habits: Vec<HabitConfig>,
+    habitica: Option<HabiticaConfig>,
+    #[serde(default)]
+    daily_goals: Vec<DailyGoal>,
+    mastodon: Option<MastodonConfig>,
+    /// Faith score components (default: `faithstats::score::default_components`)
+    #[serde(default = "default_components")]
+    score: Vec<ScoreComponent>,
+    /// Weekly time budget (default: 7 hours without allocations)
+    #[serde(default)]
+    budget: TimeBudget,
+    /// Adjustments to the server's display format, e.g. imperial units or a 12-hour clock
+    #[serde(default)]
+    format: DisplayFormatConfig,
+}
+
+#[derive(Deserialize)]
+struct UsersFile {
+    users: Vec<UserEntry>,
+    #[serde(default)]
+    household_goals: Vec<Goal>,
+}
+
+/// All configured users, looked up by API key
+///
+/// All users of the server form one household.
+#[derive(Clone)]
+pub struct Users {
+    by_api_key: Arc<HashMap<String, UserProfile>>,
+    by_share_token: Arc<HashMap<String, UserProfile>>,
+    household_goals: Arc<Vec<Goal>>,
+}
+
+impl Users {
+    /// Loads users from the TOML file at USERS_CONFIG_PATH, or falls back to a single
+    /// "default" user configured with the `*_DATABASE_PATH`/`*_EXPORT_PATH` and API_KEY
+    /// environment variables
+    ///
+    /// Each user's sources are opened (and checked to exist) once here; a source without a
+    /// path is left out.
+    pub fn from_env() -> Result<Self> {
+        match env::var("USERS_CONFIG_PATH") {
+            Ok(path) => Self::from_file(&path),
+            Err(_) => Self::from_users(vec![default_user_from_env()?], Vec::new()),
+        }
+    }
+
+    /// Loads users from a TOML users config file
+    pub fn from_file(path: &str) -> Result<Self> {
+        let (users, household_goals) = load_users_file(path)?;
+        Self::from_users(users, household_goals)
+    }
+
+    /// Validates the users' settings and indexes them by API key and share token
+    fn from_users(users: KeyedUsers, household_goals: Vec<Goal>) -> Result<Self> {
+        let mut by_api_key = HashMap::new();
+        let mut by_share_token = HashMap::new();
+        let mut ids = HashSet::new();
+        for (api_key, user) in users {
+            if !ids.insert(user.id.clone()) {
+                bail!("User id '{}' is used by more than one user", user.id);
+            }
+            validate_habits(&user)?;
+            if let Some(mastodon) = &user.mastodon {
+                mastodon
+                    .validate()
+                    .context(format!("Invalid Mastodon template for user '{}'", user.id))?;
+            }
+            validate_components(&user.score)
+                .context(format!("Invalid faith score for user '{}'", user.id))?;
+            user.budget
+                .validate()
+                .context(format!("Invalid weekly budget for user '{}'", user.id))?;
+            if let Some(share) = &user.share
+                && by_share_token
+                    .insert(share.token.clone(), user.clone())
+                    .is_some()
+            {
+                bail!("User '{}' reuses another user's share token", user.id);
+            }
+            if by_api_key.insert(api_key, user.clone()).is_some() {
+                bail!("User '{}' reuses another user's API key", user.id);
+            }
+        }
+
+        Ok(Self {
+            by_api_key: Arc::new(by_api_key),
+            by_share_token: Arc::new(by_share_token),
+            household_goals: Arc::new(household_goals),
+        })
+    }
+
+    /// Returns the user owning the given API key
+    pub fn authenticate(&self, api_key: &str) -> Option<&UserProfile> {
+        self.by_api_key.get(api_key)
+    }
+
+    /// Returns the user who shares their stats under the given public token
+    pub fn find_by_share_token(&self, token: &str) -> Option<&UserProfile> {
+        self.by_share_token.get(token)
+    }
+
+    /// All configured users, sorted by id
+    pub fn profiles(&self) -> Vec<UserProfile> {
+        let mut profiles: Vec<UserProfile> = self.by_api_key.values().cloned().collect();
+        profiles.sort_by(|a, b| a.id.cmp(&b.id));
+        profiles
+    }
+
+    /// Goals shared by the whole household
+    pub fn household_goals(&self) -> &[Goal] {
+        &self.household_goals
+    }
+}
+
+/// Users keyed by their API key
+type KeyedUsers = Vec<(String, UserProfile)>;
+
+fn load_users_file(path: &str) -> Result<(KeyedUsers, Vec<Goal>)> {
+    let contents =
+        std::fs::read_to_string(path).context(format!("Failed to read users config {}", path))?;
+    let file: UsersFile =
+        toml::from_str(&contents).context(format!("Failed to parse users config {}", path))?;
+
+    if file.users.is_empty() {
+        bail!("Users config {} does not define any users", path);
+    }
+
+    let users = file
+        .users
+        .into_iter()
+        .map(|entry| {
+            let sources = Sources::open(
+                entry.anki_database_path,
+                entry.koreader_database_path,
+                entry.proseuche_database_path,
+                entry.arcstats_export_path,
+            )
+            .context(format!("Invalid sources for user '{}'", entry.id))?
+            .with_anki_config(anki_config(entry.anki_deck_name, entry.anki_note_type))
+            .with_place_overrides(entry.place_overrides_path)
+            .with_home_place(entry.home_place);
+            let profile = UserProfile {
+                id: entry.id,
+                config: AppConfig {
+                    sources,
+                    stats_store_path: entry.stats_store_path,
+                    warehouse_path: entry.warehouse_path,
+                    display_format: entry.format.apply(display_format()),
+                },
+                share: entry.share,
+                goals: entry.goals,
+                caldav: entry.caldav,
+                habits: entry.habits,
+                habitica: entry.habitica,
+                daily_goals: entry.daily_goals,
+                mastodon: entry.mastodon,
+                score: entry.score,
+                budget: entry.budget,
+            };
+            Ok((entry.api_key, profile))
+        })
+        .collect::<Result<_>>()?;
+
+    Ok((users, file.household_goals))
+}
+
+fn default_user_from_env() -> Result<(String, UserProfile)> {
+    let var =
+        |name: &str| env::var(name).context(format!("{} environment variable is required", name));
+
+    let profile = UserProfile {
+        id: DEFAULT_USER_ID.to_string(),
+        config: AppConfig {
+            sources: Sources::open(
+                Some(var("ANKI_DATABASE_PATH")?),
+                env::var("KOREADER_DATABASE_PATH").ok(),
+                env::var("PROSEUCHE_DATABASE_PATH").ok(),
+                env::var("ARCSTATS_EXPORT_PATH").ok(),
+            )?
+            .with_anki_config(AnkiStatsConfig::from_env())
+            .with_place_overrides(env::var_os("PLACE_OVERRIDES_PATH").map(PathBuf::from))
+            .with_home_place(env::var("HOME_PLACE").ok()),
+            stats_store_path: env::var("STATS_STORE_PATH").ok(),
+            warehouse_path: env::var("WAREHOUSE_PATH").ok(),
+            display_format: display_format(),
+        },
+        share: share_from_env()?,
+        goals: goals_from_env()?,
+        caldav: CalDavConfig::from_env(),
+        habits: Vec::new(),
+        habitica: None,
+        daily_goals: daily_goals_from_env()?,
+        mastodon: MastodonConfig::from_env()?,
+        score: score_from_env()?,
+        budget: budget_from_env()?,
+    };
+
+    Ok((var("API_KEY")?, profile))
+}
+
+/// A user's deck and note type of the verse cards, falling back to ANKI_DECK_NAME and
+/// ANKI_NOTE_TYPE for the ones not given
+fn anki_config(deck_name: Option<String>, note_type: Option<String>) -> AnkiStatsConfig {
+    let default = AnkiStatsConfig::from_env();
+    AnkiStatsConfig {
+        deck_name: deck_name.unwrap_or(default.deck_name),
+        note_type: note_type.unwrap_or(default.note_type),
+    }
+}
+
+/// Validates the user's habits and that Habitica credentials exist where needed
+fn validate_habits(user: &UserProfile) -> Result<()> {
+    for habit in &user.habits {
+        habit
+            .validate()
+            .context(format!("Invalid habit for user '{}'", user.id))?;
+        if habit.habitica_task_id.is_some() && user.habitica.is_none() {
+            bail!(
+                "Habit '{}' of user '{}' uses Habitica, but no habitica credentials are configured",
+                habit.goal.name,
+                user.id
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads the default user's share settings from SHARE_TOKEN and SHARE_METRICS
+/// (comma-separated metric names, default "total_minutes")
+fn share_from_env() -> Result<Option<ShareConfig>> {
+    let Ok(token) = env::var("SHARE_TOKEN") else {
+        return Ok(None);
+    };
+
+    let metrics = match env::var("SHARE_METRICS") {
+        Ok(value) => value
+            .split(',')
+            .map(|metric| metric.trim().parse())
+            .collect::<Result<Vec<GoalMetric>>>()
+            .context("Invalid SHARE_METRICS")?,
+        Err(_) => default_share_metrics(),
+    };
+
+    Ok(Some(ShareConfig { token, metrics }))
+}
+
+/// Reads the default user's weekly goals from WEEKLY_GOALS, a comma-separated list of
+/// `metric=minutes` pairs (e.g. "reading_minutes=150,prayer_minutes=70")
+fn goals_from_env() -> Result<Vec<Goal>> {
+    let Ok(value) = env::var("WEEKLY_GOALS") else {
+        return Ok(Vec::new());
+    };
+
+    parse_goal_pairs("WEEKLY_GOALS", &value)?
+        .into_iter()
+        .map(|(name, metric, minutes)| {
+            Ok(Goal {
+                name,
+                metric,
+                weekly_target_minutes: minutes,
+            })
+        })
+        .collect()
+}
+
+/// Reads the default user's daily goals from DAILY_GOALS, in the same format as WEEKLY_GOALS
+/// (e.g. "reading_minutes=20,prayer_minutes=10")
+fn daily_goals_from_env() -> Result<Vec<DailyGoal>> {
+    let Ok(value) = env::var("DAILY_GOALS") else {
+        return Ok(Vec::new());
+    };
+
+    parse_goal_pairs("DAILY_GOALS", &value)?
+        .into_iter()
+        .map(|(name, metric, minutes)| {
+            Ok(DailyGoal {
+                name,
+                metric,
+                daily_target_minutes: minutes,
+            })
+        })
+        .collect()
+}
+
+/// Reads the default user's faith score components from FAITH_SCORE_CAPS (cap minutes per
+/// metric, in the same format as WEEKLY_GOALS) and FAITH_SCORE_WEIGHTS (e.g.
+/// "reading_minutes=2"; default 1)
+fn score_from_env() -> Result<Vec<ScoreComponent>> {
+    let Ok(caps) = env::var("FAITH_SCORE_CAPS") else {
+        if env::var("FAITH_SCORE_WEIGHTS").is_ok() {
+            bail!("FAITH_SCORE_WEIGHTS requires FAITH_SCORE_CAPS");
+        }
+        return Ok(default_components());
+    };
+
+    let weights = match env::var("FAITH_SCORE_WEIGHTS") {
+        Ok(weights) => parse_goal_pairs("FAITH_SCORE_WEIGHTS", &weights)?,
+        Err(_) => Vec::new(),
+    };
+    let mut components: Vec<ScoreComponent> = parse_goal_pairs("FAITH_SCORE_CAPS", &caps)?
+        .into_iter()
+        .map(|(_, metric, cap_minutes)| ScoreComponent {
+            metric,
+            weight: 1.0,
+            cap_minutes,
+        })
+        .collect();
+
+    for (name, metric, weight) in weights {
+        let component = components
+            .iter_mut()
+            .find(|component| component.metric == metric)
+            .context(format!(
+                "FAITH_SCORE_WEIGHTS has a weight for {}, which has no cap in FAITH_SCORE_CAPS",
+                name
+            ))?;
+        component.weight = weight;
+    }
+
+    Ok(components)
+}
+
+/// Reads the default user's weekly time budget from FAITH_BUDGET_MINUTES (default 420) and
+/// FAITH_BUDGET_ALLOCATION (planned minutes per metric, in the same format as WEEKLY_GOALS)
+fn budget_from_env() -> Result<TimeBudget> {
+    let mut budget = TimeBudget::default();
+    if let Ok(minutes) = env::var("FAITH_BUDGET_MINUTES") {
+        budget.total_minutes = minutes
+            .trim()
+            .parse()
+            .context("Invalid FAITH_BUDGET_MINUTES")?;
+    }
+    if let Ok(allocation) = env::var("FAITH_BUDGET_ALLOCATION") {
+        budget.allocation = parse_goal_pairs("FAITH_BUDGET_ALLOCATION", &allocation)?
+            .into_iter()
+            .map(|(_, metric, minutes)| BudgetAllocation { metric, minutes })
+            .collect();
+    }
+    Ok(budget)
+}
+
+/// Parses a comma-separated list of `metric=minutes` pairs from the named variable
+fn parse_goal_pairs(variable: &str, value: &str) -> Result<Vec<(String, GoalMetric, f64)>> {
+    value
+        .split(',')
+        .map(|goal| {
+            let (metric, minutes) = goal
+                .split_once('=')
+                .context(format!("Invalid {} entry '{}'", variable, goal))?;
+            Ok((
+                metric.trim().to_string(),
+                metric
+                    .trim()
+                    .parse()
+                    .context(format!("Invalid {}", variable))?,
+                minutes
+                    .trim()
+                    .parse()
+                    .context(format!("Invalid {} minutes '{}'", variable, minutes))?,
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::NamedTempFile;
+
+    /// Loads users from a users config file with the given contents
+    fn load(config: &str) -> Result<Users> {
+        let file = NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), config).unwrap();
+        Users::from_file(file.path().to_str().unwrap())
+    }
+
+    #[test]
+    fn test_duplicate_user_ids_are_rejected() {
+        let error = load(
+            r#"
+            [[users]]
+            id = "alice"
+            api_key = "key"
+
+            [[users]]
+            id = "alice"
+            api_key = "other-key"
+            "#,
+        )
+        .err()
+        .unwrap();
+
+        assert!(
+            format!("{:#}", error).contains("User id 'alice' is used by more than one user"),
+            "{:#}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_habit_with_webhook_is_valid() {
+        let users = load(
+            r#"
+            [[users]]
+            id = "alice"
+            api_key = "key"
+
+            [[users.habits]]
+            name = "Prayer"
+            metric = "prayer_minutes"
+            daily_target_minutes = 10
+            webhook_url = "https://example.com/hook"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(users.profiles()[0].habits.len(), 1);
+    }
+
+    #[test]
+    fn test_habit_needs_a_target() {
+        let error = load(
+            r#"
+            [[users]]
+            id = "alice"
+            api_key = "key"
+
+            [[users.habits]]
+            name = "Prayer"
+            metric = "prayer_minutes"
+            daily_target_minutes = 10
+            "#,
+        )
+        .err()
+        .unwrap();
+
+        assert!(
+            format!("{:#}", error).contains("needs a habitica_task_id or webhook_url"),
+            "{:#}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_habit_rejects_weekly_only_metric() {
+        let error = load(
+            r#"
+            [[users]]
+            id = "alice"
+            api_key = "key"
+
+            [[users.habits]]
+            name = "Church"
+            metric = "at_church_minutes"
+            daily_target_minutes = 60
+            webhook_url = "https://example.com/hook"
+            "#,
+        )
+        .err()
+        .unwrap();
+
+        assert!(
+            format!("{:#}", error).contains("only tracked per week"),
+            "{:#}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_habitica_habit_needs_credentials() {
+        let config = r#"
+            [[users]]
+            id = "alice"
+            api_key = "key"
+
+            [[users.habits]]
+            name = "Reading"
+            metric = "reading_minutes"
+            daily_target_minutes = 15
+            habitica_task_id = "task"
+            "#;
+        let error = load(config).err().unwrap();
+        assert!(
+            format!("{:#}", error).contains("no habitica credentials"),
+            "{:#}",
+            error
+        );
+
+        let with_credentials = format!(
+            "{}\n[users.habitica]\nuser_id = \"user\"\napi_token = \"token\"\n",
+            config
+        );
+        assert!(load(&with_credentials).is_ok());
+    }
+}
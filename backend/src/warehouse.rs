@@ -0,0 +1,162 @@
+use ankistats::get_deck_settings;
+use anyhow::{Context, Result, bail};
+use chrono::NaiveDate;
+use faithstats::warehouse::{HistoryRegression, Warehouse, day_rollups, week_rollups};
+use std::collections::HashSet;
+use std::env;
+use std::time::Duration;
+
+use crate::AppConfig;
+use crate::staleness::StalenessState;
+use crate::users::UserProfile;
+
+/// Default local hour from which the nightly rollup runs
+const DEFAULT_ROLLUP_HOUR: u32 = 3;
+
+/// Default number of days rolled up into an empty warehouse
+const DEFAULT_BACKFILL_DAYS: i32 = 730;
+
+/// Days before the last rollup that are rolled up again, since sources sync late
+const OVERLAP_DAYS: i32 = 7;
+
+/// Interval between checks whether the nightly rollup is due
+const CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Spawns a background task that rolls each user's daily stats up into their warehouse
+///
+/// Runs once per day from WAREHOUSE_ROLLUP_HOUR (local time, default 3) on, or right away
+/// when the server starts later in the day. An empty warehouse is filled with the last
+/// WAREHOUSE_BACKFILL_DAYS days (default 730). Sources whose history regressed since the
+/// last rollup are recorded for the health endpoint. Does nothing when no user has a
+/// warehouse.
+pub fn spawn_nightly_rollups(users: Vec<UserProfile>, staleness: StalenessState) -> Result<()> {
+    let users: Vec<UserProfile> = users
+        .into_iter()
+        .filter(|user| user.config.warehouse_path.is_some())
+        .collect();
+
+    if users.is_empty() {
+        return Ok(());
+    }
+
+    let rollup_hour = match env::var("WAREHOUSE_ROLLUP_HOUR") {
+        Ok(hour) => hour.parse().context("Invalid WAREHOUSE_ROLLUP_HOUR")?,
+        Err(_) => DEFAULT_ROLLUP_HOUR,
+    };
+    if rollup_hour > 23 {
+        bail!("WAREHOUSE_ROLLUP_HOUR must be between 0 and 23");
+    }
+    let backfill_days = match env::var("WAREHOUSE_BACKFILL_DAYS") {
+        Ok(days) => days.parse().context("Invalid WAREHOUSE_BACKFILL_DAYS")?,
+        Err(_) => DEFAULT_BACKFILL_DAYS,
+    };
+    if backfill_days < 1 {
+        bail!("WAREHOUSE_BACKFILL_DAYS must be at least 1");
+    }
+
+    tokio::spawn(async move {
+        // (user id, date) of rollups already run
+        let mut rolled_up = HashSet::new();
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let now_ms = chrono::Utc::now().timestamp_millis();
+            let (hour, today) = match statsutils::timestamp_ms_to_hour_and_weekday(now_ms)
+                .and_then(|(hour, _)| Ok((hour, statsutils::get_today_date_at(now_ms)?)))
+            {
+                Ok(now) => now,
+                Err(e) => {
+                    eprintln!("Error: {:#}", e);
+                    continue;
+                }
+            };
+            if hour < rollup_hour {
+                continue;
+            }
+
+            for user in &users {
+                let key = (user.id.clone(), today.clone());
+                if rolled_up.contains(&key) {
+                    continue;
+                }
+
+                let config = user.config.clone();
+                let result = tokio::task::spawn_blocking(move || rollup(&config, backfill_days))
+                    .await
+                    .map_err(anyhow::Error::from)
+                    .and_then(|result| result);
+                match result {
+                    Ok((days, regressions)) => {
+                        println!(
+                            "Rolled up {} days into the warehouse of user '{}'",
+                            days, user.id
+                        );
+                        for regression in &regressions {
+                            eprintln!("Warning: user '{}': {}", user.id, regression.reason);
+                        }
+                        staleness.record_history_regressions(user, regressions);
+                        rolled_up.insert(key);
+                    }
+                    Err(e) => eprintln!("Warehouse rollup for user '{}' failed: {:#}", user.id, e),
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Rolls the days since the last rollup (plus an overlap), or the backfill period for an
+/// empty warehouse, up into the user's warehouse, and records changes of the Bible deck's
+/// scheduling settings
+///
+/// Days before a source's current history are only filled in where the warehouse has no
+/// value yet, so history pruned by the source app is preserved. Returns the number of days
+/// rolled up and the sources whose history regressed.
+pub fn rollup(config: &AppConfig, backfill_days: i32) -> Result<(i32, Vec<HistoryRegression>)> {
+    let Some(warehouse_path) = &config.warehouse_path else {
+        return Ok((0, Vec::new()));
+    };
+    let mut warehouse = Warehouse::open(warehouse_path)?;
+
+    let ranges = config.sources.faith_stats().build().data_ranges();
+    let regressions = warehouse.check_source_history(&ranges)?;
+
+    let days = match warehouse.last_rollup_date()? {
+        Some(last_date) => {
+            let last_date = NaiveDate::parse_from_str(&last_date, "%Y-%m-%d")
+                .context(format!("Invalid last rollup date {}", last_date))?;
+            let today = NaiveDate::parse_from_str(&statsutils::get_today_date()?, "%Y-%m-%d")?;
+            ((today - last_date).num_days() as i32 + OVERLAP_DAYS).clamp(1, backfill_days)
+        }
+        None => backfill_days,
+    };
+
+    let daily = config.faith_daily_stats(days)?;
+    let weekly = config
+        .sources
+        .faith_stats()
+        .weeks(days / 7 + 1)
+        .build()
+        .weekly()?;
+
+    let rollups: Vec<_> = daily
+        .days
+        .iter()
+        .flat_map(day_rollups)
+        .chain(weekly.weeks.iter().flat_map(week_rollups))
+        .collect();
+    warehouse.upsert_rollups(&rollups, &ranges)?;
+
+    // Unreadable settings (e.g. a filtered Bible deck) should not hold up the rollup itself
+    match config.sources.anki_collection().and_then(get_deck_settings) {
+        Ok(settings) => {
+            warehouse.record_deck_settings(&statsutils::get_today_date()?, &settings)?;
+        }
+        Err(e) => eprintln!("Warning: could not read deck settings: {:#}", e),
+    }
+
+    Ok((days, regressions))
+}
@@ -0,0 +1,304 @@
+//! Web Push message encryption (RFC 8291) and VAPID authentication (RFC 8292)
+
+use anyhow::{Context, Result, anyhow, bail};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use ring::aead::{AES_128_GCM, Aad, LessSafeKey, Nonce, UnboundKey};
+use ring::agreement::{ECDH_P256, EphemeralPrivateKey, UnparsedPublicKey, agree_ephemeral};
+use ring::hkdf::{HKDF_SHA256, KeyType, Salt};
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::signature::{ECDSA_P256_SHA256_FIXED_SIGNING, EcdsaKeyPair};
+
+/// How long a VAPID token stays valid; push services reject tokens valid for over 24 hours
+const VAPID_TOKEN_SECONDS: i64 = 12 * 60 * 60;
+
+/// Record size advertised in the encrypted content header; messages are sent as one record
+const RECORD_SIZE: u32 = 4096;
+
+/// Length of an uncompressed P-256 public key
+const PUBLIC_KEY_LEN: usize = 65;
+
+/// Length of a subscription's authentication secret
+const AUTH_SECRET_LEN: usize = 16;
+
+/// Application server key pair used to sign VAPID tokens
+pub struct VapidKey {
+    key_pair: EcdsaKeyPair,
+    /// Public key, base64url-encoded as expected by `PushManager.subscribe()`
+    public_key: String,
+    /// Contact URI of the server operator (`mailto:` or `https:`)
+    subject: String,
+    rng: SystemRandom,
+}
+
+impl VapidKey {
+    /// Creates a key from base64url-encoded raw keys, as printed by
+    /// `npx web-push generate-vapid-keys`
+    ///
+    /// # Arguments
+    /// * `public_key` - Uncompressed P-256 public key (65 bytes)
+    /// * `private_key` - P-256 private scalar (32 bytes)
+    /// * `subject` - Contact URI sent to push services, e.g. `mailto:admin@example.com`
+    pub fn new(public_key: &str, private_key: &str, subject: &str) -> Result<Self> {
+        let public_bytes = URL_SAFE_NO_PAD
+            .decode(public_key.trim_end_matches('='))
+            .context("VAPID public key is not valid base64url")?;
+        let private_bytes = URL_SAFE_NO_PAD
+            .decode(private_key.trim_end_matches('='))
+            .context("VAPID private key is not valid base64url")?;
+
+        let rng = SystemRandom::new();
+        let key_pair = EcdsaKeyPair::from_private_key_and_public_key(
+            &ECDSA_P256_SHA256_FIXED_SIGNING,
+            &private_bytes,
+            &public_bytes,
+            &rng,
+        )
+        .map_err(|e| anyhow!("Invalid VAPID key pair: {}", e))?;
+
+        Ok(Self {
+            key_pair,
+            public_key: URL_SAFE_NO_PAD.encode(&public_bytes),
+            subject: subject.to_string(),
+            rng,
+        })
+    }
+
+    /// Public key to pass as `applicationServerKey` when subscribing
+    pub fn public_key(&self) -> &str {
+        &self.public_key
+    }
+
+    /// Builds the `Authorization` header value for a push service endpoint
+    pub fn authorization(&self, endpoint: &str) -> Result<String> {
+        let url = reqwest::Url::parse(endpoint)
+            .context(format!("Invalid push endpoint: {}", endpoint))?;
+        let audience = url.origin().ascii_serialization();
+        let expires = chrono::Utc::now().timestamp() + VAPID_TOKEN_SECONDS;
+
+        let header = URL_SAFE_NO_PAD.encode(r#"{"typ":"JWT","alg":"ES256"}"#);
+        let claims = URL_SAFE_NO_PAD.encode(
+            serde_json::json!({ "aud": audience, "exp": expires, "sub": self.subject }).to_string(),
+        );
+        let signing_input = format!("{}.{}", header, claims);
+        let signature = self
+            .key_pair
+            .sign(&self.rng, signing_input.as_bytes())
+            .map_err(|_| anyhow!("Failed to sign VAPID token"))?;
+
+        Ok(format!(
+            "vapid t={}.{}, k={}",
+            signing_input,
+            URL_SAFE_NO_PAD.encode(signature.as_ref()),
+            self.public_key
+        ))
+    }
+}
+
+/// Output length for HKDF expansion
+struct Len(usize);
+
+impl KeyType for Len {
+    fn len(&self) -> usize {
+        self.0
+    }
+}
+
+/// HKDF-SHA256 extract and expand into `out`
+fn hkdf(salt: &[u8], ikm: &[u8], info: &[&[u8]], out: &mut [u8]) -> Result<()> {
+    Salt::new(HKDF_SHA256, salt)
+        .extract(ikm)
+        .expand(info, Len(out.len()))
+        .and_then(|okm| okm.fill(out))
+        .map_err(|_| anyhow!("HKDF expansion failed"))
+}
+
+/// Decodes a subscription's public key and authentication secret
+///
+/// # Errors
+/// Returns an error if either is not valid base64url or has the wrong length
+pub fn decode_subscription_keys(p256dh: &str, auth: &str) -> Result<(Vec<u8>, Vec<u8>)> {
+    let ua_public = URL_SAFE_NO_PAD
+        .decode(p256dh.trim_end_matches('='))
+        .context("Subscription p256dh key is not valid base64url")?;
+    let auth_secret = URL_SAFE_NO_PAD
+        .decode(auth.trim_end_matches('='))
+        .context("Subscription auth secret is not valid base64url")?;
+    if ua_public.len() != PUBLIC_KEY_LEN {
+        bail!("Subscription p256dh key must be an uncompressed P-256 point");
+    }
+    if auth_secret.len() != AUTH_SECRET_LEN {
+        bail!("Subscription auth secret must be 16 bytes");
+    }
+    Ok((ua_public, auth_secret))
+}
+
+/// Encrypts a push message for a subscription with the `aes128gcm` content encoding
+///
+/// # Arguments
+/// * `p256dh` - The subscription's base64url-encoded P-256 public key
+/// * `auth` - The subscription's base64url-encoded authentication secret
+/// * `payload` - Message to encrypt
+///
+/// # Returns
+/// The request body: the content coding header followed by a single encrypted record
+pub fn encrypt(p256dh: &str, auth: &str, payload: &[u8]) -> Result<Vec<u8>> {
+    let (ua_public, auth_secret) = decode_subscription_keys(p256dh, auth)?;
+
+    let rng = SystemRandom::new();
+    let as_private = EphemeralPrivateKey::generate(&ECDH_P256, &rng)
+        .map_err(|_| anyhow!("Failed to generate ephemeral key"))?;
+    let as_public = as_private
+        .compute_public_key()
+        .map_err(|_| anyhow!("Failed to compute ephemeral public key"))?;
+    let as_public = as_public.as_ref();
+
+    let mut salt = [0u8; 16];
+    rng.fill(&mut salt)
+        .map_err(|_| anyhow!("Failed to generate salt"))?;
+
+    let ecdh_secret = agree_ephemeral(
+        as_private,
+        &UnparsedPublicKey::new(&ECDH_P256, &ua_public),
+        |secret| secret.to_vec(),
+    )
+    .map_err(|_| anyhow!("Subscription p256dh key is not a valid P-256 point"))?;
+
+    encrypt_record(
+        &ua_public,
+        &auth_secret,
+        as_public,
+        &ecdh_secret,
+        &salt,
+        payload,
+    )
+}
+
+/// Builds the request body from the key agreement's result and a salt
+///
+/// # Arguments
+/// * `ua_public` - The subscription's public key
+/// * `auth_secret` - The subscription's authentication secret
+/// * `as_public` - The ephemeral public key of the application server
+/// * `ecdh_secret` - Shared secret of the ephemeral key and the subscription's key
+/// * `salt` - Random salt of the message
+/// * `payload` - Message to encrypt
+fn encrypt_record(
+    ua_public: &[u8],
+    auth_secret: &[u8],
+    as_public: &[u8],
+    ecdh_secret: &[u8],
+    salt: &[u8; 16],
+    payload: &[u8],
+) -> Result<Vec<u8>> {
+    // Combine the shared secret with the subscription's auth secret
+    let mut ikm = [0u8; 32];
+    hkdf(
+        auth_secret,
+        ecdh_secret,
+        &[b"WebPush: info\0", ua_public, as_public],
+        &mut ikm,
+    )?;
+
+    let mut cek = [0u8; 16];
+    hkdf(salt, &ikm, &[b"Content-Encoding: aes128gcm\0"], &mut cek)?;
+    let mut nonce = [0u8; 12];
+    hkdf(salt, &ikm, &[b"Content-Encoding: nonce\0"], &mut nonce)?;
+
+    // A single record, terminated by the last-record delimiter
+    let mut record = payload.to_vec();
+    record.push(2);
+
+    let key = LessSafeKey::new(
+        UnboundKey::new(&AES_128_GCM, &cek).map_err(|_| anyhow!("Invalid content key"))?,
+    );
+    key.seal_in_place_append_tag(
+        Nonce::assume_unique_for_key(nonce),
+        Aad::empty(),
+        &mut record,
+    )
+    .map_err(|_| anyhow!("Failed to encrypt push message"))?;
+
+    let mut body = Vec::with_capacity(16 + 4 + 1 + as_public.len() + record.len());
+    body.extend_from_slice(salt);
+    body.extend_from_slice(&RECORD_SIZE.to_be_bytes());
+    body.push(as_public.len() as u8);
+    body.extend_from_slice(as_public);
+    body.extend_from_slice(&record);
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::signature::{ECDSA_P256_SHA256_FIXED, UnparsedPublicKey as SignaturePublicKey};
+
+    // Example from RFC 8291, Appendix A
+    const PLAINTEXT: &str = "V2hlbiBJIGdyb3cgdXAsIEkgd2FudCB0byBiZSBhIHdhdGVybWVsb24";
+    const AS_PUBLIC: &str =
+        "BP4z9KsN6nGRTbVYI_c7VJSPQTBtkgcy27mlmlMoZIIgDll6e3vCYLocInmYWAmS6TlzAC8wEqKK6PBru3jl7A8";
+    const AS_PRIVATE: &str = "yfWPiYE-n46HLnH0KqZOF1fJJU3MYrct3AELtAQ-oRw";
+    const UA_PUBLIC: &str =
+        "BCVxsr7N_eNgVRqvHtD0zTZsEc6-VV-JvLexhqUzORcxaOzi6-AYWXvTBHm4bjyPjs7Vd8pZGH6SRpkNtoIAiw4";
+    const SALT: &str = "DGv6ra1nlYgDCS1FRnbzlw";
+    const AUTH_SECRET: &str = "BTBZMqHH6r4Tts7J_aSIgg";
+    const ECDH_SECRET: &str = "kyrL1jIIOHEzg3sM2ZWRHDRB62YACZhhSlknJ672kSs";
+    const MESSAGE: &str = "DGv6ra1nlYgDCS1FRnbzlwAAEABBBP4z9KsN6nGRTbVYI_c7VJSPQTBtkgcy27mlmlMoZIIgDll6e3vCYLocInmYWAmS6TlzAC8wEqKK6PBru3jl7A_yl95bQpu6cVPTpK4Mqgkf1CXztLVBSt2Ks3oZwbuwXPXLWyouBWLVWGNWQexSgSxsj_Qulcy4a-fN";
+
+    fn decode(value: &str) -> Vec<u8> {
+        URL_SAFE_NO_PAD.decode(value).unwrap()
+    }
+
+    #[test]
+    fn test_encrypt_record_matches_rfc_8291_example() {
+        let salt: [u8; 16] = decode(SALT).try_into().unwrap();
+        let body = encrypt_record(
+            &decode(UA_PUBLIC),
+            &decode(AUTH_SECRET),
+            &decode(AS_PUBLIC),
+            &decode(ECDH_SECRET),
+            &salt,
+            &decode(PLAINTEXT),
+        )
+        .unwrap();
+
+        assert_eq!(URL_SAFE_NO_PAD.encode(body), MESSAGE);
+    }
+
+    #[test]
+    fn test_encrypt_writes_header_for_subscription() {
+        let body = encrypt(UA_PUBLIC, AUTH_SECRET, b"Hello").unwrap();
+
+        // Salt, record size, key length, ephemeral key, then the payload, delimiter and tag
+        assert_eq!(body[16..20], RECORD_SIZE.to_be_bytes());
+        assert_eq!(body[20] as usize, PUBLIC_KEY_LEN);
+        assert_eq!(body.len(), 21 + PUBLIC_KEY_LEN + 5 + 1 + 16);
+        assert!(encrypt(UA_PUBLIC, "c2hvcnQ", b"Hello").is_err());
+    }
+
+    #[test]
+    fn test_authorization_is_signed_vapid_token() {
+        let key = VapidKey::new(AS_PUBLIC, AS_PRIVATE, "mailto:admin@example.com").unwrap();
+        let header = key
+            .authorization("https://push.example.net/send/abc?x=1")
+            .unwrap();
+
+        let (token, public_key) = header
+            .strip_prefix("vapid t=")
+            .and_then(|rest| rest.split_once(", k="))
+            .unwrap();
+        assert_eq!(public_key, AS_PUBLIC);
+
+        let (signing_input, signature) = token.rsplit_once('.').unwrap();
+        SignaturePublicKey::new(&ECDSA_P256_SHA256_FIXED, decode(public_key))
+            .verify(signing_input.as_bytes(), &decode(signature))
+            .unwrap();
+
+        let (_, claims) = signing_input.split_once('.').unwrap();
+        let claims: serde_json::Value = serde_json::from_slice(&decode(claims)).unwrap();
+        assert_eq!(claims["aud"], "https://push.example.net");
+        assert_eq!(claims["sub"], "mailto:admin@example.com");
+        let expires = claims["exp"].as_i64().unwrap() - chrono::Utc::now().timestamp();
+        assert!(expires > 0 && expires <= VAPID_TOKEN_SECONDS);
+    }
+}
@@ -0,0 +1,614 @@
+//! Canonical Bible metadata embedded at compile time
+//!
+//! The 66 books of the Protestant canon in canonical order, with the number of verses in
+//! every chapter (KJV versification). Book names match the ones the reference parsers
+//! produce, e.g. "Psalms", "Song of Solomon" and "1 John".
+
+/// Which half of the Bible a book belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Testament {
+    Old,
+    New,
+}
+
+/// A book of the Bible
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Book {
+    /// Canonical name, e.g. "1 Corinthians"
+    pub name: &'static str,
+    pub testament: Testament,
+    /// Number of verses in each chapter, starting at chapter 1
+    pub chapters: &'static [u16],
+}
+
+impl Book {
+    /// Number of chapters in the book
+    pub const fn chapter_count(&self) -> i64 {
+        self.chapters.len() as i64
+    }
+
+    /// Number of verses in the whole book
+    pub fn verse_count(&self) -> i64 {
+        self.chapters.iter().map(|&verses| i64::from(verses)).sum()
+    }
+
+    /// Number of verses in a chapter (starting at 1), or `None` past the last chapter
+    pub fn chapter_verse_count(&self, chapter: i64) -> Option<i64> {
+        let index = usize::try_from(chapter).ok()?.checked_sub(1)?;
+        self.chapters.get(index).map(|&verses| i64::from(verses))
+    }
+
+    /// Whether the book has a single chapter, so references may leave the chapter out
+    /// ("Jude 24-25")
+    pub const fn is_single_chapter(&self) -> bool {
+        self.chapters.len() == 1
+    }
+}
+
+/// Number of books in the Old Testament, which come first in `BOOKS`
+const OLD_TESTAMENT_LEN: usize = 39;
+
+/// All books in canonical order
+pub const BOOKS: &[Book] = &[
+    // Old Testament
+    Book {
+        name: "Genesis",
+        testament: Testament::Old,
+        chapters: &[
+            31, 25, 24, 26, 32, 22, 24, 22, 29, 32, 32, 20, 18, 24, 21, 16, 27, 33, 38, 18, 34, 24,
+            20, 67, 34, 35, 46, 22, 35, 43, 55, 32, 20, 31, 29, 43, 36, 30, 23, 23, 57, 38, 34, 34,
+            28, 34, 31, 22, 33, 26,
+        ],
+    },
+    Book {
+        name: "Exodus",
+        testament: Testament::Old,
+        chapters: &[
+            22, 25, 22, 31, 23, 30, 25, 32, 35, 29, 10, 51, 22, 31, 27, 36, 16, 27, 25, 26, 36, 31,
+            33, 18, 40, 37, 21, 43, 46, 38, 18, 35, 23, 35, 35, 38, 29, 31, 43, 38,
+        ],
+    },
+    Book {
+        name: "Leviticus",
+        testament: Testament::Old,
+        chapters: &[
+            17, 16, 17, 35, 19, 30, 38, 36, 24, 20, 47, 8, 59, 57, 33, 34, 16, 30, 37, 27, 24, 33,
+            44, 23, 55, 46, 34,
+        ],
+    },
+    Book {
+        name: "Numbers",
+        testament: Testament::Old,
+        chapters: &[
+            54, 34, 51, 49, 31, 27, 89, 26, 23, 36, 35, 16, 33, 45, 41, 50, 13, 32, 22, 29, 35, 41,
+            30, 25, 18, 65, 23, 31, 40, 16, 54, 42, 56, 29, 34, 13,
+        ],
+    },
+    Book {
+        name: "Deuteronomy",
+        testament: Testament::Old,
+        chapters: &[
+            46, 37, 29, 49, 33, 25, 26, 20, 29, 22, 32, 32, 18, 29, 23, 22, 20, 22, 21, 20, 23, 30,
+            25, 22, 19, 19, 26, 68, 29, 20, 30, 52, 29, 12,
+        ],
+    },
+    Book {
+        name: "Joshua",
+        testament: Testament::Old,
+        chapters: &[
+            18, 24, 17, 24, 15, 27, 26, 35, 27, 43, 23, 24, 33, 15, 63, 10, 18, 28, 51, 9, 45, 34,
+            16, 33,
+        ],
+    },
+    Book {
+        name: "Judges",
+        testament: Testament::Old,
+        chapters: &[
+            36, 23, 31, 24, 31, 40, 25, 35, 57, 18, 40, 15, 25, 20, 20, 31, 13, 31, 30, 48, 25,
+        ],
+    },
+    Book {
+        name: "Ruth",
+        testament: Testament::Old,
+        chapters: &[22, 23, 18, 22],
+    },
+    Book {
+        name: "1 Samuel",
+        testament: Testament::Old,
+        chapters: &[
+            28, 36, 21, 22, 12, 21, 17, 22, 27, 27, 15, 25, 23, 52, 35, 23, 58, 30, 24, 42, 15, 23,
+            29, 22, 44, 25, 12, 25, 11, 31, 13,
+        ],
+    },
+    Book {
+        name: "2 Samuel",
+        testament: Testament::Old,
+        chapters: &[
+            27, 32, 39, 12, 25, 23, 29, 18, 13, 19, 27, 31, 39, 33, 37, 23, 29, 33, 43, 26, 22, 51,
+            39, 25,
+        ],
+    },
+    Book {
+        name: "1 Kings",
+        testament: Testament::Old,
+        chapters: &[
+            53, 46, 28, 34, 18, 38, 51, 66, 28, 29, 43, 33, 34, 31, 34, 34, 24, 46, 21, 43, 29, 53,
+        ],
+    },
+    Book {
+        name: "2 Kings",
+        testament: Testament::Old,
+        chapters: &[
+            18, 25, 27, 44, 27, 33, 20, 29, 37, 36, 21, 21, 25, 29, 38, 20, 41, 37, 37, 21, 26, 20,
+            37, 20, 30,
+        ],
+    },
+    Book {
+        name: "1 Chronicles",
+        testament: Testament::Old,
+        chapters: &[
+            54, 55, 24, 43, 26, 81, 40, 40, 44, 14, 47, 40, 14, 17, 29, 43, 27, 17, 19, 8, 30, 19,
+            32, 31, 31, 32, 34, 21, 30,
+        ],
+    },
+    Book {
+        name: "2 Chronicles",
+        testament: Testament::Old,
+        chapters: &[
+            17, 18, 17, 22, 14, 42, 22, 18, 31, 19, 23, 16, 22, 15, 19, 14, 19, 34, 11, 37, 20, 12,
+            21, 27, 28, 23, 9, 27, 36, 27, 21, 33, 25, 33, 27, 23,
+        ],
+    },
+    Book {
+        name: "Ezra",
+        testament: Testament::Old,
+        chapters: &[11, 70, 13, 24, 17, 22, 28, 36, 15, 44],
+    },
+    Book {
+        name: "Nehemiah",
+        testament: Testament::Old,
+        chapters: &[11, 20, 32, 23, 19, 19, 73, 18, 38, 39, 36, 47, 31],
+    },
+    Book {
+        name: "Esther",
+        testament: Testament::Old,
+        chapters: &[22, 23, 15, 17, 14, 14, 10, 17, 32, 3],
+    },
+    Book {
+        name: "Job",
+        testament: Testament::Old,
+        chapters: &[
+            22, 13, 26, 21, 27, 30, 21, 22, 35, 22, 20, 25, 28, 22, 35, 22, 16, 21, 29, 29, 34, 30,
+            17, 25, 6, 14, 23, 28, 25, 31, 40, 22, 33, 37, 16, 33, 24, 41, 30, 24, 34, 17,
+        ],
+    },
+    Book {
+        name: "Psalms",
+        testament: Testament::Old,
+        chapters: &[
+            6, 12, 8, 8, 12, 10, 17, 9, 20, 18, 7, 8, 6, 7, 5, 11, 15, 50, 14, 9, 13, 31, 6, 10,
+            22, 12, 14, 9, 11, 12, 24, 11, 22, 22, 28, 12, 40, 22, 13, 17, 13, 11, 5, 26, 17, 11,
+            9, 14, 20, 23, 19, 9, 6, 7, 23, 13, 11, 11, 17, 12, 8, 12, 11, 10, 13, 20, 7, 35, 36,
+            5, 24, 20, 28, 23, 10, 12, 20, 72, 13, 19, 16, 8, 18, 12, 13, 17, 7, 18, 52, 17, 16,
+            15, 5, 23, 11, 13, 12, 9, 9, 5, 8, 28, 22, 35, 45, 48, 43, 13, 31, 7, 10, 10, 9, 8, 18,
+            19, 2, 29, 176, 7, 8, 9, 4, 8, 5, 6, 5, 6, 8, 8, 3, 18, 3, 3, 21, 26, 9, 8, 24, 13, 10,
+            7, 12, 15, 21, 10, 20, 14, 9, 6,
+        ],
+    },
+    Book {
+        name: "Proverbs",
+        testament: Testament::Old,
+        chapters: &[
+            33, 22, 35, 27, 23, 35, 27, 36, 18, 32, 31, 28, 25, 35, 33, 33, 28, 24, 29, 30, 31, 29,
+            35, 34, 28, 28, 27, 28, 27, 33, 31,
+        ],
+    },
+    Book {
+        name: "Ecclesiastes",
+        testament: Testament::Old,
+        chapters: &[18, 26, 22, 16, 20, 12, 29, 17, 18, 20, 10, 14],
+    },
+    Book {
+        name: "Song of Solomon",
+        testament: Testament::Old,
+        chapters: &[17, 17, 11, 16, 16, 13, 13, 14],
+    },
+    Book {
+        name: "Isaiah",
+        testament: Testament::Old,
+        chapters: &[
+            31, 22, 26, 6, 30, 13, 25, 22, 21, 34, 16, 6, 22, 32, 9, 14, 14, 7, 25, 6, 17, 25, 18,
+            23, 12, 21, 13, 29, 24, 33, 9, 20, 24, 17, 10, 22, 38, 22, 8, 31, 29, 25, 28, 28, 25,
+            13, 15, 22, 26, 11, 23, 15, 12, 17, 13, 12, 21, 14, 21, 22, 11, 12, 19, 12, 25, 24,
+        ],
+    },
+    Book {
+        name: "Jeremiah",
+        testament: Testament::Old,
+        chapters: &[
+            19, 37, 25, 31, 31, 30, 34, 22, 26, 25, 23, 17, 27, 22, 21, 21, 27, 23, 15, 18, 14, 30,
+            40, 10, 38, 24, 22, 17, 32, 24, 40, 44, 26, 22, 19, 32, 21, 28, 18, 16, 18, 22, 13, 30,
+            5, 28, 7, 47, 39, 46, 64, 34,
+        ],
+    },
+    Book {
+        name: "Lamentations",
+        testament: Testament::Old,
+        chapters: &[22, 22, 66, 22, 22],
+    },
+    Book {
+        name: "Ezekiel",
+        testament: Testament::Old,
+        chapters: &[
+            28, 10, 27, 17, 17, 14, 27, 18, 11, 22, 25, 28, 23, 23, 8, 63, 24, 32, 14, 49, 32, 31,
+            49, 27, 17, 21, 36, 26, 21, 26, 18, 32, 33, 31, 15, 38, 28, 23, 29, 49, 26, 20, 27, 31,
+            25, 24, 23, 35,
+        ],
+    },
+    Book {
+        name: "Daniel",
+        testament: Testament::Old,
+        chapters: &[21, 49, 30, 37, 31, 28, 28, 27, 27, 21, 45, 13],
+    },
+    Book {
+        name: "Hosea",
+        testament: Testament::Old,
+        chapters: &[11, 23, 5, 19, 15, 11, 16, 14, 17, 15, 12, 14, 16, 9],
+    },
+    Book {
+        name: "Joel",
+        testament: Testament::Old,
+        chapters: &[20, 32, 21],
+    },
+    Book {
+        name: "Amos",
+        testament: Testament::Old,
+        chapters: &[15, 16, 15, 13, 27, 14, 17, 14, 15],
+    },
+    Book {
+        name: "Obadiah",
+        testament: Testament::Old,
+        chapters: &[21],
+    },
+    Book {
+        name: "Jonah",
+        testament: Testament::Old,
+        chapters: &[17, 10, 10, 11],
+    },
+    Book {
+        name: "Micah",
+        testament: Testament::Old,
+        chapters: &[16, 13, 12, 13, 15, 16, 20],
+    },
+    Book {
+        name: "Nahum",
+        testament: Testament::Old,
+        chapters: &[15, 13, 19],
+    },
+    Book {
+        name: "Habakkuk",
+        testament: Testament::Old,
+        chapters: &[17, 20, 19],
+    },
+    Book {
+        name: "Zephaniah",
+        testament: Testament::Old,
+        chapters: &[18, 15, 20],
+    },
+    Book {
+        name: "Haggai",
+        testament: Testament::Old,
+        chapters: &[15, 23],
+    },
+    Book {
+        name: "Zechariah",
+        testament: Testament::Old,
+        chapters: &[21, 13, 10, 14, 11, 15, 14, 23, 17, 12, 17, 14, 9, 21],
+    },
+    Book {
+        name: "Malachi",
+        testament: Testament::Old,
+        chapters: &[14, 17, 18, 6],
+    },
+    // New Testament
+    Book {
+        name: "Matthew",
+        testament: Testament::New,
+        chapters: &[
+            25, 23, 17, 25, 48, 34, 29, 34, 38, 42, 30, 50, 58, 36, 39, 28, 27, 35, 30, 34, 46, 46,
+            39, 51, 46, 75, 66, 20,
+        ],
+    },
+    Book {
+        name: "Mark",
+        testament: Testament::New,
+        chapters: &[
+            45, 28, 35, 41, 43, 56, 37, 38, 50, 52, 33, 44, 37, 72, 47, 20,
+        ],
+    },
+    Book {
+        name: "Luke",
+        testament: Testament::New,
+        chapters: &[
+            80, 52, 38, 44, 39, 49, 50, 56, 62, 42, 54, 59, 35, 35, 32, 31, 37, 43, 48, 47, 38, 71,
+            56, 53,
+        ],
+    },
+    Book {
+        name: "John",
+        testament: Testament::New,
+        chapters: &[
+            51, 25, 36, 54, 47, 71, 53, 59, 41, 42, 57, 50, 38, 31, 27, 33, 26, 40, 42, 31, 25,
+        ],
+    },
+    Book {
+        name: "Acts",
+        testament: Testament::New,
+        chapters: &[
+            26, 47, 26, 37, 42, 15, 60, 40, 43, 48, 30, 25, 52, 28, 41, 40, 34, 28, 41, 38, 40, 30,
+            35, 27, 27, 32, 44, 31,
+        ],
+    },
+    Book {
+        name: "Romans",
+        testament: Testament::New,
+        chapters: &[
+            32, 29, 31, 25, 21, 23, 25, 39, 33, 21, 36, 21, 14, 23, 33, 27,
+        ],
+    },
+    Book {
+        name: "1 Corinthians",
+        testament: Testament::New,
+        chapters: &[
+            31, 16, 23, 21, 13, 20, 40, 13, 27, 33, 34, 31, 13, 40, 58, 24,
+        ],
+    },
+    Book {
+        name: "2 Corinthians",
+        testament: Testament::New,
+        chapters: &[24, 17, 18, 18, 21, 18, 16, 24, 15, 18, 33, 21, 14],
+    },
+    Book {
+        name: "Galatians",
+        testament: Testament::New,
+        chapters: &[24, 21, 29, 31, 26, 18],
+    },
+    Book {
+        name: "Ephesians",
+        testament: Testament::New,
+        chapters: &[23, 22, 21, 32, 33, 24],
+    },
+    Book {
+        name: "Philippians",
+        testament: Testament::New,
+        chapters: &[30, 30, 21, 23],
+    },
+    Book {
+        name: "Colossians",
+        testament: Testament::New,
+        chapters: &[29, 23, 25, 18],
+    },
+    Book {
+        name: "1 Thessalonians",
+        testament: Testament::New,
+        chapters: &[10, 20, 13, 18, 28],
+    },
+    Book {
+        name: "2 Thessalonians",
+        testament: Testament::New,
+        chapters: &[12, 17, 18],
+    },
+    Book {
+        name: "1 Timothy",
+        testament: Testament::New,
+        chapters: &[20, 15, 16, 16, 25, 21],
+    },
+    Book {
+        name: "2 Timothy",
+        testament: Testament::New,
+        chapters: &[18, 26, 17, 22],
+    },
+    Book {
+        name: "Titus",
+        testament: Testament::New,
+        chapters: &[16, 15, 15],
+    },
+    Book {
+        name: "Philemon",
+        testament: Testament::New,
+        chapters: &[25],
+    },
+    Book {
+        name: "Hebrews",
+        testament: Testament::New,
+        chapters: &[14, 18, 19, 16, 14, 20, 28, 13, 28, 39, 40, 29, 25],
+    },
+    Book {
+        name: "James",
+        testament: Testament::New,
+        chapters: &[27, 26, 18, 17, 20],
+    },
+    Book {
+        name: "1 Peter",
+        testament: Testament::New,
+        chapters: &[25, 25, 22, 19, 14],
+    },
+    Book {
+        name: "2 Peter",
+        testament: Testament::New,
+        chapters: &[21, 22, 18],
+    },
+    Book {
+        name: "1 John",
+        testament: Testament::New,
+        chapters: &[10, 29, 24, 21, 21],
+    },
+    Book {
+        name: "2 John",
+        testament: Testament::New,
+        chapters: &[13],
+    },
+    Book {
+        name: "3 John",
+        testament: Testament::New,
+        chapters: &[14],
+    },
+    Book {
+        name: "Jude",
+        testament: Testament::New,
+        chapters: &[25],
+    },
+    Book {
+        name: "Revelation",
+        testament: Testament::New,
+        chapters: &[
+            20, 29, 22, 11, 14, 17, 17, 13, 21, 11, 19, 17, 18, 20, 8, 21, 18, 24, 21, 15, 27, 21,
+        ],
+    },
+];
+
+/// Old Testament books in canonical order
+pub const OLD_TESTAMENT: &[Book] = BOOKS.split_at(OLD_TESTAMENT_LEN).0;
+
+/// New Testament books in canonical order
+pub const NEW_TESTAMENT: &[Book] = BOOKS.split_at(OLD_TESTAMENT_LEN).1;
+
+/// Names of all books in canonical order
+pub fn all_books() -> impl Iterator<Item = &'static str> {
+    BOOKS.iter().map(|book| book.name)
+}
+
+/// Normalizes a book name to its display name: "Psalm" (as in references, ignoring case)
+/// becomes "Psalms", and any other name is returned as is
+pub fn normalize_book_name(name: &str) -> &str {
+    if name.eq_ignore_ascii_case("Psalm") {
+        "Psalms"
+    } else {
+        name
+    }
+}
+
+/// Looks up a book by name, ignoring case ("psalm" and "Psalm" find "Psalms")
+pub fn book(name: &str) -> Option<&'static Book> {
+    let name = normalize_book_name(name);
+    BOOKS
+        .iter()
+        .find(|book| book.name.eq_ignore_ascii_case(name))
+}
+
+/// Canonical name of a book, looked up like `book`
+pub fn find_book(name: &str) -> Option<&'static str> {
+    book(name).map(|book| book.name)
+}
+
+/// Position of a book in canonical order (Genesis is 0), looked up like `book`
+pub fn position(name: &str) -> Option<usize> {
+    let name = find_book(name)?;
+    BOOKS.iter().position(|book| book.name == name)
+}
+
+/// Returns the number of chapters in a book, or `None` for an unknown book name
+pub fn chapter_count(name: &str) -> Option<i64> {
+    book(name).map(Book::chapter_count)
+}
+
+/// Returns the number of verses in a book, or `None` for an unknown book name
+pub fn verse_count(name: &str) -> Option<i64> {
+    book(name).map(Book::verse_count)
+}
+
+/// Returns the number of verses in a chapter of a book, or `None` for an unknown book name
+/// or a chapter the book does not have
+pub fn chapter_verse_count(name: &str, chapter: i64) -> Option<i64> {
+    book(name)?.chapter_verse_count(chapter)
+}
+
+/// Checks if a book name is a single-chapter book (Obadiah, Philemon, 2 John, 3 John, Jude)
+pub fn is_single_chapter_book(name: &str) -> bool {
+    book(name).is_some_and(Book::is_single_chapter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_testaments() {
+        assert_eq!(BOOKS.len(), 66);
+        assert_eq!(OLD_TESTAMENT.len(), 39);
+        assert_eq!(NEW_TESTAMENT.len(), 27);
+        assert_eq!(OLD_TESTAMENT.last().unwrap().name, "Malachi");
+        assert_eq!(NEW_TESTAMENT[0].name, "Matthew");
+        assert!(
+            OLD_TESTAMENT
+                .iter()
+                .all(|book| book.testament == Testament::Old)
+        );
+        assert!(
+            NEW_TESTAMENT
+                .iter()
+                .all(|book| book.testament == Testament::New)
+        );
+        assert_eq!(position("Genesis"), Some(0));
+        assert_eq!(position("revelation"), Some(65));
+    }
+
+    #[test]
+    fn test_normalize_book_name() {
+        assert_eq!(normalize_book_name("Psalm"), "Psalms");
+        assert_eq!(normalize_book_name("psalm"), "Psalms");
+        assert_eq!(normalize_book_name("Psalms"), "Psalms");
+        assert_eq!(normalize_book_name("Romans"), "Romans");
+        assert_eq!(find_book("PSALM"), Some("Psalms"));
+    }
+
+    #[test]
+    fn test_chapter_count() {
+        assert_eq!(chapter_count("Genesis"), Some(50));
+        assert_eq!(chapter_count("psalm"), Some(150));
+        assert_eq!(chapter_count("Jude"), Some(1));
+        assert_eq!(chapter_count("Revelation"), Some(22));
+        assert_eq!(chapter_count("Hezekiah"), None);
+
+        let chapters = |books: &[Book]| books.iter().map(Book::chapter_count).sum::<i64>();
+        assert_eq!(chapters(OLD_TESTAMENT), 929);
+        assert_eq!(chapters(BOOKS), 1189);
+    }
+
+    #[test]
+    fn test_verse_count() {
+        assert_eq!(verse_count("Genesis"), Some(1533));
+        assert_eq!(verse_count("Psalms"), Some(2461));
+        assert_eq!(verse_count("jude"), Some(25));
+        assert_eq!(verse_count("Hezekiah"), None);
+
+        let verses = |books: &[Book]| books.iter().map(Book::verse_count).sum::<i64>();
+        assert_eq!(verses(OLD_TESTAMENT), 23145);
+        assert_eq!(verses(NEW_TESTAMENT), 7957);
+        assert_eq!(verses(BOOKS), 31102);
+    }
+
+    #[test]
+    fn test_chapter_verse_count() {
+        assert_eq!(chapter_verse_count("Psalms", 117), Some(2));
+        assert_eq!(chapter_verse_count("Psalm", 119), Some(176));
+        assert_eq!(chapter_verse_count("John", 3), Some(36));
+        assert_eq!(chapter_verse_count("3 John", 1), Some(14));
+        assert_eq!(chapter_verse_count("John", 22), None);
+        assert_eq!(chapter_verse_count("John", 0), None);
+        assert_eq!(chapter_verse_count("Hezekiah", 1), None);
+    }
+
+    #[test]
+    fn test_is_single_chapter_book() {
+        let single: Vec<_> = BOOKS
+            .iter()
+            .filter(|book| book.is_single_chapter())
+            .map(|book| book.name)
+            .collect();
+        assert_eq!(single, ["Obadiah", "Philemon", "2 John", "3 John", "Jude"]);
+        assert!(is_single_chapter_book("jude"));
+        assert!(!is_single_chapter_book("1 John"));
+        assert!(!is_single_chapter_book("Hezekiah"));
+    }
+}
@@ -0,0 +1,231 @@
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::exemptions::active_week_fraction;
+use crate::goals::GoalMetric;
+use crate::models::FaithWeekStats;
+
+/// Default weekly time budget: 7 hours
+pub const DEFAULT_BUDGET_MINUTES: f64 = 420.0;
+
+/// Activities a budget is split into
+const BUDGET_METRICS: [GoalMetric; 4] = [
+    GoalMetric::AnkiMinutes,
+    GoalMetric::ReadingMinutes,
+    GoalMetric::AtChurchMinutes,
+    GoalMetric::PrayerMinutes,
+];
+
+/// Weekly time set aside for faith activities, optionally split per activity
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TimeBudget {
+    /// Minutes budgeted per week
+    pub total_minutes: f64,
+    /// Planned minutes per activity; activities without one have no over/under allocation
+    #[serde(default)]
+    pub allocation: Vec<BudgetAllocation>,
+}
+
+impl Default for TimeBudget {
+    fn default() -> Self {
+        Self {
+            total_minutes: DEFAULT_BUDGET_MINUTES,
+            allocation: Vec::new(),
+        }
+    }
+}
+
+/// Minutes of the weekly budget planned for one activity
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BudgetAllocation {
+    pub metric: GoalMetric,
+    pub minutes: f64,
+}
+
+impl TimeBudget {
+    /// Checks that the budget is positive and the allocations fit into it, once per activity
+    pub fn validate(&self) -> Result<()> {
+        if self.total_minutes <= 0.0 || !self.total_minutes.is_finite() {
+            bail!("Weekly budget must be positive");
+        }
+        for (index, allocation) in self.allocation.iter().enumerate() {
+            if !BUDGET_METRICS.contains(&allocation.metric) {
+                bail!("Cannot allocate budget to {:?}", allocation.metric);
+            }
+            if allocation.minutes < 0.0 || !allocation.minutes.is_finite() {
+                bail!("Budget of {:?} must not be negative", allocation.metric);
+            }
+            if self.allocation[..index]
+                .iter()
+                .any(|earlier| earlier.metric == allocation.metric)
+            {
+                bail!("Budget of {:?} is allocated twice", allocation.metric);
+            }
+        }
+        let allocated: f64 = self.allocation.iter().map(|a| a.minutes).sum();
+        if allocated > self.total_minutes {
+            bail!(
+                "Allocated {} minutes, more than the weekly budget of {} minutes",
+                allocated,
+                self.total_minutes
+            );
+        }
+        Ok(())
+    }
+
+    fn planned_minutes(&self, metric: GoalMetric) -> Option<f64> {
+        self.allocation
+            .iter()
+            .find(|allocation| allocation.metric == metric)
+            .map(|allocation| allocation.minutes)
+    }
+}
+
+/// Time spent on one activity in a week, against the budget
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BudgetCategory {
+    pub metric: GoalMetric,
+    pub actual_minutes: f64,
+    /// actual_minutes as a percentage of the week's budget
+    pub percent_of_budget: f64,
+    /// Minutes planned for the activity (`null` without an allocation)
+    pub planned_minutes: Option<f64>,
+    /// actual_minutes - planned_minutes: positive when over, negative when under
+    pub difference_minutes: Option<f64>,
+}
+
+/// Time spent on faith activities in a week, against the budget
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BudgetWeek {
+    /// Week start date in YYYY-MM-DD format
+    pub week_start: String,
+    /// Budget of the week, reduced by a seventh for each exempt day
+    pub budget_minutes: f64,
+    pub actual_minutes: f64,
+    /// actual_minutes as a percentage of budget_minutes
+    pub percent_of_budget: f64,
+    /// actual_minutes - budget_minutes: positive when over, negative when under
+    pub difference_minutes: f64,
+    pub categories: Vec<BudgetCategory>,
+}
+
+impl BudgetWeek {
+    pub fn new(week: &FaithWeekStats, budget: &TimeBudget) -> Self {
+        // Like weekly goals, the budget shrinks with exempt days
+        let scale = active_week_fraction(week.exempt_days);
+        let budget_minutes = budget.total_minutes * scale;
+        let percent = |minutes: f64| {
+            if budget_minutes > 0.0 {
+                (minutes / budget_minutes * 1000.0).round() / 10.0
+            } else {
+                0.0
+            }
+        };
+
+        let categories = BUDGET_METRICS
+            .iter()
+            .map(|&metric| {
+                let actual_minutes = metric.week_minutes(week);
+                let planned_minutes = budget.planned_minutes(metric).map(|m| m * scale);
+                BudgetCategory {
+                    metric,
+                    actual_minutes,
+                    percent_of_budget: percent(actual_minutes),
+                    planned_minutes,
+                    difference_minutes: planned_minutes.map(|planned| actual_minutes - planned),
+                }
+            })
+            .collect();
+
+        let actual_minutes = week.total_minutes();
+        Self {
+            week_start: week.week_start.clone(),
+            budget_minutes,
+            actual_minutes,
+            percent_of_budget: percent(actual_minutes),
+            difference_minutes: actual_minutes - budget_minutes,
+            categories,
+        }
+    }
+}
+
+/// Weekly time budget report, oldest week first
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BudgetReport {
+    pub budget: TimeBudget,
+    pub weeks: Vec<BudgetWeek>,
+}
+
+impl BudgetReport {
+    pub fn new(weeks: &[FaithWeekStats], budget: TimeBudget) -> Self {
+        Self {
+            weeks: weeks
+                .iter()
+                .map(|week| BudgetWeek::new(week, &budget))
+                .collect(),
+            budget,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_week() {
+        let budget = TimeBudget {
+            total_minutes: 420.0,
+            allocation: vec![BudgetAllocation {
+                metric: GoalMetric::ReadingMinutes,
+                minutes: 140.0,
+            }],
+        };
+        budget.validate().unwrap();
+        let week = FaithWeekStats {
+            week_start: "2025-01-05".to_string(),
+            reading_minutes: 150.0,
+            prayer_minutes: 60.0,
+            ..Default::default()
+        };
+
+        let report = BudgetWeek::new(&week, &budget);
+        assert_eq!(report.percent_of_budget, 50.0);
+        assert_eq!(report.difference_minutes, -210.0);
+        let reading = &report.categories[1];
+        assert_eq!(reading.planned_minutes, Some(140.0));
+        assert_eq!(reading.difference_minutes, Some(10.0));
+        assert_eq!(report.categories[3].difference_minutes, None);
+
+        let exempt = FaithWeekStats {
+            exempt_days: 1,
+            ..week
+        };
+        let report = BudgetWeek::new(&exempt, &budget);
+        assert_eq!(report.budget_minutes, 360.0);
+        assert_eq!(report.categories[1].planned_minutes, Some(120.0));
+    }
+
+    #[test]
+    fn test_validate_rejects_overallocation() {
+        let budget = TimeBudget {
+            total_minutes: 100.0,
+            allocation: vec![
+                BudgetAllocation {
+                    metric: GoalMetric::ReadingMinutes,
+                    minutes: 80.0,
+                },
+                BudgetAllocation {
+                    metric: GoalMetric::PrayerMinutes,
+                    minutes: 30.0,
+                },
+            ],
+        };
+        assert!(budget.validate().is_err());
+    }
+}
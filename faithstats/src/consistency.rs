@@ -0,0 +1,175 @@
+use serde::Serialize;
+
+use crate::goals::GoalMetric;
+use crate::models::FaithDayStats;
+
+/// Windows (in days, ending today) over which consistency is measured
+pub const CONSISTENCY_WINDOWS: [i32; 3] = [30, 90, 365];
+
+/// Default minutes a day needs for an activity to count as practiced
+pub const DEFAULT_MIN_MINUTES: f64 = 5.0;
+
+/// Activities tracked per day
+const DAILY_METRICS: [GoalMetric; 4] = [
+    GoalMetric::AnkiMinutes,
+    GoalMetric::ReadingMinutes,
+    GoalMetric::PrayerMinutes,
+    GoalMetric::TotalMinutes,
+];
+
+/// How regularly an activity was practiced within a window
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ActivityConsistency {
+    pub metric: GoalMetric,
+    /// Days with at least the minimum minutes
+    pub active_days: i32,
+    /// Share of non-exempt days (since the source's first recorded activity) with at least
+    /// the minimum minutes (0-100)
+    pub active_day_percent: f64,
+    /// Longest run of consecutive days below the minimum
+    pub longest_gap_days: i32,
+    /// Average minutes per day
+    pub mean_minutes: f64,
+    /// Standard deviation of the daily minutes divided by their mean; lower is steadier.
+    /// `null` when nothing was recorded.
+    pub coefficient_of_variation: Option<f64>,
+}
+
+impl ActivityConsistency {
+    /// Measures the consistency of one activity over the given days, leaving exempt days and
+    /// days before the source's history out
+    pub fn new(metric: GoalMetric, days: &[FaithDayStats], min_minutes: f64) -> Self {
+        let minutes: Vec<f64> = days
+            .iter()
+            .filter(|day| !day.exempt)
+            .filter_map(|day| metric.day_minutes(day))
+            .collect();
+
+        let mut active_days = 0;
+        let mut gap = 0;
+        let mut longest_gap_days = 0;
+        for &m in &minutes {
+            if m >= min_minutes {
+                active_days += 1;
+                gap = 0;
+            } else {
+                gap += 1;
+                longest_gap_days = longest_gap_days.max(gap);
+            }
+        }
+
+        let count = minutes.len() as f64;
+        let mean = if minutes.is_empty() {
+            0.0
+        } else {
+            minutes.iter().sum::<f64>() / count
+        };
+        let coefficient_of_variation = (mean > 0.0).then(|| {
+            let variance = minutes.iter().map(|m| (m - mean).powi(2)).sum::<f64>() / count;
+            round2(variance.sqrt() / mean)
+        });
+
+        Self {
+            metric,
+            active_days,
+            active_day_percent: if minutes.is_empty() {
+                0.0
+            } else {
+                (active_days as f64 / count * 1000.0).round() / 10.0
+            },
+            longest_gap_days,
+            mean_minutes: round2(mean),
+            coefficient_of_variation,
+        }
+    }
+}
+
+/// Consistency of every daily activity within one window
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ConsistencyWindow {
+    /// Length of the window in days, ending today
+    pub days: i32,
+    /// Exempt days within the window, which are left out of every metric
+    pub exempt_days: i32,
+    pub activities: Vec<ActivityConsistency>,
+}
+
+/// Habit consistency over the last 30, 90 and 365 days
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FaithConsistency {
+    /// Minutes a day needs for an activity to count as practiced
+    pub min_minutes: f64,
+    pub windows: Vec<ConsistencyWindow>,
+}
+
+impl FaithConsistency {
+    /// Measures each window over the most recent of `days` (oldest first), which should cover
+    /// the longest window
+    pub fn new(days: &[FaithDayStats], min_minutes: f64) -> Self {
+        let windows = CONSISTENCY_WINDOWS
+            .iter()
+            .map(|&window| {
+                let recent = &days[days.len().saturating_sub(window as usize)..];
+                ConsistencyWindow {
+                    days: window,
+                    exempt_days: recent.iter().filter(|day| day.exempt).count() as i32,
+                    activities: DAILY_METRICS
+                        .iter()
+                        .map(|metric| ActivityConsistency::new(*metric, recent, min_minutes))
+                        .collect(),
+                }
+            })
+            .collect();
+
+        Self {
+            min_minutes,
+            windows,
+        }
+    }
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reading_day(reading_minutes: f64) -> FaithDayStats {
+        FaithDayStats {
+            reading_minutes: Some(reading_minutes),
+            prayer_minutes: Some(0.0),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_activity_consistency() {
+        let days: Vec<FaithDayStats> = [14.0, 0.0, 0.0, 0.0, 14.0, 14.0]
+            .into_iter()
+            .map(reading_day)
+            .collect();
+
+        let reading = ActivityConsistency::new(GoalMetric::ReadingMinutes, &days, 5.0);
+        assert_eq!(reading.active_days, 3);
+        assert_eq!(reading.active_day_percent, 50.0);
+        assert_eq!(reading.longest_gap_days, 3);
+        assert_eq!(reading.mean_minutes, 7.0);
+        // Population standard deviation of 7.0 over a mean of 7.0
+        assert_eq!(reading.coefficient_of_variation, Some(1.0));
+
+        let mut with_vacation = days.clone();
+        with_vacation[2].exempt = true;
+        let reading = ActivityConsistency::new(GoalMetric::ReadingMinutes, &with_vacation, 5.0);
+        assert_eq!(reading.active_day_percent, 60.0);
+        assert_eq!(reading.longest_gap_days, 2);
+
+        let prayer = ActivityConsistency::new(GoalMetric::PrayerMinutes, &days, 5.0);
+        assert_eq!(prayer.longest_gap_days, 6);
+        assert_eq!(prayer.coefficient_of_variation, None);
+    }
+}
@@ -0,0 +1,117 @@
+use anyhow::{Context, Result, bail};
+use chrono::{Days, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::models::{FaithDailyStats, FaithWeeklyStats};
+
+/// A date range exempt from streaks, goals and consistency metrics (vacation, illness)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Exemption {
+    pub id: i64,
+    /// First exempt date in YYYY-MM-DD format
+    pub start: String,
+    /// Last exempt date in YYYY-MM-DD format (inclusive)
+    pub end: String,
+    /// Why the days are exempt, e.g. "Vacation"
+    pub reason: Option<String>,
+}
+
+impl Exemption {
+    /// Whether the date (YYYY-MM-DD) lies within the range
+    pub fn contains(&self, date: &str) -> bool {
+        self.start.as_str() <= date && date <= self.end.as_str()
+    }
+}
+
+/// Checks that both dates are valid and the range is not reversed
+pub fn validate_range(start: &str, end: &str) -> Result<()> {
+    let first = parse_date(start)?;
+    let last = parse_date(end)?;
+    if last < first {
+        bail!("Exemption ends ({}) before it starts ({})", end, start);
+    }
+    Ok(())
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .context(format!("Invalid date '{}' (expected YYYY-MM-DD)", date))
+}
+
+/// Fraction of a week that is not exempt, which weekly goals and budgets are scaled by
+///
+/// Each exempt day takes a seventh off; counts outside 0..=7 are clamped.
+pub fn active_week_fraction(exempt_days: i64) -> f64 {
+    (7 - exempt_days).clamp(0, 7) as f64 / 7.0
+}
+
+/// Whether any of the exemptions covers the date (YYYY-MM-DD)
+pub fn is_exempt(exemptions: &[Exemption], date: &str) -> bool {
+    exemptions.iter().any(|exemption| exemption.contains(date))
+}
+
+/// Marks the exempt days of daily stats
+pub fn with_exempt_days(mut stats: FaithDailyStats, exemptions: &[Exemption]) -> FaithDailyStats {
+    for day in &mut stats.days {
+        day.exempt = is_exempt(exemptions, &day.date);
+    }
+    stats
+}
+
+/// Counts the exempt days of each week of weekly stats
+pub fn with_exempt_weeks(
+    mut stats: FaithWeeklyStats,
+    exemptions: &[Exemption],
+) -> Result<FaithWeeklyStats> {
+    for week in &mut stats.weeks {
+        let week_start = parse_date(&week.week_start)?;
+        week.exempt_days = (0..7)
+            .filter_map(|offset| week_start.checked_add_days(Days::new(offset)))
+            .filter(|date| is_exempt(exemptions, &date.format("%Y-%m-%d").to_string()))
+            .count() as i64;
+    }
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::FaithWeekStats;
+
+    #[test]
+    fn test_active_week_fraction() {
+        assert_eq!(active_week_fraction(0), 1.0);
+        assert_eq!(active_week_fraction(7), 0.0);
+        assert_eq!(active_week_fraction(-1), 1.0);
+        assert_eq!(active_week_fraction(10), 0.0);
+        assert!((active_week_fraction(2) * 7.0 - 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_exempt_days_per_week() {
+        let exemptions = vec![Exemption {
+            id: 1,
+            start: "2025-01-10".to_string(),
+            end: "2025-01-13".to_string(),
+            reason: Some("Vacation".to_string()),
+        }];
+        let stats = FaithWeeklyStats::new(
+            ["2025-01-05", "2025-01-12", "2025-01-19"]
+                .into_iter()
+                .map(|week_start| FaithWeekStats {
+                    week_start: week_start.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+        );
+
+        let stats = with_exempt_weeks(stats, &exemptions).unwrap();
+        let exempt_days: Vec<i64> = stats.weeks.iter().map(|w| w.exempt_days).collect();
+        assert_eq!(exempt_days, vec![2, 2, 0]);
+
+        assert!(validate_range("2025-01-10", "2025-01-10").is_ok());
+        assert!(validate_range("2025-01-10", "2025-01-09").is_err());
+        assert!(validate_range("2025-13-10", "2025-01-12").is_err());
+    }
+}
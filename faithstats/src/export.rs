@@ -0,0 +1,139 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use statsutils::now_rfc3339;
+use std::io::{Seek, Write};
+use zip::ZipWriter;
+use zip::write::SimpleFileOptions;
+
+use crate::models::{FaithDailyStats, FaithWeeklyStats};
+use crate::{FaithStats, merge_daily_stats, merge_weekly_stats, places};
+
+/// Version of the archive layout, bumped whenever files are renamed or change shape
+pub const EXPORT_FORMAT_VERSION: u32 = 1;
+
+/// Number of days included in the daily series of an export archive
+pub const EXPORT_DAYS: i32 = 365;
+
+/// Number of weeks included in the weekly series of an export archive
+pub const EXPORT_WEEKS: i32 = 52;
+
+/// Describes the contents of an export archive, stored as `manifest.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportManifest {
+    pub format_version: u32,
+    /// Time the archive was generated (RFC 3339, UTC)
+    pub generated_at: String,
+    /// Number of days in each daily series
+    pub days: i32,
+    /// Number of weeks in each weekly series
+    pub weeks: i32,
+    /// Paths of the JSON files contained in the archive
+    pub files: Vec<String>,
+}
+
+/// Writes a zip archive containing JSON dumps of all derived statistics
+///
+/// The archive contains:
+/// - `manifest.json`: archive metadata (see `ExportManifest`)
+/// - `sources.json`: recorded activity range of each source
+/// - `anki/books.json`: Bible book statistics
+/// - `{anki,reading,prayer}/daily.json`: per-source daily series for the last 365 days
+/// - `{anki,reading,prayer}/weekly.json`, `arc/church_weekly.json`: per-source weekly
+///   series for the last 52 weeks
+/// - `arc/places.json`: time spent per place over the last 6 months
+/// - `faith/daily.json`, `faith/weekly.json`: the combined daily and weekly statistics
+///
+/// # Errors
+/// Returns an error if any database/export is unconfigured, unavailable or the archive cannot
+/// be written
+pub fn write_export_archive<W: Write + Seek>(writer: W, sources: &FaithStats) -> Result<()> {
+    let anki_collection = sources.anki_collection()?;
+    let koreader_db = sources.koreader_db()?;
+    let location_history = sources.location_history()?;
+    let proseuche_db = sources.proseuche_db()?;
+
+    let anki_daily = ankistats::get_last_n_days_stats(anki_collection, EXPORT_DAYS)?;
+    let reading_daily = readingstats::get_last_n_days_stats(koreader_db, EXPORT_DAYS)?;
+    let prayer_daily = prayerstats::get_last_n_days_stats(proseuche_db, EXPORT_DAYS)?;
+
+    let anki_weekly = ankistats::get_last_n_weeks_stats(anki_collection, EXPORT_WEEKS)?;
+    let reading_weekly = readingstats::get_last_n_weeks_stats(koreader_db, EXPORT_WEEKS)?;
+    let church_weekly = places::get_church_last_n_weeks_stats(location_history, EXPORT_WEEKS)?;
+    let prayer_weekly = prayerstats::get_last_n_weeks_stats(proseuche_db, EXPORT_WEEKS)?;
+
+    let faith_daily = FaithDailyStats::new(merge_daily_stats(
+        anki_daily.clone(),
+        reading_daily.clone(),
+        prayer_daily.clone(),
+    ));
+    let faith_weekly = FaithWeeklyStats::new(merge_weekly_stats(
+        anki_weekly.clone(),
+        reading_weekly.clone(),
+        church_weekly.clone(),
+        prayer_weekly.clone(),
+    ));
+
+    let mut archive = ArchiveWriter::new(writer);
+
+    archive.add_json("sources.json", &sources.data_ranges())?;
+    archive.add_json(
+        "anki/books.json",
+        &ankistats::get_bible_stats(anki_collection)?,
+    )?;
+    archive.add_json("anki/daily.json", &anki_daily)?;
+    archive.add_json("anki/weekly.json", &anki_weekly)?;
+    archive.add_json("reading/daily.json", &reading_daily)?;
+    archive.add_json("reading/weekly.json", &reading_weekly)?;
+    archive.add_json("prayer/daily.json", &prayer_daily)?;
+    archive.add_json("prayer/weekly.json", &prayer_weekly)?;
+    archive.add_json("arc/church_weekly.json", &church_weekly)?;
+    archive.add_json(
+        "arc/places.json",
+        &places::get_top_places_last_6_months(location_history, usize::MAX)?,
+    )?;
+    archive.add_json("faith/daily.json", &faith_daily)?;
+    archive.add_json("faith/weekly.json", &faith_weekly)?;
+
+    archive.finish()
+}
+
+/// Zip writer that records the files it contains for the manifest
+struct ArchiveWriter<W: Write + Seek> {
+    zip: ZipWriter<W>,
+    files: Vec<String>,
+}
+
+impl<W: Write + Seek> ArchiveWriter<W> {
+    fn new(writer: W) -> Self {
+        Self {
+            zip: ZipWriter::new(writer),
+            files: Vec::new(),
+        }
+    }
+
+    fn add_json<T: Serialize + ?Sized>(&mut self, path: &str, value: &T) -> Result<()> {
+        self.zip
+            .start_file(path, SimpleFileOptions::default())
+            .context(format!("Failed to add {} to export archive", path))?;
+        serde_json::to_writer_pretty(&mut self.zip, value)
+            .context(format!("Failed to write {} to export archive", path))?;
+        self.files.push(path.to_string());
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<()> {
+        let manifest = ExportManifest {
+            format_version: EXPORT_FORMAT_VERSION,
+            generated_at: now_rfc3339()?,
+            days: EXPORT_DAYS,
+            weeks: EXPORT_WEEKS,
+            files: std::mem::take(&mut self.files),
+        };
+        self.add_json("manifest.json", &manifest)?;
+
+        self.zip
+            .finish()
+            .context("Failed to finalize export archive")?;
+        Ok(())
+    }
+}
@@ -0,0 +1,287 @@
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::exemptions::active_week_fraction;
+use crate::models::{FaithDayStats, FaithWeekStats};
+
+/// Activity a goal is measured against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum GoalMetric {
+    AnkiMinutes,
+    ReadingMinutes,
+    AtChurchMinutes,
+    PrayerMinutes,
+    /// Sum of all faith activities
+    TotalMinutes,
+}
+
+impl GoalMetric {
+    /// Minutes recorded for this metric in the given week
+    pub fn week_minutes(&self, week: &FaithWeekStats) -> f64 {
+        match self {
+            GoalMetric::AnkiMinutes => week.anki_minutes,
+            GoalMetric::ReadingMinutes => week.reading_minutes,
+            GoalMetric::AtChurchMinutes => week.at_church_minutes,
+            GoalMetric::PrayerMinutes => week.prayer_minutes,
+            GoalMetric::TotalMinutes => week.total_minutes(),
+        }
+    }
+
+    /// Minutes recorded for this metric on the given day, or `None` if the metric is not
+    /// tracked per day (church attendance only has weekly totals) or the day is before the
+    /// source's first recorded activity
+    pub fn day_minutes(&self, day: &FaithDayStats) -> Option<f64> {
+        match self {
+            GoalMetric::AnkiMinutes => day.anki_minutes,
+            GoalMetric::ReadingMinutes => day.reading_minutes,
+            GoalMetric::AtChurchMinutes => None,
+            GoalMetric::PrayerMinutes => day.prayer_minutes,
+            GoalMetric::TotalMinutes => day.has_history().then(|| day.total_minutes()),
+        }
+    }
+
+    /// Short lowercase name of the activity for use in sentences (e.g. "reading")
+    pub fn activity_label(&self) -> &'static str {
+        match self {
+            GoalMetric::AnkiMinutes => "scripture memory",
+            GoalMetric::ReadingMinutes => "reading",
+            GoalMetric::AtChurchMinutes => "church",
+            GoalMetric::PrayerMinutes => "prayer",
+            GoalMetric::TotalMinutes => "faith activity",
+        }
+    }
+}
+
+impl FromStr for GoalMetric {
+    type Err = anyhow::Error;
+
+    /// Parses the snake_case metric name used in configuration (e.g. "reading_minutes")
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "anki_minutes" => GoalMetric::AnkiMinutes,
+            "reading_minutes" => GoalMetric::ReadingMinutes,
+            "at_church_minutes" => GoalMetric::AtChurchMinutes,
+            "prayer_minutes" => GoalMetric::PrayerMinutes,
+            "total_minutes" => GoalMetric::TotalMinutes,
+            _ => bail!("Unknown metric '{}'", s),
+        })
+    }
+}
+
+/// A weekly time target for one faith activity
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Goal {
+    /// Human-readable name (e.g. "Family reading")
+    pub name: String,
+    pub metric: GoalMetric,
+    /// Minutes to reach each week
+    pub weekly_target_minutes: f64,
+}
+
+/// A daily time target for one faith activity
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DailyGoal {
+    /// Human-readable name (e.g. "Morning prayer")
+    pub name: String,
+    pub metric: GoalMetric,
+    /// Minutes to reach each day
+    pub daily_target_minutes: f64,
+}
+
+impl DailyGoal {
+    /// Whether the goal is met on the given day; never met for metrics without daily data
+    pub fn is_met(&self, day: &FaithDayStats) -> bool {
+        self.metric
+            .day_minutes(day)
+            .is_some_and(|minutes| minutes >= self.daily_target_minutes)
+    }
+}
+
+/// Progress towards a goal in a single week
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GoalProgress {
+    pub name: String,
+    pub metric: GoalMetric,
+    /// Week start date in YYYY-MM-DD format
+    pub week_start: String,
+    /// Weekly target, reduced by a seventh for each exempt day
+    pub target_minutes: f64,
+    pub actual_minutes: f64,
+    /// Fraction of the target reached (may exceed 1.0)
+    pub progress: f64,
+    pub met: bool,
+}
+
+impl Goal {
+    /// Target of the given week, reduced by a seventh for each exempt day
+    pub fn target_minutes_for_week(&self, week: &FaithWeekStats) -> f64 {
+        self.weekly_target_minutes * active_week_fraction(week.exempt_days)
+    }
+
+    /// Evaluates this goal against the given week
+    pub fn progress_for_week(&self, week: &FaithWeekStats) -> GoalProgress {
+        let target_minutes = self.target_minutes_for_week(week);
+        let actual_minutes = self.metric.week_minutes(week);
+        let progress = if target_minutes > 0.0 {
+            actual_minutes / target_minutes
+        } else {
+            1.0
+        };
+
+        GoalProgress {
+            name: self.name.clone(),
+            metric: self.metric,
+            week_start: week.week_start.clone(),
+            target_minutes,
+            actual_minutes,
+            progress,
+            met: actual_minutes >= target_minutes,
+        }
+    }
+
+    /// Minutes by which the week lags behind an even pace towards the (exemption-adjusted)
+    /// target, given the fraction of the week that has passed (0.0 when on or ahead of pace)
+    pub fn minutes_behind_pace(&self, week: &FaithWeekStats, elapsed_fraction: f64) -> f64 {
+        let expected_minutes =
+            self.target_minutes_for_week(week) * elapsed_fraction.clamp(0.0, 1.0);
+        (expected_minutes - self.metric.week_minutes(week)).max(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_for_week() {
+        let week = FaithWeekStats {
+            week_start: "2025-01-05".to_string(),
+            anki_minutes: 30.0,
+            anki_matured_passages: 0,
+            anki_lost_passages: 0,
+            anki_cumulative_passages: 0,
+            anki_matured_references: Vec::new(),
+            anki_lost_references: Vec::new(),
+            anki_reviews: 0,
+            anki_avg_seconds_per_review: None,
+            anki_projected_minutes: None,
+            reading_minutes: 90.0,
+            at_church_minutes: 60.0,
+            prayer_minutes: 20.0,
+            exempt_days: 0,
+        };
+        let goal = Goal {
+            name: "Family reading".to_string(),
+            metric: GoalMetric::ReadingMinutes,
+            weekly_target_minutes: 120.0,
+        };
+
+        let progress = goal.progress_for_week(&week);
+        assert_eq!(progress.actual_minutes, 90.0);
+        assert_eq!(progress.progress, 0.75);
+        assert!(!progress.met);
+
+        let total = Goal {
+            metric: GoalMetric::TotalMinutes,
+            ..goal
+        };
+        assert!(total.progress_for_week(&week).met);
+    }
+
+    #[test]
+    fn test_daily_goal_is_met() {
+        let day = FaithDayStats {
+            date: "2025-01-05".to_string(),
+            anki_minutes: Some(10.0),
+            anki_matured_passages: 0,
+            anki_lost_passages: 0,
+            anki_cumulative_passages: 0,
+            anki_matured_references: Vec::new(),
+            anki_lost_references: Vec::new(),
+            anki_reviews: 0,
+            anki_avg_seconds_per_review: None,
+            reading_minutes: Some(20.0),
+            prayer_minutes: Some(5.0),
+            exempt: false,
+            note: None,
+            tags: Vec::new(),
+        };
+        let goal = DailyGoal {
+            name: "Reading".to_string(),
+            metric: GoalMetric::ReadingMinutes,
+            daily_target_minutes: 15.0,
+        };
+
+        assert!(goal.is_met(&day));
+        assert!(
+            !DailyGoal {
+                metric: GoalMetric::PrayerMinutes,
+                ..goal.clone()
+            }
+            .is_met(&day)
+        );
+        assert!(
+            !DailyGoal {
+                metric: GoalMetric::AtChurchMinutes,
+                daily_target_minutes: 0.0,
+                ..goal
+            }
+            .is_met(&day)
+        );
+    }
+
+    #[test]
+    fn test_minutes_behind_pace() {
+        let week = FaithWeekStats {
+            week_start: "2025-01-05".to_string(),
+            anki_minutes: 0.0,
+            anki_matured_passages: 0,
+            anki_lost_passages: 0,
+            anki_cumulative_passages: 0,
+            anki_matured_references: Vec::new(),
+            anki_lost_references: Vec::new(),
+            anki_reviews: 0,
+            anki_avg_seconds_per_review: None,
+            anki_projected_minutes: None,
+            reading_minutes: 50.0,
+            at_church_minutes: 0.0,
+            prayer_minutes: 0.0,
+            exempt_days: 0,
+        };
+        let goal = Goal {
+            name: "Reading".to_string(),
+            metric: GoalMetric::ReadingMinutes,
+            weekly_target_minutes: 150.0,
+        };
+
+        assert_eq!(goal.minutes_behind_pace(&week, 0.5), 25.0);
+        assert_eq!(goal.minutes_behind_pace(&week, 0.25), 0.0);
+        assert_eq!(goal.minutes_behind_pace(&week, 2.0), 100.0);
+    }
+
+    #[test]
+    fn test_exempt_days_reduce_target() {
+        let week = FaithWeekStats {
+            week_start: "2025-01-05".to_string(),
+            reading_minutes: 100.0,
+            exempt_days: 2,
+            ..Default::default()
+        };
+        let goal = Goal {
+            name: "Reading".to_string(),
+            metric: GoalMetric::ReadingMinutes,
+            weekly_target_minutes: 140.0,
+        };
+
+        let progress = goal.progress_for_week(&week);
+        assert_eq!(progress.target_minutes, 100.0);
+        assert!(progress.met);
+        assert_eq!(goal.minutes_behind_pace(&week, 1.0), 0.0);
+    }
+}
@@ -0,0 +1,272 @@
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::io::{Read, Seek};
+use zip::ZipArchive;
+
+use crate::export::{EXPORT_FORMAT_VERSION, ExportManifest};
+use crate::models::{FaithDailyStats, FaithDayStats, FaithWeekStats, FaithWeeklyStats};
+use crate::store::Store;
+
+/// Result of importing an export archive
+#[derive(Debug, Clone)]
+pub struct ImportSummary {
+    /// Manifest of the imported archive
+    pub manifest: ExportManifest,
+    /// Number of days stored
+    pub days: usize,
+    /// Number of weeks stored
+    pub weeks: usize,
+}
+
+/// Subset of FaithDailyStats read back from `faith/daily.json`
+#[derive(Deserialize)]
+struct ArchivedDailyStats {
+    days: Vec<FaithDayStats>,
+}
+
+/// Subset of FaithWeeklyStats read back from `faith/weekly.json`
+#[derive(Deserialize)]
+struct ArchivedWeeklyStats {
+    weeks: Vec<FaithWeekStats>,
+}
+
+/// Loads the combined daily and weekly series of an export archive into the stats store
+///
+/// Values already imported for the same dates are replaced, so importing overlapping
+/// archives keeps the most recently imported values. Nothing is stored if the import fails.
+///
+/// # Errors
+/// Returns an error if the archive is not a valid export archive, was written by a newer
+/// version, or the store cannot be written
+pub fn import_archive<R: Read + Seek>(reader: R, store: &mut Store) -> Result<ImportSummary> {
+    let mut archive = ZipArchive::new(reader).context("Failed to read export archive")?;
+
+    let manifest: ExportManifest = read_json(&mut archive, "manifest.json")?;
+    if manifest.format_version > EXPORT_FORMAT_VERSION {
+        bail!(
+            "Export archive format version {} is newer than supported version {}",
+            manifest.format_version,
+            EXPORT_FORMAT_VERSION
+        );
+    }
+
+    let daily: ArchivedDailyStats = read_json(&mut archive, "faith/daily.json")?;
+    let weekly: ArchivedWeeklyStats = read_json(&mut archive, "faith/weekly.json")?;
+
+    store.import_history(&manifest, &daily.days, &weekly.weeks)?;
+
+    Ok(ImportSummary {
+        manifest,
+        days: daily.days.len(),
+        weeks: weekly.weeks.len(),
+    })
+}
+
+fn read_json<R: Read + Seek, T: DeserializeOwned>(
+    archive: &mut ZipArchive<R>,
+    path: &str,
+) -> Result<T> {
+    let file = archive
+        .by_name(path)
+        .context(format!("Export archive is missing {}", path))?;
+    serde_json::from_reader(file).context(format!("Failed to parse {} in export archive", path))
+}
+
+/// Fills gaps in live daily stats with previously imported history
+///
+/// Each source is filled independently: imported values are only used for a day when the
+/// live source reports no activity at all for it (e.g. before the current source app or
+/// server was set up). The summary is recomputed afterwards.
+pub fn with_imported_days(stats: FaithDailyStats, store: &Store) -> Result<FaithDailyStats> {
+    let (Some(first), Some(last)) = (stats.days.first(), stats.days.last()) else {
+        return Ok(stats);
+    };
+    let imported = store.get_imported_days(&first.date, &last.date)?;
+
+    let days = stats
+        .days
+        .into_iter()
+        .map(|mut day| {
+            if let Some(past) = imported.get(&day.date) {
+                if day.anki_minutes.unwrap_or(0.0) == 0.0
+                    && day.anki_matured_passages == 0
+                    && day.anki_lost_passages == 0
+                    && day.anki_cumulative_passages == 0
+                {
+                    day.anki_minutes = past.anki_minutes;
+                    day.anki_matured_passages = past.anki_matured_passages;
+                    day.anki_lost_passages = past.anki_lost_passages;
+                    day.anki_cumulative_passages = past.anki_cumulative_passages;
+                }
+                if day.reading_minutes.unwrap_or(0.0) == 0.0 {
+                    day.reading_minutes = past.reading_minutes;
+                }
+                if day.prayer_minutes.unwrap_or(0.0) == 0.0 {
+                    day.prayer_minutes = past.prayer_minutes;
+                }
+            }
+            day
+        })
+        .collect();
+
+    Ok(FaithDailyStats::new(days))
+}
+
+/// Fills gaps in live weekly stats with previously imported history
+///
+/// Uses the same per-source rules as `with_imported_days()`.
+pub fn with_imported_weeks(stats: FaithWeeklyStats, store: &Store) -> Result<FaithWeeklyStats> {
+    let (Some(first), Some(last)) = (stats.weeks.first(), stats.weeks.last()) else {
+        return Ok(stats);
+    };
+    let imported = store.get_imported_weeks(&first.week_start, &last.week_start)?;
+
+    let weeks = stats
+        .weeks
+        .into_iter()
+        .map(|mut week| {
+            if let Some(past) = imported.get(&week.week_start) {
+                if week.anki_minutes == 0.0
+                    && week.anki_matured_passages == 0
+                    && week.anki_lost_passages == 0
+                    && week.anki_cumulative_passages == 0
+                {
+                    week.anki_minutes = past.anki_minutes;
+                    week.anki_matured_passages = past.anki_matured_passages;
+                    week.anki_lost_passages = past.anki_lost_passages;
+                    week.anki_cumulative_passages = past.anki_cumulative_passages;
+                }
+                if week.reading_minutes == 0.0 {
+                    week.reading_minutes = past.reading_minutes;
+                }
+                if week.at_church_minutes == 0.0 {
+                    week.at_church_minutes = past.at_church_minutes;
+                }
+                if week.prayer_minutes == 0.0 {
+                    week.prayer_minutes = past.prayer_minutes;
+                }
+            }
+            week
+        })
+        .collect();
+
+    Ok(FaithWeeklyStats::new(weeks))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+    use zip::ZipWriter;
+    use zip::write::SimpleFileOptions;
+
+    fn day(date: &str, anki_minutes: f64, cumulative: i64, reading_minutes: f64) -> FaithDayStats {
+        FaithDayStats {
+            date: date.to_string(),
+            anki_minutes: Some(anki_minutes),
+            anki_matured_passages: 0,
+            anki_lost_passages: 0,
+            anki_cumulative_passages: cumulative,
+            anki_matured_references: Vec::new(),
+            anki_lost_references: Vec::new(),
+            anki_reviews: 0,
+            anki_avg_seconds_per_review: None,
+            reading_minutes: Some(reading_minutes),
+            prayer_minutes: Some(0.0),
+            exempt: false,
+            note: None,
+            tags: Vec::new(),
+        }
+    }
+
+    fn archive(format_version: u32, days: &[FaithDayStats]) -> Cursor<Vec<u8>> {
+        let manifest = ExportManifest {
+            format_version,
+            generated_at: "2025-01-01T00:00:00+00:00".to_string(),
+            days: days.len() as i32,
+            weeks: 0,
+            files: vec![],
+        };
+
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let files = [
+            ("manifest.json", serde_json::to_vec(&manifest).unwrap()),
+            (
+                "faith/daily.json",
+                serde_json::to_vec(&FaithDailyStats::new(days.to_vec())).unwrap(),
+            ),
+            (
+                "faith/weekly.json",
+                serde_json::to_vec(&FaithWeeklyStats::new(vec![])).unwrap(),
+            ),
+        ];
+        for (path, contents) in files {
+            zip.start_file(path, SimpleFileOptions::default()).unwrap();
+            zip.write_all(&contents).unwrap();
+        }
+
+        let mut cursor = zip.finish().unwrap();
+        cursor.set_position(0);
+        cursor
+    }
+
+    #[test]
+    fn test_import_fills_only_days_without_live_activity() {
+        let mut store = Store::open_in_memory().unwrap();
+        let imported = [
+            day("2025-01-01", 10.0, 5, 20.0),
+            day("2025-01-02", 15.0, 6, 0.0),
+        ];
+
+        let summary = import_archive(archive(1, &imported), &mut store).unwrap();
+        assert_eq!(summary.days, 2);
+
+        // The live Anki collection starts on Jan 2, the live reading source has no data
+        let live = FaithDailyStats::new(vec![
+            day("2025-01-01", 0.0, 0, 0.0),
+            day("2025-01-02", 3.0, 1, 0.0),
+            day("2025-01-03", 4.0, 1, 0.0),
+        ]);
+        let merged = with_imported_days(live, &store).unwrap();
+
+        assert_eq!(merged.days[0].anki_minutes, Some(10.0));
+        assert_eq!(merged.days[0].anki_cumulative_passages, 5);
+        assert_eq!(merged.days[0].reading_minutes, Some(20.0));
+        assert_eq!(merged.days[1].anki_minutes, Some(3.0));
+        assert_eq!(merged.days[2].anki_minutes, Some(4.0));
+        assert_eq!(merged.summary.reading_total_minutes, 20.0);
+    }
+
+    #[test]
+    fn test_failed_import_stores_nothing() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("stats.sqlite3");
+        let path = path.to_str().unwrap();
+        let mut store = Store::open(path).unwrap();
+        // Fail the last step, recording the import, after the days have been written
+        rusqlite::Connection::open(path)
+            .unwrap()
+            .execute_batch(
+                "CREATE TRIGGER fail_import BEFORE INSERT ON imports
+                 BEGIN SELECT RAISE(ABORT, 'disk full'); END;",
+            )
+            .unwrap();
+
+        let imported = [day("2025-01-01", 10.0, 5, 20.0)];
+        assert!(import_archive(archive(1, &imported), &mut store).is_err());
+        assert!(
+            store
+                .get_imported_days("2025-01-01", "2025-01-01")
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_import_rejects_newer_format_version() {
+        let mut store = Store::open_in_memory().unwrap();
+        let result = import_archive(archive(EXPORT_FORMAT_VERSION + 1, &[]), &mut store);
+        assert!(result.is_err());
+    }
+}
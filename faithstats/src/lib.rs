@@ -1,167 +1,741 @@
+pub mod budget;
+pub mod consistency;
+pub mod exemptions;
+pub mod export;
+pub mod goals;
+pub mod import;
 pub mod models;
+pub mod notes;
+pub mod on_this_day;
+pub mod places;
+pub mod plan;
+pub mod query;
+pub mod report;
+pub mod score;
+pub mod search;
+pub mod simulate;
+pub mod store;
+pub mod tags;
+pub mod templates;
+pub mod warehouse;
+
+pub use query::{FaithStats, FaithStatsBuilder, SourceNotConfiguredError};
 
 use anyhow::Result;
+use statsutils::DatePeriod;
+use std::thread::ScopedJoinHandle;
+use std::time::Duration;
 
+use crate::goals::Goal;
 use crate::models::{
     FaithDailyStats, FaithDayStats, FaithTodayStats, FaithWeekStats, FaithWeeklyStats,
+    HistoryStarts, HouseholdMemberWeeklyStats, HouseholdWeeklyStats, SourceDataRange, StaleSource,
+    StalenessThresholds,
 };
+use crate::on_this_day::OnThisDay;
+use crate::search::SearchResults;
+use crate::simulate::{Commitment, GoalSimulation};
 
-/// Gets unified faith statistics for the last 30 days, combining Anki Bible memorization,
-/// KOReader Bible reading, and prayer time data.
-///
-/// # Arguments
-/// * `anki_db_path` - Path to the Anki collection.anki2 database file
-/// * `koreader_db_path` - Path to the KOReader statistics.sqlite3 database file
-/// * `proseuche_db_path` - Path to the Proseuche database.sqlite file
-///
-/// # Returns
-/// FaithDailyStats containing daily breakdown and summary statistics
-///
-/// # Errors
-/// Returns an error if any database is unavailable or cannot be queried
+/// Waits for a source query running on its own thread, re-raising its panic if it had one
 ///
-/// # Example
-/// ```ignore
-/// use faithstats::get_faith_daily_stats;
+/// Each source opens its own database connection, so they can be queried at the same time and
+/// the total latency is that of the slowest source rather than the sum.
+pub(crate) fn join<T>(handle: ScopedJoinHandle<'_, T>) -> T {
+    handle
+        .join()
+        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+}
+
+/// Merges per-source daily series into unified FaithDayStats
 ///
-/// let stats = get_faith_daily_stats(
-///     "/path/to/collection.anki2",
-///     "/path/to/statistics.sqlite3",
-///     "/path/to/database.sqlite"
-/// )?;
-/// println!("Total faith time: {:.2} hours", stats.summary.total_hours);
-/// ```
-pub fn get_faith_daily_stats(
-    anki_db_path: &str,
-    koreader_db_path: &str,
-    proseuche_db_path: &str,
-) -> Result<FaithDailyStats> {
-    // Query all databases - will return error if any is unavailable
-    let anki_stats = ankistats::get_last_30_days_stats(anki_db_path)?;
-    let reading_stats = readingstats::get_last_30_days_stats(koreader_db_path)?;
-    let prayer_stats = prayerstats::get_last_30_days_stats(proseuche_db_path)?;
-
-    // All functions return the same 30 dates in the same order (guaranteed by DatePeriod),
-    // so we can simply zip them together
-    let merged_days: Vec<FaithDayStats> = anki_stats
+/// All source functions return the same dates in the same order (guaranteed by DatePeriod),
+/// so the series can simply be zipped together. Every source's minutes are reported; use
+/// `HistoryStarts::clear_days_before()` to mark days before a source's history.
+pub fn merge_daily_stats(
+    anki_stats: Vec<ankistats::models::DayStats>,
+    reading_stats: Vec<readingstats::models::DayStats>,
+    prayer_stats: Vec<prayerstats::DayStats>,
+) -> Vec<FaithDayStats> {
+    anki_stats
         .into_iter()
         .zip(reading_stats)
         .zip(prayer_stats)
         .map(|((anki_day, reading_day), prayer_day)| FaithDayStats {
-            date: anki_day.date,
-            anki_minutes: anki_day.minutes,
+            date: anki_day.time.date,
+            anki_minutes: Some(anki_day.time.minutes),
             anki_matured_passages: anki_day.matured_passages,
             anki_lost_passages: anki_day.lost_passages,
             anki_cumulative_passages: anki_day.cumulative_passages,
-            reading_minutes: reading_day.minutes,
-            prayer_minutes: prayer_day.minutes,
+            anki_matured_references: anki_day.matured_references,
+            anki_lost_references: anki_day.lost_references,
+            anki_reviews: anki_day.reviews,
+            anki_avg_seconds_per_review: anki_day.avg_seconds_per_review,
+            reading_minutes: Some(reading_day.minutes),
+            prayer_minutes: Some(prayer_day.minutes),
+            exempt: false,
+            note: None,
+            tags: Vec::new(),
         })
-        .collect();
+        .collect()
+}
 
-    Ok(FaithDailyStats::new(merged_days))
+/// Merges per-source weekly series into unified FaithWeekStats
+///
+/// All source functions return the same weeks in the same order (guaranteed by DatePeriod),
+/// so the series can simply be zipped together.
+pub fn merge_weekly_stats(
+    anki_stats: Vec<ankistats::models::WeekStats>,
+    reading_stats: Vec<readingstats::models::WeekStats>,
+    church_stats: Vec<arcstats::WeekStats>,
+    prayer_stats: Vec<prayerstats::WeekStats>,
+) -> Vec<FaithWeekStats> {
+    anki_stats
+        .into_iter()
+        .zip(reading_stats)
+        .zip(church_stats)
+        .zip(prayer_stats)
+        .map(
+            |(((anki_week, reading_week), church_week), prayer_week)| FaithWeekStats {
+                week_start: anki_week.time.week_start,
+                anki_minutes: anki_week.time.minutes,
+                anki_matured_passages: anki_week.matured_passages,
+                anki_lost_passages: anki_week.lost_passages,
+                anki_cumulative_passages: anki_week.cumulative_passages,
+                anki_matured_references: anki_week.matured_references,
+                anki_lost_references: anki_week.lost_references,
+                anki_reviews: anki_week.reviews,
+                anki_avg_seconds_per_review: anki_week.avg_seconds_per_review,
+                anki_projected_minutes: None,
+                reading_minutes: reading_week.minutes,
+                at_church_minutes: church_week.minutes,
+                prayer_minutes: prayer_week.minutes,
+                exempt_days: 0,
+            },
+        )
+        .collect()
 }
 
-/// Gets unified faith statistics for today, combining Anki Bible memorization,
-/// KOReader Bible reading, and prayer time data.
+/// Combines the weekly stats of several household members into household totals
 ///
-/// # Arguments
-/// * `anki_db_path` - Path to the Anki collection.anki2 database file
-/// * `koreader_db_path` - Path to the KOReader statistics.sqlite3 database file
-/// * `proseuche_db_path` - Path to the Proseuche database.sqlite file
+/// All members' stats must cover the same weeks (as returned by `FaithStats::weekly()`).
+/// The combined series sums every metric per week, and each shared goal is evaluated
+/// against the combined current (last) week.
 ///
-/// # Returns
-/// FaithTodayStats containing today's combined statistics
+/// # Arguments
+/// * `members` - Each member's user id and weekly stats
+/// * `goals` - Shared household goals
+pub fn combine_household_weekly_stats(
+    members: Vec<(String, FaithWeeklyStats)>,
+    goals: &[Goal],
+) -> HouseholdWeeklyStats {
+    let mut combined_weeks: Vec<FaithWeekStats> = Vec::new();
+    for (_, stats) in &members {
+        if combined_weeks.is_empty() {
+            combined_weeks = stats.weeks.clone();
+            continue;
+        }
+        for (total, week) in combined_weeks.iter_mut().zip(&stats.weeks) {
+            total.anki_minutes += week.anki_minutes;
+            total.anki_matured_passages += week.anki_matured_passages;
+            total.anki_lost_passages += week.anki_lost_passages;
+            total.anki_cumulative_passages += week.anki_cumulative_passages;
+            merge_references(
+                &mut total.anki_matured_references,
+                &week.anki_matured_references,
+            );
+            merge_references(&mut total.anki_lost_references, &week.anki_lost_references);
+            total.anki_reviews += week.anki_reviews;
+            total.anki_avg_seconds_per_review = (total.anki_reviews > 0)
+                .then(|| total.anki_minutes * 60.0 / total.anki_reviews as f64);
+            total.anki_projected_minutes =
+                match (total.anki_projected_minutes, week.anki_projected_minutes) {
+                    (Some(a), Some(b)) => Some(a + b),
+                    (a, b) => a.or(b),
+                };
+            total.reading_minutes += week.reading_minutes;
+            total.at_church_minutes += week.at_church_minutes;
+            total.prayer_minutes += week.prayer_minutes;
+        }
+    }
+
+    let goals = match combined_weeks.last() {
+        Some(current_week) => goals
+            .iter()
+            .map(|goal| goal.progress_for_week(current_week))
+            .collect(),
+        None => Vec::new(),
+    };
+
+    HouseholdWeeklyStats {
+        members: members
+            .into_iter()
+            .map(|(user, stats)| HouseholdMemberWeeklyStats { user, stats })
+            .collect(),
+        combined: FaithWeeklyStats::new(combined_weeks),
+        goals,
+    }
+}
+
+/// Adds the references that are not listed yet, keeping at most
+/// `ankistats::db::MAX_PROGRESS_REFERENCES` like each member's own list
+fn merge_references(total: &mut Vec<String>, references: &[String]) {
+    for reference in references {
+        if total.len() >= ankistats::db::MAX_PROGRESS_REFERENCES {
+            break;
+        }
+        if !total.contains(reference) {
+            total.push(reference.clone());
+        }
+    }
+}
+
+/// Finds sources whose latest recorded activity is older than their staleness threshold
 ///
-/// # Errors
-/// Returns an error if any database is unavailable or cannot be queried
+/// A source that could not be read, or that has no recorded activity at all, is also
+/// reported as stale when it has a threshold configured.
 ///
-/// # Example
-/// ```ignore
-/// use faithstats::get_faith_today_stats;
+/// # Arguments
+/// * `ranges` - Data ranges as returned by `FaithStats::data_ranges()`
+/// * `thresholds` - Staleness thresholds in days per source
 ///
-/// let stats = get_faith_today_stats(
-///     "/path/to/collection.anki2",
-///     "/path/to/statistics.sqlite3",
-///     "/path/to/database.sqlite"
-/// )?;
-/// println!("Total faith time today: {:.2} hours", stats.total_hours);
-/// ```
+/// # Returns
+/// The sources breaching their threshold, in the same order as `ranges`
+pub fn find_stale_sources(
+    ranges: &[SourceDataRange],
+    thresholds: &StalenessThresholds,
+) -> Vec<StaleSource> {
+    ranges
+        .iter()
+        .filter_map(|source| {
+            let threshold_days = thresholds.for_source(&source.source)?;
+            let days_since_latest = source.range.days_since_latest;
+
+            let reason = match (&source.error, days_since_latest) {
+                (Some(error), _) => format!("{} data could not be read: {}", source.source, error),
+                (None, None) => format!("{} has no recorded activity", source.source),
+                (None, Some(days)) if days > threshold_days => format!(
+                    "{} data is {:.1} days old (threshold: {} days)",
+                    source.source, days, threshold_days
+                ),
+                (None, Some(_)) => return None,
+            };
+
+            Some(StaleSource {
+                source: source.source.clone(),
+                days_since_latest,
+                threshold_days,
+                reason,
+            })
+        })
+        .collect()
+}
+
+/// Gets unified faith statistics for the last 30 days, reading Anki, KOReader and Proseuche
+#[deprecated(note = "Use `FaithStats::builder()` with `.daily()`")]
+pub fn get_faith_daily_stats(
+    anki_db_path: &str,
+    koreader_db_path: &str,
+    proseuche_db_path: &str,
+) -> Result<FaithDailyStats> {
+    daily_sources(anki_db_path, koreader_db_path, proseuche_db_path)
+        .build()
+        .daily()
+}
+
+/// Gets unified faith statistics for the last `days` days, reading Anki, KOReader and
+/// Proseuche
+#[deprecated(note = "Use `FaithStats::builder()` with `.period()` and `.daily()`")]
+pub fn get_faith_last_n_days_stats(
+    anki_db_path: &str,
+    koreader_db_path: &str,
+    proseuche_db_path: &str,
+    days: i32,
+) -> Result<FaithDailyStats> {
+    daily_sources(anki_db_path, koreader_db_path, proseuche_db_path)
+        .period(DatePeriod::last_n_days(days)?)
+        .build()
+        .daily()
+}
+
+/// Gets unified faith statistics for each day of the period, reading Anki, KOReader and
+/// Proseuche
+#[deprecated(note = "Use `FaithStats::builder()` with `.period()` and `.daily()`")]
+pub fn get_faith_days_stats(
+    anki_db_path: &str,
+    koreader_db_path: &str,
+    proseuche_db_path: &str,
+    period: DatePeriod,
+) -> Result<FaithDailyStats> {
+    daily_sources(anki_db_path, koreader_db_path, proseuche_db_path)
+        .period(period)
+        .build()
+        .daily()
+}
+
+/// Gets the first date with recorded activity in each daily source
+#[deprecated(note = "Use `FaithStats::builder()` with `.history_starts()`")]
+pub fn get_history_starts(
+    anki_db_path: &str,
+    koreader_db_path: &str,
+    proseuche_db_path: &str,
+) -> Result<HistoryStarts> {
+    daily_sources(anki_db_path, koreader_db_path, proseuche_db_path)
+        .build()
+        .history_starts()
+}
+
+/// Gets unified faith statistics for today, reading Anki, KOReader and Proseuche
+#[deprecated(note = "Use `FaithStats::builder()` with `.today()`")]
 pub fn get_faith_today_stats(
     anki_db_path: &str,
     koreader_db_path: &str,
     proseuche_db_path: &str,
 ) -> Result<FaithTodayStats> {
-    // Query all databases - will return error if any is unavailable
-    let anki_minutes = ankistats::get_today_study_time(anki_db_path)?;
-    let reading_minutes = readingstats::get_today_reading_time(koreader_db_path)?;
-    let prayer_minutes = prayerstats::get_today_prayer_time(proseuche_db_path)?;
-
-    Ok(FaithTodayStats::new(
-        anki_minutes,
-        reading_minutes,
-        prayer_minutes,
-    ))
+    daily_sources(anki_db_path, koreader_db_path, proseuche_db_path)
+        .build()
+        .today()
 }
 
-/// Gets unified faith statistics for the last 12 weeks, combining Anki Bible memorization,
-/// KOReader Bible reading, Arc church attendance, and prayer time data.
-///
-/// # Arguments
-/// * `anki_db_path` - Path to the Anki collection.anki2 database file
-/// * `koreader_db_path` - Path to the KOReader statistics.sqlite3 database file
-/// * `arcstats_export_path` - Path to the Arc Timeline export directory
-/// * `proseuche_db_path` - Path to the Proseuche database.sqlite file
-///
-/// # Returns
-/// FaithWeeklyStats containing weekly breakdown and summary statistics
-///
-/// # Errors
-/// Returns an error if any database/export is unavailable or cannot be queried
-///
-/// # Example
-/// ```ignore
-/// use faithstats::get_faith_weekly_stats;
-///
-/// let stats = get_faith_weekly_stats(
-///     "/path/to/collection.anki2",
-///     "/path/to/statistics.sqlite3",
-///     "/path/to/arc/export",
-///     "/path/to/database.sqlite"
-/// )?;
-/// println!("Total faith time: {:.2} hours", stats.summary.total_hours);
-/// ```
+/// Gets unified faith statistics for the last 12 weeks, reading every source
+#[deprecated(note = "Use `FaithStats::builder()` with `.weekly()`")]
 pub fn get_faith_weekly_stats(
     anki_db_path: &str,
     koreader_db_path: &str,
     arcstats_export_path: &str,
     proseuche_db_path: &str,
 ) -> Result<FaithWeeklyStats> {
-    // Query all databases - will return error if any is unavailable
-    let anki_stats = ankistats::get_last_12_weeks_stats(anki_db_path)?;
-    let reading_stats = readingstats::get_last_12_weeks_stats(koreader_db_path)?;
-    let church_stats = arcstats::get_last_12_weeks_stats(arcstats_export_path)?;
-    let prayer_stats = prayerstats::get_last_12_weeks_stats(proseuche_db_path)?;
-
-    // All functions return the same 12 weeks in the same order (guaranteed by DatePeriod),
-    // so we can simply zip them together
-    let merged_weeks: Vec<FaithWeekStats> = anki_stats
-        .into_iter()
-        .zip(reading_stats)
-        .zip(church_stats)
-        .zip(prayer_stats)
-        .map(
-            |(((anki_week, reading_week), church_week), prayer_week)| FaithWeekStats {
-                week_start: anki_week.week_start,
-                anki_minutes: anki_week.minutes,
-                anki_matured_passages: anki_week.matured_passages,
-                anki_lost_passages: anki_week.lost_passages,
-                anki_cumulative_passages: anki_week.cumulative_passages,
-                reading_minutes: reading_week.minutes,
-                at_church_minutes: church_week.minutes,
-                prayer_minutes: prayer_week.minutes,
+    daily_sources(anki_db_path, koreader_db_path, proseuche_db_path)
+        .arc(arcstats_export_path)
+        .build()
+        .weekly()
+}
+
+/// Gets unified faith statistics for the last `weeks` weeks, reading every source
+#[deprecated(note = "Use `FaithStats::builder()` with `.weeks()` and `.weekly()`")]
+pub fn get_faith_last_n_weeks_stats(
+    anki_db_path: &str,
+    koreader_db_path: &str,
+    arcstats_export_path: &str,
+    proseuche_db_path: &str,
+    weeks: i32,
+) -> Result<FaithWeeklyStats> {
+    daily_sources(anki_db_path, koreader_db_path, proseuche_db_path)
+        .arc(arcstats_export_path)
+        .weeks(weeks)
+        .build()
+        .weekly()
+}
+
+/// Projects when the given books and the whole New Testament would be memorized and read
+/// under a hypothetical daily commitment, reading Anki
+#[deprecated(note = "Use `FaithStats::builder()` with `.simulate_goals()`")]
+pub fn simulate_goals(
+    anki_db_path: &str,
+    books: &[&'static str],
+    commitment: Commitment,
+) -> Result<GoalSimulation> {
+    FaithStats::builder()
+        .anki(anki_db_path)
+        .build()
+        .simulate_goals(books, commitment)
+}
+
+/// Gets the passages first memorized, books finished and notable places visited on
+/// `today`'s month and day in earlier years, reading Anki, KOReader and Arc
+#[deprecated(note = "Use `FaithStats::builder()` with `.on_this_day()`")]
+pub fn get_on_this_day(
+    anki_db_path: &str,
+    koreader_db_path: &str,
+    arcstats_export_path: &str,
+    today: &str,
+) -> Result<OnThisDay> {
+    FaithStats::builder()
+        .anki(anki_db_path)
+        .reading(koreader_db_path)
+        .arc(arcstats_export_path)
+        .build()
+        .on_this_day(today)
+}
+
+/// Searches passage references, places and book titles, reading Anki, KOReader and Arc
+#[deprecated(note = "Use `FaithStats::builder()` with `.search()`")]
+pub fn search(
+    anki_db_path: &str,
+    koreader_db_path: &str,
+    arcstats_export_path: &str,
+    query: &str,
+    limit: usize,
+) -> Result<SearchResults> {
+    FaithStats::builder()
+        .anki(anki_db_path)
+        .reading(koreader_db_path)
+        .arc(arcstats_export_path)
+        .build()
+        .search(query, limit)
+}
+
+/// Gets the recorded activity range and file freshness of every data source
+#[deprecated(note = "Use `FaithStats::builder()` with `.data_ranges()`")]
+pub fn get_sources_data_range(
+    anki_db_path: &str,
+    koreader_db_path: &str,
+    arcstats_export_path: &str,
+    proseuche_db_path: &str,
+) -> Vec<SourceDataRange> {
+    daily_sources(anki_db_path, koreader_db_path, proseuche_db_path)
+        .arc(arcstats_export_path)
+        .build()
+        .data_ranges()
+}
+
+/// Same as `get_sources_data_range()`, but also returns how long reading each source took
+#[deprecated(note = "Use `FaithStats::builder()` with `.data_ranges_timed()`")]
+pub fn get_sources_data_range_timed(
+    anki_db_path: &str,
+    koreader_db_path: &str,
+    arcstats_export_path: &str,
+    proseuche_db_path: &str,
+) -> Vec<(SourceDataRange, Duration)> {
+    daily_sources(anki_db_path, koreader_db_path, proseuche_db_path)
+        .arc(arcstats_export_path)
+        .build()
+        .data_ranges_timed()
+}
+
+/// Builder reading the Anki, KOReader and Proseuche databases, as the deprecated functions do
+fn daily_sources(
+    anki_db_path: &str,
+    koreader_db_path: &str,
+    proseuche_db_path: &str,
+) -> FaithStatsBuilder {
+    FaithStats::builder()
+        .anki(anki_db_path)
+        .reading(koreader_db_path)
+        .prayer(proseuche_db_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::goals::GoalMetric;
+    use crate::models::{FaithStreaks, HomeAssistantState, SharedWeeklyStats};
+    use statsutils::{DataRange, PartialPeriod};
+
+    fn source(name: &str, days_since_latest: Option<f64>) -> SourceDataRange {
+        SourceDataRange {
+            source: name.to_string(),
+            range: DataRange {
+                days_since_latest,
+                ..DataRange::default()
             },
-        )
-        .collect();
+            error: None,
+        }
+    }
+
+    fn week(week_start: &str, reading_minutes: f64, prayer_minutes: f64) -> FaithWeekStats {
+        FaithWeekStats {
+            week_start: week_start.to_string(),
+            anki_minutes: 0.0,
+            anki_matured_passages: 0,
+            anki_lost_passages: 0,
+            anki_cumulative_passages: 0,
+            anki_matured_references: Vec::new(),
+            anki_lost_references: Vec::new(),
+            anki_reviews: 0,
+            anki_avg_seconds_per_review: None,
+            anki_projected_minutes: None,
+            reading_minutes,
+            at_church_minutes: 0.0,
+            prayer_minutes,
+            exempt_days: 0,
+        }
+    }
+
+    #[test]
+    fn test_combine_household_weekly_stats() {
+        let members = vec![
+            (
+                "alice".to_string(),
+                FaithWeeklyStats::new(vec![
+                    week("2025-01-05", 30.0, 10.0),
+                    week("2025-01-12", 40.0, 0.0),
+                ]),
+            ),
+            (
+                "bob".to_string(),
+                FaithWeeklyStats::new(vec![
+                    week("2025-01-05", 15.0, 5.0),
+                    week("2025-01-12", 20.0, 5.0),
+                ]),
+            ),
+        ];
+        let goals = [Goal {
+            name: "Family reading".to_string(),
+            metric: GoalMetric::ReadingMinutes,
+            weekly_target_minutes: 60.0,
+        }];
+
+        let household = combine_household_weekly_stats(members, &goals);
+
+        assert_eq!(household.members.len(), 2);
+        assert_eq!(household.combined.weeks[0].reading_minutes, 45.0);
+        assert_eq!(household.combined.weeks[1].reading_minutes, 60.0);
+        assert_eq!(household.combined.weeks[1].prayer_minutes, 5.0);
+        assert_eq!(household.goals[0].week_start, "2025-01-12");
+        assert!(household.goals[0].met);
+    }
+
+    #[test]
+    fn test_combine_household_references() {
+        let references = |range: std::ops::Range<usize>| -> Vec<String> {
+            range.map(|verse| format!("John 3:{}", verse)).collect()
+        };
+        let member = |matured: Vec<String>| {
+            let mut week = week("2025-01-05", 0.0, 0.0);
+            week.anki_matured_references = matured;
+            week.anki_lost_references = vec!["Psalm 23:1".to_string()];
+            FaithWeeklyStats::new(vec![week])
+        };
+        let members = vec![
+            ("alice".to_string(), member(references(1..7))),
+            ("bob".to_string(), member(references(4..12))),
+        ];
+
+        let household = combine_household_weekly_stats(members, &[]);
+
+        let combined = &household.combined.weeks[0];
+        assert_eq!(combined.anki_matured_references, references(1..11));
+        assert_eq!(
+            combined.anki_lost_references,
+            vec!["Psalm 23:1".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_shared_weekly_stats_only_include_selected_metrics() {
+        let stats = FaithWeeklyStats::new(vec![week("2025-01-05", 30.0, 10.0)]);
+
+        let shared = SharedWeeklyStats::new(&stats, &[GoalMetric::TotalMinutes]);
+
+        assert_eq!(shared.weeks[0].total_minutes, Some(40.0));
+        assert_eq!(shared.weeks[0].reading_minutes, None);
+        let json = serde_json::to_value(&shared).unwrap();
+        assert!(json["weeks"][0].get("prayer_minutes").is_none());
+    }
+
+    fn day(date: &str, anki_minutes: f64, reading_minutes: f64) -> FaithDayStats {
+        FaithDayStats {
+            date: date.to_string(),
+            anki_minutes: Some(anki_minutes),
+            anki_matured_passages: 0,
+            anki_lost_passages: 0,
+            anki_cumulative_passages: 0,
+            anki_matured_references: Vec::new(),
+            anki_lost_references: Vec::new(),
+            anki_reviews: 0,
+            anki_avg_seconds_per_review: None,
+            reading_minutes: Some(reading_minutes),
+            prayer_minutes: Some(0.0),
+            exempt: false,
+            note: None,
+            tags: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_daily_summary_excludes_partial_today() {
+        let stats = FaithDailyStats::new(vec![
+            day("2025-01-03", 10.0, 20.0),
+            day("2025-01-04", 20.0, 10.0),
+            day("2025-01-05", 5.0, 0.0),
+        ]);
+        assert_eq!(stats.summary.partial_period, PartialPeriod::Include);
+        assert_eq!(stats.summary.average_minutes_per_day, 65.0 / 3.0);
+
+        let stats = stats.with_partial_period(PartialPeriod::Exclude, "2025-01-05");
+        assert_eq!(stats.days.len(), 3);
+        assert_eq!(stats.summary.partial_period, PartialPeriod::Exclude);
+        assert_eq!(stats.summary.total_days, 2);
+        assert_eq!(stats.summary.average_minutes_per_day, 30.0);
+
+        let stats = stats.with_partial_period(PartialPeriod::Include, "2025-01-05");
+        assert_eq!(stats.summary.total_days, 3);
+    }
+
+    #[test]
+    fn test_days_before_history_are_left_out() {
+        let mut days = vec![
+            day("2025-01-03", 10.0, 0.0),
+            day("2025-01-04", 10.0, 0.0),
+            day("2025-01-05", 10.0, 30.0),
+        ];
+        days[2].prayer_minutes = Some(6.0);
+        let starts = HistoryStarts {
+            anki: Some("2024-12-01".to_string()),
+            reading: Some("2025-01-04".to_string()),
+            prayer: None,
+        };
+
+        starts.clear_days_before(&mut days);
+        assert_eq!(days[0].reading_minutes, None);
+        assert_eq!(days[1].reading_minutes, Some(0.0));
+        assert!(days.iter().all(|day| day.prayer_minutes.is_none()));
+
+        let summary = FaithDailyStats::new(days).summary;
+        assert_eq!(summary.anki_days_tracked, 3);
+        assert_eq!(summary.reading_days_tracked, 2);
+        assert_eq!(summary.reading_average_minutes_per_day, 15.0);
+        assert_eq!(summary.prayer_days_tracked, 0);
+        assert_eq!(summary.prayer_average_minutes_per_day, 0.0);
+        assert_eq!(summary.days_tracked, 3);
+    }
+
+    #[test]
+    fn test_untracked_days_as_zero() {
+        let mut days = vec![day("2025-01-04", 10.0, 0.0), day("2025-01-05", 10.0, 30.0)];
+        days[0].reading_minutes = None;
+        days[0].prayer_minutes = None;
+
+        let stats = FaithDailyStats::new(days).with_untracked_as_zero();
+        assert_eq!(stats.days[0].reading_minutes, Some(0.0));
+        assert_eq!(stats.days[0].prayer_minutes, Some(0.0));
+        assert_eq!(stats.summary.reading_days_tracked, 2);
+        assert_eq!(stats.summary.reading_average_minutes_per_day, 15.0);
+    }
+
+    #[test]
+    fn test_weeks_before_history_are_left_out() {
+        let weeks = vec![
+            week("2024-12-29", 0.0, 0.0),
+            week("2025-01-05", 20.0, 0.0),
+            week("2025-01-12", 40.0, 0.0),
+        ];
+        let stats = FaithWeeklyStats::new(weeks);
+        assert_eq!(stats.summary.reading_weeks_tracked, 3);
+        assert_eq!(stats.summary.reading_average_minutes_per_week, 20.0);
+
+        // Reading started within the second week, so that week counts
+        let stats = stats.with_history(HistoryStarts {
+            anki: None,
+            reading: Some("2025-01-09".to_string()),
+            prayer: None,
+        });
+        assert_eq!(stats.summary.anki_weeks_tracked, 0);
+        assert_eq!(stats.summary.reading_weeks_tracked, 2);
+        assert_eq!(stats.summary.reading_average_minutes_per_week, 30.0);
+        assert_eq!(stats.summary.weeks_tracked, 2);
+        assert_eq!(stats.summary.average_minutes_per_week, 30.0);
+
+        // The history is kept when the summary is recomputed
+        let stats = stats.with_partial_period(PartialPeriod::Exclude, "2025-01-12");
+        assert_eq!(stats.summary.reading_weeks_tracked, 1);
+        assert_eq!(stats.summary.reading_average_minutes_per_week, 20.0);
+    }
+
+    #[test]
+    fn test_streaks_count_back_from_today() {
+        let days = vec![
+            day("2025-01-01", 5.0, 0.0),
+            day("2025-01-02", 0.0, 0.0),
+            day("2025-01-03", 5.0, 10.0),
+            day("2025-01-04", 5.0, 0.0),
+            day("2025-01-05", 5.0, 10.0),
+        ];
+
+        let streaks = FaithStreaks::from_days(&days);
+
+        assert_eq!(streaks.any_activity_days, 3);
+        assert_eq!(streaks.anki_days, 3);
+        assert_eq!(streaks.reading_days, 1);
+        assert_eq!(streaks.prayer_days, 0);
+    }
+
+    #[test]
+    fn test_streaks_keep_counting_before_todays_activity() {
+        let days = vec![
+            day("2025-01-01", 5.0, 0.0),
+            day("2025-01-02", 5.0, 0.0),
+            day("2025-01-03", 0.0, 0.0),
+        ];
+
+        assert_eq!(FaithStreaks::from_days(&days).any_activity_days, 2);
+    }
+
+    #[test]
+    fn test_streaks_skip_exempt_days() {
+        let mut days = vec![
+            day("2025-01-01", 5.0, 0.0),
+            day("2025-01-02", 0.0, 0.0),
+            day("2025-01-03", 0.0, 0.0),
+            day("2025-01-04", 5.0, 0.0),
+        ];
+        days[1].exempt = true;
+
+        // The unexempted gap on 2025-01-03 still breaks the streak
+        assert_eq!(FaithStreaks::from_days(&days).any_activity_days, 1);
+
+        days[2].exempt = true;
+        assert_eq!(FaithStreaks::from_days(&days).any_activity_days, 2);
+    }
+
+    #[test]
+    fn test_home_assistant_state_uses_latest_day_and_week() {
+        let daily = FaithDailyStats::new(vec![
+            day("2025-01-04", 5.0, 0.0),
+            day("2025-01-05", 10.0, 20.0),
+        ]);
+        let weekly = FaithWeeklyStats::new(vec![
+            week("2024-12-29", 60.0, 0.0),
+            week("2025-01-05", 20.0, 15.0),
+        ]);
+
+        let state = HomeAssistantState::new(&daily, &weekly);
+
+        assert_eq!(state.today, "2025-01-05");
+        assert_eq!(state.today_total_minutes, 30.0);
+        assert_eq!(state.streak_days, 2);
+        assert_eq!(state.reading_streak_days, 1);
+        assert_eq!(state.week_start, "2025-01-05");
+        assert_eq!(state.weekly_reading_minutes, 20.0);
+        assert_eq!(state.weekly_total_minutes, 35.0);
+    }
+
+    #[test]
+    fn test_find_stale_sources_respects_thresholds() {
+        let ranges = vec![
+            source("anki", Some(0.5)),
+            source("reading", Some(9.2)),
+            source("prayer", Some(30.0)),
+        ];
+        let thresholds = StalenessThresholds {
+            anki: Some(2.0),
+            reading: Some(3.0),
+            ..StalenessThresholds::default()
+        };
+
+        let stale = find_stale_sources(&ranges, &thresholds);
+
+        // Prayer has no threshold, so only reading is stale
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].source, "reading");
+        assert_eq!(stale[0].threshold_days, 3.0);
+    }
+
+    #[test]
+    fn test_find_stale_sources_reports_unreadable_and_empty_sources() {
+        let mut unreadable = source("anki", None);
+        unreadable.error = Some("database is locked".to_string());
+        let ranges = vec![unreadable, source("reading", None)];
+        let thresholds = StalenessThresholds {
+            anki: Some(1.0),
+            reading: Some(1.0),
+            ..StalenessThresholds::default()
+        };
+
+        let stale = find_stale_sources(&ranges, &thresholds);
 
-    Ok(FaithWeeklyStats::new(merged_weeks))
+        assert_eq!(stale.len(), 2);
+        assert!(stale[0].reason.contains("database is locked"));
+        assert!(stale[1].reason.contains("no recorded activity"));
+    }
 }
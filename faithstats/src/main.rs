@@ -1,8 +1,23 @@
+use ankistats::config::AnkiStatsConfig;
+use arcstats::dedup::DropReason;
 use clap::{Parser, Subcommand};
+use faithstats::export::write_export_archive;
+use faithstats::import::{import_archive, with_imported_days, with_imported_weeks};
 use faithstats::models::{FaithDayStatsDisplay, FaithWeekStatsDisplay};
-use faithstats::{get_faith_daily_stats, get_faith_weekly_stats};
+use faithstats::places::LocationHistory;
+use faithstats::store::Store;
+use faithstats::{FaithStats, FaithStatsBuilder};
+use statsutils::format::{display_format, format_number};
+use statsutils::i18n::tr;
+use statsutils::{PartialPeriod, get_current_week_start, get_today_date};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::PathBuf;
 use std::process;
-use tabled::{Table, settings::Style};
+use tabled::{
+    Table,
+    settings::{Format, Style, object::Rows},
+};
 
 #[derive(Parser)]
 #[command(name = "faithstats")]
@@ -16,28 +31,92 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Show faith statistics for each of the last 30 days
-    Daily,
+    Daily {
+        /// Whether the summary counts today, which is not over yet (include or exclude)
+        #[arg(long, default_value_t = PartialPeriod::Exclude)]
+        partial: PartialPeriod,
+    },
     /// Show faith statistics for each of the last 12 weeks
-    Weekly,
+    Weekly {
+        /// Whether the summary counts this week, which is not over yet (include or exclude)
+        #[arg(long, default_value_t = PartialPeriod::Exclude)]
+        partial: PartialPeriod,
+    },
+    /// Export all derived statistics as a zip archive of JSON files
+    Export {
+        /// Path of the zip archive to write
+        output: String,
+    },
+    /// Import history from a previous export archive into the stats store
+    Import {
+        /// Path of the zip archive to import
+        archive: String,
+    },
+    /// Check the location history for duplicated and overlapping timeline items
+    Doctor,
 }
 
 fn main() {
     // Load environment variables from .env file if present
     let _ = dotenvy::dotenv();
 
+    // Print parser warnings (e.g. unparsable references) to stderr
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .without_time()
+        .init();
+
+    // Fail on unparsable Bible references instead of falling back (STRICT_REFERENCES=true)
+    let strict = std::env::var("STRICT_REFERENCES").is_ok_and(|v| v == "1" || v == "true");
+    ankistats::parse_warnings::set_strict_references(strict);
+
+    // Name unnamed places with a reverse geocoder (GEONAMES_CITIES_PATH or NOMINATIM_URL)
+    match faithstats::places::build_geocoder(
+        std::env::var("GEONAMES_CITIES_PATH").ok().as_deref(),
+        std::env::var("NOMINATIM_URL").ok().as_deref(),
+        std::env::var("GEOCODE_CACHE_PATH").ok().as_deref(),
+    ) {
+        Ok(geocoder) => faithstats::places::set_geocoder(geocoder),
+        Err(e) => eprintln!("Warning: reverse geocoding disabled: {:#}", e),
+    }
+
+    match statsutils::i18n::locale_from_env() {
+        Ok(locale) => statsutils::i18n::set_locale(locale),
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            process::exit(1);
+        }
+    }
+    match statsutils::format::display_format_from_env() {
+        Ok(format) => statsutils::format::set_display_format(format),
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            process::exit(1);
+        }
+    }
+
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Daily => {
-            run_daily_command();
+        Commands::Daily { partial } => {
+            run_daily_command(partial);
         }
-        Commands::Weekly => {
-            run_weekly_command();
+        Commands::Weekly { partial } => {
+            run_weekly_command(partial);
+        }
+        Commands::Export { output } => {
+            run_export_command(&output);
+        }
+        Commands::Import { archive } => {
+            run_import_command(&archive);
+        }
+        Commands::Doctor => {
+            run_doctor_command();
         }
     }
 }
 
-fn run_daily_command() {
+fn run_daily_command(partial: PartialPeriod) {
     // Get database paths from environment variables
     let anki_db = std::env::var("ANKI_DATABASE_PATH").unwrap_or_else(|_| {
         eprintln!("Error: ANKI_DATABASE_PATH environment variable is required");
@@ -57,81 +136,93 @@ fn run_daily_command() {
         process::exit(1);
     });
 
-    match get_faith_daily_stats(&anki_db, &koreader_db, &proseuche_db) {
+    let stats = FaithStats::builder()
+        .anki(anki_db)
+        .anki_config(AnkiStatsConfig::from_env())
+        .reading(koreader_db)
+        .prayer(proseuche_db)
+        .build()
+        .daily()
+        .and_then(|stats| {
+            let stats = match open_store_if_configured()? {
+                Some(store) => with_imported_days(stats, &store)?,
+                None => stats,
+            };
+            Ok(stats.with_partial_period(partial, &get_today_date()?))
+        });
+
+    match stats {
         Ok(stats) => {
-            println!("\n=== FAITH STATS - LAST 30 DAYS ===\n");
+            println!("\n=== {} ===\n", tr("FAITH STATS - LAST 30 DAYS"));
 
             // Convert to display format and create table
             let display_stats: Vec<FaithDayStatsDisplay> =
                 stats.days.iter().map(|s| s.into()).collect();
-            let table = Table::new(display_stats).with(Style::rounded()).to_string();
+            let table = Table::new(display_stats)
+                .with(Style::rounded())
+                .modify(
+                    Rows::first(),
+                    Format::content(|header| tr(header).to_string()),
+                )
+                .to_string();
             println!("{}", table);
 
             // Print summary statistics
-            println!("\n=== SUMMARY ===\n");
+            println!("\n=== {} ===\n", tr("SUMMARY"));
 
-            println!("ANKI MEMORIZATION:");
-            println!(
-                "  Total: {:.2} min ({:.1} hrs)",
-                stats.summary.anki_total_minutes, stats.summary.anki_total_hours
+            print_heading("ANKI MEMORIZATION");
+            print_total(
+                stats.summary.anki_total_minutes,
+                stats.summary.anki_total_hours,
             );
-            println!(
-                "  Average: {:.2} min/day",
-                stats.summary.anki_average_minutes_per_day
-            );
-            println!(
-                "  Days studied: {} / {}",
-                stats.summary.anki_days_studied, stats.summary.total_days
+            print_average(stats.summary.anki_average_minutes_per_day, "day");
+            print_count(
+                "Days studied",
+                stats.summary.anki_days_studied,
+                stats.summary.total_days,
             );
-            println!(
-                "  Passages: +{} matured, -{} lost (net: {:+})",
+            print_passages(
                 stats.summary.anki_total_matured_passages,
                 stats.summary.anki_total_lost_passages,
-                stats.summary.anki_net_progress
+                stats.summary.anki_net_progress,
             );
 
-            println!("\nBIBLE READING:");
-            println!(
-                "  Total: {:.2} min ({:.1} hrs)",
-                stats.summary.reading_total_minutes, stats.summary.reading_total_hours
+            println!();
+            print_heading("BIBLE READING");
+            print_total(
+                stats.summary.reading_total_minutes,
+                stats.summary.reading_total_hours,
             );
-            println!(
-                "  Average: {:.2} min/day",
-                stats.summary.reading_average_minutes_per_day
-            );
-            println!(
-                "  Days read: {} / {}",
-                stats.summary.reading_days_studied, stats.summary.total_days
+            print_average(stats.summary.reading_average_minutes_per_day, "day");
+            print_count(
+                "Days read",
+                stats.summary.reading_days_studied,
+                stats.summary.total_days,
             );
 
             if stats.summary.prayer_total_minutes > 0.0 {
-                println!("\nPRAYER:");
-                println!(
-                    "  Total: {:.2} min ({:.1} hrs)",
-                    stats.summary.prayer_total_minutes, stats.summary.prayer_total_hours
+                println!();
+                print_heading("PRAYER");
+                print_total(
+                    stats.summary.prayer_total_minutes,
+                    stats.summary.prayer_total_hours,
                 );
-                println!(
-                    "  Average: {:.2} min/day",
-                    stats.summary.prayer_average_minutes_per_day
-                );
-                println!(
-                    "  Days prayed: {} / {}",
-                    stats.summary.prayer_days_studied, stats.summary.total_days
+                print_average(stats.summary.prayer_average_minutes_per_day, "day");
+                print_count(
+                    "Days prayed",
+                    stats.summary.prayer_days_studied,
+                    stats.summary.total_days,
                 );
             }
 
-            println!("\nCOMBINED TOTAL:");
-            println!(
-                "  Total: {:.2} min ({:.1} hrs)",
-                stats.summary.total_minutes, stats.summary.total_hours
-            );
-            println!(
-                "  Average: {:.2} min/day",
-                stats.summary.average_minutes_per_day
-            );
-            println!(
-                "  Days with any activity: {} / {}",
-                stats.summary.days_with_any_activity, stats.summary.total_days
+            println!();
+            print_heading("COMBINED TOTAL");
+            print_total(stats.summary.total_minutes, stats.summary.total_hours);
+            print_average(stats.summary.average_minutes_per_day, "day");
+            print_count(
+                "Days with any activity",
+                stats.summary.days_with_any_activity,
+                stats.summary.total_days,
             );
 
             println!();
@@ -143,7 +234,7 @@ fn run_daily_command() {
     }
 }
 
-fn run_weekly_command() {
+fn run_weekly_command(partial: PartialPeriod) {
     // Get database paths from environment variables
     let anki_db = std::env::var("ANKI_DATABASE_PATH").unwrap_or_else(|_| {
         eprintln!("Error: ANKI_DATABASE_PATH environment variable is required");
@@ -169,98 +260,243 @@ fn run_weekly_command() {
         process::exit(1);
     });
 
-    match get_faith_weekly_stats(&anki_db, &koreader_db, &arcstats_export, &proseuche_db) {
+    let stats = with_place_overrides(FaithStats::builder())
+        .anki(anki_db)
+        .anki_config(AnkiStatsConfig::from_env())
+        .reading(koreader_db)
+        .arc(arcstats_export)
+        .prayer(proseuche_db)
+        .build()
+        .weekly()
+        .and_then(|stats| {
+            let stats = match open_store_if_configured()? {
+                Some(store) => with_imported_weeks(stats, &store)?,
+                None => stats,
+            };
+            Ok(stats.with_partial_period(partial, &get_current_week_start()?))
+        });
+
+    match stats {
         Ok(stats) => {
-            println!("\n=== FAITH STATS - LAST 12 WEEKS ===\n");
+            println!("\n=== {} ===\n", tr("FAITH STATS - LAST 12 WEEKS"));
 
             // Convert to display format and create table
             let display_stats: Vec<FaithWeekStatsDisplay> =
                 stats.weeks.iter().map(|s| s.into()).collect();
-            let table = Table::new(display_stats).with(Style::rounded()).to_string();
+            let table = Table::new(display_stats)
+                .with(Style::rounded())
+                .modify(
+                    Rows::first(),
+                    Format::content(|header| tr(header).to_string()),
+                )
+                .to_string();
             println!("{}", table);
 
             // Print summary statistics
-            println!("\n=== SUMMARY ===\n");
+            println!("\n=== {} ===\n", tr("SUMMARY"));
 
-            println!("ANKI MEMORIZATION:");
-            println!(
-                "  Total: {:.2} min ({:.1} hrs)",
-                stats.summary.anki_total_minutes, stats.summary.anki_total_hours
+            print_heading("ANKI MEMORIZATION");
+            print_total(
+                stats.summary.anki_total_minutes,
+                stats.summary.anki_total_hours,
             );
-            println!(
-                "  Average: {:.2} min/week",
-                stats.summary.anki_average_minutes_per_week
+            print_average(stats.summary.anki_average_minutes_per_week, "week");
+            print_count(
+                "Weeks studied",
+                stats.summary.anki_weeks_studied,
+                stats.summary.total_weeks,
             );
-            println!(
-                "  Weeks studied: {} / {}",
-                stats.summary.anki_weeks_studied, stats.summary.total_weeks
-            );
-            println!(
-                "  Passages: +{} matured, -{} lost (net: {:+})",
+            print_passages(
                 stats.summary.anki_total_matured_passages,
                 stats.summary.anki_total_lost_passages,
-                stats.summary.anki_net_progress
+                stats.summary.anki_net_progress,
             );
+            if let Some(projected) = stats.weeks.last().and_then(|w| w.anki_projected_minutes) {
+                println!(
+                    "  {}: {} {}",
+                    tr("Projected next 7 days"),
+                    format_number(projected, 2),
+                    tr("min")
+                );
+            }
 
-            println!("\nBIBLE READING:");
-            println!(
-                "  Total: {:.2} min ({:.1} hrs)",
-                stats.summary.reading_total_minutes, stats.summary.reading_total_hours
-            );
-            println!(
-                "  Average: {:.2} min/week",
-                stats.summary.reading_average_minutes_per_week
+            println!();
+            print_heading("BIBLE READING");
+            print_total(
+                stats.summary.reading_total_minutes,
+                stats.summary.reading_total_hours,
             );
-            println!(
-                "  Weeks read: {} / {}",
-                stats.summary.reading_weeks_studied, stats.summary.total_weeks
+            print_average(stats.summary.reading_average_minutes_per_week, "week");
+            print_count(
+                "Weeks read",
+                stats.summary.reading_weeks_studied,
+                stats.summary.total_weeks,
             );
 
-            println!("\nCHURCH ATTENDANCE:");
-            println!(
-                "  Total: {:.2} min ({:.1} hrs)",
-                stats.summary.church_total_minutes, stats.summary.church_total_hours
-            );
-            println!(
-                "  Average: {:.2} min/week",
-                stats.summary.church_average_minutes_per_week
+            println!();
+            print_heading("CHURCH ATTENDANCE");
+            print_total(
+                stats.summary.church_total_minutes,
+                stats.summary.church_total_hours,
             );
-            println!(
-                "  Weeks attended: {} / {}",
-                stats.summary.church_weeks_attended, stats.summary.total_weeks
+            print_average(stats.summary.church_average_minutes_per_week, "week");
+            print_count(
+                "Weeks attended",
+                stats.summary.church_weeks_attended,
+                stats.summary.total_weeks,
             );
 
             if stats.summary.prayer_total_minutes > 0.0 {
-                println!("\nPRAYER:");
-                println!(
-                    "  Total: {:.2} min ({:.1} hrs)",
-                    stats.summary.prayer_total_minutes, stats.summary.prayer_total_hours
+                println!();
+                print_heading("PRAYER");
+                print_total(
+                    stats.summary.prayer_total_minutes,
+                    stats.summary.prayer_total_hours,
                 );
-                println!(
-                    "  Average: {:.2} min/week",
-                    stats.summary.prayer_average_minutes_per_week
-                );
-                println!(
-                    "  Weeks prayed: {} / {}",
-                    stats.summary.prayer_weeks_studied, stats.summary.total_weeks
+                print_average(stats.summary.prayer_average_minutes_per_week, "week");
+                print_count(
+                    "Weeks prayed",
+                    stats.summary.prayer_weeks_studied,
+                    stats.summary.total_weeks,
                 );
             }
 
-            println!("\nCOMBINED TOTAL:");
-            println!(
-                "  Total: {:.2} min ({:.1} hrs)",
-                stats.summary.total_minutes, stats.summary.total_hours
+            println!();
+            print_heading("COMBINED TOTAL");
+            print_total(stats.summary.total_minutes, stats.summary.total_hours);
+            print_average(stats.summary.average_minutes_per_week, "week");
+            print_count(
+                "Weeks with any activity",
+                stats.summary.weeks_with_any_activity,
+                stats.summary.total_weeks,
             );
+
+            println!();
+        }
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn run_export_command(output: &str) {
+    // Get database paths from environment variables
+    let anki_db = std::env::var("ANKI_DATABASE_PATH").unwrap_or_else(|_| {
+        eprintln!("Error: ANKI_DATABASE_PATH environment variable is required");
+        eprintln!("Set it in a .env file or export it in your shell");
+        process::exit(1);
+    });
+
+    let koreader_db = std::env::var("KOREADER_DATABASE_PATH").unwrap_or_else(|_| {
+        eprintln!("Error: KOREADER_DATABASE_PATH environment variable is required");
+        eprintln!("Set it in a .env file or export it in your shell");
+        process::exit(1);
+    });
+
+    let arcstats_export = std::env::var("ARCSTATS_EXPORT_PATH").unwrap_or_else(|_| {
+        eprintln!("Error: ARCSTATS_EXPORT_PATH environment variable is required");
+        eprintln!("Set it in a .env file or export it in your shell");
+        process::exit(1);
+    });
+
+    let proseuche_db = std::env::var("PROSEUCHE_DATABASE_PATH").unwrap_or_else(|_| {
+        eprintln!("Error: PROSEUCHE_DATABASE_PATH environment variable is required");
+        eprintln!("Set it in a .env file or export it in your shell");
+        process::exit(1);
+    });
+
+    let file = File::create(output).unwrap_or_else(|e| {
+        eprintln!("Error: Failed to create {}: {}", output, e);
+        process::exit(1);
+    });
+
+    let sources = with_place_overrides(FaithStats::builder())
+        .anki(anki_db)
+        .anki_config(AnkiStatsConfig::from_env())
+        .reading(koreader_db)
+        .arc(arcstats_export)
+        .prayer(proseuche_db)
+        .build();
+    match write_export_archive(BufWriter::new(file), &sources) {
+        Ok(()) => {
+            println!("Exported stats to {}", output);
+        }
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn run_import_command(archive: &str) {
+    let store_path = std::env::var("STATS_STORE_PATH").unwrap_or_else(|_| {
+        eprintln!("Error: STATS_STORE_PATH environment variable is required");
+        eprintln!("Set it in a .env file or export it in your shell");
+        process::exit(1);
+    });
+
+    let file = File::open(archive).unwrap_or_else(|e| {
+        eprintln!("Error: Failed to open {}: {}", archive, e);
+        process::exit(1);
+    });
+
+    let result = Store::open(&store_path)
+        .and_then(|mut store| import_archive(BufReader::new(file), &mut store));
+
+    match result {
+        Ok(summary) => {
             println!(
-                "  Average: {:.2} min/week",
-                stats.summary.average_minutes_per_week
+                "Imported {} days and {} weeks (archive generated {}) into {}",
+                summary.days, summary.weeks, summary.manifest.generated_at, store_path
             );
+        }
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn run_doctor_command() {
+    let arcstats_export = std::env::var("ARCSTATS_EXPORT_PATH").unwrap_or_else(|_| {
+        eprintln!("Error: ARCSTATS_EXPORT_PATH environment variable is required");
+        eprintln!("Set it in a .env file or export it in your shell");
+        process::exit(1);
+    });
+
+    let overrides_path = place_overrides_path();
+    let mut history = LocationHistory::new(&arcstats_export);
+    if let Some(path) = &overrides_path {
+        history = history.with_overrides(path);
+    }
+    match faithstats::places::check_location_history(history) {
+        Ok(None) => {
+            println!("Location history is not an Arc export, nothing to check");
+        }
+        Ok(Some(report)) if report.is_empty() => {
+            println!("✓ No duplicated or overlapping timeline items");
+        }
+        Ok(Some(report)) => {
             println!(
-                "  Weeks with any activity: {} / {}",
-                stats.summary.weeks_with_any_activity, stats.summary.total_weeks
+                "✗ Dropped {} duplicated and {} overlapping timeline items before stats:\n",
+                report.count(DropReason::DuplicateId),
+                report.count(DropReason::Overlapping)
             );
-
-            println!();
+            for dropped in &report.dropped {
+                let reason = match dropped.reason {
+                    DropReason::DuplicateId => "duplicate ID",
+                    DropReason::Overlapping => "overlaps",
+                };
+                println!(
+                    "  {} ({} to {}): {} {}",
+                    dropped.item_id,
+                    dropped.start.format(display_format().datetime_pattern()),
+                    dropped.end.format(display_format().datetime_pattern()),
+                    reason,
+                    dropped.kept_item_id
+                );
+            }
         }
         Err(e) => {
             eprintln!("Error: {:#}", e);
@@ -268,3 +504,72 @@ fn run_weekly_command() {
         }
     }
 }
+
+/// Prints a summary section heading, e.g. "BIBLE READING:"
+fn print_heading(heading: &str) {
+    println!("{}:", tr(heading));
+}
+
+/// Prints the total minutes and hours of a summary section
+fn print_total(minutes: f64, hours: f64) {
+    println!(
+        "  {}: {} {} ({} {})",
+        tr("Total"),
+        format_number(minutes, 2),
+        tr("min"),
+        format_number(hours, 1),
+        tr("hrs")
+    );
+}
+
+/// Prints the average minutes per period ("day" or "week") of a summary section
+fn print_average(minutes: f64, period: &str) {
+    println!(
+        "  {}: {} {}/{}",
+        tr("Average"),
+        format_number(minutes, 2),
+        tr("min"),
+        tr(period)
+    );
+}
+
+/// Prints the number of periods with activity out of all periods, e.g. "Days read: 5 / 30"
+fn print_count(label: &str, count: usize, total: usize) {
+    println!("  {}: {} / {}", tr(label), count, total);
+}
+
+/// Prints the passages matured and lost in the range
+fn print_passages(matured: i64, lost: i64, net: i64) {
+    println!(
+        "  {}: +{} {}, -{} {} ({}: {:+})",
+        tr("Passages"),
+        matured,
+        tr("matured"),
+        lost,
+        tr("lost"),
+        tr("net"),
+        net
+    );
+}
+
+/// File correcting Arc's place names, categories and duplicates, if PLACE_OVERRIDES_PATH
+/// is set
+fn place_overrides_path() -> Option<PathBuf> {
+    std::env::var_os("PLACE_OVERRIDES_PATH").map(PathBuf::from)
+}
+
+/// Applies the place overrides file from PLACE_OVERRIDES_PATH, if set, to a query
+fn with_place_overrides(builder: FaithStatsBuilder) -> FaithStatsBuilder {
+    match place_overrides_path() {
+        Some(path) => builder.place_overrides(path),
+        None => builder,
+    }
+}
+
+/// Opens the stats store if STATS_STORE_PATH is set, so imported history can be merged in
+fn open_store_if_configured() -> anyhow::Result<Option<Store>> {
+    std::env::var("STATS_STORE_PATH")
+        .ok()
+        .map(|path| Store::open(&path))
+        .transpose()
+}
@@ -1,36 +1,128 @@
-use serde::Serialize;
+use chrono::{Days, NaiveDate};
+use serde::{Deserialize, Serialize};
+use statsutils::format::format_number;
+use statsutils::{DataRange, PartialPeriod};
+
+use crate::goals::{GoalMetric, GoalProgress};
 use tabled::Tabled;
-use utoipa::ToSchema;
 
 /// Combined faith statistics for a single day
-#[derive(Debug, Clone, Serialize, ToSchema)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct FaithDayStats {
     /// Date in YYYY-MM-DD format
     pub date: String,
 
     // Anki Bible memorization stats
-    /// Anki study time in minutes
-    pub anki_minutes: f64,
+    /// Anki study time in minutes (`None` before the first recorded review)
+    pub anki_minutes: Option<f64>,
     /// Number of passages that matured on this day
     pub anki_matured_passages: i64,
     /// Number of passages that were lost on this day
     pub anki_lost_passages: i64,
     /// Cumulative count of mature passages at end of day
     pub anki_cumulative_passages: i64,
+    /// References of passages that matured on this day (capped)
+    #[serde(default)]
+    pub anki_matured_references: Vec<String>,
+    /// References of passages that were lost on this day (capped)
+    #[serde(default)]
+    pub anki_lost_references: Vec<String>,
+    /// Number of Anki reviews on this day
+    #[serde(default)]
+    pub anki_reviews: i64,
+    /// Average time per Anki review in seconds (`None` without reviews)
+    #[serde(default)]
+    pub anki_avg_seconds_per_review: Option<f64>,
 
     // KOReader Bible reading stats
-    /// Bible reading time in minutes
-    pub reading_minutes: f64,
+    /// Bible reading time in minutes (`None` before the first recorded reading)
+    pub reading_minutes: Option<f64>,
 
     // Prayer stats (future)
-    /// Prayer time in minutes
-    pub prayer_minutes: f64,
+    /// Prayer time in minutes (`None` before the first recorded prayer)
+    pub prayer_minutes: Option<f64>,
+
+    /// Whether the day is exempt (vacation, illness): it neither breaks nor extends streaks,
+    /// and is left out of goal reminders and consistency metrics
+    #[serde(default)]
+    pub exempt: bool,
+
+    /// First line of the day's note, shortened to 80 characters (`None` without a note)
+    #[serde(default)]
+    pub note: Option<String>,
+
+    /// Tags of the day, e.g. "travel" or "feast"
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 impl FaithDayStats {
     /// Total minutes across all faith activities for this day
     pub fn total_minutes(&self) -> f64 {
-        self.anki_minutes + self.reading_minutes + self.prayer_minutes
+        [self.anki_minutes, self.reading_minutes, self.prayer_minutes]
+            .iter()
+            .flatten()
+            // Summing floats starts at -0.0, which would show as "-0" without any activity
+            .fold(0.0, |total, minutes| total + minutes)
+    }
+
+    /// Whether any source had recorded activity by this day
+    pub fn has_history(&self) -> bool {
+        self.anki_minutes.is_some()
+            || self.reading_minutes.is_some()
+            || self.prayer_minutes.is_some()
+    }
+}
+
+/// First date (YYYY-MM-DD) with recorded activity in each daily source, `None` for a source
+/// without any
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HistoryStarts {
+    pub anki: Option<String>,
+    pub reading: Option<String>,
+    pub prayer: Option<String>,
+}
+
+impl HistoryStarts {
+    /// Reports the minutes of days before a source's first recorded activity as `None`, so
+    /// that they are not mistaken for days without activity
+    pub fn clear_days_before(&self, days: &mut [FaithDayStats]) {
+        let before =
+            |start: &Option<String>, date: &str| start.as_deref().is_none_or(|start| date < start);
+        for day in days {
+            if before(&self.anki, &day.date) {
+                day.anki_minutes = None;
+            }
+            if before(&self.reading, &day.date) {
+                day.reading_minutes = None;
+            }
+            if before(&self.prayer, &day.date) {
+                day.prayer_minutes = None;
+            }
+        }
+    }
+
+    /// Whether each source had recorded activity by the end of the week starting on
+    /// `week_start`, as (Anki, reading, prayer)
+    fn tracks_week(&self, week_start: &str) -> (bool, bool, bool) {
+        let week_end = NaiveDate::parse_from_str(week_start, "%Y-%m-%d")
+            .ok()
+            .and_then(|date| date.checked_add_days(Days::new(6)))
+            .map_or_else(
+                || week_start.to_string(),
+                |date| date.format("%Y-%m-%d").to_string(),
+            );
+        let tracks = |start: &Option<String>| {
+            start
+                .as_deref()
+                .is_some_and(|start| start <= week_end.as_str())
+        };
+        (
+            tracks(&self.anki),
+            tracks(&self.reading),
+            tracks(&self.prayer),
+        )
     }
 }
 
@@ -57,22 +149,42 @@ impl From<&FaithDayStats> for FaithDayStatsDisplay {
     fn from(stats: &FaithDayStats) -> Self {
         Self {
             date: stats.date.clone(),
-            anki_minutes: format!("{:.1}", stats.anki_minutes),
-            reading_minutes: format!("{:.1}", stats.reading_minutes),
-            prayer_minutes: format!("{:.1}", stats.prayer_minutes),
-            total_minutes: format!("{:.1}", stats.total_minutes()),
+            anki_minutes: format_tracked_minutes(stats.anki_minutes),
+            reading_minutes: format_tracked_minutes(stats.reading_minutes),
+            prayer_minutes: format_tracked_minutes(stats.prayer_minutes),
+            total_minutes: format_number(stats.total_minutes(), 1),
         }
     }
 }
 
+/// Formats minutes for the table, with a dash for days before the source's history
+fn format_tracked_minutes(minutes: Option<f64>) -> String {
+    minutes.map_or_else(|| "-".to_string(), |minutes| format_number(minutes, 1))
+}
+
+/// Average over the days or weeks counted, 0 without any
+fn average(total: f64, count: usize) -> f64 {
+    if count == 0 {
+        0.0
+    } else {
+        total / count as f64
+    }
+}
+
 /// Summary statistics for faith activities over a period
-#[derive(Debug, Clone, Serialize, ToSchema)]
+///
+/// Days without minutes for a source (before its first recorded activity) are left out of
+/// its averages.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct FaithDailySummary {
     // Anki stats
     pub anki_total_minutes: f64,
     pub anki_total_hours: f64,
     pub anki_average_minutes_per_day: f64,
     pub anki_days_studied: usize,
+    /// Days since the first recorded review, which the Anki average is taken over
+    pub anki_days_tracked: usize,
     pub anki_total_matured_passages: i64,
     pub anki_total_lost_passages: i64,
     pub anki_net_progress: i64,
@@ -82,38 +194,59 @@ pub struct FaithDailySummary {
     pub reading_total_hours: f64,
     pub reading_average_minutes_per_day: f64,
     pub reading_days_studied: usize,
+    /// Days since the first recorded reading, which the reading average is taken over
+    pub reading_days_tracked: usize,
 
     // Prayer stats
     pub prayer_total_minutes: f64,
     pub prayer_total_hours: f64,
     pub prayer_average_minutes_per_day: f64,
     pub prayer_days_studied: usize,
+    /// Days since the first recorded prayer, which the prayer average is taken over
+    pub prayer_days_tracked: usize,
 
     // Combined stats
     pub total_minutes: f64,
     pub total_hours: f64,
     pub average_minutes_per_day: f64,
     pub total_days: usize,
+    /// Days on which any source had history, which the combined average is taken over
+    pub days_tracked: usize,
     pub days_with_any_activity: usize,
+    /// Whether the current, still running day is counted (`include`) or left out (`exclude`)
+    pub partial_period: PartialPeriod,
 }
 
 impl FaithDailySummary {
     pub fn from_faith_daily_stats(days: &[FaithDayStats]) -> Self {
-        let anki_total: f64 = days.iter().map(|d| d.anki_minutes).sum();
-        let reading_total: f64 = days.iter().map(|d| d.reading_minutes).sum();
-        let prayer_total: f64 = days.iter().map(|d| d.prayer_minutes).sum();
+        let anki_total: f64 = days.iter().filter_map(|d| d.anki_minutes).sum();
+        let reading_total: f64 = days.iter().filter_map(|d| d.reading_minutes).sum();
+        let prayer_total: f64 = days.iter().filter_map(|d| d.prayer_minutes).sum();
         let combined_total = anki_total + reading_total + prayer_total;
 
-        let anki_days = days.iter().filter(|d| d.anki_minutes > 0.0).count();
-        let reading_days = days.iter().filter(|d| d.reading_minutes > 0.0).count();
-        let prayer_days = days.iter().filter(|d| d.prayer_minutes > 0.0).count();
+        let studied = |minutes: fn(&FaithDayStats) -> Option<f64>| {
+            days.iter()
+                .filter(|d| minutes(d).is_some_and(|m| m > 0.0))
+                .count()
+        };
+        let tracked = |minutes: fn(&FaithDayStats) -> Option<f64>| {
+            days.iter().filter(|d| minutes(d).is_some()).count()
+        };
+        let anki_days = studied(|d| d.anki_minutes);
+        let reading_days = studied(|d| d.reading_minutes);
+        let prayer_days = studied(|d| d.prayer_minutes);
         let any_activity_days = days.iter().filter(|d| d.total_minutes() > 0.0).count();
 
+        let anki_tracked = tracked(|d| d.anki_minutes);
+        let reading_tracked = tracked(|d| d.reading_minutes);
+        let prayer_tracked = tracked(|d| d.prayer_minutes);
+        let days_tracked = days.iter().filter(|d| d.has_history()).count();
+
         let total_days = days.len();
-        let anki_avg = anki_total / total_days as f64;
-        let reading_avg = reading_total / total_days as f64;
-        let prayer_avg = prayer_total / total_days as f64;
-        let combined_avg = combined_total / total_days as f64;
+        let anki_avg = average(anki_total, anki_tracked);
+        let reading_avg = average(reading_total, reading_tracked);
+        let prayer_avg = average(prayer_total, prayer_tracked);
+        let combined_avg = average(combined_total, days_tracked);
 
         let anki_matured: i64 = days.iter().map(|d| d.anki_matured_passages).sum();
         let anki_lost: i64 = days.iter().map(|d| d.anki_lost_passages).sum();
@@ -123,6 +256,7 @@ impl FaithDailySummary {
             anki_total_hours: anki_total / 60.0,
             anki_average_minutes_per_day: anki_avg,
             anki_days_studied: anki_days,
+            anki_days_tracked: anki_tracked,
             anki_total_matured_passages: anki_matured,
             anki_total_lost_passages: anki_lost,
             anki_net_progress: anki_matured - anki_lost,
@@ -131,23 +265,28 @@ impl FaithDailySummary {
             reading_total_hours: reading_total / 60.0,
             reading_average_minutes_per_day: reading_avg,
             reading_days_studied: reading_days,
+            reading_days_tracked: reading_tracked,
 
             prayer_total_minutes: prayer_total,
             prayer_total_hours: prayer_total / 60.0,
             prayer_average_minutes_per_day: prayer_avg,
             prayer_days_studied: prayer_days,
+            prayer_days_tracked: prayer_tracked,
 
             total_minutes: combined_total,
             total_hours: combined_total / 60.0,
             average_minutes_per_day: combined_avg,
             total_days,
+            days_tracked,
             days_with_any_activity: any_activity_days,
+            partial_period: PartialPeriod::Include,
         }
     }
 }
 
 /// Faith statistics for multiple days with summary
-#[derive(Debug, Clone, Serialize, ToSchema)]
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct FaithDailyStats {
     pub days: Vec<FaithDayStats>,
     pub summary: FaithDailySummary,
@@ -158,10 +297,36 @@ impl FaithDailyStats {
         let summary = FaithDailySummary::from_faith_daily_stats(&days);
         Self { days, summary }
     }
+
+    /// Recomputes the summary, counting or leaving out the current day (`today`)
+    pub fn with_partial_period(mut self, partial_period: PartialPeriod, today: &str) -> Self {
+        let days = partial_period.complete(&self.days, |day| day.date == today);
+        self.summary = FaithDailySummary {
+            partial_period,
+            ..FaithDailySummary::from_faith_daily_stats(days)
+        };
+        self
+    }
+
+    /// Reports days before a source's first recorded activity as 0 minutes, counting them in
+    /// the averages like days without activity
+    pub fn with_untracked_as_zero(mut self) -> Self {
+        for day in &mut self.days {
+            for minutes in [
+                &mut day.anki_minutes,
+                &mut day.reading_minutes,
+                &mut day.prayer_minutes,
+            ] {
+                minutes.get_or_insert(0.0);
+            }
+        }
+        Self::new(self.days)
+    }
 }
 
 /// Combined faith statistics for today
-#[derive(Debug, Clone, Serialize, ToSchema)]
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct FaithTodayStats {
     /// Anki study time in minutes
     pub anki_minutes: f64,
@@ -188,8 +353,115 @@ impl FaithTodayStats {
     }
 }
 
+/// Current streaks of consecutive days with activity, per source and overall
+///
+/// A streak that has not been continued today yet still counts until the day is over.
+/// Exempt days are skipped: they neither break a streak nor count towards it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FaithStreaks {
+    /// Consecutive days with any faith activity
+    pub any_activity_days: u32,
+    /// Consecutive days with Anki study
+    pub anki_days: u32,
+    /// Consecutive days with Bible reading
+    pub reading_days: u32,
+    /// Consecutive days with prayer
+    pub prayer_days: u32,
+}
+
+impl FaithStreaks {
+    /// Computes the current streaks from a daily series ending with today
+    pub fn from_days(days: &[FaithDayStats]) -> Self {
+        Self {
+            any_activity_days: current_streak(days, |d| d.total_minutes()),
+            anki_days: current_streak(days, |d| d.anki_minutes.unwrap_or(0.0)),
+            reading_days: current_streak(days, |d| d.reading_minutes.unwrap_or(0.0)),
+            prayer_days: current_streak(days, |d| d.prayer_minutes.unwrap_or(0.0)),
+        }
+    }
+}
+
+/// Counts consecutive active days backwards from the last day, skipping today if it has
+/// no activity yet and exempt days without activity
+fn current_streak(days: &[FaithDayStats], minutes: impl Fn(&FaithDayStats) -> f64) -> u32 {
+    let mut remaining = days;
+    if let Some((today, earlier)) = days.split_last()
+        && minutes(today) <= 0.0
+    {
+        remaining = earlier;
+    }
+
+    remaining
+        .iter()
+        .rev()
+        .filter(|day| !day.exempt || minutes(day) > 0.0)
+        .take_while(|day| minutes(day) > 0.0)
+        .count() as u32
+}
+
+/// Flat snapshot of today's, this week's and streak statistics
+///
+/// Keys are stable and never nested, so that Home Assistant REST sensors can pick values
+/// with a plain `value_json.<key>` template.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct HomeAssistantState {
+    /// Today's date in YYYY-MM-DD format
+    pub today: String,
+    pub today_anki_minutes: f64,
+    pub today_reading_minutes: f64,
+    pub today_prayer_minutes: f64,
+    pub today_total_minutes: f64,
+    /// Consecutive days with any faith activity
+    pub streak_days: u32,
+    pub anki_streak_days: u32,
+    pub reading_streak_days: u32,
+    pub prayer_streak_days: u32,
+    /// Start of the current week in YYYY-MM-DD format
+    pub week_start: String,
+    pub weekly_anki_minutes: f64,
+    pub weekly_reading_minutes: f64,
+    pub weekly_church_minutes: f64,
+    pub weekly_prayer_minutes: f64,
+    pub weekly_total_minutes: f64,
+    /// Total mature passages at the end of the current week
+    pub anki_mature_passages: i64,
+}
+
+impl HomeAssistantState {
+    /// Builds the snapshot from daily stats ending today and weekly stats ending this week
+    pub fn new(daily: &FaithDailyStats, weekly: &FaithWeeklyStats) -> Self {
+        let streaks = FaithStreaks::from_days(&daily.days);
+        let today = daily.days.last();
+        let week = weekly.weeks.last();
+        let today_minutes = |minutes: fn(&FaithDayStats) -> f64| today.map_or(0.0, minutes);
+        let week_minutes = |minutes: fn(&FaithWeekStats) -> f64| week.map_or(0.0, minutes);
+
+        Self {
+            today: today.map(|d| d.date.clone()).unwrap_or_default(),
+            today_anki_minutes: today_minutes(|d| d.anki_minutes.unwrap_or(0.0)),
+            today_reading_minutes: today_minutes(|d| d.reading_minutes.unwrap_or(0.0)),
+            today_prayer_minutes: today_minutes(|d| d.prayer_minutes.unwrap_or(0.0)),
+            today_total_minutes: today_minutes(|d| d.total_minutes()),
+            streak_days: streaks.any_activity_days,
+            anki_streak_days: streaks.anki_days,
+            reading_streak_days: streaks.reading_days,
+            prayer_streak_days: streaks.prayer_days,
+            week_start: week.map(|w| w.week_start.clone()).unwrap_or_default(),
+            weekly_anki_minutes: week_minutes(|w| w.anki_minutes),
+            weekly_reading_minutes: week_minutes(|w| w.reading_minutes),
+            weekly_church_minutes: week_minutes(|w| w.at_church_minutes),
+            weekly_prayer_minutes: week_minutes(|w| w.prayer_minutes),
+            weekly_total_minutes: week_minutes(|w| w.total_minutes()),
+            anki_mature_passages: week.map_or(0, |w| w.anki_cumulative_passages),
+        }
+    }
+}
+
 /// Combined faith statistics for a single week
-#[derive(Debug, Clone, Serialize, ToSchema)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct FaithWeekStats {
     /// Week start date in YYYY-MM-DD format
     pub week_start: String,
@@ -203,6 +475,22 @@ pub struct FaithWeekStats {
     pub anki_lost_passages: i64,
     /// Cumulative count of mature passages at end of week
     pub anki_cumulative_passages: i64,
+    /// References of passages that matured during this week (capped)
+    #[serde(default)]
+    pub anki_matured_references: Vec<String>,
+    /// References of passages that were lost during this week (capped)
+    #[serde(default)]
+    pub anki_lost_references: Vec<String>,
+    /// Number of Anki reviews during this week
+    #[serde(default)]
+    pub anki_reviews: i64,
+    /// Average time per Anki review in seconds (`None` without reviews)
+    #[serde(default)]
+    pub anki_avg_seconds_per_review: Option<f64>,
+    /// Expected Anki review minutes for the next 7 days (due reviews × average seconds per
+    /// review); only set on the current week, and left out if the due reviews cannot be read
+    #[serde(default)]
+    pub anki_projected_minutes: Option<f64>,
 
     // KOReader Bible reading stats
     /// Bible reading time in minutes
@@ -215,6 +503,10 @@ pub struct FaithWeekStats {
     // Prayer stats (future)
     /// Prayer time in minutes
     pub prayer_minutes: f64,
+
+    /// Number of exempt days in the week; weekly goal targets shrink by 1/7 per exempt day
+    #[serde(default)]
+    pub exempt_days: i64,
 }
 
 impl FaithWeekStats {
@@ -250,23 +542,27 @@ impl From<&FaithWeekStats> for FaithWeekStatsDisplay {
     fn from(stats: &FaithWeekStats) -> Self {
         Self {
             week_start: stats.week_start.clone(),
-            anki_minutes: format!("{:.1}", stats.anki_minutes),
-            reading_minutes: format!("{:.1}", stats.reading_minutes),
-            church_minutes: format!("{:.1}", stats.at_church_minutes),
-            prayer_minutes: format!("{:.1}", stats.prayer_minutes),
-            total_minutes: format!("{:.1}", stats.total_minutes()),
+            anki_minutes: format_number(stats.anki_minutes, 1),
+            reading_minutes: format_number(stats.reading_minutes, 1),
+            church_minutes: format_number(stats.at_church_minutes, 1),
+            prayer_minutes: format_number(stats.prayer_minutes, 1),
+            total_minutes: format_number(stats.total_minutes(), 1),
         }
     }
 }
 
 /// Summary statistics for faith activities over a weekly period
-#[derive(Debug, Clone, Serialize, ToSchema)]
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct FaithWeeklySummary {
     // Anki stats
     pub anki_total_minutes: f64,
     pub anki_total_hours: f64,
     pub anki_average_minutes_per_week: f64,
     pub anki_weeks_studied: usize,
+    /// Weeks ending on or after the first recorded review, which the Anki average is taken
+    /// over
+    pub anki_weeks_tracked: usize,
     pub anki_total_matured_passages: i64,
     pub anki_total_lost_passages: i64,
     pub anki_net_progress: i64,
@@ -276,6 +572,9 @@ pub struct FaithWeeklySummary {
     pub reading_total_hours: f64,
     pub reading_average_minutes_per_week: f64,
     pub reading_weeks_studied: usize,
+    /// Weeks ending on or after the first recorded reading, which the reading average is
+    /// taken over
+    pub reading_weeks_tracked: usize,
 
     // Church stats
     pub church_total_minutes: f64,
@@ -288,17 +587,31 @@ pub struct FaithWeeklySummary {
     pub prayer_total_hours: f64,
     pub prayer_average_minutes_per_week: f64,
     pub prayer_weeks_studied: usize,
+    /// Weeks ending on or after the first recorded prayer, which the prayer average is taken
+    /// over
+    pub prayer_weeks_tracked: usize,
 
     // Combined stats
     pub total_minutes: f64,
     pub total_hours: f64,
     pub average_minutes_per_week: f64,
     pub total_weeks: usize,
+    /// Weeks tracked by any source or with church attendance, which the combined average is
+    /// taken over
+    pub weeks_tracked: usize,
     pub weeks_with_any_activity: usize,
+    /// Whether the current, still running week is counted (`include`) or left out (`exclude`)
+    pub partial_period: PartialPeriod,
 }
 
 impl FaithWeeklySummary {
     pub fn from_faith_weekly_stats(weeks: &[FaithWeekStats]) -> Self {
+        Self::summarize(weeks, None)
+    }
+
+    /// Summarizes the weeks; with `history`, weeks ending before a source's first recorded
+    /// activity are left out of its average
+    fn summarize(weeks: &[FaithWeekStats], history: Option<&HistoryStarts>) -> Self {
         let anki_total: f64 = weeks.iter().map(|w| w.anki_minutes).sum();
         let reading_total: f64 = weeks.iter().map(|w| w.reading_minutes).sum();
         let church_total: f64 = weeks.iter().map(|w| w.at_church_minutes).sum();
@@ -311,12 +624,25 @@ impl FaithWeeklySummary {
         let prayer_weeks = weeks.iter().filter(|w| w.prayer_minutes > 0.0).count();
         let any_activity_weeks = weeks.iter().filter(|w| w.total_minutes() > 0.0).count();
 
+        let tracked: Vec<(bool, bool, bool)> = weeks
+            .iter()
+            .map(|w| history.map_or((true, true, true), |h| h.tracks_week(&w.week_start)))
+            .collect();
+        let anki_tracked = tracked.iter().filter(|t| t.0).count();
+        let reading_tracked = tracked.iter().filter(|t| t.1).count();
+        let prayer_tracked = tracked.iter().filter(|t| t.2).count();
+        let weeks_tracked = weeks
+            .iter()
+            .zip(&tracked)
+            .filter(|(w, t)| t.0 || t.1 || t.2 || w.at_church_minutes > 0.0)
+            .count();
+
         let total_weeks = weeks.len();
-        let anki_avg = anki_total / total_weeks as f64;
-        let reading_avg = reading_total / total_weeks as f64;
-        let church_avg = church_total / total_weeks as f64;
-        let prayer_avg = prayer_total / total_weeks as f64;
-        let combined_avg = combined_total / total_weeks as f64;
+        let anki_avg = average(anki_total, anki_tracked);
+        let reading_avg = average(reading_total, reading_tracked);
+        let church_avg = average(church_total, total_weeks);
+        let prayer_avg = average(prayer_total, prayer_tracked);
+        let combined_avg = average(combined_total, weeks_tracked);
 
         let anki_matured: i64 = weeks.iter().map(|w| w.anki_matured_passages).sum();
         let anki_lost: i64 = weeks.iter().map(|w| w.anki_lost_passages).sum();
@@ -326,6 +652,7 @@ impl FaithWeeklySummary {
             anki_total_hours: anki_total / 60.0,
             anki_average_minutes_per_week: anki_avg,
             anki_weeks_studied: anki_weeks,
+            anki_weeks_tracked: anki_tracked,
             anki_total_matured_passages: anki_matured,
             anki_total_lost_passages: anki_lost,
             anki_net_progress: anki_matured - anki_lost,
@@ -334,6 +661,7 @@ impl FaithWeeklySummary {
             reading_total_hours: reading_total / 60.0,
             reading_average_minutes_per_week: reading_avg,
             reading_weeks_studied: reading_weeks,
+            reading_weeks_tracked: reading_tracked,
 
             church_total_minutes: church_total,
             church_total_hours: church_total / 60.0,
@@ -344,26 +672,203 @@ impl FaithWeeklySummary {
             prayer_total_hours: prayer_total / 60.0,
             prayer_average_minutes_per_week: prayer_avg,
             prayer_weeks_studied: prayer_weeks,
+            prayer_weeks_tracked: prayer_tracked,
 
             total_minutes: combined_total,
             total_hours: combined_total / 60.0,
             average_minutes_per_week: combined_avg,
             total_weeks,
+            weeks_tracked,
             weeks_with_any_activity: any_activity_weeks,
+            partial_period: PartialPeriod::Include,
         }
     }
 }
 
 /// Faith statistics for multiple weeks with summary
-#[derive(Debug, Clone, Serialize, ToSchema)]
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct FaithWeeklyStats {
     pub weeks: Vec<FaithWeekStats>,
     pub summary: FaithWeeklySummary,
+    /// When each source's history starts, if weeks before it are left out of the averages
+    #[serde(skip)]
+    history: Option<HistoryStarts>,
 }
 
 impl FaithWeeklyStats {
     pub fn new(weeks: Vec<FaithWeekStats>) -> Self {
         let summary = FaithWeeklySummary::from_faith_weekly_stats(&weeks);
-        Self { weeks, summary }
+        Self {
+            weeks,
+            summary,
+            history: None,
+        }
     }
+
+    /// Recomputes the summary, leaving weeks that end before a source's first recorded
+    /// activity out of its average
+    pub fn with_history(mut self, history: HistoryStarts) -> Self {
+        self.summary = FaithWeeklySummary {
+            partial_period: self.summary.partial_period,
+            ..FaithWeeklySummary::summarize(&self.weeks, Some(&history))
+        };
+        self.history = Some(history);
+        self
+    }
+
+    /// Recomputes the summary, counting or leaving out the current week (`week_start`)
+    pub fn with_partial_period(mut self, partial_period: PartialPeriod, week_start: &str) -> Self {
+        let weeks = partial_period.complete(&self.weeks, |week| week.week_start == week_start);
+        self.summary = FaithWeeklySummary {
+            partial_period,
+            ..FaithWeeklySummary::summarize(weeks, self.history.as_ref())
+        };
+        self
+    }
+}
+
+/// Weekly faith statistics of a single household member
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct HouseholdMemberWeeklyStats {
+    /// Id of the user
+    pub user: String,
+    pub stats: FaithWeeklyStats,
+}
+
+/// Weekly faith statistics for every member of a household, their combined totals,
+/// and progress towards the household's shared goals in the current week
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct HouseholdWeeklyStats {
+    pub members: Vec<HouseholdMemberWeeklyStats>,
+    /// Sum of all members' stats for each week
+    pub combined: FaithWeeklyStats,
+    pub goals: Vec<GoalProgress>,
+}
+
+/// Publicly shareable subset of a week's faith statistics
+///
+/// Only the metrics selected for sharing are present; everything else is omitted.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SharedWeekStats {
+    /// Week start date in YYYY-MM-DD format
+    pub week_start: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub anki_minutes: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reading_minutes: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub at_church_minutes: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prayer_minutes: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_minutes: Option<f64>,
+}
+
+/// Publicly shareable weekly faith statistics
+///
+/// Contains only per-week minute totals for the shared metrics, without passage
+/// counts, verse texts or place names.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SharedWeeklyStats {
+    pub weeks: Vec<SharedWeekStats>,
+}
+
+impl SharedWeeklyStats {
+    /// Strips the weekly stats down to the given metrics
+    pub fn new(stats: &FaithWeeklyStats, metrics: &[GoalMetric]) -> Self {
+        let weeks = stats
+            .weeks
+            .iter()
+            .map(|week| {
+                let shared = |metric: GoalMetric| {
+                    metrics.contains(&metric).then(|| metric.week_minutes(week))
+                };
+                SharedWeekStats {
+                    week_start: week.week_start.clone(),
+                    anki_minutes: shared(GoalMetric::AnkiMinutes),
+                    reading_minutes: shared(GoalMetric::ReadingMinutes),
+                    at_church_minutes: shared(GoalMetric::AtChurchMinutes),
+                    prayer_minutes: shared(GoalMetric::PrayerMinutes),
+                    total_minutes: shared(GoalMetric::TotalMinutes),
+                }
+            })
+            .collect();
+
+        Self { weeks }
+    }
+}
+
+/// Recorded activity range and data freshness for a single source
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SourceDataRange {
+    /// Source name (anki, reading, prayer, or arc)
+    pub source: String,
+    #[serde(flatten)]
+    pub range: DataRange,
+    /// Error message if the source could not be read
+    pub error: Option<String>,
+}
+
+impl SourceDataRange {
+    pub fn new(source: &str, range: anyhow::Result<DataRange>) -> Self {
+        match range {
+            Ok(range) => Self {
+                source: source.to_string(),
+                range,
+                error: None,
+            },
+            Err(e) => Self {
+                source: source.to_string(),
+                range: DataRange::default(),
+                error: Some(format!("{:#}", e)),
+            },
+        }
+    }
+}
+
+/// Per-source staleness thresholds in days; `None` disables the check for that source
+#[derive(Debug, Clone, Default)]
+pub struct StalenessThresholds {
+    pub anki: Option<f64>,
+    pub reading: Option<f64>,
+    pub prayer: Option<f64>,
+    pub arc: Option<f64>,
+}
+
+impl StalenessThresholds {
+    /// Returns the threshold configured for a source name
+    pub fn for_source(&self, source: &str) -> Option<f64> {
+        match source {
+            "anki" => self.anki,
+            "reading" => self.reading,
+            "prayer" => self.prayer,
+            "arc" => self.arc,
+            _ => None,
+        }
+    }
+
+    /// Whether any threshold is configured
+    pub fn is_empty(&self) -> bool {
+        self.anki.is_none() && self.reading.is_none() && self.prayer.is_none() && self.arc.is_none()
+    }
+}
+
+/// A source whose data is older than its configured staleness threshold
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct StaleSource {
+    /// Source name (anki, reading, prayer, or arc)
+    pub source: String,
+    /// Days elapsed since the latest recorded activity, if any
+    pub days_since_latest: Option<f64>,
+    /// Configured staleness threshold in days
+    pub threshold_days: f64,
+    /// Human-readable explanation of the breach
+    pub reason: String,
 }
@@ -0,0 +1,78 @@
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::models::FaithDailyStats;
+
+/// Longest note accepted, in characters
+pub const MAX_NOTE_CHARS: usize = 2000;
+
+/// Characters of a note included in daily stats
+pub const NOTE_SNIPPET_CHARS: usize = 80;
+
+/// Free-form note on a day, e.g. "sick", "retreat day" or "started Romans"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DayNote {
+    /// Date in YYYY-MM-DD format
+    pub date: String,
+    pub text: String,
+    /// RFC 3339 timestamp of the last change
+    pub updated_at: String,
+}
+
+/// Body of a request setting a day's note
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct NoteText {
+    pub text: String,
+}
+
+/// Checks that a note is neither blank nor longer than `MAX_NOTE_CHARS`
+pub fn validate_note(text: &str) -> Result<()> {
+    if text.trim().is_empty() {
+        bail!("Note must not be empty");
+    }
+    if text.chars().count() > MAX_NOTE_CHARS {
+        bail!("Note must not exceed {} characters", MAX_NOTE_CHARS);
+    }
+    Ok(())
+}
+
+/// First line of a note, shortened to `NOTE_SNIPPET_CHARS` characters with an ellipsis
+pub fn snippet(text: &str) -> String {
+    let text = text.trim();
+    let first_line = text.lines().next().unwrap_or_default();
+    if first_line.chars().count() <= NOTE_SNIPPET_CHARS && first_line.len() == text.len() {
+        return first_line.to_string();
+    }
+    let shortened: String = first_line.chars().take(NOTE_SNIPPET_CHARS - 1).collect();
+    format!("{}…", shortened.trim_end())
+}
+
+/// Adds the snippets of the notes to the days they belong to
+pub fn with_notes(mut stats: FaithDailyStats, notes: &[DayNote]) -> FaithDailyStats {
+    for day in &mut stats.days {
+        day.note = notes
+            .iter()
+            .find(|note| note.date == day.date)
+            .map(|note| snippet(&note.text));
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snippet() {
+        assert_eq!(snippet("  retreat day "), "retreat day");
+        assert_eq!(snippet("Started Romans\nChapters 1-3"), "Started Romans…");
+        let long = "a".repeat(NOTE_SNIPPET_CHARS + 10);
+        assert_eq!(snippet(&long).chars().count(), NOTE_SNIPPET_CHARS);
+
+        assert!(validate_note("sick").is_ok());
+        assert!(validate_note(" \n").is_err());
+        assert!(validate_note(&"a".repeat(MAX_NOTE_CHARS + 1)).is_err());
+    }
+}
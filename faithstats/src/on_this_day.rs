@@ -0,0 +1,154 @@
+use ankistats::models::PassageDetails;
+use arcstats::PlaceVisit;
+use readingstats::models::CompletedBook;
+use serde::Serialize;
+
+/// A passage that first matured on this day in an earlier year
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct MemorizedPassage {
+    pub reference: String,
+    /// Date the passage first matured, in YYYY-MM-DD format
+    pub date: String,
+    pub years_ago: i32,
+}
+
+/// A book finished on this day in an earlier year
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FinishedBook {
+    pub title: String,
+    pub authors: Option<String>,
+    /// Date the book was finished, in YYYY-MM-DD format
+    pub date: String,
+    pub years_ago: i32,
+}
+
+/// A notable place visited on this day in an earlier year
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct VisitedPlace {
+    #[serde(flatten)]
+    pub visit: PlaceVisit,
+    pub years_ago: i32,
+}
+
+/// What happened on today's month and day in earlier years, oldest first
+///
+/// On February 29 only earlier leap years match.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct OnThisDay {
+    /// The day looked up, in YYYY-MM-DD format
+    pub date: String,
+    pub memorized_passages: Vec<MemorizedPassage>,
+    pub finished_books: Vec<FinishedBook>,
+    /// Places visited for the first time or for at least an hour
+    pub places: Vec<VisitedPlace>,
+}
+
+/// Whole years between an earlier date and `today` with the same month and day (YYYY-MM-DD),
+/// or `None` if the date is on another day or not in an earlier year
+fn years_ago(date: &str, today: &str) -> Option<i32> {
+    if date.get(4..) != today.get(4..) {
+        return None;
+    }
+    let years = today.get(..4)?.parse::<i32>().ok()? - date.get(..4)?.parse::<i32>().ok()?;
+    (years > 0).then_some(years)
+}
+
+impl OnThisDay {
+    /// Picks the passages, books and place visits of `today`'s month and day in earlier years
+    pub fn new(
+        today: &str,
+        passages: Vec<PassageDetails>,
+        books: Vec<CompletedBook>,
+        visits: Vec<PlaceVisit>,
+    ) -> Self {
+        let mut memorized_passages: Vec<MemorizedPassage> = passages
+            .into_iter()
+            .filter_map(|passage| {
+                let date = passage.matured_date?;
+                Some(MemorizedPassage {
+                    years_ago: years_ago(&date, today)?,
+                    reference: passage.reference,
+                    date,
+                })
+            })
+            .collect();
+        memorized_passages.sort_by(|a, b| a.date.cmp(&b.date));
+
+        let mut finished_books: Vec<FinishedBook> = books
+            .into_iter()
+            .filter_map(|book| {
+                Some(FinishedBook {
+                    years_ago: years_ago(&book.date, today)?,
+                    title: book.title,
+                    authors: book.authors,
+                    date: book.date,
+                })
+            })
+            .collect();
+        finished_books.sort_by(|a, b| a.date.cmp(&b.date));
+
+        let places = visits
+            .into_iter()
+            .filter_map(|visit| {
+                Some(VisitedPlace {
+                    years_ago: years_ago(&visit.date, today)?,
+                    visit,
+                })
+            })
+            .collect();
+
+        Self {
+            date: today.to_string(),
+            memorized_passages,
+            finished_books,
+            places,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ankistats::models::PassageStatus;
+
+    fn passage(reference: &str, matured_date: Option<&str>) -> PassageDetails {
+        PassageDetails {
+            reference: reference.to_string(),
+            status: PassageStatus::Mature,
+            first_review_date: None,
+            matured_date: matured_date.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_on_this_day() {
+        let passages = vec![
+            passage("Psalm 23:1", Some("2024-03-14")),
+            passage("John 3:16", Some("2022-03-14")),
+            passage("Jude 1:24", Some("2026-03-14")),
+            passage("Romans 8:28", Some("2025-03-15")),
+            passage("Micah 6:8", None),
+        ];
+        let books = vec![CompletedBook {
+            title: "Confessions".to_string(),
+            authors: Some("Augustine".to_string()),
+            date: "2025-03-14".to_string(),
+            finished_at: "2025-03-14T21:00:00-05:00".to_string(),
+        }];
+
+        let day = OnThisDay::new("2026-03-14", passages, books, Vec::new());
+
+        let passages: Vec<(&str, i32)> = day
+            .memorized_passages
+            .iter()
+            .map(|p| (p.reference.as_str(), p.years_ago))
+            .collect();
+        assert_eq!(passages, [("John 3:16", 4), ("Psalm 23:1", 2)]);
+        assert_eq!(day.finished_books.len(), 1);
+        assert_eq!(day.finished_books[0].years_ago, 1);
+    }
+}
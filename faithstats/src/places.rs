@@ -0,0 +1,304 @@
+use anyhow::{Result, bail};
+use arcstats::dedup::DedupReport;
+use arcstats::geocode::{GeocodedProvider, OfflineGeocoder, ReverseGeocoder};
+use arcstats::nominatim::NominatimGeocoder;
+use arcstats::stats::{DayCoverage, HomeRatioWeek, MonthNewPlaces, PlaceStats, PlaceVisit};
+use arcstats::{ArcExport, PlaceOverrides, PlaceProvider, WeekStats};
+use owntracksstats::OwnTracksRecorder;
+use statsutils::{DataRange, DatePeriod};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use takeoutstats::TakeoutHistory;
+
+/// Geocoder used to name unnamed places, if one is configured
+static GEOCODER: RwLock<Option<Arc<dyn ReverseGeocoder>>> = RwLock::new(None);
+
+/// A location history and its owner's settings for the places in it
+///
+/// Every place query takes one, so that each caller (e.g. each user of the backend) applies
+/// its own place overrides and home place. A plain path reads the history as recorded, with
+/// the place named "Home" as home.
+#[derive(Debug, Clone, Copy)]
+pub struct LocationHistory<'a> {
+    pub path: &'a str,
+    /// Place overrides file (TOML or JSON) applied to an Arc export, if any
+    ///
+    /// The file is read each time the export is loaded, so edits apply without a restart.
+    pub overrides_path: Option<&'a Path>,
+    /// ID or name of the home place, "Home" if `None`
+    pub home_place: Option<&'a str>,
+}
+
+impl<'a> LocationHistory<'a> {
+    pub fn new(path: &'a str) -> Self {
+        Self {
+            path,
+            overrides_path: None,
+            home_place: None,
+        }
+    }
+
+    /// Applies the place overrides file to an Arc export
+    pub fn with_overrides(mut self, overrides_path: &'a Path) -> Self {
+        self.overrides_path = Some(overrides_path);
+        self
+    }
+
+    /// Uses the place with the given ID or name as home instead of the place named "Home"
+    pub fn with_home_place(mut self, home_place: &'a str) -> Self {
+        self.home_place = Some(home_place);
+        self
+    }
+
+    /// ID or name of the home place
+    pub fn home_place(&self) -> &'a str {
+        self.home_place.unwrap_or(DEFAULT_HOME_PLACE)
+    }
+}
+
+impl<'a> From<&'a str> for LocationHistory<'a> {
+    fn from(path: &'a str) -> Self {
+        Self::new(path)
+    }
+}
+
+impl<'a> From<&'a String> for LocationHistory<'a> {
+    fn from(path: &'a String) -> Self {
+        Self::new(path)
+    }
+}
+
+/// Place named "Home" in Arc, used when no home place is configured
+const DEFAULT_HOME_PLACE: &str = "Home";
+
+/// Sets the reverse geocoder used to name unnamed places (process-wide)
+///
+/// Intended to be called once at startup; `None` turns geocoding off.
+pub fn set_geocoder(geocoder: Option<Arc<dyn ReverseGeocoder>>) {
+    *GEOCODER.write().unwrap() = geocoder;
+}
+
+/// Builds a reverse geocoder from the configured sources
+///
+/// A GeoNames cities file takes precedence over a Nominatim server since it works offline.
+///
+/// # Arguments
+/// * `cities_path` - GeoNames cities dump (e.g. `cities15000.txt`)
+/// * `nominatim_url` - Base URL of a Nominatim server
+/// * `cache_path` - JSON file to cache Nominatim results in
+///
+/// # Errors
+/// Returns an error if the cities file or cache file cannot be read
+pub fn build_geocoder(
+    cities_path: Option<&str>,
+    nominatim_url: Option<&str>,
+    cache_path: Option<&str>,
+) -> Result<Option<Arc<dyn ReverseGeocoder>>> {
+    if let Some(path) = cities_path {
+        return Ok(Some(Arc::new(OfflineGeocoder::load(path)?)));
+    }
+    if let Some(url) = nominatim_url {
+        let geocoder = NominatimGeocoder::new(url, cache_path.map(PathBuf::from))?;
+        return Ok(Some(Arc::new(geocoder)));
+    }
+    Ok(None)
+}
+
+/// Opens the location history with the place provider that understands it
+///
+/// # Errors
+/// Returns an error if the path is not in any supported location history format
+pub fn open_place_provider<'a>(
+    history: impl Into<LocationHistory<'a>>,
+) -> Result<Box<dyn PlaceProvider>> {
+    let provider = open_unenriched_provider(history.into())?;
+    match GEOCODER.read().unwrap().as_ref() {
+        Some(geocoder) => Ok(Box::new(GeocodedProvider::new(
+            provider,
+            Arc::clone(geocoder),
+        ))),
+        None => Ok(provider),
+    }
+}
+
+/// Opens the location history without naming unnamed places
+fn open_unenriched_provider(history: LocationHistory) -> Result<Box<dyn PlaceProvider>> {
+    let path = history.path;
+    if ArcExport::is_arc_export(path) {
+        return Ok(Box::new(open_arc_export(history)?));
+    }
+    if TakeoutHistory::is_takeout_export(path) {
+        return Ok(Box::new(TakeoutHistory::open(path)?));
+    }
+    if OwnTracksRecorder::is_owntracks_storage(path) {
+        return Ok(Box::new(OwnTracksRecorder::open(path)?));
+    }
+
+    bail!(
+        "No supported location history found at '{}' (expected an Arc Timeline export directory \
+         with a metadata.json, a Google Takeout Semantic Location History or OwnTracks recorder \
+         .rec files)",
+        path
+    )
+}
+
+/// Opens an Arc export, with the place overrides file applied if one is given
+fn open_arc_export(history: LocationHistory) -> Result<ArcExport> {
+    match history.overrides_path {
+        Some(overrides_path) => {
+            Ok(ArcExport::new(history.path).with_overrides(PlaceOverrides::load(overrides_path)?))
+        }
+        None => Ok(ArcExport::new(history.path)),
+    }
+}
+
+/// Finds the duplicated and overlapping items dropped from the location history
+///
+/// Returns `None` for location histories other than Arc exports, which are not deduplicated.
+pub fn check_location_history<'a>(
+    history: impl Into<LocationHistory<'a>>,
+) -> Result<Option<DedupReport>> {
+    let history = history.into();
+    if !ArcExport::is_arc_export(history.path) {
+        return Ok(None);
+    }
+    let (_, report) = open_arc_export(history)?.load_deduplicated()?;
+    Ok(Some(report))
+}
+
+/// Gets church attendance for the last `weeks` weeks from the location history
+pub fn get_church_last_n_weeks_stats<'a>(
+    history: impl Into<LocationHistory<'a>>,
+    weeks: i32,
+) -> Result<Vec<WeekStats>> {
+    arcstats::get_last_n_weeks_stats_from(open_place_provider(history)?.as_ref(), weeks)
+}
+
+/// Gets the top places by hours spent over the last 6 months from the location history
+pub fn get_top_places_last_6_months<'a>(
+    history: impl Into<LocationHistory<'a>>,
+    limit: usize,
+) -> Result<Vec<PlaceStats>> {
+    arcstats::stats::get_top_places_last_6_months_from(
+        open_place_provider(history)?.as_ref(),
+        limit,
+    )
+}
+
+/// Gets the hours of each of the last `days` days without recorded timeline items in the
+/// location history
+pub fn get_coverage_gaps<'a>(
+    history: impl Into<LocationHistory<'a>>,
+    days: i32,
+) -> Result<Vec<DayCoverage>> {
+    arcstats::get_coverage_gaps_from(
+        open_place_provider(history)?.as_ref(),
+        DatePeriod::last_n_days(days)?,
+    )
+}
+
+/// Gets the weekly share of visit time spent at the home place for the last `weeks` weeks
+/// from the location history
+pub fn get_home_ratio<'a>(
+    history: impl Into<LocationHistory<'a>>,
+    weeks: i32,
+) -> Result<Vec<HomeRatioWeek>> {
+    let history = history.into();
+    arcstats::get_home_ratio_last_n_weeks_from(
+        open_place_provider(history)?.as_ref(),
+        history.home_place(),
+        weeks,
+    )
+}
+
+/// Gets the places visited for the first time ever in each of the last `months` months from
+/// the location history
+pub fn get_new_places<'a>(
+    history: impl Into<LocationHistory<'a>>,
+    months: u32,
+) -> Result<Vec<MonthNewPlaces>> {
+    arcstats::get_new_places_last_n_months_from(open_place_provider(history)?.as_ref(), months)
+}
+
+/// Gets every place other than home with the hours spent there, most hours first, from the
+/// location history
+pub fn get_visited_places<'a>(history: impl Into<LocationHistory<'a>>) -> Result<Vec<PlaceStats>> {
+    let history = history.into();
+    arcstats::get_visited_places_from(open_place_provider(history)?.as_ref(), history.home_place())
+}
+
+/// Gets the places other than home visited for the first time or for at least an hour on
+/// `today`'s month and day (YYYY-MM-DD) in earlier years, from the location history
+pub fn get_notable_visits_on_this_day<'a>(
+    history: impl Into<LocationHistory<'a>>,
+    today: &str,
+) -> Result<Vec<PlaceVisit>> {
+    let history = history.into();
+    arcstats::get_notable_visits_on_this_day_from(
+        open_place_provider(history)?.as_ref(),
+        history.home_place(),
+        today,
+    )
+}
+
+/// Gets the earliest and latest timeline items in the location history
+pub fn get_data_range<'a>(history: impl Into<LocationHistory<'a>>) -> Result<DataRange> {
+    arcstats::get_data_range_from(open_place_provider(history)?.as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_open_place_provider() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().to_str().unwrap();
+
+        let error = open_place_provider(path).err().unwrap();
+        assert!(error.to_string().contains("No supported location history"));
+
+        std::fs::write(dir.path().join("metadata.json"), "{}").unwrap();
+        let provider = open_place_provider(path).unwrap();
+        assert_eq!(provider.name(), "Arc Timeline");
+        assert_eq!(provider.modified_path(), dir.path().join("metadata.json"));
+
+        let takeout = tempfile::tempdir().unwrap();
+        let year = takeout.path().join("Semantic Location History/2024");
+        std::fs::create_dir_all(&year).unwrap();
+        std::fs::write(year.join("2024_MARCH.json"), r#"{"timelineObjects": []}"#).unwrap();
+        let provider = open_place_provider(takeout.path().to_str().unwrap()).unwrap();
+        assert_eq!(provider.name(), "Google Takeout");
+
+        let recorder = tempfile::tempdir().unwrap();
+        let device = recorder.path().join("rec/alice/phone");
+        std::fs::create_dir_all(&device).unwrap();
+        std::fs::write(device.join("2024-03.rec"), "").unwrap();
+        let provider = open_place_provider(recorder.path().to_str().unwrap()).unwrap();
+        assert_eq!(provider.name(), "OwnTracks");
+    }
+
+    #[test]
+    fn test_build_geocoder() {
+        assert!(build_geocoder(None, None, None).unwrap().is_none());
+
+        let dir = tempfile::tempdir().unwrap();
+        let cities = dir.path().join("cities15000.txt");
+        std::fs::write(
+            &cities,
+            "4409896\tSpringfield\tSpringfield\t\t37.21533\t-93.29824\tP\tPPLA2\tUS\n",
+        )
+        .unwrap();
+        let geocoder = build_geocoder(cities.to_str(), None, None)
+            .unwrap()
+            .unwrap();
+        let geocoded = geocoder.reverse(37.2, -93.3).unwrap().unwrap();
+        assert_eq!(geocoded.locality.as_deref(), Some("Springfield"));
+
+        assert!(
+            build_geocoder(None, Some("http://localhost:8080"), None)
+                .unwrap()
+                .is_some()
+        );
+    }
+}
@@ -0,0 +1,374 @@
+use ankistats::models::ReviewForecast;
+use chrono::{Datelike, Days, NaiveDate};
+use serde::Serialize;
+
+use crate::exemptions::{Exemption, active_week_fraction, is_exempt};
+use crate::goals::{Goal, GoalMetric};
+use crate::models::{FaithDayStats, FaithWeekStats};
+
+/// Days of history the weekday availability is learned from (12 weeks)
+pub const AVAILABILITY_DAYS: i32 = 84;
+
+/// Minutes planned for one activity on a day
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PlannedActivity {
+    /// Activity; `total_minutes` stands for time of any activity a total goal still needs
+    pub metric: GoalMetric,
+    pub minutes: f64,
+}
+
+/// One day of the plan
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PlanDay {
+    /// Date in YYYY-MM-DD format
+    pub date: String,
+    /// Day name, e.g. "Sunday"
+    pub weekday: String,
+    /// Exempt days get nothing planned
+    pub exempt: bool,
+    /// Average faith minutes on this weekday over the last 12 weeks
+    pub typical_minutes: f64,
+    /// Share of the week's goal minutes planned on this day (0-1), from typical_minutes
+    pub availability: f64,
+    /// Anki reviews falling due on this day
+    pub due_reviews: i64,
+    /// due_reviews × recent time per review, in minutes
+    pub review_minutes: f64,
+    pub activities: Vec<PlannedActivity>,
+    pub total_minutes: f64,
+}
+
+/// A weekly goal and the minutes planned for it
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PlannedGoal {
+    pub name: String,
+    pub metric: GoalMetric,
+    /// Weekly target, reduced by a seventh for each exempt day of the planned week
+    pub target_minutes: f64,
+    /// Minutes the current week is behind an even pace, added to the plan to catch up
+    pub carried_over_minutes: f64,
+    /// target_minutes + carried_over_minutes
+    pub planned_minutes: f64,
+}
+
+/// Anki reviews due in the planned week
+#[derive(Debug, Clone)]
+pub struct ReviewOutlook {
+    /// Reviews due on each day of the week, Sunday first
+    pub due_reviews: Vec<i64>,
+    /// Recent average time per review
+    pub seconds_per_review: f64,
+}
+
+impl ReviewOutlook {
+    /// Reviews due in the week starting on `week_start`, looked up by date in the forecast
+    /// so that they line up with the planned days; days past the forecast have none
+    pub fn for_week(week_start: NaiveDate, forecast: &ReviewForecast) -> Self {
+        let due_reviews = (0..7)
+            .map(|offset| {
+                let Some(date) = week_start.checked_add_days(Days::new(offset)) else {
+                    return 0;
+                };
+                let date = date.format("%Y-%m-%d").to_string();
+                forecast
+                    .daily
+                    .iter()
+                    .find(|day| day.date == date)
+                    .map_or(0, |day| day.due_reviews)
+            })
+            .collect();
+        Self {
+            due_reviews,
+            seconds_per_review: forecast.average_seconds_per_review,
+        }
+    }
+}
+
+/// Suggested plan for a week: goal minutes spread over the days in proportion to how much
+/// time is usually spent on each weekday, with Anki time raised to cover the due reviews
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct WeekPlan {
+    /// Week start date (Sunday) in YYYY-MM-DD format
+    pub week_start: String,
+    pub goals: Vec<PlannedGoal>,
+    pub days: Vec<PlanDay>,
+    pub total_minutes: f64,
+}
+
+impl PlannedGoal {
+    /// Plans a goal for a week with `exempt_days` exempt days, catching up on the current
+    /// week's lag behind an even pace (`elapsed_fraction` of it has passed)
+    pub fn new(
+        goal: &Goal,
+        exempt_days: i64,
+        current_week: Option<&FaithWeekStats>,
+        elapsed_fraction: f64,
+    ) -> Self {
+        let target_minutes = goal.weekly_target_minutes * active_week_fraction(exempt_days);
+        let carried_over_minutes = current_week
+            .map_or(0.0, |week| goal.minutes_behind_pace(week, elapsed_fraction))
+            .round();
+
+        Self {
+            name: goal.name.clone(),
+            metric: goal.metric,
+            target_minutes,
+            carried_over_minutes,
+            planned_minutes: target_minutes + carried_over_minutes,
+        }
+    }
+}
+
+/// Average total minutes per weekday (Sunday first), leaving exempt days out
+fn typical_minutes_by_weekday(history: &[FaithDayStats]) -> [f64; 7] {
+    let mut totals = [(0.0, 0); 7];
+    for day in history.iter().filter(|day| !day.exempt) {
+        let Ok(date) = NaiveDate::parse_from_str(&day.date, "%Y-%m-%d") else {
+            continue;
+        };
+        let weekday = date.weekday().num_days_from_sunday() as usize;
+        totals[weekday].0 += day.total_minutes();
+        totals[weekday].1 += 1;
+    }
+    totals.map(|(minutes, days)| {
+        if days > 0 {
+            (minutes / days as f64 * 10.0).round() / 10.0
+        } else {
+            0.0
+        }
+    })
+}
+
+/// Adds minutes to the day's activity of the metric
+fn add_minutes(activities: &mut Vec<PlannedActivity>, metric: GoalMetric, minutes: f64) {
+    if minutes <= 0.0 {
+        return;
+    }
+    match activities.iter_mut().find(|a| a.metric == metric) {
+        Some(activity) => activity.minutes += minutes,
+        None => activities.push(PlannedActivity { metric, minutes }),
+    }
+}
+
+impl WeekPlan {
+    /// Plans the week starting on `week_start` (a Sunday)
+    ///
+    /// Church goals are planned on the first non-exempt day, since attendance is only tracked
+    /// per week. A total goal only adds the minutes the other goals don't already cover.
+    pub fn new(
+        week_start: NaiveDate,
+        goals: Vec<PlannedGoal>,
+        history: &[FaithDayStats],
+        exemptions: &[Exemption],
+        reviews: &ReviewOutlook,
+    ) -> Self {
+        let typical = typical_minutes_by_weekday(history);
+        let dates: Vec<NaiveDate> = (0..7)
+            .filter_map(|offset| week_start.checked_add_days(Days::new(offset)))
+            .collect();
+        let exempt: Vec<bool> = dates
+            .iter()
+            .map(|date| is_exempt(exemptions, &date.format("%Y-%m-%d").to_string()))
+            .collect();
+
+        // Weekdays without any history still get a little time so nothing is unplannable
+        let weights: Vec<f64> = dates
+            .iter()
+            .zip(&exempt)
+            .map(|(date, &exempt)| {
+                if exempt {
+                    0.0
+                } else {
+                    typical[date.weekday().num_days_from_sunday() as usize].max(1.0)
+                }
+            })
+            .collect();
+        let weight_sum: f64 = weights.iter().sum();
+        let availability: Vec<f64> = weights
+            .iter()
+            .map(|weight| {
+                if weight_sum > 0.0 {
+                    weight / weight_sum
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+
+        let covered_minutes: f64 = goals
+            .iter()
+            .filter(|goal| goal.metric != GoalMetric::TotalMinutes)
+            .map(|goal| goal.planned_minutes)
+            .sum();
+        let church_day = exempt.iter().position(|&exempt| !exempt);
+
+        let days = dates
+            .iter()
+            .enumerate()
+            .map(|(index, date)| {
+                let mut activities = Vec::new();
+                for goal in &goals {
+                    let minutes = match goal.metric {
+                        GoalMetric::AtChurchMinutes if church_day == Some(index) => {
+                            goal.planned_minutes
+                        }
+                        GoalMetric::AtChurchMinutes => 0.0,
+                        GoalMetric::TotalMinutes => {
+                            (goal.planned_minutes - covered_minutes).max(0.0) * availability[index]
+                        }
+                        _ => goal.planned_minutes * availability[index],
+                    };
+                    add_minutes(&mut activities, goal.metric, minutes.round());
+                }
+
+                let due_reviews = reviews.due_reviews.get(index).copied().unwrap_or(0);
+                let review_minutes =
+                    (due_reviews as f64 * reviews.seconds_per_review / 60.0).round();
+                let anki_minutes = activities
+                    .iter()
+                    .find(|a| a.metric == GoalMetric::AnkiMinutes)
+                    .map_or(0.0, |a| a.minutes);
+                if !exempt[index] {
+                    add_minutes(
+                        &mut activities,
+                        GoalMetric::AnkiMinutes,
+                        review_minutes - anki_minutes,
+                    );
+                }
+
+                PlanDay {
+                    date: date.format("%Y-%m-%d").to_string(),
+                    weekday: date.format("%A").to_string(),
+                    exempt: exempt[index],
+                    typical_minutes: typical[date.weekday().num_days_from_sunday() as usize],
+                    availability: (availability[index] * 1000.0).round() / 1000.0,
+                    due_reviews,
+                    review_minutes,
+                    total_minutes: activities.iter().map(|a| a.minutes).sum(),
+                    activities,
+                }
+            })
+            .collect::<Vec<PlanDay>>();
+
+        Self {
+            week_start: week_start.format("%Y-%m-%d").to_string(),
+            total_minutes: days.iter().map(|day| day.total_minutes).sum(),
+            goals,
+            days,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn goal(metric: GoalMetric, weekly_target_minutes: f64) -> Goal {
+        Goal {
+            name: format!("{:?}", metric),
+            metric,
+            weekly_target_minutes,
+        }
+    }
+
+    #[test]
+    fn test_review_outlook_matches_forecast_dates() {
+        use ankistats::models::DueDay;
+
+        let due = |date: &str, due_reviews: i64| DueDay {
+            date: date.to_string(),
+            due_reviews,
+        };
+        // Forecast from a Friday: the planned week starts two days in
+        let forecast = ReviewForecast::new(
+            vec![
+                due("2025-01-03", 50),
+                due("2025-01-04", 4),
+                due("2025-01-05", 7),
+                due("2025-01-06", 3),
+            ],
+            6.0,
+        );
+
+        let outlook =
+            ReviewOutlook::for_week(NaiveDate::from_ymd_opt(2025, 1, 5).unwrap(), &forecast);
+        assert_eq!(outlook.due_reviews, vec![7, 3, 0, 0, 0, 0, 0]);
+        assert_eq!(outlook.seconds_per_review, 6.0);
+    }
+
+    #[test]
+    fn test_week_plan() {
+        // Two weeks of history: Saturdays are busy, every other day has 10 minutes
+        let start = NaiveDate::from_ymd_opt(2025, 1, 5).unwrap();
+        let history: Vec<FaithDayStats> = (0..14)
+            .map(|offset| {
+                let date = start + Days::new(offset);
+                FaithDayStats {
+                    date: date.format("%Y-%m-%d").to_string(),
+                    reading_minutes: Some(if offset % 7 == 6 { 40.0 } else { 10.0 }),
+                    ..Default::default()
+                }
+            })
+            .collect();
+        let current_week = FaithWeekStats {
+            week_start: "2025-01-19".to_string(),
+            reading_minutes: 20.0,
+            ..Default::default()
+        };
+        let goals = vec![
+            PlannedGoal::new(
+                &goal(GoalMetric::ReadingMinutes, 100.0),
+                0,
+                Some(&current_week),
+                0.5,
+            ),
+            PlannedGoal::new(&goal(GoalMetric::AtChurchMinutes, 60.0), 0, None, 0.5),
+        ];
+        assert_eq!(goals[0].carried_over_minutes, 30.0);
+        assert_eq!(goals[0].planned_minutes, 130.0);
+
+        let reviews = ReviewOutlook {
+            due_reviews: vec![0, 60, 0, 0, 0, 0, 0],
+            seconds_per_review: 30.0,
+        };
+        let week_start = NaiveDate::from_ymd_opt(2025, 1, 26).unwrap();
+        let plan = WeekPlan::new(week_start, goals, &history, &[], &reviews);
+
+        assert_eq!(plan.days.len(), 7);
+        assert_eq!(plan.days[0].weekday, "Sunday");
+        // 130 minutes split 10:10:10:10:10:10:40
+        assert_eq!(plan.days[6].availability, 0.4);
+        let reading = |day: &PlanDay| {
+            day.activities
+                .iter()
+                .find(|a| a.metric == GoalMetric::ReadingMinutes)
+                .map(|a| a.minutes)
+        };
+        assert_eq!(reading(&plan.days[6]), Some(52.0));
+        assert_eq!(reading(&plan.days[1]), Some(13.0));
+        // Church goes to Sunday, the due reviews to Monday
+        assert_eq!(plan.days[0].total_minutes, 73.0);
+        assert_eq!(plan.days[1].review_minutes, 30.0);
+        assert_eq!(plan.days[1].total_minutes, 43.0);
+
+        let exemptions = vec![Exemption {
+            id: 1,
+            start: "2025-01-26".to_string(),
+            end: "2025-01-26".to_string(),
+            reason: None,
+        }];
+        let goals = vec![PlannedGoal::new(
+            &goal(GoalMetric::AtChurchMinutes, 60.0),
+            1,
+            None,
+            0.0,
+        )];
+        let plan = WeekPlan::new(week_start, goals, &history, &exemptions, &reviews);
+        assert!(plan.days[0].activities.is_empty());
+        assert_eq!(plan.days[1].total_minutes, 81.0);
+    }
+}
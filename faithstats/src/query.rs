@@ -0,0 +1,640 @@
+use ankistats::config::{AnkiCollection, AnkiStatsConfig};
+use anyhow::{Result, bail};
+use statsutils::pool::{ConnectionPool, Database};
+use statsutils::{DataRange, DatePeriod};
+use std::fmt;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::models::{
+    FaithDailyStats, FaithTodayStats, FaithWeeklyStats, HistoryStarts, SourceDataRange,
+};
+use crate::on_this_day::OnThisDay;
+use crate::places::{self, LocationHistory};
+use crate::search::{SearchCandidates, SearchResults, cached_candidates};
+use crate::simulate::{Commitment, GoalSimulation, NEW_TESTAMENT_TARGET, Target};
+use crate::{join, merge_daily_stats, merge_weekly_stats};
+
+/// Number of days queried by `FaithStats::daily()` without a period
+pub const DEFAULT_DAYS: i32 = 30;
+
+/// Number of weeks queried by `FaithStats::weekly()` by default
+pub const DEFAULT_WEEKS: i32 = 12;
+
+/// Most days a `FaithStats::daily()` period may include
+pub const MAX_DAYS: usize = 3650;
+
+/// Most weeks `FaithStats::weekly()` may include
+pub const MAX_WEEKS: i32 = 520;
+
+/// Unified faith statistics over the configured data sources, combining Anki Bible
+/// memorization, KOReader Bible reading, Arc church attendance, and prayer time data.
+///
+/// Sources are configured through `FaithStats::builder()`. A query fails if a source it
+/// reads has not been configured, so e.g. daily stats don't need an Arc export.
+///
+/// # Example
+/// ```ignore
+/// use faithstats::FaithStats;
+///
+/// let stats = FaithStats::builder()
+///     .anki("/path/to/collection.anki2")
+///     .reading("/path/to/statistics.sqlite3")
+///     .prayer("/path/to/database.sqlite")
+///     .period(DatePeriod::last_n_days(7)?)
+///     .build()
+///     .daily()?;
+/// println!("Total faith time: {:.2} hours", stats.summary.total_hours);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FaithStats {
+    anki_db_path: Option<String>,
+    anki_pool: Option<ConnectionPool>,
+    anki_config: Option<AnkiStatsConfig>,
+    koreader_db_path: Option<String>,
+    koreader_pool: Option<ConnectionPool>,
+    proseuche_db_path: Option<String>,
+    proseuche_pool: Option<ConnectionPool>,
+    arcstats_export_path: Option<String>,
+    place_overrides_path: Option<PathBuf>,
+    home_place: Option<String>,
+    period: Option<DatePeriod>,
+    weeks: i32,
+}
+
+/// Configures the data sources and periods of a `FaithStats` query
+#[derive(Debug, Clone)]
+pub struct FaithStatsBuilder {
+    stats: FaithStats,
+}
+
+impl FaithStatsBuilder {
+    /// Path to the Anki collection.anki2 database file
+    pub fn anki(mut self, path: impl Into<String>) -> Self {
+        self.stats.anki_db_path = Some(path.into());
+        self
+    }
+
+    /// Pool of connections to the Anki collection, which is read through it instead of
+    /// opening a connection for each query
+    pub fn anki_pool(mut self, pool: ConnectionPool) -> Self {
+        self.stats.anki_db_path = Some(pool.path().to_string());
+        self.stats.anki_pool = Some(pool);
+        self
+    }
+
+    /// Deck and note type of the Bible verse cards in the Anki collection, "Bible::Verses"
+    /// and "Bible Verse" by default
+    pub fn anki_config(mut self, config: AnkiStatsConfig) -> Self {
+        self.stats.anki_config = Some(config);
+        self
+    }
+
+    /// Path to the KOReader statistics.sqlite3 database file
+    pub fn reading(mut self, path: impl Into<String>) -> Self {
+        self.stats.koreader_db_path = Some(path.into());
+        self
+    }
+
+    /// Pool of connections to the KOReader database, read through it like `anki_pool`
+    pub fn reading_pool(mut self, pool: ConnectionPool) -> Self {
+        self.stats.koreader_db_path = Some(pool.path().to_string());
+        self.stats.koreader_pool = Some(pool);
+        self
+    }
+
+    /// Path to the Proseuche database.sqlite file
+    pub fn prayer(mut self, path: impl Into<String>) -> Self {
+        self.stats.proseuche_db_path = Some(path.into());
+        self
+    }
+
+    /// Pool of connections to the Proseuche database, read through it like `anki_pool`
+    pub fn prayer_pool(mut self, pool: ConnectionPool) -> Self {
+        self.stats.proseuche_db_path = Some(pool.path().to_string());
+        self.stats.proseuche_pool = Some(pool);
+        self
+    }
+
+    /// Path to the Arc Timeline export directory
+    pub fn arc(mut self, path: impl Into<String>) -> Self {
+        self.stats.arcstats_export_path = Some(path.into());
+        self
+    }
+
+    /// Place overrides file (TOML or JSON) correcting the places of the Arc export
+    pub fn place_overrides(mut self, path: impl Into<PathBuf>) -> Self {
+        self.stats.place_overrides_path = Some(path.into());
+        self
+    }
+
+    /// ID or name of the home place in the location history, left out of the visited places
+    /// of searches and "on this day"; the place named "Home" by default
+    pub fn home_place(mut self, home_place: impl Into<String>) -> Self {
+        self.stats.home_place = Some(home_place.into());
+        self
+    }
+
+    /// Days included in daily stats, e.g. specific dates from `DatePeriod::for_dates`.
+    /// Defaults to the last 30 days; daily stats fail for an empty period or one of more
+    /// than [`MAX_DAYS`] days.
+    pub fn period(mut self, period: DatePeriod) -> Self {
+        self.stats.period = Some(period);
+        self
+    }
+
+    /// Number of weeks included in weekly stats, ending with this week. Defaults to 12;
+    /// weekly stats fail for less than 1 or more than [`MAX_WEEKS`] weeks.
+    pub fn weeks(mut self, weeks: i32) -> Self {
+        self.stats.weeks = weeks;
+        self
+    }
+
+    pub fn build(self) -> FaithStats {
+        self.stats
+    }
+}
+
+impl FaithStats {
+    pub fn builder() -> FaithStatsBuilder {
+        FaithStatsBuilder {
+            stats: FaithStats {
+                anki_db_path: None,
+                anki_pool: None,
+                anki_config: None,
+                koreader_db_path: None,
+                koreader_pool: None,
+                proseuche_db_path: None,
+                proseuche_pool: None,
+                arcstats_export_path: None,
+                place_overrides_path: None,
+                home_place: None,
+                period: None,
+                weeks: DEFAULT_WEEKS,
+            },
+        }
+    }
+
+    pub(crate) fn anki_collection(&self) -> Result<AnkiCollection<'_>> {
+        let path = configured(&self.anki_db_path, "Anki collection")?;
+        Ok(self.anki_collection_at(path))
+    }
+
+    fn anki_collection_at<'a>(&'a self, path: &'a str) -> AnkiCollection<'a> {
+        let mut collection = AnkiCollection::new(path);
+        collection.config = self.anki_config.as_ref();
+        collection.pool = self.anki_pool.as_ref();
+        collection
+    }
+
+    pub(crate) fn koreader_db(&self) -> Result<Database<'_>> {
+        let path = configured(&self.koreader_db_path, "KOReader database")?;
+        Ok(database(path, &self.koreader_pool))
+    }
+
+    pub(crate) fn proseuche_db(&self) -> Result<Database<'_>> {
+        let path = configured(&self.proseuche_db_path, "Proseuche database")?;
+        Ok(database(path, &self.proseuche_pool))
+    }
+
+    pub(crate) fn location_history(&self) -> Result<LocationHistory<'_>> {
+        let path = configured(&self.arcstats_export_path, "Arc Timeline export")?;
+        Ok(self.location_history_at(path))
+    }
+
+    fn location_history_at<'a>(&'a self, path: &'a str) -> LocationHistory<'a> {
+        LocationHistory {
+            path,
+            overrides_path: self.place_overrides_path.as_deref(),
+            home_place: self.home_place.as_deref(),
+        }
+    }
+
+    /// Gets unified statistics for each day of the period, reading Anki, KOReader and
+    /// Proseuche
+    ///
+    /// # Returns
+    /// FaithDailyStats with one entry per date of the period and a summary over them
+    ///
+    /// # Errors
+    /// Returns an error if the period is empty or longer than [`MAX_DAYS`], or if any
+    /// database is unconfigured, unavailable or cannot be queried
+    pub fn daily(&self) -> Result<FaithDailyStats> {
+        let (anki_collection, koreader_db, proseuche_db) = (
+            self.anki_collection()?,
+            self.koreader_db()?,
+            self.proseuche_db()?,
+        );
+        let period = match &self.period {
+            Some(period) => period.clone(),
+            None => DatePeriod::last_n_days(DEFAULT_DAYS)?,
+        };
+        if !(1..=MAX_DAYS).contains(&period.dates.len()) {
+            bail!(
+                "The period must include between 1 and {} days, got {}",
+                MAX_DAYS,
+                period.dates.len()
+            );
+        }
+
+        // Query all databases in parallel - will return error if any is unavailable
+        let (anki_stats, reading_stats, prayer_stats, history) = thread::scope(|s| {
+            let anki = s.spawn(|| ankistats::get_days_stats(anki_collection, period.clone()));
+            let reading = s.spawn(|| readingstats::get_days_stats(koreader_db, period.clone()));
+            let prayer = s.spawn(|| prayerstats::get_days_stats(proseuche_db, period.clone()));
+            let history = s.spawn(|| self.history_starts());
+            (join(anki), join(reading), join(prayer), join(history))
+        });
+        let (anki_stats, reading_stats, prayer_stats) =
+            (anki_stats?, reading_stats?, prayer_stats?);
+
+        let mut days = merge_daily_stats(anki_stats, reading_stats, prayer_stats);
+        history?.clear_days_before(&mut days);
+        Ok(FaithDailyStats::new(days))
+    }
+
+    /// Gets the first date with recorded activity in each daily source
+    ///
+    /// # Errors
+    /// Returns an error if any database is unconfigured, unavailable or cannot be queried
+    pub fn history_starts(&self) -> Result<HistoryStarts> {
+        Ok(HistoryStarts {
+            anki: ankistats::get_data_range(self.anki_collection()?)?.earliest_date()?,
+            reading: readingstats::get_data_range(self.koreader_db()?)?.earliest_date()?,
+            prayer: prayerstats::get_data_range(self.proseuche_db()?)?.earliest_date()?,
+        })
+    }
+
+    /// Gets unified statistics for today, reading Anki, KOReader and Proseuche
+    ///
+    /// # Errors
+    /// Returns an error if any database is unconfigured, unavailable or cannot be queried
+    pub fn today(&self) -> Result<FaithTodayStats> {
+        let (anki_collection, koreader_db, proseuche_db) = (
+            self.anki_collection()?,
+            self.koreader_db()?,
+            self.proseuche_db()?,
+        );
+
+        // Query all databases in parallel - will return error if any is unavailable
+        let (anki_minutes, reading_minutes, prayer_minutes) = thread::scope(|s| {
+            let anki = s.spawn(|| ankistats::get_today_study_time(anki_collection));
+            let reading = s.spawn(|| readingstats::get_today_reading_time(koreader_db));
+            let prayer = s.spawn(|| prayerstats::get_today_prayer_time(proseuche_db));
+            (join(anki), join(reading), join(prayer))
+        });
+
+        Ok(FaithTodayStats::new(
+            anki_minutes?,
+            reading_minutes?,
+            prayer_minutes?,
+        ))
+    }
+
+    /// Gets unified statistics for the configured number of weeks, reading every source
+    ///
+    /// The current week also gets the projected Anki review minutes of the next 7 days; if
+    /// the projection cannot be made, it is logged and left out rather than failing the stats.
+    ///
+    /// # Returns
+    /// FaithWeeklyStats containing weekly breakdown and summary statistics
+    ///
+    /// # Errors
+    /// Returns an error if the number of weeks is not between 1 and [`MAX_WEEKS`], or if any
+    /// database/export is unconfigured, unavailable or cannot be queried
+    pub fn weekly(&self) -> Result<FaithWeeklyStats> {
+        if !(1..=MAX_WEEKS).contains(&self.weeks) {
+            bail!(
+                "The number of weeks must be between 1 and {}, got {}",
+                MAX_WEEKS,
+                self.weeks
+            );
+        }
+        let (anki_collection, koreader_db, location_history, proseuche_db) = (
+            self.anki_collection()?,
+            self.koreader_db()?,
+            self.location_history()?,
+            self.proseuche_db()?,
+        );
+        let weeks = self.weeks;
+
+        // Query all databases in parallel - will return error if any is unavailable
+        let (anki_stats, reading_stats, church_stats, prayer_stats, forecast) =
+            thread::scope(|s| {
+                let anki = s.spawn(|| ankistats::get_last_n_weeks_stats(anki_collection, weeks));
+                let reading = s.spawn(|| readingstats::get_last_n_weeks_stats(koreader_db, weeks));
+                let church =
+                    s.spawn(|| places::get_church_last_n_weeks_stats(location_history, weeks));
+                let prayer = s.spawn(|| prayerstats::get_last_n_weeks_stats(proseuche_db, weeks));
+                let forecast = s.spawn(|| ankistats::get_review_forecast(anki_collection, 7));
+                (
+                    join(anki),
+                    join(reading),
+                    join(church),
+                    join(prayer),
+                    join(forecast),
+                )
+            });
+        let (anki_stats, reading_stats, church_stats, prayer_stats) =
+            (anki_stats?, reading_stats?, church_stats?, prayer_stats?);
+        let projected_minutes = match forecast {
+            Ok(forecast) => Some(forecast.projected_minutes),
+            Err(e) => {
+                eprintln!("Warning: could not project Anki review minutes: {:#}", e);
+                None
+            }
+        };
+
+        let mut weeks = merge_weekly_stats(anki_stats, reading_stats, church_stats, prayer_stats);
+        if let Some(current_week) = weeks.last_mut() {
+            current_week.anki_projected_minutes = projected_minutes;
+        }
+
+        Ok(FaithWeeklyStats::new(weeks))
+    }
+
+    /// Projects when the given books and the whole New Testament would be memorized and read
+    /// under a hypothetical daily commitment, reading Anki
+    ///
+    /// Memorization starts from the verses that are mature today, and each new verse only
+    /// counts as much as matured passages stay mature (1 - relapse rate, per book where
+    /// available).
+    ///
+    /// # Arguments
+    /// * `books` - Book names as listed in `ankistats::bible`
+    /// * `commitment` - New verses and reading time per day
+    pub fn simulate_goals(
+        &self,
+        books: &[&'static str],
+        commitment: Commitment,
+    ) -> Result<GoalSimulation> {
+        let anki_collection = self.anki_collection()?;
+        let coverage = ankistats::get_books_coverage(anki_collection)?;
+        let relapse = ankistats::get_relapse_stats(anki_collection)?;
+        let overall_retention = 1.0 - relapse.total.relapse_rate;
+
+        let mature_verses = |book: &str| {
+            coverage.get(book).map_or(0, |coverage| {
+                coverage.verse_count(ankistats::models::VerseStatus::Mature)
+            })
+        };
+        let book_target = |book: &'static str| Target {
+            name: book.to_string(),
+            total_verses: ankistats::bible::verse_count(book).unwrap_or(0),
+            mature_verses: mature_verses(book),
+            retention: relapse
+                .books
+                .iter()
+                .find(|stats| stats.book == book && stats.matured_passages > 0)
+                .map_or(overall_retention, |stats| 1.0 - stats.relapse_rate),
+        };
+
+        let new_testament = ankistats::bible::NEW_TESTAMENT
+            .iter()
+            .map(|book| book_target(book.name));
+        let new_testament = Target {
+            name: NEW_TESTAMENT_TARGET.to_string(),
+            total_verses: new_testament
+                .clone()
+                .map(|target| target.total_verses)
+                .sum(),
+            mature_verses: new_testament.map(|target| target.mature_verses).sum(),
+            retention: overall_retention,
+        };
+
+        let targets = books
+            .iter()
+            .map(|&book| book_target(book))
+            .chain(std::iter::once(new_testament))
+            .collect();
+
+        let today = chrono::NaiveDate::parse_from_str(&statsutils::get_today_date()?, "%Y-%m-%d")?;
+        Ok(GoalSimulation::new(targets, commitment, today))
+    }
+
+    /// Gets the passages first memorized, books finished and notable places visited on
+    /// `today`'s month and day in earlier years, reading Anki, KOReader and Arc
+    ///
+    /// # Arguments
+    /// * `today` - Date in YYYY-MM-DD format
+    ///
+    /// # Errors
+    /// Returns an error if any database/export is unconfigured, unavailable or cannot be
+    /// queried
+    pub fn on_this_day(&self, today: &str) -> Result<OnThisDay> {
+        let (anki_collection, koreader_db, location_history) = (
+            self.anki_collection()?,
+            self.koreader_db()?,
+            self.location_history()?,
+        );
+
+        let (passages, books, visits) = thread::scope(|s| {
+            let passages = s.spawn(|| ankistats::get_passages(anki_collection, None));
+            let books = s.spawn(|| readingstats::get_completed_books(koreader_db, None));
+            let visits =
+                s.spawn(|| places::get_notable_visits_on_this_day(location_history, today));
+            (join(passages), join(books), join(visits))
+        });
+
+        Ok(OnThisDay::new(
+            today,
+            passages?.unwrap_or_default(),
+            books?,
+            visits?,
+        ))
+    }
+
+    /// Searches passage references, places and book titles for a quick-jump box, reading
+    /// Anki, KOReader and Arc
+    ///
+    /// What the sources contain is reused for [`crate::search::SEARCH_CACHE_TTL`], so a
+    /// search that follows soon after another doesn't read them again.
+    ///
+    /// # Arguments
+    /// * `query` - Text to look for (case-insensitive)
+    /// * `limit` - Maximum number of results of each kind
+    ///
+    /// # Errors
+    /// Returns an error if any database/export is unconfigured, unavailable or cannot be
+    /// queried
+    pub fn search(&self, query: &str, limit: usize) -> Result<SearchResults> {
+        let (anki_collection, koreader_db, location_history) = (
+            self.anki_collection()?,
+            self.koreader_db()?,
+            self.location_history()?,
+        );
+
+        let anki_config = anki_collection.config();
+        let key = vec![
+            anki_collection.path.to_string(),
+            anki_config.deck_name,
+            anki_config.note_type,
+            koreader_db.path().to_string(),
+            location_history.path.to_string(),
+            location_history
+                .overrides_path
+                .map(|path| path.display().to_string())
+                .unwrap_or_default(),
+            location_history.home_place().to_string(),
+        ];
+        let candidates = cached_candidates(key, || {
+            let (passages, places, books) = thread::scope(|s| {
+                let passages = s.spawn(|| ankistats::get_passages(anki_collection, None));
+                let places = s.spawn(|| places::get_visited_places(location_history));
+                let books = s.spawn(|| readingstats::get_books(koreader_db));
+                (join(passages), join(places), join(books))
+            });
+            Ok(SearchCandidates {
+                passages: passages?.unwrap_or_default(),
+                places: places?,
+                books: books?,
+            })
+        })?;
+
+        Ok(SearchResults::new(
+            query,
+            candidates.passages,
+            candidates.places,
+            candidates.books,
+            limit,
+        ))
+    }
+
+    /// Gets the recorded activity range and file freshness of every configured data source
+    ///
+    /// Unlike the other queries, a failing source does not fail the whole call: its error is
+    /// reported in the corresponding `SourceDataRange` so the remaining sources can still be
+    /// inspected. Unconfigured sources are left out.
+    ///
+    /// # Returns
+    /// One SourceDataRange per configured source, in the order anki, reading, prayer, arc
+    pub fn data_ranges(&self) -> Vec<SourceDataRange> {
+        self.data_ranges_timed()
+            .into_iter()
+            .map(|(range, _)| range)
+            .collect()
+    }
+
+    /// Same as `data_ranges()`, but also returns how long reading each source took
+    ///
+    /// Reading a source's data range reads all of its data (including loading and indexing
+    /// every place of the location history), so the durations show which source is slow.
+    pub fn data_ranges_timed(&self) -> Vec<(SourceDataRange, Duration)> {
+        let timed =
+            |source: &str, path: &Option<String>, read: &dyn Fn(&str) -> Result<DataRange>| {
+                let path = path.as_deref()?;
+                let start = Instant::now();
+                let range = SourceDataRange::new(source, read(path));
+                Some((range, start.elapsed()))
+            };
+
+        [
+            timed("anki", &self.anki_db_path, &|path| {
+                ankistats::get_data_range(self.anki_collection_at(path))
+            }),
+            timed("reading", &self.koreader_db_path, &|path| {
+                readingstats::get_data_range(database(path, &self.koreader_pool))
+            }),
+            timed("prayer", &self.proseuche_db_path, &|path| {
+                prayerstats::get_data_range(database(path, &self.proseuche_pool))
+            }),
+            timed("arc", &self.arcstats_export_path, &|path| {
+                places::get_data_range(self.location_history_at(path))
+            }),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
+
+/// Error returned when a query reads a source whose path is not configured
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceNotConfiguredError {
+    /// Kind of source, e.g. "Anki collection"
+    pub source: String,
+}
+
+impl fmt::Display for SourceNotConfiguredError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "No {} path configured", self.source)
+    }
+}
+
+impl std::error::Error for SourceNotConfiguredError {}
+
+fn configured<'a>(path: &'a Option<String>, source: &str) -> Result<&'a str> {
+    path.as_deref().ok_or_else(|| {
+        SourceNotConfiguredError {
+            source: source.to_string(),
+        }
+        .into()
+    })
+}
+
+/// The pool of connections to the database at `path` if there is one, else the path itself
+fn database<'a>(path: &'a str, pool: &'a Option<ConnectionPool>) -> Database<'a> {
+    match pool {
+        Some(pool) => Database::Pool(pool),
+        None => Database::Path(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unconfigured_sources() {
+        let stats = FaithStats::builder()
+            .anki("/nonexistent/collection.anki2")
+            .build();
+
+        let error = stats.daily().unwrap_err();
+        assert_eq!(error.to_string(), "No KOReader database path configured");
+        assert!(error.is::<SourceNotConfiguredError>());
+
+        let ranges = stats.data_ranges();
+        assert_eq!(ranges.len(), 1);
+        assert_eq!(ranges[0].source, "anki");
+        assert!(ranges[0].error.is_some());
+    }
+
+    #[test]
+    fn test_invalid_ranges() {
+        let sources = FaithStats::builder()
+            .anki("/nonexistent/collection.anki2")
+            .reading("/nonexistent/statistics.sqlite3")
+            .arc("/nonexistent/arc")
+            .prayer("/nonexistent/database.sqlite");
+
+        for weeks in [0, -1, MAX_WEEKS + 1] {
+            let error = sources.clone().weeks(weeks).build().weekly().unwrap_err();
+            assert!(
+                error.to_string().starts_with("The number of weeks"),
+                "{}",
+                error
+            );
+        }
+
+        let error = sources
+            .clone()
+            .period(DatePeriod {
+                dates: Vec::new(),
+                start_ms: 0,
+                end_ms: 0,
+            })
+            .build()
+            .daily()
+            .unwrap_err();
+        assert!(error.to_string().starts_with("The period"), "{}", error);
+
+        let error = sources
+            .period(DatePeriod::last_n_days(MAX_DAYS as i32 + 1).unwrap())
+            .build()
+            .daily()
+            .unwrap_err();
+        assert!(error.to_string().starts_with("The period"), "{}", error);
+    }
+}
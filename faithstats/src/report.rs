@@ -0,0 +1,64 @@
+use anyhow::Result;
+
+use crate::models::FaithWeekStats;
+use crate::templates::{render, week_context};
+use statsutils::format::format_number;
+use statsutils::i18n::tr;
+
+/// Formats a duration as "N min" below an hour, and "N.N hours" above
+pub fn format_minutes(minutes: f64) -> String {
+    if minutes < 60.0 {
+        format!("{} {}", format_number(minutes, 0), tr("min"))
+    } else {
+        format!("{} {}", format_number(minutes / 60.0, 1), tr("hours"))
+    }
+}
+
+/// Builds the plain-text summary of a week's faith activity
+///
+/// Rendered from the `weekly_summary.txt` template; sources without any activity in the
+/// week are left out by the built-in template.
+///
+/// # Example
+/// ```text
+/// Week of 2025-01-05: 4.2 hours of faith activity
+/// - Scripture memory: 45 min (+3 matured, -1 lost, 120 mature passages)
+/// - Bible reading: 1.5 hours
+/// - Church: 1.5 hours
+/// - Prayer: 30 min
+/// ```
+pub fn weekly_summary_text(week: &FaithWeekStats) -> Result<String> {
+    render("weekly_summary.txt", week_context(week))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weekly_summary_text_skips_inactive_sources() {
+        let week = FaithWeekStats {
+            week_start: "2025-01-05".to_string(),
+            anki_minutes: 45.0,
+            anki_matured_passages: 3,
+            anki_lost_passages: 1,
+            anki_cumulative_passages: 120,
+            anki_matured_references: Vec::new(),
+            anki_lost_references: Vec::new(),
+            anki_reviews: 0,
+            anki_avg_seconds_per_review: None,
+            anki_projected_minutes: None,
+            reading_minutes: 90.0,
+            at_church_minutes: 0.0,
+            prayer_minutes: 0.0,
+            exempt_days: 0,
+        };
+
+        assert_eq!(
+            weekly_summary_text(&week).unwrap(),
+            "Week of 2025-01-05: 2.2 hours of faith activity\n\
+             - Scripture memory: 45 min (+3 matured, -1 lost, 120 mature passages)\n\
+             - Bible reading: 1.5 hours"
+        );
+    }
+}
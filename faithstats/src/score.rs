@@ -0,0 +1,225 @@
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::goals::GoalMetric;
+use crate::models::{FaithDayStats, FaithWeekStats};
+
+/// Days per week, for scaling daily caps to weekly ones
+const DAYS_PER_WEEK: f64 = 7.0;
+
+/// One weighted activity of the faith score
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ScoreComponent {
+    pub metric: GoalMetric,
+    /// Weight relative to the other components (default 1)
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+    /// Minutes at which the activity counts fully; more minutes add nothing. Per day, except
+    /// for `at_church_minutes`, which is only tracked per week.
+    pub cap_minutes: f64,
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+impl ScoreComponent {
+    /// Cap of this component over a whole week
+    fn weekly_cap_minutes(&self) -> f64 {
+        match self.metric {
+            GoalMetric::AtChurchMinutes => self.cap_minutes,
+            _ => self.cap_minutes * DAYS_PER_WEEK,
+        }
+    }
+}
+
+/// Components of the default faith score: 15 minutes of Scripture memory, 20 of reading and
+/// 15 of prayer a day, and an hour at church a week, weighted equally
+pub fn default_components() -> Vec<ScoreComponent> {
+    [
+        (GoalMetric::AnkiMinutes, 15.0),
+        (GoalMetric::ReadingMinutes, 20.0),
+        (GoalMetric::PrayerMinutes, 15.0),
+        (GoalMetric::AtChurchMinutes, 60.0),
+    ]
+    .into_iter()
+    .map(|(metric, cap_minutes)| ScoreComponent {
+        metric,
+        weight: 1.0,
+        cap_minutes,
+    })
+    .collect()
+}
+
+/// Checks that weights are not negative, at least one is positive, and caps are positive
+pub fn validate_components(components: &[ScoreComponent]) -> Result<()> {
+    for component in components {
+        if component.weight < 0.0 || !component.weight.is_finite() {
+            bail!(
+                "Score weight of {:?} must not be negative",
+                component.metric
+            );
+        }
+        if component.cap_minutes <= 0.0 || !component.cap_minutes.is_finite() {
+            bail!("Score cap of {:?} must be positive", component.metric);
+        }
+    }
+    if !components.iter().any(|component| component.weight > 0.0) {
+        bail!("At least one score component needs a positive weight");
+    }
+    Ok(())
+}
+
+/// Weighted average of the capped fractions, scaled to 0-100 and rounded to one decimal
+///
+/// `minutes` returns the component's minutes and cap, or `None` to leave it out.
+fn score<'a>(
+    components: &'a [ScoreComponent],
+    minutes: impl Fn(&'a ScoreComponent) -> Option<(f64, f64)>,
+) -> f64 {
+    let (weighted, total_weight) = components
+        .iter()
+        .filter_map(|component| {
+            let (minutes, cap) = minutes(component)?;
+            Some((
+                component.weight * (minutes / cap).clamp(0.0, 1.0),
+                component.weight,
+            ))
+        })
+        .fold((0.0, 0.0), |(weighted, total), (w, weight)| {
+            (weighted + w, total + weight)
+        });
+
+    if total_weight > 0.0 {
+        (weighted / total_weight * 1000.0).round() / 10.0
+    } else {
+        0.0
+    }
+}
+
+/// Faith score of a day
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DayScore {
+    /// Date in YYYY-MM-DD format
+    pub date: String,
+    /// 0-100
+    pub score: f64,
+}
+
+/// Faith score of a week
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct WeekScore {
+    /// Week start date in YYYY-MM-DD format
+    pub week_start: String,
+    /// 0-100
+    pub score: f64,
+}
+
+/// Score of a day; components only tracked per week (church) are left out
+pub fn day_score(day: &FaithDayStats, components: &[ScoreComponent]) -> DayScore {
+    DayScore {
+        date: day.date.clone(),
+        score: score(components, |component| {
+            Some((component.metric.day_minutes(day)?, component.cap_minutes))
+        }),
+    }
+}
+
+/// Score of a week, with daily caps scaled to the whole week
+pub fn week_score(week: &FaithWeekStats, components: &[ScoreComponent]) -> WeekScore {
+    WeekScore {
+        week_start: week.week_start.clone(),
+        score: score(components, |component| {
+            Some((
+                component.metric.week_minutes(week),
+                component.weekly_cap_minutes(),
+            ))
+        }),
+    }
+}
+
+/// Daily and weekly faith scores, with the current ones as headline numbers
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FaithScore {
+    /// Score of today (0-100)
+    pub today: f64,
+    /// Score of the current week so far (0-100)
+    pub this_week: f64,
+    /// Daily scores, oldest first
+    pub days: Vec<DayScore>,
+    /// Weekly scores, oldest first
+    pub weeks: Vec<WeekScore>,
+    pub components: Vec<ScoreComponent>,
+}
+
+impl FaithScore {
+    /// Scores every day and week
+    pub fn new(
+        days: &[FaithDayStats],
+        weeks: &[FaithWeekStats],
+        components: Vec<ScoreComponent>,
+    ) -> Self {
+        let days: Vec<DayScore> = days.iter().map(|d| day_score(d, &components)).collect();
+        let weeks: Vec<WeekScore> = weeks.iter().map(|w| week_score(w, &components)).collect();
+
+        Self {
+            today: days.last().map_or(0.0, |day| day.score),
+            this_week: weeks.last().map_or(0.0, |week| week.score),
+            days,
+            weeks,
+            components,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scores_are_capped_and_weighted() {
+        let components = vec![
+            ScoreComponent {
+                metric: GoalMetric::ReadingMinutes,
+                weight: 3.0,
+                cap_minutes: 20.0,
+            },
+            ScoreComponent {
+                metric: GoalMetric::PrayerMinutes,
+                weight: 1.0,
+                cap_minutes: 10.0,
+            },
+            ScoreComponent {
+                metric: GoalMetric::AtChurchMinutes,
+                weight: 1.0,
+                cap_minutes: 60.0,
+            },
+        ];
+        validate_components(&components).unwrap();
+
+        // Reading is capped at 1.0, prayer counts half; church is left out per day
+        let day = FaithDayStats {
+            date: "2025-01-06".to_string(),
+            reading_minutes: Some(45.0),
+            prayer_minutes: Some(5.0),
+            ..Default::default()
+        };
+        assert_eq!(day_score(&day, &components).score, 87.5);
+
+        let week = FaithWeekStats {
+            week_start: "2025-01-05".to_string(),
+            reading_minutes: 70.0,
+            prayer_minutes: 70.0,
+            at_church_minutes: 90.0,
+            ..Default::default()
+        };
+        // (3 * 0.5 + 1 * 1.0 + 1 * 1.0) / 5
+        assert_eq!(week_score(&week, &components).score, 70.0);
+
+        assert!(validate_components(&[]).is_err());
+    }
+}
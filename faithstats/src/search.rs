@@ -0,0 +1,287 @@
+use ankistats::models::{PassageDetails, PassageStatus};
+use ankistats::reference::parse_reference;
+use anyhow::Result;
+use arcstats::stats::PlaceStats;
+use readingstats::models::ReadBook;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+/// Default number of results per kind
+pub const DEFAULT_SEARCH_LIMIT: usize = 10;
+
+/// How long the passages, places and books loaded for a search are reused, so that a
+/// quick-jump box searching on every keystroke doesn't reload each source every time
+pub const SEARCH_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Everything a search is matched against
+#[derive(Debug, Clone, Default)]
+pub struct SearchCandidates {
+    pub passages: Vec<PassageDetails>,
+    pub places: Vec<PlaceStats>,
+    pub books: Vec<ReadBook>,
+}
+
+/// Recently loaded candidates by the sources they were loaded from, with their load time
+type CandidateCache = HashMap<Vec<String>, (Instant, SearchCandidates)>;
+
+static CANDIDATES: LazyLock<Mutex<CandidateCache>> = LazyLock::new(Default::default);
+
+/// Returns the candidates loaded from the same sources (`key`) within the last
+/// [`SEARCH_CACHE_TTL`], or loads and remembers them
+///
+/// The cache is not locked while loading, so a slow source doesn't hold up other searches.
+pub fn cached_candidates(
+    key: Vec<String>,
+    load: impl FnOnce() -> Result<SearchCandidates>,
+) -> Result<SearchCandidates> {
+    if let Some((loaded_at, candidates)) = CANDIDATES.lock().unwrap().get(&key)
+        && loaded_at.elapsed() < SEARCH_CACHE_TTL
+    {
+        return Ok(candidates.clone());
+    }
+
+    let candidates = load()?;
+    let mut cache = CANDIDATES.lock().unwrap();
+    cache.retain(|_, (loaded_at, _)| loaded_at.elapsed() < SEARCH_CACHE_TTL);
+    cache.insert(key, (Instant::now(), candidates.clone()));
+    Ok(candidates)
+}
+
+/// Where a search result comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum SearchKind {
+    /// Anki passage, matched by reference
+    Passage,
+    /// Place from the location history, matched by name
+    Place,
+    /// KOReader book, matched by title
+    Book,
+}
+
+/// A passage, place or book matching a search
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SearchHit {
+    pub kind: SearchKind,
+    /// Passage reference, place name or book title
+    pub label: String,
+    /// Passage status, place locality or book authors, if any
+    pub detail: Option<String>,
+    /// Date the passage matured, the place was last visited or the book was last read
+    /// (YYYY-MM-DD), if any
+    pub date: Option<String>,
+}
+
+/// Search results, best matches first: exact matches, then labels starting with the query,
+/// then labels containing it
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SearchResults {
+    pub query: String,
+    pub hits: Vec<SearchHit>,
+}
+
+/// How well a label matches a lowercase query (lower is better), or `None` if it doesn't
+fn match_rank(label: &str, query: &str) -> Option<u8> {
+    let label = label.to_lowercase();
+    if label == query {
+        Some(0)
+    } else if label.starts_with(query) {
+        Some(1)
+    } else if label.contains(query) {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+fn status_name(status: PassageStatus) -> &'static str {
+    match status {
+        PassageStatus::Mature => "mature",
+        PassageStatus::Young => "young",
+        PassageStatus::Unseen => "unseen",
+        PassageStatus::Suspended => "suspended",
+    }
+}
+
+/// Ranks the matching items, keeping the `limit` best; the items' own order (e.g. most hours
+/// first) breaks ties
+fn best_matches<T>(
+    items: Vec<T>,
+    limit: usize,
+    rank: impl Fn(&T) -> Option<u8>,
+    hit: impl Fn(T) -> SearchHit,
+) -> Vec<(u8, SearchHit)> {
+    let mut matches: Vec<(u8, T)> = items
+        .into_iter()
+        .filter_map(|item| Some((rank(&item)?, item)))
+        .collect();
+    matches.sort_by_key(|(rank, _)| *rank);
+    matches
+        .into_iter()
+        .take(limit)
+        .map(|(rank, item)| (rank, hit(item)))
+        .collect()
+}
+
+impl SearchResults {
+    /// Matches the query case-insensitively against passage references, place names and book
+    /// titles, keeping up to `limit` hits of each kind
+    ///
+    /// A query that parses as a Bible reference ("romans 5: 1") also matches its canonical
+    /// form ("Romans 5:1").
+    pub fn new(
+        query: &str,
+        passages: Vec<PassageDetails>,
+        places: Vec<PlaceStats>,
+        books: Vec<ReadBook>,
+        limit: usize,
+    ) -> Self {
+        let query = query.trim();
+        let lowercase = query.to_lowercase();
+        let reference = parse_reference(query)
+            .ok()
+            .map(|reference| reference.to_string().to_lowercase());
+        let rank = |label: &str| {
+            let reference_rank = reference
+                .as_deref()
+                .and_then(|reference| match_rank(label, reference));
+            match_rank(label, &lowercase)
+                .into_iter()
+                .chain(reference_rank)
+                .min()
+        };
+
+        let mut hits = best_matches(
+            passages,
+            limit,
+            |passage| rank(&passage.reference),
+            |passage| SearchHit {
+                kind: SearchKind::Passage,
+                label: passage.reference,
+                detail: Some(status_name(passage.status).to_string()),
+                date: passage.matured_date,
+            },
+        );
+        hits.extend(best_matches(
+            places,
+            limit,
+            |place| rank(&place.place_name),
+            |place| SearchHit {
+                kind: SearchKind::Place,
+                label: place.place_name,
+                detail: place.locality,
+                date: place.last_visit.get(..10).map(str::to_string),
+            },
+        ));
+        hits.extend(best_matches(
+            books,
+            limit,
+            |book| rank(&book.title),
+            |book| SearchHit {
+                kind: SearchKind::Book,
+                label: book.title,
+                detail: book.authors,
+                date: Some(book.last_read_date),
+            },
+        ));
+        // Stable, so passages stay before places before books within a rank
+        hits.sort_by_key(|(rank, _)| *rank);
+
+        Self {
+            query: query.to_string(),
+            hits: hits.into_iter().map(|(_, hit)| hit).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn place(place_name: &str, locality: &str) -> PlaceStats {
+        PlaceStats {
+            place_name: place_name.to_string(),
+            hours: 1.0,
+            category: None,
+            locality: Some(locality.to_string()),
+            visit_count: 1,
+            first_visit: "2025-01-05T10:00:00+00:00".to_string(),
+            last_visit: "2025-03-02T10:00:00+00:00".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_search_results() {
+        let passages = ["Romans 5:1", "Romans 8:28", "John 3:16"]
+            .into_iter()
+            .map(|reference| PassageDetails {
+                reference: reference.to_string(),
+                status: PassageStatus::Mature,
+                first_review_date: None,
+                matured_date: Some("2024-06-01".to_string()),
+            })
+            .collect::<Vec<_>>();
+        let places = vec![
+            place("St. John Lutheran Church", "Springfield"),
+            place("John's Diner", "Springfield"),
+        ];
+        let books = vec![ReadBook {
+            title: "The Gospel according to John".to_string(),
+            authors: None,
+            minutes: 90.0,
+            last_read_date: "2025-02-01".to_string(),
+        }];
+
+        let results = SearchResults::new(" john ", passages.clone(), places, books, 10);
+        assert_eq!(results.query, "john");
+        let labels: Vec<&str> = results.hits.iter().map(|h| h.label.as_str()).collect();
+        assert_eq!(
+            labels,
+            vec![
+                "John 3:16",
+                "John's Diner",
+                "St. John Lutheran Church",
+                "The Gospel according to John"
+            ]
+        );
+        assert_eq!(results.hits[0].detail.as_deref(), Some("mature"));
+        assert_eq!(results.hits[1].date.as_deref(), Some("2025-03-02"));
+
+        // Loosely written references match the canonical form
+        let results = SearchResults::new("romans 8: 28", passages.clone(), vec![], vec![], 10);
+        assert_eq!(results.hits.len(), 1);
+        assert_eq!(results.hits[0].label, "Romans 8:28");
+
+        let results = SearchResults::new("romans", passages, vec![], vec![], 1);
+        assert_eq!(results.hits.len(), 1);
+    }
+
+    #[test]
+    fn test_cached_candidates_are_reused() {
+        let key = vec!["test_cached_candidates_are_reused".to_string()];
+        let mut loads = 0;
+        let mut load = || {
+            loads += 1;
+            Ok(SearchCandidates {
+                places: vec![place("Grace Church", "Springfield")],
+                ..Default::default()
+            })
+        };
+
+        let first = cached_candidates(key.clone(), &mut load).unwrap();
+        let second = cached_candidates(key, &mut load).unwrap();
+        assert_eq!(loads, 1);
+        assert_eq!(first.places[0].place_name, second.places[0].place_name);
+
+        // Failed loads are not remembered
+        let key = vec!["test_cached_candidates_failed_load".to_string()];
+        assert!(cached_candidates(key.clone(), || anyhow::bail!("unavailable")).is_err());
+        let candidates = cached_candidates(key, || Ok(SearchCandidates::default())).unwrap();
+        assert!(candidates.places.is_empty());
+    }
+}
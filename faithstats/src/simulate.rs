@@ -0,0 +1,193 @@
+use chrono::{Days, NaiveDate};
+use serde::Serialize;
+
+/// Silent reading speed assumed when none is given: about 250 words a minute at roughly 25
+/// words per verse
+pub const DEFAULT_READING_VERSES_PER_MINUTE: f64 = 10.0;
+
+/// Name of the target covering every book of the New Testament
+pub const NEW_TESTAMENT_TARGET: &str = "New Testament";
+
+/// A hypothetical daily commitment
+#[derive(Debug, Clone, Copy)]
+pub struct Commitment {
+    /// New verses added to Anki per day
+    pub new_verses_per_day: f64,
+    /// Minutes of Bible reading per day
+    pub reading_minutes_per_day: f64,
+    pub reading_verses_per_minute: f64,
+}
+
+/// Current memorization state of a book or group of books
+#[derive(Debug, Clone)]
+pub struct Target {
+    pub name: String,
+    pub total_verses: i64,
+    pub mature_verses: i64,
+    /// Share of matured passages that stay mature (1 - relapse rate)
+    pub retention: f64,
+}
+
+/// Projected completion of one target under the commitment
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TargetProjection {
+    /// Book name, or "New Testament"
+    pub target: String,
+    pub total_verses: i64,
+    pub mature_verses: i64,
+    /// Verses not mature yet
+    pub remaining_verses: i64,
+    /// Share of matured passages that stay mature (1 - relapse rate), from the book's own
+    /// history when it has any, otherwise from the whole deck
+    pub retention: f64,
+    /// Verses that become lasting memory per day: new verses per day × retention
+    pub effective_verses_per_day: f64,
+    /// Days until every remaining verse is memorized (`null` without new verses)
+    pub days_to_memorize: Option<i64>,
+    /// Date in YYYY-MM-DD format every verse is memorized, if all new verses go to this target
+    /// (`null` without new verses or when the date is out of range)
+    pub memorized_by: Option<String>,
+    /// Days to read the whole target once (`null` without reading time)
+    pub days_to_read: Option<i64>,
+    /// Date in YYYY-MM-DD format the whole target is read once, starting today (`null`
+    /// without reading time or when the date is out of range)
+    pub read_by: Option<String>,
+}
+
+impl TargetProjection {
+    pub fn new(target: Target, commitment: &Commitment, today: NaiveDate) -> Self {
+        let remaining_verses = (target.total_verses - target.mature_verses).max(0);
+        let effective_verses_per_day = commitment.new_verses_per_day * target.retention;
+        let days_to_memorize = days_needed(remaining_verses, effective_verses_per_day);
+        let days_to_read = days_needed(
+            target.total_verses,
+            commitment.reading_minutes_per_day * commitment.reading_verses_per_minute,
+        );
+
+        Self {
+            target: target.name,
+            total_verses: target.total_verses,
+            mature_verses: target.mature_verses,
+            remaining_verses,
+            retention: (target.retention * 1000.0).round() / 1000.0,
+            effective_verses_per_day: (effective_verses_per_day * 100.0).round() / 100.0,
+            days_to_memorize,
+            memorized_by: days_to_memorize.and_then(|days| date_after(today, days)),
+            days_to_read,
+            read_by: days_to_read.and_then(|days| date_after(today, days)),
+        }
+    }
+}
+
+/// Days needed for `verses` at `verses_per_day`, or `None` if there is no progress
+fn days_needed(verses: i64, verses_per_day: f64) -> Option<i64> {
+    if verses == 0 {
+        Some(0)
+    } else if verses_per_day > 0.0 {
+        Some((verses as f64 / verses_per_day).ceil() as i64)
+    } else {
+        None
+    }
+}
+
+/// The date `days` after today, or `None` if it is out of range (a tiny daily rate can take
+/// longer than any date chrono supports)
+fn date_after(today: NaiveDate, days: i64) -> Option<String> {
+    let date = today.checked_add_days(Days::new(u64::try_from(days).ok()?))?;
+    Some(date.format("%Y-%m-%d").to_string())
+}
+
+/// Projections of the selected books and the whole New Testament
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GoalSimulation {
+    pub new_verses_per_day: f64,
+    pub reading_minutes_per_day: f64,
+    pub reading_verses_per_minute: f64,
+    /// Selected books in the requested order, followed by the New Testament
+    pub targets: Vec<TargetProjection>,
+}
+
+impl GoalSimulation {
+    /// Projects each target independently, as if the whole commitment went to it
+    pub fn new(targets: Vec<Target>, commitment: Commitment, today: NaiveDate) -> Self {
+        Self {
+            new_verses_per_day: commitment.new_verses_per_day,
+            reading_minutes_per_day: commitment.reading_minutes_per_day,
+            reading_verses_per_minute: commitment.reading_verses_per_minute,
+            targets: targets
+                .into_iter()
+                .map(|target| TargetProjection::new(target, &commitment, today))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_projection_accounts_for_retention() {
+        let commitment = Commitment {
+            new_verses_per_day: 2.0,
+            reading_minutes_per_day: 20.0,
+            reading_verses_per_minute: DEFAULT_READING_VERSES_PER_MINUTE,
+        };
+        let target = Target {
+            name: "Isaiah".to_string(),
+            total_verses: 1292,
+            mature_verses: 92,
+            retention: 0.8,
+        };
+        let today = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+
+        let projection = TargetProjection::new(target, &commitment, today);
+
+        assert_eq!(projection.remaining_verses, 1200);
+        assert_eq!(projection.effective_verses_per_day, 1.6);
+        assert_eq!(projection.days_to_memorize, Some(750));
+        assert_eq!(projection.memorized_by.as_deref(), Some("2027-01-21"));
+        // 1292 verses at 200 verses a day
+        assert_eq!(projection.days_to_read, Some(7));
+        assert_eq!(projection.read_by.as_deref(), Some("2025-01-08"));
+
+        let idle = Commitment {
+            new_verses_per_day: 0.0,
+            ..commitment
+        };
+        let target = Target {
+            name: "Jude".to_string(),
+            total_verses: 25,
+            mature_verses: 0,
+            retention: 1.0,
+        };
+        assert_eq!(
+            TargetProjection::new(target, &idle, today).memorized_by,
+            None
+        );
+    }
+
+    #[test]
+    fn test_projection_with_tiny_rates_has_no_dates() {
+        let commitment = Commitment {
+            new_verses_per_day: 1e-300,
+            reading_minutes_per_day: 1e-300,
+            reading_verses_per_minute: DEFAULT_READING_VERSES_PER_MINUTE,
+        };
+        let target = Target {
+            name: "Psalms".to_string(),
+            total_verses: 2461,
+            mature_verses: 0,
+            retention: 1.0,
+        };
+        let today = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+
+        let projection = TargetProjection::new(target, &commitment, today);
+
+        assert_eq!(projection.days_to_memorize, Some(i64::MAX));
+        assert_eq!(projection.memorized_by, None);
+        assert_eq!(projection.read_by, None);
+    }
+}
@@ -0,0 +1,582 @@
+use anyhow::{Context, Result};
+use rusqlite::{Connection, OptionalExtension, params};
+use statsutils::now_rfc3339;
+use std::collections::HashMap;
+
+use crate::exemptions::Exemption;
+use crate::export::ExportManifest;
+use crate::models::{FaithDayStats, FaithWeekStats};
+use crate::notes::DayNote;
+use crate::tags::{DayTag, TagCount};
+
+/// Schema of the stats store, applied on every open
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS imports (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    imported_at TEXT NOT NULL,
+    generated_at TEXT NOT NULL,
+    format_version INTEGER NOT NULL,
+    days INTEGER NOT NULL,
+    weeks INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS imported_days (
+    date TEXT PRIMARY KEY,
+    anki_minutes REAL NOT NULL,
+    anki_matured_passages INTEGER NOT NULL,
+    anki_lost_passages INTEGER NOT NULL,
+    anki_cumulative_passages INTEGER NOT NULL,
+    reading_minutes REAL NOT NULL,
+    prayer_minutes REAL NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS imported_weeks (
+    week_start TEXT PRIMARY KEY,
+    anki_minutes REAL NOT NULL,
+    anki_matured_passages INTEGER NOT NULL,
+    anki_lost_passages INTEGER NOT NULL,
+    anki_cumulative_passages INTEGER NOT NULL,
+    reading_minutes REAL NOT NULL,
+    at_church_minutes REAL NOT NULL,
+    prayer_minutes REAL NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS exemptions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    start TEXT NOT NULL,
+    end TEXT NOT NULL,
+    reason TEXT
+);
+
+CREATE TABLE IF NOT EXISTS notes (
+    date TEXT PRIMARY KEY,
+    text TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS day_tags (
+    date TEXT NOT NULL,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (date, tag)
+);
+";
+
+/// Writeable SQLite store for data that does not come from the read-only sources,
+/// such as history imported from a previous export archive, exempt date ranges, notes and day tags
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    /// Opens (or creates) the stats store at the given path
+    pub fn open(path: &str) -> Result<Self> {
+        let conn =
+            Connection::open(path).context(format!("Failed to open stats store at {}", path))?;
+        Self::init(conn)
+    }
+
+    /// Opens a temporary in-memory stats store
+    pub fn open_in_memory() -> Result<Self> {
+        let conn = Connection::open_in_memory().context("Failed to open in-memory stats store")?;
+        Self::init(conn)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA)
+            .context("Failed to initialize stats store schema")?;
+        Ok(Self { conn })
+    }
+
+    /// Stores the daily and weekly stats of an imported archive, replacing previously imported
+    /// values for the same dates, and records the import
+    ///
+    /// Everything is written in one transaction, so a failing import leaves the store as it
+    /// was.
+    pub fn import_history(
+        &mut self,
+        manifest: &ExportManifest,
+        days: &[FaithDayStats],
+        weeks: &[FaithWeekStats],
+    ) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        insert_imported_days(&tx, days)?;
+        insert_imported_weeks(&tx, weeks)?;
+        record_import(&tx, manifest)?;
+        tx.commit().context("Failed to commit import")?;
+        Ok(())
+    }
+
+    /// Returns imported daily stats between two dates (inclusive), keyed by date
+    pub fn get_imported_days(
+        &self,
+        first_date: &str,
+        last_date: &str,
+    ) -> Result<HashMap<String, FaithDayStats>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT date, anki_minutes, anki_matured_passages, anki_lost_passages,
+                    anki_cumulative_passages, reading_minutes, prayer_minutes
+             FROM imported_days
+             WHERE date BETWEEN ?1 AND ?2",
+        )?;
+
+        let days = stmt
+            .query_map(params![first_date, last_date], |row| {
+                Ok(FaithDayStats {
+                    date: row.get(0)?,
+                    anki_minutes: row.get(1)?,
+                    anki_matured_passages: row.get(2)?,
+                    anki_lost_passages: row.get(3)?,
+                    anki_cumulative_passages: row.get(4)?,
+                    // Only the counts are kept for imported history
+                    anki_matured_references: Vec::new(),
+                    anki_lost_references: Vec::new(),
+                    anki_reviews: 0,
+                    anki_avg_seconds_per_review: None,
+                    reading_minutes: row.get(5)?,
+                    prayer_minutes: row.get(6)?,
+                    exempt: false,
+                    note: None,
+                    tags: Vec::new(),
+                })
+            })?
+            .map(|day| day.map(|day| (day.date.clone(), day)))
+            .collect::<Result<HashMap<_, _>, _>>()
+            .context("Failed to query imported days")?;
+
+        Ok(days)
+    }
+
+    /// Returns imported weekly stats between two week starts (inclusive), keyed by week start
+    pub fn get_imported_weeks(
+        &self,
+        first_week_start: &str,
+        last_week_start: &str,
+    ) -> Result<HashMap<String, FaithWeekStats>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT week_start, anki_minutes, anki_matured_passages, anki_lost_passages,
+                    anki_cumulative_passages, reading_minutes, at_church_minutes, prayer_minutes
+             FROM imported_weeks
+             WHERE week_start BETWEEN ?1 AND ?2",
+        )?;
+
+        let weeks = stmt
+            .query_map(params![first_week_start, last_week_start], |row| {
+                Ok(FaithWeekStats {
+                    week_start: row.get(0)?,
+                    anki_minutes: row.get(1)?,
+                    anki_matured_passages: row.get(2)?,
+                    anki_lost_passages: row.get(3)?,
+                    anki_cumulative_passages: row.get(4)?,
+                    // Only the counts are kept for imported history
+                    anki_matured_references: Vec::new(),
+                    anki_lost_references: Vec::new(),
+                    anki_reviews: 0,
+                    anki_avg_seconds_per_review: None,
+                    anki_projected_minutes: None,
+                    reading_minutes: row.get(5)?,
+                    at_church_minutes: row.get(6)?,
+                    prayer_minutes: row.get(7)?,
+                    exempt_days: 0,
+                })
+            })?
+            .map(|week| week.map(|week| (week.week_start.clone(), week)))
+            .collect::<Result<HashMap<_, _>, _>>()
+            .context("Failed to query imported weeks")?;
+
+        Ok(weeks)
+    }
+
+    /// Adds an exempt date range and returns it with its new id
+    pub fn add_exemption(&self, start: &str, end: &str, reason: Option<&str>) -> Result<Exemption> {
+        self.conn
+            .execute(
+                "INSERT INTO exemptions (start, end, reason) VALUES (?1, ?2, ?3)",
+                params![start, end, reason],
+            )
+            .context(format!("Failed to store exemption {} to {}", start, end))?;
+
+        Ok(Exemption {
+            id: self.conn.last_insert_rowid(),
+            start: start.to_string(),
+            end: end.to_string(),
+            reason: reason.map(str::to_string),
+        })
+    }
+
+    /// Returns all exempt date ranges, ordered by start date
+    pub fn get_exemptions(&self) -> Result<Vec<Exemption>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT id, start, end, reason FROM exemptions ORDER BY start, id")?;
+
+        let exemptions = stmt
+            .query_map([], |row| {
+                Ok(Exemption {
+                    id: row.get(0)?,
+                    start: row.get(1)?,
+                    end: row.get(2)?,
+                    reason: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()
+            .context("Failed to query exemptions")?;
+
+        Ok(exemptions)
+    }
+
+    /// Removes an exempt date range; returns whether it existed
+    pub fn delete_exemption(&self, id: i64) -> Result<bool> {
+        let deleted = self
+            .conn
+            .execute("DELETE FROM exemptions WHERE id = ?1", params![id])
+            .context(format!("Failed to delete exemption {}", id))?;
+        Ok(deleted > 0)
+    }
+
+    /// Sets the note of a day, replacing an earlier one, and returns it
+    pub fn put_note(&self, date: &str, text: &str) -> Result<DayNote> {
+        let note = DayNote {
+            date: date.to_string(),
+            text: text.to_string(),
+            updated_at: now_rfc3339()?,
+        };
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO notes (date, text, updated_at) VALUES (?1, ?2, ?3)",
+                params![note.date, note.text, note.updated_at],
+            )
+            .context(format!("Failed to store note of {}", date))?;
+        Ok(note)
+    }
+
+    /// Returns the note of a day, if any
+    pub fn get_note(&self, date: &str) -> Result<Option<DayNote>> {
+        self.conn
+            .query_row(
+                "SELECT date, text, updated_at FROM notes WHERE date = ?1",
+                params![date],
+                |row| {
+                    Ok(DayNote {
+                        date: row.get(0)?,
+                        text: row.get(1)?,
+                        updated_at: row.get(2)?,
+                    })
+                },
+            )
+            .optional()
+            .context(format!("Failed to query note of {}", date))
+    }
+
+    /// Returns the notes between two dates (inclusive), ordered by date
+    pub fn get_notes(&self, first_date: &str, last_date: &str) -> Result<Vec<DayNote>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT date, text, updated_at FROM notes WHERE date BETWEEN ?1 AND ?2 ORDER BY date",
+        )?;
+
+        let notes = stmt
+            .query_map(params![first_date, last_date], |row| {
+                Ok(DayNote {
+                    date: row.get(0)?,
+                    text: row.get(1)?,
+                    updated_at: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()
+            .context("Failed to query notes")?;
+
+        Ok(notes)
+    }
+
+    /// Removes the note of a day; returns whether it existed
+    pub fn delete_note(&self, date: &str) -> Result<bool> {
+        let deleted = self
+            .conn
+            .execute("DELETE FROM notes WHERE date = ?1", params![date])
+            .context(format!("Failed to delete note of {}", date))?;
+        Ok(deleted > 0)
+    }
+
+    /// Attaches a tag to a day; returns whether the day did not carry it yet
+    pub fn add_tag(&self, date: &str, tag: &str) -> Result<bool> {
+        let added = self
+            .conn
+            .execute(
+                "INSERT OR IGNORE INTO day_tags (date, tag) VALUES (?1, ?2)",
+                params![date, tag],
+            )
+            .context(format!("Failed to tag {} with '{}'", date, tag))?;
+        Ok(added > 0)
+    }
+
+    /// Removes a tag from a day; returns whether the day carried it
+    pub fn remove_tag(&self, date: &str, tag: &str) -> Result<bool> {
+        let removed = self
+            .conn
+            .execute(
+                "DELETE FROM day_tags WHERE date = ?1 AND tag = ?2",
+                params![date, tag],
+            )
+            .context(format!("Failed to remove tag '{}' from {}", tag, date))?;
+        Ok(removed > 0)
+    }
+
+    /// Returns the tags of the days between two dates (inclusive), ordered by date and tag
+    pub fn get_day_tags(&self, first_date: &str, last_date: &str) -> Result<Vec<DayTag>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT date, tag FROM day_tags WHERE date BETWEEN ?1 AND ?2 ORDER BY date, tag",
+        )?;
+
+        let tags = stmt
+            .query_map(params![first_date, last_date], |row| {
+                Ok(DayTag {
+                    date: row.get(0)?,
+                    tag: row.get(1)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()
+            .context("Failed to query day tags")?;
+
+        Ok(tags)
+    }
+
+    /// Returns every tag with the number of days carrying it, ordered by tag
+    pub fn get_tag_counts(&self) -> Result<Vec<TagCount>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT tag, COUNT(*), MIN(date), MAX(date) FROM day_tags GROUP BY tag ORDER BY tag",
+        )?;
+
+        let counts = stmt
+            .query_map([], |row| {
+                Ok(TagCount {
+                    tag: row.get(0)?,
+                    days: row.get(1)?,
+                    first_date: row.get(2)?,
+                    last_date: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()
+            .context("Failed to query tags")?;
+
+        Ok(counts)
+    }
+}
+
+/// Records that an archive described by `manifest` was imported
+fn record_import(conn: &Connection, manifest: &ExportManifest) -> Result<()> {
+    conn.execute(
+        "INSERT INTO imports (imported_at, generated_at, format_version, days, weeks)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            now_rfc3339()?,
+            manifest.generated_at,
+            manifest.format_version,
+            manifest.days,
+            manifest.weeks
+        ],
+    )
+    .context("Failed to record import")?;
+    Ok(())
+}
+
+/// Inserts imported daily stats, replacing previously imported values for the same dates
+fn insert_imported_days(conn: &Connection, days: &[FaithDayStats]) -> Result<()> {
+    let mut stmt = conn.prepare_cached(
+        "INSERT OR REPLACE INTO imported_days (
+            date, anki_minutes, anki_matured_passages, anki_lost_passages,
+            anki_cumulative_passages, reading_minutes, prayer_minutes
+         ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    )?;
+    for day in days {
+        // Days before a source's history are kept as days without activity
+        stmt.execute(params![
+            day.date,
+            day.anki_minutes.unwrap_or(0.0),
+            day.anki_matured_passages,
+            day.anki_lost_passages,
+            day.anki_cumulative_passages,
+            day.reading_minutes.unwrap_or(0.0),
+            day.prayer_minutes.unwrap_or(0.0)
+        ])
+        .context(format!("Failed to store imported day {}", day.date))?;
+    }
+    Ok(())
+}
+
+/// Inserts imported weekly stats, replacing previously imported values for the same weeks
+fn insert_imported_weeks(conn: &Connection, weeks: &[FaithWeekStats]) -> Result<()> {
+    let mut stmt = conn.prepare_cached(
+        "INSERT OR REPLACE INTO imported_weeks (
+            week_start, anki_minutes, anki_matured_passages, anki_lost_passages,
+            anki_cumulative_passages, reading_minutes, at_church_minutes, prayer_minutes
+         ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+    )?;
+    for week in weeks {
+        stmt.execute(params![
+            week.week_start,
+            week.anki_minutes,
+            week.anki_matured_passages,
+            week.anki_lost_passages,
+            week.anki_cumulative_passages,
+            week.reading_minutes,
+            week.at_church_minutes,
+            week.prayer_minutes
+        ])
+        .context(format!("Failed to store imported week {}", week.week_start))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exemptions_round_trip() {
+        let store = Store::open_in_memory().unwrap();
+        let trip = store
+            .add_exemption("2024-07-10", "2024-07-20", Some("Vacation"))
+            .unwrap();
+        let sick = store
+            .add_exemption("2024-03-01", "2024-03-03", None)
+            .unwrap();
+        assert_ne!(trip.id, sick.id);
+
+        let exemptions = store.get_exemptions().unwrap();
+        let ranges: Vec<(i64, &str, &str, Option<&str>)> = exemptions
+            .iter()
+            .map(|e| (e.id, e.start.as_str(), e.end.as_str(), e.reason.as_deref()))
+            .collect();
+        assert_eq!(
+            ranges,
+            vec![
+                (sick.id, "2024-03-01", "2024-03-03", None),
+                (trip.id, "2024-07-10", "2024-07-20", Some("Vacation")),
+            ]
+        );
+
+        assert!(store.delete_exemption(sick.id).unwrap());
+        assert!(!store.delete_exemption(sick.id).unwrap());
+        let remaining = store.get_exemptions().unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, trip.id);
+    }
+
+    #[test]
+    fn test_exemptions_persist_across_opens() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("store.sqlite3");
+        let path = path.to_str().unwrap();
+
+        let id = Store::open(path)
+            .unwrap()
+            .add_exemption("2024-01-01", "2024-01-07", Some("Illness"))
+            .unwrap()
+            .id;
+
+        let exemptions = Store::open(path).unwrap().get_exemptions().unwrap();
+        assert_eq!(exemptions.len(), 1);
+        assert_eq!(exemptions[0].id, id);
+        assert_eq!(exemptions[0].reason.as_deref(), Some("Illness"));
+    }
+
+    #[test]
+    fn test_notes_round_trip() {
+        let store = Store::open_in_memory().unwrap();
+        assert!(store.get_note("2024-05-01").unwrap().is_none());
+
+        let first = store.put_note("2024-05-01", "Started Romans").unwrap();
+        store.put_note("2024-05-03", "Prayed with friends").unwrap();
+        store.put_note("2024-06-01", "Outside the range").unwrap();
+
+        let note = store.get_note("2024-05-01").unwrap().unwrap();
+        assert_eq!(note.text, "Started Romans");
+        assert_eq!(note.updated_at, first.updated_at);
+
+        let notes = store.get_notes("2024-05-01", "2024-05-31").unwrap();
+        let dates: Vec<&str> = notes.iter().map(|note| note.date.as_str()).collect();
+        assert_eq!(dates, vec!["2024-05-01", "2024-05-03"]);
+    }
+
+    #[test]
+    fn test_put_note_replaces_and_delete_removes() {
+        let store = Store::open_in_memory().unwrap();
+        store.put_note("2024-05-01", "Draft").unwrap();
+        store.put_note("2024-05-01", "Final").unwrap();
+
+        let notes = store.get_notes("2024-05-01", "2024-05-01").unwrap();
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].text, "Final");
+
+        assert!(store.delete_note("2024-05-01").unwrap());
+        assert!(!store.delete_note("2024-05-01").unwrap());
+        assert!(store.get_note("2024-05-01").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_day_tags_round_trip() {
+        let store = Store::open_in_memory().unwrap();
+        assert!(store.add_tag("2024-05-02", "travel").unwrap());
+        assert!(store.add_tag("2024-05-02", "conference").unwrap());
+        assert!(store.add_tag("2024-05-01", "travel").unwrap());
+        assert!(!store.add_tag("2024-05-01", "travel").unwrap());
+        assert!(store.add_tag("2024-06-01", "fast").unwrap());
+
+        let tags: Vec<(String, String)> = store
+            .get_day_tags("2024-05-01", "2024-05-31")
+            .unwrap()
+            .into_iter()
+            .map(|tag| (tag.date, tag.tag))
+            .collect();
+        assert_eq!(
+            tags,
+            vec![
+                ("2024-05-01".to_string(), "travel".to_string()),
+                ("2024-05-02".to_string(), "conference".to_string()),
+                ("2024-05-02".to_string(), "travel".to_string()),
+            ]
+        );
+
+        assert!(store.remove_tag("2024-05-02", "travel").unwrap());
+        assert!(!store.remove_tag("2024-05-02", "travel").unwrap());
+        assert_eq!(
+            store
+                .get_day_tags("2024-05-02", "2024-05-02")
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_tag_counts() {
+        let store = Store::open_in_memory().unwrap();
+        for date in ["2024-03-10", "2024-01-05", "2024-02-01"] {
+            store.add_tag(date, "travel").unwrap();
+        }
+        store.add_tag("2024-02-14", "feast").unwrap();
+
+        let counts: Vec<(String, i64, String, String)> = store
+            .get_tag_counts()
+            .unwrap()
+            .into_iter()
+            .map(|count| (count.tag, count.days, count.first_date, count.last_date))
+            .collect();
+        assert_eq!(
+            counts,
+            vec![
+                (
+                    "feast".to_string(),
+                    1,
+                    "2024-02-14".to_string(),
+                    "2024-02-14".to_string()
+                ),
+                (
+                    "travel".to_string(),
+                    3,
+                    "2024-01-05".to_string(),
+                    "2024-03-10".to_string()
+                ),
+            ]
+        );
+    }
+}
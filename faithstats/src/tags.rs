@@ -0,0 +1,126 @@
+use anyhow::{Result, bail};
+use serde::Serialize;
+
+use crate::models::FaithDailyStats;
+
+/// Longest tag accepted, in characters
+pub const MAX_TAG_CHARS: usize = 32;
+
+/// A tag attached to a date
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DayTag {
+    /// Date in YYYY-MM-DD format
+    pub date: String,
+    pub tag: String,
+}
+
+/// A tag and how many days carry it
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TagCount {
+    pub tag: String,
+    pub days: i64,
+    /// First tagged date in YYYY-MM-DD format
+    pub first_date: String,
+    /// Last tagged date in YYYY-MM-DD format
+    pub last_date: String,
+}
+
+/// Lowercases a tag and checks that it only consists of ASCII letters, digits and `-`, so
+/// "Travel" and "travel" are the same tag and tags fit into URLs and query strings as they are
+pub fn normalize_tag(tag: &str) -> Result<String> {
+    let tag = tag.trim().to_lowercase();
+    if tag.is_empty() {
+        bail!("Tag must not be empty");
+    }
+    if tag.chars().count() > MAX_TAG_CHARS {
+        bail!("Tag must not exceed {} characters", MAX_TAG_CHARS);
+    }
+    if !tag
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+    {
+        bail!("Tag '{}' may only contain letters a-z, digits and '-'", tag);
+    }
+    Ok(tag)
+}
+
+/// Adds the tags to the days they belong to
+pub fn with_tags(mut stats: FaithDailyStats, tags: &[DayTag]) -> FaithDailyStats {
+    for day in &mut stats.days {
+        day.tags = tags
+            .iter()
+            .filter(|tag| tag.date == day.date)
+            .map(|tag| tag.tag.clone())
+            .collect();
+    }
+    stats
+}
+
+/// Keeps the days that carry `tag` (or, with `tagged` false, the days that don't) and
+/// recomputes the summary over them
+pub fn filter_by_tag(stats: FaithDailyStats, tag: &str, tagged: bool) -> FaithDailyStats {
+    FaithDailyStats::new(
+        stats
+            .days
+            .into_iter()
+            .filter(|day| day.tags.iter().any(|t| t == tag) == tagged)
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::FaithDayStats;
+
+    #[test]
+    fn test_filter_by_tag() {
+        let stats = FaithDailyStats::new(
+            [
+                ("2025-01-05", 30.0),
+                ("2025-01-06", 10.0),
+                ("2025-01-07", 50.0),
+            ]
+            .into_iter()
+            .map(|(date, reading_minutes)| FaithDayStats {
+                date: date.to_string(),
+                reading_minutes: Some(reading_minutes),
+                ..Default::default()
+            })
+            .collect(),
+        );
+        let tags = vec![
+            DayTag {
+                date: "2025-01-05".to_string(),
+                tag: "travel".to_string(),
+            },
+            DayTag {
+                date: "2025-01-07".to_string(),
+                tag: "travel".to_string(),
+            },
+            DayTag {
+                date: "2025-01-07".to_string(),
+                tag: "conference".to_string(),
+            },
+        ];
+        let stats = with_tags(stats, &tags);
+        assert_eq!(stats.days[2].tags, vec!["travel", "conference"]);
+
+        let travel = filter_by_tag(stats.clone(), "travel", true);
+        assert_eq!(travel.summary.total_days, 2);
+        assert_eq!(travel.summary.reading_average_minutes_per_day, 40.0);
+        let home = filter_by_tag(stats, "travel", false);
+        assert_eq!(home.summary.total_days, 1);
+        assert_eq!(home.summary.reading_average_minutes_per_day, 10.0);
+
+        assert_eq!(normalize_tag(" Feast-Day ").unwrap(), "feast-day");
+        assert!(normalize_tag("").is_err());
+        assert!(normalize_tag("a b").is_err());
+        assert!(normalize_tag("fast_day").is_err());
+        assert!(normalize_tag("fête").is_err());
+        assert!(normalize_tag("ＡＢＣ").is_err());
+        assert!(normalize_tag(&"a".repeat(MAX_TAG_CHARS + 1)).is_err());
+    }
+}
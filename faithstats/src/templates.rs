@@ -0,0 +1,195 @@
+//! Text templates for summaries and reports
+//!
+//! Templates use minijinja (Jinja2) syntax. The built-in templates in `faithstats/templates`
+//! can be overridden by placing a file with the same name in the template directory. In
+//! addition to the minijinja built-ins, the `duration` filter formats minutes like
+//! `report::format_minutes` ("45 min", "1.5 hours") and `hours` formats minutes as hours
+//! with one decimal, both in the current display format.
+//!
+//! Templates are looked up in the subdirectory of the current locale (e.g. `de/`) first, so
+//! translations can be added next to the English templates.
+
+use anyhow::{Context, Result};
+use minijinja::{Environment, ErrorKind, UndefinedBehavior, context};
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+pub use minijinja::value::Value;
+
+use crate::models::{FaithDayStats, FaithWeekStats};
+use crate::report::format_minutes;
+use statsutils::format::format_number;
+use statsutils::i18n::locale;
+
+/// Built-in templates by name
+const BUILTIN_TEMPLATES: &[(&str, &str)] = &[
+    (
+        "weekly_summary.txt",
+        include_str!("../templates/weekly_summary.txt"),
+    ),
+    (
+        "social_daily.txt",
+        include_str!("../templates/social_daily.txt"),
+    ),
+    (
+        "social_weekly.txt",
+        include_str!("../templates/social_weekly.txt"),
+    ),
+    (
+        "de/weekly_summary.txt",
+        include_str!("../templates/de/weekly_summary.txt"),
+    ),
+    (
+        "de/social_daily.txt",
+        include_str!("../templates/de/social_daily.txt"),
+    ),
+    (
+        "de/social_weekly.txt",
+        include_str!("../templates/de/social_weekly.txt"),
+    ),
+];
+
+/// Directory with user templates overriding the built-in ones, if one is configured
+static TEMPLATE_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Sets the directory whose templates override the built-in ones (process-wide)
+///
+/// Templates are read on each render, so edits apply without a restart.
+pub fn set_template_dir(dir: Option<PathBuf>) {
+    *TEMPLATE_DIR.write().unwrap() = dir;
+}
+
+/// Names of all templates that can be overridden, without locale subdirectory
+pub fn template_names() -> impl Iterator<Item = &'static str> {
+    BUILTIN_TEMPLATES
+        .iter()
+        .map(|(name, _)| *name)
+        .filter(|name| !name.contains('/'))
+}
+
+/// Parses every template, including overrides, to report syntax errors early
+pub fn check_templates() -> Result<()> {
+    let env = environment();
+    for name in template_names() {
+        env.get_template(name)
+            .context(format!("Invalid template {}", name))?;
+    }
+    Ok(())
+}
+
+/// Renders a named template
+///
+/// # Errors
+/// Returns an error if the template is unknown, cannot be read or fails to render, e.g.
+/// because it uses a variable that is not defined
+pub fn render(name: &str, context: Value) -> Result<String> {
+    environment()
+        .get_template(name)
+        .context(format!("Failed to load template {}", name))?
+        .render(context)
+        .context(format!("Failed to render template {}", name))
+}
+
+/// Renders a template given as a string, such as one from a config file
+pub fn render_str(source: &str, context: Value) -> Result<String> {
+    environment()
+        .render_str(source, context)
+        .context("Failed to render template")
+}
+
+/// Template context of a day: `day` (`FaithDayStats`) and `total_minutes`
+pub fn day_context(day: &FaithDayStats) -> Value {
+    context! {
+        day => day,
+        total_minutes => day.total_minutes(),
+    }
+}
+
+/// Template context of a week: `week` (`FaithWeekStats`) and `total_minutes`
+pub fn week_context(week: &FaithWeekStats) -> Value {
+    context! {
+        week => week,
+        total_minutes => week.total_minutes(),
+    }
+}
+
+/// Creates an environment that loads templates from the template directory, falling back
+/// to the built-in ones, preferring the translation of the current locale in both
+fn environment() -> Environment<'static> {
+    let dir = TEMPLATE_DIR.read().unwrap().clone();
+    let locale = locale().code();
+
+    let mut env = Environment::new();
+    env.set_undefined_behavior(UndefinedBehavior::Strict);
+    // Minutes are `none` on days before a source's history, which read as no activity
+    env.add_filter("duration", |minutes: Option<f64>| {
+        format_minutes(minutes.unwrap_or(0.0))
+    });
+    env.add_filter("hours", |minutes: Option<f64>| {
+        format_number(minutes.unwrap_or(0.0) / 60.0, 1)
+    });
+    env.set_loader(move |name| {
+        let localized = format!("{}/{}", locale, name);
+        if let Some(dir) = &dir {
+            for path in [dir.join(&localized), dir.join(name)] {
+                if path.is_file() {
+                    return std::fs::read_to_string(&path).map(Some).map_err(|e| {
+                        minijinja::Error::new(
+                            ErrorKind::InvalidOperation,
+                            format!("Failed to read template {:?}", path),
+                        )
+                        .with_source(e)
+                    });
+                }
+            }
+        }
+        Ok(BUILTIN_TEMPLATES
+            .iter()
+            .find(|(builtin, _)| *builtin == localized)
+            .or_else(|| {
+                BUILTIN_TEMPLATES
+                    .iter()
+                    .find(|(builtin, _)| *builtin == name)
+            })
+            .map(|(_, source)| source.to_string()))
+    });
+    env
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_social_weekly() {
+        let week = FaithWeekStats {
+            week_start: "2025-01-05".to_string(),
+            anki_minutes: 72.0,
+            anki_matured_passages: 2,
+            reading_minutes: 120.0,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            render("social_weekly.txt", week_context(&week)).unwrap(),
+            "This week: 3.2 hrs with Scripture and prayer, 2 new passages mature"
+        );
+        assert_eq!(
+            render_str("{{ week.reading_minutes | duration }}", week_context(&week)).unwrap(),
+            "2.0 hours"
+        );
+        assert!(render_str("{{ unknown }}", week_context(&week)).is_err());
+    }
+
+    #[test]
+    fn test_render_day_before_history() {
+        let day = FaithDayStats {
+            date: "2025-01-06".to_string(),
+            reading_minutes: Some(30.0),
+            ..Default::default()
+        };
+
+        let text = render("social_daily.txt", day_context(&day)).unwrap();
+        assert!(text.contains("30 min Bible reading"), "{}", text);
+    }
+}
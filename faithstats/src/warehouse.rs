@@ -0,0 +1,619 @@
+use ankistats::models::DeckSettings;
+use anyhow::{Context, Result};
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::Serialize;
+use statsutils::now_rfc3339;
+
+use crate::models::{FaithDayStats, FaithWeekStats, SourceDataRange};
+
+/// Schema of the warehouse, applied on every open
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS daily_rollups (
+    date TEXT NOT NULL,
+    source TEXT NOT NULL,
+    metric TEXT NOT NULL,
+    value REAL NOT NULL,
+    PRIMARY KEY (date, source, metric)
+);
+
+CREATE INDEX IF NOT EXISTS daily_rollups_by_metric ON daily_rollups (source, metric, date);
+
+CREATE TABLE IF NOT EXISTS rollup_runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    run_at TEXT NOT NULL,
+    first_date TEXT NOT NULL,
+    last_date TEXT NOT NULL,
+    rollups INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS source_history (
+    source TEXT PRIMARY KEY,
+    earliest_date TEXT NOT NULL,
+    checked_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS deck_settings_history (
+    date TEXT PRIMARY KEY,
+    config_name TEXT NOT NULL,
+    new_per_day INTEGER NOT NULL,
+    reviews_per_day INTEGER NOT NULL,
+    maximum_review_interval_days INTEGER NOT NULL,
+    fsrs_enabled INTEGER NOT NULL,
+    desired_retention REAL
+);
+";
+
+/// Warehouse source of each source in `FaithStats::data_ranges()`
+const RANGE_SOURCES: &[(&str, &str)] = &[
+    ("anki", "anki"),
+    ("reading", "koreader"),
+    ("prayer", "proseuche"),
+    ("arc", "arc"),
+];
+
+/// One day's value of a source metric
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Rollup {
+    /// Date in YYYY-MM-DD format
+    pub date: String,
+    /// Source the value was read from: "anki", "koreader", "proseuche" or "arc"
+    pub source: String,
+    /// Name of the metric, e.g. "minutes" or "matured_passages"
+    pub metric: String,
+    pub value: f64,
+}
+
+impl Rollup {
+    fn new(date: &str, source: &str, metric: &str, value: f64) -> Self {
+        Self {
+            date: date.to_string(),
+            source: source.to_string(),
+            metric: metric.to_string(),
+            value,
+        }
+    }
+}
+
+/// Rollups of a day's Anki, KOReader and Proseuche activity
+///
+/// Days before a source's history are recorded as 0 minutes, like days without activity.
+pub fn day_rollups(day: &FaithDayStats) -> Vec<Rollup> {
+    vec![
+        Rollup::new(
+            &day.date,
+            "anki",
+            "minutes",
+            day.anki_minutes.unwrap_or(0.0),
+        ),
+        Rollup::new(&day.date, "anki", "reviews", day.anki_reviews as f64),
+        Rollup::new(
+            &day.date,
+            "anki",
+            "matured_passages",
+            day.anki_matured_passages as f64,
+        ),
+        Rollup::new(
+            &day.date,
+            "anki",
+            "lost_passages",
+            day.anki_lost_passages as f64,
+        ),
+        Rollup::new(
+            &day.date,
+            "anki",
+            "cumulative_passages",
+            day.anki_cumulative_passages as f64,
+        ),
+        Rollup::new(
+            &day.date,
+            "koreader",
+            "minutes",
+            day.reading_minutes.unwrap_or(0.0),
+        ),
+        Rollup::new(
+            &day.date,
+            "proseuche",
+            "minutes",
+            day.prayer_minutes.unwrap_or(0.0),
+        ),
+    ]
+}
+
+/// Rollups of a week's Arc church attendance, which is only tracked per week
+///
+/// The week's total is recorded on its first day as the `church_minutes_week` metric.
+pub fn week_rollups(week: &FaithWeekStats) -> Vec<Rollup> {
+    vec![Rollup::new(
+        &week.week_start,
+        "arc",
+        "church_minutes_week",
+        week.at_church_minutes,
+    )]
+}
+
+/// A source whose recorded history now starts later than it used to, because the app pruned
+/// old data or was reset
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct HistoryRegression {
+    /// Source name (anki, reading, prayer, or arc)
+    pub source: String,
+    /// Earliest date the source ever had, preserved in the warehouse (YYYY-MM-DD)
+    pub preserved_earliest: String,
+    /// Earliest date the source has now, or `None` if it has no data left
+    pub current_earliest: Option<String>,
+    /// Human-readable description
+    pub reason: String,
+}
+
+/// A change of the Bible deck's scheduling settings, detected by a nightly rollup
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DeckSettingsChange {
+    /// Date of the rollup that first saw the new settings, in YYYY-MM-DD format; the change
+    /// happened at most a day earlier
+    pub date: String,
+    /// Settings from that date on
+    pub settings: DeckSettings,
+    /// Human-readable list of what changed, e.g. "new_per_day: 10 → 5"
+    pub changes: Vec<String>,
+}
+
+/// Describes each setting that differs between two snapshots
+fn describe_changes(before: &DeckSettings, after: &DeckSettings) -> Vec<String> {
+    let retention = |settings: &DeckSettings| {
+        settings
+            .desired_retention
+            .map_or("none".to_string(), |retention| retention.to_string())
+    };
+    [
+        (
+            "config_name",
+            before.config_name.clone(),
+            after.config_name.clone(),
+        ),
+        (
+            "new_per_day",
+            before.new_per_day.to_string(),
+            after.new_per_day.to_string(),
+        ),
+        (
+            "reviews_per_day",
+            before.reviews_per_day.to_string(),
+            after.reviews_per_day.to_string(),
+        ),
+        (
+            "maximum_review_interval_days",
+            before.maximum_review_interval_days.to_string(),
+            after.maximum_review_interval_days.to_string(),
+        ),
+        (
+            "fsrs_enabled",
+            before.fsrs_enabled.to_string(),
+            after.fsrs_enabled.to_string(),
+        ),
+        ("desired_retention", retention(before), retention(after)),
+    ]
+    .into_iter()
+    .filter(|(_, before, after)| before != after)
+    .map(|(name, before, after)| format!("{}: {} → {}", name, before, after))
+    .collect()
+}
+
+/// Date (YYYY-MM-DD) of the earliest recorded activity of a source, if it could be read and
+/// has any data
+fn earliest_date(range: &SourceDataRange) -> Option<&str> {
+    range
+        .range
+        .earliest
+        .as_deref()
+        .and_then(|earliest| earliest.get(..10))
+}
+
+/// Whether a rollup lies within the current history of its source
+///
+/// A source that could not be read or has no data covers no dates; sources without a data
+/// range cover every date.
+fn is_covered(rollup: &Rollup, ranges: &[SourceDataRange]) -> bool {
+    let range = RANGE_SOURCES
+        .iter()
+        .find(|(_, source)| *source == rollup.source)
+        .and_then(|(range_source, _)| ranges.iter().find(|r| r.source == *range_source));
+    match range {
+        Some(range) if range.error.is_some() => false,
+        Some(range) => earliest_date(range).is_some_and(|earliest| *rollup.date >= *earliest),
+        None => true,
+    }
+}
+
+/// SQLite database of daily rollups from every source
+///
+/// Rollups are kept after the source apps prune their own history, so the warehouse can
+/// answer multi-year queries without reading the original databases.
+pub struct Warehouse {
+    conn: Connection,
+}
+
+impl Warehouse {
+    /// Opens (or creates) the warehouse at the given path
+    pub fn open(path: &str) -> Result<Self> {
+        let conn =
+            Connection::open(path).context(format!("Failed to open warehouse at {}", path))?;
+        Self::init(conn)
+    }
+
+    /// Opens a temporary in-memory warehouse
+    pub fn open_in_memory() -> Result<Self> {
+        let conn = Connection::open_in_memory().context("Failed to open in-memory warehouse")?;
+        Self::init(conn)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA)
+            .context("Failed to initialize warehouse schema")?;
+        Ok(Self { conn })
+    }
+
+    /// Inserts rollups, replacing earlier values for the same date, source and metric, and
+    /// records the run
+    ///
+    /// Rollups of dates before the current history of their source (see `ranges`) only fill
+    /// in missing values, so history the source has since pruned is preserved rather than
+    /// overwritten with zeros.
+    pub fn upsert_rollups(&mut self, rollups: &[Rollup], ranges: &[SourceDataRange]) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        {
+            let mut replace = tx.prepare_cached(
+                "INSERT OR REPLACE INTO daily_rollups (date, source, metric, value)
+                 VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            let mut fill_in = tx.prepare_cached(
+                "INSERT OR IGNORE INTO daily_rollups (date, source, metric, value)
+                 VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for rollup in rollups {
+                let stmt = if is_covered(rollup, ranges) {
+                    &mut replace
+                } else {
+                    &mut fill_in
+                };
+                stmt.execute(params![
+                    rollup.date,
+                    rollup.source,
+                    rollup.metric,
+                    rollup.value
+                ])
+                .context(format!(
+                    "Failed to store rollup {} {}/{}",
+                    rollup.date, rollup.source, rollup.metric
+                ))?;
+            }
+
+            let first_date = rollups.iter().map(|r| r.date.as_str()).min();
+            let last_date = rollups.iter().map(|r| r.date.as_str()).max();
+            if let (Some(first_date), Some(last_date)) = (first_date, last_date) {
+                tx.execute(
+                    "INSERT INTO rollup_runs (run_at, first_date, last_date, rollups)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    params![now_rfc3339()?, first_date, last_date, rollups.len()],
+                )
+                .context("Failed to record rollup run")?;
+            }
+        }
+        tx.commit().context("Failed to commit rollups")?;
+        Ok(())
+    }
+
+    /// Compares the sources' current data ranges with the earliest dates seen before and
+    /// returns the sources whose history now starts later
+    ///
+    /// The earliest date ever seen is kept per source, so a regression is reported on every
+    /// check until the source's history reaches back as far again. Sources that could not be
+    /// read are skipped.
+    pub fn check_source_history(
+        &mut self,
+        ranges: &[SourceDataRange],
+    ) -> Result<Vec<HistoryRegression>> {
+        let tx = self.conn.transaction()?;
+        let mut regressions = Vec::new();
+        for range in ranges.iter().filter(|range| range.error.is_none()) {
+            let preserved: Option<String> = tx
+                .query_row(
+                    "SELECT earliest_date FROM source_history WHERE source = ?1",
+                    params![range.source],
+                    |row| row.get(0),
+                )
+                .optional()
+                .context(format!("Failed to query history of {}", range.source))?;
+            let current = earliest_date(range);
+
+            match (&preserved, current) {
+                (Some(preserved), Some(current)) if current > preserved.as_str() => {
+                    regressions.push(HistoryRegression {
+                        source: range.source.clone(),
+                        preserved_earliest: preserved.clone(),
+                        current_earliest: Some(current.to_string()),
+                        reason: format!(
+                            "{} history now starts on {} instead of {}; older days are kept in the warehouse",
+                            range.source, current, preserved
+                        ),
+                    });
+                }
+                (Some(preserved), None) => regressions.push(HistoryRegression {
+                    source: range.source.clone(),
+                    preserved_earliest: preserved.clone(),
+                    current_earliest: None,
+                    reason: format!(
+                        "{} has no recorded activity left; its history is kept in the warehouse",
+                        range.source
+                    ),
+                }),
+                _ => {}
+            }
+
+            if let Some(current) = current {
+                tx.execute(
+                    "INSERT INTO source_history (source, earliest_date, checked_at)
+                     VALUES (?1, ?2, ?3)
+                     ON CONFLICT (source) DO UPDATE SET
+                         earliest_date = MIN(earliest_date, excluded.earliest_date),
+                         checked_at = excluded.checked_at",
+                    params![range.source, current, now_rfc3339()?],
+                )
+                .context(format!("Failed to record history of {}", range.source))?;
+            }
+        }
+        tx.commit().context("Failed to commit source history")?;
+        Ok(regressions)
+    }
+
+    /// Records the deck settings seen on a date, unless they equal the latest recorded ones
+    ///
+    /// Only changes are stored, so the history stays small however often it is recorded.
+    /// Returns whether the settings were recorded.
+    pub fn record_deck_settings(&self, date: &str, settings: &DeckSettings) -> Result<bool> {
+        let latest = self.get_deck_settings_history()?;
+        if latest.last().is_some_and(|(_, latest)| latest == settings) {
+            return Ok(false);
+        }
+
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO deck_settings_history (
+                     date, config_name, new_per_day, reviews_per_day,
+                     maximum_review_interval_days, fsrs_enabled, desired_retention
+                 )
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    date,
+                    settings.config_name,
+                    settings.new_per_day,
+                    settings.reviews_per_day,
+                    settings.maximum_review_interval_days,
+                    settings.fsrs_enabled,
+                    settings.desired_retention
+                ],
+            )
+            .context(format!("Failed to record deck settings of {}", date))?;
+        Ok(true)
+    }
+
+    /// Returns every recorded deck settings snapshot, oldest first
+    fn get_deck_settings_history(&self) -> Result<Vec<(String, DeckSettings)>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT date, config_name, new_per_day, reviews_per_day,
+                    maximum_review_interval_days, fsrs_enabled, desired_retention
+             FROM deck_settings_history
+             ORDER BY date",
+        )?;
+
+        let history = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get(0)?,
+                    DeckSettings {
+                        config_name: row.get(1)?,
+                        new_per_day: row.get(2)?,
+                        reviews_per_day: row.get(3)?,
+                        maximum_review_interval_days: row.get(4)?,
+                        fsrs_enabled: row.get(5)?,
+                        desired_retention: row.get(6)?,
+                    },
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()
+            .context("Failed to query deck settings history")?;
+
+        Ok(history)
+    }
+
+    /// Returns the changes of the deck settings, oldest first
+    ///
+    /// The first recorded settings are the baseline rather than a change.
+    pub fn get_deck_settings_changes(&self) -> Result<Vec<DeckSettingsChange>> {
+        let history = self.get_deck_settings_history()?;
+        Ok(history
+            .windows(2)
+            .map(|pair| DeckSettingsChange {
+                date: pair[1].0.clone(),
+                settings: pair[1].1.clone(),
+                changes: describe_changes(&pair[0].1, &pair[1].1),
+            })
+            .collect())
+    }
+
+    /// Returns the latest date rolled up, if any
+    pub fn last_rollup_date(&self) -> Result<Option<String>> {
+        self.conn
+            .query_row("SELECT MAX(last_date) FROM rollup_runs", [], |row| {
+                row.get(0)
+            })
+            .optional()
+            .map(Option::flatten)
+            .context("Failed to query last rollup date")
+    }
+
+    /// Returns rollups between two dates (inclusive), optionally of one source and metric,
+    /// ordered by date, source and metric
+    pub fn get_rollups(
+        &self,
+        first_date: &str,
+        last_date: &str,
+        source: Option<&str>,
+        metric: Option<&str>,
+    ) -> Result<Vec<Rollup>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT date, source, metric, value
+             FROM daily_rollups
+             WHERE date BETWEEN ?1 AND ?2
+               AND (?3 IS NULL OR source = ?3)
+               AND (?4 IS NULL OR metric = ?4)
+             ORDER BY date, source, metric",
+        )?;
+
+        let rollups = stmt
+            .query_map(params![first_date, last_date, source, metric], |row| {
+                Ok(Rollup {
+                    date: row.get(0)?,
+                    source: row.get(1)?,
+                    metric: row.get(2)?,
+                    value: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()
+            .context("Failed to query rollups")?;
+
+        Ok(rollups)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use statsutils::DataRange;
+
+    #[test]
+    fn test_rollups_replace_earlier_values() {
+        let mut warehouse = Warehouse::open_in_memory().unwrap();
+        assert_eq!(warehouse.last_rollup_date().unwrap(), None);
+
+        let day = FaithDayStats {
+            date: "2025-01-06".to_string(),
+            reading_minutes: Some(10.0),
+            ..Default::default()
+        };
+        warehouse.upsert_rollups(&day_rollups(&day), &[]).unwrap();
+        let day = FaithDayStats {
+            reading_minutes: Some(25.0),
+            ..day
+        };
+        warehouse.upsert_rollups(&day_rollups(&day), &[]).unwrap();
+
+        let rollups = warehouse
+            .get_rollups("2025-01-01", "2025-01-31", Some("koreader"), None)
+            .unwrap();
+        assert_eq!(
+            rollups,
+            vec![Rollup::new("2025-01-06", "koreader", "minutes", 25.0)]
+        );
+        assert_eq!(
+            warehouse.last_rollup_date().unwrap().as_deref(),
+            Some("2025-01-06")
+        );
+    }
+
+    #[test]
+    fn test_deck_settings_changes() {
+        let warehouse = Warehouse::open_in_memory().unwrap();
+        let settings = DeckSettings {
+            config_name: "Bible".to_string(),
+            new_per_day: 10,
+            reviews_per_day: 200,
+            maximum_review_interval_days: 36500,
+            fsrs_enabled: false,
+            desired_retention: None,
+        };
+        assert!(
+            warehouse
+                .record_deck_settings("2025-01-01", &settings)
+                .unwrap()
+        );
+        assert!(
+            !warehouse
+                .record_deck_settings("2025-01-02", &settings)
+                .unwrap()
+        );
+        assert!(warehouse.get_deck_settings_changes().unwrap().is_empty());
+
+        let reset = DeckSettings {
+            new_per_day: 20,
+            ..settings.clone()
+        };
+        assert!(
+            warehouse
+                .record_deck_settings("2025-02-10", &reset)
+                .unwrap()
+        );
+        assert!(
+            warehouse
+                .record_deck_settings("2025-03-01", &settings)
+                .unwrap()
+        );
+
+        let changes = warehouse.get_deck_settings_changes().unwrap();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].date, "2025-02-10");
+        assert_eq!(changes[0].changes, vec!["new_per_day: 10 → 20"]);
+        assert_eq!(changes[1].date, "2025-03-01");
+        assert_eq!(changes[1].settings.new_per_day, 10);
+    }
+
+    fn reading_range(earliest: Option<&str>) -> SourceDataRange {
+        SourceDataRange::new(
+            "reading",
+            Ok(DataRange {
+                earliest: earliest.map(|date| format!("{}T08:00:00-05:00", date)),
+                ..Default::default()
+            }),
+        )
+    }
+
+    #[test]
+    fn test_pruned_history_is_preserved_and_reported() {
+        let mut warehouse = Warehouse::open_in_memory().unwrap();
+        let ranges = [reading_range(Some("2024-01-01"))];
+        assert!(warehouse.check_source_history(&ranges).unwrap().is_empty());
+
+        let day = FaithDayStats {
+            date: "2024-06-01".to_string(),
+            reading_minutes: Some(30.0),
+            ..Default::default()
+        };
+        warehouse
+            .upsert_rollups(&day_rollups(&day), &ranges)
+            .unwrap();
+
+        // KOReader pruned everything before 2025
+        let ranges = [reading_range(Some("2025-01-01"))];
+        let regressions = warehouse.check_source_history(&ranges).unwrap();
+        assert_eq!(regressions.len(), 1);
+        assert_eq!(regressions[0].preserved_earliest, "2024-01-01");
+        assert_eq!(
+            regressions[0].current_earliest.as_deref(),
+            Some("2025-01-01")
+        );
+
+        let pruned_day = FaithDayStats {
+            reading_minutes: Some(0.0),
+            ..day
+        };
+        warehouse
+            .upsert_rollups(&day_rollups(&pruned_day), &ranges)
+            .unwrap();
+        let rollups = warehouse
+            .get_rollups("2024-06-01", "2024-06-01", Some("koreader"), None)
+            .unwrap();
+        assert_eq!(rollups[0].value, 30.0);
+    }
+}
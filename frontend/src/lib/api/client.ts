@@ -32,7 +32,7 @@ export type PlaceStats = components['schemas']['PlaceStats'];
  * Get Bible book statistics including Old and New Testament breakdowns.
  */
 export async function getBibleStats(): Promise<BibleStats> {
-	const { data, error, response } = await apiClient.GET('/api/anki/books');
+	const { data, error, response } = await apiClient.GET('/api/v1/anki/books');
 
 	if (error) {
 		throw new Error(`Failed to fetch Bible stats: ${error.error}`);
@@ -47,7 +47,7 @@ export async function getBibleStats(): Promise<BibleStats> {
  * Get today's unified faith statistics, combining Bible reading and Anki memorization.
  */
 export async function getFaithTodayStats(): Promise<FaithTodayStats> {
-	const { data, error, response } = await apiClient.GET('/api/faith/today');
+	const { data, error, response } = await apiClient.GET('/api/v1/faith/today');
 
 	if (error) {
 		throw new Error(`Failed to fetch faith today stats: ${error.error}`);
@@ -62,7 +62,7 @@ export async function getFaithTodayStats(): Promise<FaithTodayStats> {
  * Get unified faith statistics for the last 12 weeks, combining Bible reading and Anki memorization.
  */
 export async function getFaithWeeklyStats(): Promise<FaithWeeklyStats> {
-	const { data, error, response } = await apiClient.GET('/api/faith/weekly');
+	const { data, error, response } = await apiClient.GET('/api/v1/faith/weekly');
 
 	if (error) {
 		throw new Error(`Failed to fetch faith weekly stats: ${error.error}`);
@@ -77,7 +77,7 @@ export async function getFaithWeeklyStats(): Promise<FaithWeeklyStats> {
  * Get unified faith statistics for the last 30 days, combining Bible reading and Anki memorization.
  */
 export async function getFaithDailyStats(): Promise<FaithDailyStats> {
-	const { data, error, response } = await apiClient.GET('/api/faith/daily');
+	const { data, error, response } = await apiClient.GET('/api/v1/faith/daily');
 
 	if (error) {
 		throw new Error(`Failed to fetch faith daily stats: ${error.error}`);
@@ -92,7 +92,7 @@ export async function getFaithDailyStats(): Promise<FaithDailyStats> {
  * Get top 10 places by hours spent over the last 6 months.
  */
 export async function getTopPlaces(): Promise<PlaceStats[]> {
-	const { data, error, response } = await apiClient.GET('/api/arc/top-places');
+	const { data, error, response } = await apiClient.GET('/api/v1/arc/top-places');
 
 	if (error) {
 		throw new Error(`Failed to fetch top places: ${error.error}`);
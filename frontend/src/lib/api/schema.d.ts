@@ -4,7 +4,7 @@
  */
 
 export interface paths {
-    "/api/anki/books": {
+    "/api/v1/anki/books": {
         parameters: {
             query?: never;
             header?: never;
@@ -21,7 +21,7 @@ export interface paths {
         patch?: never;
         trace?: never;
     };
-    "/api/arc/top-places": {
+    "/api/v1/arc/top-places": {
         parameters: {
             query?: never;
             header?: never;
@@ -38,7 +38,7 @@ export interface paths {
         patch?: never;
         trace?: never;
     };
-    "/api/faith/daily": {
+    "/api/v1/faith/daily": {
         parameters: {
             query?: never;
             header?: never;
@@ -55,7 +55,7 @@ export interface paths {
         patch?: never;
         trace?: never;
     };
-    "/api/faith/today": {
+    "/api/v1/faith/today": {
         parameters: {
             query?: never;
             header?: never;
@@ -72,7 +72,7 @@ export interface paths {
         patch?: never;
         trace?: never;
     };
-    "/api/faith/weekly": {
+    "/api/v1/faith/weekly": {
         parameters: {
             query?: never;
             header?: never;
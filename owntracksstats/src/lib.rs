@@ -0,0 +1,22 @@
+//! OwnTracks Recorder Loader
+//!
+//! This library reads the .rec files of an OwnTracks recorder (`rec/<user>/<device>/YYYY-MM.rec`)
+//! and turns region enter/leave transitions into visits in the same `Item`/`Place` model as
+//! arcstats, so the church and place stats work with OwnTracks regions.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use owntracksstats::OwnTracksRecorder;
+//!
+//! let recorder = OwnTracksRecorder::open("/var/spool/owntracks/recorder/store").unwrap();
+//! for visit in recorder.load_visits().unwrap() {
+//!     println!("{}: {} to {}", visit.name, visit.start, visit.end);
+//! }
+//! ```
+
+pub mod loader;
+pub mod models;
+
+pub use loader::{OwnTracksRecorder, RecFile, RegionVisit, convert_visits, visits_from_messages};
+pub use models::{Message, parse_rec_line};
@@ -0,0 +1,371 @@
+use anyhow::{Context, Result, bail};
+use arcstats::models::{BaseItem, Item, ItemVariant, ItemWithPlace, Place, VisitDetails};
+use arcstats::{PlaceProvider, datetime_to_apple_timestamp};
+use chrono::{DateTime, NaiveDate, Utc};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::models::{Message, Transition, Waypoint, parse_rec_line};
+
+/// How deep below the configured path .rec files are searched for
+/// (the recorder stores them as `rec/<user>/<device>/YYYY-MM.rec`)
+const MAX_SEARCH_DEPTH: usize = 4;
+
+/// Source recorded on the converted items and places
+const SOURCE: &str = "OwnTracks";
+
+/// Radius used for regions whose waypoint definition was not recorded
+const DEFAULT_RADIUS_METERS: f64 = 50.0;
+
+/// A monthly .rec file of one device
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecFile {
+    /// Directory of the device the file belongs to
+    pub device: PathBuf,
+    /// Month in YYYY-MM format
+    pub year_month: String,
+    pub path: PathBuf,
+}
+
+/// An OwnTracks recorder storage directory, providing region visits like an Arc Timeline export
+#[derive(Debug, Clone)]
+pub struct OwnTracksRecorder {
+    files: Vec<RecFile>,
+}
+
+/// A completed stay in a region, before it is converted into an Arc item
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegionVisit {
+    /// Region ID, or its name if the app sent none
+    pub region: String,
+    pub name: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub radius: f64,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl OwnTracksRecorder {
+    /// Opens the .rec files at or below the path
+    ///
+    /// The path can be the recorder's storage directory, its `rec` directory, or a user or
+    /// device directory below it.
+    ///
+    /// # Errors
+    /// Returns an error if no .rec files are found
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let mut files = Vec::new();
+        find_rec_files(path, 0, &mut files)?;
+        if files.is_empty() {
+            bail!("No OwnTracks recorder .rec files found at {:?}", path);
+        }
+
+        files.sort_by(|a, b| (&a.device, &a.year_month).cmp(&(&b.device, &b.year_month)));
+        Ok(Self { files })
+    }
+
+    /// Returns whether the path contains OwnTracks recorder .rec files
+    pub fn is_owntracks_storage<P: AsRef<Path>>(path: P) -> bool {
+        let mut files = Vec::new();
+        find_rec_files(path.as_ref(), 0, &mut files).is_ok() && !files.is_empty()
+    }
+
+    /// .rec files, grouped by device and in chronological order within each device
+    pub fn files(&self) -> &[RecFile] {
+        &self.files
+    }
+
+    /// Reads the region visits of every device, sorted by start time
+    pub fn load_visits(&self) -> Result<Vec<RegionVisit>> {
+        let mut visits = Vec::new();
+        for device_files in self.files.chunk_by(|a, b| a.device == b.device) {
+            let mut messages = Vec::new();
+            for file in device_files {
+                let content = fs::read_to_string(&file.path)
+                    .context(format!("Failed to read OwnTracks file: {:?}", file.path))?;
+                messages.extend(content.lines().filter_map(parse_rec_line));
+            }
+            visits.extend(visits_from_messages(messages));
+        }
+
+        visits.sort_by_key(|visit| visit.start);
+        Ok(visits)
+    }
+}
+
+impl PlaceProvider for OwnTracksRecorder {
+    fn name(&self) -> &'static str {
+        "OwnTracks"
+    }
+
+    fn load_items_with_places(&self) -> Result<Vec<ItemWithPlace>> {
+        Ok(convert_visits(self.load_visits()?))
+    }
+
+    /// The most recent .rec file, which the recorder appends to as messages arrive
+    fn modified_path(&self) -> PathBuf {
+        self.files
+            .iter()
+            .max_by(|a, b| a.year_month.cmp(&b.year_month))
+            .map(|file| file.path.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Collects the .rec files below `dir`, up to `MAX_SEARCH_DEPTH` directories deep
+fn find_rec_files(dir: &Path, depth: usize, files: &mut Vec<RecFile>) -> Result<()> {
+    if depth > MAX_SEARCH_DEPTH || !dir.is_dir() {
+        return Ok(());
+    }
+
+    for entry in fs::read_dir(dir).context(format!("Failed to read {:?}", dir))? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_rec_files(&path, depth + 1, files)?;
+        } else if let Some(year_month) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(".rec"))
+        {
+            files.push(RecFile {
+                device: dir.to_path_buf(),
+                year_month: year_month.to_string(),
+                path,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Pairs each region's enter and leave transitions into visits
+///
+/// Messages must be from a single device, in the order they were recorded. A repeated
+/// "enter" keeps the first one; a "leave" without an "enter" (e.g. at the start of the
+/// history) and a region that has not been left yet are ignored.
+pub fn visits_from_messages(messages: Vec<Message>) -> Vec<RegionVisit> {
+    let mut regions: HashMap<String, Waypoint> = HashMap::new();
+    let mut entered: HashMap<String, Transition> = HashMap::new();
+    let mut visits = Vec::new();
+
+    for message in messages {
+        match message {
+            Message::Waypoint(waypoint) => {
+                let key = waypoint
+                    .rid
+                    .clone()
+                    .unwrap_or_else(|| waypoint.desc.clone());
+                regions.insert(key, waypoint);
+            }
+            Message::Transition(transition) => {
+                let key = transition
+                    .rid
+                    .clone()
+                    .unwrap_or_else(|| transition.desc.clone());
+                match transition.event.as_str() {
+                    "enter" => {
+                        entered.entry(key).or_insert(transition);
+                    }
+                    "leave" => {
+                        let Some(enter) = entered.remove(&key) else {
+                            continue;
+                        };
+                        let (Some(start), Some(end)) = (
+                            DateTime::from_timestamp(enter.tst, 0),
+                            DateTime::from_timestamp(transition.tst, 0),
+                        ) else {
+                            continue;
+                        };
+                        if end < start {
+                            continue;
+                        }
+
+                        let region = regions.get(&key);
+                        visits.push(RegionVisit {
+                            name: enter.desc.clone(),
+                            latitude: region.map_or(enter.lat, |region| region.lat),
+                            longitude: region.map_or(enter.lon, |region| region.lon),
+                            radius: region
+                                .and_then(|region| region.rad)
+                                .unwrap_or(DEFAULT_RADIUS_METERS),
+                            region: key,
+                            start,
+                            end,
+                        });
+                    }
+                    _ => {}
+                }
+            }
+            Message::Other => {}
+        }
+    }
+
+    visits
+}
+
+/// Converts region visits into Arc visit items, sharing one `Place` per region
+pub fn convert_visits(visits: Vec<RegionVisit>) -> Vec<ItemWithPlace> {
+    let mut visit_days: HashMap<&str, HashSet<NaiveDate>> = HashMap::new();
+    let mut visit_counts: HashMap<&str, u32> = HashMap::new();
+    let mut last_visits: HashMap<&str, DateTime<Utc>> = HashMap::new();
+    for visit in &visits {
+        visit_days
+            .entry(&visit.region)
+            .or_default()
+            .insert(visit.start.date_naive());
+        *visit_counts.entry(&visit.region).or_default() += 1;
+        let last = last_visits.entry(&visit.region).or_insert(visit.end);
+        *last = (*last).max(visit.end);
+    }
+
+    let mut places: HashMap<&str, Rc<Place>> = HashMap::new();
+    for visit in &visits {
+        places.entry(&visit.region).or_insert_with(|| {
+            Rc::new(Place {
+                id: visit.region.clone(),
+                name: visit.name.clone(),
+                latitude: visit.latitude,
+                longitude: visit.longitude,
+                radius_mean: visit.radius,
+                radius_sd: 0.0,
+                visit_count: visit_counts[visit.region.as_str()],
+                visit_days: visit_days[visit.region.as_str()].len() as u32,
+                last_saved: 0.0,
+                is_stale: false,
+                source: SOURCE.into(),
+                rtree_id: 0,
+                seconds_from_gmt: None,
+                street_address: None,
+                locality: None,
+                country_code: None,
+                google_place_id: None,
+                google_primary_type: None,
+                last_visit_date: Some(datetime_to_apple_timestamp(
+                    last_visits[visit.region.as_str()],
+                )),
+            })
+        });
+    }
+
+    visits
+        .iter()
+        .enumerate()
+        .map(|(index, visit)| {
+            let id: Rc<str> = format!("owntracks-{}", index).into();
+            ItemWithPlace {
+                item: Item {
+                    base: BaseItem {
+                        id: id.clone(),
+                        start_date: datetime_to_apple_timestamp(visit.start),
+                        end_date: datetime_to_apple_timestamp(visit.end),
+                        last_saved: 0.0,
+                        source: SOURCE.into(),
+                        source_version: None,
+                        is_visit: true,
+                        deleted: false,
+                        disabled: false,
+                        samples_changed: None,
+                        step_count: None,
+                        active_energy_burned: None,
+                        max_heart_rate: None,
+                        average_heart_rate: None,
+                        previous_item_id: None,
+                        next_item_id: None,
+                    },
+                    variant: ItemVariant::Visit(VisitDetails {
+                        item_id: id,
+                        place_id: Some(visit.region.as_str().into()),
+                        latitude: visit.latitude,
+                        longitude: visit.longitude,
+                        radius_mean: visit.radius,
+                        radius_sd: 0.0,
+                        // Regions are defined by the user, so the place is never a guess
+                        confirmed_place: true,
+                        uncertain_place: false,
+                        last_saved: 0.0,
+                        street_address: None,
+                    }),
+                },
+                place: places.get(visit.region.as_str()).map(Rc::clone),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two church visits (one spanning a month boundary) and a home visit with no leave yet
+    const JANUARY: &str = concat!(
+        "2024-01-01T00:00:00Z\t*\t{\"_type\":\"waypoint\",\"desc\":\"Grace Church\",\"lat\":38.5,\"lon\":-90.4,\"rad\":120,\"rid\":\"r1\",\"tst\":1700000000}\n",
+        "2024-01-07T15:00:00Z\t*\t{\"_type\":\"transition\",\"event\":\"leave\",\"desc\":\"Home\",\"tst\":1704639600,\"lat\":38.6,\"lon\":-90.5}\n",
+        "2024-01-07T15:10:00Z\t*\t{\"_type\":\"transition\",\"event\":\"enter\",\"desc\":\"Grace Church\",\"rid\":\"r1\",\"tst\":1704640200,\"lat\":38.5001,\"lon\":-90.4001}\n",
+        "2024-01-07T15:11:00Z\t*\t{\"_type\":\"location\",\"lat\":38.5,\"lon\":-90.4,\"tst\":1704640260}\n",
+        "2024-01-07T16:40:00Z\t*\t{\"_type\":\"transition\",\"event\":\"leave\",\"desc\":\"Grace Church\",\"rid\":\"r1\",\"tst\":1704645600,\"lat\":38.5,\"lon\":-90.4}\n",
+        "2024-01-31T23:00:00Z\t*\t{\"_type\":\"transition\",\"event\":\"enter\",\"desc\":\"Grace Church\",\"rid\":\"r1\",\"tst\":1706742000,\"lat\":38.5,\"lon\":-90.4}\n",
+    );
+    const FEBRUARY: &str = concat!(
+        "2024-02-01T01:00:00Z\t*\t{\"_type\":\"transition\",\"event\":\"leave\",\"desc\":\"Grace Church\",\"rid\":\"r1\",\"tst\":1706749200,\"lat\":38.5,\"lon\":-90.4}\n",
+        "2024-02-01T01:30:00Z\t*\t{\"_type\":\"transition\",\"event\":\"enter\",\"desc\":\"Home\",\"tst\":1706751000,\"lat\":38.6,\"lon\":-90.5}\n",
+    );
+
+    fn write_storage() -> tempfile::TempDir {
+        let storage = tempfile::tempdir().unwrap();
+        let device = storage.path().join("rec/alice/phone");
+        fs::create_dir_all(&device).unwrap();
+        fs::write(device.join("2024-01.rec"), JANUARY).unwrap();
+        fs::write(device.join("2024-02.rec"), FEBRUARY).unwrap();
+        storage
+    }
+
+    #[test]
+    fn test_load_visits() {
+        let storage = write_storage();
+        assert!(OwnTracksRecorder::is_owntracks_storage(storage.path()));
+
+        let recorder = OwnTracksRecorder::open(storage.path()).unwrap();
+        let visits = recorder.load_visits().unwrap();
+
+        assert_eq!(visits.len(), 2);
+        assert_eq!(visits[0].name, "Grace Church");
+        assert_eq!(visits[0].latitude, 38.5);
+        assert_eq!(visits[0].radius, 120.0);
+        assert_eq!((visits[0].end - visits[0].start).num_minutes(), 90);
+        assert_eq!((visits[1].end - visits[1].start).num_minutes(), 120);
+
+        assert!(
+            recorder
+                .modified_path()
+                .ends_with("rec/alice/phone/2024-02.rec")
+        );
+    }
+
+    #[test]
+    fn test_convert_visits() {
+        let storage = write_storage();
+        let recorder = OwnTracksRecorder::open(storage.path()).unwrap();
+        let items = recorder.load_items_with_places().unwrap();
+
+        assert_eq!(items.len(), 2);
+        assert!(items.iter().all(|item| item.item.is_visit()));
+        assert_eq!(items[0].item.duration_seconds(), 90.0 * 60.0);
+
+        let church = items[0].place.as_ref().unwrap();
+        assert_eq!(church.name, "Grace Church");
+        assert_eq!(church.visit_count, 2);
+        assert_eq!(church.visit_days, 2);
+        assert!(Rc::ptr_eq(church, items[1].place.as_ref().unwrap()));
+    }
+
+    #[test]
+    fn test_open_without_rec_files() {
+        let empty = tempfile::tempdir().unwrap();
+        assert!(!OwnTracksRecorder::is_owntracks_storage(empty.path()));
+        assert!(OwnTracksRecorder::open(empty.path()).is_err());
+    }
+}
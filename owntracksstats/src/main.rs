@@ -0,0 +1,44 @@
+use clap::Parser;
+use owntracksstats::OwnTracksRecorder;
+use std::process;
+
+#[derive(Parser, Debug)]
+#[command(name = "owntracksstats")]
+#[command(about = "Load region visits from an OwnTracks recorder", long_about = None)]
+struct Args {
+    /// Path to the recorder's storage directory (or a user/device directory below it)
+    storage_path: String,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let recorder = OwnTracksRecorder::open(&args.storage_path).unwrap_or_else(|e| {
+        eprintln!("Error: {:#}", e);
+        process::exit(1);
+    });
+
+    println!("=== Recorder Files ===");
+    for file in recorder.files() {
+        println!("✓ {}", file.path.display());
+    }
+
+    println!("\n=== Region Visits ===");
+    match recorder.load_visits() {
+        Ok(visits) => {
+            for visit in &visits {
+                println!(
+                    "{}  {:>6.0} min  {}",
+                    visit.start.format("%Y-%m-%d %H:%M"),
+                    (visit.end - visit.start).num_seconds() as f64 / 60.0,
+                    visit.name
+                );
+            }
+            println!("\nTotal visits: {}", visits.len());
+        }
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            process::exit(1);
+        }
+    }
+}
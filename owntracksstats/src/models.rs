@@ -0,0 +1,78 @@
+use serde::Deserialize;
+
+/// An OwnTracks message as stored by the recorder
+///
+/// Only the message types needed for visits are parsed; locations, cards, etc. are skipped.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "_type", rename_all = "lowercase")]
+pub enum Message {
+    /// A region (geofence) defined on the device
+    Waypoint(Waypoint),
+    /// The device entered or left a region
+    Transition(Transition),
+    #[serde(other)]
+    Other,
+}
+
+/// A region defined on the device
+#[derive(Debug, Clone, Deserialize)]
+pub struct Waypoint {
+    /// Region name
+    pub desc: String,
+    pub lat: f64,
+    pub lon: f64,
+    /// Radius in meters
+    #[serde(default)]
+    pub rad: Option<f64>,
+    /// Region ID, when the app sends one
+    #[serde(default)]
+    pub rid: Option<String>,
+}
+
+/// Entering or leaving a region
+#[derive(Debug, Clone, Deserialize)]
+pub struct Transition {
+    /// "enter" or "leave"
+    pub event: String,
+    /// Region name
+    pub desc: String,
+    /// Time of the transition in seconds since the Unix epoch
+    pub tst: i64,
+    pub lat: f64,
+    pub lon: f64,
+    /// Region ID, when the app sends one
+    #[serde(default)]
+    pub rid: Option<String>,
+}
+
+/// Parses a .rec line ("<timestamp>\t<topic suffix>\t<JSON payload>")
+///
+/// Returns `None` for lines without a JSON payload and for payloads that are not valid
+/// OwnTracks messages, which the recorder writes for some topics.
+pub fn parse_rec_line(line: &str) -> Option<Message> {
+    let payload = line.splitn(3, '\t').nth(2)?;
+    serde_json::from_str(payload.trim()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rec_line() {
+        let line = "2024-01-07T15:00:05Z\t*                 \t\
+                    {\"_type\":\"transition\",\"event\":\"enter\",\"desc\":\"Church\",\
+                    \"tst\":1704639605,\"lat\":38.5,\"lon\":-90.4,\"acc\":10}";
+        let Some(Message::Transition(transition)) = parse_rec_line(line) else {
+            panic!("expected a transition");
+        };
+        assert_eq!(transition.event, "enter");
+        assert_eq!(transition.desc, "Church");
+        assert_eq!(transition.tst, 1704639605);
+
+        let location =
+            "2024-01-07T15:00:05Z\t*\t{\"_type\":\"location\",\"lat\":38.5,\"lon\":-90.4}";
+        assert!(matches!(parse_rec_line(location), Some(Message::Other)));
+        assert!(parse_rec_line("not a record").is_none());
+    }
+}
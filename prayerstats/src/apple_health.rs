@@ -0,0 +1,193 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration};
+use quick_xml::Reader;
+use quick_xml::events::Event;
+use serde::Deserialize;
+use std::fs::File;
+use std::io::BufReader;
+
+use crate::session_log::SessionLogEntry;
+
+/// HealthKit record type of Mindful Minutes sessions in an Apple Health export
+const MINDFUL_SESSION_TYPE: &str = "HKCategoryTypeIdentifierMindfulSession";
+
+/// Metric name of Mindful Minutes in a Health Auto Export JSON file
+const MINDFUL_MINUTES_METRIC: &str = "mindful_minutes";
+
+/// Timestamp format used by both the Apple Health export and Health Auto Export
+const HEALTH_DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S %z";
+
+/// Reads the Mindful Minutes sessions from an Apple Health `export.xml`
+///
+/// Mindful sessions are what prayer and meditation apps (and the Apple Watch Mindfulness
+/// app) write to HealthKit. The exporting app's name becomes the session category. The file
+/// is streamed since a full export is often hundreds of megabytes.
+pub fn read_health_export(path: &str) -> Result<Vec<SessionLogEntry>> {
+    let file =
+        File::open(path).context(format!("Failed to open Apple Health export '{}'", path))?;
+    let mut reader = Reader::from_reader(BufReader::new(file));
+
+    let mut sessions = Vec::new();
+    let mut buf = Vec::new();
+    loop {
+        let event = reader
+            .read_event_into(&mut buf)
+            .context(format!("Failed to parse Apple Health export '{}'", path))?;
+        match event {
+            Event::Start(element) | Event::Empty(element)
+                if element.name().as_ref() == b"Record" =>
+            {
+                let mut record_type = None;
+                let mut start = None;
+                let mut end = None;
+                let mut source = None;
+                for attribute in element.attributes() {
+                    let attribute =
+                        attribute.context("Invalid attribute in Apple Health export")?;
+                    let value = attribute
+                        .unescape_value()
+                        .context("Invalid attribute in Apple Health export")?
+                        .into_owned();
+                    match attribute.key.as_ref() {
+                        b"type" => record_type = Some(value),
+                        b"startDate" => start = Some(value),
+                        b"endDate" => end = Some(value),
+                        b"sourceName" => source = Some(value),
+                        _ => {}
+                    }
+                }
+
+                if record_type.as_deref() == Some(MINDFUL_SESSION_TYPE)
+                    && let (Some(start), Some(end)) = (start, end)
+                {
+                    sessions.push(SessionLogEntry {
+                        start,
+                        end,
+                        category: source,
+                    });
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    Ok(sessions)
+}
+
+/// A Health Auto Export JSON file (`{"data": {"metrics": [...]}}`)
+#[derive(Debug, Deserialize)]
+pub struct HealthAutoExport {
+    data: HealthAutoExportData,
+}
+
+#[derive(Debug, Deserialize)]
+struct HealthAutoExportData {
+    metrics: Vec<HealthAutoExportMetric>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HealthAutoExportMetric {
+    name: String,
+    #[serde(default)]
+    data: Vec<HealthAutoExportSample>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HealthAutoExportSample {
+    date: String,
+    qty: f64,
+    #[serde(default)]
+    source: Option<String>,
+}
+
+impl HealthAutoExport {
+    /// Converts the Mindful Minutes samples to sessions
+    ///
+    /// Samples only have a start time and a number of minutes, so the end is derived from them.
+    pub fn mindful_sessions(&self) -> Result<Vec<SessionLogEntry>> {
+        self.data
+            .metrics
+            .iter()
+            .filter(|metric| metric.name == MINDFUL_MINUTES_METRIC)
+            .flat_map(|metric| &metric.data)
+            .map(|sample| {
+                let start = DateTime::parse_from_str(&sample.date, HEALTH_DATE_FORMAT)
+                    .context(format!("Could not parse timestamp '{}'", sample.date))?;
+                let end = start + Duration::milliseconds((sample.qty * 60_000.0).round() as i64);
+                Ok(SessionLogEntry {
+                    start: sample.date.clone(),
+                    end: end.format(HEALTH_DATE_FORMAT).to_string(),
+                    category: sample.source.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_read_health_export() {
+        let mut export = tempfile::Builder::new().suffix(".xml").tempfile().unwrap();
+        export
+            .write_all(
+                br#"<?xml version="1.0" encoding="UTF-8"?>
+<HealthData locale="en_US">
+ <Record type="HKQuantityTypeIdentifierStepCount" sourceName="iPhone" unit="count"
+  startDate="2025-01-01 07:00:00 -0600" endDate="2025-01-01 07:10:00 -0600" value="500"/>
+ <Record type="HKCategoryTypeIdentifierMindfulSession" sourceName="Prayer &amp; Meditation"
+  startDate="2025-01-01 07:00:00 -0600" endDate="2025-01-01 07:20:00 -0600">
+  <MetadataEntry key="HKTimeZone" value="America/Chicago"/>
+ </Record>
+ <Record type="HKCategoryTypeIdentifierMindfulSession" sourceName="Apple Watch"
+  startDate="2025-01-02 21:00:00 -0600" endDate="2025-01-02 21:05:00 -0600"/>
+</HealthData>"#,
+            )
+            .unwrap();
+
+        let sessions = read_health_export(export.path().to_str().unwrap()).unwrap();
+
+        assert_eq!(
+            sessions,
+            vec![
+                SessionLogEntry {
+                    start: "2025-01-01 07:00:00 -0600".to_string(),
+                    end: "2025-01-01 07:20:00 -0600".to_string(),
+                    category: Some("Prayer & Meditation".to_string()),
+                },
+                SessionLogEntry {
+                    start: "2025-01-02 21:00:00 -0600".to_string(),
+                    end: "2025-01-02 21:05:00 -0600".to_string(),
+                    category: Some("Apple Watch".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_health_auto_export_mindful_sessions() {
+        let export: HealthAutoExport = serde_json::from_str(
+            r#"{"data": {"metrics": [
+                {"name": "step_count", "units": "count",
+                 "data": [{"date": "2025-01-01 00:00:00 -0600", "qty": 8000}]},
+                {"name": "mindful_minutes", "units": "min",
+                 "data": [{"date": "2025-01-01 07:00:00 -0600", "qty": 12.5, "source": "Proseuche"}]}
+            ]}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            export.mindful_sessions().unwrap(),
+            vec![SessionLogEntry {
+                start: "2025-01-01 07:00:00 -0600".to_string(),
+                end: "2025-01-01 07:12:30 -0600".to_string(),
+                category: Some("Proseuche".to_string()),
+            }]
+        );
+    }
+}
@@ -1,9 +1,105 @@
 use anyhow::{Context, Result};
 use rusqlite::{Connection, OpenFlags};
-use statsutils::{DatePeriod, get_today_start_ms, register_date_functions};
-use std::collections::HashMap;
+use statsutils::{
+    DatePeriod, UnsupportedSchemaError, get_today_start_ms, minutes_by_day, minutes_by_week,
+    register_date_functions,
+};
 
 use crate::models::{DayStats, WeekStats};
+use crate::session_log;
+
+/// Table holding the prayer sessions in every known Proseuche schema version
+const SESSIONS_TABLE: &str = "prayer_sessions";
+
+/// Names used for the session start column, newest first
+const START_COLUMNS: &[&str] = &["started_at", "start_time", "start"];
+
+/// Names used for the session end column, newest first
+const END_COLUMNS: &[&str] = &["ended_at", "end_time", "end"];
+
+/// Column names in a `prayer_sessions` table, detected from its schema
+///
+/// Older Proseuche versions named the timestamp columns differently and had no
+/// `duration_minutes` column, so the queries are built from whatever the database has.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionColumns {
+    /// Column with the datetime the session started
+    pub started_at: &'static str,
+    /// Column with the datetime the session ended
+    pub ended_at: &'static str,
+    /// SQL expression for the session duration in minutes
+    pub duration_minutes: String,
+}
+
+impl SessionColumns {
+    /// Reads the `prayer_sessions` columns and picks the ones the queries use
+    ///
+    /// The duration comes from the `duration_minutes` column when there is one, from
+    /// `duration_seconds` otherwise, and is computed from the start and end times as a last
+    /// resort.
+    ///
+    /// # Errors
+    /// Returns an `UnsupportedSchemaError` if the table or its start/end columns are missing
+    pub fn detect(conn: &Connection) -> Result<Self> {
+        // table_xinfo (unlike table_info) includes generated columns
+        let existing = conn
+            .prepare_cached("SELECT name FROM pragma_table_xinfo(?1)")
+            .context("Failed to read database schema")?
+            .query_map([SESSIONS_TABLE], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<String>, _>>()
+            .context(format!(
+                "Failed to read columns of table '{}'",
+                SESSIONS_TABLE
+            ))?;
+
+        let find = |candidates: &[&'static str]| {
+            candidates.iter().copied().find(|candidate| {
+                existing
+                    .iter()
+                    .any(|name| name.eq_ignore_ascii_case(candidate))
+            })
+        };
+
+        let (started_at, ended_at) = match (find(START_COLUMNS), find(END_COLUMNS)) {
+            (Some(started_at), Some(ended_at)) => (started_at, ended_at),
+            (started_at, ended_at) => {
+                let missing = if existing.is_empty() {
+                    vec![format!("table '{}'", SESSIONS_TABLE)]
+                } else {
+                    [(started_at, START_COLUMNS), (ended_at, END_COLUMNS)]
+                        .into_iter()
+                        .filter(|(found, _)| found.is_none())
+                        .map(|(_, candidates)| {
+                            format!("column '{}.{}'", SESSIONS_TABLE, candidates[0])
+                        })
+                        .collect()
+                };
+                return Err(UnsupportedSchemaError {
+                    database: "Proseuche".to_string(),
+                    missing,
+                }
+                .into());
+            }
+        };
+
+        let duration_minutes = if let Some(column) = find(&["duration_minutes"]) {
+            column.to_string()
+        } else if let Some(column) = find(&["duration_seconds"]) {
+            format!("({} / 60.0)", column)
+        } else {
+            format!(
+                "((julianday({}) - julianday({})) * 1440.0)",
+                ended_at, started_at
+            )
+        };
+
+        Ok(SessionColumns {
+            started_at,
+            ended_at,
+            duration_minutes,
+        })
+    }
+}
 
 /// Opens a connection to a Proseuche database in read-only mode
 ///
@@ -19,6 +115,9 @@ use crate::models::{DayStats, WeekStats};
 /// - ended_at: datetime when prayer session ended
 /// - duration_minutes: computed column with session duration in minutes
 ///
+/// Older schema versions with other column names are detected by `SessionColumns::detect`.
+/// A .csv or .json path is read as a session log instead (see `session_log`).
+///
 /// # Example
 /// ```ignore
 /// use prayerstats::db::open_database;
@@ -26,12 +125,22 @@ use crate::models::{DayStats, WeekStats};
 /// let conn = open_database("/path/to/database.sqlite")?;
 /// ```
 pub fn open_database(path: &str) -> Result<Connection> {
+    // A CSV/JSON session log from another prayer app is loaded into an in-memory database
+    if session_log::is_session_log(path) {
+        let conn = session_log::open_session_log(path)?;
+        register_date_functions(&conn)?;
+        return Ok(conn);
+    }
+
     let conn = Connection::open_with_flags(
         path,
         OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
     )
     .context("Failed to open Proseuche database in read-only mode")?;
 
+    // Fail early with a list of what's missing rather than with an SQL error mid-request
+    SessionColumns::detect(&conn)?;
+
     // Register date functions from statsutils
     register_date_functions(&conn)?;
 
@@ -39,19 +148,32 @@ pub fn open_database(path: &str) -> Result<Connection> {
 }
 
 /// Gets the total prayer time for today in minutes
+///
+/// Only the part after 4 AM of a session started before then counts.
 pub fn get_today_prayer_minutes(conn: &Connection) -> Result<f64> {
     let today_start_ms = get_today_start_ms()?;
     let today_start_sec = today_start_ms / 1000;
 
-    let query = r#"
-        SELECT COALESCE(SUM(duration_minutes), 0) as total_minutes
+    let columns = SessionColumns::detect(conn)?;
+    let query = format!(
+        r#"
+        SELECT COALESCE(SUM(MIN(
+            {duration},
+            {duration} - (?1 - CAST(strftime('%s', {started_at}) AS INTEGER)) / 60.0
+        )), 0) as total_minutes
         FROM prayer_sessions
-        WHERE started_at IS NOT NULL
-            AND ended_at IS NOT NULL
-            AND CAST(strftime('%s', started_at) AS INTEGER) >= ?1
-    "#;
+        WHERE {started_at} IS NOT NULL
+            AND {ended_at} IS NOT NULL
+            AND CAST(strftime('%s', {started_at}) AS INTEGER) + {duration} * 60 > ?1
+    "#,
+        started_at = columns.started_at,
+        ended_at = columns.ended_at,
+        duration = columns.duration_minutes,
+    );
 
-    let total_minutes: f64 = conn.query_row(query, [today_start_sec], |row| row.get(0))?;
+    let total_minutes: f64 = conn
+        .prepare_cached(&query)?
+        .query_row([today_start_sec], |row| row.get(0))?;
 
     Ok(total_minutes)
 }
@@ -64,31 +186,14 @@ pub fn get_today_prayer_minutes(conn: &Connection) -> Result<f64> {
 /// # Returns
 /// Vector of DayStats with date and minutes for each of the last 30 days
 pub fn get_last_30_days_stats(conn: &Connection) -> Result<Vec<DayStats>> {
-    // Get the period data for the last 30 days
-    let period = DatePeriod::last_30_days()?;
-
-    // Convert milliseconds to seconds for SQL query (strftime works with seconds)
-    let start_sec = period.start_ms / 1000;
-    let end_sec = period.end_ms / 1000;
+    get_daily_stats(conn, DatePeriod::last_30_days()?)
+}
 
-    // Query prayer time grouped by date
-    let query = r#"
-        SELECT date_str_from_sec(CAST(strftime('%s', started_at) AS INTEGER)) as date,
-               SUM(duration_minutes) as total_minutes
-        FROM prayer_sessions
-        WHERE started_at IS NOT NULL
-            AND ended_at IS NOT NULL
-            AND CAST(strftime('%s', started_at) AS INTEGER) >= ?1
-            AND CAST(strftime('%s', started_at) AS INTEGER) < ?2
-        GROUP BY date_str_from_sec(CAST(strftime('%s', started_at) AS INTEGER))
-    "#;
-
-    let mut stmt = conn.prepare(query)?;
-    let prayer_results = stmt
-        .query_map([start_sec, end_sec], |row| {
-            Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?))
-        })?
-        .collect::<Result<HashMap<String, f64>, _>>()?;
+/// Gets prayer time for each day in the period
+///
+/// Sessions running past the 4 AM rollover are divided between the two days.
+pub fn get_daily_stats(conn: &Connection, period: DatePeriod) -> Result<Vec<DayStats>> {
+    let prayer_results = minutes_by_day(&get_session_spans(conn, &period)?)?;
 
     let results = period.build_results(prayer_results, |date, total_minutes| DayStats {
         date,
@@ -100,36 +205,153 @@ pub fn get_last_30_days_stats(conn: &Connection) -> Result<Vec<DayStats>> {
 
 /// Gets prayer time for each of the last 12 weeks
 pub fn get_last_12_weeks_stats(conn: &Connection) -> Result<Vec<WeekStats>> {
-    // Get the period data for the last 12 weeks
-    let period = DatePeriod::last_12_weeks()?;
+    get_weekly_stats(conn, DatePeriod::last_12_weeks()?)
+}
 
-    // Convert milliseconds to seconds for SQL query
+/// Gets prayer time for each week in the period
+///
+/// Sessions running past the start of a week are divided between the two weeks.
+pub fn get_weekly_stats(conn: &Connection, period: DatePeriod) -> Result<Vec<WeekStats>> {
+    let prayer_results = minutes_by_week(&get_session_spans(conn, &period)?)?;
+
+    let results = period.build_results(prayer_results, |week_start, total_minutes| WeekStats {
+        week_start,
+        minutes: total_minutes,
+    });
+
+    Ok(results)
+}
+
+/// Gets the (start, duration) in milliseconds of every prayer session overlapping the period
+fn get_session_spans(conn: &Connection, period: &DatePeriod) -> Result<Vec<(i64, i64)>> {
+    // Convert milliseconds to seconds for SQL query (strftime works with seconds)
     let start_sec = period.start_ms / 1000;
     let end_sec = period.end_ms / 1000;
 
-    // Query prayer time grouped by week
-    let query = r#"
-        SELECT week_str_from_sec(CAST(strftime('%s', started_at) AS INTEGER)) as week,
-               SUM(duration_minutes) as total_minutes
+    let columns = SessionColumns::detect(conn)?;
+    let query = format!(
+        r#"
+        SELECT CAST(strftime('%s', {started_at}) AS INTEGER) as start_sec,
+               {duration} as minutes
         FROM prayer_sessions
-        WHERE started_at IS NOT NULL
-            AND ended_at IS NOT NULL
-            AND CAST(strftime('%s', started_at) AS INTEGER) >= ?1
-            AND CAST(strftime('%s', started_at) AS INTEGER) < ?2
-        GROUP BY week_str_from_sec(CAST(strftime('%s', started_at) AS INTEGER))
-    "#;
-
-    let mut stmt = conn.prepare(query)?;
-    let prayer_results = stmt
+        WHERE {started_at} IS NOT NULL
+            AND {ended_at} IS NOT NULL
+            AND CAST(strftime('%s', {started_at}) AS INTEGER) + {duration} * 60 > ?1
+            AND CAST(strftime('%s', {started_at}) AS INTEGER) < ?2
+    "#,
+        started_at = columns.started_at,
+        ended_at = columns.ended_at,
+        duration = columns.duration_minutes,
+    );
+
+    let mut stmt = conn.prepare_cached(&query)?;
+    let spans = stmt
         .query_map([start_sec, end_sec], |row| {
-            Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?))
+            let start_sec: i64 = row.get(0)?;
+            let minutes = row.get::<_, Option<f64>>(1)?.unwrap_or(0.0);
+            Ok((start_sec * 1000, (minutes * 60_000.0).round() as i64))
         })?
-        .collect::<Result<HashMap<String, f64>, _>>()?;
+        .collect::<Result<Vec<(i64, i64)>, _>>()?;
 
-    let results = period.build_results(prayer_results, |week_start, total_minutes| WeekStats {
-        week_start,
-        minutes: total_minutes,
-    });
+    Ok(spans)
+}
 
-    Ok(results)
+/// Gets the first session start and last session end timestamps (seconds)
+pub fn get_prayer_range(conn: &Connection) -> Result<(Option<i64>, Option<i64>)> {
+    let columns = SessionColumns::detect(conn)?;
+    let query = format!(
+        r#"
+        SELECT MIN(CAST(strftime('%s', {started_at}) AS INTEGER)),
+               MAX(CAST(strftime('%s', {ended_at}) AS INTEGER))
+        FROM prayer_sessions
+        WHERE {started_at} IS NOT NULL
+            AND {ended_at} IS NOT NULL
+    "#,
+        started_at = columns.started_at,
+        ended_at = columns.ended_at,
+    );
+
+    let range = conn
+        .prepare_cached(&query)?
+        .query_row([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+    Ok(range)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sessions_db(columns: &str) -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(&format!("CREATE TABLE prayer_sessions ({});", columns))
+            .unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_detect_current_schema() {
+        let conn = sessions_db(
+            "started_at TEXT, ended_at TEXT, duration_minutes REAL GENERATED ALWAYS AS \
+             ((julianday(ended_at) - julianday(started_at)) * 1440) VIRTUAL",
+        );
+
+        let columns = SessionColumns::detect(&conn).unwrap();
+        assert_eq!(columns.started_at, "started_at");
+        assert_eq!(columns.ended_at, "ended_at");
+        assert_eq!(columns.duration_minutes, "duration_minutes");
+    }
+
+    #[test]
+    fn test_detect_older_schema_computes_duration() {
+        let conn = sessions_db("id INTEGER, start_time TEXT, end_time TEXT");
+        conn.execute(
+            "INSERT INTO prayer_sessions VALUES (1, '2025-01-01 08:00:00', '2025-01-01 08:15:00')",
+            [],
+        )
+        .unwrap();
+
+        let columns = SessionColumns::detect(&conn).unwrap();
+        assert_eq!(columns.started_at, "start_time");
+        assert_eq!(columns.ended_at, "end_time");
+
+        let minutes: f64 = conn
+            .query_row(
+                &format!("SELECT {} FROM prayer_sessions", columns.duration_minutes),
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!((minutes - 15.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_session_split_at_rollover() {
+        let conn = sessions_db("started_at TEXT, ended_at TEXT");
+        register_date_functions(&conn).unwrap();
+        // 3:30 to 4:30 AM in America/Chicago
+        conn.execute(
+            "INSERT INTO prayer_sessions VALUES ('2025-01-05 09:30:00', '2025-01-05 10:30:00')",
+            [],
+        )
+        .unwrap();
+
+        let dates = ["2025-01-04", "2025-01-05"].map(String::from);
+        let days = get_daily_stats(&conn, DatePeriod::for_dates(&dates).unwrap()).unwrap();
+        let minutes: Vec<f64> = days.iter().map(|day| day.minutes.round()).collect();
+        assert_eq!(minutes, vec![30.0, 30.0]);
+    }
+
+    #[test]
+    fn test_detect_unsupported_schema() {
+        let conn = sessions_db("started_at TEXT, duration_minutes REAL");
+        let error = SessionColumns::detect(&conn).unwrap_err();
+        let error = error.downcast_ref::<UnsupportedSchemaError>().unwrap();
+        assert_eq!(error.missing, vec!["column 'prayer_sessions.ended_at'"]);
+
+        let conn = Connection::open_in_memory().unwrap();
+        let error = SessionColumns::detect(&conn).unwrap_err();
+        let error = error.downcast_ref::<UnsupportedSchemaError>().unwrap();
+        assert_eq!(error.missing, vec!["table 'prayer_sessions'"]);
+    }
 }
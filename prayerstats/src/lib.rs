@@ -1,14 +1,18 @@
+pub mod apple_health;
 pub mod db;
 pub mod models;
+pub mod session_log;
 
 use anyhow::Result;
+use statsutils::pool::Database;
+use statsutils::{DataRange, DatePeriod};
 
 pub use models::{DayStats, WeekStats};
 
 /// Gets the total prayer time for today in minutes
 ///
 /// # Arguments
-/// * `db_path` - Path to the Proseuche SQLite database file
+/// * `db` - Path to the Proseuche SQLite database file, or a pool of connections to it
 ///
 /// # Returns
 /// Total prayer time in minutes for today
@@ -23,15 +27,15 @@ pub use models::{DayStats, WeekStats};
 /// let minutes = get_today_prayer_time("/path/to/database.sqlite")?;
 /// println!("Prayer time today: {:.1} minutes", minutes);
 /// ```
-pub fn get_today_prayer_time(db_path: &str) -> Result<f64> {
-    let conn = db::open_database(db_path)?;
+pub fn get_today_prayer_time<'a>(db: impl Into<Database<'a>>) -> Result<f64> {
+    let conn = db.into().open(db::open_database)?;
     db::get_today_prayer_minutes(&conn)
 }
 
 /// Gets prayer time for each of the last 30 days
 ///
 /// # Arguments
-/// * `db_path` - Path to the Proseuche SQLite database file
+/// * `db` - Path to the Proseuche SQLite database file, or a pool of connections to it
 ///
 /// # Returns
 /// Vector of DayStats with date and minutes for each of the last 30 days
@@ -48,15 +52,15 @@ pub fn get_today_prayer_time(db_path: &str) -> Result<f64> {
 ///     println!("{}: {:.1} minutes", day.date, day.minutes);
 /// }
 /// ```
-pub fn get_last_30_days_stats(db_path: &str) -> Result<Vec<DayStats>> {
-    let conn = db::open_database(db_path)?;
+pub fn get_last_30_days_stats<'a>(db: impl Into<Database<'a>>) -> Result<Vec<DayStats>> {
+    let conn = db.into().open(db::open_database)?;
     db::get_last_30_days_stats(&conn)
 }
 
 /// Gets prayer time for each of the last 12 weeks
 ///
 /// # Arguments
-/// * `db_path` - Path to the Proseuche SQLite database file
+/// * `db` - Path to the Proseuche SQLite database file, or a pool of connections to it
 ///
 /// # Returns
 /// Vector of WeekStats with week start date and minutes for each of the last 12 weeks
@@ -73,7 +77,83 @@ pub fn get_last_30_days_stats(db_path: &str) -> Result<Vec<DayStats>> {
 ///     println!("{}: {:.1} minutes", week.week_start, week.minutes);
 /// }
 /// ```
-pub fn get_last_12_weeks_stats(db_path: &str) -> Result<Vec<WeekStats>> {
-    let conn = db::open_database(db_path)?;
+pub fn get_last_12_weeks_stats<'a>(db: impl Into<Database<'a>>) -> Result<Vec<WeekStats>> {
+    let conn = db.into().open(db::open_database)?;
     db::get_last_12_weeks_stats(&conn)
 }
+
+/// Gets prayer time for each of the last `days` days
+///
+/// # Arguments
+/// * `db` - Path to the Proseuche SQLite database file, or a pool of connections to it
+/// * `days` - Number of days to include, ending with today
+///
+/// # Returns
+/// Vector of DayStats with date and minutes for each day
+///
+/// # Errors
+/// Returns an error if the database cannot be opened or queried
+pub fn get_last_n_days_stats<'a>(db: impl Into<Database<'a>>, days: i32) -> Result<Vec<DayStats>> {
+    let conn = db.into().open(db::open_database)?;
+    db::get_daily_stats(&conn, DatePeriod::last_n_days(days)?)
+}
+
+/// Gets prayer time for each day of the period
+///
+/// # Arguments
+/// * `db` - Path to the Proseuche SQLite database file, or a pool of connections to it
+/// * `period` - Days to include, e.g. from `DatePeriod::for_dates`
+///
+/// # Returns
+/// Vector of DayStats with date and minutes for each day of the period
+///
+/// # Errors
+/// Returns an error if the database cannot be opened or queried
+pub fn get_days_stats<'a>(
+    db: impl Into<Database<'a>>,
+    period: DatePeriod,
+) -> Result<Vec<DayStats>> {
+    let conn = db.into().open(db::open_database)?;
+    db::get_daily_stats(&conn, period)
+}
+
+/// Gets prayer time for each of the last `weeks` weeks
+///
+/// # Arguments
+/// * `db` - Path to the Proseuche SQLite database file, or a pool of connections to it
+/// * `weeks` - Number of weeks to include, ending with this week
+///
+/// # Returns
+/// Vector of WeekStats with week start date and minutes for each week
+///
+/// # Errors
+/// Returns an error if the database cannot be opened or queried
+pub fn get_last_n_weeks_stats<'a>(
+    db: impl Into<Database<'a>>,
+    weeks: i32,
+) -> Result<Vec<WeekStats>> {
+    let conn = db.into().open(db::open_database)?;
+    db::get_weekly_stats(&conn, DatePeriod::last_n_weeks(weeks)?)
+}
+
+/// Gets the earliest and latest recorded prayer sessions, plus the database file's
+/// last-modified time
+///
+/// # Arguments
+/// * `db` - Path to the Proseuche SQLite database file, or a pool of connections to it
+///
+/// # Returns
+/// DataRange with RFC 3339 timestamps, or `None` fields if no sessions are recorded
+///
+/// # Errors
+/// Returns an error if the database cannot be opened or queried
+pub fn get_data_range<'a>(db: impl Into<Database<'a>>) -> Result<DataRange> {
+    let db = db.into();
+    let conn = db.open(db::open_database)?;
+    let (earliest_sec, latest_sec) = db::get_prayer_range(&conn)?;
+    DataRange::new(
+        earliest_sec.map(|sec| sec * 1000),
+        latest_sec.map(|sec| sec * 1000),
+        db.path(),
+    )
+}
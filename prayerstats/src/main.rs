@@ -1,4 +1,6 @@
 use clap::Parser;
+use statsutils::i18n::tr;
+use std::process;
 
 #[derive(Parser)]
 #[command(name = "prayerstats")]
@@ -15,7 +17,18 @@ struct Cli {
 fn main() {
     let _cli = Cli::parse();
 
-    println!("prayerstats CLI - Coming soon!");
-    println!("\nThis CLI tool will provide commands for analyzing prayer statistics.");
-    println!("Subcommands are not yet implemented.");
+    match statsutils::i18n::locale_from_env() {
+        Ok(locale) => statsutils::i18n::set_locale(locale),
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            process::exit(1);
+        }
+    }
+
+    println!("{}", tr("prayerstats CLI - Coming soon!"));
+    println!(
+        "\n{}",
+        tr("This CLI tool will provide commands for analyzing prayer statistics.")
+    );
+    println!("{}", tr("Subcommands are not yet implemented."));
 }
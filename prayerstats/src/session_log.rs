@@ -0,0 +1,249 @@
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use rusqlite::Connection;
+use serde::Deserialize;
+use statsutils::local_datetime_to_ms;
+use std::fs::File;
+use std::path::Path;
+
+use crate::apple_health::{self, HealthAutoExport};
+
+/// Timestamp format with a numeric UTC offset, as used by Apple Health exports
+const OFFSET_FORMAT: &str = "%Y-%m-%d %H:%M:%S %z";
+
+/// Naive timestamp formats accepted in a session log, interpreted in the configured timezone
+const NAIVE_FORMATS: [&str; 4] = [
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M",
+];
+
+/// A prayer session from a CSV/JSON session log
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct SessionLogEntry {
+    /// Session start, RFC 3339, "YYYY-MM-DD HH:MM:SS +HHMM" or a local "YYYY-MM-DD HH:MM[:SS]"
+    /// datetime
+    pub start: String,
+    /// Session end, in the same formats as `start`
+    pub end: String,
+    /// Optional kind of prayer (e.g. "intercession"); all categories count towards the totals
+    #[serde(default)]
+    pub category: Option<String>,
+}
+
+/// Sessions in a JSON file: either a plain session log or a Health Auto Export file
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonSessions {
+    Log(Vec<SessionLogEntry>),
+    HealthAutoExport(HealthAutoExport),
+}
+
+/// Returns the lowercase extension of the path, if any
+fn extension(path: &str) -> Option<String> {
+    Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_ascii_lowercase())
+}
+
+/// Returns whether the path is a session log (.csv, .json or an Apple Health .xml export)
+/// rather than a Proseuche database
+pub fn is_session_log(path: &str) -> bool {
+    matches!(extension(path).as_deref(), Some("csv" | "json" | "xml"))
+}
+
+/// Reads the sessions from a session log
+///
+/// Supported formats:
+/// - CSV with a `start,end,category` header
+/// - JSON array of `{"start", "end", "category"}` objects
+/// - Health Auto Export JSON (its `mindful_minutes` metric)
+/// - Apple Health `export.xml` (its Mindful Minutes sessions)
+pub fn read_session_log(path: &str) -> Result<Vec<SessionLogEntry>> {
+    if extension(path).as_deref() == Some("xml") {
+        return apple_health::read_health_export(path);
+    }
+
+    let file = File::open(path).context(format!("Failed to open session log '{}'", path))?;
+
+    if extension(path).as_deref() == Some("json") {
+        let sessions = serde_json::from_reader(file)
+            .context(format!("Failed to parse session log '{}'", path))?;
+        match sessions {
+            JsonSessions::Log(sessions) => Ok(sessions),
+            JsonSessions::HealthAutoExport(export) => export.mindful_sessions(),
+        }
+    } else {
+        csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(file)
+            .deserialize()
+            .collect::<Result<Vec<SessionLogEntry>, _>>()
+            .context(format!("Failed to parse session log '{}'", path))
+    }
+}
+
+/// Loads a session log into an in-memory database with the same `prayer_sessions` table as
+/// Proseuche, so the regular queries work on it
+///
+/// Timestamps are stored as UTC "YYYY-MM-DD HH:MM:SS" like Proseuche does; the duration is
+/// computed from them since the log has no `duration_minutes` column.
+pub fn open_session_log(path: &str) -> Result<Connection> {
+    let entries = read_session_log(path)?;
+
+    let conn = Connection::open_in_memory().context("Failed to create in-memory database")?;
+    conn.execute_batch(
+        "CREATE TABLE prayer_sessions (
+            started_at TEXT NOT NULL,
+            ended_at TEXT NOT NULL,
+            category TEXT
+        );",
+    )?;
+
+    let mut stmt = conn.prepare(
+        "INSERT INTO prayer_sessions (started_at, ended_at, category) VALUES (?1, ?2, ?3)",
+    )?;
+    for (index, entry) in entries.iter().enumerate() {
+        let started_at = parse_timestamp(&entry.start).context(format!(
+            "Invalid start of session {} in '{}'",
+            index + 1,
+            path
+        ))?;
+        let ended_at = parse_timestamp(&entry.end).context(format!(
+            "Invalid end of session {} in '{}'",
+            index + 1,
+            path
+        ))?;
+        if ended_at < started_at {
+            bail!(
+                "Session {} in '{}' ends before it starts ({} to {})",
+                index + 1,
+                path,
+                entry.start,
+                entry.end
+            );
+        }
+
+        stmt.execute((
+            started_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+            ended_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+            &entry.category,
+        ))?;
+    }
+    drop(stmt);
+
+    Ok(conn)
+}
+
+/// Parses an RFC 3339 or offset timestamp, or a naive datetime in the configured timezone
+fn parse_timestamp(timestamp: &str) -> Result<DateTime<Utc>> {
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(timestamp)
+        .or_else(|_| DateTime::parse_from_str(timestamp, OFFSET_FORMAT))
+    {
+        return Ok(datetime.with_timezone(&Utc));
+    }
+
+    let naive = NAIVE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(timestamp, format).ok())
+        .context(format!("Could not parse timestamp '{}'", timestamp))?;
+
+    let timestamp_ms = local_datetime_to_ms(naive)?;
+    DateTime::from_timestamp_millis(timestamp_ms).context("Timestamp out of range")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn write_log(extension: &str, contents: &str) -> tempfile::NamedTempFile {
+        let mut file = tempfile::Builder::new()
+            .suffix(extension)
+            .tempfile()
+            .unwrap();
+        file.write_all(contents.as_bytes()).unwrap();
+        file
+    }
+
+    fn session_minutes(conn: &Connection) -> Vec<(String, f64)> {
+        conn.prepare(
+            "SELECT started_at, (julianday(ended_at) - julianday(started_at)) * 1440.0
+             FROM prayer_sessions ORDER BY started_at",
+        )
+        .unwrap()
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_is_session_log() {
+        assert!(is_session_log("/data/prayer.csv"));
+        assert!(is_session_log("/data/prayer.JSON"));
+        assert!(is_session_log("/data/apple_health_export/export.xml"));
+        assert!(!is_session_log("/data/database.sqlite"));
+    }
+
+    #[test]
+    fn test_open_csv_session_log() {
+        let log = write_log(
+            ".csv",
+            "start,end,category\n\
+             2025-01-01 07:00,2025-01-01 07:20,morning\n\
+             2025-01-02T13:00:00Z, 2025-01-02T13:05:30Z,\n",
+        );
+
+        let conn = open_session_log(log.path().to_str().unwrap()).unwrap();
+        let sessions = session_minutes(&conn);
+
+        // 07:00 in America/Chicago is 13:00 UTC
+        assert_eq!(sessions[0].0, "2025-01-01 13:00:00");
+        assert!((sessions[0].1 - 20.0).abs() < 0.001);
+        assert_eq!(sessions[1].0, "2025-01-02 13:00:00");
+        assert!((sessions[1].1 - 5.5).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_open_json_session_log() {
+        let log = write_log(
+            ".json",
+            r#"[{"start": "2025-03-01T08:00:00-06:00", "end": "2025-03-01T08:10:00-06:00",
+                 "category": "intercession"},
+                {"start": "2025-03-02 08:00", "end": "2025-03-02 08:15"}]"#,
+        );
+
+        let conn = open_session_log(log.path().to_str().unwrap()).unwrap();
+        let sessions = session_minutes(&conn);
+
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].0, "2025-03-01 14:00:00");
+        assert!((sessions[1].1 - 15.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_open_health_auto_export() {
+        let log = write_log(
+            ".json",
+            r#"{"data": {"metrics": [{"name": "mindful_minutes", "units": "min",
+                "data": [{"date": "2025-03-01 08:00:00 -0600", "qty": 10}]}]}}"#,
+        );
+
+        let conn = open_session_log(log.path().to_str().unwrap()).unwrap();
+        let sessions = session_minutes(&conn);
+
+        assert_eq!(sessions[0].0, "2025-03-01 14:00:00");
+        assert!((sessions[0].1 - 10.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_session_ending_before_start() {
+        let log = write_log(".csv", "start,end\n2025-01-01 08:00,2025-01-01 07:00\n");
+
+        let error = open_session_log(log.path().to_str().unwrap()).unwrap_err();
+        assert!(error.to_string().contains("ends before it starts"));
+    }
+}
@@ -0,0 +1,107 @@
+/// Fraction of a book's pages after which a page view counts as having reached the end
+pub const END_FRACTION: f64 = 0.98;
+
+/// Fraction of a book's pages before which a page view counts as reading from the start
+pub const START_FRACTION: f64 = 0.10;
+
+/// A page view with its position in the book, from KOReader's `page_stat_data` table
+#[derive(Debug, Clone, Copy)]
+pub struct PagePosition {
+    pub book_id: i64,
+    pub page: i64,
+    /// Number of pages of the book when the page was viewed (changes with the layout)
+    pub total_pages: i64,
+    /// Start time in Unix seconds
+    pub start_time: i64,
+}
+
+impl PagePosition {
+    fn is_near_end(&self) -> bool {
+        self.page as f64 >= self.total_pages as f64 * END_FRACTION
+    }
+
+    fn is_near_start(&self) -> bool {
+        (self.page as f64) < self.total_pages as f64 * START_FRACTION
+    }
+}
+
+/// Finds when books were finished, as (book id, Unix seconds) pairs in chronological order
+///
+/// `positions` must be sorted by book and then start time. A book is finished the first time
+/// one of its last pages is viewed; it can only be finished again after it has been read from
+/// the start once more, so paging back and forth near the end isn't counted twice but
+/// re-reading the whole book is.
+pub fn find_completions(positions: &[PagePosition]) -> Vec<(i64, i64)> {
+    let mut completions = Vec::new();
+    let mut current_book = None;
+    let mut can_finish = true;
+
+    for position in positions.iter().filter(|p| p.total_pages > 0) {
+        if current_book != Some(position.book_id) {
+            current_book = Some(position.book_id);
+            can_finish = true;
+        }
+
+        if position.is_near_end() {
+            if can_finish {
+                completions.push((position.book_id, position.start_time));
+                can_finish = false;
+            }
+        } else if position.is_near_start() {
+            can_finish = true;
+        }
+    }
+
+    completions.sort_by_key(|&(_, finished_at)| finished_at);
+    completions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(book_id: i64, page: i64, start_time: i64) -> PagePosition {
+        PagePosition {
+            book_id,
+            page,
+            total_pages: 100,
+            start_time,
+        }
+    }
+
+    #[test]
+    fn test_find_completions() {
+        let positions = vec![
+            // Book 1: finished, paged around at the end, then read again from the start
+            position(1, 1, 100),
+            position(1, 99, 200),
+            position(1, 100, 300),
+            position(1, 98, 400),
+            position(1, 2, 500),
+            position(1, 100, 600),
+            // Book 2: never reaches the end
+            position(2, 1, 150),
+            position(2, 90, 250),
+            // Book 3: statistics start in the middle, finished once
+            position(3, 50, 50),
+            position(3, 100, 550),
+        ];
+
+        assert_eq!(
+            find_completions(&positions),
+            vec![(1, 200), (3, 550), (1, 600)]
+        );
+    }
+
+    #[test]
+    fn test_find_completions_ignores_unknown_page_counts() {
+        let positions = vec![PagePosition {
+            book_id: 1,
+            page: 0,
+            total_pages: 0,
+            start_time: 100,
+        }];
+
+        assert!(find_completions(&positions).is_empty());
+    }
+}
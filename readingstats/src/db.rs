@@ -1,9 +1,23 @@
 use anyhow::{Context, Result};
 use rusqlite::{Connection, OpenFlags};
-use statsutils::{DatePeriod, get_today_start_ms, register_date_functions};
+use statsutils::{
+    DatePeriod, check_schema, get_today_start_ms, minutes_by_day, minutes_by_week,
+    register_date_functions,
+};
 use std::collections::HashMap;
 
-use crate::models::{DayStats, WeekStats};
+use crate::completions::{END_FRACTION, PagePosition, START_FRACTION};
+use crate::models::{DailyOfficeDay, DayStats, ReadBook, WeekStats};
+use crate::patterns::PageView;
+
+/// Tables and columns the queries rely on, checked when opening a database
+const EXPECTED_SCHEMA: &[(&str, &[&str])] = &[
+    (
+        "page_stat_data",
+        &["id_book", "page", "start_time", "duration", "total_pages"],
+    ),
+    ("book", &["id", "title", "authors"]),
+];
 
 /// Opens a connection to a KOReader statistics database in read-only mode
 ///
@@ -26,6 +40,9 @@ pub fn open_database(path: &str) -> Result<Connection> {
     )
     .context("Failed to open KOReader statistics database in read-only mode")?;
 
+    // Fail early with a list of what's missing rather than with an SQL error mid-request
+    check_schema(&conn, "KOReader", EXPECTED_SCHEMA)?;
+
     // Register date functions from statsutils
     register_date_functions(&conn)?;
 
@@ -40,53 +57,65 @@ pub fn open_database(path: &str) -> Result<Connection> {
 /// # Returns
 /// Vector of DayStats with date and minutes for each of the last 30 days
 pub fn get_last_30_days_stats(conn: &Connection) -> Result<Vec<DayStats>> {
-    // Get the period data for the last 30 days
-    let period = DatePeriod::last_30_days()?;
+    get_daily_stats(conn, DatePeriod::last_30_days()?)
+}
+
+/// Gets reading time for each day in the period for Bible and Treasury of Daily Prayer books
+///
+/// Page views running past the 4 AM rollover are divided between the two days.
+pub fn get_daily_stats(conn: &Connection, period: DatePeriod) -> Result<Vec<DayStats>> {
+    let reading_results = minutes_by_day(&get_reading_spans(conn, &period)?)?;
+
+    let results = period.build_results(reading_results, |date, minutes| DayStats { date, minutes });
 
+    Ok(results)
+}
+
+/// Gets the (start, duration) in milliseconds of every Bible and Treasury of Daily Prayer
+/// page view overlapping the period
+fn get_reading_spans(conn: &Connection, period: &DatePeriod) -> Result<Vec<(i64, i64)>> {
     // Convert milliseconds to seconds for KOReader database (uses Unix seconds)
     let start_sec = period.start_ms / 1000;
     let end_sec = period.end_ms / 1000;
 
-    // Query reading time grouped by date
     let query = r#"
-        SELECT date_str_from_sec(psd.start_time) as date, SUM(psd.duration) as total_seconds
+        SELECT psd.start_time, psd.duration
         FROM page_stat_data psd
         JOIN book b ON b.id = psd.id_book
         WHERE (b.title LIKE '%Bible%' OR b.title LIKE 'Treasury of Daily Prayer%')
-            AND psd.start_time >= ?1
+            AND psd.start_time + psd.duration > ?1
             AND psd.start_time < ?2
-        GROUP BY date_str_from_sec(psd.start_time)
     "#;
 
-    let mut stmt = conn.prepare(query)?;
-    let reading_results = stmt
+    let mut stmt = conn.prepare_cached(query)?;
+    let spans = stmt
         .query_map([start_sec, end_sec], |row| {
-            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+            Ok((row.get::<_, i64>(0)? * 1000, row.get::<_, i64>(1)? * 1000))
         })?
-        .collect::<Result<HashMap<String, i64>, _>>()?;
-
-    let results = period.build_results(reading_results, |date, total_seconds| DayStats {
-        date,
-        minutes: total_seconds as f64 / 60.0,
-    });
+        .collect::<Result<Vec<(i64, i64)>, _>>()?;
 
-    Ok(results)
+    Ok(spans)
 }
 
 /// Gets the total reading time for today in minutes
+///
+/// Only the part after 4 AM of a page view started before then counts.
 pub fn get_today_reading_minutes(conn: &Connection) -> Result<f64> {
     let today_start_ms = get_today_start_ms()?;
     let today_start_sec = today_start_ms / 1000;
 
     let query = r#"
-        SELECT COALESCE(SUM(psd.duration), 0) as total_seconds
+        SELECT COALESCE(SUM(MIN(psd.duration, psd.start_time + psd.duration - ?1)), 0)
+            as total_seconds
         FROM page_stat_data psd
         JOIN book b ON b.id = psd.id_book
         WHERE (b.title LIKE '%Bible%' OR b.title LIKE 'Treasury of Daily Prayer%')
-            AND psd.start_time >= ?1
+            AND psd.start_time + psd.duration > ?1
     "#;
 
-    let total_seconds: i64 = conn.query_row(query, [today_start_sec], |row| row.get(0))?;
+    let total_seconds: i64 = conn
+        .prepare_cached(query)?
+        .query_row([today_start_sec], |row| row.get(0))?;
 
     // Convert seconds to minutes
     Ok(total_seconds as f64 / 60.0)
@@ -94,35 +123,216 @@ pub fn get_today_reading_minutes(conn: &Connection) -> Result<f64> {
 
 /// Gets reading time for each of the last 12 weeks
 pub fn get_last_12_weeks_stats(conn: &Connection) -> Result<Vec<WeekStats>> {
-    // Get the period data for the last 12 weeks
-    let period = DatePeriod::last_12_weeks()?;
+    get_weekly_stats(conn, DatePeriod::last_12_weeks()?)
+}
 
-    // Convert milliseconds to seconds for KOReader database (uses Unix seconds)
+/// Gets reading time for each week in the period
+///
+/// Page views running past the start of a week are divided between the two weeks.
+pub fn get_weekly_stats(conn: &Connection, period: DatePeriod) -> Result<Vec<WeekStats>> {
+    let reading_results = minutes_by_week(&get_reading_spans(conn, &period)?)?;
+
+    let results = period.build_results(reading_results, |week_start, minutes| WeekStats {
+        week_start,
+        minutes,
+    });
+
+    Ok(results)
+}
+
+/// Gets whether the Treasury of Daily Prayer was opened on each day in the period
+///
+/// Any page view in a book whose title starts with "Treasury of Daily Prayer" counts, no
+/// matter how short.
+pub fn get_daily_office_days(conn: &Connection, period: DatePeriod) -> Result<Vec<DailyOfficeDay>> {
     let start_sec = period.start_ms / 1000;
     let end_sec = period.end_ms / 1000;
 
-    // Query reading time grouped by week
     let query = r#"
-        SELECT week_str_from_sec(psd.start_time) as week, SUM(psd.duration) as total_seconds
+        SELECT date_str_from_sec(psd.start_time) as date, COUNT(*) as page_views
         FROM page_stat_data psd
         JOIN book b ON b.id = psd.id_book
-        WHERE (b.title LIKE '%Bible%' OR b.title LIKE 'Treasury of Daily Prayer%')
+        WHERE b.title LIKE 'Treasury of Daily Prayer%'
             AND psd.start_time >= ?1
             AND psd.start_time < ?2
-        GROUP BY week_str_from_sec(psd.start_time)
+        GROUP BY date_str_from_sec(psd.start_time)
     "#;
 
-    let mut stmt = conn.prepare(query)?;
-    let reading_results = stmt
+    let mut stmt = conn.prepare_cached(query)?;
+    let page_views = stmt
         .query_map([start_sec, end_sec], |row| {
             Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
         })?
         .collect::<Result<HashMap<String, i64>, _>>()?;
 
-    let results = period.build_results(reading_results, |week_start, total_seconds| WeekStats {
-        week_start,
-        minutes: total_seconds as f64 / 60.0,
+    let results = period.build_results(page_views, |date, page_views| DailyOfficeDay {
+        date,
+        opened: page_views > 0,
     });
 
     Ok(results)
 }
+
+/// Gets the page views of Bible and Treasury of Daily Prayer books in the period, sorted by
+/// start time
+pub fn get_page_views(conn: &Connection, period: &DatePeriod) -> Result<Vec<PageView>> {
+    let start_sec = period.start_ms / 1000;
+    let end_sec = period.end_ms / 1000;
+
+    let query = r#"
+        SELECT psd.id_book, psd.start_time, psd.duration
+        FROM page_stat_data psd
+        JOIN book b ON b.id = psd.id_book
+        WHERE (b.title LIKE '%Bible%' OR b.title LIKE 'Treasury of Daily Prayer%')
+            AND psd.start_time >= ?1
+            AND psd.start_time < ?2
+        ORDER BY psd.start_time
+    "#;
+
+    let mut stmt = conn.prepare_cached(query)?;
+    let views = stmt
+        .query_map([start_sec, end_sec], |row| {
+            Ok(PageView {
+                book_id: row.get(0)?,
+                start_time: row.get(1)?,
+                duration: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(views)
+}
+
+/// Gets the page views near the start or end of any book, sorted by book and start time
+///
+/// Only these page views matter for detecting completed books, so the rest of the (large)
+/// table is skipped.
+pub fn get_completion_positions(conn: &Connection) -> Result<Vec<PagePosition>> {
+    let query = r#"
+        SELECT psd.id_book, psd.page, psd.total_pages, psd.start_time
+        FROM page_stat_data psd
+        WHERE psd.total_pages > 0
+            AND (psd.page >= psd.total_pages * ?1 OR psd.page < psd.total_pages * ?2)
+        ORDER BY psd.id_book, psd.start_time
+    "#;
+
+    let mut stmt = conn.prepare_cached(query)?;
+    let positions = stmt
+        .query_map([END_FRACTION, START_FRACTION], |row| {
+            Ok(PagePosition {
+                book_id: row.get(0)?,
+                page: row.get(1)?,
+                total_pages: row.get(2)?,
+                start_time: row.get(3)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(positions)
+}
+
+/// Gets the title and authors of a book
+pub fn get_book_title(conn: &Connection, book_id: i64) -> Result<(String, Option<String>)> {
+    let query = "SELECT title, authors FROM book WHERE id = ?1";
+
+    conn.prepare_cached(query)?
+        .query_row([book_id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .context(format!("Failed to get title of book {}", book_id))
+}
+
+/// Gets every book with recorded page views and its total reading time, most recently read
+/// first
+pub fn get_books(conn: &Connection) -> Result<Vec<ReadBook>> {
+    let query = r#"
+        SELECT b.title, b.authors, SUM(psd.duration) / 60.0,
+            date_str_from_sec(MAX(psd.start_time))
+        FROM book b
+        JOIN page_stat_data psd ON psd.id_book = b.id
+        GROUP BY b.id
+        ORDER BY MAX(psd.start_time) DESC
+    "#;
+
+    let books = conn
+        .prepare_cached(query)?
+        .query_map([], |row| {
+            Ok(ReadBook {
+                title: row.get(0)?,
+                authors: row.get(1)?,
+                minutes: (row.get::<_, f64>(2)? * 10.0).round() / 10.0,
+                last_read_date: row.get(3)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to query books")?;
+
+    Ok(books)
+}
+
+/// Gets the first and last reading timestamps (seconds) for Bible and Treasury of Daily Prayer books
+///
+/// The latest timestamp is the end of the last recorded page view.
+pub fn get_reading_range(conn: &Connection) -> Result<(Option<i64>, Option<i64>)> {
+    let query = r#"
+        SELECT MIN(psd.start_time), MAX(psd.start_time + psd.duration)
+        FROM page_stat_data psd
+        JOIN book b ON b.id = psd.id_book
+        WHERE (b.title LIKE '%Bible%' OR b.title LIKE 'Treasury of Daily Prayer%')
+    "#;
+
+    let range = conn
+        .prepare_cached(query)?
+        .query_row([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+    Ok(range)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// In-memory database with the tables the queries use
+    fn database() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE book (id INTEGER PRIMARY KEY, title TEXT, authors TEXT);
+             CREATE TABLE page_stat_data (
+                 id_book INTEGER, page INTEGER, start_time INTEGER, duration INTEGER,
+                 total_pages INTEGER
+             );",
+        )
+        .unwrap();
+        register_date_functions(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_get_books() {
+        let conn = database();
+        conn.execute_batch(
+            "INSERT INTO book VALUES
+                 (1, 'The Holy Bible (ESV)', 'Crossway'),
+                 (2, 'Orthodoxy', NULL),
+                 (3, 'Never Opened', 'Nobody');
+             -- 2025-01-10 and 2025-02-01, both 18:00 UTC
+             INSERT INTO page_stat_data VALUES
+                 (1, 1, 1736532000, 600, 1900),
+                 (1, 2, 1736532600, 130, 1900),
+                 (2, 1, 1738432800, 90, 170);",
+        )
+        .unwrap();
+
+        let books = get_books(&conn).unwrap();
+
+        // Books without page views are left out, the most recently read comes first
+        assert_eq!(books.len(), 2);
+        assert_eq!(books[0].title, "Orthodoxy");
+        assert_eq!(books[0].authors, None);
+        assert_eq!(books[0].minutes, 1.5);
+        assert_eq!(books[0].last_read_date, "2025-02-01");
+        assert_eq!(books[1].title, "The Holy Bible (ESV)");
+        assert_eq!(books[1].authors.as_deref(), Some("Crossway"));
+        // 730 seconds, rounded to a tenth of a minute
+        assert_eq!(books[1].minutes, 12.2);
+        assert_eq!(books[1].last_read_date, "2025-01-10");
+    }
+}
@@ -1,13 +1,19 @@
+pub mod completions;
 pub mod db;
 pub mod models;
+pub mod patterns;
 
-use crate::models::{DayStats, WeekStats};
+use crate::models::{
+    CompletedBook, DailyOfficeStats, DayStats, ReadBook, ReadingPatterns, WeekStats,
+};
 use anyhow::Result;
+use statsutils::pool::Database;
+use statsutils::{DataRange, DatePeriod, timestamp_ms_to_date_string, timestamp_ms_to_rfc3339};
 
 /// Gets reading time for each of the last 30 days for Bible and Treasury of Daily Prayer books
 ///
 /// # Arguments
-/// * `db_path` - Path to the KOReader statistics.sqlite3 database file
+/// * `db` - Path to the KOReader statistics.sqlite3 database file, or a pool of connections to it
 ///
 /// # Returns
 /// Vector of DayStats with date and minutes for each of the last 30 days
@@ -21,15 +27,15 @@ use anyhow::Result;
 ///     println!("{}: {:.2} minutes", day.date, day.minutes);
 /// }
 /// ```
-pub fn get_last_30_days_stats(db_path: &str) -> Result<Vec<DayStats>> {
-    let conn = db::open_database(db_path)?;
+pub fn get_last_30_days_stats<'a>(db: impl Into<Database<'a>>) -> Result<Vec<DayStats>> {
+    let conn = db.into().open(db::open_database)?;
     db::get_last_30_days_stats(&conn)
 }
 
 /// Gets the total reading time for today in minutes
 ///
 /// # Arguments
-/// * `db_path` - Path to the KOReader statistics.sqlite3 database file
+/// * `db` - Path to the KOReader statistics.sqlite3 database file, or a pool of connections to it
 ///
 /// # Returns
 /// Total reading time in minutes for today
@@ -41,15 +47,15 @@ pub fn get_last_30_days_stats(db_path: &str) -> Result<Vec<DayStats>> {
 /// let minutes = get_today_reading_time("/path/to/statistics.sqlite3")?;
 /// println!("Today's reading time: {:.2} minutes", minutes);
 /// ```
-pub fn get_today_reading_time(db_path: &str) -> Result<f64> {
-    let conn = db::open_database(db_path)?;
+pub fn get_today_reading_time<'a>(db: impl Into<Database<'a>>) -> Result<f64> {
+    let conn = db.into().open(db::open_database)?;
     db::get_today_reading_minutes(&conn)
 }
 
 /// Gets reading time for each of the last 12 weeks for Bible and Treasury of Daily Prayer books
 ///
 /// # Arguments
-/// * `db_path` - Path to the KOReader statistics.sqlite3 database file
+/// * `db` - Path to the KOReader statistics.sqlite3 database file, or a pool of connections to it
 ///
 /// # Returns
 /// Vector of WeekStats with week_start and minutes for each of the last 12 weeks
@@ -63,7 +69,152 @@ pub fn get_today_reading_time(db_path: &str) -> Result<f64> {
 ///     println!("{}: {:.2} minutes", week.week_start, week.minutes);
 /// }
 /// ```
-pub fn get_last_12_weeks_stats(db_path: &str) -> Result<Vec<WeekStats>> {
-    let conn = db::open_database(db_path)?;
+pub fn get_last_12_weeks_stats<'a>(db: impl Into<Database<'a>>) -> Result<Vec<WeekStats>> {
+    let conn = db.into().open(db::open_database)?;
     db::get_last_12_weeks_stats(&conn)
 }
+
+/// Gets reading time for each of the last `days` days for Bible and Treasury of Daily Prayer books
+///
+/// # Arguments
+/// * `db` - Path to the KOReader statistics.sqlite3 database file, or a pool of connections to it
+/// * `days` - Number of days to include, ending with today
+///
+/// # Returns
+/// Vector of DayStats with date and minutes for each day
+pub fn get_last_n_days_stats<'a>(db: impl Into<Database<'a>>, days: i32) -> Result<Vec<DayStats>> {
+    let conn = db.into().open(db::open_database)?;
+    db::get_daily_stats(&conn, DatePeriod::last_n_days(days)?)
+}
+
+/// Gets reading time for each day of the period for Bible and Treasury of Daily Prayer books
+///
+/// # Arguments
+/// * `db` - Path to the KOReader statistics.sqlite3 database file, or a pool of connections to it
+/// * `period` - Days to include, e.g. from `DatePeriod::for_dates`
+///
+/// # Returns
+/// Vector of DayStats with date and minutes for each day of the period
+pub fn get_days_stats<'a>(
+    db: impl Into<Database<'a>>,
+    period: DatePeriod,
+) -> Result<Vec<DayStats>> {
+    let conn = db.into().open(db::open_database)?;
+    db::get_daily_stats(&conn, period)
+}
+
+/// Gets reading time for each of the last `weeks` weeks for Bible and Treasury of Daily Prayer books
+///
+/// # Arguments
+/// * `db` - Path to the KOReader statistics.sqlite3 database file, or a pool of connections to it
+/// * `weeks` - Number of weeks to include, ending with this week
+///
+/// # Returns
+/// Vector of WeekStats with week_start and minutes for each week
+pub fn get_last_n_weeks_stats<'a>(
+    db: impl Into<Database<'a>>,
+    weeks: i32,
+) -> Result<Vec<WeekStats>> {
+    let conn = db.into().open(db::open_database)?;
+    db::get_weekly_stats(&conn, DatePeriod::last_n_weeks(weeks)?)
+}
+
+/// Gets whether the Treasury of Daily Prayer was opened on each of the last `days` days,
+/// with the current and longest streaks
+///
+/// # Arguments
+/// * `db` - Path to the KOReader statistics.sqlite3 database file, or a pool of connections to it
+/// * `days` - Number of days to include, ending with today (streaks cannot be longer)
+///
+/// # Returns
+/// DailyOfficeStats with the per-day series, days opened and streaks
+pub fn get_daily_office_stats<'a>(
+    db: impl Into<Database<'a>>,
+    days: i32,
+) -> Result<DailyOfficeStats> {
+    let conn = db.into().open(db::open_database)?;
+    let days = db::get_daily_office_days(&conn, DatePeriod::last_n_days(days)?)?;
+    Ok(DailyOfficeStats::new(days))
+}
+
+/// Gets when reading happens over the last `days` days, as hour-of-day and weekday histograms
+///
+/// # Arguments
+/// * `db` - Path to the KOReader statistics.sqlite3 database file, or a pool of connections to it
+/// * `days` - Number of days to include, ending with today
+///
+/// # Returns
+/// ReadingPatterns with reading minutes and session counts per hour and per weekday
+pub fn get_reading_patterns<'a>(db: impl Into<Database<'a>>, days: i32) -> Result<ReadingPatterns> {
+    let conn = db.into().open(db::open_database)?;
+    let views = db::get_page_views(&conn, &DatePeriod::last_n_days(days)?)?;
+    patterns::reading_patterns(&views, days)
+}
+
+/// Gets the books that were read to the end, in the order they were finished
+///
+/// KOReader's statistics database doesn't store whether a book is finished, so a book counts
+/// as finished when one of its last pages is viewed (see `completions::find_completions`).
+///
+/// # Arguments
+/// * `db` - Path to the KOReader statistics.sqlite3 database file, or a pool of connections to it
+/// * `year` - Only include books finished in this year, if given
+///
+/// # Returns
+/// Vector of CompletedBook, oldest first; a re-read book appears once per reading
+pub fn get_completed_books<'a>(
+    db: impl Into<Database<'a>>,
+    year: Option<i32>,
+) -> Result<Vec<CompletedBook>> {
+    let conn = db.into().open(db::open_database)?;
+    let positions = db::get_completion_positions(&conn)?;
+
+    let mut books = Vec::new();
+    for (book_id, finished_sec) in completions::find_completions(&positions) {
+        let date = timestamp_ms_to_date_string(finished_sec * 1000)?;
+        if year.is_some_and(|year| !date.starts_with(&format!("{}-", year))) {
+            continue;
+        }
+
+        let (title, authors) = db::get_book_title(&conn, book_id)?;
+        books.push(CompletedBook {
+            title,
+            authors,
+            date,
+            finished_at: timestamp_ms_to_rfc3339(finished_sec * 1000)?,
+        });
+    }
+
+    Ok(books)
+}
+
+/// Gets every book with recorded reading time, not only Bibles
+///
+/// # Arguments
+/// * `db` - Path to the KOReader statistics.sqlite3 database file, or a pool of connections to it
+///
+/// # Returns
+/// Vector of ReadBook, most recently read first
+pub fn get_books<'a>(db: impl Into<Database<'a>>) -> Result<Vec<ReadBook>> {
+    let conn = db.into().open(db::open_database)?;
+    db::get_books(&conn)
+}
+
+/// Gets the earliest and latest recorded reading activity, plus the database file's
+/// last-modified time
+///
+/// # Arguments
+/// * `db` - Path to the KOReader statistics.sqlite3 database file, or a pool of connections to it
+///
+/// # Returns
+/// DataRange with RFC 3339 timestamps, or `None` fields if nothing has been read yet
+pub fn get_data_range<'a>(db: impl Into<Database<'a>>) -> Result<DataRange> {
+    let db = db.into();
+    let conn = db.open(db::open_database)?;
+    let (earliest_sec, latest_sec) = db::get_reading_range(&conn)?;
+    DataRange::new(
+        earliest_sec.map(|sec| sec * 1000),
+        latest_sec.map(|sec| sec * 1000),
+        db.path(),
+    )
+}
@@ -1,5 +1,10 @@
 use clap::{Parser, Subcommand};
-use readingstats::get_last_30_days_stats;
+use readingstats::{
+    get_completed_books, get_daily_office_stats, get_last_30_days_stats, get_reading_patterns,
+};
+use statsutils::format::{format_number, format_time};
+use statsutils::i18n::tr;
+use statsutils::{PartialPeriod, get_today_date};
 use std::process;
 
 #[derive(Parser)]
@@ -18,52 +23,228 @@ enum Commands {
         /// Path to the KOReader statistics database file
         #[arg(value_name = "DATABASE_PATH")]
         db_path: String,
+
+        /// Whether the summary counts today, which is not over yet (include or exclude)
+        #[arg(long, default_value_t = PartialPeriod::Exclude)]
+        partial: PartialPeriod,
+    },
+    /// Show when reading happens, by hour of day and weekday
+    Patterns {
+        /// Path to the KOReader statistics database file
+        #[arg(value_name = "DATABASE_PATH")]
+        db_path: String,
+
+        /// Number of days to include, ending with today
+        #[arg(long, default_value_t = 90)]
+        days: i32,
+    },
+    /// Show on which days the Treasury of Daily Prayer was opened, with streaks
+    DailyOffice {
+        /// Path to the KOReader statistics database file
+        #[arg(value_name = "DATABASE_PATH")]
+        db_path: String,
+
+        /// Number of days to include, ending with today
+        #[arg(long, default_value_t = 30)]
+        days: i32,
+    },
+    /// Show the books that were read to the end, oldest first
+    Completed {
+        /// Path to the KOReader statistics database file
+        #[arg(value_name = "DATABASE_PATH")]
+        db_path: String,
+
+        /// Only show books finished in this year
+        #[arg(long)]
+        year: Option<i32>,
     },
 }
 
 fn main() {
     let cli = Cli::parse();
 
+    match statsutils::format::display_format_from_env() {
+        Ok(format) => statsutils::format::set_display_format(format),
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            process::exit(1);
+        }
+    }
+
+    match statsutils::i18n::locale_from_env() {
+        Ok(locale) => statsutils::i18n::set_locale(locale),
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            process::exit(1);
+        }
+    }
+
     match cli.command {
-        Commands::Daily { db_path } => {
-            run_daily_command(&db_path);
+        Commands::Daily { db_path, partial } => {
+            run_daily_command(&db_path, partial);
+        }
+        Commands::Patterns { db_path, days } => {
+            run_patterns_command(&db_path, days);
+        }
+        Commands::DailyOffice { db_path, days } => {
+            run_daily_office_command(&db_path, days);
+        }
+        Commands::Completed { db_path, year } => {
+            run_completed_command(&db_path, year);
         }
     }
 }
 
-fn run_daily_command(db_path: &str) {
-    match get_last_30_days_stats(db_path) {
-        Ok(daily_stats) => {
-            println!("\n=== DAILY READING STATS - LAST 30 DAYS ===\n");
+fn run_daily_command(db_path: &str, partial: PartialPeriod) {
+    match get_last_30_days_stats(db_path).and_then(|stats| Ok((stats, get_today_date()?))) {
+        Ok((daily_stats, today)) => {
+            println!("\n=== {} ===\n", tr("DAILY READING STATS - LAST 30 DAYS"));
 
-            let total_minutes: f64 = daily_stats.iter().map(|d| d.minutes).sum();
-            let avg_minutes = total_minutes / daily_stats.len() as f64;
+            // Days the summary covers
+            let counted = partial.complete(&daily_stats, |d| d.date == today);
+            let total_minutes: f64 = counted.iter().map(|d| d.minutes).sum();
+            let avg_minutes = total_minutes / counted.len() as f64;
 
             // Print each day
             for day in &daily_stats {
                 let hours = day.minutes / 60.0;
 
                 if day.minutes > 0.0 {
-                    println!("{}: {:.2} min ({:.1} hrs)", day.date, day.minutes, hours);
+                    println!(
+                        "{}: {} {} ({} {})",
+                        day.date,
+                        format_number(day.minutes, 2),
+                        tr("min"),
+                        format_number(hours, 1),
+                        tr("hrs")
+                    );
                 } else {
-                    println!("{}: --- (no reading)", day.date);
+                    println!("{}: --- ({})", day.date, tr("no reading"));
                 }
             }
 
-            println!("\n--- SUMMARY ---");
+            println!("\n--- {} ---", tr("SUMMARY"));
+            print_minutes(tr("Total Reading Time"), total_minutes);
+            print_minutes(tr("Average per day"), avg_minutes);
+
+            let days_read = counted.iter().filter(|d| d.minutes > 0.0).count();
             println!(
-                "Total Reading Time: {:.2} minutes ({:.1} hours)",
-                total_minutes,
-                total_minutes / 60.0
+                "{}: {} {} {}",
+                tr("Days with reading"),
+                days_read,
+                tr("out of"),
+                counted.len()
+            );
+        }
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn run_patterns_command(db_path: &str, days: i32) {
+    match get_reading_patterns(db_path, days) {
+        Ok(patterns) => {
+            println!(
+                "\n=== {} - {} {} {} ===\n",
+                tr("READING PATTERNS"),
+                tr("LAST"),
+                patterns.days,
+                tr("DAYS")
+            );
+
+            println!("{}:", tr("By hour"));
+            for hour in patterns.hours.iter().filter(|h| h.sessions > 0) {
+                println!(
+                    "  {:>8}  {:>8} {}  {:>4} {}",
+                    format_time(hour.hour, 0),
+                    format_number(hour.minutes, 2),
+                    tr("min"),
+                    hour.sessions,
+                    tr("sessions")
+                );
+            }
+
+            println!("\n{}:", tr("By weekday"));
+            for weekday in &patterns.weekdays {
+                println!(
+                    "  {:<10}  {:>8} {}  {:>4} {}",
+                    tr(&weekday.weekday),
+                    format_number(weekday.minutes, 2),
+                    tr("min"),
+                    weekday.sessions,
+                    tr("sessions")
+                );
+            }
+        }
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn run_daily_office_command(db_path: &str, days: i32) {
+    match get_daily_office_stats(db_path, days) {
+        Ok(stats) => {
+            println!(
+                "\n=== {} - {} {} {} ===\n",
+                tr("DAILY OFFICE"),
+                tr("LAST"),
+                stats.days.len(),
+                tr("DAYS")
+            );
+
+            for day in &stats.days {
+                let mark = if day.opened { tr("opened") } else { "---" };
+                println!("{}: {}", day.date, mark);
+            }
+
+            println!("\n--- {} ---", tr("SUMMARY"));
+            println!(
+                "{}: {} {} {}",
+                tr("Days opened"),
+                stats.days_opened,
+                tr("out of"),
+                stats.days.len()
+            );
+            println!(
+                "{}: {} {}",
+                tr("Current streak"),
+                stats.current_streak_days,
+                tr("days")
             );
             println!(
-                "Average per day: {:.2} minutes ({:.1} hours)",
-                avg_minutes,
-                avg_minutes / 60.0
+                "{}: {} {}",
+                tr("Longest streak"),
+                stats.longest_streak_days,
+                tr("days")
             );
+        }
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            process::exit(1);
+        }
+    }
+}
 
-            let days_read = daily_stats.iter().filter(|d| d.minutes > 0.0).count();
-            println!("Days with reading: {} out of 30", days_read);
+fn run_completed_command(db_path: &str, year: Option<i32>) {
+    match get_completed_books(db_path, year) {
+        Ok(books) => {
+            match year {
+                Some(year) => println!("\n=== {} {} ===\n", tr("BOOKS COMPLETED IN"), year),
+                None => println!("\n=== {} ===\n", tr("BOOKS COMPLETED")),
+            }
+
+            for book in &books {
+                match &book.authors {
+                    Some(authors) => println!("{}: {} ({})", book.date, book.title, authors),
+                    None => println!("{}: {}", book.date, book.title),
+                }
+            }
+
+            println!("\n{}: {} {}", tr("Total"), books.len(), tr("books"));
         }
         Err(e) => {
             eprintln!("Error: {:#}", e);
@@ -71,3 +252,15 @@ fn run_daily_command(db_path: &str) {
         }
     }
 }
+
+/// Prints a labeled time in minutes and hours, e.g. "Average per day: 12.50 minutes (0.2 hours)"
+fn print_minutes(label: &str, minutes: f64) {
+    println!(
+        "{}: {} {} ({} {})",
+        label,
+        format_number(minutes, 2),
+        tr("minutes"),
+        format_number(minutes / 60.0, 1),
+        tr("hours")
+    );
+}
@@ -1,20 +1,152 @@
 use serde::Serialize;
-use utoipa::ToSchema;
 
-/// Reading time statistics for a single day
-#[derive(Debug, Clone, Serialize, ToSchema)]
-pub struct DayStats {
+/// Reading time per day and week
+pub use statsmodels::{DayStats, WeekStats};
+
+/// Reading time and sessions started within one hour of the day
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct HourPattern {
+    /// Local clock hour (0-23)
+    pub hour: u32,
+    /// Reading time in minutes of page views that started in this hour
+    pub minutes: f64,
+    /// Number of reading sessions that started in this hour
+    pub sessions: i64,
+}
+
+/// Reading time and sessions on one day of the week
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct WeekdayPattern {
+    /// Day name, e.g. "Sunday"
+    pub weekday: String,
+    /// Reading time in minutes on this weekday
+    pub minutes: f64,
+    /// Number of reading sessions that started on this weekday
+    pub sessions: i64,
+}
+
+/// When reading happens: hour-of-day and weekday histograms over a window of days
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ReadingPatterns {
+    /// Number of days covered, ending with today
+    pub days: i32,
+    /// One entry per hour, from 0 to 23
+    pub hours: Vec<HourPattern>,
+    /// One entry per weekday, from Sunday to Saturday (weeks start on Sunday)
+    pub weekdays: Vec<WeekdayPattern>,
+}
+
+/// Whether the Treasury of Daily Prayer was opened on a single day
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DailyOfficeDay {
     /// Date in YYYY-MM-DD format
     pub date: String,
-    /// Reading time in minutes
-    pub minutes: f64,
+    /// Whether there was any reading session in the Treasury of Daily Prayer
+    pub opened: bool,
 }
 
-/// Reading time statistics for a single week
-#[derive(Debug, Clone, Serialize, ToSchema)]
-pub struct WeekStats {
-    /// Week start date in YYYY-MM-DD format
-    pub week_start: String,
-    /// Reading time in minutes
+/// Treasury of Daily Prayer adherence over a window of days
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DailyOfficeStats {
+    /// One entry per day, ending with today
+    pub days: Vec<DailyOfficeDay>,
+    /// Number of days on which the Treasury of Daily Prayer was opened
+    pub days_opened: u32,
+    /// Consecutive days opened, ending with today (or yesterday while today is still open)
+    pub current_streak_days: u32,
+    /// Longest run of consecutive days opened within the window
+    pub longest_streak_days: u32,
+}
+
+impl DailyOfficeStats {
+    /// Computes the totals and streaks from a daily series ending with today
+    pub fn new(days: Vec<DailyOfficeDay>) -> Self {
+        // A streak that has not been continued today yet still counts until the day is over
+        let mut remaining = days.as_slice();
+        if let Some((today, earlier)) = remaining.split_last()
+            && !today.opened
+        {
+            remaining = earlier;
+        }
+        let current_streak_days = remaining.iter().rev().take_while(|d| d.opened).count() as u32;
+
+        let mut longest_streak_days = 0;
+        let mut streak = 0;
+        for day in &days {
+            streak = if day.opened { streak + 1 } else { 0 };
+            longest_streak_days = longest_streak_days.max(streak);
+        }
+
+        Self {
+            days_opened: days.iter().filter(|d| d.opened).count() as u32,
+            current_streak_days,
+            longest_streak_days,
+            days,
+        }
+    }
+}
+
+/// A book that was read to the end
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CompletedBook {
+    /// Book title as stored by KOReader
+    pub title: String,
+    /// Authors as stored by KOReader, if any
+    pub authors: Option<String>,
+    /// Date the book was finished in YYYY-MM-DD format
+    pub date: String,
+    /// RFC 3339 timestamp of the page view that reached the end of the book
+    pub finished_at: String,
+}
+
+/// A book with recorded reading time
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ReadBook {
+    /// Book title as stored by KOReader
+    pub title: String,
+    /// Authors as stored by KOReader, if any
+    pub authors: Option<String>,
+    /// Total reading time in minutes
     pub minutes: f64,
+    /// Date the book was last read in YYYY-MM-DD format
+    pub last_read_date: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn days(opened: &[bool]) -> Vec<DailyOfficeDay> {
+        opened
+            .iter()
+            .enumerate()
+            .map(|(i, &opened)| DailyOfficeDay {
+                date: format!("2025-01-{:02}", i + 1),
+                opened,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_daily_office_streaks() {
+        let stats = DailyOfficeStats::new(days(&[true, true, true, false, true, true]));
+        assert_eq!(stats.days_opened, 5);
+        assert_eq!(stats.current_streak_days, 2);
+        assert_eq!(stats.longest_streak_days, 3);
+
+        // Not opened yet today: yesterday's streak still counts
+        let stats = DailyOfficeStats::new(days(&[false, true, true, false]));
+        assert_eq!(stats.current_streak_days, 2);
+
+        let stats = DailyOfficeStats::new(days(&[true, false, false]));
+        assert_eq!(stats.current_streak_days, 0);
+        assert_eq!(stats.longest_streak_days, 1);
+    }
 }
@@ -0,0 +1,119 @@
+use anyhow::Result;
+use statsutils::timestamp_ms_to_hour_and_weekday;
+
+use crate::models::{HourPattern, ReadingPatterns, WeekdayPattern};
+
+/// Longest pause (in seconds) between page views of the same book within one session
+pub const SESSION_GAP_SECONDS: i64 = 10 * 60;
+
+const WEEKDAYS: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+/// A single page view from KOReader's `page_stat_data` table
+#[derive(Debug, Clone, Copy)]
+pub struct PageView {
+    pub book_id: i64,
+    /// Start time in Unix seconds
+    pub start_time: i64,
+    /// Time spent on the page in seconds
+    pub duration: i64,
+}
+
+/// Builds the hour-of-day and weekday histograms from page views sorted by start time
+///
+/// Each page view's time counts towards the hour and weekday it started in. Page views of
+/// the same book that follow each other within `SESSION_GAP_SECONDS` form one session,
+/// which is counted where its first page view started.
+pub fn reading_patterns(views: &[PageView], days: i32) -> Result<ReadingPatterns> {
+    let mut hours: Vec<HourPattern> = (0..24)
+        .map(|hour| HourPattern {
+            hour,
+            minutes: 0.0,
+            sessions: 0,
+        })
+        .collect();
+    let mut weekdays: Vec<WeekdayPattern> = WEEKDAYS
+        .iter()
+        .map(|weekday| WeekdayPattern {
+            weekday: weekday.to_string(),
+            minutes: 0.0,
+            sessions: 0,
+        })
+        .collect();
+
+    let mut previous: Option<&PageView> = None;
+    for view in views {
+        let (hour, weekday) = timestamp_ms_to_hour_and_weekday(view.start_time * 1000)?;
+        let (hour, weekday) = (&mut hours[hour as usize], &mut weekdays[weekday as usize]);
+
+        let minutes = view.duration as f64 / 60.0;
+        hour.minutes += minutes;
+        weekday.minutes += minutes;
+
+        let continues_session = previous.is_some_and(|previous| {
+            previous.book_id == view.book_id
+                && view.start_time - (previous.start_time + previous.duration)
+                    <= SESSION_GAP_SECONDS
+        });
+        if !continues_session {
+            hour.sessions += 1;
+            weekday.sessions += 1;
+        }
+        previous = Some(view);
+    }
+
+    Ok(ReadingPatterns {
+        days,
+        hours,
+        weekdays,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2025-01-08 (a Wednesday) 10:00 in America/Chicago
+    const WEDNESDAY_MORNING: i64 = 1_736_352_000;
+
+    fn view(book_id: i64, start_time: i64, duration: i64) -> PageView {
+        PageView {
+            book_id,
+            start_time,
+            duration,
+        }
+    }
+
+    #[test]
+    fn test_reading_patterns() {
+        let views = vec![
+            // One session of two pages at 10:00
+            view(1, WEDNESDAY_MORNING, 120),
+            view(1, WEDNESDAY_MORNING + 180, 60),
+            // Another book right after starts a new session
+            view(2, WEDNESDAY_MORNING + 240, 60),
+            // The first book again after a long pause, the next day at 21:00
+            view(1, WEDNESDAY_MORNING + 35 * 60 * 60, 300),
+        ];
+
+        let patterns = reading_patterns(&views, 30).unwrap();
+
+        assert_eq!(patterns.days, 30);
+        assert_eq!(patterns.hours.len(), 24);
+        assert_eq!(patterns.hours[10].minutes, 4.0);
+        assert_eq!(patterns.hours[10].sessions, 2);
+        assert_eq!(patterns.hours[21].minutes, 5.0);
+        assert_eq!(patterns.hours[21].sessions, 1);
+        assert_eq!(patterns.weekdays[3].weekday, "Wednesday");
+        assert_eq!(patterns.weekdays[3].sessions, 2);
+        assert_eq!(patterns.weekdays[4].minutes, 5.0);
+        assert_eq!(patterns.hours.iter().map(|h| h.sessions).sum::<i64>(), 3);
+    }
+}
@@ -0,0 +1,35 @@
+//! Loadable SQLite extension with the custom functions the server registers
+//!
+//! Provides `count_verses`, `parse_book_name`, `date_str_from_ms`, `date_str_from_sec`,
+//! `week_str_from_ms` and `week_str_from_sec` for ad-hoc queries in `sqlite3`, Datasette, etc.:
+//!
+//! ```sql
+//! .load target/release/libstatsext
+//! SELECT parse_book_name(sfld), count_verses(sfld) FROM notes LIMIT 10;
+//! ```
+
+use rusqlite::{Connection, ffi};
+use std::os::raw::{c_char, c_int};
+
+/// Entry point called by SQLite when loading the extension
+///
+/// # Safety
+/// Must only be called by SQLite, with the arguments it passes to extension entry points.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_extension_init(
+    db: *mut ffi::sqlite3,
+    pz_err_msg: *mut *mut c_char,
+    p_api: *mut ffi::sqlite3_api_routines,
+) -> c_int {
+    // SAFETY: the arguments are forwarded unchanged from SQLite
+    unsafe { Connection::extension_init2(db, pz_err_msg, p_api, extension_init) }
+}
+
+/// Registers the functions on the connection that loaded the extension
+fn extension_init(conn: Connection) -> rusqlite::Result<bool> {
+    ankistats::db::register_functions(&conn)
+        .map_err(|e| rusqlite::Error::UserFunctionError(e.into()))?;
+
+    // The functions only live as long as this connection, so the extension is not persistent
+    Ok(false)
+}
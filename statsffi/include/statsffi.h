@@ -0,0 +1,40 @@
+/*
+ * C bindings for the Life Stats Bible reference parsers.
+ *
+ * All strings are NUL-terminated UTF-8. Strings returned by this library are owned by
+ * the caller and must be released with lifestats_free_string().
+ */
+
+#ifndef STATSFFI_H
+#define STATSFFI_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/*
+ * Counts the verses in a Bible reference (e.g. "John 3:16-18" is 3).
+ *
+ * Returns -1 if the reference is NULL, not valid UTF-8, or cannot be parsed.
+ * (The server counts unparsable references as a single verse.)
+ */
+int64_t lifestats_count_verses(const char *reference);
+
+/*
+ * Returns the book name of a Bible reference (e.g. "2 Timothy 3:16" is "2 Timothy").
+ *
+ * Returns NULL if the reference is NULL, not valid UTF-8, or cannot be parsed.
+ * A non-NULL result must be released with lifestats_free_string().
+ */
+char *lifestats_parse_book_name(const char *reference);
+
+/* Releases a string returned by this library; does nothing for NULL. */
+void lifestats_free_string(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* STATSFFI_H */
@@ -0,0 +1,96 @@
+//! C-compatible bindings for the Bible reference parsers
+//!
+//! Lets non-Rust tools (e.g. an Anki add-on via Python's ctypes) apply exactly the same
+//! reference-parsing rules as the server. See `include/statsffi.h` for the C declarations.
+//!
+//! All strings are NUL-terminated UTF-8. Strings returned by this library are owned by the
+//! caller and must be released with `lifestats_free_string`.
+
+use ankistats::book_name_parser::try_parse_book_name;
+use ankistats::verse_parser::try_count_verses_in_reference;
+use std::ffi::{CStr, CString, c_char};
+use std::ptr;
+
+/// Converts a C string argument to `&str`, or `None` if it is NULL or not valid UTF-8
+///
+/// # Safety
+/// `s` must be NULL or point to a NUL-terminated string that outlives the returned value.
+unsafe fn to_str<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+    // SAFETY: checked for NULL above; the caller guarantees NUL termination
+    unsafe { CStr::from_ptr(s) }.to_str().ok()
+}
+
+/// Counts the verses in a Bible reference (e.g. "John 3:16-18" is 3)
+///
+/// Returns -1 if the reference is NULL, not valid UTF-8, or cannot be parsed. (The server
+/// counts unparsable references as a single verse.)
+///
+/// # Safety
+/// `reference` must be NULL or point to a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn lifestats_count_verses(reference: *const c_char) -> i64 {
+    // SAFETY: forwarded from the caller
+    let Some(reference) = (unsafe { to_str(reference) }) else {
+        return -1;
+    };
+
+    try_count_verses_in_reference(reference).unwrap_or(-1)
+}
+
+/// Returns the book name of a Bible reference (e.g. "2 Timothy 3:16" is "2 Timothy")
+///
+/// Returns NULL if the reference is NULL, not valid UTF-8, or cannot be parsed. A non-NULL
+/// result must be released with `lifestats_free_string`.
+///
+/// # Safety
+/// `reference` must be NULL or point to a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn lifestats_parse_book_name(reference: *const c_char) -> *mut c_char {
+    // SAFETY: forwarded from the caller
+    let Some(reference) = (unsafe { to_str(reference) }) else {
+        return ptr::null_mut();
+    };
+
+    match try_parse_book_name(reference).map(CString::new) {
+        Ok(Ok(book_name)) => book_name.into_raw(),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Releases a string returned by this library; does nothing for NULL
+///
+/// # Safety
+/// `s` must be NULL or a pointer returned by this library that has not been freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn lifestats_free_string(s: *mut c_char) {
+    if !s.is_null() {
+        // SAFETY: the caller guarantees `s` came from `CString::into_raw` in this library
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ffi_round_trip() {
+        let reference = CString::new("2 Timothy 3:16-17").unwrap();
+        let invalid = CString::new("not a reference").unwrap();
+
+        unsafe {
+            assert_eq!(lifestats_count_verses(reference.as_ptr()), 2);
+            assert_eq!(lifestats_count_verses(invalid.as_ptr()), -1);
+            assert_eq!(lifestats_count_verses(ptr::null()), -1);
+
+            let book_name = lifestats_parse_book_name(reference.as_ptr());
+            assert_eq!(CStr::from_ptr(book_name).to_str().unwrap(), "2 Timothy");
+            lifestats_free_string(book_name);
+
+            assert!(lifestats_parse_book_name(ptr::null()).is_null());
+        }
+    }
+}
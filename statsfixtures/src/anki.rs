@@ -0,0 +1,386 @@
+//! Synthetic Anki collection with Bible verse cards
+//!
+//! The collection uses the legacy schema (deck and note type configuration as JSON in the
+//! `col` table) with the default deck and note type names of ankistats. Notes are added a few
+//! a day and reviewed with a simplified SM-2 scheduler: a passed review multiplies the
+//! interval by the card's ease, a failed one lapses the card to a one-day interval.
+
+use anyhow::{Context, Result};
+use chrono::{Duration, NaiveDate};
+use rand::RngExt;
+use rand::rngs::StdRng;
+use rusqlite::{Connection, params};
+use serde_json::json;
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::{FixtureConfig, is_past, local_time};
+
+/// Deck holding the Bible verse cards, ankistats' default
+pub const DECK_NAME: &str = "Bible::Verses";
+
+/// Note type of the Bible verse cards, ankistats' default
+pub const NOTE_TYPE: &str = "Bible Verse";
+
+/// Note that is not a Bible reference, as collections tend to have a few of
+pub const UNPARSABLE_NOTE: &str = "Sermon notes";
+
+/// Often memorized passages, added before the random ones
+const WELL_KNOWN_PASSAGES: [&str; 12] = [
+    "John 3:16",
+    "Psalm 23:1-6",
+    "Romans 8:28-30",
+    "Philippians 4:6-7",
+    "Proverbs 3:5-6",
+    "Ephesians 2:8-9",
+    "Isaiah 40:31",
+    "Joshua 1:9",
+    "Matthew 28:19-20",
+    "2 Timothy 3:16-17",
+    "Jude 24-25",
+    "1 John 1:9",
+];
+
+const DECK_ID: i64 = 1_500_000_000_000;
+const PARENT_DECK_ID: i64 = 1_499_999_999_999;
+const NOTE_TYPE_ID: i64 = 1_400_000_000_001;
+const FIRST_NOTE_ID: i64 = 1_600_000_000_000;
+
+const STARTING_EASE: i64 = 2500;
+const MINIMUM_EASE: i64 = 1300;
+const NEW_CARDS_PER_DAY: usize = 20;
+
+const QUEUE_NEW: i64 = 0;
+const QUEUE_REVIEW: i64 = 2;
+const REVLOG_LEARN: i64 = 0;
+const REVLOG_REVIEW: i64 = 1;
+const EASE_AGAIN: i64 = 1;
+const EASE_GOOD: i64 = 3;
+const EASE_EASY: i64 = 4;
+
+/// Scheduling state of a card
+struct Card {
+    id: i64,
+    note_id: i64,
+    ord: i64,
+    /// Date of the next review, `None` while the card is new
+    due: Option<NaiveDate>,
+    interval: i64,
+    ease: i64,
+    reps: i64,
+    lapses: i64,
+}
+
+/// Writes a collection with `config.verses` Bible verse notes reviewed between the start and
+/// end dates
+pub fn write_collection(path: &Path, config: &FixtureConfig) -> Result<()> {
+    let mut rng = config.rng(1);
+    let mut db = Connection::open(path).context(format!(
+        "Failed to create Anki collection: {}",
+        path.display()
+    ))?;
+    let conn = db.transaction()?;
+    create_schema(&conn, config)?;
+
+    let mut references = references(&mut rng, config.verses);
+    references.push(UNPARSABLE_NOTE.to_string());
+    let mut cards = Vec::new();
+    for (index, reference) in references.iter().enumerate() {
+        let note_id = FIRST_NOTE_ID + index as i64 * 1000;
+        conn.execute(
+            "INSERT INTO notes VALUES (?1, ?2, ?3, ?4, -1, '', ?5, ?6, ?7, 0, '')",
+            params![
+                note_id,
+                format!("fixture{}", index),
+                NOTE_TYPE_ID,
+                note_id / 1000,
+                format!("{}\x1f", reference),
+                reference,
+                index as i64,
+            ],
+        )?;
+        for ord in 0..2 {
+            cards.push(Card {
+                id: note_id + ord + 1,
+                note_id,
+                ord,
+                due: None,
+                interval: 0,
+                ease: STARTING_EASE,
+                reps: 0,
+                lapses: 0,
+            });
+        }
+    }
+
+    // The new notes are added over the first nine tenths of the range, catching up after the
+    // days without study, so a few are left unstudied at the end
+    let days = config.days().count() as f64;
+    let notes_per_day = references.len() as f64 * 0.9 / days;
+
+    let mut revlog =
+        conn.prepare("INSERT INTO revlog VALUES (?1, ?2, -1, ?3, ?4, ?5, ?6, ?7, ?8)")?;
+    let mut introduced = 0;
+    for (day, date) in config.days().enumerate() {
+        if !rng.random_bool(config.activity) {
+            continue;
+        }
+        let mut time = local_time(date, rng.random_range(6..9), rng.random_range(0..60))?;
+        if !is_past(time) {
+            break;
+        }
+
+        let scheduled = ((day + 1) as f64 * notes_per_day).ceil() as usize;
+        let new_notes = scheduled.min(introduced + NEW_CARDS_PER_DAY / 2);
+        for card in &mut cards[introduced * 2..new_notes * 2] {
+            card.due = Some(date);
+        }
+        introduced = new_notes;
+
+        for card in cards
+            .iter_mut()
+            .filter(|card| card.due.is_some_and(|due| due <= date))
+        {
+            let last_interval = card.interval;
+            let kind = if card.reps == 0 {
+                REVLOG_LEARN
+            } else {
+                REVLOG_REVIEW
+            };
+            let (ease, interval) = if card.reps == 0 {
+                (EASE_GOOD, 1)
+            } else if rng.random_bool(0.1) {
+                card.lapses += 1;
+                card.ease = (card.ease - 200).max(MINIMUM_EASE);
+                (EASE_AGAIN, 1)
+            } else if rng.random_bool(0.1) {
+                card.ease += 150;
+                (EASE_EASY, next_interval(&mut rng, card, 1.3))
+            } else {
+                (EASE_GOOD, next_interval(&mut rng, card, 1.0))
+            };
+
+            card.reps += 1;
+            card.interval = interval;
+            card.due = Some(date + Duration::days(interval));
+            let duration_ms = rng.random_range(4_000..30_000);
+            revlog.execute(params![
+                time.timestamp_millis(),
+                card.id,
+                ease,
+                interval,
+                last_interval,
+                card.ease,
+                duration_ms,
+                kind,
+            ])?;
+            time += Duration::milliseconds(duration_ms + rng.random_range(500..3_000));
+        }
+    }
+    drop(revlog);
+
+    let mut insert = conn.prepare(
+        "INSERT INTO cards VALUES (?1, ?2, ?3, ?4, ?5, -1, ?6, ?6, ?7, ?8, ?9, ?10, ?11, 0, 0, 0, 0, '')",
+    )?;
+    for (position, card) in cards.iter().enumerate() {
+        let (queue, due) = match card.due {
+            // Review cards are due a number of days after the collection was created
+            Some(due) if card.reps > 0 => (QUEUE_REVIEW, (due - config.start).num_days()),
+            _ => (QUEUE_NEW, position as i64 / 2),
+        };
+        insert.execute(params![
+            card.id,
+            card.note_id,
+            DECK_ID,
+            card.ord,
+            card.note_id / 1000,
+            queue,
+            due,
+            card.interval,
+            if queue == QUEUE_NEW { 0 } else { card.ease },
+            card.reps,
+            card.lapses,
+        ])?;
+    }
+    drop(insert);
+
+    conn.commit()?;
+    Ok(())
+}
+
+/// Interval after a passed review, growing with the card's ease
+fn next_interval(rng: &mut StdRng, card: &Card, bonus: f64) -> i64 {
+    let fuzz = rng.random_range(0.9..1.1);
+    let interval = (card.interval as f64 * card.ease as f64 / 1000.0 * bonus * fuzz).round();
+    (interval as i64).clamp(card.interval + 1, 36500)
+}
+
+/// The well-known passages followed by random verses and short passages, without duplicates
+fn references(rng: &mut StdRng, count: usize) -> Vec<String> {
+    let mut references: Vec<String> = WELL_KNOWN_PASSAGES
+        .iter()
+        .take(count)
+        .map(|reference| reference.to_string())
+        .collect();
+    let mut seen: HashSet<String> = references.iter().cloned().collect();
+
+    while references.len() < count {
+        let book = &bibledata::BOOKS[rng.random_range(0..bibledata::BOOKS.len())];
+        let chapter = rng.random_range(1..=book.chapter_count());
+        let verses = book.chapter_verse_count(chapter).unwrap_or(1);
+        let first = rng.random_range(1..=verses);
+        let last = if rng.random_bool(0.3) {
+            (first + rng.random_range(1..4)).min(verses)
+        } else {
+            first
+        };
+
+        let verses = if last > first {
+            format!("{}-{}", first, last)
+        } else {
+            first.to_string()
+        };
+        let reference = if book.is_single_chapter() {
+            format!("{} {}", book.name, verses)
+        } else {
+            format!("{} {}:{}", book.name, chapter, verses)
+        };
+        if seen.insert(reference.clone()) {
+            references.push(reference);
+        }
+    }
+
+    references
+}
+
+/// Creates the tables of a legacy (schema 11) collection with the decks and note type
+fn create_schema(conn: &Connection, config: &FixtureConfig) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE col (
+             id integer primary key, crt integer not null, mod integer not null,
+             scm integer not null, ver integer not null, dty integer not null,
+             usn integer not null, ls integer not null, conf text not null,
+             models text not null, decks text not null, dconf text not null,
+             tags text not null
+         );
+         CREATE TABLE notes (
+             id integer primary key, guid text not null, mid integer not null,
+             mod integer not null, usn integer not null, tags text not null,
+             flds text not null, sfld integer not null, csum integer not null,
+             flags integer not null, data text not null
+         );
+         CREATE TABLE cards (
+             id integer primary key, nid integer not null, did integer not null,
+             ord integer not null, mod integer not null, usn integer not null,
+             type integer not null, queue integer not null, due integer not null,
+             ivl integer not null, factor integer not null, reps integer not null,
+             lapses integer not null, left integer not null, odue integer not null,
+             odid integer not null, flags integer not null, data text not null
+         );
+         CREATE TABLE revlog (
+             id integer primary key, cid integer not null, usn integer not null,
+             ease integer not null, ivl integer not null, lastIvl integer not null,
+             factor integer not null, time integer not null, type integer not null
+         );
+         CREATE TABLE graves (usn integer not null, oid integer not null, type integer not null);
+         CREATE INDEX ix_notes_usn on notes (usn);
+         CREATE INDEX ix_cards_usn on cards (usn);
+         CREATE INDEX ix_revlog_usn on revlog (usn);
+         CREATE INDEX ix_cards_nid on cards (nid);
+         CREATE INDEX ix_cards_sched on cards (did, queue, due);
+         CREATE INDEX ix_revlog_cid on revlog (cid);
+         CREATE INDEX ix_notes_csum on notes (csum);",
+    )
+    .context("Failed to create Anki schema")?;
+
+    // The collection is created at the rollover hour of the first day, which the due dates
+    // of review cards count from
+    let created = local_time(config.start, 4, 0)?.timestamp();
+    let deck = |id: i64, name: &str| json!({"id": id, "name": name, "conf": 1, "dyn": 0});
+    let decks = json!({
+        "1": deck(1, "Default"),
+        PARENT_DECK_ID.to_string(): deck(PARENT_DECK_ID, "Bible"),
+        DECK_ID.to_string(): deck(DECK_ID, DECK_NAME),
+    });
+    let models = json!({
+        NOTE_TYPE_ID.to_string(): {
+            "id": NOTE_TYPE_ID,
+            "name": NOTE_TYPE,
+            "type": 0,
+            "sortf": 0,
+            "did": DECK_ID,
+            "flds": [{"name": "Reference", "ord": 0}, {"name": "Text", "ord": 1}],
+            "tmpls": [
+                {"name": "Reference to Text", "ord": 0},
+                {"name": "Text to Reference", "ord": 1},
+            ],
+        },
+    });
+    let dconf = json!({
+        "1": {
+            "id": 1,
+            "name": "Default",
+            "new": {"perDay": NEW_CARDS_PER_DAY, "delays": [1.0, 10.0]},
+            "rev": {"perDay": 200, "maxIvl": 36500},
+            "lapse": {"delays": [10.0], "leechFails": 8},
+        },
+    });
+    conn.execute(
+        "INSERT INTO col VALUES (1, ?1, ?2, ?2, 11, 0, 0, 0, '{}', ?3, ?4, ?5, '{}')",
+        params![
+            created,
+            created * 1000,
+            models.to_string(),
+            decks.to_string(),
+            dconf.to_string(),
+        ],
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_references_are_unique_and_parse() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let references = references(&mut rng, 300);
+        assert_eq!(references.len(), 300);
+        assert_eq!(references[0], "John 3:16");
+
+        let unique: HashSet<&String> = references.iter().collect();
+        assert_eq!(unique.len(), references.len());
+        for reference in &references {
+            assert!(
+                ankistats::reference::parse_reference(reference).is_ok(),
+                "{} does not parse",
+                reference
+            );
+        }
+    }
+
+    #[test]
+    fn test_mature_interval_is_reached() {
+        const MATURE_INTERVAL: i64 = 21;
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut card = Card {
+            id: 1,
+            note_id: 1,
+            ord: 0,
+            due: None,
+            interval: 1,
+            ease: STARTING_EASE,
+            reps: 1,
+            lapses: 0,
+        };
+        let mut reviews = 0;
+        while card.interval < MATURE_INTERVAL {
+            card.interval = next_interval(&mut rng, &card, 1.0);
+            reviews += 1;
+        }
+        assert!(reviews <= 5, "matured after {} reviews", reviews);
+    }
+}
@@ -0,0 +1,220 @@
+//! Synthetic Arc Timeline export
+//!
+//! A week of home, work and errands: the office on weekdays, church on Sunday mornings and
+//! some Wednesday evenings, the park and the grocery store now and then. Only visits are
+//! exported, no trips or location samples.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc, Weekday};
+use rand::RngExt;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::{FixtureConfig, is_past, local_time};
+
+/// Seconds between the Unix epoch and Apple's reference date (2001-01-01)
+const APPLE_EPOCH_OFFSET: i64 = 978_307_200;
+
+/// A place: name, Google place type and location
+struct Place {
+    id: &'static str,
+    name: &'static str,
+    primary_type: &'static str,
+    latitude: f64,
+    longitude: f64,
+}
+
+const HOME: Place = Place {
+    id: "A0000000-0000-0000-0000-000000000001",
+    name: "Home",
+    primary_type: "home",
+    latitude: 38.6127,
+    longitude: -90.2594,
+};
+const CHURCH: Place = Place {
+    id: "A0000000-0000-0000-0000-000000000002",
+    name: "Grace Lutheran Church",
+    primary_type: "church",
+    latitude: 38.6205,
+    longitude: -90.2710,
+};
+const OFFICE: Place = Place {
+    id: "A0000000-0000-0000-0000-000000000003",
+    name: "Office",
+    primary_type: "corporate_office",
+    latitude: 38.6270,
+    longitude: -90.1994,
+};
+const GROCERY: Place = Place {
+    id: "A0000000-0000-0000-0000-000000000004",
+    name: "Schnucks",
+    primary_type: "grocery_store",
+    latitude: 38.6050,
+    longitude: -90.2650,
+};
+const PARK: Place = Place {
+    id: "A0000000-0000-0000-0000-000000000005",
+    name: "Tower Grove Park",
+    primary_type: "park",
+    latitude: 38.6064,
+    longitude: -90.2560,
+};
+
+const PLACES: [&Place; 5] = [&HOME, &CHURCH, &OFFICE, &GROCERY, &PARK];
+
+/// A visit: the place, start and end
+type Visit = (&'static Place, DateTime<Utc>, DateTime<Utc>);
+
+/// Writes an export with visits between the start and end dates
+pub fn write_export(path: &Path, config: &FixtureConfig) -> Result<()> {
+    let mut rng = config.rng(4);
+    let mut visits: Vec<Visit> = Vec::new();
+    for date in config.days() {
+        // Outings of the day as (place, start hour, start minute, minutes), in order
+        let mut outings = Vec::new();
+        match date.weekday() {
+            Weekday::Sun => {
+                if rng.random_bool(config.activity) {
+                    outings.push((&CHURCH, 9, 15, rng.random_range(120..160)));
+                }
+            }
+            Weekday::Sat => {
+                if rng.random_bool(0.5) {
+                    outings.push((&PARK, 10, 0, rng.random_range(45..120)));
+                }
+                if rng.random_bool(0.6) {
+                    outings.push((&GROCERY, 14, 30, rng.random_range(20..50)));
+                }
+            }
+            weekday => {
+                outings.push((&OFFICE, 8, 30, rng.random_range(460..520)));
+                if weekday == Weekday::Wed && rng.random_bool(config.activity * 0.5) {
+                    outings.push((&CHURCH, 19, 0, rng.random_range(60..90)));
+                }
+            }
+        }
+
+        // At home in between, from midnight to midnight
+        let mut home_since = local_time(date, 0, 0)?;
+        for (place, hour, minute, minutes) in outings {
+            let start =
+                local_time(date, hour, minute)? + Duration::minutes(rng.random_range(0..15));
+            let end = start + Duration::minutes(minutes);
+            visits.push((&HOME, home_since, start - Duration::minutes(15)));
+            visits.push((place, start, end));
+            home_since = end + Duration::minutes(15);
+        }
+        let midnight = local_time(date + Duration::days(1), 0, 0)? - Duration::seconds(1);
+        visits.push((&HOME, home_since, midnight));
+    }
+    visits.retain(|(_, _, end)| is_past(*end));
+
+    let saved = visits.last().map_or_else(Utc::now, |(_, _, end)| *end);
+    write_places(path, &visits, saved)?;
+    let item_count = write_items(path, &visits, saved)?;
+
+    let saved = apple_timestamp(saved);
+    let metadata = json!({
+        "samplesCompleted": true,
+        "exportMode": "bucketed",
+        "sessionStartDate": saved,
+        "itemsCompleted": true,
+        "exportType": "full",
+        "sessionFinishDate": saved,
+        "stats": {"sampleCount": 0, "itemCount": item_count, "placeCount": PLACES.len()},
+        "schemaVersion": "2.2.0",
+        "placesCompleted": true,
+    });
+    write_json(&path.join("metadata.json"), &metadata)
+}
+
+fn apple_timestamp(time: DateTime<Utc>) -> f64 {
+    (time.timestamp() - APPLE_EPOCH_OFFSET) as f64
+}
+
+fn write_json(path: &Path, value: &Value) -> Result<()> {
+    fs::write(path, value.to_string()).context(format!("Failed to write {}", path.display()))
+}
+
+/// Writes the places, with their number of visits and of days visited
+fn write_places(path: &Path, visits: &[Visit], saved: DateTime<Utc>) -> Result<()> {
+    let places: Vec<Value> = PLACES
+        .iter()
+        .enumerate()
+        .map(|(index, place)| {
+            let place_visits: Vec<&Visit> = visits
+                .iter()
+                .filter(|(visited, _, _)| visited.id == place.id)
+                .collect();
+            let mut days: Vec<NaiveDate> = place_visits
+                .iter()
+                .map(|(_, start, _)| start.date_naive())
+                .collect();
+            days.dedup();
+            json!({
+                "id": place.id,
+                "name": place.name,
+                "latitude": place.latitude,
+                "longitude": place.longitude,
+                "radiusMean": 30.0,
+                "radiusSD": 5.0,
+                "visitCount": place_visits.len(),
+                "visitDays": days.len(),
+                "lastSaved": apple_timestamp(saved),
+                "isStale": false,
+                "source": "LocoKit",
+                "rtreeId": index + 1,
+                "locality": "St. Louis",
+                "countryCode": "US",
+                "googlePrimaryType": place.primary_type,
+            })
+        })
+        .collect();
+
+    let dir = path.join("places");
+    fs::create_dir_all(&dir).context(format!("Failed to create {}", dir.display()))?;
+    write_json(&dir.join("A.json"), &Value::Array(places))
+}
+
+/// Writes the visits into one file per month and returns their number
+fn write_items(path: &Path, visits: &[Visit], saved: DateTime<Utc>) -> Result<usize> {
+    let saved = apple_timestamp(saved);
+    let mut months: BTreeMap<String, Vec<Value>> = BTreeMap::new();
+    for (index, (place, start, end)) in visits.iter().enumerate() {
+        let id = format!("B0000000-0000-0000-0000-{:012}", index + 1);
+        let item = json!({
+            "base": {
+                "id": id,
+                "startDate": apple_timestamp(*start),
+                "endDate": apple_timestamp(*end),
+                "lastSaved": saved,
+                "source": "LocoKit",
+                "isVisit": true,
+                "deleted": false,
+                "disabled": false,
+            },
+            "visit": {
+                "itemId": id,
+                "placeId": place.id,
+                "latitude": place.latitude,
+                "longitude": place.longitude,
+                "radiusMean": 30.0,
+                "radiusSD": 5.0,
+                "confirmedPlace": true,
+                "uncertainPlace": false,
+                "lastSaved": saved,
+            },
+        });
+        let month = format!("{}-{:02}", start.year(), start.month());
+        months.entry(month).or_default().push(item);
+    }
+
+    let dir = path.join("items");
+    fs::create_dir_all(&dir).context(format!("Failed to create {}", dir.display()))?;
+    for (month, items) in months {
+        write_json(&dir.join(format!("{}.json", month)), &Value::Array(items))?;
+    }
+    Ok(visits.len())
+}
@@ -0,0 +1,161 @@
+//! Synthetic KOReader statistics database
+//!
+//! Morning Bible reading, the Treasury of Daily Prayer opened at the day's page and, on some
+//! evenings, a book read from cover to cover before the next one is started.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use rand::RngExt;
+use rand::rngs::StdRng;
+use rusqlite::{Connection, params};
+use std::path::Path;
+
+use crate::{FixtureConfig, is_past, local_time};
+
+/// A book in the database: title, authors and number of pages
+type Book = (&'static str, &'static str, i64);
+
+const BIBLE: Book = ("The Holy Bible (ESV)", "Crossway", 1900);
+const TREASURY: Book = ("Treasury of Daily Prayer", "Scot A. Kinnaman", 1400);
+
+/// Books read in the evenings, in order
+const OTHER_BOOKS: [Book; 4] = [
+    ("The Screwtape Letters", "C. S. Lewis", 160),
+    ("The Hiding Place", "Corrie ten Boom", 240),
+    ("Mere Christianity", "C. S. Lewis", 230),
+    ("Orthodoxy", "G. K. Chesterton", 170),
+];
+
+const BIBLE_ID: i64 = 1;
+const TREASURY_ID: i64 = 2;
+const FIRST_OTHER_BOOK_ID: i64 = 3;
+
+/// Writes a database with reading between the start and end dates
+pub fn write_database(path: &Path, config: &FixtureConfig) -> Result<()> {
+    let mut rng = config.rng(2);
+    let mut db = Connection::open(path).context(format!(
+        "Failed to create KOReader database: {}",
+        path.display()
+    ))?;
+    let conn = db.transaction()?;
+    conn.execute_batch(
+        "CREATE TABLE book (
+             id integer PRIMARY KEY autoincrement, title text, authors text, notes integer,
+             last_open integer, highlights integer, pages integer, series text,
+             language text, md5 text, total_read_time integer, total_read_pages integer
+         );
+         CREATE TABLE page_stat_data (
+             id_book integer, page integer NOT NULL DEFAULT 0,
+             start_time integer NOT NULL DEFAULT 0, duration integer NOT NULL DEFAULT 0,
+             total_pages integer NOT NULL DEFAULT 0,
+             UNIQUE (id_book, page, start_time),
+             FOREIGN KEY(id_book) REFERENCES book(id)
+         );
+         CREATE INDEX page_stat_data_start_time ON page_stat_data(start_time);",
+    )
+    .context("Failed to create KOReader schema")?;
+
+    let books = [BIBLE, TREASURY].into_iter().chain(OTHER_BOOKS);
+    for (index, (title, authors, pages)) in books.enumerate() {
+        conn.execute(
+            "INSERT INTO book VALUES (?1, ?2, ?3, 0, 0, 0, ?4, NULL, 'en', ?5, 0, 0)",
+            params![
+                index as i64 + 1,
+                title,
+                authors,
+                pages,
+                format!("{:032x}", index + 1)
+            ],
+        )?;
+    }
+
+    let mut writer = PageWriter {
+        conn: &conn,
+        rng: &mut rng,
+    };
+    let mut bible_page = 1;
+    let mut other_book = 0;
+    let mut other_page = 1;
+    for date in config.days() {
+        if writer.rng.random_bool(config.activity) {
+            let start = writer.start(date, 6, 30)?;
+            writer.read_treasury(date, start)?;
+        }
+
+        if writer.rng.random_bool(config.activity) {
+            let start = writer.start(date, 7, 30)?;
+            let minutes = writer.rng.random_range(8..30);
+            bible_page = writer.read(BIBLE_ID, BIBLE.2, bible_page, start, minutes)?;
+            if bible_page > BIBLE.2 {
+                bible_page = 1;
+            }
+        }
+
+        if other_book < OTHER_BOOKS.len() && writer.rng.random_bool(config.activity * 0.4) {
+            let start = writer.start(date, 20, 30)?;
+            let minutes = writer.rng.random_range(15..50);
+            let pages = OTHER_BOOKS[other_book].2;
+            let id = FIRST_OTHER_BOOK_ID + other_book as i64;
+            other_page = writer.read(id, pages, other_page, start, minutes)?;
+            if other_page > pages {
+                other_book += 1;
+                other_page = 1;
+            }
+        }
+    }
+
+    conn.execute_batch(
+        "UPDATE book SET
+             total_read_time = (SELECT COALESCE(SUM(duration), 0) FROM page_stat_data WHERE id_book = book.id),
+             total_read_pages = (SELECT COUNT(DISTINCT page) FROM page_stat_data WHERE id_book = book.id),
+             last_open = (SELECT COALESCE(MAX(start_time), 0) FROM page_stat_data WHERE id_book = book.id);",
+    )?;
+
+    conn.commit()?;
+    Ok(())
+}
+
+/// Writes the page views of reading sessions
+struct PageWriter<'a> {
+    conn: &'a Connection,
+    rng: &'a mut StdRng,
+}
+
+impl PageWriter<'_> {
+    /// Random start of a session within an hour of the given time
+    fn start(&mut self, date: NaiveDate, hour: u32, minute: u32) -> Result<DateTime<Utc>> {
+        let start = local_time(date, hour, minute)?;
+        Ok(start + Duration::minutes(self.rng.random_range(0..60)))
+    }
+
+    /// Reads the Treasury's pages of the day
+    fn read_treasury(&mut self, date: NaiveDate, start: DateTime<Utc>) -> Result<()> {
+        let first_page = 20 + i64::from(date.ordinal0()) * 3;
+        let minutes = self.rng.random_range(5..15);
+        self.read(TREASURY_ID, TREASURY.2, first_page, start, minutes)?;
+        Ok(())
+    }
+
+    /// Reads from a page for about the given number of minutes, stopping at the end of the
+    /// book, and returns the page to continue from
+    fn read(
+        &mut self,
+        book: i64,
+        total_pages: i64,
+        mut page: i64,
+        start: DateTime<Utc>,
+        minutes: i64,
+    ) -> Result<i64> {
+        let end = start + Duration::minutes(minutes);
+        let mut time = start;
+        while time < end && page <= total_pages && is_past(time) {
+            let duration = self.rng.random_range(40..200);
+            self.conn
+                .prepare_cached("INSERT INTO page_stat_data VALUES (?1, ?2, ?3, ?4, ?5)")?
+                .execute(params![book, page, time.timestamp(), duration, total_pages])?;
+            time += Duration::seconds(duration + self.rng.random_range(1..5));
+            page += 1;
+        }
+        Ok(page)
+    }
+}
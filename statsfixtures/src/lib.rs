@@ -0,0 +1,321 @@
+//! Synthetic data sources for tests, benchmarks and the demo
+//!
+//! Writes an Anki collection, a KOReader statistics database, a Proseuche database and an
+//! Arc Timeline export with plausible activity between two dates, so that the project can be
+//! run and exercised without anyone's personal data. The data is random but reproducible: the
+//! same configuration always gives the same files.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use statsfixtures::{FixtureConfig, generate};
+//!
+//! let config = FixtureConfig::last_days(90)?;
+//! let paths = generate("fixtures".as_ref(), &config)?;
+//! println!("Anki collection at {}", paths.anki.display());
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+pub mod anki;
+pub mod arc;
+pub mod koreader;
+pub mod proseuche;
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Days, NaiveDate, NaiveTime, Utc};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use statsutils::{get_today_date, local_datetime_to_ms};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// What to generate: the date range, the size of the Anki collection and how regular the
+/// activity is
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixtureConfig {
+    /// First day with activity
+    pub start: NaiveDate,
+    /// Last day with activity; activity later than the current time is left out
+    pub end: NaiveDate,
+    /// Number of Bible verse notes in the Anki collection
+    pub verses: usize,
+    /// Chance of each source having activity on a day, between 0 and 1
+    pub activity: f64,
+    /// Seed of the random data
+    pub seed: u64,
+}
+
+impl FixtureConfig {
+    /// Default number of Bible verse notes
+    pub const DEFAULT_VERSES: usize = 150;
+
+    /// Default chance of activity on a day
+    pub const DEFAULT_ACTIVITY: f64 = 0.85;
+
+    /// Most Bible verse notes; well below the number of distinct references, so that picking
+    /// random unique ones stays quick
+    pub const MAX_VERSES: usize = 5000;
+
+    /// Configuration with activity over the given number of days, ending today
+    pub fn last_days(days: i64) -> Result<Self> {
+        let today = NaiveDate::parse_from_str(&get_today_date()?, "%Y-%m-%d")
+            .context("Failed to parse today's date")?;
+        Self::days_ending(today, days)
+    }
+
+    /// Configuration with activity over the given number of days, ending on the given date
+    ///
+    /// # Errors
+    /// Returns an error if `days` is less than 1 or reaches before the earliest date
+    pub fn days_ending(end: NaiveDate, days: i64) -> Result<Self> {
+        let Some(before) = days
+            .checked_sub(1)
+            .and_then(|days| u64::try_from(days).ok())
+        else {
+            bail!("The number of days must be at least 1, got {}", days);
+        };
+        let start = end
+            .checked_sub_days(Days::new(before))
+            .context(format!("{} days before {} is out of range", days, end))?;
+        Ok(Self {
+            start,
+            end,
+            verses: Self::DEFAULT_VERSES,
+            activity: Self::DEFAULT_ACTIVITY,
+            seed: 1,
+        })
+    }
+
+    /// Checks that the date range is not empty, the activity is a probability and the
+    /// number of verses is at most [`FixtureConfig::MAX_VERSES`]
+    pub fn validate(&self) -> Result<()> {
+        if self.end < self.start {
+            bail!(
+                "The end date ({}) is before the start date ({})",
+                self.end,
+                self.start
+            );
+        }
+        if !(0.0..=1.0).contains(&self.activity) {
+            bail!(
+                "The activity must be between 0 and 1, got {}",
+                self.activity
+            );
+        }
+        if self.verses > Self::MAX_VERSES {
+            bail!(
+                "The number of verses must be at most {}, got {}",
+                Self::MAX_VERSES,
+                self.verses
+            );
+        }
+        Ok(())
+    }
+
+    /// Days from the start to the end date
+    pub fn days(&self) -> impl Iterator<Item = NaiveDate> + use<> {
+        self.start.iter_days().take_while({
+            let end = self.end;
+            move |date| *date <= end
+        })
+    }
+
+    /// Random generator of a source, seeded so that every source gets its own sequence
+    fn rng(&self, source: u64) -> StdRng {
+        StdRng::seed_from_u64(self.seed ^ source.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+    }
+}
+
+/// Paths of the generated sources
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixturePaths {
+    /// Anki collection.anki2 database
+    pub anki: PathBuf,
+    /// KOReader statistics.sqlite3 database
+    pub koreader: PathBuf,
+    /// Proseuche database.sqlite file
+    pub proseuche: PathBuf,
+    /// Arc Timeline export directory
+    pub arc: PathBuf,
+}
+
+impl FixturePaths {
+    /// Standard file names of the sources in a directory
+    pub fn in_dir(dir: &Path) -> Self {
+        Self {
+            anki: dir.join("collection.anki2"),
+            koreader: dir.join("statistics.sqlite3"),
+            proseuche: dir.join("database.sqlite"),
+            arc: dir.join("arc"),
+        }
+    }
+}
+
+/// Writes every source into the directory, replacing any earlier ones
+pub fn generate(dir: &Path, config: &FixtureConfig) -> Result<FixturePaths> {
+    config.validate()?;
+    fs::create_dir_all(dir).context(format!("Failed to create directory: {}", dir.display()))?;
+
+    let paths = FixturePaths::in_dir(dir);
+    for database in [&paths.anki, &paths.koreader, &paths.proseuche] {
+        if database.exists() {
+            fs::remove_file(database).context(format!(
+                "Failed to remove old database: {}",
+                database.display()
+            ))?;
+        }
+    }
+    if paths.arc.exists() {
+        fs::remove_dir_all(&paths.arc).context(format!(
+            "Failed to remove old export: {}",
+            paths.arc.display()
+        ))?;
+    }
+
+    anki::write_collection(&paths.anki, config)?;
+    koreader::write_database(&paths.koreader, config)?;
+    proseuche::write_database(&paths.proseuche, config)?;
+    arc::write_export(&paths.arc, config)?;
+
+    Ok(paths)
+}
+
+/// The instant of a local time on a date, in the configured timezone
+fn local_time(date: NaiveDate, hour: u32, minute: u32) -> Result<DateTime<Utc>> {
+    let time = NaiveTime::from_hms_opt(hour, minute, 0)
+        .context(format!("Invalid time of day: {}:{:02}", hour, minute))?;
+    let ms = local_datetime_to_ms(date.and_time(time))?;
+    DateTime::from_timestamp_millis(ms).context(format!("Timestamp out of range: {}", ms))
+}
+
+/// Whether an activity starting at this time has already happened
+fn is_past(time: DateTime<Utc>) -> bool {
+    time <= Utc::now()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use tempfile::TempDir;
+
+    fn config() -> FixtureConfig {
+        let end = NaiveDate::parse_from_str(&get_today_date().unwrap(), "%Y-%m-%d").unwrap()
+            - Duration::days(1);
+        FixtureConfig {
+            start: end - Duration::days(59),
+            end,
+            verses: 40,
+            activity: FixtureConfig::DEFAULT_ACTIVITY,
+            seed: 7,
+        }
+    }
+
+    fn path(path: &Path) -> &str {
+        path.to_str().unwrap()
+    }
+
+    #[test]
+    fn test_generated_sources_are_readable() {
+        let dir = TempDir::new().unwrap();
+        let paths = generate(dir.path(), &config()).unwrap();
+
+        let bible = ankistats::get_bible_stats(path(&paths.anki)).unwrap();
+        assert!(bible.total_mature_passages() > 0);
+        let warnings = ankistats::get_parse_warnings(path(&paths.anki)).unwrap();
+        assert_eq!(warnings.len(), 1, "{:?}", warnings);
+        assert!(
+            ankistats::get_streaks(path(&paths.anki))
+                .unwrap()
+                .days_studied
+                > 30
+        );
+
+        let reading = readingstats::get_last_30_days_stats(path(&paths.koreader)).unwrap();
+        assert!(reading.iter().filter(|day| day.minutes > 0.0).count() > 15);
+        let completed = readingstats::get_completed_books(path(&paths.koreader), None).unwrap();
+        assert!(!completed.is_empty());
+
+        let prayer = prayerstats::get_last_30_days_stats(path(&paths.proseuche)).unwrap();
+        assert!(prayer.iter().filter(|day| day.minutes > 0.0).count() > 15);
+
+        let church = arcstats::get_last_n_weeks_stats(path(&paths.arc), 8).unwrap();
+        assert!(church.iter().filter(|week| week.minutes > 0.0).count() > 4);
+    }
+
+    #[test]
+    fn test_review_forecast() {
+        let dir = TempDir::new().unwrap();
+        let paths = generate(dir.path(), &config()).unwrap();
+
+        let forecast = ankistats::get_review_forecast(path(&paths.anki), 7).unwrap();
+        assert_eq!(forecast.days, 7);
+        assert_eq!(forecast.daily.len(), 7);
+        assert_eq!(forecast.daily[0].date, get_today_date().unwrap());
+        assert_eq!(
+            forecast.due_reviews,
+            forecast
+                .daily
+                .iter()
+                .map(|day| day.due_reviews)
+                .sum::<i64>()
+        );
+        assert!(forecast.due_reviews > 0);
+        assert!(forecast.average_seconds_per_review > 0.0);
+        assert_eq!(
+            forecast.projected_minutes,
+            forecast.due_reviews as f64 * forecast.average_seconds_per_review / 60.0
+        );
+    }
+
+    #[test]
+    fn test_same_config_gives_same_data() {
+        let first = TempDir::new().unwrap();
+        let second = TempDir::new().unwrap();
+        generate(first.path(), &config()).unwrap();
+        generate(second.path(), &config()).unwrap();
+
+        for name in [
+            "collection.anki2",
+            "statistics.sqlite3",
+            "database.sqlite",
+            "arc/places/A.json",
+            "arc/metadata.json",
+        ] {
+            assert_eq!(
+                fs::read(first.path().join(name)).unwrap(),
+                fs::read(second.path().join(name)).unwrap(),
+                "{} differs",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_rejects_empty_range() {
+        let mut config = config();
+        config.end = config.start - Duration::days(1);
+        assert!(config.validate().is_err());
+
+        let mut config = self::config();
+        config.activity = 1.5;
+        assert!(config.validate().is_err());
+
+        let mut config = self::config();
+        config.verses = FixtureConfig::MAX_VERSES + 1;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_days_ending() {
+        let end = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let config = FixtureConfig::days_ending(end, 30).unwrap();
+        assert_eq!(config.start, NaiveDate::from_ymd_opt(2024, 2, 1).unwrap());
+        assert_eq!(config.days().count(), 30);
+        assert_eq!(FixtureConfig::days_ending(end, 1).unwrap().start, end);
+
+        assert!(FixtureConfig::days_ending(end, 0).is_err());
+        assert!(FixtureConfig::days_ending(end, i64::MIN).is_err());
+        assert!(FixtureConfig::days_ending(end, i64::MAX).is_err());
+    }
+}
@@ -0,0 +1,138 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use clap::Parser;
+use statsfixtures::{FixtureConfig, FixturePaths, generate};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// API key of the demo user in the generated users.toml
+const DEMO_API_KEY: &str = "demo-api-key";
+
+#[derive(Parser)]
+#[command(name = "genfixtures")]
+#[command(
+    about = "Generate synthetic Anki, KOReader, Proseuche and Arc Timeline data",
+    long_about = "Generate synthetic Anki, KOReader, Proseuche and Arc Timeline data, plus a \
+                  users.toml to run the backend on it (USERS_CONFIG_PATH=<dir>/users.toml)"
+)]
+#[command(version)]
+struct Cli {
+    /// Directory to write the data sources to, replacing earlier ones
+    #[arg(value_name = "OUTPUT_DIR", default_value = "fixtures")]
+    output_dir: PathBuf,
+
+    /// Number of days with data, ending with the end date
+    #[arg(long, default_value_t = 90, conflicts_with = "start")]
+    days: i64,
+
+    /// First day with data (YYYY-MM-DD)
+    #[arg(long)]
+    start: Option<NaiveDate>,
+
+    /// Last day with data (YYYY-MM-DD), today by default
+    #[arg(long)]
+    end: Option<NaiveDate>,
+
+    /// Number of Bible verse notes in the Anki collection, at most 5000
+    #[arg(long, default_value_t = FixtureConfig::DEFAULT_VERSES)]
+    verses: usize,
+
+    /// Chance of each source having activity on a day, between 0 and 1
+    #[arg(long, default_value_t = FixtureConfig::DEFAULT_ACTIVITY)]
+    activity: f64,
+
+    /// Seed of the random data; the same options always give the same data
+    #[arg(long, default_value_t = 1)]
+    seed: u64,
+}
+
+fn main() {
+    let cli = Cli::parse();
+    if let Err(e) = run(cli) {
+        eprintln!("Error: {:#}", e);
+        process::exit(1);
+    }
+}
+
+fn run(cli: Cli) -> Result<()> {
+    let mut config = match cli.end {
+        Some(end) => FixtureConfig::days_ending(end, cli.days),
+        None => FixtureConfig::last_days(cli.days),
+    }
+    .context("Invalid --days")?;
+    if let Some(start) = cli.start {
+        config.start = start;
+    }
+    config.verses = cli.verses;
+    config.activity = cli.activity;
+    config.seed = cli.seed;
+
+    let paths = generate(&cli.output_dir, &config)?;
+    let users_path = cli.output_dir.join("users.toml");
+    write_users_config(&users_path, &cli.output_dir, &paths)?;
+
+    println!(
+        "Generated data from {} to {} in {}:",
+        config.start,
+        config.end,
+        cli.output_dir.display()
+    );
+    println!("  Anki collection:     {}", paths.anki.display());
+    println!("  KOReader database:   {}", paths.koreader.display());
+    println!("  Proseuche database:  {}", paths.proseuche.display());
+    println!("  Arc Timeline export: {}", paths.arc.display());
+    println!();
+    println!("Run the backend on it with:");
+    println!(
+        "  USERS_CONFIG_PATH={} cargo run -p backend",
+        users_path.display()
+    );
+    println!(
+        "and authenticate with \"Authorization: Bearer {}\"",
+        DEMO_API_KEY
+    );
+
+    Ok(())
+}
+
+/// Writes a users.toml with a demo user reading the generated sources
+fn write_users_config(path: &Path, dir: &Path, paths: &FixturePaths) -> Result<()> {
+    let dir = fs::canonicalize(dir).context(format!("Failed to resolve {}", dir.display()))?;
+    let absolute = |path: &Path| -> Result<String> {
+        let name = path.file_name().context("Source path has no file name")?;
+        Ok(dir.join(name).display().to_string())
+    };
+
+    let config = format!(
+        r#"# Demo user reading the data generated by genfixtures
+[[users]]
+id = "demo"
+api_key = "{api_key}"
+anki_database_path = "{anki}"
+koreader_database_path = "{koreader}"
+proseuche_database_path = "{proseuche}"
+arcstats_export_path = "{arc}"
+stats_store_path = "{stats_store}"
+warehouse_path = "{warehouse}"
+
+[[users.goals]]
+name = "Bible reading"
+metric = "reading_minutes"
+weekly_target_minutes = 150
+
+[[users.goals]]
+name = "Prayer"
+metric = "prayer_minutes"
+weekly_target_minutes = 90
+"#,
+        api_key = DEMO_API_KEY,
+        anki = absolute(&paths.anki)?,
+        koreader = absolute(&paths.koreader)?,
+        proseuche = absolute(&paths.proseuche)?,
+        arc = absolute(&paths.arc)?,
+        stats_store = dir.join("lifestats.sqlite").display(),
+        warehouse = dir.join("warehouse.sqlite").display(),
+    );
+    fs::write(path, config).context(format!("Failed to write {}", path.display()))
+}
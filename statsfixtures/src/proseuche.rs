@@ -0,0 +1,61 @@
+//! Synthetic Proseuche database
+//!
+//! A morning prayer session on most days and a shorter evening one on some, stored like the
+//! current Proseuche schema with UTC timestamps and a generated duration column.
+
+use anyhow::{Context, Result};
+use chrono::Duration;
+use rand::RngExt;
+use rusqlite::{Connection, params};
+use std::path::Path;
+
+use crate::{FixtureConfig, is_past, local_time};
+
+/// Writes a database with prayer sessions between the start and end dates
+pub fn write_database(path: &Path, config: &FixtureConfig) -> Result<()> {
+    let mut rng = config.rng(3);
+    let mut db = Connection::open(path).context(format!(
+        "Failed to create Proseuche database: {}",
+        path.display()
+    ))?;
+    let conn = db.transaction()?;
+    conn.execute_batch(
+        "CREATE TABLE prayer_sessions (
+             id INTEGER PRIMARY KEY,
+             started_at TEXT NOT NULL,
+             ended_at TEXT NOT NULL,
+             duration_minutes REAL GENERATED ALWAYS AS
+                 ((julianday(ended_at) - julianday(started_at)) * 1440) VIRTUAL
+         );",
+    )
+    .context("Failed to create Proseuche schema")?;
+
+    let mut insert =
+        conn.prepare("INSERT INTO prayer_sessions (started_at, ended_at) VALUES (?1, ?2)")?;
+    for date in config.days() {
+        let mut sessions = Vec::new();
+        if rng.random_bool(config.activity) {
+            sessions.push((local_time(date, 6, 0)?, 30, 5..25));
+        }
+        if rng.random_bool(config.activity * 0.3) {
+            sessions.push((local_time(date, 21, 0)?, 60, 3..12));
+        }
+
+        for (earliest, spread, minutes) in sessions {
+            let start = earliest + Duration::minutes(rng.random_range(0..spread));
+            let end =
+                start + Duration::seconds(rng.random_range(minutes) * 60 + rng.random_range(0..60));
+            if !is_past(end) {
+                continue;
+            }
+            insert.execute(params![
+                start.format("%Y-%m-%d %H:%M:%S").to_string(),
+                end.format("%Y-%m-%d %H:%M:%S").to_string(),
+            ])?;
+        }
+    }
+    drop(insert);
+
+    conn.commit()?;
+    Ok(())
+}
@@ -0,0 +1,46 @@
+//! Models shared by the stats crates
+//!
+//! Sources that only record time (KOReader reading, Proseuche prayer, Arc Timeline church
+//! visits) report their daily and weekly series with `DayStats` and `WeekStats`, so a new
+//! field only has to be added here. Anki's series flatten them into their own models, which
+//! carry progress counts on top.
+
+use serde::{Deserialize, Serialize};
+use tabled::Tabled;
+
+/// Time recorded by a source on a single day
+#[derive(Debug, Clone, Serialize, Deserialize, Tabled)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DayStats {
+    /// Date in YYYY-MM-DD format
+    #[tabled(rename = "Date")]
+    pub date: String,
+    /// Time in minutes (reading, prayer or at church, depending on the source)
+    #[tabled(rename = "Minutes")]
+    pub minutes: f64,
+}
+
+/// Time recorded by a source in a single week
+#[derive(Debug, Clone, Serialize, Deserialize, Tabled)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct WeekStats {
+    /// Week start date (Sunday) in YYYY-MM-DD format
+    #[tabled(rename = "Week Start")]
+    pub week_start: String,
+    /// Time in minutes (reading, prayer or at church, depending on the source)
+    #[tabled(rename = "Minutes")]
+    pub minutes: f64,
+}
+
+/// Error response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ErrorResponse {
+    pub error: String,
+}
+
+impl ErrorResponse {
+    pub fn new(error: String) -> Self {
+        Self { error }
+    }
+}
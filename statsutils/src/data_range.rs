@@ -0,0 +1,103 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use crate::config;
+
+/// Range of recorded activity in a data source, used to judge data freshness
+#[derive(Debug, Clone, Default, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DataRange {
+    /// Earliest recorded activity (RFC 3339), if the source has any data
+    pub earliest: Option<String>,
+    /// Latest recorded activity (RFC 3339), if the source has any data
+    pub latest: Option<String>,
+    /// Days elapsed since the latest recorded activity
+    pub days_since_latest: Option<f64>,
+    /// Last-modified time of the underlying file or export (RFC 3339)
+    pub file_modified: Option<String>,
+}
+
+impl DataRange {
+    /// Builds a DataRange from activity timestamps (milliseconds) and the source file path
+    pub fn new<P: AsRef<Path>>(
+        earliest_ms: Option<i64>,
+        latest_ms: Option<i64>,
+        path: P,
+    ) -> Result<Self> {
+        let now_ms = Utc::now().timestamp_millis();
+
+        Ok(Self {
+            earliest: earliest_ms.map(timestamp_ms_to_rfc3339).transpose()?,
+            latest: latest_ms.map(timestamp_ms_to_rfc3339).transpose()?,
+            days_since_latest: latest_ms.map(|ms| (now_ms - ms) as f64 / 86_400_000.0),
+            file_modified: file_modified_ms(path)?
+                .map(timestamp_ms_to_rfc3339)
+                .transpose()?,
+        })
+    }
+
+    /// Date (YYYY-MM-DD, applies 4 AM rollover) of the earliest recorded activity, if any
+    pub fn earliest_date(&self) -> Result<Option<String>> {
+        self.earliest
+            .as_deref()
+            .map(|earliest| {
+                let dt = DateTime::parse_from_rfc3339(earliest)
+                    .context(format!("Failed to parse timestamp '{}'", earliest))?;
+                crate::timestamp_ms_to_date_string(dt.timestamp_millis())
+            })
+            .transpose()
+    }
+}
+
+/// Formats a timestamp as RFC 3339 in the configured timezone
+pub fn timestamp_ms_to_rfc3339(timestamp_ms: i64) -> Result<String> {
+    let tz: Tz = config::TIMEZONE
+        .parse()
+        .context("Failed to parse timezone from config")?;
+
+    let dt = tz
+        .timestamp_millis_opt(timestamp_ms)
+        .single()
+        .context("Failed to convert timestamp to datetime")?;
+
+    Ok(dt.to_rfc3339())
+}
+
+/// Formats the current time as RFC 3339 in the configured timezone
+pub fn now_rfc3339() -> Result<String> {
+    timestamp_ms_to_rfc3339(Utc::now().timestamp_millis())
+}
+
+/// Returns the last-modified time of a file in milliseconds, or None if it doesn't exist
+///
+/// SQLite databases in WAL mode write to a `-wal` sidecar file before checkpointing,
+/// so the newer of the two modification times is used.
+pub fn file_modified_ms<P: AsRef<Path>>(path: P) -> Result<Option<i64>> {
+    let path = path.as_ref();
+    let mut wal_path = path.as_os_str().to_owned();
+    wal_path.push("-wal");
+
+    let mut latest_ms = None;
+    for candidate in [path, Path::new(&wal_path)] {
+        let Ok(metadata) = fs::metadata(candidate) else {
+            continue;
+        };
+        let modified = metadata.modified().context(format!(
+            "Failed to read modification time of {:?}",
+            candidate
+        ))?;
+        let modified_ms = modified
+            .duration_since(UNIX_EPOCH)
+            .context("File modification time is before the Unix epoch")?
+            .as_millis() as i64;
+
+        latest_ms = latest_ms.max(Some(modified_ms));
+    }
+
+    Ok(latest_ms)
+}
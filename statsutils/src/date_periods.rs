@@ -1,5 +1,5 @@
 use anyhow::{Context, Result};
-use chrono::{Datelike, Duration, Local, TimeZone};
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc};
 use chrono_tz::Tz;
 use std::collections::HashMap;
 
@@ -19,12 +19,27 @@ pub struct DatePeriod {
 impl DatePeriod {
     /// Returns DatePeriod for the last 30 days
     pub fn last_30_days() -> Result<Self> {
-        let (start_ms, _, _) = get_day_boundaries(29)?;
-        let (_, end_ms, _) = get_day_boundaries(0)?;
+        Self::last_n_days(30)
+    }
+
+    /// Returns DatePeriod for the last 12 weeks (Sunday to Sunday)
+    pub fn last_12_weeks() -> Result<Self> {
+        Self::last_n_weeks(12)
+    }
+
+    /// Returns DatePeriod for the last `days` days, ending with today
+    pub fn last_n_days(days: i32) -> Result<Self> {
+        Self::last_n_days_at(days, now_ms())
+    }
+
+    /// Returns DatePeriod for the last `days` days, ending with the day containing `now_ms`
+    pub fn last_n_days_at(days: i32, now_ms: i64) -> Result<Self> {
+        let (start_ms, _, _) = get_day_boundaries(days - 1, now_ms)?;
+        let (_, end_ms, _) = get_day_boundaries(0, now_ms)?;
 
         let mut dates = Vec::new();
-        for day_offset in (0..30).rev() {
-            let (_, _, date_str) = get_day_boundaries(day_offset)?;
+        for day_offset in (0..days).rev() {
+            let (_, _, date_str) = get_day_boundaries(day_offset, now_ms)?;
             dates.push(date_str);
         }
 
@@ -35,14 +50,20 @@ impl DatePeriod {
         })
     }
 
-    /// Returns DatePeriod for the last 12 weeks (Sunday to Sunday)
-    pub fn last_12_weeks() -> Result<Self> {
-        let (start_ms, _, _) = get_week_boundaries(11)?;
-        let (_, end_ms, _) = get_week_boundaries(0)?;
+    /// Returns DatePeriod for the last `weeks` weeks (Sunday to Sunday), ending with this week
+    pub fn last_n_weeks(weeks: i32) -> Result<Self> {
+        Self::last_n_weeks_at(weeks, now_ms())
+    }
+
+    /// Returns DatePeriod for the last `weeks` weeks (Sunday to Sunday), ending with the week
+    /// containing `now_ms`
+    pub fn last_n_weeks_at(weeks: i32, now_ms: i64) -> Result<Self> {
+        let (start_ms, _, _) = get_week_boundaries(weeks - 1, now_ms)?;
+        let (_, end_ms, _) = get_week_boundaries(0, now_ms)?;
 
         let mut dates = Vec::new();
-        for week_offset in (0..12).rev() {
-            let (_, _, week_start_str) = get_week_boundaries(week_offset)?;
+        for week_offset in (0..weeks).rev() {
+            let (_, _, week_start_str) = get_week_boundaries(week_offset, now_ms)?;
             dates.push(week_start_str);
         }
 
@@ -53,6 +74,35 @@ impl DatePeriod {
         })
     }
 
+    /// Returns DatePeriod for specific days (YYYY-MM-DD), sorted and without duplicates
+    ///
+    /// The boundaries span from the first to the end of the last day, so queries still cover
+    /// the days in between; only the listed dates get results.
+    pub fn for_dates(dates: &[String]) -> Result<Self> {
+        let mut days = dates
+            .iter()
+            .map(|date| {
+                NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                    .context(format!("Failed to parse date '{}'", date))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        days.sort();
+        days.dedup();
+        let (Some(first), Some(last)) = (days.first(), days.last()) else {
+            anyhow::bail!("No dates given");
+        };
+
+        let start_ms = date_start_ms(&first.to_string())?;
+        let after_last = *last + Duration::days(1);
+        let end_ms = date_start_ms(&after_last.to_string())?;
+
+        Ok(DatePeriod {
+            dates: days.iter().map(|day| day.to_string()).collect(),
+            start_ms,
+            end_ms,
+        })
+    }
+
     /// Builds results for all dates, using defaults for missing entries
     pub fn build_results<T, R>(
         self,
@@ -93,94 +143,492 @@ impl DatePeriod {
     }
 }
 
+/// Returns the current time in milliseconds
+///
+/// Functions taking a `now_ms` argument can be used instead where the system clock is not
+/// available (e.g. in WebAssembly).
+fn now_ms() -> i64 {
+    Utc::now().timestamp_millis()
+}
+
 /// Returns the start of today in milliseconds (applies 4 AM rollover)
 pub fn get_today_start_ms() -> Result<i64> {
-    let (today_start_ms, _, _) = get_day_boundaries(0)?;
+    let (today_start_ms, _, _) = get_day_boundaries(0, now_ms())?;
     Ok(today_start_ms)
 }
 
-/// Calculates day boundaries with 4 AM rollover
-/// Returns (start_ms, end_ms, date_str)
-fn get_day_boundaries(day_offset: i32) -> Result<(i64, i64, String)> {
+/// Returns today's date as YYYY-MM-DD (applies 4 AM rollover)
+pub fn get_today_date() -> Result<String> {
+    get_today_date_at(now_ms())
+}
+
+/// Returns the date of the day containing `now_ms` as YYYY-MM-DD (applies 4 AM rollover)
+pub fn get_today_date_at(now_ms: i64) -> Result<String> {
+    let (_, _, date_str) = get_day_boundaries(0, now_ms)?;
+    Ok(date_str)
+}
+
+/// Returns the start (Sunday) of the current week as YYYY-MM-DD (applies 4 AM rollover)
+pub fn get_current_week_start() -> Result<String> {
+    timestamp_ms_to_week_string(now_ms())
+}
+
+/// Returns the start of the given YYYY-MM-DD day in milliseconds (applies 4 AM rollover)
+pub fn date_start_ms(date: &str) -> Result<i64> {
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .context(format!("Failed to parse date '{}'", date))?;
+    day_start_ms(date)
+}
+
+/// Returns the start of a day in milliseconds (applies 4 AM rollover)
+///
+/// The rollover is counted in elapsed hours from midnight, matching
+/// `timestamp_ms_to_date_string`, so on daylight saving time changes the day starts at
+/// 3 AM or 5 AM on the clock.
+fn day_start_ms(date: NaiveDate) -> Result<i64> {
     let tz: Tz = config::TIMEZONE
         .parse()
         .context("Failed to parse timezone from config")?;
 
-    let now_in_tz = Local::now().with_timezone(&tz);
-
-    // Calculate the target date (today - day_offset)
-    let target_date = now_in_tz - Duration::days(day_offset as i64);
-
-    // Get start of that day at midnight
     let day_midnight = tz
-        .with_ymd_and_hms(
-            target_date.year(),
-            target_date.month(),
-            target_date.day(),
-            0,
-            0,
-            0,
-        )
+        .with_ymd_and_hms(date.year(), date.month(), date.day(), 0, 0, 0)
         .single()
-        .context("Failed to create target day's midnight")?;
+        .context("Failed to create day's midnight")?;
 
-    // Add rollover hours for day start
-    let day_start = day_midnight + Duration::hours(config::ROLLOVER_HOUR);
+    Ok((day_midnight + Duration::hours(config::ROLLOVER_HOUR)).timestamp_millis())
+}
 
-    // Next day start
-    let next_day_start = day_start + Duration::days(1);
+/// Converts a wall-clock datetime in the configured timezone to milliseconds
+///
+/// Ambiguous times (when clocks fall back) resolve to the earlier instant; times skipped when
+/// clocks spring forward are an error.
+pub fn local_datetime_to_ms(datetime: NaiveDateTime) -> Result<i64> {
+    let tz: Tz = config::TIMEZONE
+        .parse()
+        .context("Failed to parse timezone from config")?;
 
-    // Format date for output
-    let date_str = target_date.format("%Y-%m-%d").to_string();
+    let local = tz
+        .from_local_datetime(&datetime)
+        .earliest()
+        .context(format!("Time {} does not exist in {}", datetime, tz))?;
 
-    Ok((
-        day_start.timestamp_millis(),
-        next_day_start.timestamp_millis(),
-        date_str,
-    ))
+    Ok(local.timestamp_millis())
 }
 
-/// Calculates week boundaries (Sunday start, 4 AM rollover)
-/// Returns (start_ms, end_ms, week_start_str)
-fn get_week_boundaries(week_offset: i32) -> Result<(i64, i64, String)> {
+/// Returns how much of the week starting on the given YYYY-MM-DD date has passed,
+/// from 0.0 (not started) to 1.0 (over)
+pub fn week_elapsed_fraction(week_start: &str) -> Result<f64> {
+    let start_ms = date_start_ms(week_start)?;
+    let week_ms = Duration::weeks(1).num_milliseconds();
+    let elapsed_ms = now_ms() - start_ms;
+
+    Ok((elapsed_ms as f64 / week_ms as f64).clamp(0.0, 1.0))
+}
+
+/// Returns the day a timestamp is counted on, with timezone and rollover applied
+pub fn timestamp_ms_to_date(timestamp_ms: i64) -> Result<NaiveDate> {
     let tz: Tz = config::TIMEZONE
         .parse()
         .context("Failed to parse timezone from config")?;
 
-    let now_in_tz = Local::now().with_timezone(&tz);
+    // Convert timestamp to datetime in Chicago timezone
+    let dt = tz
+        .timestamp_millis_opt(timestamp_ms)
+        .single()
+        .context("Failed to convert timestamp to datetime")?;
 
-    // Calculate days since last Sunday (0 if today is Sunday)
-    let days_since_sunday = now_in_tz.weekday().num_days_from_sunday();
+    // Subtract rollover hours to get the logical date
+    Ok((dt - Duration::hours(config::ROLLOVER_HOUR)).date_naive())
+}
 
-    // Calculate the target Sunday (go back to most recent Sunday, then subtract week_offset weeks)
-    let target_date =
-        now_in_tz - Duration::days(days_since_sunday as i64) - Duration::weeks(week_offset as i64);
-
-    // Get start of that Sunday at midnight
-    let week_midnight = tz
-        .with_ymd_and_hms(
-            target_date.year(),
-            target_date.month(),
-            target_date.day(),
-            0,
-            0,
-            0,
-        )
+/// Returns the Sunday starting the week of a day
+fn week_start(date: NaiveDate) -> NaiveDate {
+    // Days are subtracted from the date rather than a datetime, which would land an hour off
+    // (possibly on Saturday) across a daylight saving time change
+    date - Duration::days(date.weekday().num_days_from_sunday() as i64)
+}
+
+/// Converts timestamp to date string with timezone and rollover applied
+pub fn timestamp_ms_to_date_string(timestamp_ms: i64) -> Result<String> {
+    Ok(timestamp_ms_to_date(timestamp_ms)?
+        .format("%Y-%m-%d")
+        .to_string())
+}
+
+/// Converts timestamp to week string (Sunday of that week)
+pub fn timestamp_ms_to_week_string(timestamp_ms: i64) -> Result<String> {
+    Ok(week_start(timestamp_ms_to_date(timestamp_ms)?)
+        .format("%Y-%m-%d")
+        .to_string())
+}
+
+/// Converts timestamp to the local clock hour (0-23) and the weekday counted from Sunday (0-6)
+///
+/// The hour is the wall-clock hour, while the weekday has the rollover applied so that it
+/// matches the day the timestamp is counted on (1 AM Sunday is still Saturday).
+pub fn timestamp_ms_to_hour_and_weekday(timestamp_ms: i64) -> Result<(u32, u32)> {
+    let tz: Tz = config::TIMEZONE
+        .parse()
+        .context("Failed to parse timezone from config")?;
+
+    let dt = tz
+        .timestamp_millis_opt(timestamp_ms)
         .single()
-        .context("Failed to create week's midnight")?;
+        .context("Failed to convert timestamp to datetime")?;
 
-    // Add rollover hours for week start
-    let week_start = week_midnight + Duration::hours(config::ROLLOVER_HOUR);
+    let adjusted_dt = dt - Duration::hours(config::ROLLOVER_HOUR);
 
-    // Next week start (7 days later)
-    let next_week_start = week_start + Duration::weeks(1);
+    Ok((dt.hour(), adjusted_dt.weekday().num_days_from_sunday()))
+}
 
-    // Format week start date for output
-    let week_start_str = target_date.format("%Y-%m-%d").to_string();
+/// Splits a span of time at the day boundaries (4 AM) it crosses
+///
+/// Returns the YYYY-MM-DD date and the milliseconds falling on it for each day the span
+/// touches, in order, so a session running from 3:30 to 4:30 counts half on each day.
+pub fn split_by_day(start_ms: i64, duration_ms: i64) -> Result<Vec<(String, i64)>> {
+    let end_ms = start_ms + duration_ms.max(0);
+    let mut parts = Vec::new();
+    let mut part_start_ms = start_ms;
+    loop {
+        let date = timestamp_ms_to_date_string(part_start_ms)?;
+        let next_date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")?
+            .succ_opt()
+            .context(format!("No day after {}", date))?;
+        let part_end_ms = end_ms.min(date_start_ms(&next_date.format("%Y-%m-%d").to_string())?);
+        parts.push((date, part_end_ms - part_start_ms));
+        if part_end_ms >= end_ms {
+            return Ok(parts);
+        }
+        part_start_ms = part_end_ms;
+    }
+}
+
+/// Splits a span of time at the week boundaries (Sunday 4 AM) it crosses
+///
+/// Returns the week start (Sunday, YYYY-MM-DD) and the milliseconds falling in it for each
+/// week the span touches, in order.
+pub fn split_by_week(start_ms: i64, duration_ms: i64) -> Result<Vec<(String, i64)>> {
+    let mut parts: Vec<(String, i64)> = Vec::new();
+    for (date, ms) in split_by_day(start_ms, duration_ms)? {
+        let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")?;
+        let week = week_start(date).format("%Y-%m-%d").to_string();
+        match parts.last_mut() {
+            Some((last, total)) if *last == week => *total += ms,
+            _ => parts.push((week, ms)),
+        }
+    }
+    Ok(parts)
+}
+
+/// Sums spans of time, given as (start, duration) in milliseconds, into minutes per day
+///
+/// Spans crossing the 4 AM rollover are divided between the days (see `split_by_day`).
+pub fn minutes_by_day(spans: &[(i64, i64)]) -> Result<HashMap<String, f64>> {
+    sum_minutes(spans, split_by_day)
+}
+
+/// Sums spans of time, given as (start, duration) in milliseconds, into minutes per week
+///
+/// Spans crossing the start of a week are divided between the weeks (see `split_by_week`).
+pub fn minutes_by_week(spans: &[(i64, i64)]) -> Result<HashMap<String, f64>> {
+    sum_minutes(spans, split_by_week)
+}
+
+/// Splits a (start, duration) span into parts keyed by day or week
+type SplitSpan = fn(i64, i64) -> Result<Vec<(String, i64)>>;
+
+fn sum_minutes(spans: &[(i64, i64)], split: SplitSpan) -> Result<HashMap<String, f64>> {
+    let mut totals: HashMap<String, f64> = HashMap::new();
+    for &(start_ms, duration_ms) in spans {
+        for (key, ms) in split(start_ms, duration_ms)? {
+            *totals.entry(key).or_default() += ms as f64 / 60_000.0;
+        }
+    }
+    Ok(totals)
+}
+
+/// Calculates day boundaries with 4 AM rollover
+/// Returns (start_ms, end_ms, date_str)
+///
+/// The rollover also applies to `now_ms`: before 4 AM, today is still the previous day.
+fn get_day_boundaries(day_offset: i32, now_ms: i64) -> Result<(i64, i64, String)> {
+    // Calculate the target date (today - day_offset)
+    let target_date = timestamp_ms_to_date(now_ms)? - Duration::days(day_offset as i64);
+
+    // The day ends when the next one starts, which is not 24 hours later on daylight saving
+    // time changes
+    let next_date = target_date + Duration::days(1);
 
     Ok((
-        week_start.timestamp_millis(),
-        next_week_start.timestamp_millis(),
-        week_start_str,
+        day_start_ms(target_date)?,
+        day_start_ms(next_date)?,
+        target_date.format("%Y-%m-%d").to_string(),
     ))
 }
+
+/// Calculates week boundaries (Sunday start, 4 AM rollover)
+/// Returns (start_ms, end_ms, week_start_str)
+///
+/// The rollover also applies to `now_ms`: before 4 AM on Sunday, the previous week is current.
+fn get_week_boundaries(week_offset: i32, now_ms: i64) -> Result<(i64, i64, String)> {
+    // Go back to the most recent Sunday, then subtract week_offset weeks
+    let target_date =
+        week_start(timestamp_ms_to_date(now_ms)?) - Duration::weeks(week_offset as i64);
+    let next_week_date = target_date + Duration::weeks(1);
+
+    Ok((
+        day_start_ms(target_date)?,
+        day_start_ms(next_week_date)?,
+        target_date.format("%Y-%m-%d").to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2025-01-08 (a Wednesday) 10:00 in America/Chicago
+    const WEDNESDAY_MORNING_MS: i64 = 1_736_352_000_000;
+
+    #[test]
+    fn test_periods_at_fixed_time() {
+        let days = DatePeriod::last_n_days_at(3, WEDNESDAY_MORNING_MS).unwrap();
+        assert_eq!(days.dates, vec!["2025-01-06", "2025-01-07", "2025-01-08"]);
+        assert_eq!(days.end_ms - days.start_ms, 3 * 24 * 60 * 60 * 1000);
+
+        let weeks = DatePeriod::last_n_weeks_at(2, WEDNESDAY_MORNING_MS).unwrap();
+        assert_eq!(weeks.dates, vec!["2024-12-29", "2025-01-05"]);
+
+        let sundays = ["2025-01-12", "2025-01-05", "2025-01-12"].map(String::from);
+        let sundays = DatePeriod::for_dates(&sundays).unwrap();
+        assert_eq!(sundays.dates, vec!["2025-01-05", "2025-01-12"]);
+        assert_eq!(sundays.end_ms - sundays.start_ms, 8 * 24 * 60 * 60 * 1000);
+        assert!(DatePeriod::for_dates(&[]).is_err());
+        assert!(DatePeriod::for_dates(&["2025-02-30".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_periods_apply_rollover() {
+        // 2025-01-08 02:00 in America/Chicago still belongs to Tuesday 2025-01-07
+        let early_morning_ms = WEDNESDAY_MORNING_MS - 8 * 60 * 60 * 1000;
+
+        assert_eq!(get_today_date_at(early_morning_ms).unwrap(), "2025-01-07");
+        let days = DatePeriod::last_n_days_at(1, early_morning_ms).unwrap();
+        assert_eq!(days.start_ms, date_start_ms("2025-01-07").unwrap());
+        assert_eq!(days.end_ms, date_start_ms("2025-01-08").unwrap());
+
+        // 2025-01-05 02:00, early on a Sunday, still belongs to the week of 2024-12-29
+        let early_sunday_ms = date_start_ms("2025-01-05").unwrap() - 2 * 60 * 60 * 1000;
+        let weeks = DatePeriod::last_n_weeks_at(1, early_sunday_ms).unwrap();
+        assert_eq!(weeks.dates, vec!["2024-12-29"]);
+        assert_eq!(weeks.start_ms, date_start_ms("2024-12-29").unwrap());
+        assert_eq!(weeks.end_ms, date_start_ms("2025-01-05").unwrap());
+    }
+
+    #[test]
+    fn test_timestamp_strings_apply_rollover() {
+        // 2025-01-05 03:00 in America/Chicago belongs to Saturday 2025-01-04
+        let before_rollover_ms = date_start_ms("2025-01-05").unwrap() - 60 * 60 * 1000;
+
+        assert_eq!(
+            timestamp_ms_to_date_string(before_rollover_ms).unwrap(),
+            "2025-01-04"
+        );
+        assert_eq!(
+            timestamp_ms_to_week_string(before_rollover_ms).unwrap(),
+            "2024-12-29"
+        );
+    }
+
+    #[test]
+    fn test_split_at_rollover() {
+        // 2025-01-05 (a Sunday) 3:30 to 4:30 in America/Chicago
+        let start_ms = date_start_ms("2025-01-05").unwrap() - 30 * 60 * 1000;
+        let hour_ms = 60 * 60 * 1000;
+
+        assert_eq!(
+            split_by_day(start_ms, hour_ms).unwrap(),
+            vec![
+                ("2025-01-04".to_string(), hour_ms / 2),
+                ("2025-01-05".to_string(), hour_ms / 2)
+            ]
+        );
+        assert_eq!(
+            split_by_week(start_ms, hour_ms).unwrap(),
+            vec![
+                ("2024-12-29".to_string(), hour_ms / 2),
+                ("2025-01-05".to_string(), hour_ms / 2)
+            ]
+        );
+        assert_eq!(
+            split_by_day(start_ms, 0).unwrap(),
+            vec![("2025-01-04".to_string(), 0)]
+        );
+
+        let minutes =
+            minutes_by_day(&[(start_ms, hour_ms), (start_ms + hour_ms, hour_ms)]).unwrap();
+        assert_eq!(minutes["2025-01-04"], 30.0);
+        assert_eq!(minutes["2025-01-05"], 90.0);
+        let minutes = minutes_by_week(&[(start_ms, 2 * 24 * hour_ms)]).unwrap();
+        assert_eq!(minutes["2025-01-05"], 48.0 * 60.0 - 30.0);
+    }
+
+    #[test]
+    fn test_hour_and_weekday() {
+        assert_eq!(
+            timestamp_ms_to_hour_and_weekday(WEDNESDAY_MORNING_MS).unwrap(),
+            (10, 3)
+        );
+
+        // 2025-01-05 03:00 is the Saturday before rollover, but still 3 AM on the clock
+        let before_rollover_ms = date_start_ms("2025-01-05").unwrap() - 60 * 60 * 1000;
+        assert_eq!(
+            timestamp_ms_to_hour_and_weekday(before_rollover_ms).unwrap(),
+            (3, 6)
+        );
+    }
+
+    #[test]
+    fn test_local_datetime_to_ms() {
+        let datetime =
+            NaiveDateTime::parse_from_str("2025-01-08 10:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        assert_eq!(
+            local_datetime_to_ms(datetime).unwrap(),
+            WEDNESDAY_MORNING_MS
+        );
+
+        // 2:30 AM was skipped when clocks sprang forward on 2025-03-09
+        let skipped =
+            NaiveDateTime::parse_from_str("2025-03-09 02:30:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        assert!(local_datetime_to_ms(skipped).is_err());
+    }
+
+    /// Property tests for the day and week boundaries, over random times and the hours around
+    /// daylight saving time transitions
+    mod properties {
+        use super::*;
+        use proptest::prelude::*;
+
+        const HOUR_MS: i64 = 60 * 60 * 1000;
+
+        /// 2000-01-01 to 2038-01-01
+        fn any_time() -> impl Strategy<Value = i64> {
+            946_684_800_000..2_145_916_800_000i64
+        }
+
+        /// Within a day and a half of clocks springing forward (second Sunday in March) or
+        /// falling back (first Sunday in November)
+        fn around_dst_transition() -> impl Strategy<Value = i64> {
+            (2007..2038i32, any::<bool>(), -36 * HOUR_MS..36 * HOUR_MS).prop_map(
+                |(year, spring, offset_ms)| {
+                    let (month, sunday) = if spring { (3, 2) } else { (11, 1) };
+                    let transition = NaiveDate::from_weekday_of_month_opt(
+                        year,
+                        month,
+                        chrono::Weekday::Sun,
+                        sunday,
+                    )
+                    .unwrap()
+                    .and_hms_opt(2, 0, 0)
+                    .unwrap();
+                    let tz: Tz = config::TIMEZONE.parse().unwrap();
+                    tz.from_local_datetime(&transition)
+                        .earliest()
+                        .unwrap_or_else(|| tz.from_utc_datetime(&transition))
+                        .timestamp_millis()
+                        + offset_ms
+                },
+            )
+        }
+
+        fn time() -> impl Strategy<Value = i64> {
+            prop_oneof![any_time(), around_dst_transition()]
+        }
+
+        fn parse(date: &str) -> NaiveDate {
+            NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+        }
+
+        fn next_start_ms(date: &str, days: i64) -> i64 {
+            date_start_ms(&(parse(date) + Duration::days(days)).to_string()).unwrap()
+        }
+
+        proptest! {
+            #[test]
+            fn timestamps_fall_in_their_day(timestamp_ms in time()) {
+                let date = timestamp_ms_to_date_string(timestamp_ms).unwrap();
+
+                prop_assert!(date_start_ms(&date).unwrap() <= timestamp_ms);
+                prop_assert!(timestamp_ms < next_start_ms(&date, 1));
+            }
+
+            #[test]
+            fn timestamps_fall_in_their_week(timestamp_ms in time()) {
+                let week = timestamp_ms_to_week_string(timestamp_ms).unwrap();
+
+                prop_assert_eq!(parse(&week).weekday(), chrono::Weekday::Sun);
+                prop_assert!(date_start_ms(&week).unwrap() <= timestamp_ms);
+                prop_assert!(timestamp_ms < next_start_ms(&week, 7));
+            }
+
+            #[test]
+            fn day_boundaries_are_contiguous(now_ms in time(), day_offset in 1..60i32) {
+                let (start_ms, end_ms, date) = get_day_boundaries(day_offset, now_ms).unwrap();
+                let (next_start_ms, _, next_date) =
+                    get_day_boundaries(day_offset - 1, now_ms).unwrap();
+
+                prop_assert_eq!(end_ms, next_start_ms);
+                prop_assert_eq!(parse(&date).succ_opt().unwrap(), parse(&next_date));
+                prop_assert_eq!(start_ms, date_start_ms(&date).unwrap());
+                prop_assert_eq!(timestamp_ms_to_date_string(start_ms).unwrap(), date.clone());
+                prop_assert_eq!(timestamp_ms_to_date_string(end_ms - 1).unwrap(), date);
+            }
+
+            #[test]
+            fn week_boundaries_are_contiguous(now_ms in time(), week_offset in 1..20i32) {
+                let (start_ms, end_ms, week) = get_week_boundaries(week_offset, now_ms).unwrap();
+                let (next_start_ms, _, next_week) =
+                    get_week_boundaries(week_offset - 1, now_ms).unwrap();
+
+                prop_assert_eq!(end_ms, next_start_ms);
+                prop_assert_eq!(parse(&week).weekday(), chrono::Weekday::Sun);
+                prop_assert_eq!(parse(&week) + Duration::weeks(1), parse(&next_week));
+                prop_assert_eq!(start_ms, date_start_ms(&week).unwrap());
+                prop_assert_eq!(timestamp_ms_to_week_string(start_ms).unwrap(), week.clone());
+                prop_assert_eq!(timestamp_ms_to_week_string(end_ms - 1).unwrap(), week);
+            }
+
+            #[test]
+            fn current_day_and_week_contain_now(now_ms in time()) {
+                let (start_ms, end_ms, date) = get_day_boundaries(0, now_ms).unwrap();
+                prop_assert!(start_ms <= now_ms && now_ms < end_ms);
+                prop_assert_eq!(date, timestamp_ms_to_date_string(now_ms).unwrap());
+
+                let (start_ms, end_ms, week) = get_week_boundaries(0, now_ms).unwrap();
+                prop_assert!(start_ms <= now_ms && now_ms < end_ms);
+                prop_assert_eq!(week, timestamp_ms_to_week_string(now_ms).unwrap());
+            }
+
+            #[test]
+            fn periods_list_every_day_and_week_once(now_ms in time(), count in 1..60i32) {
+                let days = DatePeriod::last_n_days_at(count, now_ms).unwrap();
+                prop_assert_eq!(days.dates.len(), count as usize);
+                prop_assert!(days
+                    .dates
+                    .windows(2)
+                    .all(|pair| parse(&pair[0]).succ_opt().unwrap() == parse(&pair[1])));
+                prop_assert_eq!(days.start_ms, date_start_ms(&days.dates[0]).unwrap());
+                prop_assert_eq!(days.end_ms, next_start_ms(days.dates.last().unwrap(), 1));
+
+                let weeks = DatePeriod::last_n_weeks_at(count, now_ms).unwrap();
+                prop_assert_eq!(weeks.dates.len(), count as usize);
+                prop_assert!(weeks
+                    .dates
+                    .windows(2)
+                    .all(|pair| parse(&pair[0]) + Duration::weeks(1) == parse(&pair[1])));
+                prop_assert_eq!(weeks.start_ms, date_start_ms(&weeks.dates[0]).unwrap());
+                prop_assert_eq!(weeks.end_ms, next_start_ms(weeks.dates.last().unwrap(), 7));
+            }
+        }
+    }
+}
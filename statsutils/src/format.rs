@@ -0,0 +1,300 @@
+//! Locale-aware formatting of numbers, distances and times
+//!
+//! The display format defaults to the conventions of the current locale and can be adjusted
+//! through UNITS, DECIMAL_SEPARATOR, THOUSANDS_SEPARATOR and TIME_FORMAT (or per user in the
+//! backend). Code formatting for one user runs inside `with_display_format`, everything else
+//! uses the process-wide format.
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::cell::Cell;
+use std::sync::RwLock;
+
+use crate::i18n::{locale, locale_from_env, Locale};
+
+/// Meters per mile
+const METERS_PER_MILE: f64 = 1609.344;
+
+/// Feet per meter
+const FEET_PER_METER: f64 = 3.28084;
+
+/// Unit system of distances
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnitSystem {
+    /// Meters and kilometers
+    #[default]
+    Metric,
+    /// Feet and miles
+    Imperial,
+}
+
+impl std::str::FromStr for UnitSystem {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "metric" => Ok(UnitSystem::Metric),
+            "imperial" => Ok(UnitSystem::Imperial),
+            _ => bail!("Unknown unit system '{}' (expected metric or imperial)", s),
+        }
+    }
+}
+
+/// Clock used for times of day
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum TimeFormat {
+    /// "14:30"
+    #[default]
+    #[serde(rename = "24h")]
+    TwentyFourHour,
+    /// "2:30 PM"
+    #[serde(rename = "12h")]
+    TwelveHour,
+}
+
+impl std::str::FromStr for TimeFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "24h" => Ok(TimeFormat::TwentyFourHour),
+            "12h" => Ok(TimeFormat::TwelveHour),
+            _ => bail!("Unknown time format '{}' (expected 24h or 12h)", s),
+        }
+    }
+}
+
+/// How numbers, distances and times are displayed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisplayFormat {
+    pub units: UnitSystem,
+    pub decimal_separator: char,
+    /// Separator between groups of three digits, if digits are grouped
+    pub thousands_separator: Option<char>,
+    pub time_format: TimeFormat,
+}
+
+impl DisplayFormat {
+    /// Conventions of a locale; English keeps numbers ungrouped, as in the JSON API
+    pub fn for_locale(locale: Locale) -> Self {
+        match locale {
+            Locale::English => Self {
+                units: UnitSystem::Metric,
+                decimal_separator: '.',
+                thousands_separator: None,
+                time_format: TimeFormat::TwentyFourHour,
+            },
+            Locale::German => Self {
+                units: UnitSystem::Metric,
+                decimal_separator: ',',
+                thousands_separator: Some('.'),
+                time_format: TimeFormat::TwentyFourHour,
+            },
+        }
+    }
+
+    /// Formats a number with a fixed number of decimals, e.g. "1.234,5" in German
+    pub fn number(&self, value: f64, decimals: usize) -> String {
+        let formatted = format!("{:.*}", decimals, value);
+        let (sign, unsigned) = match formatted.strip_prefix('-') {
+            Some(unsigned) => ("-", unsigned),
+            None => ("", formatted.as_str()),
+        };
+        let (integer, fraction) = match unsigned.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (unsigned, None),
+        };
+
+        let mut result = sign.to_string();
+        for (i, digit) in integer.chars().enumerate() {
+            if i > 0 && (integer.len() - i) % 3 == 0 {
+                if let Some(separator) = self.thousands_separator {
+                    result.push(separator);
+                }
+            }
+            result.push(digit);
+        }
+        if let Some(fraction) = fraction {
+            result.push(self.decimal_separator);
+            result.push_str(fraction);
+        }
+        result
+    }
+
+    /// Formats a distance in meters, e.g. "850 m" and "12.3 km", or "500 ft" and "7.6 mi"
+    pub fn distance(&self, meters: f64) -> String {
+        match self.units {
+            UnitSystem::Metric if meters < 1000.0 => format!("{} m", self.number(meters, 0)),
+            UnitSystem::Metric => format!("{} km", self.number(meters / 1000.0, 1)),
+            UnitSystem::Imperial if meters < METERS_PER_MILE / 10.0 => {
+                format!("{} ft", self.number(meters * FEET_PER_METER, 0))
+            }
+            UnitSystem::Imperial => format!("{} mi", self.number(meters / METERS_PER_MILE, 1)),
+        }
+    }
+
+    /// Formats a time of day, e.g. "14:30" or "2:30 PM"
+    pub fn time(&self, hour: u32, minute: u32) -> String {
+        match self.time_format {
+            TimeFormat::TwentyFourHour => format!("{:02}:{:02}", hour, minute),
+            TimeFormat::TwelveHour => {
+                let suffix = if hour < 12 { "AM" } else { "PM" };
+                let hour = match hour % 12 {
+                    0 => 12,
+                    hour => hour,
+                };
+                format!("{}:{:02} {}", hour, minute, suffix)
+            }
+        }
+    }
+
+    /// chrono format string of a date and time of day, e.g. "%Y-%m-%d %H:%M"
+    pub fn datetime_pattern(&self) -> &'static str {
+        match self.time_format {
+            TimeFormat::TwentyFourHour => "%Y-%m-%d %H:%M",
+            TimeFormat::TwelveHour => "%Y-%m-%d %-I:%M %p",
+        }
+    }
+}
+
+/// Adjustments to the locale's display format, from the environment or a user's config
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct DisplayFormatConfig {
+    pub units: Option<UnitSystem>,
+    pub decimal_separator: Option<char>,
+    pub thousands_separator: Option<char>,
+    pub time_format: Option<TimeFormat>,
+}
+
+impl DisplayFormatConfig {
+    /// Reads UNITS, DECIMAL_SEPARATOR, THOUSANDS_SEPARATOR and TIME_FORMAT
+    pub fn from_env() -> Result<Self> {
+        Ok(Self {
+            units: match std::env::var("UNITS") {
+                Ok(units) => Some(units.parse().context("Invalid UNITS")?),
+                Err(_) => None,
+            },
+            decimal_separator: match std::env::var("DECIMAL_SEPARATOR") {
+                Ok(separator) => {
+                    Some(single_char(&separator).context("Invalid DECIMAL_SEPARATOR")?)
+                }
+                Err(_) => None,
+            },
+            thousands_separator: match std::env::var("THOUSANDS_SEPARATOR") {
+                Ok(separator) => {
+                    Some(single_char(&separator).context("Invalid THOUSANDS_SEPARATOR")?)
+                }
+                Err(_) => None,
+            },
+            time_format: match std::env::var("TIME_FORMAT") {
+                Ok(time_format) => Some(time_format.parse().context("Invalid TIME_FORMAT")?),
+                Err(_) => None,
+            },
+        })
+    }
+
+    /// Applies the adjustments to a display format
+    pub fn apply(&self, format: DisplayFormat) -> DisplayFormat {
+        DisplayFormat {
+            units: self.units.unwrap_or(format.units),
+            decimal_separator: self.decimal_separator.unwrap_or(format.decimal_separator),
+            thousands_separator: self.thousands_separator.or(format.thousands_separator),
+            time_format: self.time_format.unwrap_or(format.time_format),
+        }
+    }
+}
+
+/// Parses a separator consisting of exactly one character
+fn single_char(s: &str) -> Result<char> {
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => bail!("Expected a single character, got '{}'", s),
+    }
+}
+
+/// Process-wide display format; `None` follows the locale
+static DISPLAY_FORMAT: RwLock<Option<DisplayFormat>> = RwLock::new(None);
+
+thread_local! {
+    /// Display format of the user being formatted for on this thread
+    static SCOPED_FORMAT: Cell<Option<DisplayFormat>> = const { Cell::new(None) };
+}
+
+/// Sets the process-wide display format
+pub fn set_display_format(format: DisplayFormat) {
+    *DISPLAY_FORMAT.write().unwrap() = Some(format);
+}
+
+/// Display format in effect: the scoped one, the process-wide one, or the locale's
+pub fn display_format() -> DisplayFormat {
+    SCOPED_FORMAT
+        .with(Cell::get)
+        .or(*DISPLAY_FORMAT.read().unwrap())
+        .unwrap_or_else(|| DisplayFormat::for_locale(locale()))
+}
+
+/// Display format of the LOCALE adjusted by UNITS, DECIMAL_SEPARATOR, THOUSANDS_SEPARATOR and
+/// TIME_FORMAT
+pub fn display_format_from_env() -> Result<DisplayFormat> {
+    Ok(DisplayFormatConfig::from_env()?.apply(DisplayFormat::for_locale(locale_from_env()?)))
+}
+
+/// Runs `f` with a display format in effect on the current thread
+pub fn with_display_format<T>(format: DisplayFormat, f: impl FnOnce() -> T) -> T {
+    let previous = SCOPED_FORMAT.with(|scoped| scoped.replace(Some(format)));
+    let result = f();
+    SCOPED_FORMAT.with(|scoped| scoped.set(previous));
+    result
+}
+
+/// Formats a number with the current display format
+pub fn format_number(value: f64, decimals: usize) -> String {
+    display_format().number(value, decimals)
+}
+
+/// Formats a distance in meters with the current display format
+pub fn format_distance(meters: f64) -> String {
+    display_format().distance(meters)
+}
+
+/// Formats a time of day with the current display format
+pub fn format_time(hour: u32, minute: u32) -> String {
+    display_format().time(hour, minute)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_number_uses_separators() {
+        let english = DisplayFormat::for_locale(Locale::English);
+        let german = DisplayFormat::for_locale(Locale::German);
+
+        assert_eq!(english.number(1234.56, 1), "1234.6");
+        assert_eq!(german.number(1234.56, 1), "1.234,6");
+        assert_eq!(german.number(-1234567.0, 0), "-1.234.567");
+        assert_eq!(german.number(12.0, 2), "12,00");
+    }
+
+    #[test]
+    fn test_distance_and_time() {
+        let metric = DisplayFormat::for_locale(Locale::English);
+        let imperial = DisplayFormatConfig {
+            units: Some(UnitSystem::Imperial),
+            time_format: Some(TimeFormat::TwelveHour),
+            ..Default::default()
+        }
+        .apply(metric);
+
+        assert_eq!(metric.distance(850.0), "850 m");
+        assert_eq!(metric.distance(12_345.0), "12.3 km");
+        assert_eq!(imperial.distance(100.0), "328 ft");
+        assert_eq!(imperial.distance(12_345.0), "7.7 mi");
+        assert_eq!(metric.time(14, 5), "14:05");
+        assert_eq!(imperial.time(0, 30), "12:30 AM");
+        assert_eq!(imperial.time(14, 5), "2:05 PM");
+    }
+}
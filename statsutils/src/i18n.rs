@@ -0,0 +1,264 @@
+//! Translations of user-facing labels
+//!
+//! Labels are looked up by their English text in per-locale string tables, so untranslated
+//! labels fall back to English. The locale is process-wide and read from the LOCALE
+//! environment variable by the CLIs and the backend.
+
+use anyhow::{bail, Result};
+use std::sync::RwLock;
+
+/// Language of generated labels and reports
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    English,
+    German,
+}
+
+impl Locale {
+    /// Language code, e.g. "de"
+    pub fn code(&self) -> &'static str {
+        match self {
+            Locale::English => "en",
+            Locale::German => "de",
+        }
+    }
+
+    /// Translations from English into this locale
+    fn table(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Locale::English => &[],
+            Locale::German => GERMAN,
+        }
+    }
+}
+
+impl std::str::FromStr for Locale {
+    type Err = anyhow::Error;
+
+    /// Parses a language code, ignoring region and encoding (e.g. "de_DE.UTF-8")
+    fn from_str(s: &str) -> Result<Self> {
+        let language = s
+            .split(['_', '-', '.'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match language.as_str() {
+            "en" | "c" | "posix" => Ok(Locale::English),
+            "de" => Ok(Locale::German),
+            _ => bail!("Unsupported locale '{}' (supported: en, de)", s),
+        }
+    }
+}
+
+/// Current locale
+static LOCALE: RwLock<Locale> = RwLock::new(Locale::English);
+
+/// Sets the locale of labels and reports (process-wide)
+pub fn set_locale(locale: Locale) {
+    *LOCALE.write().unwrap() = locale;
+}
+
+/// Current locale of labels and reports
+pub fn locale() -> Locale {
+    *LOCALE.read().unwrap()
+}
+
+/// Reads the locale from the LOCALE environment variable (default English)
+pub fn locale_from_env() -> Result<Locale> {
+    match std::env::var("LOCALE") {
+        Ok(locale) => locale.parse(),
+        Err(_) => Ok(Locale::English),
+    }
+}
+
+/// Translates an English label into the current locale
+///
+/// Returns the label itself when there is no translation.
+pub fn tr(label: &str) -> &str {
+    locale()
+        .table()
+        .iter()
+        .find(|(english, _)| *english == label)
+        .map_or(label, |(_, translated)| translated)
+}
+
+/// German translations
+const GERMAN: &[(&str, &str)] = &[
+    // Units
+    ("min", "Min."),
+    ("hours", "Std."),
+    // Table headers
+    ("Date", "Datum"),
+    ("Week", "Woche"),
+    ("Book", "Buch"),
+    ("Chapter", "Kapitel"),
+    ("Anki (min)", "Anki (Min.)"),
+    ("Reading (min)", "Lesen (Min.)"),
+    ("Church (min)", "Kirche (Min.)"),
+    ("Prayer (min)", "Gebet (Min.)"),
+    ("Total (min)", "Gesamt (Min.)"),
+    ("Mature", "Gefestigt"),
+    ("Young", "Jung"),
+    ("Unseen", "Ungesehen"),
+    ("Suspended", "Ausgesetzt"),
+    ("Unique Verses", "Eindeutige Verse"),
+    // faithstats CLI
+    (
+        "FAITH STATS - LAST 30 DAYS",
+        "GLAUBENSSTATISTIK - LETZTE 30 TAGE",
+    ),
+    (
+        "FAITH STATS - LAST 12 WEEKS",
+        "GLAUBENSSTATISTIK - LETZTE 12 WOCHEN",
+    ),
+    ("SUMMARY", "ZUSAMMENFASSUNG"),
+    ("ANKI MEMORIZATION", "ANKI-AUSWENDIGLERNEN"),
+    ("BIBLE READING", "BIBELLESEN"),
+    ("CHURCH ATTENDANCE", "GOTTESDIENSTBESUCH"),
+    ("PRAYER", "GEBET"),
+    ("COMBINED TOTAL", "GESAMT"),
+    ("Total", "Gesamt"),
+    ("Average", "Durchschnitt"),
+    ("day", "Tag"),
+    ("week", "Woche"),
+    ("hrs", "Std."),
+    ("Days studied", "Tage gelernt"),
+    ("Days read", "Tage gelesen"),
+    ("Days prayed", "Tage gebetet"),
+    ("Days with any activity", "Tage mit Aktivität"),
+    ("Weeks studied", "Wochen gelernt"),
+    ("Weeks read", "Wochen gelesen"),
+    ("Weeks attended", "Wochen besucht"),
+    ("Weeks prayed", "Wochen gebetet"),
+    ("Weeks with any activity", "Wochen mit Aktivität"),
+    ("Passages", "Passagen"),
+    ("matured", "gefestigt"),
+    ("lost", "verloren"),
+    ("net", "netto"),
+    ("Projected next 7 days", "Prognose nächste 7 Tage"),
+    // ankistats CLI
+    ("(Format: Passages / Verses)", "(Format: Passagen / Verse)"),
+    ("OLD TESTAMENT", "ALTES TESTAMENT"),
+    ("NEW TESTAMENT", "NEUES TESTAMENT"),
+    ("GRAND TOTAL", "GESAMTSUMME"),
+    ("OT", "AT"),
+    ("Verses", "Verse"),
+    ("Unique", "Eindeutig"),
+    ("Unknown book", "Unbekanntes Buch"),
+    ("No statistics for", "Keine Statistik für"),
+    ("TODAY'S STUDY TIME", "HEUTIGE LERNZEIT"),
+    (
+        "DAILY STATS - LAST 30 DAYS",
+        "TAGESSTATISTIK - LETZTE 30 TAGE",
+    ),
+    (
+        "WEEKLY STATS - LAST 12 WEEKS",
+        "WOCHENSTATISTIK - LETZTE 12 WOCHEN",
+    ),
+    ("minutes", "Min."),
+    ("seconds", "Sek."),
+    ("Study Time", "Lernzeit"),
+    ("Average per day", "Durchschnitt pro Tag"),
+    ("Average per week", "Durchschnitt pro Woche"),
+    ("Average per review", "Durchschnitt pro Wiederholung"),
+    ("reviews", "Wiederholungen"),
+    ("s/review", "s/Wiederholung"),
+    ("out of", "von"),
+    ("Progress", "Fortschritt"),
+    ("Matured", "Gefestigt"),
+    ("Lost", "Verloren"),
+    ("Net", "Netto"),
+    ("Cumulative", "Kumuliert"),
+    ("passages", "Passagen"),
+    ("verses", "Verse"),
+    ("no activity", "keine Aktivität"),
+    ("Week of", "Woche vom"),
+    ("CHANGES FROM", "ÄNDERUNGEN VON"),
+    ("TO", "BIS"),
+    ("No changes", "Keine Änderungen"),
+    ("STUDY STREAKS", "LERNSERIEN"),
+    ("RECENT STREAKS", "LETZTE SERIEN"),
+    ("to", "bis"),
+    // readingstats CLI
+    (
+        "DAILY READING STATS - LAST 30 DAYS",
+        "LESESTATISTIK - LETZTE 30 TAGE",
+    ),
+    ("no reading", "nicht gelesen"),
+    ("Total Reading Time", "Gesamte Lesezeit"),
+    ("Days with reading", "Tage mit Lesen"),
+    ("READING PATTERNS", "LESEMUSTER"),
+    ("DAILY OFFICE", "STUNDENGEBET"),
+    ("LAST", "LETZTE"),
+    ("DAYS", "TAGE"),
+    ("By hour", "Nach Uhrzeit"),
+    ("By weekday", "Nach Wochentag"),
+    ("sessions", "Sitzungen"),
+    ("Sunday", "Sonntag"),
+    ("Monday", "Montag"),
+    ("Tuesday", "Dienstag"),
+    ("Wednesday", "Mittwoch"),
+    ("Thursday", "Donnerstag"),
+    ("Friday", "Freitag"),
+    ("Saturday", "Samstag"),
+    ("opened", "geöffnet"),
+    ("Days opened", "Tage geöffnet"),
+    ("Current streak", "Aktuelle Serie"),
+    ("Longest streak", "Längste Serie"),
+    ("days", "Tage"),
+    ("BOOKS COMPLETED IN", "BEENDETE BÜCHER IM JAHR"),
+    ("BOOKS COMPLETED", "BEENDETE BÜCHER"),
+    ("books", "Bücher"),
+    // prayerstats CLI
+    (
+        "prayerstats CLI - Coming soon!",
+        "prayerstats-CLI - Demnächst verfügbar!",
+    ),
+    (
+        "This CLI tool will provide commands for analyzing prayer statistics.",
+        "Dieses CLI-Werkzeug wird Befehle zur Auswertung der Gebetsstatistik bieten.",
+    ),
+    (
+        "Subcommands are not yet implemented.",
+        "Unterbefehle sind noch nicht implementiert.",
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_locale_ignores_region_and_encoding() {
+        assert_eq!("de_DE.UTF-8".parse::<Locale>().unwrap(), Locale::German);
+        assert_eq!("en-US".parse::<Locale>().unwrap(), Locale::English);
+        assert!("fr".parse::<Locale>().is_err());
+    }
+
+    #[test]
+    fn test_tr_follows_locale() {
+        set_locale(Locale::German);
+        assert_eq!(tr("Date"), "Datum");
+        assert_eq!(tr("OLD TESTAMENT"), "ALTES TESTAMENT");
+        assert_eq!(tr("Not a known label"), "Not a known label");
+
+        // The locale is process-wide, so leave it at the default for other tests
+        set_locale(Locale::English);
+        assert_eq!(tr("Date"), "Date");
+        assert_eq!(tr("OLD TESTAMENT"), "OLD TESTAMENT");
+    }
+
+    #[test]
+    fn test_german_labels_are_unique() {
+        for (index, (english, _)) in GERMAN.iter().enumerate() {
+            assert!(
+                !GERMAN[index + 1..]
+                    .iter()
+                    .any(|(other, _)| other == english),
+                "Duplicate translation of '{}'",
+                english
+            );
+        }
+    }
+}
@@ -1,6 +1,21 @@
 mod config;
+mod data_range;
 mod date_periods;
+pub mod format;
+pub mod i18n;
+mod partial_period;
+#[cfg(feature = "sqlite")]
+pub mod pool;
+#[cfg(feature = "sqlite")]
+mod schema;
+#[cfg(feature = "sqlite")]
 mod sqlite_functions;
 
+pub use config::{ROLLOVER_HOUR, TIMEZONE};
+pub use data_range::*;
 pub use date_periods::*;
+pub use partial_period::*;
+#[cfg(feature = "sqlite")]
+pub use schema::*;
+#[cfg(feature = "sqlite")]
 pub use sqlite_functions::*;
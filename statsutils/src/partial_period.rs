@@ -0,0 +1,83 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// How summaries treat the current day or week, which is still in progress
+///
+/// Counting today's few morning minutes as a full day understates the averages, so summaries
+/// leave the current period out unless asked otherwise.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum PartialPeriod {
+    /// Count the current period like any complete one
+    Include,
+    /// Leave the current period out of totals, averages and counts
+    #[default]
+    Exclude,
+}
+
+impl PartialPeriod {
+    /// Returns the periods a summary covers, given periods sorted oldest first
+    ///
+    /// With `Exclude`, the last period is dropped when `is_current` says it is still in progress.
+    pub fn complete<T>(self, periods: &[T], is_current: impl FnOnce(&T) -> bool) -> &[T] {
+        match (self, periods.split_last()) {
+            (Self::Exclude, Some((last, rest))) if is_current(last) => rest,
+            _ => periods,
+        }
+    }
+}
+
+impl fmt::Display for PartialPeriod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Include => write!(f, "include"),
+            Self::Exclude => write!(f, "exclude"),
+        }
+    }
+}
+
+impl FromStr for PartialPeriod {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "include" => Ok(Self::Include),
+            "exclude" => Ok(Self::Exclude),
+            _ => bail!(
+                "Unknown partial period handling '{}' (use include or exclude)",
+                s
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_complete_periods() {
+        let days = ["2025-01-06", "2025-01-07", "2025-01-08"];
+        let is_today = |day: &&str| *day == "2025-01-08";
+
+        assert_eq!(PartialPeriod::Exclude.complete(&days, is_today), &days[..2]);
+        assert_eq!(PartialPeriod::Include.complete(&days, is_today), &days[..]);
+        // Periods that end before today are all complete
+        assert_eq!(
+            PartialPeriod::Exclude.complete(&days[..2], is_today),
+            &days[..2]
+        );
+        assert!(PartialPeriod::Exclude
+            .complete(&[] as &[&str], is_today)
+            .is_empty());
+
+        assert_eq!(
+            "include".parse::<PartialPeriod>().unwrap(),
+            PartialPeriod::Include
+        );
+        assert!("partial".parse::<PartialPeriod>().is_err());
+    }
+}
@@ -0,0 +1,268 @@
+//! Read-only SQLite connections kept between queries
+//!
+//! Opening a database registers the custom functions and checks its schema, and a fresh
+//! connection starts with an empty statement cache. A `ConnectionPool` keeps a few idle
+//! connections to one file so that later queries can reuse them. The source apps replace
+//! their files while syncing, so the pool drops its connections whenever the file's
+//! modification time or size changes; connections still in use then are closed when
+//! returned.
+
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+use std::fmt;
+use std::fs;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Most idle connections kept per file
+pub const MAX_IDLE_CONNECTIONS: usize = 4;
+
+/// Idle connections to an SQLite file, reopened when the file changes
+///
+/// Cloning the pool is cheap and shares its connections.
+#[derive(Clone)]
+pub struct ConnectionPool {
+    path: Arc<str>,
+    state: Arc<Mutex<PoolState>>,
+}
+
+#[derive(Default)]
+struct PoolState {
+    /// Modification time and size of the file when the idle connections were opened
+    version: Option<(SystemTime, u64)>,
+    /// Incremented whenever the file changes, so that older connections are not reused
+    generation: u64,
+    idle: Vec<Connection>,
+}
+
+impl ConnectionPool {
+    /// Pool of connections to the file at `path`; nothing is opened until the first `get`
+    pub fn new(path: impl Into<Arc<str>>) -> Self {
+        Self {
+            path: path.into(),
+            state: Arc::default(),
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Takes an idle connection, or opens one with `open` if there is none or the file has
+    /// changed since the idle connections were opened
+    ///
+    /// `open` is expected to open the file read-only, register any custom functions and
+    /// check the schema, as the connection is reused as it is.
+    ///
+    /// # Errors
+    /// Returns an error if the file's metadata cannot be read or `open` fails
+    pub fn get(&self, open: impl FnOnce(&str) -> Result<Connection>) -> Result<PooledConnection> {
+        let metadata =
+            fs::metadata(&*self.path).context(format!("Failed to read {}", self.path))?;
+        let version = (
+            metadata
+                .modified()
+                .context(format!("Failed to read modification time of {}", self.path))?,
+            metadata.len(),
+        );
+
+        let (idle, generation) = {
+            let mut state = self.state.lock().unwrap();
+            if state.version != Some(version) {
+                state.idle.clear();
+                state.generation += 1;
+                state.version = Some(version);
+            }
+            (state.idle.pop(), state.generation)
+        };
+
+        let conn = match idle {
+            Some(conn) => conn,
+            None => open(&self.path)?,
+        };
+        Ok(PooledConnection {
+            conn: Some(conn),
+            pool: Some((self.clone(), generation)),
+        })
+    }
+
+    /// Number of idle connections
+    pub fn idle_count(&self) -> usize {
+        self.state.lock().unwrap().idle.len()
+    }
+
+    /// Takes back a connection opened for the given generation of the file
+    fn put(&self, conn: Connection, generation: u64) {
+        let mut state = self.state.lock().unwrap();
+        if state.generation == generation && state.idle.len() < MAX_IDLE_CONNECTIONS {
+            state.idle.push(conn);
+        }
+    }
+}
+
+impl fmt::Debug for ConnectionPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ConnectionPool").field(&self.path).finish()
+    }
+}
+
+/// A connection that goes back to its pool when dropped
+pub struct PooledConnection {
+    /// Always `Some` until dropped
+    conn: Option<Connection>,
+    pool: Option<(ConnectionPool, u64)>,
+}
+
+impl PooledConnection {
+    /// Wraps a connection that does not belong to a pool and is closed when dropped
+    pub fn unpooled(conn: Connection) -> Self {
+        Self {
+            conn: Some(conn),
+            pool: None,
+        }
+    }
+}
+
+impl Deref for PooledConnection {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().expect("connection taken before drop")
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        if let (Some(conn), Some((pool, generation))) = (self.conn.take(), self.pool.take()) {
+            pool.put(conn, generation);
+        }
+    }
+}
+
+/// An SQLite file to query: a path opened for a single query, or a pool of connections to
+/// it
+#[derive(Debug, Clone, Copy)]
+pub enum Database<'a> {
+    Path(&'a str),
+    Pool(&'a ConnectionPool),
+}
+
+impl<'a> Database<'a> {
+    pub fn path(&self) -> &'a str {
+        match self {
+            Database::Path(path) => path,
+            Database::Pool(pool) => pool.path(),
+        }
+    }
+
+    /// Opens a connection with `open`, or takes one from the pool
+    pub fn open(&self, open: impl FnOnce(&str) -> Result<Connection>) -> Result<PooledConnection> {
+        match self {
+            Database::Path(path) => Ok(PooledConnection::unpooled(open(path)?)),
+            Database::Pool(pool) => pool.get(open),
+        }
+    }
+}
+
+impl<'a> From<&'a str> for Database<'a> {
+    fn from(path: &'a str) -> Self {
+        Database::Path(path)
+    }
+}
+
+impl<'a> From<&'a String> for Database<'a> {
+    fn from(path: &'a String) -> Self {
+        Database::Path(path)
+    }
+}
+
+impl<'a> From<&'a ConnectionPool> for Database<'a> {
+    fn from(pool: &'a ConnectionPool) -> Self {
+        Database::Pool(pool)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn open(path: &str) -> Result<Connection> {
+        Ok(Connection::open(path)?)
+    }
+
+    #[test]
+    fn test_connections_are_reused() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("reuse.sqlite");
+        Connection::open(&path).unwrap();
+        let pool = ConnectionPool::new(path.to_str().unwrap());
+
+        let opened = Cell::new(0);
+        let counting_open = |path: &str| {
+            opened.set(opened.get() + 1);
+            open(path)
+        };
+
+        let first = pool.get(counting_open).unwrap();
+        let second = pool.get(counting_open).unwrap();
+        assert_eq!(opened.get(), 2);
+        drop(first);
+        drop(second);
+        assert_eq!(pool.idle_count(), 2);
+
+        // Returned connections are reused, along with the statements they cached
+        pool.get(counting_open)
+            .unwrap()
+            .prepare_cached("SELECT 1")
+            .unwrap();
+        let conn = pool.get(counting_open).unwrap();
+        assert_eq!(opened.get(), 2);
+        drop(conn);
+
+        // Replacing the file drops the idle connections
+        std::thread::sleep(Duration::from_millis(20));
+        let in_use = pool.get(counting_open).unwrap();
+        fs::remove_file(&path).unwrap();
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch("CREATE TABLE replaced (id INTEGER)")
+            .unwrap();
+        let conn = pool.get(counting_open).unwrap();
+        assert_eq!(opened.get(), 3);
+        assert_eq!(pool.idle_count(), 0);
+        let replaced: i64 = conn
+            .query_row("SELECT COUNT(*) FROM replaced", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(replaced, 0);
+
+        // A connection to the old file is closed instead of going back to the pool
+        drop(in_use);
+        assert_eq!(pool.idle_count(), 0);
+        drop(conn);
+        assert_eq!(pool.idle_count(), 1);
+    }
+
+    #[test]
+    fn test_idle_connections_are_capped() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("cap.sqlite");
+        Connection::open(&path).unwrap();
+        let pool = ConnectionPool::new(path.to_str().unwrap());
+
+        let connections: Vec<_> = (0..MAX_IDLE_CONNECTIONS + 2)
+            .map(|_| pool.get(open).unwrap())
+            .collect();
+        drop(connections);
+        assert_eq!(pool.idle_count(), MAX_IDLE_CONNECTIONS);
+
+        assert!(ConnectionPool::new("/nonexistent/db.sqlite")
+            .get(open)
+            .is_err());
+        assert_eq!(Database::from(&pool).path(), pool.path());
+        assert!(Database::from("/nonexistent/db.sqlite").open(open).is_err());
+    }
+}
@@ -0,0 +1,112 @@
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+use std::fmt;
+
+/// Error returned when a database lacks tables or columns the queries rely on
+///
+/// Usually means the file comes from a different (older or newer) version of the app, or is
+/// not the expected kind of database at all.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsupportedSchemaError {
+    /// Kind of database, e.g. "Anki"
+    pub database: String,
+    /// Missing tables ("table") and columns ("table.column")
+    pub missing: Vec<String>,
+}
+
+impl fmt::Display for UnsupportedSchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Unsupported {} database schema version: missing {}",
+            self.database,
+            self.missing.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for UnsupportedSchemaError {}
+
+/// Checks that every expected table exists with (at least) the expected columns
+///
+/// # Arguments
+/// * `conn` - Database connection to check
+/// * `database` - Kind of database, used in the error message
+/// * `expected` - Table names with the columns the queries use
+///
+/// # Errors
+/// Returns an `UnsupportedSchemaError` listing everything that is missing
+pub fn check_schema(conn: &Connection, database: &str, expected: &[(&str, &[&str])]) -> Result<()> {
+    let mut missing = Vec::new();
+
+    for &(table, columns) in expected {
+        // table_xinfo (unlike table_info) includes generated columns
+        let mut stmt = conn
+            .prepare_cached("SELECT name FROM pragma_table_xinfo(?1)")
+            .context("Failed to read database schema")?;
+        let existing = stmt
+            .query_map([table], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<String>, _>>()
+            .context(format!("Failed to read columns of table '{}'", table))?;
+
+        if existing.is_empty() {
+            missing.push(format!("table '{}'", table));
+            continue;
+        }
+        for &column in columns {
+            if !existing
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(column))
+            {
+                missing.push(format!("column '{}.{}'", table, column));
+            }
+        }
+    }
+
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(UnsupportedSchemaError {
+            database: database.to_string(),
+            missing,
+        }
+        .into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_schema() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE book (id INTEGER, title TEXT);
+             CREATE TABLE sessions (start INTEGER, minutes INTEGER GENERATED ALWAYS AS (start));",
+        )
+        .unwrap();
+
+        assert!(check_schema(&conn, "Test", &[("sessions", &["start", "minutes"])]).is_ok());
+
+        let error = check_schema(
+            &conn,
+            "Test",
+            &[
+                ("book", &["id", "title", "authors"]),
+                ("page_stat_data", &["page"]),
+            ],
+        )
+        .unwrap_err();
+        let error = error.downcast_ref::<UnsupportedSchemaError>().unwrap();
+        assert_eq!(
+            error.missing,
+            vec!["column 'book.authors'", "table 'page_stat_data'"]
+        );
+        assert_eq!(
+            error.to_string(),
+            "Unsupported Test database schema version: missing column 'book.authors', \
+             table 'page_stat_data'"
+        );
+    }
+}
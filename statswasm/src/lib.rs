@@ -0,0 +1,71 @@
+//! WebAssembly bindings for the web dashboard
+//!
+//! Exposes the Bible reference parsers, the date/week logic and the Arc Timeline models so
+//! the dashboard can validate references and recompute values client-side. WebAssembly has
+//! no system clock, so functions that depend on the current time take it as `now_ms`
+//! (e.g. `Date.now()`).
+//!
+//! JavaScript numbers are passed as `f64`; timestamps are milliseconds since the Unix epoch.
+
+use ankistats::book_name_parser::try_parse_book_name;
+use ankistats::verse_parser::try_count_verses_in_reference;
+use arcstats::models::Item;
+use statsutils::DatePeriod;
+use wasm_bindgen::prelude::*;
+
+/// Counts the verses in a Bible reference (e.g. "John 3:16-18" is 3 verses)
+#[wasm_bindgen(js_name = countVerses)]
+pub fn count_verses(reference: &str) -> Result<i64, JsError> {
+    try_count_verses_in_reference(reference).map_err(|e| JsError::new(&e))
+}
+
+/// Returns the book name of a Bible reference (e.g. "2 Timothy 3:16" is "2 Timothy")
+#[wasm_bindgen(js_name = parseBookName)]
+pub fn parse_book_name(reference: &str) -> Result<String, JsError> {
+    try_parse_book_name(reference).map_err(|e| JsError::new(&e))
+}
+
+/// Returns the YYYY-MM-DD day a timestamp belongs to (applies timezone and 4 AM rollover)
+#[wasm_bindgen(js_name = dateStringFromMs)]
+pub fn date_string_from_ms(timestamp_ms: f64) -> Result<String, JsError> {
+    statsutils::timestamp_ms_to_date_string(timestamp_ms as i64).map_err(to_js_error)
+}
+
+/// Returns the Sunday (YYYY-MM-DD) of the week a timestamp belongs to (applies timezone and
+/// 4 AM rollover)
+#[wasm_bindgen(js_name = weekStringFromMs)]
+pub fn week_string_from_ms(timestamp_ms: f64) -> Result<String, JsError> {
+    statsutils::timestamp_ms_to_week_string(timestamp_ms as i64).map_err(to_js_error)
+}
+
+/// Returns the dates (YYYY-MM-DD) of the last `days` days, ending with today
+#[wasm_bindgen(js_name = lastNDays)]
+pub fn last_n_days(days: i32, now_ms: f64) -> Result<Vec<String>, JsError> {
+    let period = DatePeriod::last_n_days_at(days, now_ms as i64).map_err(to_js_error)?;
+    Ok(period.dates)
+}
+
+/// Returns the week start dates (YYYY-MM-DD) of the last `weeks` weeks, ending with this week
+#[wasm_bindgen(js_name = lastNWeeks)]
+pub fn last_n_weeks(weeks: i32, now_ms: f64) -> Result<Vec<String>, JsError> {
+    let period = DatePeriod::last_n_weeks_at(weeks, now_ms as i64).map_err(to_js_error)?;
+    Ok(period.dates)
+}
+
+/// Sums the minutes of visits to a place in the contents of an Arc Timeline items file
+/// (`items/YYYY-MM.json`)
+#[wasm_bindgen(js_name = arcVisitMinutes)]
+pub fn arc_visit_minutes(items_json: &str, place_id: &str) -> Result<f64, JsError> {
+    let items: Vec<Item> = serde_json::from_str(items_json)?;
+
+    Ok(items
+        .iter()
+        .filter(|item| item.place_id() == Some(place_id))
+        .map(|item| item.duration_seconds() / 60.0)
+        .sum())
+}
+
+/// Converts an error chain into a JavaScript error
+fn to_js_error(e: anyhow::Error) -> JsError {
+    JsError::new(&format!("{:#}", e))
+}
@@ -0,0 +1,24 @@
+//! Google Takeout Location History Loader
+//!
+//! This library loads the monthly Semantic Location History files from a Google Takeout
+//! export (`Semantic Location History/2024/2024_JANUARY.json`, ...) into the same
+//! `Item`/`Place` model as arcstats, so Android users get the church and place stats too.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use arcstats::{PlaceProvider, get_last_n_weeks_stats_from};
+//! use takeoutstats::TakeoutHistory;
+//!
+//! let history = TakeoutHistory::open("path/to/Takeout").unwrap();
+//! let weeks = get_last_n_weeks_stats_from(&history, 12).unwrap();
+//! for week in weeks {
+//!     println!("{}: {:.0} minutes at church", week.week_start, week.minutes);
+//! }
+//! ```
+
+pub mod loader;
+pub mod models;
+
+pub use loader::{MonthFile, TakeoutHistory, convert_months, load_month};
+pub use models::SemanticMonth;
@@ -0,0 +1,442 @@
+use anyhow::{Context, Result, bail};
+use arcstats::models::{
+    BaseItem, Item, ItemVariant, ItemWithPlace, Place, TripDetails, VisitDetails,
+};
+use arcstats::{PlaceProvider, datetime_to_apple_timestamp};
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::models::{ActivitySegment, PlaceVisit, SemanticMonth};
+
+/// Where the Semantic Location History can be, relative to the configured path
+const SEMANTIC_DIRS: [&str; 4] = [
+    "",
+    "Semantic Location History",
+    "Location History/Semantic Location History",
+    "Location History (Timeline)/Semantic Location History",
+];
+
+/// Month names used in the file names (e.g. `2024_JANUARY.json`)
+const MONTHS: [&str; 12] = [
+    "JANUARY",
+    "FEBRUARY",
+    "MARCH",
+    "APRIL",
+    "MAY",
+    "JUNE",
+    "JULY",
+    "AUGUST",
+    "SEPTEMBER",
+    "OCTOBER",
+    "NOVEMBER",
+    "DECEMBER",
+];
+
+/// Source recorded on the converted items and places
+const SOURCE: &str = "Google Takeout";
+
+/// Visit radius used for places, since Takeout does not record one
+const DEFAULT_RADIUS_METERS: f64 = 50.0;
+
+/// A monthly Semantic Location History file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MonthFile {
+    /// Month in YYYY-MM format
+    pub year_month: String,
+    pub path: PathBuf,
+}
+
+/// A Google Takeout Location History export, providing visits like an Arc Timeline export
+#[derive(Debug, Clone)]
+pub struct TakeoutHistory {
+    months: Vec<MonthFile>,
+}
+
+impl TakeoutHistory {
+    /// Opens the Semantic Location History at or below the path
+    ///
+    /// The path can be the Takeout folder, its Location History folder, or the Semantic
+    /// Location History folder with the year folders.
+    ///
+    /// # Errors
+    /// Returns an error if no monthly files are found
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let Some(months) = find_month_files(path)? else {
+            bail!(
+                "No Google Takeout Semantic Location History found at {:?}",
+                path
+            );
+        };
+        Ok(Self { months })
+    }
+
+    /// Returns whether the path contains a Semantic Location History
+    pub fn is_takeout_export<P: AsRef<Path>>(path: P) -> bool {
+        matches!(find_month_files(path.as_ref()), Ok(Some(_)))
+    }
+
+    /// Monthly files in chronological order
+    pub fn months(&self) -> &[MonthFile] {
+        &self.months
+    }
+}
+
+impl PlaceProvider for TakeoutHistory {
+    fn name(&self) -> &'static str {
+        "Google Takeout"
+    }
+
+    fn load_items_with_places(&self) -> Result<Vec<ItemWithPlace>> {
+        let mut months = Vec::new();
+        for month in &self.months {
+            months.push(load_month(&month.path)?);
+        }
+        convert_months(months)
+    }
+
+    /// The most recent month's file, which Google rewrites on every export
+    fn modified_path(&self) -> PathBuf {
+        self.months
+            .last()
+            .map(|month| month.path.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Finds the monthly files in the first candidate folder that has any, sorted by month
+fn find_month_files(path: &Path) -> Result<Option<Vec<MonthFile>>> {
+    for dir in SEMANTIC_DIRS {
+        let dir = path.join(dir);
+        if !dir.is_dir() {
+            continue;
+        }
+
+        let mut months = Vec::new();
+        for year_entry in fs::read_dir(&dir).context(format!("Failed to read {:?}", dir))? {
+            let year_path = year_entry?.path();
+            if !year_path.is_dir() {
+                continue;
+            }
+            for entry in
+                fs::read_dir(&year_path).context(format!("Failed to read {:?}", year_path))?
+            {
+                let path = entry?.path();
+                if let Some(year_month) = path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .and_then(parse_month_file_name)
+                {
+                    months.push(MonthFile { year_month, path });
+                }
+            }
+        }
+
+        if !months.is_empty() {
+            months.sort_by(|a, b| a.year_month.cmp(&b.year_month));
+            return Ok(Some(months));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Parses "2024_JANUARY.json" into "2024-01"
+fn parse_month_file_name(name: &str) -> Option<String> {
+    let (year, month) = name.strip_suffix(".json")?.split_once('_')?;
+    let year: i32 = year.parse().ok()?;
+    let month = MONTHS.iter().position(|m| m.eq_ignore_ascii_case(month))? + 1;
+    Some(format!("{:04}-{:02}", year, month))
+}
+
+/// Loads one monthly file
+pub fn load_month<P: AsRef<Path>>(path: P) -> Result<SemanticMonth> {
+    let path = path.as_ref();
+    let content =
+        fs::read_to_string(path).context(format!("Failed to read Takeout file: {:?}", path))?;
+    serde_json::from_str(&content).context(format!("Failed to parse Takeout file: {:?}", path))
+}
+
+/// Converts Semantic Location History months into Arc items and places
+///
+/// Visits to the same Google place share one `Place`, with visit counts and the last visit
+/// date filled in from all months.
+pub fn convert_months(months: Vec<SemanticMonth>) -> Result<Vec<ItemWithPlace>> {
+    let objects: Vec<_> = months
+        .into_iter()
+        .flat_map(|month| month.timeline_objects)
+        .collect();
+
+    // First pass: collect visit times per place so the shared places have their totals
+    let mut places: HashMap<String, PlaceTotals> = HashMap::new();
+    for visit in objects
+        .iter()
+        .filter_map(|object| object.place_visit.as_ref())
+    {
+        let start = visit.duration.start()?;
+        let end = visit.duration.end()?;
+        let totals = places
+            .entry(place_key(visit))
+            .or_insert_with(|| PlaceTotals::new(visit));
+        totals.visit_count += 1;
+        totals.visit_days.insert(start.date_naive());
+        totals.last_visit = totals.last_visit.max(Some(end));
+    }
+    let places: HashMap<String, Rc<Place>> = places
+        .into_iter()
+        .map(|(key, totals)| (key, Rc::new(totals.into_place())))
+        .collect();
+
+    let mut items = Vec::new();
+    for (index, object) in objects.iter().enumerate() {
+        let id = format!("takeout-{}", index);
+        if let Some(visit) = &object.place_visit {
+            let place = places.get(&place_key(visit)).map(Rc::clone);
+            items.push(ItemWithPlace {
+                item: convert_visit(id, visit)?,
+                place,
+            });
+        } else if let Some(segment) = &object.activity_segment {
+            items.push(ItemWithPlace {
+                item: convert_segment(id, segment)?,
+                place: None,
+            });
+        }
+    }
+
+    Ok(items)
+}
+
+/// Place ID of a visit, or its coordinates for unnamed places
+fn place_key(visit: &PlaceVisit) -> String {
+    visit.location.place_id.clone().unwrap_or_else(|| {
+        format!(
+            "{:.5},{:.5}",
+            visit.location.latitude(),
+            visit.location.longitude()
+        )
+    })
+}
+
+/// Visit totals for a place, gathered before the shared `Place` is created
+struct PlaceTotals {
+    id: String,
+    name: String,
+    latitude: f64,
+    longitude: f64,
+    address: Option<String>,
+    google_place_id: Option<String>,
+    semantic_type: Option<String>,
+    visit_count: u32,
+    visit_days: HashSet<chrono::NaiveDate>,
+    last_visit: Option<DateTime<Utc>>,
+}
+
+impl PlaceTotals {
+    fn new(visit: &PlaceVisit) -> Self {
+        let location = &visit.location;
+        Self {
+            id: place_key(visit),
+            // Home and work are often unnamed, but carry a semantic type
+            name: location
+                .name
+                .clone()
+                .or_else(|| match location.semantic_type.as_deref() {
+                    Some("TYPE_HOME") => Some("Home".to_string()),
+                    Some("TYPE_WORK") => Some("Work".to_string()),
+                    _ => None,
+                })
+                .or_else(|| location.address.clone())
+                .unwrap_or_else(|| "Unknown place".to_string()),
+            latitude: location.latitude(),
+            longitude: location.longitude(),
+            address: location.address.clone(),
+            google_place_id: location.place_id.clone(),
+            semantic_type: location.semantic_type.clone(),
+            visit_count: 0,
+            visit_days: HashSet::new(),
+            last_visit: None,
+        }
+    }
+
+    fn into_place(self) -> Place {
+        Place {
+            id: self.id,
+            name: self.name,
+            latitude: self.latitude,
+            longitude: self.longitude,
+            radius_mean: DEFAULT_RADIUS_METERS,
+            radius_sd: 0.0,
+            visit_count: self.visit_count,
+            visit_days: self.visit_days.len() as u32,
+            last_saved: 0.0,
+            is_stale: false,
+            source: SOURCE.into(),
+            rtree_id: 0,
+            seconds_from_gmt: None,
+            street_address: self.address,
+            locality: None,
+            country_code: None,
+            google_place_id: self.google_place_id,
+            google_primary_type: self.semantic_type,
+            last_visit_date: self.last_visit.map(datetime_to_apple_timestamp),
+        }
+    }
+}
+
+/// Base fields of a converted item
+fn base_item(id: &Rc<str>, start: DateTime<Utc>, end: DateTime<Utc>, is_visit: bool) -> BaseItem {
+    BaseItem {
+        id: Rc::clone(id),
+        start_date: datetime_to_apple_timestamp(start),
+        end_date: datetime_to_apple_timestamp(end),
+        last_saved: 0.0,
+        source: SOURCE.into(),
+        source_version: None,
+        is_visit,
+        deleted: false,
+        disabled: false,
+        samples_changed: None,
+        step_count: None,
+        active_energy_burned: None,
+        max_heart_rate: None,
+        average_heart_rate: None,
+        previous_item_id: None,
+        next_item_id: None,
+    }
+}
+
+fn convert_visit(id: String, visit: &PlaceVisit) -> Result<Item> {
+    let id: Rc<str> = id.into();
+    let confidence = visit.place_confidence.as_deref();
+    Ok(Item {
+        base: base_item(&id, visit.duration.start()?, visit.duration.end()?, true),
+        variant: ItemVariant::Visit(VisitDetails {
+            item_id: id,
+            place_id: Some(place_key(visit).into()),
+            latitude: visit.location.latitude(),
+            longitude: visit.location.longitude(),
+            radius_mean: DEFAULT_RADIUS_METERS,
+            radius_sd: 0.0,
+            confirmed_place: confidence == Some("USER_CONFIRMED"),
+            uncertain_place: confidence == Some("LOW_CONFIDENCE"),
+            last_saved: 0.0,
+            street_address: visit.location.address.clone(),
+        }),
+    })
+}
+
+fn convert_segment(id: String, segment: &ActivitySegment) -> Result<Item> {
+    let id: Rc<str> = id.into();
+    let start = segment.duration.start()?;
+    let end = segment.duration.end()?;
+    let distance = segment.distance.unwrap_or(0.0);
+    let seconds = (end - start).num_milliseconds() as f64 / 1000.0;
+
+    Ok(Item {
+        base: base_item(&id, start, end, false),
+        variant: ItemVariant::Trip(TripDetails {
+            item_id: id,
+            distance,
+            speed: if seconds > 0.0 {
+                distance / seconds
+            } else {
+                0.0
+            },
+            classified_activity_type: None,
+            confirmed_activity_type: None,
+            uncertain_activity_type: false,
+            last_saved: 0.0,
+        }),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MONTH: &str = r#"{"timelineObjects": [
+        {"placeVisit": {
+            "location": {"latitudeE7": 385000000, "longitudeE7": -904000000,
+                         "placeId": "ChIJchurch", "name": "Grace Church",
+                         "address": "1 Church St"},
+            "duration": {"startTimestamp": "2024-01-07T15:00:00Z",
+                         "endTimestamp": "2024-01-07T16:30:00Z"},
+            "placeConfidence": "USER_CONFIRMED"}},
+        {"activitySegment": {
+            "startLocation": {"latitudeE7": 385000000, "longitudeE7": -904000000},
+            "duration": {"startTimestampMs": "1704643200000",
+                         "endTimestampMs": "1704644100000"},
+            "distance": 9000, "activityType": "IN_PASSENGER_VEHICLE"}},
+        {"placeVisit": {
+            "location": {"latitudeE7": 386000000, "longitudeE7": -904500000,
+                         "placeId": "ChIJhome", "semanticType": "TYPE_HOME"},
+            "duration": {"startTimestamp": "2024-01-07T16:45:00Z",
+                         "endTimestamp": "2024-01-08T01:00:00Z"}}},
+        {"placeVisit": {
+            "location": {"latitudeE7": 385000000, "longitudeE7": -904000000,
+                         "placeId": "ChIJchurch", "name": "Grace Church"},
+            "duration": {"startTimestamp": "2024-01-14T15:00:00Z",
+                         "endTimestamp": "2024-01-14T16:00:00Z"}}}
+    ]}"#;
+
+    #[test]
+    fn test_convert_months() {
+        let month: SemanticMonth = serde_json::from_str(MONTH).unwrap();
+        let items = convert_months(vec![month]).unwrap();
+
+        assert_eq!(items.len(), 4);
+        assert!(items[0].item.is_visit());
+        assert!(items[1].item.is_trip());
+        assert_eq!(items[0].item.duration_seconds(), 90.0 * 60.0);
+        assert_eq!(
+            items[0].item.start_datetime().to_rfc3339(),
+            "2024-01-07T15:00:00+00:00"
+        );
+
+        let church = items[0].place.as_ref().unwrap();
+        assert_eq!(church.name, "Grace Church");
+        assert_eq!(church.visit_count, 2);
+        assert_eq!(church.visit_days, 2);
+        assert!(Rc::ptr_eq(church, items[3].place.as_ref().unwrap()));
+
+        assert_eq!(items[2].place.as_ref().unwrap().name, "Home");
+        assert!(items[1].place.is_none());
+    }
+
+    #[test]
+    fn test_open_takeout_folder() {
+        let takeout = tempfile::tempdir().unwrap();
+        let semantic = takeout
+            .path()
+            .join("Location History (Timeline)/Semantic Location History");
+        fs::create_dir_all(semantic.join("2023")).unwrap();
+        fs::create_dir_all(semantic.join("2024")).unwrap();
+        fs::write(semantic.join("2024/2024_JANUARY.json"), MONTH).unwrap();
+        fs::write(
+            semantic.join("2023/2023_DECEMBER.json"),
+            r#"{"timelineObjects": []}"#,
+        )
+        .unwrap();
+
+        assert!(TakeoutHistory::is_takeout_export(takeout.path()));
+        let history = TakeoutHistory::open(takeout.path()).unwrap();
+        let months: Vec<_> = history
+            .months()
+            .iter()
+            .map(|month| month.year_month.as_str())
+            .collect();
+        assert_eq!(months, vec!["2023-12", "2024-01"]);
+        assert_eq!(
+            history.modified_path(),
+            semantic.join("2024/2024_JANUARY.json")
+        );
+        assert_eq!(history.load_items_with_places().unwrap().len(), 4);
+
+        let empty = tempfile::tempdir().unwrap();
+        assert!(!TakeoutHistory::is_takeout_export(empty.path()));
+    }
+}
@@ -0,0 +1,52 @@
+use arcstats::PlaceProvider;
+use arcstats::stats::get_top_places_last_6_months_from;
+use clap::Parser;
+use std::process;
+use takeoutstats::{TakeoutHistory, load_month};
+
+#[derive(Parser, Debug)]
+#[command(name = "takeoutstats")]
+#[command(about = "Load and parse Google Takeout Semantic Location History", long_about = None)]
+struct Args {
+    /// Path to the Takeout folder (or its Semantic Location History folder)
+    takeout_path: String,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let history = TakeoutHistory::open(&args.takeout_path).unwrap_or_else(|e| {
+        eprintln!("Error: {:#}", e);
+        process::exit(1);
+    });
+
+    println!("=== Loading Months ===");
+    let mut total_objects = 0;
+    for month in history.months() {
+        match load_month(&month.path) {
+            Ok(data) => {
+                println!(
+                    "✓ Loaded {}: {} timeline objects",
+                    month.year_month,
+                    data.timeline_objects.len()
+                );
+                total_objects += data.timeline_objects.len();
+            }
+            Err(e) => println!("✗ Failed to load {}: {:#}", month.year_month, e),
+        }
+    }
+    println!("\nTotal timeline objects loaded: {}", total_objects);
+
+    println!("\n=== Top Places (last 6 months) ===");
+    match get_top_places_last_6_months_from(&history, 10) {
+        Ok(places) => {
+            for place in places {
+                println!("{:>8.1} h  {}", place.hours, place.place_name);
+            }
+        }
+        Err(e) => {
+            eprintln!("Error loading {}: {:#}", history.name(), e);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+/// One month of Semantic Location History (e.g. `2024/2024_JANUARY.json`)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SemanticMonth {
+    #[serde(default)]
+    pub timeline_objects: Vec<TimelineObject>,
+}
+
+/// Either a visit to a place or a movement between places
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineObject {
+    pub place_visit: Option<PlaceVisit>,
+    pub activity_segment: Option<ActivitySegment>,
+}
+
+/// A stay at a place
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaceVisit {
+    pub location: Location,
+    pub duration: TimeSpan,
+    /// "USER_CONFIRMED", "HIGH_CONFIDENCE", "MEDIUM_CONFIDENCE" or "LOW_CONFIDENCE"
+    pub place_confidence: Option<String>,
+}
+
+/// A movement between two places
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivitySegment {
+    pub start_location: Option<Location>,
+    pub duration: TimeSpan,
+    /// Distance in meters
+    pub distance: Option<f64>,
+    /// e.g. "WALKING" or "IN_PASSENGER_VEHICLE"
+    pub activity_type: Option<String>,
+}
+
+/// A location; the place fields are only present for visits
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Location {
+    #[serde(rename = "latitudeE7")]
+    pub latitude_e7: Option<i64>,
+    #[serde(rename = "longitudeE7")]
+    pub longitude_e7: Option<i64>,
+    pub place_id: Option<String>,
+    pub name: Option<String>,
+    pub address: Option<String>,
+    /// e.g. "TYPE_HOME" or "TYPE_WORK"
+    pub semantic_type: Option<String>,
+}
+
+/// Start and end of a timeline object
+///
+/// Exports since 2022 use RFC 3339 timestamps; older ones use millisecond strings.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeSpan {
+    pub start_timestamp: Option<String>,
+    pub end_timestamp: Option<String>,
+    pub start_timestamp_ms: Option<String>,
+    pub end_timestamp_ms: Option<String>,
+}
+
+impl Location {
+    /// Latitude in degrees
+    pub fn latitude(&self) -> f64 {
+        self.latitude_e7.unwrap_or(0) as f64 / 1e7
+    }
+
+    /// Longitude in degrees
+    pub fn longitude(&self) -> f64 {
+        self.longitude_e7.unwrap_or(0) as f64 / 1e7
+    }
+}
+
+impl TimeSpan {
+    /// Start of the span
+    pub fn start(&self) -> Result<DateTime<Utc>> {
+        parse_timestamp(&self.start_timestamp, &self.start_timestamp_ms)
+    }
+
+    /// End of the span
+    pub fn end(&self) -> Result<DateTime<Utc>> {
+        parse_timestamp(&self.end_timestamp, &self.end_timestamp_ms)
+    }
+}
+
+/// Parses whichever of the RFC 3339 and millisecond timestamps is present
+fn parse_timestamp(rfc3339: &Option<String>, ms: &Option<String>) -> Result<DateTime<Utc>> {
+    if let Some(timestamp) = rfc3339 {
+        return DateTime::parse_from_rfc3339(timestamp)
+            .map(|datetime| datetime.with_timezone(&Utc))
+            .context(format!("Could not parse timestamp '{}'", timestamp));
+    }
+
+    let ms = ms.as_deref().context("Timeline object has no timestamp")?;
+    ms.parse::<i64>()
+        .ok()
+        .and_then(DateTime::from_timestamp_millis)
+        .context(format!("Could not parse timestamp '{}'", ms))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_span_formats() {
+        let span: TimeSpan = serde_json::from_str(
+            r#"{"startTimestamp": "2024-01-07T15:00:00.000Z",
+                "endTimestampMs": "1704643200000"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            span.start().unwrap().to_rfc3339(),
+            "2024-01-07T15:00:00+00:00"
+        );
+        assert_eq!(
+            span.end().unwrap().to_rfc3339(),
+            "2024-01-07T16:00:00+00:00"
+        );
+    }
+}